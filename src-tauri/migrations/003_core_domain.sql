@@ -0,0 +1,67 @@
+-- POTracker Database Schema
+-- Migration 003: Core domain tables managed by the Rust backend
+--
+-- Mirrors the tables the frontend creates (including columns it adds later)
+-- so either side can create a fresh database, and adds the customer directory.
+
+CREATE TABLE IF NOT EXISTS events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    description TEXT,
+    start_date DATE,
+    end_date DATE,
+    is_active INTEGER DEFAULT 1,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS products (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    description TEXT,
+    price REAL NOT NULL,
+    event_id INTEGER REFERENCES events(id),
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    image_url TEXT,
+    is_active INTEGER DEFAULT 1,
+    currency_code TEXT DEFAULT 'USD',
+    unique_id TEXT
+);
+
+CREATE TABLE IF NOT EXISTS preorders (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    customer_name TEXT NOT NULL,
+    customer_email TEXT NOT NULL,
+    confirmation_code TEXT UNIQUE NOT NULL,
+    status TEXT DEFAULT 'pending',
+    total_amount REAL NOT NULL,
+    notes TEXT,
+    event_id INTEGER REFERENCES events(id),
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    confirmed_at DATETIME
+);
+
+CREATE TABLE IF NOT EXISTS order_items (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    preorder_id INTEGER NOT NULL,
+    product_id INTEGER NOT NULL,
+    quantity INTEGER NOT NULL,
+    unit_price REAL NOT NULL,
+    FOREIGN KEY (preorder_id) REFERENCES preorders(id) ON DELETE CASCADE,
+    FOREIGN KEY (product_id) REFERENCES products(id)
+);
+
+-- Customers are keyed by email; orders link to them through customer_email
+CREATE TABLE IF NOT EXISTS customers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    email TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    phone TEXT,
+    notes TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_preorders_status ON preorders(status);
+CREATE INDEX IF NOT EXISTS idx_preorders_code ON preorders(confirmation_code);
+CREATE INDEX IF NOT EXISTS idx_preorders_email ON preorders(customer_email COLLATE NOCASE);
+CREATE INDEX IF NOT EXISTS idx_order_items_preorder ON order_items(preorder_id);
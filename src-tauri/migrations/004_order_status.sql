@@ -0,0 +1,19 @@
+-- POTracker Database Schema
+-- Migration 004: Order lifecycle timestamps and status history
+
+ALTER TABLE preorders ADD COLUMN invoiced_at DATETIME;
+ALTER TABLE preorders ADD COLUMN paid_at DATETIME;
+ALTER TABLE preorders ADD COLUMN fulfilled_at DATETIME;
+ALTER TABLE preorders ADD COLUMN cancelled_at DATETIME;
+
+CREATE TABLE IF NOT EXISTS order_status_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    preorder_id INTEGER NOT NULL,
+    from_status TEXT NOT NULL,
+    to_status TEXT NOT NULL,
+    event TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (preorder_id) REFERENCES preorders(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_order_status_history_order ON order_status_history(preorder_id);
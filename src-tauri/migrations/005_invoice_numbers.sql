@@ -0,0 +1,20 @@
+-- POTracker Database Schema
+-- Migration 005: Sequential invoice numbers
+
+ALTER TABLE preorders ADD COLUMN invoice_number TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_preorders_invoice_number ON preorders(invoice_number);
+
+-- Single-row numbering settings
+CREATE TABLE IF NOT EXISTS invoice_settings (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    number_pattern TEXT NOT NULL DEFAULT 'INV-{YYYY}{MM}-{seq:4}'
+);
+
+INSERT OR IGNORE INTO invoice_settings (id) VALUES (1);
+
+-- Last issued sequence value per year
+CREATE TABLE IF NOT EXISTS invoice_sequences (
+    year INTEGER PRIMARY KEY,
+    last_value INTEGER NOT NULL
+);
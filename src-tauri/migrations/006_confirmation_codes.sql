@@ -0,0 +1,10 @@
+-- POTracker Database Schema
+-- Migration 006: Confirmation code settings
+
+-- Single-row settings; use_checksum appends a check character to new codes
+CREATE TABLE IF NOT EXISTS confirmation_code_settings (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    use_checksum INTEGER NOT NULL DEFAULT 0
+);
+
+INSERT OR IGNORE INTO confirmation_code_settings (id) VALUES (1);
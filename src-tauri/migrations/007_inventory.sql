@@ -0,0 +1,21 @@
+-- POTracker Database Schema
+-- Migration 007: Stock tracking
+
+-- NULL stock_quantity means the product's stock isn't tracked
+ALTER TABLE products ADD COLUMN stock_quantity INTEGER;
+ALTER TABLE products ADD COLUMN low_stock_threshold INTEGER;
+
+CREATE TABLE IF NOT EXISTS stock_movements (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    product_id INTEGER NOT NULL,
+    change INTEGER NOT NULL,
+    quantity_after INTEGER NOT NULL,
+    reason TEXT NOT NULL,
+    preorder_id INTEGER,
+    note TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE,
+    FOREIGN KEY (preorder_id) REFERENCES preorders(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_stock_movements_product ON stock_movements(product_id);
@@ -0,0 +1,19 @@
+-- POTracker Database Schema
+-- Migration 008: Customer directory
+
+ALTER TABLE customers ADD COLUMN address TEXT;
+
+-- Backfill customers from existing orders, keeping the most recent name per email
+INSERT OR IGNORE INTO customers (name, email)
+SELECT customer_name, TRIM(customer_email) FROM preorders
+WHERE TRIM(customer_email) <> ''
+ORDER BY id DESC;
+
+-- Orders created outside the backend (frontend, form sync) still land in the directory
+CREATE TRIGGER IF NOT EXISTS trg_preorders_add_customer
+AFTER INSERT ON preorders
+WHEN TRIM(NEW.customer_email) <> ''
+BEGIN
+    INSERT OR IGNORE INTO customers (name, email)
+    VALUES (NEW.customer_name, TRIM(NEW.customer_email));
+END;
@@ -0,0 +1,19 @@
+-- POTracker Database Schema
+-- Migration 009: Payments
+
+CREATE TABLE IF NOT EXISTS payments (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    preorder_id INTEGER NOT NULL,
+    amount REAL NOT NULL CHECK (amount > 0),
+    method TEXT NOT NULL,
+    paid_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    reference TEXT,
+    -- Drive file holding the proof of payment (transfer receipt, screenshot)
+    proof_file_id TEXT,
+    proof_file_name TEXT,
+    notes TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (preorder_id) REFERENCES preorders(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_payments_order ON payments(preorder_id);
@@ -0,0 +1,96 @@
+-- POTracker Database Schema
+-- Migration 010: Full-text search index over orders, customers and products
+
+CREATE VIRTUAL TABLE IF NOT EXISTS search_index USING fts5(
+    entity_type UNINDEXED,
+    entity_id UNINDEXED,
+    title,
+    keywords,
+    body,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+-- Orders: customer name, then code/invoice/email, then notes
+CREATE TRIGGER IF NOT EXISTS trg_search_preorders_insert AFTER INSERT ON preorders
+BEGIN
+    INSERT INTO search_index (entity_type, entity_id, title, keywords, body)
+    VALUES ('order', NEW.id, NEW.customer_name,
+            NEW.confirmation_code || ' ' || COALESCE(NEW.invoice_number, '') || ' ' || NEW.customer_email,
+            COALESCE(NEW.notes, ''));
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_search_preorders_update AFTER UPDATE ON preorders
+BEGIN
+    DELETE FROM search_index WHERE entity_type = 'order' AND entity_id = OLD.id;
+    INSERT INTO search_index (entity_type, entity_id, title, keywords, body)
+    VALUES ('order', NEW.id, NEW.customer_name,
+            NEW.confirmation_code || ' ' || COALESCE(NEW.invoice_number, '') || ' ' || NEW.customer_email,
+            COALESCE(NEW.notes, ''));
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_search_preorders_delete AFTER DELETE ON preorders
+BEGIN
+    DELETE FROM search_index WHERE entity_type = 'order' AND entity_id = OLD.id;
+END;
+
+-- Customers: name, then email/phone, then address and notes
+CREATE TRIGGER IF NOT EXISTS trg_search_customers_insert AFTER INSERT ON customers
+BEGIN
+    INSERT INTO search_index (entity_type, entity_id, title, keywords, body)
+    VALUES ('customer', NEW.id, NEW.name,
+            NEW.email || ' ' || COALESCE(NEW.phone, ''),
+            COALESCE(NEW.address, '') || ' ' || COALESCE(NEW.notes, ''));
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_search_customers_update AFTER UPDATE ON customers
+BEGIN
+    DELETE FROM search_index WHERE entity_type = 'customer' AND entity_id = OLD.id;
+    INSERT INTO search_index (entity_type, entity_id, title, keywords, body)
+    VALUES ('customer', NEW.id, NEW.name,
+            NEW.email || ' ' || COALESCE(NEW.phone, ''),
+            COALESCE(NEW.address, '') || ' ' || COALESCE(NEW.notes, ''));
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_search_customers_delete AFTER DELETE ON customers
+BEGIN
+    DELETE FROM search_index WHERE entity_type = 'customer' AND entity_id = OLD.id;
+END;
+
+-- Products: name, then SKU, then description. Inactive products drop out.
+CREATE TRIGGER IF NOT EXISTS trg_search_products_insert AFTER INSERT ON products
+WHEN COALESCE(NEW.is_active, 1) = 1
+BEGIN
+    INSERT INTO search_index (entity_type, entity_id, title, keywords, body)
+    VALUES ('product', NEW.id, NEW.name, COALESCE(NEW.unique_id, ''), COALESCE(NEW.description, ''));
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_search_products_update AFTER UPDATE ON products
+BEGIN
+    DELETE FROM search_index WHERE entity_type = 'product' AND entity_id = OLD.id;
+    INSERT INTO search_index (entity_type, entity_id, title, keywords, body)
+    SELECT 'product', NEW.id, NEW.name, COALESCE(NEW.unique_id, ''), COALESCE(NEW.description, '')
+    WHERE COALESCE(NEW.is_active, 1) = 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_search_products_delete AFTER DELETE ON products
+BEGIN
+    DELETE FROM search_index WHERE entity_type = 'product' AND entity_id = OLD.id;
+END;
+
+-- Index existing rows
+DELETE FROM search_index;
+
+INSERT INTO search_index (entity_type, entity_id, title, keywords, body)
+SELECT 'order', id, customer_name,
+       confirmation_code || ' ' || COALESCE(invoice_number, '') || ' ' || customer_email,
+       COALESCE(notes, '')
+FROM preorders;
+
+INSERT INTO search_index (entity_type, entity_id, title, keywords, body)
+SELECT 'customer', id, name, email || ' ' || COALESCE(phone, ''),
+       COALESCE(address, '') || ' ' || COALESCE(notes, '')
+FROM customers;
+
+INSERT INTO search_index (entity_type, entity_id, title, keywords, body)
+SELECT 'product', id, name, COALESCE(unique_id, ''), COALESCE(description, '')
+FROM products WHERE COALESCE(is_active, 1) = 1;
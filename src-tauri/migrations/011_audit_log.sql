@@ -0,0 +1,54 @@
+-- POTracker Database Schema
+-- Migration 011: Audit log and soft delete for orders and customers
+
+-- Created by the frontend on sign-in; declared here too so the audit log can
+-- always look up the signed-in account
+CREATE TABLE IF NOT EXISTS google_auth (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    access_token TEXT NOT NULL,
+    refresh_token TEXT,
+    token_expiry TEXT,
+    user_email TEXT,
+    user_name TEXT,
+    auth_mode TEXT DEFAULT 'oauth',
+    api_key TEXT
+);
+
+CREATE TABLE IF NOT EXISTS audit_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    entity_type TEXT NOT NULL,
+    entity_id INTEGER NOT NULL,
+    action TEXT NOT NULL,
+    actor TEXT NOT NULL,
+    before_json TEXT,
+    after_json TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_entity ON audit_log(entity_type, entity_id);
+
+ALTER TABLE preorders ADD COLUMN deleted_at DATETIME;
+ALTER TABLE customers ADD COLUMN deleted_at DATETIME;
+
+-- Soft-deleted rows drop out of search
+DROP TRIGGER IF EXISTS trg_search_preorders_update;
+CREATE TRIGGER trg_search_preorders_update AFTER UPDATE ON preorders
+BEGIN
+    DELETE FROM search_index WHERE entity_type = 'order' AND entity_id = OLD.id;
+    INSERT INTO search_index (entity_type, entity_id, title, keywords, body)
+    SELECT 'order', NEW.id, NEW.customer_name,
+           NEW.confirmation_code || ' ' || COALESCE(NEW.invoice_number, '') || ' ' || NEW.customer_email,
+           COALESCE(NEW.notes, '')
+    WHERE NEW.deleted_at IS NULL;
+END;
+
+DROP TRIGGER IF EXISTS trg_search_customers_update;
+CREATE TRIGGER trg_search_customers_update AFTER UPDATE ON customers
+BEGIN
+    DELETE FROM search_index WHERE entity_type = 'customer' AND entity_id = OLD.id;
+    INSERT INTO search_index (entity_type, entity_id, title, keywords, body)
+    SELECT 'customer', NEW.id, NEW.name,
+           NEW.email || ' ' || COALESCE(NEW.phone, ''),
+           COALESCE(NEW.address, '') || ' ' || COALESCE(NEW.notes, '')
+    WHERE NEW.deleted_at IS NULL;
+END;
@@ -0,0 +1,34 @@
+-- POTracker Database Schema
+-- Migration 012: Effective-dated product prices
+
+-- Timestamps are stored in SQLite's UTC "YYYY-MM-DD HH:MM:SS" form so they compare as text
+CREATE TABLE IF NOT EXISTS product_price_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    product_id INTEGER NOT NULL,
+    price REAL NOT NULL,
+    currency_code TEXT,
+    effective_from DATETIME NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_price_history_product ON product_price_history(product_id, effective_from);
+
+-- Existing prices have applied since the product was created
+INSERT INTO product_price_history (product_id, price, currency_code, effective_from)
+SELECT id, price, currency_code, COALESCE(created_at, CURRENT_TIMESTAMP) FROM products
+WHERE NOT EXISTS (SELECT 1 FROM product_price_history h WHERE h.product_id = products.id);
+
+-- Price edits from either the backend or the frontend take effect immediately
+CREATE TRIGGER IF NOT EXISTS trg_products_price_insert AFTER INSERT ON products
+BEGIN
+    INSERT INTO product_price_history (product_id, price, currency_code, effective_from)
+    VALUES (NEW.id, NEW.price, NEW.currency_code, COALESCE(NEW.created_at, CURRENT_TIMESTAMP));
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_products_price_update AFTER UPDATE OF price, currency_code ON products
+WHEN NEW.price IS NOT OLD.price OR NEW.currency_code IS NOT OLD.currency_code
+BEGIN
+    INSERT INTO product_price_history (product_id, price, currency_code, effective_from)
+    VALUES (NEW.id, NEW.price, NEW.currency_code, CURRENT_TIMESTAMP);
+END;
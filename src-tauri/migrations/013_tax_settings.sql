@@ -0,0 +1,16 @@
+-- POTracker Database Schema
+-- Migration 013: Tax and rounding settings for the totals engine
+
+CREATE TABLE IF NOT EXISTS tax_settings (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    tax_name TEXT NOT NULL DEFAULT 'Tax',
+    -- Percentage, e.g. 11 for 11%
+    tax_rate REAL NOT NULL DEFAULT 0,
+    -- 'exclusive' adds tax on top of prices; 'inclusive' means prices already contain it
+    tax_mode TEXT NOT NULL DEFAULT 'exclusive',
+    rounding_mode TEXT NOT NULL DEFAULT 'half_up',
+    decimal_places INTEGER NOT NULL DEFAULT 2,
+    round_per_line INTEGER NOT NULL DEFAULT 0
+);
+
+INSERT OR IGNORE INTO tax_settings (id) VALUES (1);
@@ -0,0 +1,25 @@
+-- POTracker Database Schema
+-- Migration 014: Order currencies and cached exchange rates
+
+-- Per-currency product prices; also created by the frontend
+CREATE TABLE IF NOT EXISTS product_prices (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    product_id INTEGER NOT NULL,
+    currency_code TEXT NOT NULL,
+    price REAL NOT NULL,
+    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE
+);
+
+-- NULL means the app's default currency (orders created before this migration)
+ALTER TABLE preorders ADD COLUMN currency_code TEXT;
+
+-- Rates as published by the source (ECB rates are quoted against EUR)
+CREATE TABLE IF NOT EXISTS exchange_rates (
+    base_currency TEXT NOT NULL,
+    quote_currency TEXT NOT NULL,
+    rate REAL NOT NULL,
+    rate_date TEXT NOT NULL,
+    source TEXT NOT NULL,
+    fetched_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (base_currency, quote_currency)
+);
@@ -0,0 +1,37 @@
+-- POTracker Database Schema
+-- Migration 015: Recurring (standing) order templates
+
+CREATE TABLE IF NOT EXISTS recurring_orders (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    customer_name TEXT NOT NULL,
+    customer_email TEXT NOT NULL,
+    currency_code TEXT,
+    notes TEXT,
+    event_id INTEGER REFERENCES events(id),
+    frequency TEXT NOT NULL CHECK (frequency IN ('weekly', 'monthly')),
+    -- Every N weeks/months
+    interval_count INTEGER NOT NULL DEFAULT 1 CHECK (interval_count > 0),
+    next_run_at DATETIME NOT NULL,
+    ends_at DATETIME,
+    send_confirmation INTEGER NOT NULL DEFAULT 0,
+    status TEXT NOT NULL DEFAULT 'active' CHECK (status IN ('active', 'paused', 'ended')),
+    last_run_at DATETIME,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_recurring_orders_due ON recurring_orders(status, next_run_at);
+
+CREATE TABLE IF NOT EXISTS recurring_order_items (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    recurring_order_id INTEGER NOT NULL,
+    product_id INTEGER NOT NULL,
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    -- NULL takes the product's price when each order is generated
+    unit_price REAL,
+    FOREIGN KEY (recurring_order_id) REFERENCES recurring_orders(id) ON DELETE CASCADE,
+    FOREIGN KEY (product_id) REFERENCES products(id)
+);
+
+-- Template an order was generated from
+ALTER TABLE preorders ADD COLUMN recurring_order_id INTEGER REFERENCES recurring_orders(id);
@@ -0,0 +1,46 @@
+-- POTracker Database Schema
+-- Migration 016: Order notes, email log and form response links for the order timeline
+
+CREATE TABLE IF NOT EXISTS order_notes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    preorder_id INTEGER NOT NULL,
+    body TEXT NOT NULL,
+    author TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (preorder_id) REFERENCES preorders(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_order_notes_preorder ON order_notes(preorder_id);
+
+-- Emails sent about an order, whichever channel sent them
+CREATE TABLE IF NOT EXISTS order_emails (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    preorder_id INTEGER NOT NULL,
+    to_email TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    channel TEXT NOT NULL,
+    sent_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (preorder_id) REFERENCES preorders(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_order_emails_preorder ON order_emails(preorder_id);
+
+-- Created by the frontend on first use of Google Forms sync
+CREATE TABLE IF NOT EXISTS google_forms (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    form_id TEXT UNIQUE NOT NULL,
+    form_url TEXT NOT NULL,
+    responder_url TEXT NOT NULL,
+    title TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    last_synced_at DATETIME
+);
+
+CREATE TABLE IF NOT EXISTS synced_responses (
+    response_id TEXT PRIMARY KEY,
+    form_id TEXT NOT NULL,
+    synced_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Order a form response was imported as
+ALTER TABLE synced_responses ADD COLUMN preorder_id INTEGER REFERENCES preorders(id);
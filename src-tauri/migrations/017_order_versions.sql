@@ -0,0 +1,13 @@
+-- POTracker Database Schema
+-- Migration 017: Row versions on orders for optimistic concurrency
+
+ALTER TABLE preorders ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+-- Writers that don't manage the version themselves (the frontend, status
+-- transitions) still invalidate copies other windows are editing
+CREATE TRIGGER IF NOT EXISTS trg_preorders_bump_version
+AFTER UPDATE ON preorders
+WHEN NEW.version = OLD.version
+BEGIN
+    UPDATE preorders SET version = OLD.version + 1 WHERE id = NEW.id;
+END;
@@ -0,0 +1,61 @@
+-- POTracker Database Schema
+-- Migration 018: Order archive
+
+-- Archived orders keep their original ID. The summary columns feed reports;
+-- snapshot_json holds every row needed to restore the order.
+CREATE TABLE IF NOT EXISTS archived_orders (
+    id INTEGER PRIMARY KEY,
+    customer_name TEXT NOT NULL,
+    customer_email TEXT NOT NULL,
+    confirmation_code TEXT,
+    invoice_number TEXT,
+    status TEXT NOT NULL,
+    total_amount REAL NOT NULL DEFAULT 0,
+    amount_paid REAL NOT NULL DEFAULT 0,
+    currency_code TEXT,
+    event_id INTEGER,
+    created_at DATETIME,
+    deleted_at DATETIME,
+    snapshot_json TEXT NOT NULL,
+    archived_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_archived_orders_created ON archived_orders(created_at);
+CREATE INDEX IF NOT EXISTS idx_archived_orders_email ON archived_orders(customer_email);
+
+-- Line items of archived orders, for per-product reports
+CREATE TABLE IF NOT EXISTS archived_order_items (
+    archived_order_id INTEGER NOT NULL,
+    product_id INTEGER NOT NULL,
+    quantity INTEGER NOT NULL,
+    unit_price REAL NOT NULL,
+    FOREIGN KEY (archived_order_id) REFERENCES archived_orders(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_archived_order_items_order ON archived_order_items(archived_order_id);
+
+-- NULL disables automatic archiving
+CREATE TABLE IF NOT EXISTS archive_settings (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    archive_after_days INTEGER CHECK (archive_after_days IS NULL OR archive_after_days > 0)
+);
+
+INSERT OR IGNORE INTO archive_settings (id, archive_after_days) VALUES (1, NULL);
+
+-- Live and archived orders together, for reports that span both
+CREATE VIEW IF NOT EXISTS order_history AS
+SELECT id, customer_name, customer_email, COALESCE(status, 'pending') AS status, total_amount,
+       currency_code, event_id, created_at, 0 AS archived
+FROM preorders WHERE deleted_at IS NULL
+UNION ALL
+SELECT id, customer_name, customer_email, status, total_amount,
+       currency_code, event_id, created_at, 1 AS archived
+FROM archived_orders WHERE deleted_at IS NULL;
+
+CREATE VIEW IF NOT EXISTS order_item_history AS
+SELECT preorder_id, product_id, quantity, unit_price, 0 AS archived
+FROM order_items WHERE preorder_id IN (SELECT id FROM preorders WHERE deleted_at IS NULL)
+UNION ALL
+SELECT i.archived_order_id, i.product_id, i.quantity, i.unit_price, 1 AS archived
+FROM archived_order_items i JOIN archived_orders a ON a.id = i.archived_order_id
+WHERE a.deleted_at IS NULL;
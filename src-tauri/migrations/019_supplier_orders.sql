@@ -0,0 +1,53 @@
+-- POTracker Database Schema
+-- Migration 019: Supplier purchase orders
+
+CREATE TABLE IF NOT EXISTS suppliers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    email TEXT,
+    phone TEXT,
+    notes TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Supplier a product is normally bought from
+ALTER TABLE products ADD COLUMN supplier_id INTEGER REFERENCES suppliers(id) ON DELETE SET NULL;
+
+CREATE TABLE IF NOT EXISTS supplier_orders (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    supplier_id INTEGER REFERENCES suppliers(id),
+    status TEXT NOT NULL DEFAULT 'draft' CHECK (status IN ('draft', 'ordered', 'received', 'cancelled')),
+    currency_code TEXT,
+    notes TEXT,
+    ordered_at DATETIME,
+    received_at DATETIME,
+    cancelled_at DATETIME,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_supplier_orders_status ON supplier_orders(status);
+
+CREATE TABLE IF NOT EXISTS supplier_order_items (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    supplier_order_id INTEGER NOT NULL,
+    product_id INTEGER NOT NULL,
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    -- NULL until the supplier's price is known
+    unit_cost REAL,
+    FOREIGN KEY (supplier_order_id) REFERENCES supplier_orders(id) ON DELETE CASCADE,
+    FOREIGN KEY (product_id) REFERENCES products(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_supplier_order_items_order ON supplier_order_items(supplier_order_id);
+
+-- Customer order quantities a supplier order item was raised to cover
+CREATE TABLE IF NOT EXISTS supplier_order_demand (
+    supplier_order_item_id INTEGER NOT NULL,
+    preorder_id INTEGER NOT NULL,
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    PRIMARY KEY (supplier_order_item_id, preorder_id),
+    FOREIGN KEY (supplier_order_item_id) REFERENCES supplier_order_items(id) ON DELETE CASCADE,
+    FOREIGN KEY (preorder_id) REFERENCES preorders(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_supplier_order_demand_preorder ON supplier_order_demand(preorder_id);
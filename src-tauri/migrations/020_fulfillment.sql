@@ -0,0 +1,19 @@
+-- POTracker Database Schema
+-- Migration 020: Fulfillment tracking
+
+-- One row per order once packing starts
+CREATE TABLE IF NOT EXISTS fulfillments (
+    preorder_id INTEGER PRIMARY KEY,
+    method TEXT NOT NULL DEFAULT 'shipping' CHECK (method IN ('shipping', 'pickup')),
+    courier TEXT,
+    tracking_number TEXT,
+    notes TEXT,
+    packed_at DATETIME,
+    -- Handed to the courier, or collected for pickups
+    shipped_at DATETIME,
+    delivered_at DATETIME,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (preorder_id) REFERENCES preorders(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_fulfillments_tracking ON fulfillments(tracking_number);
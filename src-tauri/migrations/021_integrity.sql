@@ -0,0 +1,22 @@
+-- POTracker Database Schema
+-- Migration 021: Referential integrity rules
+-- The backend checks these first and returns typed errors; the triggers catch
+-- writes that bypass the backend commands.
+
+CREATE TRIGGER IF NOT EXISTS trg_products_block_deactivate_in_use
+BEFORE UPDATE OF is_active ON products
+WHEN NEW.is_active = 0 AND COALESCE(OLD.is_active, 1) = 1 AND EXISTS (
+    SELECT 1 FROM order_items oi JOIN preorders po ON po.id = oi.preorder_id
+    WHERE oi.product_id = NEW.id AND po.deleted_at IS NULL
+    AND COALESCE(po.status, 'pending') NOT IN ('fulfilled', 'cancelled')
+)
+BEGIN
+    SELECT RAISE(ABORT, 'Product is still on open orders');
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_events_block_delete_with_orders
+BEFORE DELETE ON events
+WHEN EXISTS (SELECT 1 FROM preorders WHERE event_id = OLD.id)
+BEGIN
+    SELECT RAISE(ABORT, 'Event still has orders; archive them first');
+END;
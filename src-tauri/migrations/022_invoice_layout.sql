@@ -0,0 +1,20 @@
+-- POTracker Database Schema
+-- Migration 022: Invoice layout template
+
+-- Single-row layout for invoices rendered by the backend (PDF and HTML).
+-- Text fields may contain {{placeholders}}; a NULL template_html means the
+-- built-in HTML layout.
+CREATE TABLE IF NOT EXISTS invoice_layout (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    title TEXT NOT NULL DEFAULT 'Invoice',
+    business_name TEXT,
+    business_details TEXT,
+    -- Image as a data URL (data:image/png;base64,...)
+    logo TEXT,
+    accent_color TEXT NOT NULL DEFAULT '#6366f1',
+    footer_terms TEXT,
+    template_html TEXT,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT OR IGNORE INTO invoice_layout (id) VALUES (1);
@@ -0,0 +1,11 @@
+-- POTracker Database Schema
+-- Migration 023: Language of backend-generated documents and emails
+
+CREATE TABLE IF NOT EXISTS locale_settings (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    -- 'en' or 'id'
+    locale TEXT NOT NULL DEFAULT 'en',
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT OR IGNORE INTO locale_settings (id) VALUES (1);
@@ -0,0 +1,7 @@
+-- POTracker Database Schema
+-- Migration 024: Product barcodes (EAN/UPC or Code128)
+
+-- Stored normalized: digits only for EAN/UPC (UPC-A widened to EAN-13)
+ALTER TABLE products ADD COLUMN barcode TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_products_barcode ON products(barcode) WHERE barcode IS NOT NULL;
@@ -0,0 +1,15 @@
+-- POTracker Database Schema
+-- Migration 025: Cached online product data for scanned barcodes
+
+-- Misses are cached too (found = 0), so unknown codes aren't looked up on every scan
+CREATE TABLE IF NOT EXISTS barcode_lookups (
+    barcode TEXT PRIMARY KEY,
+    found INTEGER NOT NULL DEFAULT 0,
+    -- 'openfoodfacts' or 'upcitemdb'
+    source TEXT,
+    name TEXT,
+    brand TEXT,
+    description TEXT,
+    image_url TEXT,
+    fetched_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
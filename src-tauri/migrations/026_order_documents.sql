@@ -0,0 +1,22 @@
+-- POTracker Database Schema
+-- Migration 026: Photos and scans attached to orders
+
+-- The files live under the profile's data dir; file_name is relative to its documents folder
+CREATE TABLE IF NOT EXISTS order_documents (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    preorder_id INTEGER NOT NULL,
+    -- 'payment_proof', 'delivery_note' or 'other'
+    kind TEXT NOT NULL,
+    file_name TEXT NOT NULL,
+    mime_type TEXT NOT NULL,
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    size_bytes INTEGER NOT NULL,
+    -- Of the stored file, so the same capture isn't attached twice
+    sha256 TEXT NOT NULL,
+    notes TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (preorder_id) REFERENCES preorders(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_order_documents_order ON order_documents(preorder_id);
@@ -0,0 +1,27 @@
+-- POTracker Database Schema
+-- Migration 027: Online payment links (Stripe) and their reconciliation
+
+CREATE TABLE IF NOT EXISTS payment_links (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    preorder_id INTEGER NOT NULL,
+    -- 'stripe'
+    provider TEXT NOT NULL,
+    -- The provider's ID for the link, e.g. Stripe's plink_...
+    external_id TEXT NOT NULL,
+    url TEXT NOT NULL,
+    amount REAL NOT NULL,
+    currency_code TEXT NOT NULL,
+    -- 'open', 'paid' or 'cancelled'
+    status TEXT NOT NULL DEFAULT 'open',
+    -- The provider's ID for the successful payment, and the payment it was recorded as
+    payment_reference TEXT,
+    payment_id INTEGER,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    paid_at DATETIME,
+    checked_at DATETIME,
+    FOREIGN KEY (preorder_id) REFERENCES preorders(id) ON DELETE CASCADE,
+    FOREIGN KEY (payment_id) REFERENCES payments(id) ON DELETE SET NULL
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_payment_links_external ON payment_links(provider, external_id);
+CREATE INDEX IF NOT EXISTS idx_payment_links_order ON payment_links(preorder_id);
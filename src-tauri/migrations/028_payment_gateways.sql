@@ -0,0 +1,42 @@
+-- POTracker Database Schema
+-- Migration 028: Payment gateway charges (bank virtual accounts, QRIS) as payment links
+
+-- A virtual account is a number to transfer to rather than a page to open, so
+-- url becomes optional. SQLite can't relax NOT NULL in place; rebuild the table.
+CREATE TABLE IF NOT EXISTS payment_links_rebuilt (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    preorder_id INTEGER NOT NULL,
+    -- 'stripe', 'paypal' or 'midtrans'
+    provider TEXT NOT NULL,
+    -- The provider's ID for the link, invoice or charge
+    external_id TEXT NOT NULL,
+    url TEXT,
+    -- e.g. 'bank_transfer:bca' or 'qris'; NULL when the customer picks on the provider's page
+    method TEXT,
+    -- Virtual account number or QRIS payload to pay to
+    payment_code TEXT,
+    amount REAL NOT NULL,
+    currency_code TEXT NOT NULL,
+    -- 'open', 'paid' or 'cancelled'
+    status TEXT NOT NULL DEFAULT 'open',
+    payment_reference TEXT,
+    payment_id INTEGER,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    expires_at DATETIME,
+    paid_at DATETIME,
+    checked_at DATETIME,
+    FOREIGN KEY (preorder_id) REFERENCES preorders(id) ON DELETE CASCADE,
+    FOREIGN KEY (payment_id) REFERENCES payments(id) ON DELETE SET NULL
+);
+
+INSERT INTO payment_links_rebuilt (id, preorder_id, provider, external_id, url, amount,
+    currency_code, status, payment_reference, payment_id, created_at, paid_at, checked_at)
+SELECT id, preorder_id, provider, external_id, url, amount,
+    currency_code, status, payment_reference, payment_id, created_at, paid_at, checked_at
+FROM payment_links;
+
+DROP TABLE payment_links;
+ALTER TABLE payment_links_rebuilt RENAME TO payment_links;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_payment_links_external ON payment_links(provider, external_id);
+CREATE INDEX IF NOT EXISTS idx_payment_links_order ON payment_links(preorder_id);
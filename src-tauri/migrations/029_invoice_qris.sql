@@ -0,0 +1,9 @@
+-- POTracker Database Schema
+-- Migration 029: QRIS payment code on invoices
+
+-- The merchant's static QRIS payload, as printed on their QRIS sticker.
+-- Invoices in rupiah with a balance due print it as a QR code; with
+-- qris_dynamic set, the balance is written into the code so the customer's
+-- app fills in the amount.
+ALTER TABLE invoice_layout ADD COLUMN qris_payload TEXT;
+ALTER TABLE invoice_layout ADD COLUMN qris_dynamic INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,44 @@
+-- POTracker Database Schema
+-- Migration 030: Payment reminder rules and queue
+
+-- When to remind customers with a balance due: offset_days after the order
+-- was invoiced, or offset_days before its event's start date (pickup day)
+CREATE TABLE IF NOT EXISTS reminder_rules (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    anchor TEXT NOT NULL CHECK (anchor IN ('invoiced', 'pickup')),
+    offset_days INTEGER NOT NULL CHECK (offset_days >= 0),
+    enabled INTEGER NOT NULL DEFAULT 1,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Reminder emails queued by the rules, at most one per order and rule.
+-- 'pending' ones wait to be sent; 'skipped' ones were dismissed, or dropped
+-- because the order was paid, cancelled or opted out first.
+CREATE TABLE IF NOT EXISTS payment_reminders (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    preorder_id INTEGER NOT NULL,
+    rule_id INTEGER NOT NULL,
+    due_at DATETIME NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'sent', 'skipped')),
+    queued_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    closed_at DATETIME,
+    UNIQUE (preorder_id, rule_id),
+    FOREIGN KEY (preorder_id) REFERENCES preorders(id) ON DELETE CASCADE,
+    FOREIGN KEY (rule_id) REFERENCES reminder_rules(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_payment_reminders_status ON payment_reminders(status, due_at);
+
+-- Orders whose customer shouldn't get reminders
+CREATE TABLE IF NOT EXISTS reminder_opt_outs (
+    preorder_id INTEGER PRIMARY KEY,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (preorder_id) REFERENCES preorders(id) ON DELETE CASCADE
+);
+
+-- The two common rules, off until turned on
+INSERT INTO reminder_rules (name, anchor, offset_days, enabled) VALUES
+    ('3 days after invoice', 'invoiced', 3, 0),
+    ('1 day before pickup', 'pickup', 1, 0);
@@ -0,0 +1,61 @@
+-- POTracker Database Schema
+-- Migration 031: Refunds and credit notes
+
+-- A credit note takes part of an invoiced order back (returned or damaged
+-- items, a price adjustment). Amounts are stored as printed: negative.
+CREATE TABLE IF NOT EXISTS credit_notes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    preorder_id INTEGER NOT NULL,
+    credit_note_number TEXT NOT NULL UNIQUE,
+    -- Invoice number of the order when the note was issued
+    invoice_number TEXT NOT NULL,
+    reason_code TEXT NOT NULL CHECK (reason_code IN
+        ('returned', 'damaged', 'not_delivered', 'price_adjustment', 'goodwill', 'other')),
+    reason TEXT,
+    subtotal REAL NOT NULL,
+    tax_amount REAL NOT NULL DEFAULT 0,
+    total_amount REAL NOT NULL CHECK (total_amount < 0),
+    issued_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (preorder_id) REFERENCES preorders(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_credit_notes_order ON credit_notes(preorder_id);
+
+CREATE TABLE IF NOT EXISTS credit_note_items (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    credit_note_id INTEGER NOT NULL,
+    -- The invoice line being credited; NULL for adjustments not tied to one
+    order_item_id INTEGER,
+    product_id INTEGER,
+    description TEXT NOT NULL,
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    unit_price REAL NOT NULL CHECK (unit_price < 0),
+    FOREIGN KEY (credit_note_id) REFERENCES credit_notes(id) ON DELETE CASCADE,
+    FOREIGN KEY (order_item_id) REFERENCES order_items(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_credit_note_items_note ON credit_note_items(credit_note_id);
+
+-- Last issued credit note sequence value per year
+CREATE TABLE IF NOT EXISTS credit_note_sequences (
+    year INTEGER PRIMARY KEY,
+    last_value INTEGER NOT NULL
+);
+
+-- Money paid back to the customer
+CREATE TABLE IF NOT EXISTS refunds (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    preorder_id INTEGER NOT NULL,
+    -- The credit note the refund settles, if any
+    credit_note_id INTEGER,
+    amount REAL NOT NULL CHECK (amount > 0),
+    method TEXT NOT NULL,
+    refunded_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    reference TEXT,
+    notes TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (preorder_id) REFERENCES preorders(id) ON DELETE CASCADE,
+    FOREIGN KEY (credit_note_id) REFERENCES credit_notes(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_refunds_order ON refunds(preorder_id);
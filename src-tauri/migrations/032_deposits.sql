@@ -0,0 +1,16 @@
+-- POTracker Database Schema
+-- Migration 032: Upfront deposits
+
+-- Share of the order total to collect before the final invoice; NULL for
+-- orders paid in one go. deposit_amount is fixed when the deposit invoice
+-- is issued, so later edits to the order only change the remainder.
+ALTER TABLE preorders ADD COLUMN deposit_percent REAL
+    CHECK (deposit_percent IS NULL OR (deposit_percent > 0 AND deposit_percent < 100));
+ALTER TABLE preorders ADD COLUMN deposit_amount REAL;
+-- Drawn from the same sequence as invoice_number
+ALTER TABLE preorders ADD COLUMN deposit_invoice_number TEXT;
+ALTER TABLE preorders ADD COLUMN deposit_invoiced_at DATETIME;
+ALTER TABLE preorders ADD COLUMN deposit_paid_at DATETIME;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_preorders_deposit_invoice_number
+    ON preorders(deposit_invoice_number);
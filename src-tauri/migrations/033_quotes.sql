@@ -0,0 +1,41 @@
+-- POTracker Database Schema
+-- Migration 033: Quotes with locked prices and exchange rate
+
+-- A quote fixes its line prices, in the customer's currency, until
+-- valid_until; converting it creates an order at exactly those prices.
+-- The exchange rate from the app's currency at quoting time is kept for
+-- reference. Expiry is derived from valid_until, not stored.
+CREATE TABLE IF NOT EXISTS quotes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    customer_name TEXT NOT NULL,
+    customer_email TEXT NOT NULL,
+    currency_code TEXT NOT NULL,
+    base_currency TEXT NOT NULL,
+    -- Units of currency_code per unit of base_currency
+    exchange_rate REAL NOT NULL CHECK (exchange_rate > 0),
+    -- Publication date of the rate; NULL when no conversion was needed
+    rate_date TEXT,
+    notes TEXT,
+    event_id INTEGER REFERENCES events(id),
+    total_amount REAL NOT NULL DEFAULT 0,
+    valid_until DATETIME NOT NULL,
+    status TEXT NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'accepted', 'withdrawn')),
+    -- Order the quote was converted into
+    preorder_id INTEGER REFERENCES preorders(id),
+    accepted_at DATETIME,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_quotes_status ON quotes(status, valid_until);
+
+CREATE TABLE IF NOT EXISTS quote_items (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    quote_id INTEGER NOT NULL,
+    product_id INTEGER NOT NULL,
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    -- Locked price in the quote's currency
+    unit_price REAL NOT NULL CHECK (unit_price >= 0),
+    FOREIGN KEY (quote_id) REFERENCES quotes(id) ON DELETE CASCADE,
+    FOREIGN KEY (product_id) REFERENCES products(id)
+);
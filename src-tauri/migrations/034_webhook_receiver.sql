@@ -0,0 +1,43 @@
+-- POTracker Database Schema
+-- Migration 034: Incoming webhooks
+
+-- Single-row settings for the local webhook receiver. It listens on
+-- 127.0.0.1 unless bind_all is set; reaching it from outside needs a
+-- tunnel or port forward either way.
+CREATE TABLE IF NOT EXISTS webhook_receiver_settings (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    enabled INTEGER NOT NULL DEFAULT 0,
+    port INTEGER NOT NULL DEFAULT 8787 CHECK (port BETWEEN 1024 AND 65535),
+    bind_all INTEGER NOT NULL DEFAULT 0
+);
+
+INSERT OR IGNORE INTO webhook_receiver_settings (id) VALUES (1);
+
+-- A system allowed to push events, at POST /webhooks/<slug>. Requests must
+-- be signed with signing_secret the way the provider does it.
+CREATE TABLE IF NOT EXISTS webhook_sources (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    slug TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    provider TEXT NOT NULL CHECK (provider IN ('stripe', 'shopify', 'generic')),
+    signing_secret TEXT NOT NULL,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Verified events as received. external_id is the sender's event ID, so
+-- redeliveries are stored once.
+CREATE TABLE IF NOT EXISTS webhook_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    source_id INTEGER NOT NULL,
+    event_type TEXT NOT NULL,
+    external_id TEXT,
+    payload TEXT NOT NULL,
+    received_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    handled_at DATETIME,
+    UNIQUE (source_id, external_id),
+    FOREIGN KEY (source_id) REFERENCES webhook_sources(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_events_unhandled ON webhook_events(handled_at, received_at);
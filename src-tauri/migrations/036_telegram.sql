@@ -0,0 +1,32 @@
+-- POTracker Database Schema
+-- Migration 036: Telegram notifications for the business owner
+
+-- Single-row settings. chat_id is the owner's chat (or a group) the bot
+-- posts to; each notify_* column turns one kind of message on or off.
+CREATE TABLE IF NOT EXISTS telegram_settings (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    enabled INTEGER NOT NULL DEFAULT 0,
+    bot_token TEXT,
+    chat_id TEXT,
+    notify_new_order INTEGER NOT NULL DEFAULT 1,
+    notify_payment INTEGER NOT NULL DEFAULT 1,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT OR IGNORE INTO telegram_settings (id) VALUES (1);
+
+-- Messages queued with the change they report and sent in the background
+CREATE TABLE IF NOT EXISTS telegram_messages (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    event TEXT NOT NULL CHECK (event IN ('new_order', 'payment')),
+    preorder_id INTEGER,
+    text TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'sent', 'failed')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    sent_at DATETIME,
+    FOREIGN KEY (preorder_id) REFERENCES preorders(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_telegram_messages_status ON telegram_messages(status, id);
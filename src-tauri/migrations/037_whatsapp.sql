@@ -0,0 +1,43 @@
+-- POTracker Database Schema
+-- Migration 037: WhatsApp messages to customers through the Cloud API
+
+-- Single-row settings. Templates are the names of message templates
+-- approved in WhatsApp Manager; their body variables are filled in order
+-- (see whatsapp.rs). A NULL template turns that message off.
+CREATE TABLE IF NOT EXISTS whatsapp_settings (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    enabled INTEGER NOT NULL DEFAULT 0,
+    phone_number_id TEXT,
+    access_token TEXT,
+    template_language TEXT NOT NULL DEFAULT 'en',
+    confirmation_template TEXT,
+    pickup_template TEXT,
+    pickup_days_before INTEGER NOT NULL DEFAULT 1 CHECK (pickup_days_before BETWEEN 0 AND 30),
+    -- Prefix for local numbers written with a leading 0
+    default_country_code TEXT NOT NULL DEFAULT '62',
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT OR IGNORE INTO whatsapp_settings (id) VALUES (1);
+
+-- Every message sent (or waiting to be), at most one of each kind per order
+CREATE TABLE IF NOT EXISTS whatsapp_messages (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    preorder_id INTEGER NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('confirmation', 'pickup_reminder')),
+    to_phone TEXT NOT NULL,
+    template TEXT NOT NULL,
+    -- JSON array of the template's body variables
+    variables TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'sent', 'failed')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    -- wamid returned by the Cloud API
+    message_id TEXT,
+    last_error TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    sent_at DATETIME,
+    UNIQUE (preorder_id, kind),
+    FOREIGN KEY (preorder_id) REFERENCES preorders(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_whatsapp_messages_status ON whatsapp_messages(status, id);
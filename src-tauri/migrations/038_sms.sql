@@ -0,0 +1,45 @@
+-- POTracker Database Schema
+-- Migration 038: SMS through Twilio or Vonage
+
+-- How a customer wants to hear from us. 'sms' sends confirmation codes and
+-- payment reminders as texts to their phone number instead of email.
+ALTER TABLE customers ADD COLUMN contact_preference TEXT NOT NULL DEFAULT 'email'
+    CHECK (contact_preference IN ('email', 'sms'));
+
+-- Single-row settings. account_id and auth_token are the Twilio Account SID
+-- and auth token, or the Vonage API key and secret.
+CREATE TABLE IF NOT EXISTS sms_settings (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    enabled INTEGER NOT NULL DEFAULT 0,
+    provider TEXT NOT NULL DEFAULT 'twilio' CHECK (provider IN ('twilio', 'vonage')),
+    account_id TEXT,
+    auth_token TEXT,
+    -- Sending number, or an alphanumeric sender ID where the carrier allows it
+    sender TEXT,
+    -- Prefix for local numbers written with a leading 0
+    default_country_code TEXT NOT NULL DEFAULT '62',
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT OR IGNORE INTO sms_settings (id) VALUES (1);
+
+-- Every text sent or waiting to be. Confirmations are queued when the order
+-- is confirmed; reminders are logged as they're sent.
+CREATE TABLE IF NOT EXISTS sms_messages (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    preorder_id INTEGER NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('confirmation_code', 'payment_reminder')),
+    provider TEXT NOT NULL,
+    to_phone TEXT NOT NULL,
+    body TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'sent', 'failed')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    provider_message_id TEXT,
+    last_error TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    sent_at DATETIME,
+    FOREIGN KEY (preorder_id) REFERENCES preorders(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_sms_messages_status ON sms_messages(status, id);
+CREATE INDEX IF NOT EXISTS idx_sms_messages_order ON sms_messages(preorder_id);
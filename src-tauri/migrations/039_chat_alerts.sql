@@ -0,0 +1,33 @@
+-- POTracker Database Schema
+-- Migration 039: Slack and Discord alerts for the team
+
+-- Single-row settings. Each channel is used when its incoming webhook URL is
+-- set; each notify_* column turns one kind of alert on or off.
+CREATE TABLE IF NOT EXISTS chat_alert_settings (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    enabled INTEGER NOT NULL DEFAULT 0,
+    slack_webhook_url TEXT,
+    discord_webhook_url TEXT,
+    notify_new_order INTEGER NOT NULL DEFAULT 1,
+    notify_low_stock INTEGER NOT NULL DEFAULT 1,
+    notify_email_failed INTEGER NOT NULL DEFAULT 1,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT OR IGNORE INTO chat_alert_settings (id) VALUES (1);
+
+-- One row per alert and channel, queued with the change it reports and
+-- sent in the background
+CREATE TABLE IF NOT EXISTS chat_alerts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    event TEXT NOT NULL CHECK (event IN ('new_order', 'low_stock', 'email_failed')),
+    channel TEXT NOT NULL CHECK (channel IN ('slack', 'discord')),
+    text TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'sent', 'failed')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    sent_at DATETIME
+);
+
+CREATE INDEX IF NOT EXISTS idx_chat_alerts_status ON chat_alerts(status, id);
@@ -0,0 +1,29 @@
+-- POTracker Database Schema
+-- Migration 040: Native desktop notifications
+
+-- Single-row settings; each notify_* column turns one kind of notification
+-- on or off
+CREATE TABLE IF NOT EXISTS desktop_notification_settings (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    enabled INTEGER NOT NULL DEFAULT 1,
+    notify_form_response INTEGER NOT NULL DEFAULT 1,
+    notify_email_failed INTEGER NOT NULL DEFAULT 1,
+    notify_reminder_due INTEGER NOT NULL DEFAULT 1,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT OR IGNORE INTO desktop_notification_settings (id) VALUES (1);
+
+-- Notifications queued by backend jobs and shown by the app's notifier loop.
+-- Skipped ones were switched off by the time they came up.
+CREATE TABLE IF NOT EXISTS desktop_notifications (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    event TEXT NOT NULL CHECK (event IN ('form_response', 'email_failed', 'reminder_due')),
+    title TEXT NOT NULL,
+    body TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'shown', 'skipped')),
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    shown_at DATETIME
+);
+
+CREATE INDEX IF NOT EXISTS idx_desktop_notifications_status ON desktop_notifications(status, id);
@@ -0,0 +1,22 @@
+-- POTracker Database Schema
+-- Migration 041: Scheduled automatic backups
+
+-- Single-row settings. folder is where copies are written, the profile's
+-- backups directory when unset; keep_count copies are kept there. With
+-- drive_enabled an encrypted copy also goes to the Drive appDataFolder,
+-- locked with drive_passphrase.
+CREATE TABLE IF NOT EXISTS auto_backup_settings (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    enabled INTEGER NOT NULL DEFAULT 0,
+    folder TEXT,
+    keep_count INTEGER NOT NULL DEFAULT 7 CHECK (keep_count >= 1),
+    interval_hours INTEGER NOT NULL DEFAULT 24 CHECK (interval_hours >= 1),
+    drive_enabled INTEGER NOT NULL DEFAULT 0,
+    drive_passphrase TEXT,
+    -- Set by the scheduler, not by the settings form
+    last_backup_at DATETIME,
+    last_error TEXT,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT OR IGNORE INTO auto_backup_settings (id) VALUES (1);
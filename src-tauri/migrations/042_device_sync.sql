@@ -0,0 +1,96 @@
+-- POTracker Database Schema
+-- Migration 042: Two-way sync between devices through a Drive changelog
+
+-- Single-row sync state. device_id names this install in the shared
+-- changelog; clock is its Lamport clock. applying is only set inside the
+-- transaction that applies other devices' changes, so the capture triggers
+-- don't echo them back.
+CREATE TABLE IF NOT EXISTS sync_state (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    enabled INTEGER NOT NULL DEFAULT 0,
+    device_id TEXT,
+    passphrase TEXT,
+    clock INTEGER NOT NULL DEFAULT 0,
+    applying INTEGER NOT NULL DEFAULT 0,
+    last_pushed_seq INTEGER NOT NULL DEFAULT 0,
+    last_sync_at DATETIME,
+    last_error TEXT,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT OR IGNORE INTO sync_state (id) VALUES (1);
+
+-- Global IDs for synced rows, since local IDs differ between devices. A row
+-- merged with another device's copy can have several. Deleted rows keep
+-- theirs as a tombstone; local_id is NULL for rows deleted before they
+-- arrived here.
+CREATE TABLE IF NOT EXISTS sync_rows (
+    gid TEXT PRIMARY KEY,
+    table_name TEXT NOT NULL,
+    local_id INTEGER,
+    deleted INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS idx_sync_rows_local ON sync_rows(table_name, local_id);
+
+-- Changes made on this device, captured by triggers and removed once pushed.
+-- data holds the row (insert, snapshot) or the changed fields (update), with
+-- references to other synced rows given as global IDs.
+CREATE TABLE IF NOT EXISTS sync_ops (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    clock INTEGER NOT NULL,
+    table_name TEXT NOT NULL,
+    row_gid TEXT NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('insert', 'update', 'delete', 'snapshot')),
+    data TEXT NOT NULL DEFAULT '{}',
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Last write to each field, for last-writer-wins merging
+CREATE TABLE IF NOT EXISTS sync_field_clocks (
+    table_name TEXT NOT NULL,
+    local_id INTEGER NOT NULL,
+    column_name TEXT NOT NULL,
+    clock INTEGER NOT NULL,
+    device_id TEXT NOT NULL,
+    PRIMARY KEY (table_name, local_id, column_name)
+);
+
+-- Highest changelog sequence read from each other device
+CREATE TABLE IF NOT EXISTS sync_peers (
+    device_id TEXT PRIMARY KEY,
+    last_seq INTEGER NOT NULL DEFAULT 0,
+    last_seen_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Other devices' changes waiting to be applied, e.g. an order line whose
+-- order hasn't arrived yet
+CREATE TABLE IF NOT EXISTS sync_inbox (
+    device_id TEXT NOT NULL,
+    seq INTEGER NOT NULL,
+    clock INTEGER NOT NULL,
+    table_name TEXT NOT NULL,
+    row_gid TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    data TEXT NOT NULL,
+    received_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (device_id, seq)
+);
+
+-- Changes that couldn't be applied, e.g. two devices issuing the same
+-- invoice number, kept for the user to retry or dismiss
+CREATE TABLE IF NOT EXISTS sync_conflicts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    device_id TEXT NOT NULL,
+    seq INTEGER NOT NULL,
+    clock INTEGER NOT NULL,
+    table_name TEXT NOT NULL,
+    row_gid TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    data TEXT NOT NULL,
+    error TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    resolved_at DATETIME
+);
+
+CREATE INDEX IF NOT EXISTS idx_sync_conflicts_open ON sync_conflicts(resolved_at, id);
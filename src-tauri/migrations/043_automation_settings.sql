@@ -0,0 +1,31 @@
+-- POTracker Database Schema
+-- Migration 043: Poll intervals, quiet hours and a daily send limit for
+-- background automations
+
+-- Single-row settings. The intervals are seconds between passes of the
+-- background loops: the recurring order scheduler, the payment reminder
+-- engine, the outbox senders (webhooks, Telegram, WhatsApp, SMS, chat alerts)
+-- and the desktop notifier. Between quiet_start and quiet_end (local HH:MM,
+-- may wrap past midnight) nothing is sent to customers or shown to the user;
+-- queued messages wait. max_daily_sends caps automated messages per local
+-- day, unlimited when NULL.
+CREATE TABLE IF NOT EXISTS automation_settings (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    scheduler_interval_secs INTEGER NOT NULL DEFAULT 60,
+    reminder_interval_secs INTEGER NOT NULL DEFAULT 300,
+    sender_interval_secs INTEGER NOT NULL DEFAULT 30,
+    notifier_interval_secs INTEGER NOT NULL DEFAULT 5,
+    quiet_hours_enabled INTEGER NOT NULL DEFAULT 0,
+    quiet_start TEXT NOT NULL DEFAULT '22:00',
+    quiet_end TEXT NOT NULL DEFAULT '07:00',
+    max_daily_sends INTEGER CHECK (max_daily_sends IS NULL OR max_daily_sends >= 1),
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT OR IGNORE INTO automation_settings (id) VALUES (1);
+
+-- Automated messages sent per local day (YYYY-MM-DD), for max_daily_sends
+CREATE TABLE IF NOT EXISTS automation_sends (
+    day TEXT PRIMARY KEY,
+    sent INTEGER NOT NULL DEFAULT 0
+);
@@ -0,0 +1,48 @@
+-- POTracker Database Schema
+-- Migration 044: Settings owned by the backend
+
+-- SMTP account, Google OAuth client and invoice email template were created
+-- by the frontend; declared here so the settings store can rely on them
+CREATE TABLE IF NOT EXISTS smtp_settings (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    smtp_server TEXT NOT NULL,
+    smtp_port INTEGER NOT NULL DEFAULT 587,
+    username TEXT NOT NULL,
+    password TEXT NOT NULL,
+    from_email TEXT NOT NULL,
+    from_name TEXT DEFAULT 'POTracker'
+);
+
+CREATE TABLE IF NOT EXISTS google_config (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    client_id TEXT NOT NULL,
+    client_secret TEXT NOT NULL
+);
+
+-- sections is a JSON array of {id, type, label, enabled, order}
+CREATE TABLE IF NOT EXISTS invoice_templates (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    sections TEXT NOT NULL,
+    header_title TEXT DEFAULT 'Pre-Order Invoice',
+    header_subtitle TEXT DEFAULT 'Thank you for your order!',
+    footer_text TEXT DEFAULT 'This is an automated email from POTracker',
+    primary_color TEXT DEFAULT '#6366f1',
+    secondary_color TEXT DEFAULT '#a855f7',
+    use_banner_image INTEGER DEFAULT 0,
+    banner_image_url TEXT DEFAULT ''
+);
+
+-- Single-row business profile. Invoices print it when the invoice layout
+-- has no business name or details of its own.
+CREATE TABLE IF NOT EXISTS business_profile (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    name TEXT NOT NULL DEFAULT '',
+    email TEXT,
+    phone TEXT,
+    address TEXT,
+    tax_id TEXT,
+    website TEXT,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT OR IGNORE INTO business_profile (id) VALUES (1);
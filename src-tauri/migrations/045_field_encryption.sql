@@ -0,0 +1,14 @@
+-- POTracker Database Schema
+-- Migration 045: Optional encryption of sensitive columns
+
+-- Present only while encryption is on. wrapped_key is the random data key
+-- that encrypts customer contact details and integration credentials,
+-- itself encrypted with the user's passphrase (Argon2id + AES-256-GCM).
+-- Encrypted values are stored as "enc:v1:" followed by base64 of the nonce
+-- and ciphertext; plaintext values are still read as they are.
+CREATE TABLE IF NOT EXISTS encryption_settings (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    wrapped_key BLOB NOT NULL,
+    enabled_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,13 @@
+-- POTracker Database Schema
+-- Migration 046: Key for signing full-data exports
+
+-- This install's Ed25519 key pair, created on the first export. Exports are
+-- signed with secret_key and carry public_key, so an import can tell a
+-- damaged or edited file from an intact one, and whether it came from this
+-- install. Both are base64; secret_key is encrypted while encryption is on.
+CREATE TABLE IF NOT EXISTS data_signing_key (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    secret_key TEXT NOT NULL,
+    public_key TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,13 @@
+-- POTracker Database Schema
+-- Migration 047: Session lock after idle time
+
+-- idle_timeout_mins: minutes without activity before the app locks, NULL
+-- for never. While locked, contact details and integration credentials can't
+-- be read or written and no email is sent until the passphrase (when
+-- encryption is on) or the PIN is entered. pin_hash is an Argon2id PHC string.
+CREATE TABLE IF NOT EXISTS session_lock_settings (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    idle_timeout_mins INTEGER,
+    pin_hash TEXT,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,12 @@
+-- POTracker Database Schema
+-- Migration 048: Log level setting
+
+-- level: most detailed messages written to the rotating log file in the app
+-- data dir (error, warn, info, debug or trace). Other libraries log warnings
+-- and errors only.
+CREATE TABLE IF NOT EXISTS log_settings (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    level TEXT NOT NULL DEFAULT 'info'
+        CHECK (level IN ('error', 'warn', 'info', 'debug', 'trace')),
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
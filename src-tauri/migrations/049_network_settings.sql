@@ -0,0 +1,15 @@
+-- POTracker Database Schema
+-- Migration 049: Proxy and custom CA settings
+
+-- proxy_url: http://, https://, socks5:// or socks5h:// proxy for calls to
+-- Google and other HTTP APIs, NULL to connect directly.
+-- ca_bundle_path: PEM file of extra root certificates trusted by the HTTP
+-- client and SMTP, for networks with a TLS-inspecting firewall.
+CREATE TABLE IF NOT EXISTS network_settings (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    proxy_url TEXT,
+    proxy_username TEXT,
+    proxy_password TEXT,
+    ca_bundle_path TEXT,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,7 @@
+-- POTracker Database Schema
+-- Migration 050: Configurable HTTP timeouts
+
+-- Seconds to wait for a connection and for a whole request (body included);
+-- NULL uses the defaults of 10 and 300
+ALTER TABLE network_settings ADD COLUMN connect_timeout_secs INTEGER;
+ALTER TABLE network_settings ADD COLUMN request_timeout_secs INTEGER;
@@ -0,0 +1,20 @@
+-- POTracker Database Schema
+-- Migration 051: Campaign expenses
+
+-- Costs of running a campaign besides supplier orders: payment fees,
+-- shipping, ads. Tied to a campaign (event) or to the Google Form its orders
+-- came from; neither means general overhead.
+CREATE TABLE IF NOT EXISTS expenses (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    event_id INTEGER REFERENCES events(id) ON DELETE SET NULL,
+    form_id TEXT,
+    category TEXT NOT NULL CHECK (category IN ('fee', 'shipping', 'marketing', 'supplies', 'other')),
+    description TEXT NOT NULL,
+    amount REAL NOT NULL CHECK (amount > 0),
+    incurred_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    CHECK (event_id IS NULL OR form_id IS NULL)
+);
+
+CREATE INDEX IF NOT EXISTS idx_expenses_event ON expenses(event_id);
+CREATE INDEX IF NOT EXISTS idx_expenses_form ON expenses(form_id);
@@ -0,0 +1,7 @@
+-- POTracker Database Schema
+-- Migration 052: Invoice patterns must contain the year
+
+-- The invoice counter restarts every year, so a pattern without {YYYY} or {YY}
+-- repeats last year's numbers. Such patterns go back to the default.
+UPDATE invoice_settings SET number_pattern = 'INV-{YYYY}{MM}-{seq:4}'
+WHERE number_pattern NOT LIKE '%{YYYY}%' AND number_pattern NOT LIKE '%{YY}%';
@@ -0,0 +1,82 @@
+-- POTracker Database Schema
+-- Migration 053: Amounts in the order history views
+--
+-- Reports and the dashboard read live and archived orders through
+-- order_history, so the views carry everything they sum: credit notes,
+-- payments net of refunds, supplier costs, the invoice date and the form an
+-- order was imported from. Archived orders keep these as summary columns
+-- because their child rows only live on in the snapshot.
+
+ALTER TABLE archived_orders ADD COLUMN amount_credited REAL NOT NULL DEFAULT 0;
+ALTER TABLE archived_orders ADD COLUMN supplier_cost REAL NOT NULL DEFAULT 0;
+ALTER TABLE archived_orders ADD COLUMN invoiced_at DATETIME;
+ALTER TABLE archived_orders ADD COLUMN form_id TEXT;
+ALTER TABLE archived_order_items ADD COLUMN amount_credited REAL NOT NULL DEFAULT 0;
+
+-- Fill them in for orders archived before this migration, from their snapshots
+UPDATE archived_orders SET
+    amount_credited = -COALESCE((
+        SELECT SUM(json_extract(c.value, '$.total_amount'))
+        FROM json_each(archived_orders.snapshot_json, '$.children.credit_notes') c), 0.0),
+    supplier_cost = COALESCE((
+        SELECT SUM(json_extract(d.value, '$.quantity') * si.unit_cost)
+        FROM json_each(archived_orders.snapshot_json, '$.children.supplier_order_demand') d
+        JOIN supplier_order_items si ON si.id = json_extract(d.value, '$.supplier_order_item_id')
+        JOIN supplier_orders so ON so.id = si.supplier_order_id
+        WHERE so.status != 'cancelled' AND si.unit_cost IS NOT NULL), 0.0),
+    invoiced_at = json_extract(snapshot_json, '$.order.invoiced_at'),
+    form_id = (
+        SELECT r.form_id FROM synced_responses r
+        WHERE r.response_id IN (
+            SELECT value FROM json_each(archived_orders.snapshot_json, '$.form_response_ids'))
+        LIMIT 1);
+
+-- Archived items don't keep their original ID, so credits are matched to the
+-- line with the same product, quantity and price
+UPDATE archived_order_items SET amount_credited = -COALESCE((
+    SELECT SUM(json_extract(ci.value, '$.quantity') * json_extract(ci.value, '$.unit_price'))
+    FROM archived_orders a,
+         json_each(a.snapshot_json, '$.children.order_items') oi,
+         json_each(a.snapshot_json, '$.children.credit_note_items') ci
+    WHERE a.id = archived_order_items.archived_order_id
+      AND json_extract(ci.value, '$.order_item_id') = json_extract(oi.value, '$.id')
+      AND json_extract(oi.value, '$.product_id') = archived_order_items.product_id
+      AND json_extract(oi.value, '$.quantity') = archived_order_items.quantity
+      AND json_extract(oi.value, '$.unit_price') = archived_order_items.unit_price), 0.0);
+
+-- amount_credited is positive (what credit notes took off); amount_paid is
+-- net of refunds; supplier_cost covers demand on supplier orders that weren't
+-- cancelled
+DROP VIEW IF EXISTS order_history;
+CREATE VIEW order_history AS
+SELECT po.id, po.customer_name, po.customer_email, COALESCE(po.status, 'pending') AS status,
+       po.total_amount,
+       -(SELECT COALESCE(SUM(total_amount), 0.0) FROM credit_notes WHERE preorder_id = po.id)
+           AS amount_credited,
+       (SELECT COALESCE(SUM(amount), 0.0) FROM payments WHERE preorder_id = po.id) -
+       (SELECT COALESCE(SUM(amount), 0.0) FROM refunds WHERE preorder_id = po.id) AS amount_paid,
+       (SELECT COALESCE(SUM(d.quantity * si.unit_cost), 0.0) FROM supplier_order_demand d
+        JOIN supplier_order_items si ON si.id = d.supplier_order_item_id
+        JOIN supplier_orders so ON so.id = si.supplier_order_id
+        WHERE d.preorder_id = po.id AND so.status != 'cancelled' AND si.unit_cost IS NOT NULL)
+           AS supplier_cost,
+       po.currency_code, po.event_id,
+       (SELECT form_id FROM synced_responses WHERE preorder_id = po.id LIMIT 1) AS form_id,
+       po.created_at, po.invoiced_at, 0 AS archived
+FROM preorders po WHERE po.deleted_at IS NULL
+UNION ALL
+SELECT id, customer_name, customer_email, status, total_amount, amount_credited, amount_paid,
+       supplier_cost, currency_code, event_id, form_id, created_at, invoiced_at, 1 AS archived
+FROM archived_orders WHERE deleted_at IS NULL;
+
+DROP VIEW IF EXISTS order_item_history;
+CREATE VIEW order_item_history AS
+SELECT oi.preorder_id, oi.product_id, oi.quantity, oi.unit_price,
+       -(SELECT COALESCE(SUM(ci.quantity * ci.unit_price), 0.0) FROM credit_note_items ci
+         WHERE ci.order_item_id = oi.id) AS amount_credited,
+       0 AS archived
+FROM order_items oi WHERE oi.preorder_id IN (SELECT id FROM preorders WHERE deleted_at IS NULL)
+UNION ALL
+SELECT i.archived_order_id, i.product_id, i.quantity, i.unit_price, i.amount_credited, 1 AS archived
+FROM archived_order_items i JOIN archived_orders a ON a.id = i.archived_order_id
+WHERE a.deleted_at IS NULL;
@@ -0,0 +1,58 @@
+-- POTracker Database Schema
+-- Migration 054: Effective-dated per-currency prices
+--
+-- product_prices only holds the explicit prices in effect now (the frontend
+-- rewrites a product's rows on every edit), so it can't say which price an
+-- older order was placed at. Every change is recorded here with the time it
+-- took effect, like product_price_history does for base prices; a NULL price
+-- marks an explicit price being removed.
+
+CREATE TABLE IF NOT EXISTS currency_price_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    product_id INTEGER NOT NULL,
+    currency_code TEXT NOT NULL COLLATE NOCASE,
+    price REAL,
+    effective_from DATETIME NOT NULL,
+    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_currency_price_history_product
+    ON currency_price_history(product_id, currency_code, effective_from);
+
+-- Existing prices have applied since the product was created
+INSERT INTO currency_price_history (product_id, currency_code, price, effective_from)
+SELECT pp.product_id, UPPER(pp.currency_code), pp.price, COALESCE(p.created_at, CURRENT_TIMESTAMP)
+FROM product_prices pp JOIN products p ON p.id = pp.product_id
+WHERE pp.id = (
+    SELECT MAX(id) FROM product_prices
+    WHERE product_id = pp.product_id AND currency_code = pp.currency_code COLLATE NOCASE);
+
+-- Only a price that differs from the one in effect is recorded
+CREATE TRIGGER IF NOT EXISTS trg_product_prices_insert AFTER INSERT ON product_prices
+WHEN NEW.price IS NOT (
+    SELECT price FROM currency_price_history
+    WHERE product_id = NEW.product_id AND currency_code = NEW.currency_code
+    ORDER BY effective_from DESC, id DESC LIMIT 1)
+BEGIN
+    INSERT INTO currency_price_history (product_id, currency_code, price, effective_from)
+    VALUES (NEW.product_id, UPPER(NEW.currency_code), NEW.price, CURRENT_TIMESTAMP);
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_product_prices_update AFTER UPDATE OF price ON product_prices
+WHEN NEW.price IS NOT OLD.price
+BEGIN
+    INSERT INTO currency_price_history (product_id, currency_code, price, effective_from)
+    VALUES (NEW.product_id, UPPER(NEW.currency_code), NEW.price, CURRENT_TIMESTAMP);
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_product_prices_delete AFTER DELETE ON product_prices
+WHEN (
+    SELECT price FROM currency_price_history
+    WHERE product_id = OLD.product_id AND currency_code = OLD.currency_code
+    ORDER BY effective_from DESC, id DESC LIMIT 1) IS NOT NULL
+    -- Not when the product itself is being deleted
+    AND EXISTS (SELECT 1 FROM products WHERE id = OLD.product_id)
+BEGIN
+    INSERT INTO currency_price_history (product_id, currency_code, price, effective_from)
+    VALUES (OLD.product_id, UPPER(OLD.currency_code), NULL, CURRENT_TIMESTAMP);
+END;
@@ -0,0 +1,574 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection, SqlitePool};
+use std::collections::BTreeMap;
+use tauri::{AppHandle, Manager, State};
+
+use crate::audit;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::models::PurchaseOrder;
+use crate::orders::{fetch_order, load_order};
+use crate::{log_info, log_warning};
+
+// Every table that cascades from preorders, with the filter picking an order's
+// rows. Their rows travel with the order into the archive and back, restored
+// in this order so parents (payments, credit_notes) exist before the rows
+// pointing at them.
+const ORDER_CHILD_TABLES: &[(&str, &str)] = &[
+    ("order_items", "preorder_id = ?"),
+    ("payments", "preorder_id = ?"),
+    ("order_status_history", "preorder_id = ?"),
+    ("order_notes", "preorder_id = ?"),
+    ("order_emails", "preorder_id = ?"),
+    ("supplier_order_demand", "preorder_id = ?"),
+    ("fulfillments", "preorder_id = ?"),
+    ("order_documents", "preorder_id = ?"),
+    ("payment_links", "preorder_id = ?"),
+    ("payment_reminders", "preorder_id = ?"),
+    ("reminder_opt_outs", "preorder_id = ?"),
+    ("credit_notes", "preorder_id = ?"),
+    (
+        "credit_note_items",
+        "credit_note_id IN (SELECT id FROM credit_notes WHERE preorder_id = ?)",
+    ),
+    ("refunds", "preorder_id = ?"),
+    ("whatsapp_messages", "preorder_id = ?"),
+    ("sms_messages", "preorder_id = ?"),
+];
+
+// Only orders that are finished with are archived
+const ARCHIVABLE_FILTER: &str =
+    "(deleted_at IS NOT NULL OR COALESCE(status, 'pending') IN ('fulfilled', 'cancelled'))";
+
+const ARCHIVED_COLUMNS: &str = "id, customer_name, customer_email, confirmation_code, invoice_number, \
+     status, total_amount, amount_paid, currency_code, event_id, created_at, deleted_at, archived_at";
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ArchivedOrder {
+    pub id: i64,
+    pub customer_name: String,
+    pub customer_email: String,
+    pub confirmation_code: Option<String>,
+    pub invoice_number: Option<String>,
+    pub status: String,
+    pub total_amount: f64,
+    pub amount_paid: f64,
+    pub currency_code: Option<String>,
+    pub event_id: Option<i64>,
+    pub created_at: Option<String>,
+    pub deleted_at: Option<String>,
+    pub archived_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ArchiveSettings {
+    // Orders older than this are archived at startup; None turns that off
+    pub archive_after_days: Option<i64>,
+}
+
+// Everything needed to put an archived order back, as column -> value maps
+// so it survives columns being added later
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OrderSnapshot {
+    order: Map<String, Value>,
+    children: BTreeMap<String, Vec<Map<String, Value>>>,
+    // Rows that only point at the order; unlinked on archive, relinked on restore
+    stock_movement_ids: Vec<i64>,
+    form_response_ids: Vec<String>,
+    // Quotes converted into the order; missing from older snapshots
+    #[serde(default)]
+    quote_ids: Vec<i64>,
+}
+
+// Helper: Current columns of a table
+pub async fn table_columns(
+    conn: &mut SqliteConnection,
+    table: &str,
+) -> Result<Vec<String>, AppError> {
+    sqlx::query_scalar::<_, String>("SELECT name FROM pragma_table_info(?) ORDER BY cid")
+        .bind(table)
+        .fetch_all(conn)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to read columns of {}: {}", table, e)))
+}
+
+pub fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+// Helper: Rows of `table` matching `filter` (one `?` bound to `id`), each as a
+// JSON object
+async fn select_rows(
+    conn: &mut SqliteConnection,
+    table: &str,
+    filter: &str,
+    id: i64,
+) -> Result<Vec<Map<String, Value>>, AppError> {
+    let columns = table_columns(&mut *conn, table).await?;
+    let fields = columns
+        .iter()
+        .map(|c| format!("'{}', {}", c.replace('\'', "''"), quote_identifier(c)))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let rows = sqlx::query_scalar::<_, String>(&format!(
+        "SELECT json_object({}) FROM {} WHERE {}",
+        fields,
+        quote_identifier(table),
+        filter
+    ))
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to read {}: {}", table, e))?;
+
+    rows.iter()
+        .map(|row| {
+            serde_json::from_str(row)
+                .map_err(|e| AppError::Internal(format!("Failed to read {}: {}", table, e)))
+        })
+        .collect()
+}
+
+// Helper: Insert snapshot rows, skipping columns the table no longer has
+pub async fn insert_rows(
+    conn: &mut SqliteConnection,
+    table: &str,
+    rows: &[Map<String, Value>],
+) -> Result<(), AppError> {
+    if rows.is_empty() {
+        return Ok(());
+    }
+    let existing = table_columns(&mut *conn, table).await?;
+
+    for row in rows {
+        let columns: Vec<&String> = row.keys().filter(|c| existing.contains(c)).collect();
+        if columns.is_empty() {
+            continue;
+        }
+
+        let mut query = QueryBuilder::<Sqlite>::new(format!(
+            "INSERT INTO {} ({}) VALUES (",
+            quote_identifier(table),
+            columns
+                .iter()
+                .map(|c| quote_identifier(c))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+        let mut values = query.separated(", ");
+        for column in columns {
+            match &row[column] {
+                Value::Null => values.push_bind(None::<String>),
+                Value::Bool(flag) => values.push_bind(*flag),
+                Value::Number(n) => match n.as_i64() {
+                    Some(int) => values.push_bind(int),
+                    None => values.push_bind(n.as_f64()),
+                },
+                Value::String(text) => values.push_bind(text.clone()),
+                other => values.push_bind(other.to_string()),
+            };
+        }
+        values.push_unseparated(")");
+
+        query
+            .build()
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to restore {}: {}", table, e))?;
+    }
+    Ok(())
+}
+
+// Move one order and its rows into the archive, inside the caller's transaction
+pub async fn archive_order(conn: &mut SqliteConnection, id: i64) -> Result<(), AppError> {
+    let order = fetch_order(&mut *conn, id).await?;
+
+    let mut snapshot = OrderSnapshot {
+        order: select_rows(&mut *conn, "preorders", "id = ?", id)
+            .await?
+            .pop()
+            .ok_or_else(|| AppError::NotFound(format!("Order {} not found", id)))?,
+        children: BTreeMap::new(),
+        stock_movement_ids: sqlx::query_scalar(
+            "SELECT id FROM stock_movements WHERE preorder_id = ?",
+        )
+        .bind(id)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to read stock movements: {}", e))?,
+        form_response_ids: sqlx::query_scalar(
+            "SELECT response_id FROM synced_responses WHERE preorder_id = ?",
+        )
+        .bind(id)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to read form responses: {}", e))?,
+        quote_ids: sqlx::query_scalar("SELECT id FROM quotes WHERE preorder_id = ?")
+            .bind(id)
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to read quotes: {}", e))?,
+    };
+    for (table, filter) in ORDER_CHILD_TABLES {
+        let rows = select_rows(&mut *conn, table, filter, id).await?;
+        snapshot.children.insert(table.to_string(), rows);
+    }
+    let snapshot_json = serde_json::to_string(&snapshot)
+        .map_err(|e| format!("Failed to serialize archived order: {}", e))?;
+
+    // Summary columns read by the order_history views; supplier costs and the
+    // form come from rows that go away with the order
+    sqlx::query(
+        "INSERT INTO archived_orders (id, customer_name, customer_email, confirmation_code, \
+         invoice_number, status, total_amount, amount_paid, amount_credited, supplier_cost, \
+         currency_code, event_id, form_id, created_at, invoiced_at, deleted_at, snapshot_json) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, \
+         (SELECT COALESCE(SUM(d.quantity * si.unit_cost), 0.0) FROM supplier_order_demand d \
+          JOIN supplier_order_items si ON si.id = d.supplier_order_item_id \
+          JOIN supplier_orders so ON so.id = si.supplier_order_id \
+          WHERE d.preorder_id = ? AND so.status != 'cancelled' AND si.unit_cost IS NOT NULL), \
+         ?, ?, (SELECT form_id FROM synced_responses WHERE preorder_id = ? LIMIT 1), ?, ?, ?, ?)",
+    )
+    .bind(order.id)
+    .bind(&order.customer_name)
+    .bind(&order.customer_email)
+    .bind(&order.confirmation_code)
+    .bind(&order.invoice_number)
+    .bind(&order.status)
+    .bind(order.total_amount)
+    .bind(order.amount_paid)
+    .bind(order.amount_credited)
+    .bind(id)
+    .bind(&order.currency_code)
+    .bind(order.event_id)
+    .bind(id)
+    .bind(&order.created_at)
+    .bind(&order.invoiced_at)
+    .bind(&order.deleted_at)
+    .bind(snapshot_json)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to archive order {}: {}", id, e))?;
+
+    for item in &order.items {
+        sqlx::query(
+            "INSERT INTO archived_order_items (archived_order_id, product_id, quantity, unit_price, \
+             amount_credited) VALUES (?, ?, ?, ?, \
+             (SELECT -COALESCE(SUM(quantity * unit_price), 0.0) FROM credit_note_items \
+              WHERE order_item_id = ?))",
+        )
+        .bind(id)
+        .bind(item.product_id)
+        .bind(item.quantity)
+        .bind(item.unit_price)
+        .bind(item.id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to archive order items: {}", e))?;
+    }
+
+    sqlx::query("UPDATE synced_responses SET preorder_id = NULL WHERE preorder_id = ?")
+        .bind(id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to unlink form responses: {}", e))?;
+    sqlx::query("UPDATE quotes SET preorder_id = NULL WHERE preorder_id = ?")
+        .bind(id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to unlink quotes: {}", e))?;
+
+    // Child tables cascade; stock movements keep their history with a NULL order
+    sqlx::query("DELETE FROM preorders WHERE id = ?")
+        .bind(id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to archive order {}: {}", id, e))?;
+
+    audit::record(&mut *conn, "order", id, "archive", Some(&order), None).await?;
+    Ok(())
+}
+
+// Helper: Archive every finished order created before `cutoff` (SQLite datetime)
+async fn archive_before(conn: &mut SqliteConnection, cutoff: &str) -> Result<Vec<i64>, AppError> {
+    let ids = sqlx::query_scalar::<_, i64>(&format!(
+        "SELECT id FROM preorders WHERE created_at < ? AND {} ORDER BY id",
+        ARCHIVABLE_FILTER
+    ))
+    .bind(cutoff)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to find orders to archive: {}", e))?;
+
+    for id in &ids {
+        archive_order(&mut *conn, *id).await?;
+    }
+    Ok(ids)
+}
+
+// Archive orders past the configured age. Runs once at startup.
+pub fn start_auto_archive(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<Database>().pool.clone();
+        match run_auto_archive(&pool).await {
+            Ok(0) => {}
+            Ok(count) => log_info!("Archived {} old orders", count),
+            Err(e) => log_warning!("Automatic archiving failed: {}", e),
+        }
+    });
+}
+
+// Helper: Apply the archive_after_days setting
+async fn run_auto_archive(pool: &SqlitePool) -> Result<usize, AppError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let settings = load_archive_settings(&mut tx).await?;
+    let Some(days) = settings.archive_after_days else {
+        return Ok(0);
+    };
+
+    let cutoff = sqlx::query_scalar::<_, String>("SELECT datetime('now', ?)")
+        .bind(format!("-{} days", days))
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to compute archive cutoff: {}", e))?;
+
+    let archived = archive_before(&mut tx, &cutoff).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save archive: {}", e))?;
+
+    Ok(archived.len())
+}
+
+// Helper: Saved archive settings
+async fn load_archive_settings(conn: &mut SqliteConnection) -> Result<ArchiveSettings, AppError> {
+    sqlx::query_as::<_, ArchiveSettings>(
+        "SELECT archive_after_days FROM archive_settings WHERE id = 1",
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to load archive settings: {}", e)))
+    .map(|settings| {
+        settings.unwrap_or(ArchiveSettings {
+            archive_after_days: None,
+        })
+    })
+}
+
+// Move fulfilled, cancelled and deleted orders created before `before` into
+// the archive. Returns the archived order IDs.
+#[tauri::command]
+pub async fn archive_orders_before(
+    db: State<'_, Database>,
+    before: String,
+) -> Result<Vec<i64>, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let cutoff = sqlx::query_scalar::<_, Option<String>>("SELECT datetime(?)")
+        .bind(before.trim())
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to parse date: {}", e))?
+        .ok_or_else(|| AppError::Validation(format!("Invalid date: {}", before)))?;
+
+    let archived = archive_before(&mut tx, &cutoff).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save archive: {}", e))?;
+
+    Ok(archived)
+}
+
+// Put an archived order back with its items, payments and history
+#[tauri::command]
+pub async fn restore_archived_order(
+    db: State<'_, Database>,
+    id: i64,
+) -> Result<PurchaseOrder, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let snapshot_json =
+        sqlx::query_scalar::<_, String>("SELECT snapshot_json FROM archived_orders WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to load archived order: {}", e))?
+            .ok_or_else(|| AppError::NotFound(format!("Archived order {} not found", id)))?;
+    let mut snapshot: OrderSnapshot = serde_json::from_str(&snapshot_json)
+        .map_err(|e| format!("Failed to read archived order: {}", e))?;
+
+    // The order's campaign may have been deleted while it was archived
+    if let Some(event_id) = snapshot.order.get("event_id").and_then(Value::as_i64) {
+        let event_exists = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM events WHERE id = ?")
+            .bind(event_id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to check event: {}", e))?
+            > 0;
+        if !event_exists {
+            snapshot.order.insert("event_id".to_string(), Value::Null);
+        }
+    }
+
+    // Reminders of rules deleted meanwhile would have cascaded away with the rule
+    if let Some(reminders) = snapshot.children.get_mut("payment_reminders") {
+        let rule_ids = sqlx::query_scalar::<_, i64>("SELECT id FROM reminder_rules")
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to load reminder rules: {}", e))?;
+        reminders.retain(|row| {
+            row.get("rule_id")
+                .and_then(Value::as_i64)
+                .is_some_and(|rule_id| rule_ids.contains(&rule_id))
+        });
+    }
+
+    insert_rows(&mut tx, "preorders", std::slice::from_ref(&snapshot.order)).await?;
+    for (table, _) in ORDER_CHILD_TABLES {
+        if let Some(rows) = snapshot.children.get(*table) {
+            insert_rows(&mut tx, table, rows).await?;
+        }
+    }
+
+    for movement_id in &snapshot.stock_movement_ids {
+        sqlx::query(
+            "UPDATE stock_movements SET preorder_id = ? WHERE id = ? AND preorder_id IS NULL",
+        )
+        .bind(id)
+        .bind(movement_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to relink stock movements: {}", e))?;
+    }
+    for response_id in &snapshot.form_response_ids {
+        sqlx::query(
+            "UPDATE synced_responses SET preorder_id = ? WHERE response_id = ? AND preorder_id IS NULL",
+        )
+        .bind(id)
+        .bind(response_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to relink form responses: {}", e))?;
+    }
+    for quote_id in &snapshot.quote_ids {
+        sqlx::query("UPDATE quotes SET preorder_id = ? WHERE id = ? AND preorder_id IS NULL")
+            .bind(id)
+            .bind(quote_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to relink quotes: {}", e))?;
+    }
+
+    // The insert trigger indexes every order; deleted ones stay out of search
+    sqlx::query(
+        "DELETE FROM search_index WHERE entity_type = 'order' AND entity_id = ? \
+         AND EXISTS (SELECT 1 FROM preorders WHERE id = ? AND deleted_at IS NOT NULL)",
+    )
+    .bind(id)
+    .bind(id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to update search index: {}", e))?;
+
+    sqlx::query("DELETE FROM archived_orders WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to remove archived order: {}", e))?;
+
+    let restored = load_order(&mut *tx, id).await?;
+    audit::record(&mut *tx, "order", id, "unarchive", None, Some(&restored)).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to restore archived order: {}", e))?;
+
+    Ok(restored)
+}
+
+// Archived orders, most recently created first
+#[tauri::command]
+pub async fn list_archived_orders(
+    db: State<'_, Database>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Vec<ArchivedOrder>, AppError> {
+    sqlx::query_as::<_, ArchivedOrder>(&format!(
+        "SELECT {} FROM archived_orders ORDER BY created_at DESC, id DESC LIMIT ? OFFSET ?",
+        ARCHIVED_COLUMNS
+    ))
+    .bind(limit.unwrap_or(100).clamp(1, 1000))
+    .bind(offset.unwrap_or(0).max(0))
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to list archived orders: {}", e)))
+}
+
+#[tauri::command]
+pub async fn get_archive_settings(db: State<'_, Database>) -> Result<ArchiveSettings, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    load_archive_settings(&mut conn).await
+}
+
+#[tauri::command]
+pub async fn set_archive_settings(
+    db: State<'_, Database>,
+    settings: ArchiveSettings,
+) -> Result<ArchiveSettings, AppError> {
+    if matches!(settings.archive_after_days, Some(days) if days < 1) {
+        return Err(AppError::Validation(
+            "Archive age must be at least one day".to_string(),
+        ));
+    }
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_archive_settings(&mut tx).await?;
+
+    sqlx::query("INSERT OR REPLACE INTO archive_settings (id, archive_after_days) VALUES (1, ?)")
+        .bind(settings.archive_after_days)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to save archive settings: {}", e))?;
+
+    let after = load_archive_settings(&mut tx).await?;
+    audit::record(
+        &mut *tx,
+        "archive_settings",
+        1,
+        "update",
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save archive settings: {}", e))?;
+
+    Ok(after)
+}
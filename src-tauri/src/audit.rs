@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, Sqlite, SqliteConnection};
+use tauri::State;
+
+use crate::db::Database;
+use crate::domain_events::{DomainEvent, Subscriber};
+use crate::error::AppError;
+use crate::orders::fetch_order;
+
+// Falls back to this when nobody is signed in to Google
+const LOCAL_ACTOR: &str = "local";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub entity_type: String,
+    pub entity_id: i64,
+    pub action: String,
+    pub actor: String,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub created_at: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct AuditRow {
+    id: i64,
+    entity_type: String,
+    entity_id: i64,
+    action: String,
+    actor: String,
+    before_json: Option<String>,
+    after_json: Option<String>,
+    created_at: Option<String>,
+}
+
+impl From<AuditRow> for AuditEntry {
+    fn from(row: AuditRow) -> Self {
+        let parse = |json: Option<String>| json.and_then(|j| serde_json::from_str(&j).ok());
+        AuditEntry {
+            id: row.id,
+            entity_type: row.entity_type,
+            entity_id: row.entity_id,
+            action: row.action,
+            actor: row.actor,
+            before: parse(row.before_json),
+            after: parse(row.after_json),
+            created_at: row.created_at,
+        }
+    }
+}
+
+// Write an audit entry with JSON snapshots of the entity before and after the
+// change. Accepts a pool or a transaction, so callers already inside a
+// transaction can log atomically with the change itself.
+pub async fn record<'e, E, T>(
+    executor: E,
+    entity_type: &str,
+    entity_id: i64,
+    action: &str,
+    before: Option<&T>,
+    after: Option<&T>,
+) -> Result<(), AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+    T: Serialize,
+{
+    let to_json = |value: Option<&T>| -> Result<Option<String>, AppError> {
+        value
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| AppError::Internal(format!("Failed to serialize audit snapshot: {}", e)))
+    };
+
+    sqlx::query(
+        "INSERT INTO audit_log (entity_type, entity_id, action, actor, before_json, after_json) \
+         VALUES (?, ?, ?, COALESCE((SELECT user_email FROM google_auth WHERE id = 1), ?), ?, ?)",
+    )
+    .bind(entity_type)
+    .bind(entity_id)
+    .bind(action)
+    .bind(LOCAL_ACTOR)
+    .bind(to_json(before)?)
+    .bind(to_json(after)?)
+    .execute(executor)
+    .await
+    .map_err(|e| format!("Failed to write audit log: {}", e))?;
+
+    Ok(())
+}
+
+// Audit entries for order creation, status changes and payments. Other
+// changes are audited right where they're made.
+pub struct AuditLog;
+
+impl Subscriber for AuditLog {
+    async fn handle(
+        &self,
+        conn: &mut SqliteConnection,
+        event: &DomainEvent<'_>,
+    ) -> Result<(), AppError> {
+        match *event {
+            DomainEvent::OrderCreated { order_id } => {
+                let created = fetch_order(&mut *conn, order_id).await?;
+                record(conn, "order", order_id, "create", None, Some(&created)).await
+            }
+            DomainEvent::OrderStatusChanged(change) => {
+                record(
+                    conn,
+                    "order",
+                    change.order_id,
+                    change.event.as_str(),
+                    Some(&serde_json::json!({ "status": change.from })),
+                    Some(&serde_json::json!({ "status": change.to })),
+                )
+                .await
+            }
+            DomainEvent::PaymentRecorded(payment) => {
+                record(conn, "payment", payment.id, "create", None, Some(payment)).await
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// History of changes to an entity, oldest first
+#[tauri::command]
+pub async fn get_audit_log(
+    db: State<'_, Database>,
+    entity_id: i64,
+    entity_type: Option<String>,
+) -> Result<Vec<AuditEntry>, AppError> {
+    let rows = sqlx::query_as::<_, AuditRow>(
+        "SELECT id, entity_type, entity_id, action, actor, before_json, after_json, created_at \
+         FROM audit_log WHERE entity_id = ? AND (? IS NULL OR entity_type = ?) ORDER BY id",
+    )
+    .bind(entity_id)
+    .bind(&entity_type)
+    .bind(&entity_type)
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| format!("Failed to load audit log: {}", e))?;
+
+    Ok(rows.into_iter().map(AuditEntry::from).collect())
+}
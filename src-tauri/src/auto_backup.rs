@@ -0,0 +1,530 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
+use uuid::Uuid;
+
+use crate::audit;
+use crate::backup::{self, LocalBackupInfo};
+use crate::crypto::MIN_PASSPHRASE_LEN;
+use crate::db::Database;
+use crate::encryption::{conceal_opt, reveal_opt};
+use crate::error::AppError;
+use crate::http::{self, SendWithRetry};
+use crate::log_warning;
+
+// How often the scheduler looks for a backup coming due
+const CHECK_INTERVAL_SECS: u64 = 15 * 60;
+
+// Automatic copies are named potracker-auto-<UTC timestamp>, locally and on
+// Drive, so rotation never touches backups the user made by hand
+const BACKUP_PREFIX: &str = "potracker-auto-";
+const BACKUP_EXTENSION: &str = ".db";
+const DRIVE_BACKUP_EXTENSION: &str = ".potbak";
+
+// Default folder, inside the active profile's data directory
+const BACKUP_DIR: &str = "backups";
+
+const MAX_KEEP_COUNT: i64 = 365;
+const MAX_INTERVAL_HOURS: i64 = 24 * 30;
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AutoBackupSettings {
+    pub enabled: bool,
+    pub folder: Option<String>,
+    pub keep_count: i64,
+    pub interval_hours: i64,
+    pub drive_enabled: bool,
+    pub drive_passphrase: Option<String>,
+    // Outcome of the last run, ignored when saving
+    #[serde(default)]
+    pub last_backup_at: Option<String>,
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+// One automatic backup in the backup folder
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AutoBackupFile {
+    pub name: String,
+    pub path: String,
+    pub size_bytes: u64,
+    pub created_at: Option<String>,
+}
+
+// Helper: Automatic backup settings, off if the row is missing
+async fn load_settings(conn: &mut SqliteConnection) -> Result<AutoBackupSettings, AppError> {
+    let settings = sqlx::query_as::<_, AutoBackupSettings>(
+        "SELECT enabled, folder, keep_count, interval_hours, drive_enabled, drive_passphrase, \
+         last_backup_at, last_error FROM auto_backup_settings WHERE id = 1",
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| format!("Failed to load backup settings: {}", e))?
+    .unwrap_or(AutoBackupSettings {
+        enabled: false,
+        folder: None,
+        keep_count: 7,
+        interval_hours: 24,
+        drive_enabled: false,
+        drive_passphrase: None,
+        last_backup_at: None,
+        last_error: None,
+    });
+    Ok(AutoBackupSettings {
+        drive_passphrase: reveal_opt(settings.drive_passphrase)?,
+        ..settings
+    })
+}
+
+// Helper: Folder automatic backups go to
+fn backup_folder(app: &AppHandle, settings: &AutoBackupSettings) -> Result<PathBuf, AppError> {
+    match &settings.folder {
+        Some(folder) => Ok(PathBuf::from(folder)),
+        None => Ok(crate::profiles::active_data_dir(app)?.join(BACKUP_DIR)),
+    }
+}
+
+// Helper: Whether a file name is one of our automatic backups
+fn is_backup_name(name: &str) -> bool {
+    name.starts_with(BACKUP_PREFIX) && name.ends_with(BACKUP_EXTENSION)
+}
+
+// Helper: Automatic backups in `folder`, newest first. The timestamp in the
+// name orders them, so copies touched later still sort where they belong.
+fn list_backup_files(folder: &Path) -> Result<Vec<AutoBackupFile>, AppError> {
+    if !folder.exists() {
+        return Ok(Vec::new());
+    }
+    let entries =
+        std::fs::read_dir(folder).map_err(|e| format!("Failed to read backup folder: {}", e))?;
+
+    let mut files = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read backup folder: {}", e))?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !is_backup_name(&name) {
+            continue;
+        }
+        let meta = entry
+            .metadata()
+            .map_err(|e| format!("Failed to read backup file: {}", e))?;
+        if !meta.is_file() {
+            continue;
+        }
+        files.push(AutoBackupFile {
+            path: entry.path().to_string_lossy().to_string(),
+            size_bytes: meta.len(),
+            created_at: meta
+                .modified()
+                .ok()
+                .map(|time| DateTime::<Utc>::from(time).to_rfc3339()),
+            name,
+        });
+    }
+    files.sort_by(|a, b| b.name.cmp(&a.name));
+    Ok(files)
+}
+
+// Helper: Snapshot the database into `folder` and check the copy opens and
+// passes an integrity check. A copy that fails is deleted, so it can never
+// count towards the copies kept.
+async fn write_backup(app: &AppHandle, folder: &Path) -> Result<AutoBackupFile, AppError> {
+    std::fs::create_dir_all(folder)
+        .map_err(|e| format!("Failed to create backup folder: {}", e))?;
+
+    let name = format!(
+        "{}{}{}",
+        BACKUP_PREFIX,
+        Utc::now().format("%Y%m%d-%H%M%S"),
+        BACKUP_EXTENSION
+    );
+    let dest = folder.join(&name);
+    let partial = folder.join(format!("{}.partial-{}", name, Uuid::new_v4()));
+
+    let result = async {
+        backup::vacuum_into(app, &partial).await?;
+        backup::verify_database_file(&partial).await?;
+        std::fs::rename(&partial, &dest)
+            .map_err(|e| AppError::Internal(format!("Failed to write backup file: {}", e)))
+    }
+    .await;
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e);
+    }
+
+    let size_bytes = std::fs::metadata(&dest)
+        .map(|meta| meta.len())
+        .map_err(|e| format!("Failed to read backup file: {}", e))?;
+    Ok(AutoBackupFile {
+        name,
+        path: dest.to_string_lossy().to_string(),
+        size_bytes,
+        created_at: Some(Utc::now().to_rfc3339()),
+    })
+}
+
+// Remove half-written copies an interrupted run left in the backup folder.
+// Ones younger than `min_age` may belong to a run still in progress.
+// Returns how many were removed.
+pub async fn remove_partial_backups(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    min_age: std::time::Duration,
+) -> Result<usize, AppError> {
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let folder = backup_folder(app, &load_settings(&mut conn).await?)?;
+    if !folder.exists() {
+        return Ok(0);
+    }
+    let entries =
+        std::fs::read_dir(&folder).map_err(|e| format!("Failed to read backup folder: {}", e))?;
+
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let stale = entry
+            .metadata()
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age >= min_age);
+        if name.starts_with(BACKUP_PREFIX)
+            && name.contains(".partial-")
+            && stale
+            && std::fs::remove_file(entry.path()).is_ok()
+        {
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+// Helper: Delete the oldest automatic backups beyond `keep_count`. Only
+// called after a new copy has been verified.
+fn rotate_backups(folder: &Path, keep_count: i64) -> Result<(), AppError> {
+    let keep = keep_count.max(1) as usize;
+    for old in list_backup_files(folder)?.into_iter().skip(keep) {
+        std::fs::remove_file(&old.path)
+            .map_err(|e| format!("Failed to remove old backup {}: {}", old.name, e))?;
+    }
+    Ok(())
+}
+
+// Helper: Upload an encrypted copy to the Drive appDataFolder, then delete
+// the oldest automatic copies there beyond `keep_count`
+async fn copy_to_drive(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    settings: &AutoBackupSettings,
+) -> Result<(), AppError> {
+    let passphrase = settings
+        .drive_passphrase
+        .as_deref()
+        .ok_or("No passphrase set for Drive backups")?;
+    let access_token = crate::drive::stored_access_token(pool).await?;
+
+    // Settings live in the database, so the snapshot already carries them
+    let data = backup::build_encrypted_backup(app, passphrase, None).await?;
+    let name = format!(
+        "{}{}{}",
+        BACKUP_PREFIX,
+        Utc::now().format("%Y%m%d-%H%M%S"),
+        DRIVE_BACKUP_EXTENSION
+    );
+    backup::upload_drive_backup(app, &access_token, name, data).await?;
+
+    // Listed newest first
+    let old = backup::list_drive_backups(access_token.clone())
+        .await?
+        .into_iter()
+        .filter(|file| file.name.starts_with(BACKUP_PREFIX))
+        .skip(settings.keep_count.max(1) as usize);
+
+    let client = http::client();
+    for file in old {
+        let response = client
+            .delete(format!(
+                "https://www.googleapis.com/drive/v3/files/{}",
+                file.id
+            ))
+            .bearer_auth(&access_token)
+            .send_with_retry()
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to remove old Drive backup: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(http::google_error(response, "Drive API delete error").await);
+        }
+    }
+    Ok(())
+}
+
+// Helper: Remember how the last run went. A local copy that was written
+// counts as a backup even if the Drive copy failed, so a Drive outage doesn't
+// make the scheduler write a new local copy every check.
+async fn record_outcome(pool: &SqlitePool, saved: bool, error: Option<&str>) {
+    let result = sqlx::query(
+        "UPDATE auto_backup_settings SET \
+         last_backup_at = CASE WHEN ? THEN CURRENT_TIMESTAMP ELSE last_backup_at END, \
+         last_error = ? WHERE id = 1",
+    )
+    .bind(saved)
+    .bind(error)
+    .execute(pool)
+    .await;
+    if let Err(e) = result {
+        log_warning!("Failed to record backup outcome: {}", e);
+    }
+}
+
+// Helper: Write, verify and rotate a backup, then copy it to Drive if enabled
+async fn run_backup(app: &AppHandle, pool: &SqlitePool) -> Result<AutoBackupFile, AppError> {
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let settings = load_settings(&mut conn).await?;
+    drop(conn);
+
+    let folder = backup_folder(app, &settings)?;
+    let saved = match write_backup(app, &folder).await {
+        Ok(file) => file,
+        Err(e) => {
+            record_outcome(pool, false, Some(e.message())).await;
+            return Err(e);
+        }
+    };
+
+    let mut result = rotate_backups(&folder, settings.keep_count);
+    if result.is_ok() && settings.drive_enabled {
+        result = copy_to_drive(app, pool, &settings)
+            .await
+            .map_err(|e| AppError::Validation(format!("Drive copy failed: {}", e)));
+    }
+
+    match result {
+        Ok(()) => {
+            record_outcome(pool, true, None).await;
+            Ok(saved)
+        }
+        Err(e) => {
+            record_outcome(pool, true, Some(e.message())).await;
+            Err(AppError::Validation(format!(
+                "Backup saved to {}, but {}",
+                saved.path, e
+            )))
+        }
+    }
+}
+
+// Helper: Whether automatic backups are on and the last one is older than
+// the interval
+async fn backup_due(pool: &SqlitePool) -> Result<bool, AppError> {
+    Ok(sqlx::query_scalar::<_, bool>(
+        "SELECT enabled AND (last_backup_at IS NULL \
+         OR last_backup_at <= datetime('now', printf('-%d hours', interval_hours))) \
+         FROM auto_backup_settings WHERE id = 1",
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load backup settings: {}", e))?
+    .unwrap_or(false))
+}
+
+// Start the background loop that takes a backup whenever one comes due
+pub fn start_auto_backups(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<Database>().pool.clone();
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(CHECK_INTERVAL_SECS));
+        loop {
+            ticker.tick().await;
+            match backup_due(&pool).await {
+                Ok(true) => {
+                    if let Err(e) = run_backup(&app, &pool).await {
+                        log_warning!("Automatic backup failed: {}", e);
+                    }
+                }
+                Ok(false) => {}
+                Err(e) => log_warning!("Backup scheduler failed: {}", e),
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_auto_backup_settings(
+    db: State<'_, Database>,
+) -> Result<AutoBackupSettings, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    load_settings(&mut conn).await
+}
+
+#[tauri::command]
+pub async fn set_auto_backup_settings(
+    db: State<'_, Database>,
+    settings: AutoBackupSettings,
+) -> Result<AutoBackupSettings, AppError> {
+    let folder = settings
+        .folder
+        .as_deref()
+        .map(str::trim)
+        .filter(|folder| !folder.is_empty())
+        .map(str::to_string);
+    if let Some(folder) = &folder {
+        if !Path::new(folder).is_absolute() {
+            return Err(AppError::Validation(
+                "Backup folder must be an absolute path".to_string(),
+            ));
+        }
+    }
+    if !(1..=MAX_KEEP_COUNT).contains(&settings.keep_count) {
+        return Err(AppError::Validation(format!(
+            "Copies to keep must be between 1 and {}",
+            MAX_KEEP_COUNT
+        )));
+    }
+    if !(1..=MAX_INTERVAL_HOURS).contains(&settings.interval_hours) {
+        return Err(AppError::Validation(format!(
+            "Backup interval must be between 1 and {} hours",
+            MAX_INTERVAL_HOURS
+        )));
+    }
+    let drive_passphrase = settings.drive_passphrase.filter(|p| !p.is_empty());
+    if settings.drive_enabled
+        && drive_passphrase
+            .as_deref()
+            .is_none_or(|p| p.chars().count() < MIN_PASSPHRASE_LEN)
+    {
+        return Err(AppError::Validation(format!(
+            "Drive backups need a passphrase of at least {} characters",
+            MIN_PASSPHRASE_LEN
+        )));
+    }
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_settings(&mut tx).await?;
+    // Upsert so the last run's outcome survives a save
+    sqlx::query(
+        "INSERT INTO auto_backup_settings \
+         (id, enabled, folder, keep_count, interval_hours, drive_enabled, drive_passphrase, updated_at) \
+         VALUES (1, ?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP) \
+         ON CONFLICT(id) DO UPDATE SET enabled = excluded.enabled, folder = excluded.folder, \
+         keep_count = excluded.keep_count, interval_hours = excluded.interval_hours, \
+         drive_enabled = excluded.drive_enabled, drive_passphrase = excluded.drive_passphrase, \
+         updated_at = excluded.updated_at",
+    )
+    .bind(settings.enabled)
+    .bind(&folder)
+    .bind(settings.keep_count)
+    .bind(settings.interval_hours)
+    .bind(settings.drive_enabled)
+    .bind(conceal_opt(drive_passphrase.as_deref())?)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save backup settings: {}", e))?;
+    let after = load_settings(&mut tx).await?;
+
+    // The passphrase stays out of the audit log
+    let view = |s: &AutoBackupSettings| {
+        serde_json::json!({
+            "enabled": s.enabled,
+            "folder": s.folder,
+            "keep_count": s.keep_count,
+            "interval_hours": s.interval_hours,
+            "drive_enabled": s.drive_enabled,
+        })
+    };
+    audit::record(
+        &mut *tx,
+        "auto_backup_settings",
+        1,
+        "update",
+        Some(&view(&before)),
+        Some(&view(&after)),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save backup settings: {}", e))?;
+    Ok(after)
+}
+
+// Take an automatic backup right away, whether or not one is due
+#[tauri::command]
+pub async fn run_backup_now(
+    app: AppHandle,
+    db: State<'_, Database>,
+) -> Result<AutoBackupFile, AppError> {
+    run_backup(&app, &db.pool).await
+}
+
+// Automatic backups in the backup folder, newest first
+#[tauri::command]
+pub async fn list_backups(
+    app: AppHandle,
+    db: State<'_, Database>,
+) -> Result<Vec<AutoBackupFile>, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let settings = load_settings(&mut conn).await?;
+    list_backup_files(&backup_folder(&app, &settings)?)
+}
+
+// Verify an automatic backup and stage it to replace the current database on
+// the next launch
+#[tauri::command]
+pub async fn restore_backup(
+    app: AppHandle,
+    db: State<'_, Database>,
+    name: String,
+) -> Result<LocalBackupInfo, AppError> {
+    // Only names from list_backups, never a path into another folder
+    if !is_backup_name(&name) || Path::new(&name).file_name() != Some(std::ffi::OsStr::new(&name)) {
+        return Err(AppError::Validation(format!(
+            "Not an automatic backup: {}",
+            name
+        )));
+    }
+
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let settings = load_settings(&mut conn).await?;
+    drop(conn);
+
+    let path = backup_folder(&app, &settings)?.join(&name);
+    if !path.is_file() {
+        return Err(AppError::NotFound(format!("Backup not found: {}", name)));
+    }
+    let schema_version = backup::verify_database_file(&path).await?;
+
+    let database =
+        std::fs::read(&path).map_err(|e| format!("Failed to read backup file: {}", e))?;
+    backup::stage_restore(&app, &database)?;
+
+    Ok(LocalBackupInfo {
+        path: path.to_string_lossy().to_string(),
+        size_bytes: database.len() as u64,
+        schema_version,
+    })
+}
@@ -0,0 +1,296 @@
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, Sqlite, SqlitePool};
+use std::time::Duration;
+use tauri::State;
+
+use crate::audit;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::log_warning;
+
+// Largest batch an outbox sender takes from its queue in one pass
+pub const SEND_BATCH_SIZE: i64 = 20;
+
+// Allowed range of each interval, in seconds
+const SCHEDULER_INTERVAL_RANGE: (i64, i64) = (10, 60 * 60);
+const REMINDER_INTERVAL_RANGE: (i64, i64) = (60, 24 * 60 * 60);
+const SENDER_INTERVAL_RANGE: (i64, i64) = (5, 60 * 60);
+const NOTIFIER_INTERVAL_RANGE: (i64, i64) = (1, 5 * 60);
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AutomationSettings {
+    pub scheduler_interval_secs: i64,
+    pub reminder_interval_secs: i64,
+    pub sender_interval_secs: i64,
+    pub notifier_interval_secs: i64,
+    pub quiet_hours_enabled: bool,
+    // Local time, HH:MM. Quiet hours may wrap past midnight.
+    pub quiet_start: String,
+    pub quiet_end: String,
+    // Automated messages per local day, unlimited when None
+    pub max_daily_sends: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutomationStatus {
+    pub in_quiet_hours: bool,
+    pub sent_today: i64,
+    // How many more automated messages may go out now, None if unlimited
+    pub sends_left: Option<i64>,
+}
+
+// Background loops whose pace the settings control
+#[derive(Debug, Clone, Copy)]
+pub enum Poller {
+    // Recurring orders
+    Scheduler,
+    // Payment reminders coming due
+    Reminders,
+    // Outboxes: webhooks, Telegram, WhatsApp, SMS and chat alerts
+    Sender,
+    // Desktop notifications and the form response watcher
+    Notifier,
+}
+
+impl Default for AutomationSettings {
+    fn default() -> Self {
+        AutomationSettings {
+            scheduler_interval_secs: 60,
+            reminder_interval_secs: 300,
+            sender_interval_secs: 30,
+            notifier_interval_secs: 5,
+            quiet_hours_enabled: false,
+            quiet_start: "22:00".to_string(),
+            quiet_end: "07:00".to_string(),
+            max_daily_sends: None,
+        }
+    }
+}
+
+impl AutomationSettings {
+    fn interval(&self, poller: Poller) -> Duration {
+        let secs = match poller {
+            Poller::Scheduler => self.scheduler_interval_secs,
+            Poller::Reminders => self.reminder_interval_secs,
+            Poller::Sender => self.sender_interval_secs,
+            Poller::Notifier => self.notifier_interval_secs,
+        };
+        Duration::from_secs(secs.max(1) as u64)
+    }
+
+    // Whether `now` falls in quiet hours
+    fn is_quiet_at(&self, now: NaiveTime) -> bool {
+        if !self.quiet_hours_enabled {
+            return false;
+        }
+        let (Ok(start), Ok(end)) = (parse_time(&self.quiet_start), parse_time(&self.quiet_end))
+        else {
+            return false;
+        };
+        if start <= end {
+            start <= now && now < end
+        } else {
+            now >= start || now < end
+        }
+    }
+}
+
+// Helper: Parse a local HH:MM time
+fn parse_time(value: &str) -> Result<NaiveTime, AppError> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M")
+        .map_err(|_| AppError::Validation(format!("Time must be HH:MM: {}", value)))
+}
+
+// Helper: Today's date in local time, the key sends are counted under
+fn today() -> String {
+    chrono::Local::now().format("%Y-%m-%d").to_string()
+}
+
+// Helper: Automation settings, the defaults if the row is missing
+pub async fn load_settings<'e, E>(executor: E) -> Result<AutomationSettings, AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    Ok(sqlx::query_as::<_, AutomationSettings>(
+        "SELECT scheduler_interval_secs, reminder_interval_secs, sender_interval_secs, \
+         notifier_interval_secs, quiet_hours_enabled, quiet_start, quiet_end, max_daily_sends \
+         FROM automation_settings WHERE id = 1",
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| format!("Failed to load automation settings: {}", e))?
+    .unwrap_or_default())
+}
+
+// Sleep until the loop's next pass. The interval is read each time, so a
+// change applies without a restart.
+pub async fn wait_for_next_poll(pool: &SqlitePool, poller: Poller) {
+    let settings = load_settings(pool).await.unwrap_or_else(|e| {
+        log_warning!("{}", e);
+        AutomationSettings::default()
+    });
+    tokio::time::sleep(settings.interval(poller)).await;
+}
+
+// Whether it's quiet hours now, when nothing should reach the user
+pub async fn in_quiet_hours(pool: &SqlitePool) -> Result<bool, AppError> {
+    Ok(load_settings(pool)
+        .await?
+        .is_quiet_at(chrono::Local::now().time()))
+}
+
+// Helper: Automated messages sent today
+async fn sent_today(pool: &SqlitePool) -> Result<i64, AppError> {
+    Ok(
+        sqlx::query_scalar::<_, i64>("SELECT sent FROM automation_sends WHERE day = ?")
+            .bind(today())
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| format!("Failed to load send count: {}", e))?
+            .unwrap_or(0),
+    )
+}
+
+// How many automated messages may go out now: none in quiet hours or once
+// today's limit is reached, None when there's no limit
+pub async fn send_allowance(pool: &SqlitePool) -> Result<Option<i64>, AppError> {
+    let settings = load_settings(pool).await?;
+    if settings.is_quiet_at(chrono::Local::now().time()) {
+        return Ok(Some(0));
+    }
+    match settings.max_daily_sends {
+        Some(max) => Ok(Some((max - sent_today(pool).await?).max(0))),
+        None => Ok(None),
+    }
+}
+
+// Batch size for an outbox sender's pass, zero when nothing may go out
+pub async fn send_batch_size(pool: &SqlitePool) -> Result<i64, AppError> {
+    Ok(send_allowance(pool)
+        .await?
+        .map_or(SEND_BATCH_SIZE, |left| left.min(SEND_BATCH_SIZE)))
+}
+
+// Count automated messages toward today's limit
+pub async fn record_sends(pool: &SqlitePool, count: usize) -> Result<(), AppError> {
+    if count == 0 {
+        return Ok(());
+    }
+    sqlx::query(
+        "INSERT INTO automation_sends (day, sent) VALUES (?, ?) \
+         ON CONFLICT(day) DO UPDATE SET sent = sent + excluded.sent",
+    )
+    .bind(today())
+    .bind(count as i64)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to record sends: {}", e))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_automation_settings(
+    db: State<'_, Database>,
+) -> Result<AutomationSettings, AppError> {
+    load_settings(&db.pool).await
+}
+
+#[tauri::command]
+pub async fn set_automation_settings(
+    db: State<'_, Database>,
+    settings: AutomationSettings,
+) -> Result<AutomationSettings, AppError> {
+    let ranges = [
+        (
+            "Scheduler interval",
+            settings.scheduler_interval_secs,
+            SCHEDULER_INTERVAL_RANGE,
+        ),
+        (
+            "Reminder interval",
+            settings.reminder_interval_secs,
+            REMINDER_INTERVAL_RANGE,
+        ),
+        (
+            "Sender interval",
+            settings.sender_interval_secs,
+            SENDER_INTERVAL_RANGE,
+        ),
+        (
+            "Notifier interval",
+            settings.notifier_interval_secs,
+            NOTIFIER_INTERVAL_RANGE,
+        ),
+    ];
+    for (name, value, (min, max)) in ranges {
+        if !(min..=max).contains(&value) {
+            return Err(AppError::Validation(format!(
+                "{} must be between {} and {} seconds",
+                name, min, max
+            )));
+        }
+    }
+    let quiet_start = parse_time(&settings.quiet_start)?;
+    let quiet_end = parse_time(&settings.quiet_end)?;
+    if settings.quiet_hours_enabled && quiet_start == quiet_end {
+        return Err(AppError::Validation(
+            "Quiet hours must start and end at different times".to_string(),
+        ));
+    }
+    if settings.max_daily_sends.is_some_and(|max| max < 1) {
+        return Err(AppError::Validation(
+            "Daily send limit must be at least 1".to_string(),
+        ));
+    }
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_settings(&mut *tx).await?;
+    sqlx::query(
+        "INSERT OR REPLACE INTO automation_settings \
+         (id, scheduler_interval_secs, reminder_interval_secs, sender_interval_secs, \
+         notifier_interval_secs, quiet_hours_enabled, quiet_start, quiet_end, max_daily_sends, \
+         updated_at) VALUES (1, ?, ?, ?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP)",
+    )
+    .bind(settings.scheduler_interval_secs)
+    .bind(settings.reminder_interval_secs)
+    .bind(settings.sender_interval_secs)
+    .bind(settings.notifier_interval_secs)
+    .bind(settings.quiet_hours_enabled)
+    .bind(quiet_start.format("%H:%M").to_string())
+    .bind(quiet_end.format("%H:%M").to_string())
+    .bind(settings.max_daily_sends)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save automation settings: {}", e))?;
+    let after = load_settings(&mut *tx).await?;
+
+    audit::record(
+        &mut *tx,
+        "automation_settings",
+        1,
+        "update",
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save automation settings: {}", e))?;
+    Ok(after)
+}
+
+#[tauri::command]
+pub async fn get_automation_status(db: State<'_, Database>) -> Result<AutomationStatus, AppError> {
+    Ok(AutomationStatus {
+        in_quiet_hours: in_quiet_hours(&db.pool).await?,
+        sent_today: sent_today(&db.pool).await?,
+        sends_left: send_allowance(&db.pool).await?,
+    })
+}
@@ -0,0 +1,454 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{ConnectOptions, Connection};
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use uuid::Uuid;
+
+use crate::crypto;
+use crate::db::{database_path, DATABASE_FILE};
+use crate::error::AppError;
+use crate::http::{self, SendWithRetry};
+use crate::migrations;
+
+// A restored database is staged here and swapped in on the next launch,
+// since the frontend keeps the live file open
+const PENDING_RESTORE_FILE: &str = "potracker.db.restore";
+const PREVIOUS_DATABASE_FILE: &str = "potracker.db.before-restore";
+
+const BACKUP_FORMAT_VERSION: u32 = 1;
+const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
+
+// Snapshots are staged in the temp dir under this prefix
+const SNAPSHOT_PREFIX: &str = "potracker-snapshot-";
+
+// Contents of a backup before encryption
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupPayload {
+    pub format_version: u32,
+    pub created_at: String,
+    pub app_version: String,
+    pub database_base64: String,
+    pub settings: Option<serde_json::Value>,
+}
+
+// Result of a local backup or a validated restore file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LocalBackupInfo {
+    pub path: String,
+    pub size_bytes: u64,
+    pub schema_version: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DriveBackupInfo {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "createdTime")]
+    pub created_time: Option<String>,
+    // Drive reports sizes as strings
+    pub size: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DriveBackupList {
+    pub files: Vec<DriveBackupInfo>,
+}
+
+// Helper: Write a consistent copy of the live database to `target` with
+// VACUUM INTO, which reads through SQLite's locking like the backup API does
+// instead of copying a file that may be mid-write
+pub async fn vacuum_into(app: &AppHandle, target: &Path) -> Result<(), AppError> {
+    let db_path = database_path(app)?;
+    if !db_path.exists() {
+        return Err(AppError::Validation(
+            "Database has not been created yet".to_string(),
+        ));
+    }
+
+    let mut conn = SqliteConnectOptions::new()
+        .filename(&db_path)
+        .read_only(true)
+        .connect()
+        .await
+        .map_err(|e| format!("Failed to open database: {}", e))?;
+
+    let result = sqlx::query("VACUUM INTO ?")
+        .bind(target.to_string_lossy().to_string())
+        .execute(&mut conn)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to snapshot database: {}", e)));
+
+    let _ = conn.close().await;
+    result.map(|_| ())
+}
+
+// Take a consistent snapshot of the live database
+pub async fn snapshot_database(app: &AppHandle) -> Result<Vec<u8>, AppError> {
+    let snapshot_path =
+        std::env::temp_dir().join(format!("{}{}.db", SNAPSHOT_PREFIX, Uuid::new_v4()));
+
+    vacuum_into(app, &snapshot_path).await?;
+
+    let bytes = std::fs::read(&snapshot_path)
+        .map_err(|e| AppError::Internal(format!("Failed to read snapshot: {}", e)));
+    let _ = std::fs::remove_file(&snapshot_path);
+    bytes
+}
+
+// Remove snapshots a crash left in the temp dir. Ones younger than `min_age`
+// may still be in use. Returns how many were removed.
+pub fn remove_stale_snapshots(min_age: std::time::Duration) -> usize {
+    let Ok(entries) = std::fs::read_dir(std::env::temp_dir()) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with(SNAPSHOT_PREFIX)
+        })
+        .filter(|entry| {
+            entry
+                .metadata()
+                .and_then(|meta| meta.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .is_some_and(|age| age >= min_age)
+        })
+        .filter(|entry| std::fs::remove_file(entry.path()).is_ok())
+        .count()
+}
+
+// Helper: Check a database file is intact and one this build can open.
+// Returns its schema version.
+pub async fn verify_database_file(path: &Path) -> Result<i64, AppError> {
+    let header = std::fs::read(path)
+        .map(|bytes| bytes.starts_with(SQLITE_HEADER))
+        .map_err(|e| format!("Failed to read database file: {}", e))?;
+    if !header {
+        return Err(AppError::Validation(
+            "File is not a SQLite database".to_string(),
+        ));
+    }
+
+    let mut conn = SqliteConnectOptions::new()
+        .filename(path)
+        .read_only(true)
+        .connect()
+        .await
+        .map_err(|e| format!("Failed to open database file: {}", e))?;
+
+    let result = async {
+        let problems = sqlx::query_scalar::<_, String>("PRAGMA integrity_check")
+            .fetch_all(&mut conn)
+            .await
+            .map_err(|e| format!("Failed to check database integrity: {}", e))?;
+        if problems != ["ok"] {
+            return Err(AppError::Validation(format!(
+                "Database failed integrity check: {}",
+                problems.join("; ")
+            )));
+        }
+
+        let has_orders = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'preorders'",
+        )
+        .fetch_one(&mut conn)
+        .await
+        .map_err(|e| format!("Failed to read database schema: {}", e))?;
+        if has_orders == 0 {
+            return Err(AppError::Validation(
+                "File is not a POTracker database".to_string(),
+            ));
+        }
+
+        // Databases that predate the migration runner report version 0
+        let version =
+            sqlx::query_scalar::<_, Option<i64>>("SELECT MAX(version) FROM schema_migrations")
+                .fetch_one(&mut conn)
+                .await
+                .unwrap_or(None)
+                .unwrap_or(0);
+        if version > migrations::latest_version() {
+            return Err(AppError::Validation(format!(
+                "Database schema version {} is newer than this app supports",
+                version
+            )));
+        }
+        Ok(version)
+    }
+    .await;
+
+    let _ = conn.close().await;
+    result
+}
+
+// Stage a database image to replace the live one on next startup
+pub fn stage_restore(app: &AppHandle, database: &[u8]) -> Result<(), AppError> {
+    if !database.starts_with(SQLITE_HEADER) {
+        return Err(AppError::Validation(
+            "Backup does not contain a valid SQLite database".to_string(),
+        ));
+    }
+
+    let db_path = database_path(app)?;
+    let pending = db_path.with_file_name(PENDING_RESTORE_FILE);
+    std::fs::write(&pending, database)
+        .map_err(|e| AppError::Internal(format!("Failed to stage restore: {}", e)))
+}
+
+// Swap in a staged restore, keeping the replaced database alongside it.
+// Runs during setup, before the frontend opens the database.
+pub fn apply_pending_restore(app: &AppHandle) -> Result<bool, AppError> {
+    let db_path = database_path(app)?;
+    let pending = db_path.with_file_name(PENDING_RESTORE_FILE);
+    if !pending.exists() {
+        return Ok(false);
+    }
+
+    if db_path.exists() {
+        std::fs::rename(&db_path, db_path.with_file_name(PREVIOUS_DATABASE_FILE))
+            .map_err(|e| format!("Failed to move current database aside: {}", e))?;
+    }
+    for suffix in ["-wal", "-shm"] {
+        let _ =
+            std::fs::remove_file(db_path.with_file_name(format!("{}{}", DATABASE_FILE, suffix)));
+    }
+
+    std::fs::rename(&pending, &db_path)
+        .map_err(|e| format!("Failed to apply restored database: {}", e))?;
+    Ok(true)
+}
+
+// Helper: Build an encrypted backup of the database and caller-supplied settings
+pub async fn build_encrypted_backup(
+    app: &AppHandle,
+    passphrase: &str,
+    settings: Option<serde_json::Value>,
+) -> Result<Vec<u8>, AppError> {
+    let database = snapshot_database(app).await?;
+
+    let payload = BackupPayload {
+        format_version: BACKUP_FORMAT_VERSION,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        app_version: app.package_info().version.to_string(),
+        database_base64: STANDARD.encode(&database),
+        settings,
+    };
+
+    let json =
+        serde_json::to_vec(&payload).map_err(|e| format!("Failed to serialize backup: {}", e))?;
+    crypto::encrypt_with_passphrase(passphrase, &json)
+}
+
+// Helper: Decrypt and validate a backup produced by build_encrypted_backup
+fn open_encrypted_backup(
+    passphrase: &str,
+    data: &[u8],
+) -> Result<(BackupPayload, Vec<u8>), AppError> {
+    let json = crypto::decrypt_with_passphrase(passphrase, data)?;
+    let payload: BackupPayload =
+        serde_json::from_slice(&json).map_err(|e| format!("Failed to parse backup: {}", e))?;
+
+    if payload.format_version > BACKUP_FORMAT_VERSION {
+        return Err(AppError::Validation(format!(
+            "Backup format version {} is newer than this app supports",
+            payload.format_version
+        )));
+    }
+
+    let database = STANDARD
+        .decode(&payload.database_base64)
+        .map_err(|e| format!("Failed to decode database: {}", e))?;
+    Ok((payload, database))
+}
+
+// Upload an encrypted backup to the hidden Drive appDataFolder
+#[tauri::command]
+pub async fn backup_to_drive(
+    app: AppHandle,
+    access_token: String,
+    passphrase: String,
+    settings: Option<serde_json::Value>,
+) -> Result<DriveBackupInfo, AppError> {
+    let data = build_encrypted_backup(&app, &passphrase, settings).await?;
+
+    let name = format!(
+        "potracker-backup-{}.potbak",
+        chrono::Utc::now().format("%Y%m%d-%H%M%S")
+    );
+    upload_drive_backup(&app, &access_token, name, data).await
+}
+
+// Helper: Store an encrypted backup under `name` in the Drive appDataFolder
+pub async fn upload_drive_backup(
+    app: &AppHandle,
+    access_token: &str,
+    name: String,
+    data: Vec<u8>,
+) -> Result<DriveBackupInfo, AppError> {
+    let client = http::client();
+    crate::drive::ensure_quota_available(&client, access_token, data.len() as u64).await?;
+
+    let size = data.len();
+    let file_id = crate::drive::resumable_upload(
+        app,
+        &client,
+        access_token,
+        &name,
+        crate::drive::UploadSource::Bytes(data),
+        "application/octet-stream",
+        Some("appDataFolder"),
+    )
+    .await?;
+
+    Ok(DriveBackupInfo {
+        id: file_id,
+        name,
+        created_time: Some(chrono::Utc::now().to_rfc3339()),
+        size: Some(size.to_string()),
+    })
+}
+
+// List backups stored in the Drive appDataFolder, newest first
+#[tauri::command]
+pub async fn list_drive_backups(access_token: String) -> Result<Vec<DriveBackupInfo>, AppError> {
+    let client = http::client();
+
+    let response = client
+        .get("https://www.googleapis.com/drive/v3/files")
+        .query(&[
+            ("spaces", "appDataFolder"),
+            ("fields", "files(id,name,createdTime,size)"),
+            ("orderBy", "createdTime desc"),
+            ("pageSize", "100"),
+        ])
+        .bearer_auth(&access_token)
+        .send_with_retry()
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to list backups: {}", e)))?;
+
+    if !response.status().is_success() {
+        return Err(http::google_error(response, "Drive API error").await);
+    }
+
+    let list: DriveBackupList = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse backup list: {}", e))?;
+
+    Ok(list.files)
+}
+
+// Download and decrypt a Drive backup, staging its database for the next launch.
+// Returns the settings stored with the backup so the frontend can re-apply them.
+#[tauri::command]
+pub async fn restore_from_drive(
+    app: AppHandle,
+    access_token: String,
+    file_id: String,
+    passphrase: String,
+) -> Result<Option<serde_json::Value>, AppError> {
+    let client = http::client();
+
+    let response = client
+        .get(format!(
+            "https://www.googleapis.com/drive/v3/files/{}?alt=media",
+            file_id
+        ))
+        .bearer_auth(&access_token)
+        .send_with_retry()
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to download backup: {}", e)))?;
+
+    if !response.status().is_success() {
+        return Err(http::google_error(response, "Drive API read error").await);
+    }
+
+    let data = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to read backup: {}", e))?;
+
+    let (payload, database) = open_encrypted_backup(&passphrase, &data)?;
+    stage_restore(&app, &database)?;
+
+    Ok(payload.settings)
+}
+
+// Write a verified snapshot of the database to a file the user picked, e.g.
+// to carry it to another machine
+#[tauri::command]
+pub async fn backup_database(
+    app: AppHandle,
+    dest_path: String,
+) -> Result<LocalBackupInfo, AppError> {
+    let dest = PathBuf::from(&dest_path);
+    if dest == database_path(&app)? {
+        return Err(AppError::Validation(
+            "Choose a backup location other than the live database".to_string(),
+        ));
+    }
+
+    // VACUUM INTO refuses to overwrite, and a failed backup shouldn't clobber
+    // an older good one, so build it alongside and move it into place
+    let partial = dest.with_file_name(format!(
+        "{}.partial-{}",
+        dest.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| DATABASE_FILE.to_string()),
+        Uuid::new_v4()
+    ));
+
+    let result = async {
+        vacuum_into(&app, &partial).await?;
+        let schema_version = verify_database_file(&partial).await?;
+        std::fs::rename(&partial, &dest)
+            .map_err(|e| format!("Failed to write backup file: {}", e))?;
+        Ok(schema_version)
+    }
+    .await;
+
+    let schema_version = match result {
+        Ok(version) => version,
+        Err(e) => {
+            let _ = std::fs::remove_file(&partial);
+            return Err(e);
+        }
+    };
+
+    let size_bytes = std::fs::metadata(&dest)
+        .map(|meta| meta.len())
+        .map_err(|e| format!("Failed to read backup file: {}", e))?;
+
+    Ok(LocalBackupInfo {
+        path: dest_path,
+        size_bytes,
+        schema_version,
+    })
+}
+
+// Verify a database file and stage it to replace the current database on the
+// next launch. Older schemas are migrated forward when the app starts.
+#[tauri::command]
+pub async fn restore_database(
+    app: AppHandle,
+    src_path: String,
+) -> Result<LocalBackupInfo, AppError> {
+    let src = PathBuf::from(&src_path);
+    let schema_version = verify_database_file(&src).await?;
+
+    let database =
+        std::fs::read(&src).map_err(|e| format!("Failed to read database file: {}", e))?;
+    stage_restore(&app, &database)?;
+
+    Ok(LocalBackupInfo {
+        path: src_path,
+        size_bytes: database.len() as u64,
+        schema_version,
+    })
+}
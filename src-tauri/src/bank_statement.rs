@@ -0,0 +1,355 @@
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::State;
+
+use crate::currency::default_currency;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::legacy_import::{parse_amount, parse_date};
+use crate::payment_ocr::{load_open_orders, rank_orders, PaymentProofMatch};
+use crate::payments::{balance_epsilon, PaymentInput};
+use crate::product_import::{cell, normalize_header, read_spreadsheet_rows};
+
+// Bank exports put the account and period above the table
+const MAX_HEADER_ROW: usize = 30;
+// A proposal needs more than a name: a code, an invoice number or the exact balance
+const MIN_PROPOSAL_SCORE: i64 = 50;
+// Payment references are shown in lists; descriptions can run long
+const MAX_REFERENCE_CHARS: usize = 120;
+const STATEMENT_PAYMENT_METHOD: &str = "bank_transfer";
+
+// Normalized header aliases for BCA, Mandiri, BNI, BRI and generic exports
+const DATE_HEADERS: &[&str] = &[
+    "date",
+    "transactiondate",
+    "postingdate",
+    "postdate",
+    "tanggal",
+    "tanggaltransaksi",
+    "tgl",
+    "tgltransaksi",
+];
+const DESCRIPTION_HEADERS: &[&str] = &[
+    "description",
+    "transactiondescription",
+    "details",
+    "remarks",
+    "narrative",
+    "keterangan",
+    "uraian",
+    "uraiantransaksi",
+    "berita",
+];
+const AMOUNT_HEADERS: &[&str] = &["amount", "jumlah", "nominal", "mutasi", "transactionamount"];
+const CREDIT_HEADERS: &[&str] = &[
+    "credit",
+    "kredit",
+    "cr",
+    "creditamount",
+    "deposit",
+    "masuk",
+    "uangmasuk",
+];
+const DEBIT_HEADERS: &[&str] = &[
+    "debit",
+    "debet",
+    "db",
+    "dr",
+    "debitamount",
+    "withdrawal",
+    "keluar",
+    "uangkeluar",
+];
+// Separate credit/debit marker next to a single amount column
+const DIRECTION_HEADERS: &[&str] = &["crdb", "dbcr", "drcr", "type", "jenis", "tipe"];
+const REFERENCE_HEADERS: &[&str] = &[
+    "reference",
+    "referenceno",
+    "ref",
+    "refno",
+    "reffno",
+    "noreferensi",
+    "referensi",
+    "transactionid",
+];
+// Markers for money coming in and going out, lowercased
+const CREDIT_MARKERS: &[&str] = &["cr", "c", "k", "kredit", "credit"];
+const DEBIT_MARKERS: &[&str] = &["db", "dr", "d", "debet", "debit"];
+
+// One incoming transfer on the statement and what it's most likely for.
+// Nothing is recorded; proposals go through record_payment once confirmed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatementCredit {
+    // Row of the export, counting from 1 (blank lines in a CSV aren't counted)
+    pub row: usize,
+    // "YYYY-MM-DD HH:MM:SS"
+    pub paid_at: String,
+    pub description: String,
+    pub amount: f64,
+    // The bank's reference, or the description when it has none
+    pub reference: String,
+    // A payment already recorded with this reference, if any
+    pub duplicate_payment_id: Option<i64>,
+    // Best match first
+    pub suggestions: Vec<PaymentProofMatch>,
+    // Payment to record when there's one clear match
+    pub proposal: Option<PaymentInput>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BankStatementReconciliation {
+    pub credits: Vec<StatementCredit>,
+    pub proposed: usize,
+    // Outgoing transactions, which aren't customer payments
+    pub debits_skipped: usize,
+    // Rows in the table without a usable date or amount (balances, totals)
+    pub unreadable_rows: Vec<usize>,
+}
+
+struct StatementColumns {
+    date: usize,
+    description: usize,
+    amount: Option<usize>,
+    credit: Option<usize>,
+    debit: Option<usize>,
+    direction: Option<usize>,
+    reference: Option<usize>,
+}
+
+// Helper: Position of the first header matching an alias
+fn column(headers: &[String], aliases: &[&str]) -> Option<usize> {
+    aliases
+        .iter()
+        .find_map(|alias| headers.iter().position(|h| h == alias))
+}
+
+// Helper: The header row and its columns: the first row naming a date, a
+// description and an amount (or credit) column
+fn find_header(rows: &[Vec<String>]) -> Option<(usize, StatementColumns)> {
+    rows.iter()
+        .take(MAX_HEADER_ROW)
+        .enumerate()
+        .find_map(|(i, row)| {
+            let headers: Vec<String> = row.iter().map(|h| normalize_header(h)).collect();
+            let columns = StatementColumns {
+                date: column(&headers, DATE_HEADERS)?,
+                description: column(&headers, DESCRIPTION_HEADERS)?,
+                amount: column(&headers, AMOUNT_HEADERS),
+                credit: column(&headers, CREDIT_HEADERS),
+                debit: column(&headers, DEBIT_HEADERS),
+                direction: column(&headers, DIRECTION_HEADERS),
+                reference: column(&headers, REFERENCE_HEADERS),
+            };
+            (columns.amount.is_some() || columns.credit.is_some()).then_some((i, columns))
+        })
+}
+
+// Helper: Rows of a CSV export, whichever of comma, semicolon or tab it's
+// separated by (Indonesian-locale Excel writes semicolons)
+fn read_statement_csv(path: &Path) -> Result<Vec<Vec<String>>, AppError> {
+    let text = std::fs::read(path).map_err(|e| format!("Failed to open CSV file: {}", e))?;
+    let text = String::from_utf8_lossy(&text);
+    let sample: Vec<&str> = text.lines().take(MAX_HEADER_ROW).collect();
+    let delimiter = [b',', b';', b'\t']
+        .into_iter()
+        .max_by_key(|d| {
+            sample
+                .iter()
+                .map(|line| line.matches(*d as char).count())
+                .sum::<usize>()
+        })
+        .unwrap_or(b',');
+
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter)
+        .from_reader(text.trim_start_matches('\u{feff}').as_bytes());
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| format!("Failed to read CSV file: {}", e))?;
+        rows.push(record.iter().map(|field| field.to_string()).collect());
+    }
+    Ok(rows)
+}
+
+// Helper: Whether a marker like "CR" or "DB" says the money came in
+fn direction(marker: &str) -> Option<bool> {
+    let marker = marker.trim().to_lowercase();
+    if CREDIT_MARKERS.contains(&marker.as_str()) {
+        Some(true)
+    } else if DEBIT_MARKERS.contains(&marker.as_str()) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+// Helper: Signed amount of a row (positive for money in), None when it has
+// none. Amounts under `epsilon` count as an empty column.
+fn row_amount(row: &[String], columns: &StatementColumns, epsilon: f64) -> Option<f64> {
+    let credit = cell(row, columns.credit).and_then(|text| parse_amount(&text));
+    let debit = cell(row, columns.debit).and_then(|text| parse_amount(&text));
+    match (credit, debit) {
+        (Some(credit), _) if credit.abs() > epsilon => return Some(credit.abs()),
+        (_, Some(debit)) if debit.abs() > epsilon => return Some(-debit.abs()),
+        _ if columns.amount.is_none() => return None,
+        _ => {}
+    }
+
+    // One amount column: "150,000.00 CR", a separate CR/DB column, or a sign
+    let text = cell(row, columns.amount)?;
+    let amount = parse_amount(&text)?;
+    let suffix = text
+        .trim_end()
+        .rsplit(|c: char| c.is_whitespace() || c.is_ascii_digit())
+        .next()
+        .unwrap_or_default();
+    // BCA leaves the CR/DB column next to the amount unnamed
+    let marker_column = columns.direction.or(columns.amount.map(|i| i + 1));
+    let incoming = direction(suffix)
+        .or_else(|| cell(row, marker_column).and_then(|marker| direction(&marker)));
+    Some(match incoming {
+        Some(true) => amount.abs(),
+        Some(false) => -amount.abs(),
+        None => amount,
+    })
+}
+
+// Helper: Transaction date. BCA prints "'12/10" with no year (and "PEND" for
+// transfers not yet booked); the year is the latest that isn't in the future.
+fn row_date(text: &str, today: NaiveDate) -> Option<NaiveDateTime> {
+    let text = text.trim().trim_start_matches('\'');
+    if let Some(date) = parse_date(text) {
+        return Some(date);
+    }
+    let (day, month) = text.split_once('/')?;
+    let (day, month) = (day.parse::<u32>().ok()?, month.parse::<u32>().ok()?);
+    let date = NaiveDate::from_ymd_opt(today.year(), month, day)
+        .filter(|date| *date <= today)
+        .or_else(|| NaiveDate::from_ymd_opt(today.year() - 1, month, day))?;
+    date.and_hms_opt(0, 0, 0)
+}
+
+// Read a bank statement export (CSV, or a workbook) and match each incoming
+// transfer against unpaid orders by confirmation code, invoice number, amount
+// and the sender's name in the description. Where one order clearly fits, a
+// payment is proposed; the user confirms proposals, which are then recorded
+// with record_payment. Transfers already recorded (same reference) are flagged
+// instead, so the same statement can be reconciled again safely.
+#[tauri::command]
+pub async fn reconcile_bank_statement(
+    db: State<'_, Database>,
+    csv_path: String,
+) -> Result<BankStatementReconciliation, AppError> {
+    let path = Path::new(&csv_path);
+    let is_csv = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| ext == "csv" || ext == "txt");
+    let rows = if is_csv {
+        read_statement_csv(path)?
+    } else {
+        read_spreadsheet_rows(path)?
+    };
+    let (header_row, columns) = find_header(&rows).ok_or(
+        "No transaction table found; the statement needs date, description and amount columns",
+    )?;
+
+    let orders = load_open_orders(&db.pool).await?;
+    // The account is kept in the app's default currency
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let epsilon = balance_epsilon(&default_currency(&mut conn).await);
+    drop(conn);
+    let today = chrono::Local::now().date_naive();
+    // What's left of each order's balance after earlier proposals
+    let mut remaining: HashMap<i64, f64> = orders
+        .iter()
+        .map(|order| (order.id, order.balance_due))
+        .collect();
+
+    let mut credits = Vec::new();
+    let mut debits_skipped = 0;
+    let mut unreadable_rows = Vec::new();
+    for (i, row) in rows.iter().enumerate().skip(header_row + 1) {
+        if row.iter().all(|value| value.trim().is_empty()) {
+            continue;
+        }
+        let paid_at = cell(row, Some(columns.date)).and_then(|text| row_date(&text, today));
+        let (Some(paid_at), Some(amount)) = (paid_at, row_amount(row, &columns, epsilon)) else {
+            unreadable_rows.push(i + 1);
+            continue;
+        };
+        if amount <= epsilon {
+            debits_skipped += 1;
+            continue;
+        }
+
+        let description = cell(row, Some(columns.description)).unwrap_or_default();
+        let reference: String = cell(row, columns.reference)
+            .unwrap_or_else(|| description.split_whitespace().collect::<Vec<_>>().join(" "))
+            .chars()
+            .take(MAX_REFERENCE_CHARS)
+            .collect();
+        let duplicate_payment_id = sqlx::query_scalar::<_, i64>(
+            "SELECT id FROM payments WHERE reference = ? ORDER BY id LIMIT 1",
+        )
+        .bind(&reference)
+        .fetch_optional(&db.pool)
+        .await
+        .map_err(|e| format!("Failed to check payment references: {}", e))?;
+
+        let suggestions = rank_orders(&orders, &description, Some(amount), "description");
+        let best = suggestions.first().filter(|best| {
+            best.score >= MIN_PROPOSAL_SCORE
+                && suggestions
+                    .get(1)
+                    .is_none_or(|next| next.score < best.score)
+        });
+        let paid_at = paid_at.format("%Y-%m-%d %H:%M:%S").to_string();
+        let proposal = match best {
+            Some(best) if duplicate_payment_id.is_none() => match remaining.get_mut(&best.order_id)
+            {
+                Some(left) if amount <= *left + epsilon => {
+                    *left -= amount;
+                    Some(PaymentInput {
+                        preorder_id: best.order_id,
+                        amount,
+                        method: STATEMENT_PAYMENT_METHOD.to_string(),
+                        paid_at: Some(paid_at.clone()),
+                        reference: Some(reference.clone()),
+                        proof_file_id: None,
+                        proof_file_name: None,
+                        notes: Some(format!("Bank statement: {}", description)),
+                    })
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+
+        credits.push(StatementCredit {
+            row: i + 1,
+            paid_at,
+            description,
+            amount,
+            reference,
+            duplicate_payment_id,
+            suggestions,
+            proposal,
+        });
+    }
+
+    Ok(BankStatementReconciliation {
+        proposed: credits.iter().filter(|c| c.proposal.is_some()).count(),
+        credits,
+        debits_skipped,
+        unreadable_rows,
+    })
+}
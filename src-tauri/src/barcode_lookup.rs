@@ -0,0 +1,240 @@
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::SqliteConnection;
+use tauri::State;
+
+use crate::barcodes::normalize_barcode;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::http;
+use crate::redact::redact;
+
+const OPEN_FOOD_FACTS_URL: &str = "https://world.openfoodfacts.org/api/v2/product";
+const UPCITEMDB_URL: &str = "https://api.upcitemdb.com/prod/trial/lookup";
+// Open Food Facts asks every client to identify itself
+const USER_AGENT: &str = concat!("POTracker/", env!("CARGO_PKG_VERSION"));
+
+// Product data rarely changes once published
+const FOUND_CACHE_DAYS: i64 = 30;
+// Unknown codes get another try the next day, in case they've been added
+const MISS_CACHE_HOURS: i64 = 24;
+
+// Product details published online for a barcode, to pre-fill a new product
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct OnlineProduct {
+    pub barcode: String,
+    pub found: bool,
+    // 'openfoodfacts' or 'upcitemdb'
+    pub source: Option<String>,
+    pub name: Option<String>,
+    pub brand: Option<String>,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    pub fetched_at: Option<String>,
+}
+
+// What one source knows about a barcode
+struct Found {
+    source: &'static str,
+    name: Option<String>,
+    brand: Option<String>,
+    description: Option<String>,
+    image_url: Option<String>,
+}
+
+// Helper: Non-blank string field of a JSON object
+fn field(value: &Value, key: &str) -> Option<String> {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .map(str::to_string)
+}
+
+async fn open_food_facts(client: &Client, barcode: &str) -> Result<Option<Found>, AppError> {
+    let response = client
+        .get(format!("{}/{}.json", OPEN_FOOD_FACTS_URL, barcode))
+        .query(&[(
+            "fields",
+            "product_name,generic_name,brands,image_front_url,image_url",
+        )])
+        .send()
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to reach Open Food Facts: {}", e)))?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        let status = response.status().as_u16();
+        let error_text = redact(&response.text().await.unwrap_or_default());
+        return Err(AppError::from_status(
+            status,
+            format!("Open Food Facts API error: {}", error_text),
+        ));
+    }
+    let body = response
+        .json::<Value>()
+        .await
+        .map_err(|e| format!("Failed to parse Open Food Facts response: {}", e))?;
+
+    let Some(product) = body.get("product").filter(|_| body["status"] == 1) else {
+        return Ok(None);
+    };
+    let name = field(product, "product_name");
+    if name.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(Found {
+        source: "openfoodfacts",
+        name,
+        // Comma-separated, owner first
+        brand: field(product, "brands")
+            .and_then(|brands| brands.split(',').next().map(|b| b.trim().to_string())),
+        description: field(product, "generic_name"),
+        image_url: field(product, "image_front_url").or_else(|| field(product, "image_url")),
+    }))
+}
+
+// The free trial endpoint allows about 100 lookups a day per IP
+async fn upcitemdb(client: &Client, barcode: &str) -> Result<Option<Found>, AppError> {
+    let response = client
+        .get(UPCITEMDB_URL)
+        .query(&[("upc", barcode)])
+        .send()
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to reach UPCitemdb: {}", e)))?;
+    match response.status() {
+        StatusCode::NOT_FOUND => return Ok(None),
+        StatusCode::TOO_MANY_REQUESTS => {
+            return Err(AppError::RateLimited {
+                message: "UPCitemdb daily lookup limit reached; try again tomorrow".to_string(),
+                status: Some(429),
+            })
+        }
+        status if !status.is_success() => {
+            let error_text = redact(&response.text().await.unwrap_or_default());
+            return Err(AppError::from_status(
+                status.as_u16(),
+                format!("UPCitemdb API error: {}", error_text),
+            ));
+        }
+        _ => {}
+    }
+    let body = response
+        .json::<Value>()
+        .await
+        .map_err(|e| format!("Failed to parse UPCitemdb response: {}", e))?;
+
+    let Some(item) = body["items"].as_array().and_then(|items| items.first()) else {
+        return Ok(None);
+    };
+    Ok(Some(Found {
+        source: "upcitemdb",
+        name: field(item, "title"),
+        brand: field(item, "brand"),
+        description: field(item, "description"),
+        image_url: item["images"]
+            .as_array()
+            .and_then(|images| images.iter().find_map(Value::as_str))
+            .map(str::to_string),
+    }))
+}
+
+// Helper: Cached lookup that's still fresh, if any
+async fn cached_lookup(
+    conn: &mut SqliteConnection,
+    barcode: &str,
+) -> Result<Option<OnlineProduct>, AppError> {
+    sqlx::query_as::<_, OnlineProduct>(
+        "SELECT barcode, found, source, name, brand, description, image_url, fetched_at \
+         FROM barcode_lookups WHERE barcode = ? \
+         AND fetched_at >= datetime('now', CASE WHEN found THEN ? ELSE ? END)",
+    )
+    .bind(barcode)
+    .bind(format!("-{} days", FOUND_CACHE_DAYS))
+    .bind(format!("-{} hours", MISS_CACHE_HOURS))
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to check barcode cache: {}", e)))
+}
+
+// Look a barcode that isn't in the catalog up on Open Food Facts, then
+// UPCitemdb, so a new product can start from its published name and photo.
+// Answers (including "not found") are cached; `force` skips the cache.
+#[tauri::command]
+pub async fn lookup_barcode_online(
+    db: State<'_, Database>,
+    code: String,
+    force: Option<bool>,
+) -> Result<OnlineProduct, AppError> {
+    let barcode = normalize_barcode(&code)?;
+    if !barcode.chars().all(|c| c.is_ascii_digit()) {
+        return Err(AppError::Validation(
+            "Only EAN/UPC barcodes can be looked up online".to_string(),
+        ));
+    }
+
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+
+    if !force.unwrap_or(false) {
+        if let Some(cached) = cached_lookup(&mut conn, &barcode).await? {
+            return Ok(cached);
+        }
+    }
+
+    let client = http::client_builder()
+        .user_agent(USER_AGENT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let mut errors = Vec::new();
+    let mut found = open_food_facts(&client, &barcode)
+        .await
+        .unwrap_or_else(|e| {
+            errors.push(e);
+            None
+        });
+    if found.is_none() {
+        // Only spend the UPCitemdb quota on codes Open Food Facts doesn't know
+        found = upcitemdb(&client, &barcode).await.unwrap_or_else(|e| {
+            errors.push(e);
+            None
+        });
+    }
+    // A miss is only trustworthy (and worth caching) if every source answered
+    if found.is_none() && !errors.is_empty() {
+        if errors.len() == 1 {
+            return Err(errors.remove(0));
+        }
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        return Err(AppError::Provider {
+            message: messages.join("; "),
+            status: None,
+        });
+    }
+
+    sqlx::query(
+        "INSERT OR REPLACE INTO barcode_lookups \
+         (barcode, found, source, name, brand, description, image_url, fetched_at) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP)",
+    )
+    .bind(&barcode)
+    .bind(found.is_some())
+    .bind(found.as_ref().map(|f| f.source))
+    .bind(found.as_ref().and_then(|f| f.name.clone()))
+    .bind(found.as_ref().and_then(|f| f.brand.clone()))
+    .bind(found.as_ref().and_then(|f| f.description.clone()))
+    .bind(found.as_ref().and_then(|f| f.image_url.clone()))
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to cache barcode lookup: {}", e))?;
+
+    cached_lookup(&mut conn, &barcode)
+        .await?
+        .ok_or_else(|| AppError::Validation("Barcode lookup cache is empty".to_string()))
+}
@@ -0,0 +1,358 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, Sqlite, SqliteConnection};
+use tauri::State;
+
+use crate::audit;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::models::Product;
+use crate::products::load_product;
+
+// Code128 can carry any printable ASCII; longer than this won't fit a label
+const MAX_CODE128_LENGTH: usize = 48;
+
+// GS1 prefixes 20-29 are reserved for in-store numbering, so generated EANs
+// never clash with a manufacturer's code
+const IN_STORE_PREFIXES: std::ops::RangeInclusive<u32> = 20..=29;
+
+// Bar/space widths of Code128 symbols 0-106 (103-105 are the start codes,
+// 106 the stop code with its final bar)
+const CODE128_PATTERNS: [&str; 107] = [
+    "212222", "222122", "222221", "121223", "121322", "131222", "122213", "122312", "132212",
+    "221213", "221312", "231212", "112232", "122132", "122231", "113222", "123122", "123221",
+    "223211", "221132", "221231", "213212", "223112", "312131", "311222", "321122", "321221",
+    "312212", "322112", "322211", "212123", "212321", "232121", "111323", "131123", "131321",
+    "112313", "132113", "132311", "211313", "231113", "231311", "112133", "112331", "132131",
+    "113123", "113321", "133121", "313121", "211331", "231131", "213113", "213311", "213131",
+    "311123", "311321", "331121", "312113", "312311", "332111", "314111", "221411", "431111",
+    "111224", "111422", "121124", "121421", "141122", "141221", "112214", "112412", "122114",
+    "122411", "142112", "142211", "241211", "221114", "413111", "241112", "134111", "111242",
+    "121142", "121241", "114212", "124112", "124211", "411212", "421112", "421211", "212141",
+    "214121", "412121", "111143", "111341", "131141", "114113", "114311", "411113", "411311",
+    "113141", "114131", "311141", "411131", "211412", "211214", "211232", "2331112",
+];
+const CODE128_START_B: usize = 104;
+const CODE128_START_C: usize = 105;
+const CODE128_STOP: usize = 106;
+
+// EAN-13 left-half digit patterns (odd parity); the right half and the
+// even-parity left patterns are derived from these
+const EAN_L_PATTERNS: [&str; 10] = [
+    "0001101", "0011001", "0010011", "0111101", "0100011", "0110001", "0101111", "0111011",
+    "0110111", "0001011",
+];
+// Which left-half digits use even parity, picked by the first digit
+const EAN_PARITY: [&str; 10] = [
+    "LLLLLL", "LLGLGG", "LLGGLG", "LLGGGL", "LGLLGG", "LGGLLG", "LGGGLL", "LGLGLG", "LGLGGL",
+    "LGGLGL",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BarcodeFormat {
+    Ean13,
+    Code128,
+}
+
+// Helper: GS1 check digit for the digits before it (EAN-8, UPC-A, EAN-13, GTIN-14)
+pub fn gtin_check_digit(body: &str) -> u32 {
+    let sum: u32 = body
+        .chars()
+        .rev()
+        .filter_map(|c| c.to_digit(10))
+        .enumerate()
+        .map(|(i, digit)| if i % 2 == 0 { digit * 3 } else { digit })
+        .sum();
+    (10 - sum % 10) % 10
+}
+
+// Scanned or typed barcode in the form it's stored: EAN/UPC digits with
+// spaces and dashes dropped, check digit verified and UPC-A widened to EAN-13
+// so both scans of the same product match. Anything else is kept as-is as
+// Code128 text.
+pub fn normalize_barcode(code: &str) -> Result<String, AppError> {
+    let code = code.trim();
+    if code.is_empty() {
+        return Err(AppError::Validation(
+            "Barcode must not be empty".to_string(),
+        ));
+    }
+
+    let digits: String = code
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect();
+    if digits.chars().all(|c| c.is_ascii_digit()) && matches!(digits.len(), 8 | 12 | 13 | 14) {
+        let (body, check) = digits.split_at(digits.len() - 1);
+        if check.parse::<u32>().ok() != Some(gtin_check_digit(body)) {
+            return Err(AppError::Validation(format!(
+                "Invalid barcode check digit: {}",
+                code
+            )));
+        }
+        return Ok(if digits.len() == 12 {
+            format!("0{}", digits)
+        } else {
+            digits
+        });
+    }
+
+    if code.len() > MAX_CODE128_LENGTH || !code.chars().all(|c| c == ' ' || c.is_ascii_graphic()) {
+        return Err(AppError::Validation(format!(
+            "Unsupported barcode: {}",
+            code
+        )));
+    }
+    Ok(code.to_string())
+}
+
+// Symbology a stored barcode is printed in: EAN-13 for 13 digits, Code128
+// for everything else
+pub fn barcode_format(barcode: &str) -> BarcodeFormat {
+    if barcode.len() == 13 && barcode.chars().all(|c| c.is_ascii_digit()) {
+        BarcodeFormat::Ean13
+    } else {
+        BarcodeFormat::Code128
+    }
+}
+
+// Modules (true = bar) of a barcode, without quiet zones
+pub fn encode(barcode: &str, format: BarcodeFormat) -> Result<Vec<bool>, AppError> {
+    match format {
+        BarcodeFormat::Ean13 => encode_ean13(barcode),
+        BarcodeFormat::Code128 => encode_code128(barcode),
+    }
+}
+
+// Helper: Append a module pattern written as "0101..."
+fn push_bits(modules: &mut Vec<bool>, bits: &str) {
+    modules.extend(bits.chars().map(|c| c == '1'));
+}
+
+fn encode_ean13(barcode: &str) -> Result<Vec<bool>, AppError> {
+    let digits: Vec<usize> = barcode
+        .chars()
+        .map(|c| c.to_digit(10).map(|d| d as usize))
+        .collect::<Option<_>>()
+        .filter(|digits: &Vec<usize>| digits.len() == 13)
+        .ok_or_else(|| AppError::Validation(format!("Not an EAN-13 barcode: {}", barcode)))?;
+
+    let right = |digit: usize| -> String {
+        EAN_L_PATTERNS[digit]
+            .chars()
+            .map(|c| if c == '1' { '0' } else { '1' })
+            .collect()
+    };
+    let mut modules = Vec::with_capacity(95);
+    push_bits(&mut modules, "101");
+    for (i, parity) in EAN_PARITY[digits[0]].chars().enumerate() {
+        let digit = digits[i + 1];
+        match parity {
+            'L' => push_bits(&mut modules, EAN_L_PATTERNS[digit]),
+            _ => push_bits(
+                &mut modules,
+                &right(digit).chars().rev().collect::<String>(),
+            ),
+        }
+    }
+    push_bits(&mut modules, "01010");
+    for &digit in &digits[7..] {
+        push_bits(&mut modules, &right(digit));
+    }
+    push_bits(&mut modules, "101");
+    Ok(modules)
+}
+
+// Code set C (digit pairs) for even-length numbers, code set B otherwise
+fn encode_code128(text: &str) -> Result<Vec<bool>, AppError> {
+    let symbols: Vec<usize> = if text.len() >= 4
+        && text.len().is_multiple_of(2)
+        && text.chars().all(|c| c.is_ascii_digit())
+    {
+        std::iter::once(CODE128_START_C)
+            .chain(
+                text.as_bytes()
+                    .chunks(2)
+                    .map(|pair| ((pair[0] - b'0') * 10 + (pair[1] - b'0')) as usize),
+            )
+            .collect()
+    } else {
+        if text.is_empty() || !text.chars().all(|c| (' '..='~').contains(&c)) {
+            return Err(AppError::Validation(format!(
+                "Cannot encode {} as Code128",
+                text
+            )));
+        }
+        std::iter::once(CODE128_START_B)
+            .chain(text.bytes().map(|b| (b - b' ') as usize))
+            .collect()
+    };
+    let checksum = symbols
+        .iter()
+        .enumerate()
+        .map(|(i, symbol)| i.max(1) * symbol)
+        .sum::<usize>()
+        % 103;
+
+    let mut modules = Vec::new();
+    for symbol in symbols.into_iter().chain([checksum, CODE128_STOP]) {
+        for (i, width) in CODE128_PATTERNS[symbol].chars().enumerate() {
+            let width = width.to_digit(10).unwrap_or(1) as usize;
+            modules.extend(std::iter::repeat_n(i % 2 == 0, width));
+        }
+    }
+    Ok(modules)
+}
+
+// Helper: Product that already carries `barcode`, other than `except`
+async fn barcode_owner<'e, E>(
+    executor: E,
+    barcode: &str,
+    except: Option<i64>,
+) -> Result<Option<i64>, AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    sqlx::query_scalar::<_, i64>("SELECT id FROM products WHERE barcode = ? AND id != ?")
+        .bind(barcode)
+        .bind(except.unwrap_or(-1))
+        .fetch_optional(executor)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to look up barcode: {}", e)))
+}
+
+// Helper: Fail if another product already uses `barcode`
+pub async fn ensure_barcode_free<'e, E>(
+    executor: E,
+    barcode: &str,
+    product_id: Option<i64>,
+) -> Result<(), AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    match barcode_owner(executor, barcode, product_id).await? {
+        Some(owner) => Err(AppError::Conflict(format!(
+            "Barcode {} is already used by product {}",
+            barcode, owner
+        ))),
+        None => Ok(()),
+    }
+}
+
+// Helper: Product with this barcode, inactive ones included
+pub async fn find_product_by_barcode(
+    conn: &mut SqliteConnection,
+    code: &str,
+) -> Result<Option<Product>, AppError> {
+    let barcode = normalize_barcode(code)?;
+    match barcode_owner(&mut *conn, &barcode, None).await? {
+        Some(id) => load_product(&mut *conn, id).await.map(Some),
+        None => Ok(None),
+    }
+}
+
+// Helper: A free barcode for a product that has none. EAN-13s are the
+// product ID behind an in-store prefix; Code128 reuses the product's unique ID.
+async fn new_barcode(
+    conn: &mut SqliteConnection,
+    product: &Product,
+    format: BarcodeFormat,
+) -> Result<String, AppError> {
+    let candidates: Vec<String> = match format {
+        BarcodeFormat::Ean13 => IN_STORE_PREFIXES
+            .map(|prefix| {
+                let body = format!("{}{:010}", prefix, product.id);
+                format!("{}{}", body, gtin_check_digit(&body))
+            })
+            .collect(),
+        BarcodeFormat::Code128 => product
+            .unique_id
+            .as_deref()
+            .and_then(|id| normalize_barcode(id).ok())
+            .into_iter()
+            .chain(std::iter::once(format!("PRD{:08}", product.id)))
+            .collect(),
+    };
+    for candidate in candidates {
+        if barcode_owner(&mut *conn, &candidate, None).await?.is_none() {
+            return Ok(candidate);
+        }
+    }
+    Err(AppError::Validation(format!(
+        "No free barcode for product {}",
+        product.id
+    )))
+}
+
+// Find the product a scanned barcode belongs to
+#[tauri::command]
+pub async fn lookup_product_by_barcode(
+    db: State<'_, Database>,
+    code: String,
+) -> Result<Option<Product>, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    find_product_by_barcode(&mut conn, &code).await
+}
+
+// Give products without a barcode a generated one, so they can be labeled and
+// scanned. Limited to `product_ids` when given, otherwise every active product.
+#[tauri::command]
+pub async fn generate_product_barcodes(
+    db: State<'_, Database>,
+    format: BarcodeFormat,
+    product_ids: Option<Vec<i64>>,
+) -> Result<Vec<Product>, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let ids = match product_ids {
+        Some(ids) => ids,
+        None => sqlx::query_scalar::<_, i64>(
+            "SELECT id FROM products WHERE barcode IS NULL AND COALESCE(is_active, 1) = 1 \
+             ORDER BY id",
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to list products: {}", e))?,
+    };
+
+    let mut updated = Vec::new();
+    for id in ids {
+        let before = load_product(&mut *tx, id).await?;
+        if before.barcode.is_some() {
+            continue;
+        }
+        let barcode = new_barcode(&mut tx, &before, format).await?;
+        sqlx::query("UPDATE products SET barcode = ? WHERE id = ?")
+            .bind(&barcode)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to save barcode: {}", e))?;
+
+        let after = load_product(&mut *tx, id).await?;
+        audit::record(
+            &mut *tx,
+            "product",
+            id,
+            "generate_barcode",
+            Some(&before),
+            Some(&after),
+        )
+        .await?;
+        updated.push(after);
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save barcodes: {}", e))?;
+
+    Ok(updated)
+}
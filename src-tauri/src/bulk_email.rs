@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, State};
+
+use crate::db::Database;
+use crate::domain_events::{check_email_sent, publish_now, DomainEvent};
+use crate::error::AppError;
+use crate::settings::load_smtp_settings;
+use crate::tasks::{spawn_task, TaskHandle, TaskInfo};
+use crate::timeline::EmailChannel;
+use crate::SmtpSettings;
+
+// One message of a bulk send, already rendered by the caller
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkEmail {
+    pub to_email: String,
+    pub to_name: String,
+    pub subject: String,
+    pub html_body: String,
+    // Order the email is logged against on the timeline
+    pub po_id: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkEmailFailure {
+    pub to_email: String,
+    pub po_id: Option<i64>,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkEmailResult {
+    pub sent: usize,
+    pub failed: Vec<BulkEmailFailure>,
+}
+
+// Helper: Send one message and log it like send_invoice_email does
+async fn send_one(
+    pool: &SqlitePool,
+    settings: SmtpSettings,
+    email: BulkEmail,
+) -> Result<(), AppError> {
+    let (to_email, subject) = (email.to_email.clone(), email.subject.clone());
+    let sent = tauri::async_runtime::spawn_blocking(move || {
+        crate::send_smtp_email(
+            settings,
+            email.to_email,
+            email.to_name,
+            email.subject,
+            email.html_body,
+        )
+    })
+    .await
+    .map_err(|e| format!("Failed to send email: {}", e))?;
+    check_email_sent(pool, &to_email, &subject, sent).await?;
+
+    let email_sent = DomainEvent::EmailSent {
+        order_id: email.po_id,
+        to_email: &to_email,
+        subject: &subject,
+        channel: EmailChannel::Smtp,
+    };
+    publish_now(pool, &email_sent).await
+}
+
+// Helper: Send every message in turn. A failed message is recorded and the
+// rest still go out.
+async fn send_all(
+    pool: SqlitePool,
+    settings: SmtpSettings,
+    emails: Vec<BulkEmail>,
+    task: TaskHandle,
+) -> Result<BulkEmailResult, AppError> {
+    let total = emails.len() as u64;
+    let mut result = BulkEmailResult {
+        sent: 0,
+        failed: Vec::new(),
+    };
+    task.progress(0, Some(total), "Sending emails");
+    for (i, email) in emails.into_iter().enumerate() {
+        task.check_cancelled()?;
+        let (to_email, po_id) = (email.to_email.clone(), email.po_id);
+        match send_one(&pool, settings.clone(), email).await {
+            Ok(()) => result.sent += 1,
+            Err(error) => result.failed.push(BulkEmailFailure {
+                to_email: to_email.clone(),
+                po_id,
+                error: error.to_string(),
+            }),
+        }
+        task.progress(i as u64 + 1, Some(total), to_email);
+    }
+    Ok(result)
+}
+
+// Send many emails through the saved SMTP settings as a background task whose
+// result is a BulkEmailResult. Cancelling stops before the next message.
+#[tauri::command]
+pub async fn send_bulk_email(
+    app: AppHandle,
+    db: State<'_, Database>,
+    emails: Vec<BulkEmail>,
+) -> Result<TaskInfo, AppError> {
+    if emails.is_empty() {
+        return Err(AppError::Validation("No emails to send".to_string()));
+    }
+    let settings = load_smtp_settings(&db.pool)
+        .await?
+        .ok_or_else(|| AppError::Validation("SMTP is not configured".to_string()))?;
+
+    let pool = db.pool.clone();
+    let label = format!("Send {} emails", emails.len());
+    Ok(spawn_task(&app, "bulk_email", label, move |task| {
+        send_all(pool, settings, emails, task)
+    }))
+}
@@ -0,0 +1,323 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection};
+use tauri::{AppHandle, State};
+
+use crate::audit;
+use crate::currency::currency_decimals;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::models::PurchaseOrder;
+use crate::order_status::{apply_transition, emit_transition, OrderEvent, OrderStatus, Transition};
+use crate::orders::{fetch_order, order_total};
+use crate::totals::{load_tax_settings, round_amount};
+
+// Which orders a bulk update touches. Criteria combine with AND; deleted
+// orders are never included.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrderFilter {
+    pub status: Option<String>,
+    pub event_id: Option<i64>,
+    // Orders imported from this Google Form
+    pub form_id: Option<String>,
+    pub customer_email: Option<String>,
+    pub created_from: Option<String>,
+    pub created_to: Option<String>,
+    pub order_ids: Option<Vec<i64>>,
+}
+
+// One change applied to every matched order, in the order given
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BulkChange {
+    // Lifecycle event, e.g. invoice or pay
+    Transition { event: OrderEvent },
+    // Percentage off every item's unit price; only before invoicing
+    Discount { percent: f64 },
+    SetEvent { event_id: Option<i64> },
+    AppendNote { note: String },
+}
+
+// How one order looks before and after the changes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkOrderPreview {
+    pub order_id: i64,
+    pub customer_name: String,
+    pub status_before: String,
+    pub status_after: String,
+    pub total_before: f64,
+    pub total_after: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkOrderError {
+    pub order_id: i64,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkUpdateResult {
+    pub dry_run: bool,
+    pub matched: usize,
+    pub orders: Vec<BulkOrderPreview>,
+    // Only filled on dry runs; a real run stops at the first error and changes nothing
+    pub errors: Vec<BulkOrderError>,
+}
+
+impl OrderFilter {
+    fn is_empty(&self) -> bool {
+        self.status.is_none()
+            && self.event_id.is_none()
+            && self.form_id.is_none()
+            && self.customer_email.is_none()
+            && self.created_from.is_none()
+            && self.created_to.is_none()
+            && self.order_ids.is_none()
+    }
+}
+
+// Helper: IDs of live orders matching a filter (all live orders if it's empty)
+pub async fn matching_order_ids(
+    conn: &mut SqliteConnection,
+    filter: &OrderFilter,
+) -> Result<Vec<i64>, AppError> {
+    let mut query =
+        QueryBuilder::<Sqlite>::new("SELECT id FROM preorders WHERE deleted_at IS NULL");
+    if let Some(status) = &filter.status {
+        query
+            .push(" AND COALESCE(status, 'pending') = ")
+            .push_bind(status);
+    }
+    if let Some(event_id) = filter.event_id {
+        query.push(" AND event_id = ").push_bind(event_id);
+    }
+    if let Some(form_id) = &filter.form_id {
+        query
+            .push(" AND id IN (SELECT preorder_id FROM synced_responses WHERE form_id = ")
+            .push_bind(form_id)
+            .push(")");
+    }
+    if let Some(email) = &filter.customer_email {
+        query
+            .push(" AND customer_email = ")
+            .push_bind(email.trim())
+            .push(" COLLATE NOCASE");
+    }
+    if let Some(from) = &filter.created_from {
+        query
+            .push(" AND created_at >= datetime(")
+            .push_bind(from)
+            .push(")");
+    }
+    if let Some(to) = &filter.created_to {
+        query
+            .push(" AND created_at < datetime(")
+            .push_bind(to)
+            .push(")");
+    }
+    if let Some(ids) = &filter.order_ids {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        query.push(" AND id IN (");
+        let mut separated = query.separated(", ");
+        for id in ids {
+            separated.push_bind(*id);
+        }
+        separated.push_unseparated(")");
+    }
+    query.push(" ORDER BY id");
+
+    query
+        .build_query_scalar::<i64>()
+        .fetch_all(conn)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to find orders: {}", e)))
+}
+
+// Helper: Take a percentage off every item of an order and recompute its total
+async fn apply_discount(
+    conn: &mut SqliteConnection,
+    order: &PurchaseOrder,
+    percent: f64,
+) -> Result<(), AppError> {
+    let status = OrderStatus::parse(&order.status)?;
+    if !matches!(status, OrderStatus::Draft | OrderStatus::Confirmed) {
+        return Err(AppError::Conflict(format!(
+            "Order {} is already {}; discounts only apply before invoicing",
+            order.id,
+            status.as_str()
+        )));
+    }
+
+    let settings = load_tax_settings(&mut *conn).await?;
+    let decimals = order
+        .currency_code
+        .as_deref()
+        .map(currency_decimals)
+        .unwrap_or(settings.decimal_places);
+
+    for item in &order.items {
+        let discounted = round_amount(
+            item.unit_price * (1.0 - percent / 100.0),
+            decimals,
+            settings.rounding_mode,
+        );
+        sqlx::query("UPDATE order_items SET unit_price = ? WHERE id = ?")
+            .bind(discounted)
+            .bind(item.id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to update order item: {}", e))?;
+    }
+
+    let total = order_total(&mut *conn, order.id).await?;
+    sqlx::query("UPDATE preorders SET total_amount = ? WHERE id = ?")
+        .bind(total)
+        .bind(order.id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to update order total: {}", e))?;
+    Ok(())
+}
+
+// Helper: Apply every change to one order, collecting lifecycle transitions
+async fn apply_changes(
+    conn: &mut SqliteConnection,
+    id: i64,
+    changes: &[BulkChange],
+    transitions: &mut Vec<Transition>,
+) -> Result<BulkOrderPreview, AppError> {
+    let before = fetch_order(&mut *conn, id).await?;
+
+    for change in changes {
+        match change {
+            BulkChange::Transition { event } => {
+                transitions.push(apply_transition(&mut *conn, id, *event).await?);
+            }
+            BulkChange::Discount { percent } => {
+                let current = fetch_order(&mut *conn, id).await?;
+                apply_discount(&mut *conn, &current, *percent).await?;
+            }
+            BulkChange::SetEvent { event_id } => {
+                sqlx::query("UPDATE preorders SET event_id = ? WHERE id = ?")
+                    .bind(event_id)
+                    .bind(id)
+                    .execute(&mut *conn)
+                    .await
+                    .map_err(|e| format!("Failed to update order event: {}", e))?;
+            }
+            BulkChange::AppendNote { note } => {
+                sqlx::query(
+                    "UPDATE preorders SET notes = CASE WHEN COALESCE(notes, '') = '' THEN ? \
+                     ELSE notes || char(10) || ? END WHERE id = ?",
+                )
+                .bind(note.trim())
+                .bind(note.trim())
+                .bind(id)
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| format!("Failed to update order notes: {}", e))?;
+            }
+        }
+    }
+
+    let after = fetch_order(&mut *conn, id).await?;
+    audit::record(
+        &mut *conn,
+        "order",
+        id,
+        "bulk_update",
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
+
+    Ok(BulkOrderPreview {
+        order_id: id,
+        customer_name: after.customer_name,
+        status_before: before.status,
+        status_after: after.status,
+        total_before: before.total_amount,
+        total_after: after.total_amount,
+    })
+}
+
+// Apply the same changes to every order matching `filter` in one transaction.
+// With `dry_run` the changes are made and then rolled back, so the preview
+// shows exactly what a real run would do.
+#[tauri::command]
+pub async fn bulk_update_orders(
+    app: AppHandle,
+    db: State<'_, Database>,
+    filter: OrderFilter,
+    changes: Vec<BulkChange>,
+    dry_run: Option<bool>,
+) -> Result<BulkUpdateResult, AppError> {
+    let dry_run = dry_run.unwrap_or(false);
+    if filter.is_empty() {
+        return Err(AppError::Validation(
+            "Bulk updates need at least one filter".to_string(),
+        ));
+    }
+    if changes.is_empty() {
+        return Err(AppError::Validation("No changes given".to_string()));
+    }
+    for change in &changes {
+        match change {
+            BulkChange::Discount { percent }
+                if !(percent.is_finite() && *percent > 0.0 && *percent <= 100.0) =>
+            {
+                return Err(AppError::Validation(format!(
+                    "Discount must be between 0 and 100%: {}",
+                    percent
+                )));
+            }
+            BulkChange::AppendNote { note } if note.trim().is_empty() => {
+                return Err(AppError::Validation("Note must not be empty".to_string()));
+            }
+            _ => {}
+        }
+    }
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let ids = matching_order_ids(&mut tx, &filter).await?;
+    let mut result = BulkUpdateResult {
+        dry_run,
+        matched: ids.len(),
+        orders: Vec::with_capacity(ids.len()),
+        errors: Vec::new(),
+    };
+    let mut transitions = Vec::new();
+
+    for id in ids {
+        match apply_changes(&mut tx, id, &changes, &mut transitions).await {
+            Ok(preview) => result.orders.push(preview),
+            Err(message) if dry_run => result.errors.push(BulkOrderError {
+                order_id: id,
+                message: message.to_string(),
+            }),
+            Err(message) => return Err(AppError::Validation(format!("Order {}: {}", id, message))),
+        }
+    }
+
+    if dry_run {
+        tx.rollback()
+            .await
+            .map_err(|e| format!("Failed to discard preview: {}", e))?;
+        return Ok(result);
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save bulk update: {}", e))?;
+
+    for transition in &transitions {
+        emit_transition(&app, transition);
+    }
+    Ok(result)
+}
@@ -0,0 +1,442 @@
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use tauri::{AppHandle, Manager, State};
+
+use crate::audit;
+use crate::automation::{self, Poller};
+use crate::db::Database;
+use crate::domain_events::{DomainEvent, Subscriber};
+use crate::error::AppError;
+use crate::http;
+use crate::i18n::load_locale;
+use crate::inventory::StockLevel;
+use crate::log_warning;
+use crate::money::money_format;
+use crate::redact::redact;
+
+const SLACK_WEBHOOK_PREFIX: &str = "https://hooks.slack.com/";
+const DISCORD_WEBHOOK_PREFIXES: [&str; 2] = [
+    "https://discord.com/api/webhooks/",
+    "https://discordapp.com/api/webhooks/",
+];
+
+// Discord refuses messages longer than this
+const DISCORD_MAX_CHARS: usize = 2000;
+
+// Sends per alert before it's marked failed
+const MAX_ATTEMPTS: i64 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum ChatAlertEvent {
+    NewOrder,
+    LowStock,
+    EmailFailed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum ChatChannel {
+    Slack,
+    Discord,
+}
+
+const CHANNELS: [ChatChannel; 2] = [ChatChannel::Slack, ChatChannel::Discord];
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ChatAlertSettings {
+    pub enabled: bool,
+    // Incoming webhook URLs; a channel without one gets no alerts
+    pub slack_webhook_url: Option<String>,
+    pub discord_webhook_url: Option<String>,
+    pub notify_new_order: bool,
+    pub notify_low_stock: bool,
+    pub notify_email_failed: bool,
+}
+
+// Helper: Chat alert settings, disabled defaults if the row is missing
+async fn load_settings(conn: &mut SqliteConnection) -> Result<ChatAlertSettings, AppError> {
+    Ok(sqlx::query_as::<_, ChatAlertSettings>(
+        "SELECT enabled, slack_webhook_url, discord_webhook_url, notify_new_order, \
+         notify_low_stock, notify_email_failed FROM chat_alert_settings WHERE id = 1",
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| format!("Failed to load chat alert settings: {}", e))?
+    .unwrap_or(ChatAlertSettings {
+        enabled: false,
+        slack_webhook_url: None,
+        discord_webhook_url: None,
+        notify_new_order: true,
+        notify_low_stock: true,
+        notify_email_failed: true,
+    }))
+}
+
+impl ChatAlertSettings {
+    // Webhook URL to post to, if alerts of this kind should go to the channel
+    fn target(&self, channel: ChatChannel, event: Option<ChatAlertEvent>) -> Option<&str> {
+        let wanted = match event {
+            Some(ChatAlertEvent::NewOrder) => self.notify_new_order,
+            Some(ChatAlertEvent::LowStock) => self.notify_low_stock,
+            Some(ChatAlertEvent::EmailFailed) => self.notify_email_failed,
+            None => true,
+        };
+        let url = match channel {
+            ChatChannel::Slack => &self.slack_webhook_url,
+            ChatChannel::Discord => &self.discord_webhook_url,
+        };
+        let url = url.as_deref().map(str::trim).filter(|u| !u.is_empty())?;
+        (self.enabled && wanted).then_some(url)
+    }
+}
+
+// Helper: Post one message to a Slack or Discord incoming webhook
+async fn post_message(
+    client: &Client,
+    channel: ChatChannel,
+    url: &str,
+    text: &str,
+) -> Result<(), AppError> {
+    let payload = match channel {
+        ChatChannel::Slack => serde_json::json!({ "text": text }),
+        ChatChannel::Discord => serde_json::json!({
+            "content": text.chars().take(DISCORD_MAX_CHARS).collect::<String>(),
+            "username": "POTracker",
+        }),
+    };
+    let response = client.post(url).json(&payload).send().await.map_err(|e| {
+        AppError::Network(format!(
+            "Failed to reach {:?}: {}",
+            channel,
+            e.without_url()
+        ))
+    })?;
+
+    let status = response.status();
+    if status.is_success() {
+        Ok(())
+    } else {
+        let body = redact(&response.text().await.unwrap_or_default());
+        Err(AppError::from_status(
+            status.as_u16(),
+            format!("{:?} rejected the message ({}): {}", channel, status, body),
+        ))
+    }
+}
+
+// Helper: Queue an alert for every channel that wants it. Runs in the
+// caller's transaction, so a rolled-back change never gets announced.
+async fn queue_alert(
+    conn: &mut SqliteConnection,
+    settings: &ChatAlertSettings,
+    event: ChatAlertEvent,
+    text: &str,
+) -> Result<(), AppError> {
+    for channel in CHANNELS {
+        if settings.target(channel, Some(event)).is_none() {
+            continue;
+        }
+        sqlx::query("INSERT INTO chat_alerts (event, channel, text) VALUES (?, ?, ?)")
+            .bind(event)
+            .bind(channel)
+            .bind(text)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to queue chat alert: {}", e))?;
+    }
+    Ok(())
+}
+
+// Helper: Whether any channel wants alerts of this kind
+fn wanted(settings: &ChatAlertSettings, event: ChatAlertEvent) -> bool {
+    CHANNELS
+        .iter()
+        .any(|channel| settings.target(*channel, Some(event)).is_some())
+}
+
+// Helper: Alert the team about a newly created order
+async fn alert_new_order(conn: &mut SqliteConnection, po_id: i64) -> Result<(), AppError> {
+    let settings = load_settings(&mut *conn).await?;
+    if !wanted(&settings, ChatAlertEvent::NewOrder) {
+        return Ok(());
+    }
+
+    let order = sqlx::query_as::<_, (String, String, f64, Option<String>)>(
+        "SELECT confirmation_code, customer_name, total_amount, currency_code \
+         FROM preorders WHERE id = ?",
+    )
+    .bind(po_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load order: {}", e))?;
+    let (code, customer, total, currency) =
+        order.ok_or_else(|| AppError::NotFound(format!("Order {} not found", po_id)))?;
+    let money = money_format(&mut *conn, currency.as_deref()).await?;
+    let text = load_locale(&mut *conn).await.format(
+        "notify.new_order",
+        &[
+            ("code", &code),
+            ("customer", &customer),
+            ("total", &money.format(total)),
+        ],
+    );
+    queue_alert(conn, &settings, ChatAlertEvent::NewOrder, &text).await
+}
+
+// Helper: Alert the team about a product that just dropped to its low-stock
+// threshold
+async fn alert_low_stock(conn: &mut SqliteConnection, level: &StockLevel) -> Result<(), AppError> {
+    let settings = load_settings(&mut *conn).await?;
+    if !wanted(&settings, ChatAlertEvent::LowStock) {
+        return Ok(());
+    }
+
+    let text = load_locale(&mut *conn).await.format(
+        "notify.low_stock",
+        &[
+            ("product", &level.product_name),
+            ("quantity", &level.stock_quantity.unwrap_or(0).to_string()),
+            (
+                "threshold",
+                &level.low_stock_threshold.unwrap_or(0).to_string(),
+            ),
+        ],
+    );
+    queue_alert(conn, &settings, ChatAlertEvent::LowStock, &text).await
+}
+
+// Helper: Alert the team about an email that couldn't be sent
+async fn alert_email_failed(
+    conn: &mut SqliteConnection,
+    to_email: &str,
+    subject: &str,
+    error: &str,
+) -> Result<(), AppError> {
+    let settings = load_settings(&mut *conn).await?;
+    if !wanted(&settings, ChatAlertEvent::EmailFailed) {
+        return Ok(());
+    }
+
+    let text = load_locale(&mut *conn).await.format(
+        "notify.email_failed",
+        &[("to", to_email), ("subject", subject), ("error", error)],
+    );
+    queue_alert(conn, &settings, ChatAlertEvent::EmailFailed, &text).await
+}
+
+// Posts new orders, low stock and failed emails to Slack and Discord
+pub struct ChatAlerts;
+
+impl Subscriber for ChatAlerts {
+    async fn handle(
+        &self,
+        conn: &mut SqliteConnection,
+        event: &DomainEvent<'_>,
+    ) -> Result<(), AppError> {
+        match *event {
+            DomainEvent::OrderCreated { order_id } => alert_new_order(conn, order_id).await,
+            DomainEvent::StockLow(level) => alert_low_stock(conn, level).await,
+            DomainEvent::EmailFailed {
+                to_email,
+                subject,
+                error,
+            } => alert_email_failed(conn, to_email, subject, error).await,
+            _ => Ok(()),
+        }
+    }
+}
+
+// Helper: Send queued alerts. Ones whose channel or kind was switched off
+// after they were queued are marked failed instead of sent.
+pub async fn send_pending_alerts(pool: &SqlitePool) -> Result<(), AppError> {
+    let batch = automation::send_batch_size(pool).await?;
+    if batch == 0 {
+        return Ok(());
+    }
+    let pending = sqlx::query_as::<_, (i64, ChatAlertEvent, ChatChannel, String, i64)>(
+        "SELECT id, event, channel, text, attempts FROM chat_alerts \
+         WHERE status = 'pending' ORDER BY id LIMIT ?",
+    )
+    .bind(batch)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load chat alerts: {}", e))?;
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let settings = load_settings(&mut conn).await?;
+    drop(conn);
+
+    automation::record_sends(pool, pending.len()).await?;
+    let client = http::client();
+    for (id, event, channel, text, attempts) in pending {
+        let target = settings.target(channel, Some(event));
+        let result = match target {
+            Some(url) => post_message(&client, channel, url, &text).await,
+            None => Err(AppError::Validation(format!(
+                "{:?} alerts are switched off",
+                channel
+            ))),
+        };
+        let attempts = attempts + 1;
+        let status = match &result {
+            Ok(()) => "sent",
+            Err(_) if attempts < MAX_ATTEMPTS && target.is_some() => "pending",
+            Err(_) => "failed",
+        };
+        sqlx::query(
+            "UPDATE chat_alerts SET status = ?, attempts = ?, last_error = ?, \
+             sent_at = CASE WHEN ? = 'sent' THEN CURRENT_TIMESTAMP END WHERE id = ?",
+        )
+        .bind(status)
+        .bind(attempts)
+        .bind(result.err().map(|e| e.to_string()))
+        .bind(status)
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to update chat alert: {}", e))?;
+    }
+    Ok(())
+}
+
+// Start the background loop that sends queued Slack and Discord alerts
+pub fn start_chat_alert_sender(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<Database>().pool.clone();
+        loop {
+            if let Err(e) = send_pending_alerts(&pool).await {
+                log_warning!("Chat alert sender failed: {}", e);
+            }
+            automation::wait_for_next_poll(&pool, Poller::Sender).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_chat_alert_settings(
+    db: State<'_, Database>,
+) -> Result<ChatAlertSettings, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    load_settings(&mut conn).await
+}
+
+#[tauri::command]
+pub async fn set_chat_alert_settings(
+    db: State<'_, Database>,
+    settings: ChatAlertSettings,
+) -> Result<ChatAlertSettings, AppError> {
+    let slack_url = settings
+        .slack_webhook_url
+        .as_deref()
+        .map(str::trim)
+        .filter(|u| !u.is_empty());
+    let discord_url = settings
+        .discord_webhook_url
+        .as_deref()
+        .map(str::trim)
+        .filter(|u| !u.is_empty());
+    if settings.enabled && slack_url.is_none() && discord_url.is_none() {
+        return Err(AppError::Validation(
+            "A Slack or Discord webhook URL is needed to turn alerts on".to_string(),
+        ));
+    }
+    if slack_url.is_some_and(|u| !u.starts_with(SLACK_WEBHOOK_PREFIX)) {
+        return Err(AppError::Validation(
+            "That doesn't look like a Slack incoming webhook URL".to_string(),
+        ));
+    }
+    if discord_url.is_some_and(|u| !DISCORD_WEBHOOK_PREFIXES.iter().any(|p| u.starts_with(p))) {
+        return Err(AppError::Validation(
+            "That doesn't look like a Discord webhook URL".to_string(),
+        ));
+    }
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_settings(&mut tx).await?;
+    sqlx::query(
+        "INSERT OR REPLACE INTO chat_alert_settings \
+         (id, enabled, slack_webhook_url, discord_webhook_url, notify_new_order, \
+          notify_low_stock, notify_email_failed, updated_at) \
+         VALUES (1, ?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP)",
+    )
+    .bind(settings.enabled)
+    .bind(slack_url)
+    .bind(discord_url)
+    .bind(settings.notify_new_order)
+    .bind(settings.notify_low_stock)
+    .bind(settings.notify_email_failed)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save chat alert settings: {}", e))?;
+    let after = load_settings(&mut tx).await?;
+
+    // Webhook URLs carry their own secret, so only whether one is set goes
+    // into the audit log
+    let view = |s: &ChatAlertSettings| {
+        serde_json::json!({
+            "enabled": s.enabled,
+            "slack": s.slack_webhook_url.is_some(),
+            "discord": s.discord_webhook_url.is_some(),
+            "notify_new_order": s.notify_new_order,
+            "notify_low_stock": s.notify_low_stock,
+            "notify_email_failed": s.notify_email_failed,
+        })
+    };
+    audit::record(
+        &mut *tx,
+        "chat_alert_settings",
+        1,
+        "update",
+        Some(&view(&before)),
+        Some(&view(&after)),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save chat alert settings: {}", e))?;
+    Ok(after)
+}
+
+// Post a test message to a channel right away, to check its webhook URL
+#[tauri::command]
+pub async fn send_test_chat_alert(
+    db: State<'_, Database>,
+    channel: ChatChannel,
+) -> Result<(), AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let settings = load_settings(&mut conn).await?;
+    let text = load_locale(&mut *conn).await.text("notify.test");
+    drop(conn);
+
+    let url = settings.target(channel, None).ok_or_else(|| {
+        format!(
+            "{:?} alerts aren't set up; add a webhook URL and turn them on",
+            channel
+        )
+    })?;
+    post_message(&http::client(), channel, url, &text).await
+}
@@ -0,0 +1,329 @@
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+use sqlx::{Executor, Sqlite, SqliteConnection};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::State;
+
+use crate::audit;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::models::PurchaseOrder;
+use crate::orders::load_order;
+
+// No 0/O or 1/I, so codes read back over the phone or typed from a printout
+// don't get mixed up. 32 symbols, so a random byte maps onto it without bias.
+const ALPHABET: &[u8; 32] = b"23456789ABCDEFGHJKLMNPQRSTUVWXYZ";
+const CODE_LENGTH: usize = 8;
+const MAX_ATTEMPTS: usize = 10;
+
+// Wrong codes allowed within FAILURE_WINDOW before lookups are refused, so
+// codes can't be guessed by trying them one after another
+const MAX_FAILURES: usize = 5;
+const FAILURE_WINDOW: Duration = Duration::from_secs(60);
+
+// When recent lookups matched no order. Process-wide, since codes can be
+// tried from the lookup screen and the pickup counter alike.
+static FAILURES: Mutex<VecDeque<Instant>> = Mutex::new(VecDeque::new());
+
+// Helper: Position of a character in the code alphabet
+fn symbol_value(c: char) -> Option<usize> {
+    ALPHABET.iter().position(|&a| a as char == c)
+}
+
+// Luhn mod 32 check character; catches any single wrong character and most
+// swapped neighbours
+fn checksum_char(code: &str) -> Option<char> {
+    let n = ALPHABET.len();
+    let mut sum = 0;
+    for (i, c) in code.chars().rev().enumerate() {
+        let mut value = symbol_value(c)?;
+        if i % 2 == 0 {
+            value *= 2;
+            value = value / n + value % n;
+        }
+        sum += value;
+    }
+    Some(ALPHABET[(n - sum % n) % n] as char)
+}
+
+// Helper: Random code, with a trailing check character if requested
+fn random_code(with_checksum: bool) -> String {
+    let mut bytes = [0u8; CODE_LENGTH];
+    OsRng.fill_bytes(&mut bytes);
+    let mut code: String = bytes
+        .iter()
+        .map(|b| ALPHABET[*b as usize % ALPHABET.len()] as char)
+        .collect();
+    if with_checksum {
+        if let Some(check) = checksum_char(&code) {
+            code.push(check);
+        }
+    }
+    code
+}
+
+// Uppercase and drop separators people tend to type (spaces, dashes)
+pub fn normalize_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect::<String>()
+        .to_uppercase()
+}
+
+// Codes one character longer than usual carry a check character. Older
+// UUID-based codes are exactly CODE_LENGTH long and are never checked.
+pub fn verify_checksum(code: &str) -> Result<(), AppError> {
+    if code.chars().count() != CODE_LENGTH + 1 {
+        return Ok(());
+    }
+    let (body, check) = code.split_at(CODE_LENGTH);
+    match checksum_char(body) {
+        Some(expected) if check.starts_with(expected) => Ok(()),
+        _ => Err(AppError::Validation(format!(
+            "Invalid confirmation code: {} (check the code for typos)",
+            code
+        ))),
+    }
+}
+
+// Helper: Compare two codes without stopping at the first difference, so the
+// time taken doesn't reveal how much of a guess was right
+fn codes_match(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+// Helper: Refuse a lookup while too many recent ones matched nothing
+fn check_rate_limit() -> Result<(), AppError> {
+    let mut failures = FAILURES
+        .lock()
+        .map_err(|_| "Confirmation code lookups are unavailable")?;
+    while failures
+        .front()
+        .is_some_and(|at| at.elapsed() >= FAILURE_WINDOW)
+    {
+        failures.pop_front();
+    }
+    match failures.front() {
+        Some(oldest) if failures.len() >= MAX_FAILURES => Err(AppError::RateLimited {
+            message: format!(
+                "Too many wrong confirmation codes; try again in {} seconds",
+                FAILURE_WINDOW
+                    .saturating_sub(oldest.elapsed())
+                    .as_secs()
+                    .max(1)
+            ),
+            status: None,
+        }),
+        _ => Ok(()),
+    }
+}
+
+// Count a lookup that matched no order toward the limit
+pub fn record_failure() {
+    if let Ok(mut failures) = FAILURES.lock() {
+        failures.push_back(Instant::now());
+    }
+}
+
+// Normalize a typed or scanned code for a lookup, refusing it while lookups
+// are rate limited. A code failing its checksum counts as a wrong code.
+pub fn checked_code(code: &str) -> Result<String, AppError> {
+    let code = normalize_code(code);
+    if code.is_empty() {
+        return Err(AppError::Validation(
+            "Confirmation code must not be empty".to_string(),
+        ));
+    }
+    check_rate_limit()?;
+    if let Err(e) = verify_checksum(&code) {
+        record_failure();
+        return Err(e);
+    }
+    Ok(code)
+}
+
+// The order a code belongs to. Every stored code of the same length is
+// compared in full rather than letting an index find a match, so how long a
+// lookup takes says nothing about the code.
+pub async fn find_order_by_code<'e, E>(
+    executor: E,
+    code: &str,
+    include_deleted: bool,
+) -> Result<Option<i64>, AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let rows = sqlx::query_as::<_, (i64, String)>(
+        "SELECT id, confirmation_code FROM preorders \
+         WHERE length(confirmation_code) = ? AND (? OR deleted_at IS NULL)",
+    )
+    .bind(code.len() as i64)
+    .bind(include_deleted)
+    .fetch_all(executor)
+    .await
+    .map_err(|e| format!("Failed to look up confirmation code: {}", e))?;
+
+    let mut found = None;
+    for (id, stored) in rows {
+        if codes_match(&stored, code) {
+            found = Some(id);
+        }
+    }
+    Ok(found)
+}
+
+// Helper: Whether new codes should carry a check character
+async fn checksum_enabled(conn: &mut SqliteConnection) -> Result<bool, AppError> {
+    let enabled = sqlx::query_scalar::<_, bool>(
+        "SELECT use_checksum FROM confirmation_code_settings WHERE id = 1",
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| format!("Failed to load confirmation code settings: {}", e))?;
+    Ok(enabled.unwrap_or(false))
+}
+
+// Generate a code no existing order uses. Within a write transaction the check
+// and the caller's INSERT can't race; outside one, the UNIQUE constraint on
+// preorders.confirmation_code is the final guard.
+pub async fn unique_code(conn: &mut SqliteConnection) -> Result<String, AppError> {
+    let with_checksum = checksum_enabled(&mut *conn).await?;
+
+    for _ in 0..MAX_ATTEMPTS {
+        let code = random_code(with_checksum);
+        let taken = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM preorders WHERE confirmation_code = ?",
+        )
+        .bind(&code)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to check confirmation code: {}", e))?;
+
+        if taken == 0 {
+            return Ok(code);
+        }
+    }
+
+    Err(AppError::Internal(format!(
+        "Failed to generate a unique confirmation code after {} attempts",
+        MAX_ATTEMPTS
+    )))
+}
+
+// Generate unique confirmation code
+#[tauri::command]
+pub async fn generate_confirmation_code(db: State<'_, Database>) -> Result<String, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    unique_code(&mut conn).await
+}
+
+// Give an order a new confirmation code, e.g. when the old one was shared by
+// mistake. The old code stops working straight away.
+#[tauri::command]
+pub async fn regenerate_confirmation_code(
+    db: State<'_, Database>,
+    order_id: i64,
+) -> Result<PurchaseOrder, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_order(&mut *tx, order_id).await?;
+    if before.deleted_at.is_some() {
+        return Err(AppError::Conflict(format!(
+            "Order {} has been deleted",
+            order_id
+        )));
+    }
+
+    let code = unique_code(&mut tx).await?;
+    sqlx::query("UPDATE preorders SET confirmation_code = ?, version = version + 1 WHERE id = ?")
+        .bind(&code)
+        .bind(order_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to save confirmation code: {}", e))?;
+
+    let after = load_order(&mut *tx, order_id).await?;
+    audit::record(
+        &mut *tx,
+        "order",
+        order_id,
+        "regenerate_code",
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save confirmation code: {}", e))?;
+
+    Ok(after)
+}
+
+// Find the order a customer's confirmation code belongs to
+#[tauri::command]
+pub async fn lookup_order_by_confirmation_code(
+    db: State<'_, Database>,
+    code: String,
+) -> Result<Option<PurchaseOrder>, AppError> {
+    let code = checked_code(&code)?;
+    match find_order_by_code(&db.pool, &code, true).await? {
+        Some(id) => load_order(&db.pool, id).await.map(Some),
+        None => {
+            record_failure();
+            Ok(None)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn set_confirmation_checksum(
+    db: State<'_, Database>,
+    enabled: bool,
+) -> Result<bool, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let previous = checksum_enabled(&mut tx).await?;
+
+    sqlx::query(
+        "INSERT OR REPLACE INTO confirmation_code_settings (id, use_checksum) VALUES (1, ?)",
+    )
+    .bind(enabled)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save confirmation code settings: {}", e))?;
+
+    audit::record(
+        &mut *tx,
+        "confirmation_code_settings",
+        1,
+        "update",
+        Some(&previous),
+        Some(&enabled),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save confirmation code settings: {}", e))?;
+
+    Ok(enabled)
+}
@@ -0,0 +1,697 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteConnection;
+use std::collections::HashSet;
+use std::path::Path;
+use tauri::State;
+
+use crate::audit;
+use crate::customers::load_customer;
+use crate::db::Database;
+use crate::encryption::conceal_opt;
+use crate::error::AppError;
+use crate::http::{self, SendWithRetry};
+use crate::models::validate_contact;
+use crate::product_import::{cell, find_column, read_csv_rows};
+
+const PEOPLE_API: &str = "https://people.googleapis.com/v1/people/me/connections";
+
+// Largest page the People API allows
+const CONTACTS_PAGE_SIZE: usize = 1000;
+
+// A contact read from an outside source, before it's matched to a customer
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContactRecord {
+    pub name: String,
+    pub email: String,
+    pub phone: Option<String>,
+    pub address: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContactImportResult {
+    pub created: usize,
+    // Existing customers that had a missing phone or address filled in
+    pub updated: usize,
+    // Already in the directory with nothing to add, deleted, or repeated in the source
+    pub unchanged: usize,
+    // Contacts without a usable name and email, with the reason
+    pub skipped: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ConnectionsPage {
+    #[serde(default)]
+    connections: Vec<Person>,
+    #[serde(rename = "nextPageToken")]
+    next_page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Person {
+    #[serde(default)]
+    names: Vec<PersonName>,
+    #[serde(rename = "emailAddresses", default)]
+    email_addresses: Vec<PersonField>,
+    #[serde(rename = "phoneNumbers", default)]
+    phone_numbers: Vec<PersonField>,
+    #[serde(default)]
+    addresses: Vec<PersonAddress>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PersonName {
+    #[serde(rename = "displayName")]
+    display_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PersonField {
+    value: Option<String>,
+    metadata: Option<FieldMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PersonAddress {
+    #[serde(rename = "formattedValue")]
+    formatted_value: Option<String>,
+    metadata: Option<FieldMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FieldMetadata {
+    #[serde(default)]
+    primary: bool,
+}
+
+// Helper: The primary entry of a multi-valued field, else the first one
+fn primary_value<T>(
+    fields: &[T],
+    metadata: impl Fn(&T) -> Option<&FieldMetadata>,
+    value: impl Fn(&T) -> Option<&String>,
+) -> Option<String> {
+    fields
+        .iter()
+        .find(|f| metadata(f).map(|m| m.primary).unwrap_or(false))
+        .or_else(|| fields.first())
+        .and_then(value)
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+impl Person {
+    fn into_contact(self) -> ContactRecord {
+        let email = primary_value(
+            &self.email_addresses,
+            |f| f.metadata.as_ref(),
+            |f| f.value.as_ref(),
+        )
+        .unwrap_or_default();
+        let name = self
+            .names
+            .iter()
+            .find_map(|n| n.display_name.as_deref())
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .map(str::to_string)
+            // Contacts saved with only an email address get named after it
+            .unwrap_or_else(|| email.split('@').next().unwrap_or_default().to_string());
+        ContactRecord {
+            name,
+            email,
+            phone: primary_value(
+                &self.phone_numbers,
+                |f| f.metadata.as_ref(),
+                |f| f.value.as_ref(),
+            ),
+            address: primary_value(
+                &self.addresses,
+                |f| f.metadata.as_ref(),
+                |f| f.formatted_value.as_ref(),
+            ),
+        }
+    }
+}
+
+// Helper: Add contacts to the customer directory, matching on email. Existing
+// customers keep their name and only gain a phone or address they lack.
+pub async fn save_contacts(
+    conn: &mut SqliteConnection,
+    contacts: Vec<ContactRecord>,
+) -> Result<ContactImportResult, AppError> {
+    let mut result = ContactImportResult::default();
+    let mut seen = HashSet::new();
+
+    for contact in contacts {
+        let email = contact.email.trim().to_string();
+        if let Err(e) = validate_contact(&contact.name, &email) {
+            let label = if contact.name.trim().is_empty() {
+                email.clone()
+            } else {
+                contact.name.trim().to_string()
+            };
+            result.skipped.push(format!("{}: {}", label, e));
+            continue;
+        }
+        if !seen.insert(email.to_lowercase()) {
+            result.unchanged += 1;
+            continue;
+        }
+
+        let existing = sqlx::query_as::<_, (i64, Option<String>)>(
+            "SELECT id, deleted_at FROM customers WHERE email = ?",
+        )
+        .bind(&email)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to look up customer: {}", e))?;
+
+        match existing {
+            // Someone deleted this customer on purpose; don't bring them back
+            Some((_, Some(_))) => result.unchanged += 1,
+            Some((id, None)) => {
+                let before = load_customer(&mut *conn, id).await?;
+                let (phone, address) = (
+                    conceal_opt(contact.phone.as_deref())?,
+                    conceal_opt(contact.address.as_deref())?,
+                );
+                let changed = sqlx::query(
+                    "UPDATE customers SET phone = COALESCE(phone, ?), \
+                     address = COALESCE(address, ?), updated_at = CURRENT_TIMESTAMP \
+                     WHERE id = ? AND ((phone IS NULL AND ? IS NOT NULL) \
+                     OR (address IS NULL AND ? IS NOT NULL))",
+                )
+                .bind(&phone)
+                .bind(&address)
+                .bind(id)
+                .bind(&phone)
+                .bind(&address)
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| format!("Failed to update customer: {}", e))?
+                .rows_affected();
+                if changed == 0 {
+                    result.unchanged += 1;
+                    continue;
+                }
+                let after = load_customer(&mut *conn, id).await?;
+                audit::record(
+                    &mut *conn,
+                    "customer",
+                    id,
+                    "import",
+                    Some(&before),
+                    Some(&after),
+                )
+                .await?;
+                result.updated += 1;
+            }
+            None => {
+                let id = sqlx::query(
+                    "INSERT INTO customers (name, email, phone, address) VALUES (?, ?, ?, ?)",
+                )
+                .bind(contact.name.trim())
+                .bind(&email)
+                .bind(conceal_opt(contact.phone.as_deref())?)
+                .bind(conceal_opt(contact.address.as_deref())?)
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| format!("Failed to create customer: {}", e))?
+                .last_insert_rowid();
+                let created = load_customer(&mut *conn, id).await?;
+                audit::record(&mut *conn, "customer", id, "import", None, Some(&created)).await?;
+                result.created += 1;
+            }
+        }
+    }
+    Ok(result)
+}
+
+// Pull the signed-in user's Google Contacts into the customer directory.
+// Contacts without an email address are skipped.
+#[tauri::command]
+pub async fn import_google_contacts(
+    db: State<'_, Database>,
+    access_token: String,
+) -> Result<ContactImportResult, AppError> {
+    let client = http::client();
+    let mut contacts = Vec::new();
+    let mut page_token: Option<String> = None;
+
+    loop {
+        let mut request = client.get(PEOPLE_API).bearer_auth(&access_token).query(&[
+            (
+                "personFields",
+                "names,emailAddresses,phoneNumbers,addresses",
+            ),
+            ("pageSize", &CONTACTS_PAGE_SIZE.to_string()),
+        ]);
+        if let Some(token) = &page_token {
+            request = request.query(&[("pageToken", token)]);
+        }
+        let response = request
+            .send_with_retry()
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to fetch contacts: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(http::google_error(response, "Failed to fetch contacts").await);
+        }
+        let page: ConnectionsPage = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse contacts: {}", e))?;
+
+        contacts.extend(
+            page.connections
+                .into_iter()
+                .filter(|p| !p.email_addresses.is_empty())
+                .map(Person::into_contact),
+        );
+        page_token = page.next_page_token;
+        if page_token.is_none() {
+            break;
+        }
+    }
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let result = save_contacts(&mut tx, contacts).await?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save contacts: {}", e))?;
+
+    Ok(result)
+}
+
+// Header names tried for each contact field when no mapping is given (compared
+// case-insensitively, ignoring spaces and punctuation). The Shopify customer
+// export uses "First Name"/"Last Name" and "Default Address ..." columns.
+const NAME_HEADERS: &[&str] = &[
+    "name",
+    "fullname",
+    "displayname",
+    "customername",
+    "customer",
+];
+const FIRST_NAME_HEADERS: &[&str] = &["firstname", "givenname", "forename"];
+const LAST_NAME_HEADERS: &[&str] = &["lastname", "familyname", "surname"];
+const EMAIL_HEADERS: &[&str] = &["email", "emailaddress", "email1value", "customeremail"];
+const PHONE_HEADERS: &[&str] = &[
+    "phone",
+    "phonenumber",
+    "mobile",
+    "mobilephone",
+    "phone1value",
+    "defaultaddressphone",
+];
+const ADDRESS_HEADERS: &[&str] = &[
+    "address",
+    "streetaddress",
+    "address1",
+    "defaultaddressaddress1",
+    "address1formatted",
+];
+
+// Which CSV column (by header text) holds each contact field. Leave a field
+// out to have it detected from common header names. Either `name` or
+// `first_name`/`last_name` must resolve, and `email` is required.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContactColumnMapping {
+    pub name: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub address: Option<String>,
+}
+
+// What the mapping step shows before importing: the file's headers, the
+// columns detected for each field, and the contacts those columns produce
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContactFilePreview {
+    pub headers: Vec<String>,
+    pub mapping: ContactColumnMapping,
+    pub contacts: Vec<ContactRecord>,
+    pub total_rows: usize,
+}
+
+// Contacts shown in a preview
+const PREVIEW_ROWS: usize = 20;
+
+// Column positions resolved against the CSV header row
+struct ContactColumns {
+    name: Option<usize>,
+    first_name: Option<usize>,
+    last_name: Option<usize>,
+    email: usize,
+    phone: Option<usize>,
+    address: Option<usize>,
+}
+
+fn resolve_contact_columns(
+    headers: &[String],
+    mapping: &ContactColumnMapping,
+) -> Result<ContactColumns, AppError> {
+    let columns = ContactColumns {
+        name: find_column(headers, mapping.name.as_ref(), NAME_HEADERS)?,
+        first_name: find_column(headers, mapping.first_name.as_ref(), FIRST_NAME_HEADERS)?,
+        last_name: find_column(headers, mapping.last_name.as_ref(), LAST_NAME_HEADERS)?,
+        email: find_column(headers, mapping.email.as_ref(), EMAIL_HEADERS)?
+            .ok_or("No email column found; map one explicitly")?,
+        phone: find_column(headers, mapping.phone.as_ref(), PHONE_HEADERS)?,
+        address: find_column(headers, mapping.address.as_ref(), ADDRESS_HEADERS)?,
+    };
+    if columns.name.is_none() && columns.first_name.is_none() && columns.last_name.is_none() {
+        return Err(AppError::Validation(
+            "No name column found; map one explicitly".to_string(),
+        ));
+    }
+    Ok(columns)
+}
+
+impl ContactColumns {
+    // The mapping as header text, for the preview
+    fn to_mapping(&self, headers: &[String]) -> ContactColumnMapping {
+        let header = |column: Option<usize>| column.and_then(|i| headers.get(i)).cloned();
+        ContactColumnMapping {
+            name: header(self.name),
+            first_name: header(self.first_name),
+            last_name: header(self.last_name),
+            email: header(Some(self.email)),
+            phone: header(self.phone),
+            address: header(self.address),
+        }
+    }
+
+    fn read(&self, row: &[String]) -> ContactRecord {
+        let name = cell(row, self.name).unwrap_or_else(|| {
+            [cell(row, self.first_name), cell(row, self.last_name)]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(" ")
+        });
+        ContactRecord {
+            name,
+            email: cell(row, Some(self.email)).unwrap_or_default(),
+            phone: cell(row, self.phone),
+            address: cell(row, self.address),
+        }
+    }
+}
+
+// Helper: Contacts from a CSV file, with the columns that were used
+fn read_contacts_csv(
+    path: &Path,
+    mapping: &ContactColumnMapping,
+) -> Result<(Vec<String>, ContactColumns, Vec<ContactRecord>), AppError> {
+    let mut rows = read_csv_rows(path)?.into_iter();
+    let headers = rows.next().ok_or("The file is empty")?;
+    let columns = resolve_contact_columns(&headers, mapping)?;
+    let contacts = rows
+        .filter(|row| row.iter().any(|value| !value.trim().is_empty()))
+        .map(|row| columns.read(&row))
+        .collect();
+    Ok((headers, columns, contacts))
+}
+
+// Helper: Undo vCard text escaping (\n, \, \; \\)
+fn unescape_vcard(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+// Helper: Decode a vCard 2.1 QUOTED-PRINTABLE value (=XX byte escapes)
+fn decode_quoted_printable(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'=' {
+            if let Some(Ok(byte)) = value
+                .get(i + 1..i + 3)
+                .map(|hex| u8::from_str_radix(hex, 16))
+            {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// Helper: Split a structured vCard value (N, ADR) on unescaped semicolons
+fn split_components(value: &str) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut escaped = false;
+    for c in value.chars() {
+        match c {
+            ';' if !escaped => parts.push(String::new()),
+            '\\' if !escaped => {
+                escaped = true;
+                parts.last_mut().unwrap().push(c);
+                continue;
+            }
+            _ => parts.last_mut().unwrap().push(c),
+        }
+        escaped = false;
+    }
+    parts
+        .iter()
+        .map(|part| unescape_vcard(part).trim().to_string())
+        .collect()
+}
+
+// Helper: Content lines of a vCard file with folded lines joined back up
+fn unfold_vcard_lines(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut soft_break = false;
+    for raw in text.lines() {
+        if let Some(last) = lines.last_mut() {
+            // vCard 3/4 fold with leading whitespace; 2.1 quoted-printable
+            // values end a line with "=" to continue it
+            if soft_break {
+                last.pop();
+                last.push_str(raw);
+                soft_break = last.ends_with('=');
+                continue;
+            }
+            if raw.starts_with(' ') || raw.starts_with('\t') {
+                last.push_str(&raw[1..]);
+                continue;
+            }
+        }
+        let line = raw.trim_start_matches('\u{feff}').to_string();
+        soft_break = line.to_ascii_uppercase().contains("QUOTED-PRINTABLE") && line.ends_with('=');
+        lines.push(line);
+    }
+    lines
+}
+
+// Helper: Contacts from a .vcf file. Each card's preferred (else first) email,
+// phone and address are used.
+fn parse_vcards(text: &str) -> Vec<ContactRecord> {
+    #[derive(Default)]
+    struct Card {
+        formatted_name: Option<String>,
+        structured_name: Option<String>,
+        email: Option<(bool, String)>,
+        phone: Option<(bool, String)>,
+        address: Option<(bool, String)>,
+    }
+
+    // Keep the first value of a property unless a later one is marked preferred
+    fn offer(slot: &mut Option<(bool, String)>, preferred: bool, value: String) {
+        if value.is_empty() {
+            return;
+        }
+        match slot {
+            Some((true, _)) => {}
+            Some((false, _)) if !preferred => {}
+            _ => *slot = Some((preferred, value)),
+        }
+    }
+
+    let mut contacts = Vec::new();
+    let mut card: Option<Card> = None;
+    for line in unfold_vcard_lines(text) {
+        let Some((head, value)) = line.split_once(':') else {
+            continue;
+        };
+        let mut params = head.split(';');
+        let property = params.next().unwrap_or_default().to_ascii_uppercase();
+        // Apple exports group properties as "item1.EMAIL"
+        let property = property.rsplit('.').next().unwrap_or_default();
+        let params: Vec<String> = params.map(|p| p.to_ascii_uppercase()).collect();
+        // "PREF", "PREF=1" (vCard 4) or a "pref" entry in a TYPE list
+        let preferred = params.iter().any(|p| {
+            p.starts_with("PREF")
+                || p.strip_prefix("TYPE=")
+                    .map(|types| types.split(',').any(|t| t == "PREF"))
+                    .unwrap_or(false)
+        });
+        let value = if params.iter().any(|p| p.contains("QUOTED-PRINTABLE")) {
+            decode_quoted_printable(value)
+        } else {
+            value.to_string()
+        };
+
+        match property {
+            "BEGIN" if value.trim().eq_ignore_ascii_case("VCARD") => card = Some(Card::default()),
+            "END" if value.trim().eq_ignore_ascii_case("VCARD") => {
+                if let Some(done) = card.take() {
+                    let name = done
+                        .formatted_name
+                        .or(done.structured_name)
+                        .unwrap_or_default();
+                    contacts.push(ContactRecord {
+                        name,
+                        email: done.email.map(|(_, v)| v).unwrap_or_default(),
+                        phone: done.phone.map(|(_, v)| v),
+                        address: done.address.map(|(_, v)| v),
+                    });
+                }
+            }
+            _ => {
+                let Some(card) = card.as_mut() else {
+                    continue;
+                };
+                match property {
+                    "FN" => {
+                        let name = unescape_vcard(&value).trim().to_string();
+                        if !name.is_empty() {
+                            card.formatted_name = Some(name);
+                        }
+                    }
+                    "N" => {
+                        // Family;Given;Additional;Prefix;Suffix
+                        let parts = split_components(&value);
+                        let name = [
+                            parts.get(3),
+                            parts.get(1),
+                            parts.get(2),
+                            parts.first(),
+                            parts.get(4),
+                        ]
+                        .into_iter()
+                        .flatten()
+                        .filter(|part| !part.is_empty())
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                        if !name.is_empty() {
+                            card.structured_name = Some(name);
+                        }
+                    }
+                    "EMAIL" => offer(
+                        &mut card.email,
+                        preferred,
+                        unescape_vcard(&value).trim().to_string(),
+                    ),
+                    "TEL" => offer(
+                        &mut card.phone,
+                        preferred,
+                        unescape_vcard(value.trim_start_matches("tel:"))
+                            .trim()
+                            .to_string(),
+                    ),
+                    "ADR" => {
+                        // PO box;Extended;Street;City;Region;Postal code;Country
+                        let address = split_components(&value)
+                            .into_iter()
+                            .filter(|part| !part.is_empty())
+                            .map(|part| part.replace('\n', ", "))
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        offer(&mut card.address, preferred, address);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    contacts
+}
+
+// Helper: True for vCard files, which need no column mapping
+fn is_vcard(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| matches!(ext.to_ascii_lowercase().as_str(), "vcf" | "vcard"))
+        .unwrap_or(false)
+}
+
+// Helper: Contacts from a .vcf or .csv file
+fn read_contacts_file(
+    path: &Path,
+    mapping: &ContactColumnMapping,
+) -> Result<ContactFilePreview, AppError> {
+    let (headers, mapping, contacts) = if is_vcard(path) {
+        let text =
+            std::fs::read(path).map_err(|e| format!("Failed to read contacts file: {}", e))?;
+        let contacts = parse_vcards(&String::from_utf8_lossy(&text));
+        (Vec::new(), ContactColumnMapping::default(), contacts)
+    } else {
+        let (headers, columns, contacts) = read_contacts_csv(path, mapping)?;
+        let mapping = columns.to_mapping(&headers);
+        (headers, mapping, contacts)
+    };
+    Ok(ContactFilePreview {
+        headers,
+        mapping,
+        total_rows: contacts.len(),
+        contacts,
+    })
+}
+
+// First step of a file import: headers and detected columns (CSV only) plus
+// the first contacts they produce, so the mapping can be checked or adjusted
+#[tauri::command]
+pub async fn preview_contacts_file(
+    path: String,
+    mapping: Option<ContactColumnMapping>,
+) -> Result<ContactFilePreview, AppError> {
+    let mut preview = read_contacts_file(Path::new(&path), &mapping.unwrap_or_default())?;
+    preview.contacts.truncate(PREVIEW_ROWS);
+    Ok(preview)
+}
+
+// Import customers from a .vcf file or a CSV export (phone contacts, Shopify
+// customers, ...), matching existing customers on email
+#[tauri::command]
+pub async fn import_contacts_file(
+    db: State<'_, Database>,
+    path: String,
+    mapping: Option<ContactColumnMapping>,
+) -> Result<ContactImportResult, AppError> {
+    let preview = read_contacts_file(Path::new(&path), &mapping.unwrap_or_default())?;
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let result = save_contacts(&mut tx, preview.contacts).await?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save contacts: {}", e))?;
+
+    Ok(result)
+}
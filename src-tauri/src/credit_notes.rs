@@ -0,0 +1,611 @@
+use chrono::{Datelike, Local};
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteConnection;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::{AppHandle, State};
+
+use crate::audit;
+use crate::currency::{currency_decimals, default_currency};
+use crate::db::Database;
+use crate::error::AppError;
+use crate::i18n::load_locale;
+use crate::invoice_numbers::format_invoice_number;
+use crate::invoice_template::{load_print_template, resolved_text, InvoiceStyle};
+use crate::invoices::{
+    date_part, item_table_header, letterhead, load_invoice, product_name, InvoiceData,
+    AMOUNT_RIGHT, PRICE_RIGHT, QTY_RIGHT,
+};
+use crate::order_status::{apply_transition, emit_transition, OrderEvent, OrderStatus};
+use crate::orders::fetch_order;
+use crate::payments::{balance_epsilon, insert_refund, Refund, RefundInput};
+use crate::pdf::{
+    safe_file_name, save_pdf, wrap_text, PdfWriter, MARGIN, MUTED_COLOR, PAGE_WIDTH, RULE_COLOR,
+    TEXT_COLOR,
+};
+use crate::totals::{calculate_totals, load_tax_settings, TotalsInput, TotalsLine};
+
+const CREDIT_NOTE_PATTERN: &str = "CN-{YYYY}{MM}-{seq:4}";
+
+const CREDIT_NOTE_COLUMNS: &str = "id, preorder_id, credit_note_number, invoice_number, \
+     reason_code, reason, subtotal, tax_amount, total_amount, issued_at";
+
+const CREDIT_NOTE_ITEM_COLUMNS: &str =
+    "id, credit_note_id, order_item_id, product_id, description, quantity, unit_price";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum CreditReason {
+    Returned,
+    Damaged,
+    NotDelivered,
+    PriceAdjustment,
+    Goodwill,
+    // Needs a written reason
+    Other,
+}
+
+impl CreditReason {
+    fn message_key(&self) -> &'static str {
+        match self {
+            CreditReason::Returned => "credit_note.reason.returned",
+            CreditReason::Damaged => "credit_note.reason.damaged",
+            CreditReason::NotDelivered => "credit_note.reason.not_delivered",
+            CreditReason::PriceAdjustment => "credit_note.reason.price_adjustment",
+            CreditReason::Goodwill => "credit_note.reason.goodwill",
+            CreditReason::Other => "credit_note.reason.other",
+        }
+    }
+}
+
+// A credited line. Prices are negative, as printed on the credit note.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CreditNoteItem {
+    pub id: i64,
+    pub credit_note_id: i64,
+    pub order_item_id: Option<i64>,
+    pub product_id: Option<i64>,
+    pub description: String,
+    pub quantity: i64,
+    pub unit_price: f64,
+}
+
+// Amounts are negative, as printed on the credit note
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CreditNote {
+    pub id: i64,
+    pub preorder_id: i64,
+    pub credit_note_number: String,
+    // The invoice this note credits
+    pub invoice_number: String,
+    pub reason_code: CreditReason,
+    pub reason: Option<String>,
+    pub subtotal: f64,
+    pub tax_amount: f64,
+    pub total_amount: f64,
+    pub issued_at: Option<String>,
+    #[sqlx(skip)]
+    pub items: Vec<CreditNoteItem>,
+}
+
+// One line to credit. For an invoice line (order_item_id), quantity defaults
+// to the whole line and unit_price to the invoiced price; a lower unit_price
+// credits part of the price. Other lines need a description and unit_price.
+// Prices here are positive: the amount to take off.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreditLineInput {
+    pub order_item_id: Option<i64>,
+    pub description: Option<String>,
+    pub quantity: Option<i64>,
+    pub unit_price: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreditNoteInput {
+    pub preorder_id: i64,
+    pub reason_code: CreditReason,
+    pub reason: Option<String>,
+    pub lines: Vec<CreditLineInput>,
+    // Pay back what the customer has now overpaid, by this method
+    pub refund_method: Option<String>,
+    pub refund_reference: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssuedCreditNote {
+    pub credit_note: CreditNote,
+    pub refund: Option<Refund>,
+}
+
+// A line ready to store, prices still positive
+struct CreditLine {
+    order_item_id: Option<i64>,
+    product_id: Option<i64>,
+    description: String,
+    quantity: i64,
+    unit_price: f64,
+}
+
+// Helper: Load credit notes (one, or all of an order's) with their lines
+async fn load_credit_notes(
+    conn: &mut SqliteConnection,
+    id: Option<i64>,
+    preorder_id: Option<i64>,
+) -> Result<Vec<CreditNote>, AppError> {
+    let mut notes = sqlx::query_as::<_, CreditNote>(&format!(
+        "SELECT {} FROM credit_notes WHERE (? IS NULL OR id = ?) \
+         AND (? IS NULL OR preorder_id = ?) ORDER BY issued_at, id",
+        CREDIT_NOTE_COLUMNS
+    ))
+    .bind(id)
+    .bind(id)
+    .bind(preorder_id)
+    .bind(preorder_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load credit notes: {}", e))?;
+
+    for note in &mut notes {
+        note.items = sqlx::query_as::<_, CreditNoteItem>(&format!(
+            "SELECT {} FROM credit_note_items WHERE credit_note_id = ? ORDER BY id",
+            CREDIT_NOTE_ITEM_COLUMNS
+        ))
+        .bind(note.id)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to load credit note items: {}", e))?;
+    }
+    Ok(notes)
+}
+
+async fn load_credit_note(conn: &mut SqliteConnection, id: i64) -> Result<CreditNote, AppError> {
+    load_credit_notes(conn, Some(id), None)
+        .await?
+        .pop()
+        .ok_or_else(|| AppError::NotFound(format!("Credit note {} not found", id)))
+}
+
+// Helper: Next credit note number, inside the caller's transaction so the
+// counter and the note commit together (see assign_invoice_number)
+async fn next_credit_note_number(conn: &mut SqliteConnection) -> Result<String, AppError> {
+    let today = Local::now().date_naive();
+    let seq = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO credit_note_sequences (year, last_value) VALUES (?, 1) \
+         ON CONFLICT(year) DO UPDATE SET last_value = last_value + 1 \
+         RETURNING last_value",
+    )
+    .bind(today.year())
+    .fetch_one(conn)
+    .await
+    .map_err(|e| format!("Failed to advance credit note sequence: {}", e))?;
+    format_invoice_number(CREDIT_NOTE_PATTERN, today, seq)
+}
+
+// Issue a credit note against an invoiced order: returned or damaged items,
+// or an adjustment to what was charged. What it credits comes off the
+// order's balance and revenue. Crediting an order that's already been paid
+// leaves the customer in credit; with refund_method set, that much is
+// refunded straight away. An invoiced order the credit settles moves to paid.
+#[tauri::command]
+pub async fn create_credit_note(
+    app: AppHandle,
+    db: State<'_, Database>,
+    credit_note: CreditNoteInput,
+) -> Result<IssuedCreditNote, AppError> {
+    let reason = credit_note
+        .reason
+        .as_deref()
+        .map(str::trim)
+        .filter(|reason| !reason.is_empty());
+    if credit_note.reason_code == CreditReason::Other && reason.is_none() {
+        return Err(AppError::Validation(
+            "Give a reason for the credit note".to_string(),
+        ));
+    }
+    if credit_note.lines.is_empty() {
+        return Err(AppError::Validation(
+            "A credit note needs at least one line".to_string(),
+        ));
+    }
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let order = fetch_order(&mut tx, credit_note.preorder_id).await?;
+    if order.deleted_at.is_some() {
+        return Err(AppError::NotFound(format!("Order {} not found", order.id)));
+    }
+    let invoice_number = order
+        .invoice_number
+        .clone()
+        .ok_or("Only invoiced orders can be credited")?;
+
+    // Quantities already credited per invoice line
+    let credited: HashMap<i64, i64> = sqlx::query_as::<_, (i64, i64)>(
+        "SELECT ci.order_item_id, SUM(ci.quantity) FROM credit_note_items ci \
+         JOIN credit_notes cn ON cn.id = ci.credit_note_id \
+         WHERE cn.preorder_id = ? AND ci.order_item_id IS NOT NULL GROUP BY ci.order_item_id",
+    )
+    .bind(order.id)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to load credit notes: {}", e))?
+    .into_iter()
+    .collect();
+
+    let currency = match &order.currency_code {
+        Some(code) => code.clone(),
+        None => default_currency(&mut tx).await,
+    };
+    let epsilon = balance_epsilon(&currency);
+
+    // Stored descriptions of deleted products read like the invoice's
+    let locale = load_locale(&mut *tx).await;
+    let mut lines = Vec::with_capacity(credit_note.lines.len());
+    let mut requested: HashMap<i64, i64> = HashMap::new();
+    for line in &credit_note.lines {
+        let credit = match line.order_item_id {
+            Some(item_id) => {
+                let item = order
+                    .items
+                    .iter()
+                    .find(|item| item.id == item_id)
+                    .ok_or_else(|| {
+                        AppError::Validation(format!("Order {} has no line {}", order.id, item_id))
+                    })?;
+                let quantity = line.quantity.unwrap_or(item.quantity);
+                let total = requested.entry(item_id).or_default();
+                *total += quantity;
+                let left = item.quantity - credited.get(&item_id).copied().unwrap_or(0);
+                if quantity <= 0 || *total > left {
+                    return Err(AppError::Validation(format!(
+                        "Can only credit up to {} of {}",
+                        left.max(0),
+                        product_name(locale, item)
+                    )));
+                }
+                let unit_price = line.unit_price.unwrap_or(item.unit_price);
+                if unit_price > item.unit_price + epsilon {
+                    return Err(AppError::Validation(format!(
+                        "Credit of {:.2} each is more than the invoiced {:.2}",
+                        unit_price, item.unit_price
+                    )));
+                }
+                CreditLine {
+                    order_item_id: Some(item_id),
+                    product_id: Some(item.product_id),
+                    description: line
+                        .description
+                        .as_deref()
+                        .map(str::trim)
+                        .filter(|d| !d.is_empty())
+                        .map(str::to_string)
+                        .unwrap_or_else(|| product_name(locale, item)),
+                    quantity,
+                    unit_price,
+                }
+            }
+            None => {
+                let description = line
+                    .description
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|d| !d.is_empty())
+                    .ok_or("Credit lines not tied to an invoice line need a description")?;
+                let unit_price = line.unit_price.ok_or_else(|| {
+                    AppError::Validation(format!("Give an amount to credit for {}", description))
+                })?;
+                let quantity = line.quantity.unwrap_or(1);
+                if quantity <= 0 {
+                    return Err(AppError::Validation(format!(
+                        "Invalid quantity: {}",
+                        quantity
+                    )));
+                }
+                CreditLine {
+                    order_item_id: None,
+                    product_id: None,
+                    description: description.to_string(),
+                    quantity,
+                    unit_price,
+                }
+            }
+        };
+        if !credit.unit_price.is_finite() || credit.unit_price <= 0.0 {
+            return Err(AppError::Validation(format!(
+                "Invalid credit amount: {}",
+                credit.unit_price
+            )));
+        }
+        lines.push(credit);
+    }
+
+    // Taxed the way the invoice was
+    let mut settings = load_tax_settings(&mut tx).await?;
+    settings.decimal_places = currency_decimals(&currency);
+    let totals = calculate_totals(
+        &TotalsInput {
+            items: lines
+                .iter()
+                .map(|line| TotalsLine {
+                    description: Some(line.description.clone()),
+                    quantity: line.quantity as f64,
+                    unit_price: line.unit_price,
+                    discount: None,
+                    tax_rate: None,
+                })
+                .collect(),
+            order_discount: None,
+            settings: None,
+        },
+        &settings,
+    )?;
+    let creditable = order.total_amount - order.amount_credited;
+    if totals.total > creditable + epsilon {
+        return Err(AppError::Validation(format!(
+            "Credit of {:.2} is more than the {:.2} left on invoice {}",
+            totals.total, creditable, invoice_number
+        )));
+    }
+
+    let number = next_credit_note_number(&mut tx).await?;
+    let id = sqlx::query(
+        "INSERT INTO credit_notes (preorder_id, credit_note_number, invoice_number, reason_code, \
+         reason, subtotal, tax_amount, total_amount) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(order.id)
+    .bind(&number)
+    .bind(&invoice_number)
+    .bind(credit_note.reason_code)
+    .bind(reason)
+    .bind(-totals.subtotal)
+    .bind(-totals.tax)
+    .bind(-totals.total)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to create credit note: {}", e))?
+    .last_insert_rowid();
+
+    for line in &lines {
+        sqlx::query(
+            "INSERT INTO credit_note_items (credit_note_id, order_item_id, product_id, \
+             description, quantity, unit_price) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(id)
+        .bind(line.order_item_id)
+        .bind(line.product_id)
+        .bind(&line.description)
+        .bind(line.quantity)
+        .bind(-line.unit_price)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to save credit note line: {}", e))?;
+    }
+
+    let created = load_credit_note(&mut tx, id).await?;
+    audit::record(&mut *tx, "credit_note", id, "create", None, Some(&created)).await?;
+
+    let owed = creditable - totals.total;
+    let overpaid = order.amount_paid - owed;
+    let refund = match credit_note.refund_method.as_deref() {
+        Some(method) if overpaid > epsilon => Some(
+            insert_refund(
+                &mut tx,
+                &RefundInput {
+                    preorder_id: order.id,
+                    credit_note_id: Some(id),
+                    amount: overpaid.min(totals.total),
+                    method: method.to_string(),
+                    refunded_at: None,
+                    reference: credit_note.refund_reference.clone(),
+                    notes: Some(format!("Credit note {}", number)),
+                },
+            )
+            .await?,
+        ),
+        _ => None,
+    };
+
+    // The credit may cover what was left to pay; a credit that wipes out an
+    // unpaid invoice doesn't make it paid
+    let settled = order.amount_paid > epsilon && owed - order.amount_paid <= epsilon;
+    let transition = if settled && OrderStatus::parse(&order.status)? == OrderStatus::Invoiced {
+        Some(apply_transition(&mut tx, order.id, OrderEvent::Pay).await?)
+    } else {
+        None
+    };
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save credit note: {}", e))?;
+
+    if let Some(transition) = transition {
+        emit_transition(&app, &transition);
+    }
+    Ok(IssuedCreditNote {
+        credit_note: created,
+        refund,
+    })
+}
+
+#[tauri::command]
+pub async fn list_credit_notes(
+    db: State<'_, Database>,
+    preorder_id: i64,
+) -> Result<Vec<CreditNote>, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    load_credit_notes(&mut conn, None, Some(preorder_id)).await
+}
+
+// Draw a credit note, with the invoice template's letterhead
+fn render_credit_note(
+    pdf: &mut PdfWriter,
+    style: &InvoiceStyle,
+    invoice: &InvoiceData,
+    note: &CreditNote,
+) {
+    let order = &invoice.order;
+    let locale = invoice.locale;
+    let label = |key: &str| locale.text(key);
+    let money = |amount: f64| invoice.money.format(amount);
+    let (_, business_details, footer_terms) = resolved_text(&style.template, invoice);
+
+    let left_bottom = letterhead(
+        pdf,
+        style,
+        business_details.as_deref(),
+        &label("credit_note.title"),
+        &note.credit_note_number,
+    );
+    pdf.text_right(
+        AMOUNT_RIGHT,
+        9.0,
+        false,
+        MUTED_COLOR,
+        &format!(
+            "{} {}",
+            label("invoice.issued"),
+            note.issued_at.as_deref().map(date_part).unwrap_or_default()
+        ),
+    );
+    pdf.advance(5.0);
+    pdf.text_right(
+        AMOUNT_RIGHT,
+        9.0,
+        false,
+        MUTED_COLOR,
+        &locale.format(
+            "credit_note.for_invoice",
+            &[("number", &note.invoice_number)],
+        ),
+    );
+    pdf.y = pdf.y.min(left_bottom);
+    pdf.advance(10.0);
+
+    pdf.text(MARGIN, 9.0, true, MUTED_COLOR, &label("invoice.bill_to"));
+    pdf.text(120.0, 9.0, true, MUTED_COLOR, &label("credit_note.reason"));
+    pdf.advance(5.5);
+    pdf.text(MARGIN, 11.0, true, TEXT_COLOR, &order.customer_name);
+    pdf.text(
+        120.0,
+        10.0,
+        false,
+        TEXT_COLOR,
+        &label(note.reason_code.message_key()),
+    );
+    pdf.advance(5.0);
+    pdf.text(MARGIN, 10.0, false, TEXT_COLOR, &order.customer_email);
+    for line in note
+        .reason
+        .iter()
+        .flat_map(|reason| wrap_text(reason, AMOUNT_RIGHT - 120.0, 9.0, false))
+    {
+        pdf.text(120.0, 9.0, false, MUTED_COLOR, &line);
+        pdf.advance(4.5);
+    }
+    pdf.advance(10.0);
+
+    item_table_header(pdf, locale);
+    for item in &note.items {
+        let name_lines = wrap_text(&item.description, QTY_RIGHT - MARGIN - 18.0, 10.0, false);
+        let height = name_lines.len() as f32 * 4.5 + 3.0;
+        if pdf.y - height < MARGIN {
+            pdf.new_page();
+            item_table_header(pdf, locale);
+        }
+        pdf.text_right(
+            QTY_RIGHT,
+            10.0,
+            false,
+            TEXT_COLOR,
+            &item.quantity.to_string(),
+        );
+        pdf.text_right(
+            PRICE_RIGHT,
+            10.0,
+            false,
+            TEXT_COLOR,
+            &money(item.unit_price),
+        );
+        pdf.text_right(
+            AMOUNT_RIGHT,
+            10.0,
+            false,
+            TEXT_COLOR,
+            &money(item.quantity as f64 * item.unit_price),
+        );
+        for (i, text) in name_lines.iter().enumerate() {
+            if i > 0 {
+                pdf.advance(4.5);
+            }
+            pdf.text(MARGIN, 10.0, false, TEXT_COLOR, text);
+        }
+        pdf.advance(2.0);
+        pdf.rule(RULE_COLOR, 0.4);
+        pdf.advance(5.5);
+    }
+
+    let mut rows = vec![(label("invoice.subtotal"), note.subtotal, false)];
+    if note.tax_amount < 0.0 {
+        rows.push((invoice.totals.tax_name.clone(), note.tax_amount, false));
+    }
+    rows.push((label("credit_note.total"), note.total_amount, true));
+    pdf.ensure_space(rows.len() as f32 * 6.0 + 4.0);
+    pdf.advance(2.0);
+    for (label, amount, bold) in rows {
+        pdf.text_right(PRICE_RIGHT, 10.0, bold, TEXT_COLOR, &label);
+        pdf.text_right(AMOUNT_RIGHT, 10.0, bold, TEXT_COLOR, &money(amount));
+        pdf.advance(6.0);
+    }
+
+    if let Some(terms) = footer_terms.as_deref().filter(|t| !t.trim().is_empty()) {
+        let lines = wrap_text(terms, PAGE_WIDTH - 2.0 * MARGIN, 8.5, false);
+        pdf.ensure_space(lines.len() as f32 * 4.0 + 12.0);
+        pdf.advance(10.0);
+        pdf.rule(RULE_COLOR, 0.4);
+        for line in lines {
+            pdf.advance(4.5);
+            pdf.text(MARGIN, 8.5, false, MUTED_COLOR, &line);
+        }
+    }
+}
+
+// Render a credit note to `dest_dir` as a PDF named after its number and
+// return the file's path
+#[tauri::command]
+pub async fn export_credit_note_pdf(
+    db: State<'_, Database>,
+    id: i64,
+    dest_dir: String,
+) -> Result<String, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let note = load_credit_note(&mut conn, id).await?;
+    let invoice = load_invoice(&mut conn, note.preorder_id).await?;
+    let style = InvoiceStyle::prepare(load_print_template(&mut conn).await?)?;
+    drop(conn);
+
+    let mut pdf = PdfWriter::new(&format!(
+        "{} {}",
+        invoice.locale.text("credit_note.title"),
+        note.credit_note_number
+    ))?;
+    render_credit_note(&mut pdf, &style, &invoice, &note);
+
+    let dir = PathBuf::from(&dest_dir);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create export folder: {}", e))?;
+    let path = dir.join(format!("{}.pdf", safe_file_name(&note.credit_note_number)));
+    save_pdf(&path, &pdf.finish()?)?;
+    Ok(path.to_string_lossy().to_string())
+}
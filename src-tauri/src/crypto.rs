@@ -0,0 +1,76 @@
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use argon2::Argon2;
+
+use crate::error::AppError;
+
+// Header written in front of every encrypted blob so the format can evolve
+const MAGIC: &[u8] = b"POTENC1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+pub const MIN_PASSPHRASE_LEN: usize = 8;
+
+// Helper: Derive a 256-bit key from a passphrase with Argon2id
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], AppError> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Failed to derive encryption key: {}", e))?;
+    Ok(key)
+}
+
+// Encrypt data with a user passphrase (AES-256-GCM, random salt and nonce)
+pub fn encrypt_with_passphrase(passphrase: &str, plaintext: &[u8]) -> Result<Vec<u8>, AppError> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(AppError::Validation(format!(
+            "Passphrase must be at least {} characters",
+            MIN_PASSPHRASE_LEN
+        )));
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+
+    let key = derive_key(passphrase, &salt)?;
+    let cipher = Aes256Gcm::new_from_slice(&key)
+        .map_err(|e| format!("Failed to initialise cipher: {}", e))?;
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| "Failed to encrypt data".to_string())?;
+
+    let mut out = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+// Decrypt data produced by encrypt_with_passphrase
+pub fn decrypt_with_passphrase(passphrase: &str, data: &[u8]) -> Result<Vec<u8>, AppError> {
+    let header_len = MAGIC.len() + SALT_LEN + NONCE_LEN;
+    if data.len() < header_len || !data.starts_with(MAGIC) {
+        return Err(AppError::Validation(
+            "Data is not a POTracker encrypted file".to_string(),
+        ));
+    }
+
+    let salt = &data[MAGIC.len()..MAGIC.len() + SALT_LEN];
+    let nonce = &data[MAGIC.len() + SALT_LEN..header_len];
+    let ciphertext = &data[header_len..];
+
+    let key = derive_key(passphrase, salt)?;
+    let cipher = Aes256Gcm::new_from_slice(&key)
+        .map_err(|e| format!("Failed to initialise cipher: {}", e))?;
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| {
+            AppError::Internal(
+                "Failed to decrypt data: wrong passphrase or corrupted file".to_string(),
+            )
+        })
+}
@@ -0,0 +1,429 @@
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tauri::State;
+use tokio::fs::File;
+use tokio::io::{AsyncWriteExt, BufWriter};
+
+use crate::bulk_orders::{matching_order_ids, OrderFilter};
+use crate::currency::default_currency;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::http::{self, SendWithRetry};
+use crate::money::format_amount;
+use crate::{FormResponsesData, GoogleFormDetails};
+
+// Lets Excel detect UTF-8 instead of falling back to the system code page
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+// Orders are loaded this many at a time while writing
+pub const EXPORT_BATCH_SIZE: usize = 200;
+
+pub const ORDER_HEADERS: [&str; 15] = [
+    "Order ID",
+    "Created",
+    "Customer",
+    "Email",
+    "Confirmation Code",
+    "Invoice Number",
+    "Status",
+    "Event",
+    "Currency",
+    "Items",
+    "Total",
+    "Paid",
+    "Balance",
+    "Paid At",
+    "Notes",
+];
+
+pub const ORDER_EXPORT_SELECT: &str =
+    "SELECT po.id, po.created_at, po.customer_name, po.customer_email, \
+     po.confirmation_code, po.invoice_number, COALESCE(po.status, 'pending') AS status, \
+     e.name AS event_name, po.currency_code, \
+     (SELECT group_concat(oi.quantity || ' x ' || COALESCE(p.name, 'Deleted product'), '; ') \
+      FROM order_items oi LEFT JOIN products p ON p.id = oi.product_id \
+      WHERE oi.preorder_id = po.id) AS items, \
+     po.total_amount, \
+     (SELECT COALESCE(SUM(amount), 0.0) FROM payments WHERE preorder_id = po.id) - \
+     (SELECT COALESCE(SUM(amount), 0.0) FROM refunds WHERE preorder_id = po.id) AS amount_paid, \
+     (SELECT COALESCE(-SUM(total_amount), 0.0) FROM credit_notes WHERE preorder_id = po.id) \
+     AS amount_credited, \
+     po.paid_at, po.notes \
+     FROM preorders po LEFT JOIN events e ON e.id = po.event_id";
+
+// One order flattened for spreadsheets
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct OrderExportRow {
+    pub id: i64,
+    pub created_at: Option<String>,
+    pub customer_name: String,
+    pub customer_email: String,
+    pub confirmation_code: String,
+    pub invoice_number: Option<String>,
+    pub status: String,
+    pub event_name: Option<String>,
+    pub currency_code: Option<String>,
+    // e.g. "2 x Brownies; 1 x Cheesecake"
+    pub items: Option<String>,
+    pub total_amount: f64,
+    // Net of refunds
+    pub amount_paid: f64,
+    // Taken off the total by credit notes
+    pub amount_credited: f64,
+    pub paid_at: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsvExportInfo {
+    pub path: String,
+    // Data rows written, not counting the header
+    pub rows: usize,
+}
+
+// Quote a field when it holds a separator, quote or line break (RFC 4180).
+// Text (not numbers) that Excel would run as a formula gets a leading apostrophe.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@']) && value.parse::<f64>().is_err() {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) || value.trim() != value {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+// Writes rows to a temp file next to the destination and moves it into place
+// on finish, so a failed export never leaves a half-written file behind
+struct CsvWriter {
+    out: BufWriter<File>,
+    partial: PathBuf,
+    dest: PathBuf,
+    rows: usize,
+}
+
+impl CsvWriter {
+    async fn create(dest_path: &str) -> Result<Self, AppError> {
+        let dest = PathBuf::from(dest_path);
+        let partial = dest.with_file_name(format!(
+            "{}.partial-{}",
+            dest.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| "export.csv".to_string()),
+            uuid::Uuid::new_v4()
+        ));
+        let file = File::create(&partial)
+            .await
+            .map_err(|e| format!("Failed to create export file: {}", e))?;
+        let mut out = BufWriter::new(file);
+        out.write_all(UTF8_BOM)
+            .await
+            .map_err(|e| format!("Failed to write export file: {}", e))?;
+        Ok(CsvWriter {
+            out,
+            partial,
+            dest,
+            rows: 0,
+        })
+    }
+
+    async fn write_record<S: AsRef<str>>(&mut self, fields: &[S]) -> Result<(), AppError> {
+        let mut line = fields
+            .iter()
+            .map(|field| csv_field(field.as_ref()))
+            .collect::<Vec<_>>()
+            .join(",");
+        line.push_str("\r\n");
+        self.out
+            .write_all(line.as_bytes())
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to write export file: {}", e)))
+    }
+
+    async fn write_row<S: AsRef<str>>(&mut self, fields: &[S]) -> Result<(), AppError> {
+        self.write_record(fields).await?;
+        self.rows += 1;
+        Ok(())
+    }
+
+    async fn finish(mut self) -> Result<CsvExportInfo, AppError> {
+        self.out
+            .flush()
+            .await
+            .map_err(|e| format!("Failed to write export file: {}", e))?;
+        drop(self.out);
+        tokio::fs::rename(&self.partial, &self.dest)
+            .await
+            .map_err(|e| format!("Failed to save export file: {}", e))?;
+        Ok(CsvExportInfo {
+            path: self.dest.to_string_lossy().to_string(),
+            rows: self.rows,
+        })
+    }
+}
+
+// Helper: Run an export, removing the temp file if anything fails
+async fn with_cleanup<F>(partial: &Path, export: F) -> Result<CsvExportInfo, AppError>
+where
+    F: std::future::Future<Output = Result<CsvExportInfo, AppError>>,
+{
+    let result = export.await;
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(partial).await;
+    }
+    result
+}
+
+// Write a header and rows to a CSV file, for exports that have their rows at hand
+pub async fn write_table(
+    dest_path: &str,
+    header: &[String],
+    rows: &[Vec<String>],
+) -> Result<CsvExportInfo, AppError> {
+    let mut writer = CsvWriter::create(dest_path).await?;
+    let partial = writer.partial.clone();
+    with_cleanup(&partial, async move {
+        writer.write_record(header).await?;
+        for row in rows {
+            writer.write_row(row).await?;
+        }
+        writer.finish().await
+    })
+    .await
+}
+
+// Helper: Flattened rows for a batch of order IDs
+pub async fn load_order_rows(db: &Database, ids: &[i64]) -> Result<Vec<OrderExportRow>, AppError> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let placeholders = vec!["?"; ids.len()].join(", ");
+    let sql = format!(
+        "{} WHERE po.id IN ({}) ORDER BY po.id",
+        ORDER_EXPORT_SELECT, placeholders
+    );
+    let mut query = sqlx::query_as::<_, OrderExportRow>(&sql);
+    for id in ids {
+        query = query.bind(id);
+    }
+    query
+        .fetch_all(&db.pool)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to load orders: {}", e)))
+}
+
+// Helper: Write orders in batches of IDs. Amounts are plain numbers with the
+// order currency's decimal places, so spreadsheets can sum the columns.
+async fn write_orders(
+    db: &Database,
+    writer: &mut CsvWriter,
+    ids: &[i64],
+    default_currency: &str,
+) -> Result<(), AppError> {
+    writer.write_record(&ORDER_HEADERS).await?;
+
+    for batch in ids.chunks(EXPORT_BATCH_SIZE) {
+        for row in load_order_rows(db, batch).await? {
+            let currency = row
+                .currency_code
+                .clone()
+                .unwrap_or_else(|| default_currency.to_string());
+            writer
+                .write_row(&[
+                    row.id.to_string(),
+                    row.created_at.unwrap_or_default(),
+                    row.customer_name,
+                    row.customer_email,
+                    row.confirmation_code,
+                    row.invoice_number.unwrap_or_default(),
+                    row.status,
+                    row.event_name.unwrap_or_default(),
+                    row.currency_code.unwrap_or_default(),
+                    row.items.unwrap_or_default(),
+                    format_amount(row.total_amount, &currency),
+                    format_amount(row.amount_paid, &currency),
+                    format_amount(
+                        row.total_amount - row.amount_credited - row.amount_paid,
+                        &currency,
+                    ),
+                    row.paid_at.unwrap_or_default(),
+                    row.notes.unwrap_or_default(),
+                ])
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+// Write orders (all live orders, or those matching `filter`) to a CSV file
+// that opens cleanly in Excel, one row per order
+#[tauri::command]
+pub async fn export_orders_csv(
+    db: State<'_, Database>,
+    filter: Option<OrderFilter>,
+    dest_path: String,
+) -> Result<CsvExportInfo, AppError> {
+    let filter = filter.unwrap_or_default();
+    let (ids, currency) = {
+        let mut conn = db
+            .pool
+            .acquire()
+            .await
+            .map_err(|e| format!("Failed to open database connection: {}", e))?;
+        (
+            matching_order_ids(&mut conn, &filter).await?,
+            default_currency(&mut conn).await,
+        )
+    };
+
+    let mut writer = CsvWriter::create(&dest_path).await?;
+    let partial = writer.partial.clone();
+    with_cleanup(&partial, async move {
+        write_orders(&db, &mut writer, &ids, &currency).await?;
+        writer.finish().await
+    })
+    .await
+}
+
+// Helper: Fetch every response of a form, following pagination
+async fn fetch_all_responses(
+    client: &Client,
+    access_token: &str,
+    form_id: &str,
+) -> Result<FormResponsesData, AppError> {
+    let mut all = Vec::new();
+    let mut page_token: Option<String> = None;
+    loop {
+        let mut request = client
+            .get(format!(
+                "https://forms.googleapis.com/v1/forms/{}/responses",
+                form_id
+            ))
+            .bearer_auth(access_token);
+        if let Some(token) = &page_token {
+            request = request.query(&[("pageToken", token)]);
+        }
+        let response = request
+            .send_with_retry()
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to get responses: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(http::google_error(response, "Failed to get responses").await);
+        }
+        let page = response
+            .json::<FormResponsesData>()
+            .await
+            .map_err(|e| format!("Failed to parse responses: {}", e))?;
+
+        all.extend(page.responses.unwrap_or_default());
+        match page.next_page_token {
+            Some(token) if !token.is_empty() => page_token = Some(token),
+            _ => break,
+        }
+    }
+    Ok(FormResponsesData {
+        responses: Some(all),
+        next_page_token: None,
+    })
+}
+
+// Write every response of a Google Form to a CSV file, one column per
+// question in form order plus the order each response was imported as
+#[tauri::command]
+pub async fn export_form_responses_csv(
+    db: State<'_, Database>,
+    access_token: String,
+    form_id: String,
+    dest_path: String,
+) -> Result<CsvExportInfo, AppError> {
+    let client = http::client();
+
+    let response = client
+        .get(format!("https://forms.googleapis.com/v1/forms/{}", form_id))
+        .bearer_auth(&access_token)
+        .send_with_retry()
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to get form details: {}", e)))?;
+    if !response.status().is_success() {
+        return Err(http::google_error(response, "Failed to get form details").await);
+    }
+    let details = response
+        .json::<GoogleFormDetails>()
+        .await
+        .map_err(|e| format!("Failed to parse form details: {}", e))?;
+
+    // (question ID, column title) for every item that collects an answer
+    let questions: Vec<(String, String)> = details
+        .items
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|item| {
+            item.question_item.map(|q| {
+                let title = item.title.unwrap_or_else(|| q.question.question_id.clone());
+                (q.question.question_id, title)
+            })
+        })
+        .collect();
+
+    let responses = fetch_all_responses(&client, &access_token, &form_id)
+        .await?
+        .responses
+        .unwrap_or_default();
+
+    let imported: HashMap<String, i64> = sqlx::query_as::<_, (String, i64)>(
+        "SELECT response_id, preorder_id FROM synced_responses \
+         WHERE form_id = ? AND preorder_id IS NOT NULL",
+    )
+    .bind(&form_id)
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| format!("Failed to load imported responses: {}", e))?
+    .into_iter()
+    .collect();
+
+    let mut writer = CsvWriter::create(&dest_path).await?;
+    let partial = writer.partial.clone();
+    with_cleanup(&partial, async move {
+        let mut headers = vec![
+            "Response ID".to_string(),
+            "Submitted".to_string(),
+            "Order ID".to_string(),
+        ];
+        headers.extend(questions.iter().map(|(_, title)| title.clone()));
+        writer.write_record(&headers).await?;
+
+        for response in responses {
+            let answers = response.answers.unwrap_or_default();
+            let mut row = vec![
+                response.response_id.clone(),
+                response.create_time,
+                imported
+                    .get(&response.response_id)
+                    .map(|id| id.to_string())
+                    .unwrap_or_default(),
+            ];
+            for (question_id, _) in &questions {
+                let value = answers
+                    .get(question_id)
+                    .and_then(|answer| answer.text_answers.as_ref())
+                    .map(|text| {
+                        text.answers
+                            .iter()
+                            .map(|a| a.value.as_str())
+                            .collect::<Vec<_>>()
+                            .join("; ")
+                    })
+                    .unwrap_or_default();
+                row.push(value);
+            }
+            writer.write_row(&row).await?;
+        }
+        writer.finish().await
+    })
+    .await
+}
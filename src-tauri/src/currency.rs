@@ -0,0 +1,322 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteConnection;
+use std::collections::BTreeMap;
+use tauri::State;
+
+use crate::db::Database;
+use crate::error::AppError;
+use crate::http;
+use crate::redact::redact;
+
+const ECB_DAILY_URL: &str = "https://www.ecb.europa.eu/stats/eurofxref/eurofxref-daily.xml";
+const ECB_SOURCE: &str = "ecb";
+const ECB_BASE: &str = "EUR";
+
+// ECB publishes once per working day; refetching more often gains nothing
+const RATE_CACHE_HOURS: i64 = 12;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExchangeRates {
+    pub base: String,
+    // Publication date of the rates
+    pub rate_date: String,
+    pub source: String,
+    pub fetched_at: String,
+    // Units of each currency per one unit of `base`
+    pub rates: BTreeMap<String, f64>,
+}
+
+// Validate and normalize an ISO 4217 code ("idr" -> "IDR")
+pub fn normalize_currency(code: &str) -> Result<String, AppError> {
+    let code = code.trim().to_uppercase();
+    if code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic()) {
+        Ok(code)
+    } else {
+        Err(AppError::Validation(format!(
+            "Invalid currency code: {}",
+            code
+        )))
+    }
+}
+
+// Decimal places amounts are shown and rounded to. IDR has minor units on
+// paper but is never invoiced with them in practice.
+pub fn currency_decimals(code: &str) -> i64 {
+    match code.to_uppercase().as_str() {
+        "BIF" | "CLP" | "DJF" | "GNF" | "IDR" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF"
+        | "UGX" | "VND" | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+        "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+        _ => 2,
+    }
+}
+
+// Helper: The app's default currency from settings. The settings table is
+// created by the frontend, so a database that hasn't been opened there yet
+// falls back to USD like the UI does.
+pub async fn default_currency(conn: &mut SqliteConnection) -> String {
+    sqlx::query_scalar::<_, Option<String>>("SELECT currency_code FROM app_settings WHERE id = 1")
+        .fetch_optional(conn)
+        .await
+        .ok()
+        .flatten()
+        .flatten()
+        .and_then(|code| normalize_currency(&code).ok())
+        .unwrap_or_else(|| "USD".to_string())
+}
+
+// Helper: Pull the publication date and EUR rates out of the ECB daily XML
+fn parse_ecb_rates(xml: &str) -> Result<(String, Vec<(String, f64)>), AppError> {
+    let attribute = |element: &str, name: &str| -> Option<String> {
+        let start = element.find(&format!("{}='", name))? + name.len() + 2;
+        let end = element[start..].find('\'')? + start;
+        Some(element[start..end].to_string())
+    };
+
+    let mut date = None;
+    let mut rates = Vec::new();
+    for element in xml.split("<Cube").skip(1) {
+        if let Some(time) = attribute(element, "time") {
+            date = Some(time);
+        }
+        if let (Some(currency), Some(rate)) =
+            (attribute(element, "currency"), attribute(element, "rate"))
+        {
+            let rate = rate
+                .parse::<f64>()
+                .map_err(|e| format!("Invalid ECB rate for {}: {}", currency, e))?;
+            rates.push((currency, rate));
+        }
+    }
+
+    match date {
+        Some(date) if !rates.is_empty() => Ok((date, rates)),
+        _ => Err(AppError::Validation(
+            "ECB response did not contain any exchange rates".to_string(),
+        )),
+    }
+}
+
+// Helper: Rates from the cache, rebased onto `base`
+async fn cached_rates(
+    conn: &mut SqliteConnection,
+    base: &str,
+) -> Result<Option<ExchangeRates>, AppError> {
+    let rows = sqlx::query_as::<_, (String, f64, String, String)>(
+        "SELECT quote_currency, rate, rate_date, fetched_at FROM exchange_rates \
+         WHERE base_currency = ? AND source = ?",
+    )
+    .bind(ECB_BASE)
+    .bind(ECB_SOURCE)
+    .fetch_all(conn)
+    .await
+    .map_err(|e| format!("Failed to load exchange rates: {}", e))?;
+
+    let Some((_, _, rate_date, fetched_at)) = rows.first().cloned() else {
+        return Ok(None);
+    };
+
+    let mut eur_rates: BTreeMap<String, f64> = rows
+        .into_iter()
+        .map(|(code, rate, _, _)| (code, rate))
+        .collect();
+    eur_rates.insert(ECB_BASE.to_string(), 1.0);
+
+    let base_rate = *eur_rates
+        .get(base)
+        .ok_or_else(|| AppError::Validation(format!("No exchange rate available for {}", base)))?;
+    let rates = eur_rates
+        .into_iter()
+        .map(|(code, rate)| (code, rate / base_rate))
+        .collect();
+
+    Ok(Some(ExchangeRates {
+        base: base.to_string(),
+        rate_date,
+        source: ECB_SOURCE.to_string(),
+        fetched_at,
+        rates,
+    }))
+}
+
+// Helper: Whether the cached rates are older than RATE_CACHE_HOURS
+async fn cache_is_stale(conn: &mut SqliteConnection) -> Result<bool, AppError> {
+    let fresh = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM exchange_rates WHERE source = ? \
+         AND fetched_at >= datetime('now', ?)",
+    )
+    .bind(ECB_SOURCE)
+    .bind(format!("-{} hours", RATE_CACHE_HOURS))
+    .fetch_one(conn)
+    .await
+    .map_err(|e| format!("Failed to check exchange rate cache: {}", e))?;
+    Ok(fresh == 0)
+}
+
+// Convert between currencies using cached rates. Never touches the network,
+// so order math doesn't fail offline once rates have been fetched.
+pub async fn convert(
+    conn: &mut SqliteConnection,
+    amount: f64,
+    from: &str,
+    to: &str,
+) -> Result<f64, AppError> {
+    let from = normalize_currency(from)?;
+    let to = normalize_currency(to)?;
+    if from == to {
+        return Ok(amount);
+    }
+
+    let rates = cached_rates(conn, &from).await?.ok_or_else(|| {
+        AppError::Validation("No exchange rates cached yet; fetch exchange rates first".to_string())
+    })?;
+    let rate = rates
+        .rates
+        .get(&to)
+        .ok_or_else(|| AppError::Validation(format!("No exchange rate available for {}", to)))?;
+    Ok(amount * rate)
+}
+
+// Units of `to` per unit of `from` from the cached rates, with the date the
+// rates were published (None when no conversion is needed)
+pub async fn exchange_rate(
+    conn: &mut SqliteConnection,
+    from: &str,
+    to: &str,
+) -> Result<(f64, Option<String>), AppError> {
+    let from = normalize_currency(from)?;
+    let to = normalize_currency(to)?;
+    if from == to {
+        return Ok((1.0, None));
+    }
+
+    let rates = cached_rates(conn, &from).await?.ok_or_else(|| {
+        AppError::Validation("No exchange rates cached yet; fetch exchange rates first".to_string())
+    })?;
+    let rate = rates
+        .rates
+        .get(&to)
+        .ok_or_else(|| AppError::Validation(format!("No exchange rate available for {}", to)))?;
+    Ok((*rate, Some(rates.rate_date)))
+}
+
+// CTE `fx(currency, factor)` with the factor converting each currency orders
+// were placed in into `to` (the default currency) at the cached rates. Fails
+// when a currency has no cached rate rather than summing it unconverted.
+pub async fn order_currency_factors(
+    conn: &mut SqliteConnection,
+    to: &str,
+) -> Result<String, AppError> {
+    let to = normalize_currency(to)?;
+    let codes = sqlx::query_scalar::<_, String>(
+        "SELECT DISTINCT UPPER(currency_code) FROM order_history WHERE currency_code IS NOT NULL",
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load order currencies: {}", e))?;
+
+    // Codes are validated letters and factors plain numbers, so both are
+    // safe to write into the SQL
+    let mut rows = vec![format!("('{}', 1.0)", to)];
+    for code in codes {
+        let code = normalize_currency(&code)?;
+        if code != to {
+            let (factor, _) = exchange_rate(&mut *conn, &code, &to).await?;
+            rows.push(format!("('{}', {:?})", code, factor));
+        }
+    }
+    Ok(format!(
+        "fx(currency, factor) AS (VALUES {})",
+        rows.join(", ")
+    ))
+}
+
+// Latest rates against `base` (default EUR), from the cache when it's fresh
+#[tauri::command]
+pub async fn fetch_exchange_rates(
+    db: State<'_, Database>,
+    base: Option<String>,
+    force: Option<bool>,
+) -> Result<ExchangeRates, AppError> {
+    let base = normalize_currency(base.as_deref().unwrap_or(ECB_BASE))?;
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+
+    if !force.unwrap_or(false) && !cache_is_stale(&mut conn).await? {
+        if let Some(rates) = cached_rates(&mut conn, &base).await? {
+            return Ok(rates);
+        }
+    }
+
+    let client = http::client();
+    let response = client
+        .get(ECB_DAILY_URL)
+        .send()
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to fetch exchange rates: {}", e)))?;
+
+    if !response.status().is_success() {
+        let status = response.status().as_u16();
+        let error_text = redact(&response.text().await.unwrap_or_default());
+        return Err(AppError::from_status(
+            status,
+            format!("Exchange rate API error: {}", error_text),
+        ));
+    }
+
+    let xml = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read exchange rates: {}", e))?;
+    let (rate_date, rates) = parse_ecb_rates(&xml)?;
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    for (currency, rate) in &rates {
+        sqlx::query(
+            "INSERT OR REPLACE INTO exchange_rates \
+             (base_currency, quote_currency, rate, rate_date, source, fetched_at) \
+             VALUES (?, ?, ?, ?, ?, CURRENT_TIMESTAMP)",
+        )
+        .bind(ECB_BASE)
+        .bind(currency)
+        .bind(rate)
+        .bind(&rate_date)
+        .bind(ECB_SOURCE)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to cache exchange rates: {}", e))?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to cache exchange rates: {}", e))?;
+
+    cached_rates(&mut conn, &base)
+        .await?
+        .ok_or_else(|| AppError::Validation("Exchange rate cache is empty".to_string()))
+}
+
+#[tauri::command]
+pub async fn convert_amount(
+    db: State<'_, Database>,
+    amount: f64,
+    from: String,
+    to: String,
+) -> Result<f64, AppError> {
+    if !amount.is_finite() {
+        return Err(AppError::Validation(format!("Invalid amount: {}", amount)));
+    }
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    convert(&mut conn, amount, &from, &to).await
+}
@@ -0,0 +1,220 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteConnection;
+use tauri::State;
+
+use crate::currency::{default_currency, order_currency_factors};
+use crate::db::Database;
+use crate::error::AppError;
+use crate::reports::sold_orders_cte;
+
+// Customers count as churned after this many months without an order, unless
+// the caller says otherwise
+const DEFAULT_CHURN_MONTHS: i64 = 6;
+const DEFAULT_TOP_LIMIT: i64 = 10;
+
+// Spend per customer (by email) over confirmed-or-later orders
+const PER_CUSTOMER_CTE: &str = ", per_customer AS ( \
+     SELECT customer_email, MAX(customer_name) AS customer_name, COUNT(*) AS order_count, \
+     SUM(revenue) AS total_spent, MAX(created_at) AS last_order_at \
+     FROM sold WHERE customer_email <> '' GROUP BY customer_email) ";
+
+// Listed customers, by their directory entry where there is one. Deleted
+// customers are left out so they don't get re-engagement emails.
+const CUSTOMER_SPEND_SELECT: &str = "SELECT c.id AS customer_id, \
+     COALESCE(c.name, pc.customer_name) AS name, pc.customer_email AS email, \
+     pc.order_count, pc.total_spent, pc.last_order_at \
+     FROM per_customer pc LEFT JOIN customers c ON c.email = pc.customer_email COLLATE NOCASE \
+     WHERE c.deleted_at IS NULL";
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CustomerSpend {
+    // None for customers missing from the directory
+    pub customer_id: Option<i64>,
+    pub name: String,
+    pub email: String,
+    pub order_count: i64,
+    // Less credit notes
+    pub total_spent: f64,
+    pub last_order_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+struct Totals {
+    customers: i64,
+    repeat_customers: i64,
+    orders: i64,
+    revenue: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomerAnalytics {
+    // Customers with at least one confirmed-or-later order
+    pub customers: i64,
+    // ... with two or more
+    pub repeat_customers: i64,
+    // Share of customers who ordered again, 0-1
+    pub repeat_rate: f64,
+    pub average_order_value: f64,
+    pub churn_months: i64,
+    // Customers whose last order is older than churn_months, biggest
+    // spenders first
+    pub churned: Vec<CustomerSpend>,
+    pub top_spenders: Vec<CustomerSpend>,
+}
+
+// Repeat-purchase rate, average order value, churned customers and top
+// spenders, computed in SQL over live and archived confirmed-or-later orders.
+// `churn_months` (default 6) is how long without an order counts as churned;
+// `top_limit` (default 10) caps the top spenders.
+#[tauri::command]
+pub async fn get_customer_analytics(
+    db: State<'_, Database>,
+    churn_months: Option<i64>,
+    top_limit: Option<i64>,
+) -> Result<CustomerAnalytics, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    customer_analytics(&mut conn, churn_months, top_limit).await
+}
+
+// Helper: get_customer_analytics on an open connection
+async fn customer_analytics(
+    conn: &mut SqliteConnection,
+    churn_months: Option<i64>,
+    top_limit: Option<i64>,
+) -> Result<CustomerAnalytics, AppError> {
+    let churn_months = churn_months.unwrap_or(DEFAULT_CHURN_MONTHS);
+    if !(1..=120).contains(&churn_months) {
+        return Err(AppError::Validation(
+            "Churn period must be between 1 and 120 months".to_string(),
+        ));
+    }
+    let top_limit = top_limit.unwrap_or(DEFAULT_TOP_LIMIT);
+    if !(1..=500).contains(&top_limit) {
+        return Err(AppError::Validation(
+            "Top spender limit must be between 1 and 500".to_string(),
+        ));
+    }
+    let no_bound: Option<&str> = None;
+    let currency = default_currency(&mut *conn).await;
+    let sold = sold_orders_cte(&order_currency_factors(&mut *conn, &currency).await?);
+
+    let totals = sqlx::query_as::<_, Totals>(&format!(
+        "{}{} SELECT COUNT(*) AS customers, \
+         COALESCE(SUM(order_count >= 2), 0) AS repeat_customers, \
+         COALESCE(SUM(order_count), 0) AS orders, COALESCE(SUM(total_spent), 0.0) AS revenue \
+         FROM per_customer",
+        sold, PER_CUSTOMER_CTE
+    ))
+    .bind(no_bound)
+    .bind(no_bound)
+    .bind(no_bound)
+    .bind(no_bound)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to compute customer totals: {}", e))?;
+
+    let churned = sqlx::query_as::<_, CustomerSpend>(&format!(
+        "{}{} {} AND pc.last_order_at < datetime('now', ?) \
+         ORDER BY pc.total_spent DESC, pc.last_order_at DESC",
+        sold, PER_CUSTOMER_CTE, CUSTOMER_SPEND_SELECT
+    ))
+    .bind(no_bound)
+    .bind(no_bound)
+    .bind(no_bound)
+    .bind(no_bound)
+    .bind(format!("-{} months", churn_months))
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to find churned customers: {}", e))?;
+
+    let top_spenders = sqlx::query_as::<_, CustomerSpend>(&format!(
+        "{}{} {} ORDER BY pc.total_spent DESC, pc.order_count DESC LIMIT ?",
+        sold, PER_CUSTOMER_CTE, CUSTOMER_SPEND_SELECT
+    ))
+    .bind(no_bound)
+    .bind(no_bound)
+    .bind(no_bound)
+    .bind(no_bound)
+    .bind(top_limit)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to find top spenders: {}", e))?;
+
+    let repeat_rate = if totals.customers > 0 {
+        totals.repeat_customers as f64 / totals.customers as f64
+    } else {
+        0.0
+    };
+    let average_order_value = if totals.orders > 0 {
+        totals.revenue / totals.orders as f64
+    } else {
+        0.0
+    };
+
+    Ok(CustomerAnalytics {
+        customers: totals.customers,
+        repeat_customers: totals.repeat_customers,
+        repeat_rate,
+        average_order_value,
+        churn_months,
+        churned,
+        top_spenders,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::archive_order;
+    use crate::db::test_pool;
+
+    // Helper: A fulfilled order for `email` created `created_at`
+    async fn fulfilled_order(
+        conn: &mut SqliteConnection,
+        email: &str,
+        total: f64,
+        created_at: &str,
+    ) -> i64 {
+        sqlx::query(
+            "INSERT INTO preorders (customer_name, customer_email, confirmation_code, status, \
+             total_amount, created_at) VALUES ('Customer', ?, ?, 'fulfilled', ?, datetime('now', ?))",
+        )
+        .bind(email)
+        .bind(format!("CODE-{}-{}", email, created_at))
+        .bind(total)
+        .bind(created_at)
+        .execute(conn)
+        .await
+        .unwrap()
+        .last_insert_rowid()
+    }
+
+    // Archiving old orders mustn't make their customers look new or churn
+    // them out of the figures
+    #[tokio::test]
+    async fn archived_orders_count_towards_customers() {
+        let pool = test_pool().await;
+        let mut conn = pool.acquire().await.unwrap();
+
+        let old = fulfilled_order(&mut conn, "ayu@example.com", 40.0, "-2 years").await;
+        fulfilled_order(&mut conn, "ayu@example.com", 10.0, "-1 days").await;
+        let churned = fulfilled_order(&mut conn, "budi@example.com", 25.0, "-2 years").await;
+        archive_order(&mut conn, old).await.unwrap();
+        archive_order(&mut conn, churned).await.unwrap();
+
+        let analytics = customer_analytics(&mut conn, None, None).await.unwrap();
+        assert_eq!(analytics.customers, 2);
+        assert_eq!(analytics.repeat_customers, 1);
+        assert_eq!(analytics.average_order_value, 25.0);
+
+        let churned: Vec<&str> = analytics.churned.iter().map(|c| c.email.as_str()).collect();
+        assert_eq!(churned, ["budi@example.com"]);
+        assert_eq!(analytics.top_spenders[0].email, "ayu@example.com");
+        assert_eq!(analytics.top_spenders[0].total_spent, 50.0);
+        assert_eq!(analytics.top_spenders[0].order_count, 2);
+    }
+}
@@ -0,0 +1,503 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::SqliteConnection;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, State};
+
+use crate::archive::table_columns;
+use crate::audit;
+use crate::customers::load_customer;
+use crate::data_export::{data_tables, select_where};
+use crate::db::Database;
+use crate::documents::{document_path, OrderDocument, DOCUMENT_COLUMNS};
+use crate::encryption;
+use crate::error::AppError;
+use crate::log_warning;
+use crate::models::Customer;
+use crate::session_lock;
+
+// Identifies the file as a POTracker customer data export
+const EXPORT_FORMAT: &str = "potracker-customer-data";
+const EXPORT_FILE_NAME: &str = "customer-data.json";
+const ATTACHMENTS_DIR: &str = "attachments";
+
+// Name the customer and their orders keep once erased
+const ERASED_NAME: &str = "Erased customer";
+
+// Queued messages to an erased customer are failed rather than sent
+const FAIL_PENDING: &str = "status = CASE WHEN status = 'pending' THEN 'failed' ELSE status END, \
+     last_error = CASE WHEN status = 'pending' THEN 'Customer erased' ELSE last_error END";
+
+// Everything stored about one customer, as column -> value maps per table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomerDataDump {
+    pub format: String,
+    pub customer_id: i64,
+    pub exported_at: String,
+    pub tables: BTreeMap<String, Vec<Map<String, Value>>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomerDataExport {
+    // Folder holding customer-data.json and an attachments folder
+    pub path: String,
+    // Rows per table
+    pub tables: BTreeMap<String, usize>,
+    pub attachments: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomerErasure {
+    pub customer_id: i64,
+    pub orders: usize,
+    pub archived_orders: usize,
+    pub quotes: usize,
+    pub recurring_orders: usize,
+    pub documents_deleted: usize,
+    // Payment proofs on Google Drive the orders no longer point to; they're
+    // left for the caller to trash there
+    pub drive_file_ids: Vec<String>,
+}
+
+// A customer and the IDs of everything filed under their email
+struct Subject {
+    customer: Customer,
+    orders: Vec<i64>,
+    archived_orders: Vec<i64>,
+    quotes: Vec<i64>,
+    recurring_orders: Vec<i64>,
+}
+
+impl Subject {
+    // Live and archived orders; an archived order keeps its ID, so audit
+    // entries and notes made before archiving still point at it
+    fn all_orders(&self) -> String {
+        id_list(&[self.orders.as_slice(), self.archived_orders.as_slice()].concat())
+    }
+}
+
+// Helper: IDs as an SQL list for IN. They're integers, so they go into the
+// statement directly.
+fn id_list(ids: &[i64]) -> String {
+    if ids.is_empty() {
+        return "(NULL)".to_string();
+    }
+    format!(
+        "({})",
+        ids.iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+// Helper: IDs of a table's rows for an email
+async fn ids_for_email(
+    conn: &mut SqliteConnection,
+    table: &str,
+    email: &str,
+) -> Result<Vec<i64>, AppError> {
+    sqlx::query_scalar::<_, i64>(&format!(
+        "SELECT id FROM {} WHERE customer_email = ? COLLATE NOCASE ORDER BY id",
+        table
+    ))
+    .bind(email)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to load {}: {}", table, e)))
+}
+
+async fn load_subject(conn: &mut SqliteConnection, customer_id: i64) -> Result<Subject, AppError> {
+    let customer = load_customer(&mut *conn, customer_id).await?;
+    Ok(Subject {
+        orders: ids_for_email(conn, "preorders", &customer.email).await?,
+        archived_orders: ids_for_email(conn, "archived_orders", &customer.email).await?,
+        quotes: ids_for_email(conn, "quotes", &customer.email).await?,
+        recurring_orders: ids_for_email(conn, "recurring_orders", &customer.email).await?,
+        customer,
+    })
+}
+
+// Helper: Condition selecting a table's rows about the customer, None for
+// tables that hold nothing about them
+fn export_condition(subject: &Subject, table: &str, columns: &[String]) -> Option<String> {
+    let orders = id_list(&subject.orders);
+    let condition = match table {
+        "customers" => format!("id = {}", subject.customer.id),
+        "preorders" => format!("id IN {}", orders),
+        "archived_orders" => format!("id IN {}", id_list(&subject.archived_orders)),
+        "archived_order_items" => {
+            format!("archived_order_id IN {}", id_list(&subject.archived_orders))
+        }
+        "quotes" => format!("id IN {}", id_list(&subject.quotes)),
+        "quote_items" => format!("quote_id IN {}", id_list(&subject.quotes)),
+        "recurring_orders" => format!("id IN {}", id_list(&subject.recurring_orders)),
+        "recurring_order_items" => format!(
+            "recurring_order_id IN {}",
+            id_list(&subject.recurring_orders)
+        ),
+        "credit_note_items" => format!(
+            "credit_note_id IN (SELECT id FROM credit_notes WHERE preorder_id IN {})",
+            orders
+        ),
+        "webhook_delivery_attempts" => format!(
+            "delivery_id IN (SELECT id FROM webhook_deliveries WHERE preorder_id IN {})",
+            orders
+        ),
+        "audit_log" => format!(
+            "(entity_type = 'customer' AND entity_id = {}) \
+             OR (entity_type = 'order' AND entity_id IN {}) \
+             OR (entity_type = 'quote' AND entity_id IN {}) \
+             OR (entity_type IN ('order_note', 'order_document') \
+             AND json_extract(COALESCE(after_json, before_json), '$.preorder_id') IN {})",
+            subject.customer.id,
+            subject.all_orders(),
+            id_list(&subject.quotes),
+            subject.all_orders()
+        ),
+        _ if columns.iter().any(|c| c == "preorder_id") => format!("preorder_id IN {}", orders),
+        _ => return None,
+    };
+    Some(condition)
+}
+
+// Helper: Documents attached to the customer's orders
+async fn load_documents(
+    conn: &mut SqliteConnection,
+    subject: &Subject,
+) -> Result<Vec<OrderDocument>, AppError> {
+    sqlx::query_as::<_, OrderDocument>(&format!(
+        "SELECT {} FROM order_documents WHERE preorder_id IN {} ORDER BY id",
+        DOCUMENT_COLUMNS,
+        id_list(&subject.orders)
+    ))
+    .fetch_all(conn)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to load documents: {}", e)))
+}
+
+// Helper: Copy each document into `dir`, prefixed with its ID so names from
+// different orders can't collide
+fn copy_documents(
+    app: &AppHandle,
+    documents: &[OrderDocument],
+    dir: &Path,
+) -> Result<usize, AppError> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| format!("Failed to create attachments folder: {}", e))?;
+    for document in documents {
+        let name = document
+            .file_name
+            .rsplit('/')
+            .next()
+            .unwrap_or(&document.file_name);
+        std::fs::copy(
+            document_path(app, document)?,
+            dir.join(format!("{}-{}", document.id, name)),
+        )
+        .map_err(|e| format!("Failed to copy document {}: {}", document.id, e))?;
+    }
+    Ok(documents.len())
+}
+
+// Write everything stored about a customer (their record, orders, quotes,
+// messages, payments, audit history and attached documents) to a new folder
+// in `dest_dir`, for a subject access request
+#[tauri::command]
+pub async fn export_customer_data(
+    app: AppHandle,
+    db: State<'_, Database>,
+    customer_id: i64,
+    dest_dir: String,
+) -> Result<CustomerDataExport, AppError> {
+    // One read transaction so the export is a consistent snapshot
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let subject = load_subject(&mut tx, customer_id).await?;
+    let mut dump = CustomerDataDump {
+        format: EXPORT_FORMAT.to_string(),
+        customer_id,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        tables: BTreeMap::new(),
+    };
+    for table in data_tables(&mut tx).await? {
+        let columns = table_columns(&mut tx, &table).await?;
+        let Some(condition) = export_condition(&subject, &table, &columns) else {
+            continue;
+        };
+        let mut rows = select_where(&mut tx, &table, &condition).await?;
+        if rows.is_empty() {
+            continue;
+        }
+        for row in rows.iter_mut() {
+            encryption::reveal_json(&table, row)?;
+        }
+        dump.tables.insert(table, rows);
+    }
+    let documents = load_documents(&mut tx, &subject).await?;
+    tx.rollback()
+        .await
+        .map_err(|e| format!("Failed to finish export: {}", e))?;
+
+    let folder = PathBuf::from(&dest_dir).join(format!(
+        "customer-{}-{}",
+        customer_id,
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    let attachments = copy_documents(&app, &documents, &folder.join(ATTACHMENTS_DIR))?;
+    let json = serde_json::to_vec_pretty(&dump)
+        .map_err(|e| format!("Failed to serialize customer data: {}", e))?;
+    std::fs::write(folder.join(EXPORT_FILE_NAME), json)
+        .map_err(|e| format!("Failed to write export file: {}", e))?;
+
+    Ok(CustomerDataExport {
+        path: folder.to_string_lossy().to_string(),
+        tables: dump
+            .tables
+            .iter()
+            .map(|(table, rows)| (table.clone(), rows.len()))
+            .collect(),
+        attachments,
+    })
+}
+
+// Anonymize a customer for a deletion request. Their record, orders, quotes
+// and recurring orders keep their amounts for the books but lose the name,
+// email and contact details; notes, attached documents and the content of
+// messages sent to them are removed, and the audit log is scrubbed to match.
+#[tauri::command]
+pub async fn erase_customer(
+    app: AppHandle,
+    db: State<'_, Database>,
+    customer_id: i64,
+) -> Result<CustomerErasure, AppError> {
+    session_lock::ensure_unlocked()?;
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let subject = load_subject(&mut tx, customer_id).await?;
+    let erased_email = format!("erased-{}@invalid", customer_id);
+    let orders = id_list(&subject.orders);
+    let documents = load_documents(&mut tx, &subject).await?;
+    let drive_file_ids = sqlx::query_scalar::<_, String>(&format!(
+        "SELECT proof_file_id FROM payments WHERE preorder_id IN {} AND proof_file_id IS NOT NULL",
+        orders
+    ))
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to load payment proofs: {}", e))?;
+
+    // Notes, documents, message content and free-text fields on the orders
+    let statements = [
+        format!("DELETE FROM order_notes WHERE preorder_id IN {}", orders),
+        format!(
+            "DELETE FROM order_documents WHERE preorder_id IN {}",
+            orders
+        ),
+        format!(
+            "DELETE FROM webhook_delivery_attempts WHERE delivery_id IN \
+             (SELECT id FROM webhook_deliveries WHERE preorder_id IN {})",
+            orders
+        ),
+        format!(
+            "DELETE FROM webhook_deliveries WHERE preorder_id IN {}",
+            orders
+        ),
+        format!(
+            "UPDATE sms_messages SET to_phone = '', body = '[erased]', {} WHERE preorder_id IN {}",
+            FAIL_PENDING, orders
+        ),
+        format!(
+            "UPDATE whatsapp_messages SET to_phone = '', variables = '[]', {} \
+             WHERE preorder_id IN {}",
+            FAIL_PENDING, orders
+        ),
+        format!(
+            "UPDATE telegram_messages SET text = '[erased]', {} WHERE preorder_id IN {}",
+            FAIL_PENDING, orders
+        ),
+        format!(
+            "UPDATE fulfillments SET notes = NULL WHERE preorder_id IN {}",
+            orders
+        ),
+        format!(
+            "UPDATE payments SET notes = NULL, proof_file_id = NULL, proof_file_name = NULL \
+             WHERE preorder_id IN {}",
+            orders
+        ),
+        format!(
+            "UPDATE refunds SET notes = NULL WHERE preorder_id IN {}",
+            orders
+        ),
+        format!(
+            "UPDATE audit_log SET \
+             before_json = json_replace(before_json, '$.body', '[erased]', '$.notes', NULL), \
+             after_json = json_replace(after_json, '$.body', '[erased]', '$.notes', NULL) \
+             WHERE entity_type IN ('order_note', 'order_document') \
+             AND json_extract(COALESCE(after_json, before_json), '$.preorder_id') IN {}",
+            subject.all_orders()
+        ),
+    ];
+    for statement in &statements {
+        sqlx::query(statement)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to erase customer data: {}", e))?;
+    }
+
+    sqlx::query(&format!(
+        "UPDATE order_emails SET to_email = ?, subject = '[erased]' WHERE preorder_id IN {}",
+        orders
+    ))
+    .bind(&erased_email)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to erase order emails: {}", e))?;
+
+    // The customer's records, under the placeholder name and email
+    let records = [
+        ("preorders", "", &subject.orders),
+        ("quotes", "", &subject.quotes),
+        (
+            "recurring_orders",
+            ", status = 'ended'",
+            &subject.recurring_orders,
+        ),
+    ];
+    for (table, extra, ids) in records {
+        sqlx::query(&format!(
+            "UPDATE {} SET customer_name = ?, customer_email = ?, notes = NULL{} WHERE id IN {}",
+            table,
+            extra,
+            id_list(ids)
+        ))
+        .bind(ERASED_NAME)
+        .bind(&erased_email)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to erase {}: {}", table, e))?;
+    }
+
+    // An archived order's snapshot is what a restore puts back
+    sqlx::query(&format!(
+        "UPDATE archived_orders SET customer_name = ?, customer_email = ?, \
+         snapshot_json = json_replace(snapshot_json, \
+         '$.order.customer_name', ?, '$.order.customer_email', ?, '$.order.notes', NULL, \
+         '$.children.order_notes', json('[]'), '$.children.order_emails', json('[]')) \
+         WHERE id IN {}",
+        id_list(&subject.archived_orders)
+    ))
+    .bind(ERASED_NAME)
+    .bind(&erased_email)
+    .bind(ERASED_NAME)
+    .bind(&erased_email)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to erase archived orders: {}", e))?;
+
+    sqlx::query(
+        "UPDATE customers SET name = ?, email = ?, phone = NULL, address = NULL, notes = NULL, \
+         contact_preference = 'email', deleted_at = COALESCE(deleted_at, CURRENT_TIMESTAMP), \
+         updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+    )
+    .bind(ERASED_NAME)
+    .bind(&erased_email)
+    .bind(customer_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to erase customer: {}", e))?;
+
+    // Earlier audit snapshots of the customer and their orders and quotes
+    let scrubs = [
+        (
+            format!("entity_type = 'customer' AND entity_id = {}", customer_id),
+            "'$.name', ?, '$.email', ?, '$.phone', NULL, '$.address', NULL, '$.notes', NULL",
+        ),
+        (
+            format!(
+                "(entity_type = 'order' AND entity_id IN {}) \
+                 OR (entity_type = 'quote' AND entity_id IN {})",
+                subject.all_orders(),
+                id_list(&subject.quotes)
+            ),
+            "'$.customer_name', ?, '$.customer_email', ?, '$.notes', NULL",
+        ),
+    ];
+    for (condition, paths) in scrubs {
+        sqlx::query(&format!(
+            "UPDATE audit_log SET before_json = json_replace(before_json, {}), \
+             after_json = json_replace(after_json, {}) WHERE {}",
+            paths, paths, condition
+        ))
+        .bind(ERASED_NAME)
+        .bind(&erased_email)
+        .bind(ERASED_NAME)
+        .bind(&erased_email)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to scrub audit log: {}", e))?;
+    }
+
+    // Alerts and notifications aren't linked to the customer, only mention
+    // them; a very short name would match unrelated text
+    let name = subject.customer.name.trim();
+    if name.chars().count() >= 3 {
+        for (table, column) in [("chat_alerts", "text"), ("desktop_notifications", "body")] {
+            sqlx::query(&format!(
+                "UPDATE {} SET {} = REPLACE(REPLACE({}, ?, ?), ?, ?)",
+                table, column, column
+            ))
+            .bind(name)
+            .bind(ERASED_NAME)
+            .bind(&subject.customer.email)
+            .bind(&erased_email)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to erase {}: {}", table, e))?;
+        }
+    }
+
+    // The entry for the erasure itself holds only the placeholder
+    let erased = load_customer(&mut *tx, customer_id).await?;
+    audit::record(
+        &mut *tx,
+        "customer",
+        customer_id,
+        "erase",
+        None,
+        Some(&erased),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save erased customer: {}", e))?;
+
+    // The rows are gone either way; a leftover file is only wasted space
+    for document in &documents {
+        if let Err(e) = document_path(&app, document).and_then(|path| {
+            std::fs::remove_file(path)
+                .map_err(|e| AppError::Internal(format!("Failed to remove file: {}", e)))
+        }) {
+            log_warning!("Failed to remove document {}: {}", document.id, e);
+        }
+    }
+
+    Ok(CustomerErasure {
+        customer_id,
+        orders: subject.orders.len(),
+        archived_orders: subject.archived_orders.len(),
+        quotes: subject.quotes.len(),
+        recurring_orders: subject.recurring_orders.len(),
+        documents_deleted: documents.len(),
+        drive_file_ids,
+    })
+}
@@ -0,0 +1,425 @@
+use sqlx::{Executor, QueryBuilder, Sqlite, SqliteConnection, SqlitePool};
+use tauri::State;
+
+use crate::audit;
+use crate::db::Database;
+use crate::encryption::{self, conceal_opt, reveal_customer};
+use crate::error::AppError;
+use crate::models::{validate_contact, Customer, CustomerInput, CustomerSummary, PurchaseOrder};
+use crate::orders::load_orders_for_email;
+
+const CUSTOMER_COLUMNS: &str = "id, name, email, phone, contact_preference, address, notes, \
+     created_at, updated_at, deleted_at";
+
+// Customers joined with their order totals; cancelled orders don't count towards
+// spend, and credit notes come off it
+const CUSTOMER_SUMMARY_SELECT: &str = "SELECT c.id, c.name, c.email, c.phone, c.contact_preference, c.address, c.notes, \
+     c.created_at, c.updated_at, c.deleted_at, COUNT(o.id) AS order_count, \
+     COALESCE(SUM(CASE WHEN o.status = 'cancelled' THEN 0 ELSE o.total_amount + \
+     (SELECT COALESCE(SUM(total_amount), 0.0) FROM credit_notes WHERE preorder_id = o.id) END), 0.0) \
+     AS total_spent, \
+     MAX(o.created_at) AS last_order_at \
+     FROM customers c LEFT JOIN preorders o \
+     ON o.customer_email = c.email COLLATE NOCASE AND o.deleted_at IS NULL";
+
+// Helper: Load a single customer by ID, including soft-deleted ones. Contact
+// details are as stored, encrypted if encryption is on; the audit log keeps
+// them that way.
+pub async fn load_customer<'e, E>(executor: E, id: i64) -> Result<Customer, AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    sqlx::query_as::<_, Customer>(&format!(
+        "SELECT {} FROM customers WHERE id = ?",
+        CUSTOMER_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| format!("Failed to load customer: {}", e))?
+    .ok_or_else(|| AppError::NotFound(format!("Customer {} not found", id)))
+}
+
+// Helper: Create the customer for an email, or refresh the name of an existing
+// one. A deleted customer who orders again is brought back.
+pub async fn upsert_customer(
+    conn: &mut SqliteConnection,
+    name: &str,
+    email: &str,
+) -> Result<i64, AppError> {
+    sqlx::query_scalar::<_, i64>(
+        "INSERT INTO customers (name, email) VALUES (?, ?) \
+         ON CONFLICT(email) DO UPDATE SET name = excluded.name, deleted_at = NULL, \
+         updated_at = CURRENT_TIMESTAMP \
+         RETURNING id",
+    )
+    .bind(name.trim())
+    .bind(email.trim())
+    .fetch_one(conn)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to save customer: {}", e)))
+}
+
+// Helper: A phone number in international form, digits only, as WhatsApp and
+// the SMS providers want it. Local numbers with a leading 0 get
+// `country_code`: "0812-3456-789" becomes "628123456789" for 62.
+pub fn normalize_phone(raw: &str, country_code: &str) -> Option<String> {
+    let digits: String = raw.chars().filter(|c| c.is_ascii_digit()).collect();
+    let international = if raw.trim_start().starts_with('+') {
+        digits
+    } else if let Some(local) = digits.strip_prefix("00") {
+        local.to_string()
+    } else if let Some(local) = digits.strip_prefix('0') {
+        format!("{}{}", country_code, local)
+    } else {
+        digits
+    };
+    (8..=15)
+        .contains(&international.len())
+        .then_some(international)
+}
+
+#[tauri::command]
+pub async fn list_customers(
+    db: State<'_, Database>,
+    include_deleted: Option<bool>,
+) -> Result<Vec<Customer>, AppError> {
+    let filter = if include_deleted.unwrap_or(false) {
+        ""
+    } else {
+        "WHERE deleted_at IS NULL"
+    };
+
+    sqlx::query_as::<_, Customer>(&format!(
+        "SELECT {} FROM customers {} ORDER BY name COLLATE NOCASE",
+        CUSTOMER_COLUMNS, filter
+    ))
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| format!("Failed to list customers: {}", e))?
+    .into_iter()
+    .map(reveal_customer)
+    .collect()
+}
+
+#[tauri::command]
+pub async fn get_customer(db: State<'_, Database>, id: i64) -> Result<Customer, AppError> {
+    reveal_customer(load_customer(&db.pool, id).await?)
+}
+
+#[tauri::command]
+pub async fn create_customer(
+    db: State<'_, Database>,
+    customer: CustomerInput,
+) -> Result<Customer, AppError> {
+    validate_contact(&customer.name, &customer.email)?;
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let result = sqlx::query(
+        "INSERT INTO customers (name, email, phone, contact_preference, address, notes) \
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(customer.name.trim())
+    .bind(customer.email.trim())
+    .bind(conceal_opt(customer.phone.as_deref())?)
+    .bind(customer.contact_preference.unwrap_or_default())
+    .bind(conceal_opt(customer.address.as_deref())?)
+    .bind(conceal_opt(customer.notes.as_deref())?)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to create customer: {}", e))?;
+
+    let created = load_customer(&mut *tx, result.last_insert_rowid()).await?;
+    audit::record(
+        &mut *tx,
+        "customer",
+        created.id,
+        "create",
+        None,
+        Some(&created),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save customer: {}", e))?;
+
+    reveal_customer(created)
+}
+
+#[tauri::command]
+pub async fn update_customer(
+    db: State<'_, Database>,
+    id: i64,
+    customer: CustomerInput,
+) -> Result<Customer, AppError> {
+    validate_contact(&customer.name, &customer.email)?;
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_customer(&mut *tx, id).await?;
+
+    sqlx::query(
+        "UPDATE customers SET name = ?, email = ?, phone = ?, \
+         contact_preference = COALESCE(?, contact_preference), address = ?, notes = ?, \
+         updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+    )
+    .bind(customer.name.trim())
+    .bind(customer.email.trim())
+    .bind(conceal_opt(customer.phone.as_deref())?)
+    .bind(customer.contact_preference)
+    .bind(conceal_opt(customer.address.as_deref())?)
+    .bind(conceal_opt(customer.notes.as_deref())?)
+    .bind(id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to update customer: {}", e))?;
+
+    let after = load_customer(&mut *tx, id).await?;
+    audit::record(
+        &mut *tx,
+        "customer",
+        id,
+        "update",
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save customer: {}", e))?;
+
+    reveal_customer(after)
+}
+
+// Helper: Set or clear a customer's deleted_at, logging the change
+async fn set_customer_deleted(
+    conn: &mut SqliteConnection,
+    id: i64,
+    deleted: bool,
+    action: &str,
+) -> Result<Customer, AppError> {
+    let before = load_customer(&mut *conn, id).await?;
+
+    sqlx::query(
+        "UPDATE customers SET deleted_at = CASE WHEN ? THEN CURRENT_TIMESTAMP ELSE NULL END, \
+         updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+    )
+    .bind(deleted)
+    .bind(id)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to {} customer: {}", action, e))?;
+
+    let after = load_customer(&mut *conn, id).await?;
+    audit::record(
+        &mut *conn,
+        "customer",
+        id,
+        action,
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
+    Ok(after)
+}
+
+// Customers are soft-deleted; their orders and audit history stay intact
+#[tauri::command]
+pub async fn delete_customer(db: State<'_, Database>, id: i64) -> Result<String, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    set_customer_deleted(&mut tx, id, true, "delete").await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save customer: {}", e))?;
+
+    Ok("Customer deleted".to_string())
+}
+
+#[tauri::command]
+pub async fn restore_customer(db: State<'_, Database>, id: i64) -> Result<Customer, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let restored = set_customer_deleted(&mut tx, id, false, "restore").await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save customer: {}", e))?;
+
+    reveal_customer(restored)
+}
+
+// Helper: IDs of customers whose phone number contains `term`, decrypting
+// each number in turn
+async fn customers_with_phone_like(pool: &SqlitePool, term: &str) -> Result<Vec<i64>, AppError> {
+    let phones = sqlx::query_as::<_, (i64, String)>(
+        "SELECT id, phone FROM customers WHERE deleted_at IS NULL AND phone IS NOT NULL",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to search customers: {}", e))?;
+
+    let term = term.to_lowercase();
+    let mut ids = Vec::new();
+    for (id, phone) in phones {
+        if encryption::reveal(phone)?.to_lowercase().contains(&term) {
+            ids.push(id);
+        }
+    }
+    Ok(ids)
+}
+
+// Search by name, email or phone; an empty query lists the top customers by spend
+#[tauri::command]
+pub async fn search_customers(
+    db: State<'_, Database>,
+    query: Option<String>,
+    limit: Option<i64>,
+) -> Result<Vec<CustomerSummary>, AppError> {
+    let mut builder = QueryBuilder::<Sqlite>::new(CUSTOMER_SUMMARY_SELECT);
+    builder.push(" WHERE c.deleted_at IS NULL");
+    let term = query.as_deref().map(str::trim).unwrap_or_default();
+    if !term.is_empty() {
+        let pattern = format!("%{}%", term.replace('%', "\\%").replace('_', "\\_"));
+        builder
+            .push(" AND (c.name LIKE ")
+            .push_bind(pattern.clone())
+            .push(" ESCAPE '\\' OR c.email LIKE ")
+            .push_bind(pattern.clone())
+            .push(" ESCAPE '\\'");
+        if encryption::status().enabled {
+            // Encrypted phone numbers can't be matched in SQL
+            let ids = customers_with_phone_like(&db.pool, term).await?;
+            if !ids.is_empty() {
+                builder.push(" OR c.id IN (");
+                let mut separated = builder.separated(", ");
+                for id in ids {
+                    separated.push_bind(id);
+                }
+                separated.push_unseparated(")");
+            }
+        } else {
+            builder
+                .push(" OR c.phone LIKE ")
+                .push_bind(pattern)
+                .push(" ESCAPE '\\'");
+        }
+        builder.push(")");
+    }
+    builder
+        .push(" GROUP BY c.id ORDER BY total_spent DESC, c.name COLLATE NOCASE LIMIT ")
+        .push_bind(limit.unwrap_or(50).clamp(1, 500));
+
+    builder
+        .build_query_as::<CustomerSummary>()
+        .fetch_all(&db.pool)
+        .await
+        .map_err(|e| format!("Failed to search customers: {}", e))?
+        .into_iter()
+        .map(|summary| {
+            Ok(CustomerSummary {
+                customer: reveal_customer(summary.customer)?,
+                ..summary
+            })
+        })
+        .collect()
+}
+
+// Order history for a customer
+#[tauri::command]
+pub async fn get_customer_orders(
+    db: State<'_, Database>,
+    customer_id: i64,
+) -> Result<Vec<PurchaseOrder>, AppError> {
+    let customer = load_customer(&db.pool, customer_id).await?;
+    load_orders_for_email(&db.pool, &customer.email).await
+}
+
+// Fold duplicate customers into one: their orders move over to the primary's
+// email and name, contact details fill any gaps, and the duplicates are deleted
+#[tauri::command]
+pub async fn merge_customers(
+    db: State<'_, Database>,
+    primary_id: i64,
+    duplicate_ids: Vec<i64>,
+) -> Result<Customer, AppError> {
+    if duplicate_ids.contains(&primary_id) {
+        return Err(AppError::Validation(
+            "A customer can't be merged into itself".to_string(),
+        ));
+    }
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let primary = load_customer(&mut *tx, primary_id).await?;
+
+    for id in &duplicate_ids {
+        let duplicate = load_customer(&mut *tx, *id).await?;
+
+        sqlx::query(
+            "UPDATE preorders SET customer_name = ?, customer_email = ? \
+             WHERE customer_email = ? COLLATE NOCASE",
+        )
+        .bind(&primary.name)
+        .bind(&primary.email)
+        .bind(&duplicate.email)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to move customer orders: {}", e))?;
+
+        sqlx::query(
+            "UPDATE customers SET phone = COALESCE(phone, ?), address = COALESCE(address, ?), \
+             notes = COALESCE(notes, ?), updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+        )
+        .bind(&duplicate.phone)
+        .bind(&duplicate.address)
+        .bind(&duplicate.notes)
+        .bind(primary_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to merge customer details: {}", e))?;
+
+        set_customer_deleted(&mut tx, duplicate.id, true, "merge").await?;
+    }
+
+    let merged = load_customer(&mut *tx, primary_id).await?;
+    audit::record(
+        &mut *tx,
+        "customer",
+        primary_id,
+        "merge",
+        Some(&primary),
+        Some(&merged),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save merged customer: {}", e))?;
+
+    reveal_customer(merged)
+}
@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::currency::{default_currency, order_currency_factors};
+use crate::db::Database;
+use crate::error::AppError;
+
+// Statuses of orders that count as sales (confirmed or further along)
+pub const SOLD_STATUSES: &str = "('confirmed', 'deposit_paid', 'invoiced', 'paid', 'fulfilled')";
+
+// Statuses the frontend and backend use for orders not yet confirmed
+const UNCONFIRMED_STATUSES: &str = "('pending', 'sent', 'draft')";
+
+const TOP_PRODUCT_LIMIT: i64 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DashboardPeriod {
+    Today,
+    // Last 7 days
+    Week,
+    // Last 30 days
+    Month,
+    // Last 365 days
+    Year,
+    All,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TopProduct {
+    pub product_id: i64,
+    pub product_name: String,
+    pub quantity: i64,
+    // Less what credit notes took back on these lines
+    pub revenue: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+struct OrderCounts {
+    order_count: i64,
+    pending_orders: i64,
+    confirmed_orders: i64,
+    fulfilled_orders: i64,
+    cancelled_orders: i64,
+    revenue: f64,
+    amount_paid: f64,
+    unpaid_balance: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DashboardStats {
+    pub period: DashboardPeriod,
+    // Start of the period (UTC), None for all time
+    pub since: Option<String>,
+    // Active products; not limited to the period
+    pub total_products: i64,
+    pub order_count: i64,
+    pub pending_orders: i64,
+    // Confirmed or further along (invoiced, paid, fulfilled)
+    pub confirmed_orders: i64,
+    pub cancelled_orders: i64,
+    // Total of confirmed-or-later orders, less credit notes
+    pub revenue: f64,
+    // Less refunds
+    pub amount_paid: f64,
+    // What confirmed-or-later orders still owe
+    pub unpaid_balance: f64,
+    // Fulfilled share of confirmed-or-later orders, 0-1
+    pub fulfillment_rate: f64,
+    pub top_products: Vec<TopProduct>,
+}
+
+impl DashboardPeriod {
+    // SQLite datetime() modifier for the start of the period
+    fn modifier(&self) -> Option<&'static str> {
+        match self {
+            DashboardPeriod::Today => Some("start of day"),
+            DashboardPeriod::Week => Some("-7 days"),
+            DashboardPeriod::Month => Some("-30 days"),
+            DashboardPeriod::Year => Some("-365 days"),
+            DashboardPeriod::All => None,
+        }
+    }
+}
+
+// Headline numbers for the dashboard, computed in SQL over live and archived
+// orders created within `period` (default: all time). Deleted orders are left
+// out.
+#[tauri::command]
+pub async fn get_dashboard_stats(
+    db: State<'_, Database>,
+    period: Option<DashboardPeriod>,
+) -> Result<DashboardStats, AppError> {
+    let period = period.unwrap_or(DashboardPeriod::All);
+
+    let since = match period.modifier() {
+        Some(modifier) => Some(
+            sqlx::query_scalar::<_, String>("SELECT datetime('now', ?)")
+                .bind(modifier)
+                .fetch_one(&db.pool)
+                .await
+                .map_err(|e| format!("Failed to compute period start: {}", e))?,
+        ),
+        None => None,
+    };
+
+    let total_products = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM products WHERE COALESCE(is_active, 1) = 1",
+    )
+    .fetch_one(&db.pool)
+    .await
+    .map_err(|e| format!("Failed to count products: {}", e))?;
+
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let currency = default_currency(&mut conn).await;
+    let fx = order_currency_factors(&mut conn, &currency).await?;
+
+    // Archived orders count too; amounts are converted into the default currency
+    let counts = sqlx::query_as::<_, OrderCounts>(&format!(
+        "WITH {fx}, period_orders AS ( \
+             SELECT h.id, h.status, \
+             (h.total_amount - h.amount_credited) * COALESCE(fx.factor, 1.0) AS total_amount, \
+             h.amount_paid * COALESCE(fx.factor, 1.0) AS paid \
+             FROM order_history h LEFT JOIN fx ON fx.currency = UPPER(h.currency_code) \
+             WHERE (? IS NULL OR h.created_at >= ?)) \
+         SELECT COUNT(*) AS order_count, \
+         COALESCE(SUM(status IN {unconfirmed}), 0) AS pending_orders, \
+         COALESCE(SUM(status IN {sold}), 0) AS confirmed_orders, \
+         COALESCE(SUM(status = 'fulfilled'), 0) AS fulfilled_orders, \
+         COALESCE(SUM(status = 'cancelled'), 0) AS cancelled_orders, \
+         COALESCE(SUM(CASE WHEN status IN {sold} THEN total_amount END), 0.0) AS revenue, \
+         COALESCE(SUM(paid), 0.0) AS amount_paid, \
+         COALESCE(SUM(CASE WHEN status IN {sold} AND total_amount > paid \
+                      THEN total_amount - paid END), 0.0) AS unpaid_balance \
+         FROM period_orders",
+        fx = fx,
+        unconfirmed = UNCONFIRMED_STATUSES,
+        sold = SOLD_STATUSES
+    ))
+    .bind(&since)
+    .bind(&since)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to compute order stats: {}", e))?;
+
+    let top_products = sqlx::query_as::<_, TopProduct>(&format!(
+        "WITH {fx} SELECT i.product_id, COALESCE(p.name, 'Deleted product') AS product_name, \
+         SUM(i.quantity) AS quantity, \
+         SUM((i.quantity * i.unit_price - i.amount_credited) * COALESCE(fx.factor, 1.0)) AS revenue \
+         FROM order_item_history i JOIN order_history h ON h.id = i.preorder_id \
+         LEFT JOIN fx ON fx.currency = UPPER(h.currency_code) \
+         LEFT JOIN products p ON p.id = i.product_id \
+         WHERE (? IS NULL OR h.created_at >= ?) AND h.status IN {sold} \
+         GROUP BY i.product_id ORDER BY quantity DESC, revenue DESC LIMIT ?",
+        fx = fx,
+        sold = SOLD_STATUSES
+    ))
+    .bind(&since)
+    .bind(&since)
+    .bind(TOP_PRODUCT_LIMIT)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to compute top products: {}", e))?;
+
+    let fulfillment_rate = if counts.confirmed_orders > 0 {
+        counts.fulfilled_orders as f64 / counts.confirmed_orders as f64
+    } else {
+        0.0
+    };
+
+    Ok(DashboardStats {
+        period,
+        since,
+        total_products,
+        order_count: counts.order_count,
+        pending_orders: counts.pending_orders,
+        confirmed_orders: counts.confirmed_orders,
+        cancelled_orders: counts.cancelled_orders,
+        revenue: counts.revenue,
+        amount_paid: counts.amount_paid,
+        unpaid_balance: counts.unpaid_balance,
+        fulfillment_rate,
+        top_products,
+    })
+}
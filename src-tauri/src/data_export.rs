@@ -0,0 +1,454 @@
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::{SqliteConnection, SqlitePool};
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+use tauri::State;
+
+use crate::archive::{insert_rows, quote_identifier, table_columns};
+use crate::db::Database;
+use crate::encryption;
+use crate::error::AppError;
+use crate::migrations::latest_version;
+
+// Identifies the file as a POTracker dump
+const DUMP_FORMAT: &str = "potracker-data";
+
+// Bumped when the layout of the dump itself changes (not the schema).
+// Version 2 added the signature.
+const DUMP_FORMAT_VERSION: i64 = 2;
+
+const SIGNATURE_ALGORITHM: &str = "ed25519";
+
+// Tables left out of dumps and left alone on import: migration bookkeeping,
+// credentials, this install's encryption and signing keys, and the search
+// index (its triggers rebuild it from the data)
+const EXCLUDED_TABLES: &[&str] = &[
+    "schema_migrations",
+    "google_auth",
+    "smtp_settings",
+    "encryption_settings",
+    "data_signing_key",
+];
+const EXCLUDED_PREFIXES: &[&str] = &["sqlite_", "search_index"];
+
+// Every table's rows as column -> value maps. Rows are imported by column
+// name, so dumps from an older schema load into a newer one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataDump {
+    pub format: String,
+    pub format_version: i64,
+    pub schema_version: i64,
+    pub exported_at: String,
+    pub tables: BTreeMap<String, Vec<Map<String, Value>>>,
+    // Missing from dumps made before signing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<DumpSignature>,
+}
+
+// Ed25519 signature over the rest of the dump, as compact JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DumpSignature {
+    pub algorithm: String,
+    // Base64
+    pub public_key: String,
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataTransferInfo {
+    pub path: String,
+    pub schema_version: i64,
+    // Rows per table
+    pub tables: BTreeMap<String, usize>,
+    // Tables in the file this database doesn't have (import only)
+    pub skipped_tables: Vec<String>,
+    // Base64 public key the file is signed with, None if it's unsigned
+    pub signer: Option<String>,
+    // Whether that's this install's key. A file signed elsewhere is intact,
+    // but only as trustworthy as the install that made it.
+    pub signed_by_this_install: bool,
+}
+
+// Helper: User tables, parents before the tables that reference them
+pub async fn data_tables(conn: &mut SqliteConnection) -> Result<Vec<String>, AppError> {
+    let names = sqlx::query_scalar::<_, String>(
+        "SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name",
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to list tables: {}", e))?;
+    let names: Vec<String> = names
+        .into_iter()
+        .filter(|name| {
+            !EXCLUDED_TABLES.contains(&name.as_str())
+                && !EXCLUDED_PREFIXES.iter().any(|p| name.starts_with(p))
+        })
+        .collect();
+
+    let mut parents: BTreeMap<String, HashSet<String>> = BTreeMap::new();
+    for name in &names {
+        let referenced = sqlx::query_scalar::<_, String>(
+            "SELECT DISTINCT \"table\" FROM pragma_foreign_key_list(?)",
+        )
+        .bind(name)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to read foreign keys of {}: {}", name, e))?;
+        parents.insert(
+            name.clone(),
+            referenced
+                .into_iter()
+                .filter(|parent| parent != name && names.contains(parent))
+                .collect(),
+        );
+    }
+
+    // Repeatedly take every table whose parents are already placed; anything
+    // left in a reference cycle goes last in name order
+    let mut ordered: Vec<String> = Vec::with_capacity(names.len());
+    while ordered.len() < names.len() {
+        let ready: Vec<String> = parents
+            .iter()
+            .filter(|(name, deps)| {
+                !ordered.contains(name) && deps.iter().all(|d| ordered.contains(d))
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        if ready.is_empty() {
+            ordered.extend(
+                names
+                    .iter()
+                    .filter(|n| !ordered.contains(n))
+                    .cloned()
+                    .collect::<Vec<_>>(),
+            );
+            break;
+        }
+        ordered.extend(ready);
+    }
+    Ok(ordered)
+}
+
+// Helper: This install's public signing key, None before the first export
+async fn own_public_key(pool: &SqlitePool) -> Result<Option<String>, AppError> {
+    sqlx::query_scalar::<_, String>("SELECT public_key FROM data_signing_key WHERE id = 1")
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to load signing key: {}", e)))
+}
+
+// Helper: This install's signing key, created on first use
+async fn signing_key(pool: &SqlitePool) -> Result<SigningKey, AppError> {
+    if own_public_key(pool).await?.is_none() {
+        let mut seed = [0u8; 32];
+        OsRng.fill_bytes(&mut seed);
+        let key = SigningKey::from_bytes(&seed);
+        sqlx::query(
+            "INSERT OR IGNORE INTO data_signing_key (id, secret_key, public_key) VALUES (1, ?, ?)",
+        )
+        .bind(encryption::conceal(&STANDARD.encode(seed))?)
+        .bind(STANDARD.encode(key.verifying_key().as_bytes()))
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to save signing key: {}", e))?;
+    }
+
+    let secret =
+        sqlx::query_scalar::<_, String>("SELECT secret_key FROM data_signing_key WHERE id = 1")
+            .fetch_one(pool)
+            .await
+            .map_err(|e| format!("Failed to load signing key: {}", e))?;
+    let seed: [u8; 32] = STANDARD
+        .decode(encryption::reveal(secret)?)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| AppError::Validation("Signing key is damaged".to_string()))?;
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+// Helper: The bytes a signature covers: the dump without its signature, as
+// compact JSON. Parsing a dump and serializing it again gives the same bytes,
+// so an import can recompute them.
+fn signed_bytes(dump: &DataDump) -> Result<Vec<u8>, AppError> {
+    serde_json::to_vec(dump)
+        .map_err(|e| AppError::Internal(format!("Failed to serialize data: {}", e)))
+}
+
+// Helper: Check and strip a dump's signature, returning the signer's public
+// key; None for an unsigned dump
+fn verify_signature(dump: &mut DataDump) -> Result<Option<String>, AppError> {
+    let Some(signed) = dump.signature.take() else {
+        return Ok(None);
+    };
+    if signed.algorithm != SIGNATURE_ALGORITHM {
+        return Err(AppError::Validation(format!(
+            "Unsupported signature algorithm: {}",
+            signed.algorithm
+        )));
+    }
+    let malformed = || "The file's signature is malformed".to_string();
+    let public_key: [u8; 32] = STANDARD
+        .decode(&signed.public_key)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(malformed)?;
+    let key = VerifyingKey::from_bytes(&public_key).map_err(|_| malformed())?;
+    let signature = STANDARD
+        .decode(&signed.signature)
+        .ok()
+        .and_then(|bytes| Signature::from_slice(&bytes).ok())
+        .ok_or_else(malformed)?;
+
+    key.verify_strict(&signed_bytes(dump)?, &signature)
+        .map_err(|_| {
+            "The file has been changed or damaged since it was exported: its signature doesn't match"
+                .to_string()
+        })?;
+    Ok(Some(signed.public_key))
+}
+
+// Helper: Read a dump and check its format, version and signature
+fn read_dump(path: &str) -> Result<(DataDump, Option<String>), AppError> {
+    let raw = std::fs::read(path).map_err(|e| format!("Failed to read import file: {}", e))?;
+    let mut dump: DataDump = serde_json::from_slice(&raw)
+        .map_err(|e| format!("Not a POTracker data file, or it is incomplete: {}", e))?;
+    if dump.format != DUMP_FORMAT {
+        return Err(AppError::Validation(
+            "Not a POTracker data file".to_string(),
+        ));
+    }
+    if dump.format_version > DUMP_FORMAT_VERSION || dump.schema_version > latest_version() {
+        return Err(AppError::Validation(format!(
+            "This file was exported by a newer version of POTracker (schema {}); update the app first",
+            dump.schema_version
+        )));
+    }
+    let signer = verify_signature(&mut dump)?;
+    Ok((dump, signer))
+}
+
+// Helper: Every row of a table as a JSON object
+async fn select_all(
+    conn: &mut SqliteConnection,
+    table: &str,
+) -> Result<Vec<Map<String, Value>>, AppError> {
+    select_where(conn, table, "1").await
+}
+
+// Helper: Rows of a table matching an SQL condition, each as a JSON object
+pub async fn select_where(
+    conn: &mut SqliteConnection,
+    table: &str,
+    condition: &str,
+) -> Result<Vec<Map<String, Value>>, AppError> {
+    let columns = table_columns(&mut *conn, table).await?;
+    if columns.is_empty() {
+        return Ok(Vec::new());
+    }
+    let fields = columns
+        .iter()
+        .map(|c| format!("'{}', {}", c.replace('\'', "''"), quote_identifier(c)))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let rows = sqlx::query_scalar::<_, String>(&format!(
+        "SELECT json_object({}) FROM {} WHERE {} ORDER BY rowid",
+        fields,
+        quote_identifier(table),
+        condition
+    ))
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to read {}: {}", table, e))?;
+
+    rows.iter()
+        .map(|row| {
+            serde_json::from_str(row)
+                .map_err(|e| AppError::Internal(format!("Failed to read {}: {}", table, e)))
+        })
+        .collect()
+}
+
+// Write every table (except credentials) to a versioned JSON file, for moving
+// data to another install or another tool. The file is signed with this
+// install's key so an import can detect tampering or truncation.
+#[tauri::command]
+pub async fn export_all_data(
+    db: State<'_, Database>,
+    path: String,
+) -> Result<DataTransferInfo, AppError> {
+    let key = signing_key(&db.pool).await?;
+
+    // One read transaction so the dump is a consistent snapshot
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let mut dump = DataDump {
+        format: DUMP_FORMAT.to_string(),
+        format_version: DUMP_FORMAT_VERSION,
+        schema_version: latest_version(),
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        tables: BTreeMap::new(),
+        signature: None,
+    };
+    for table in data_tables(&mut tx).await? {
+        // Dumps hold plaintext; another install has its own key
+        let mut rows = select_all(&mut tx, &table).await?;
+        for row in rows.iter_mut() {
+            encryption::reveal_json(&table, row)?;
+        }
+        dump.tables.insert(table, rows);
+    }
+    tx.rollback()
+        .await
+        .map_err(|e| format!("Failed to finish export: {}", e))?;
+
+    let signer = STANDARD.encode(key.verifying_key().as_bytes());
+    dump.signature = Some(DumpSignature {
+        algorithm: SIGNATURE_ALGORITHM.to_string(),
+        public_key: signer.clone(),
+        signature: STANDARD.encode(key.sign(&signed_bytes(&dump)?).to_bytes()),
+    });
+    let json =
+        serde_json::to_vec_pretty(&dump).map_err(|e| format!("Failed to serialize data: {}", e))?;
+    let dest = PathBuf::from(&path);
+    let partial = dest.with_extension(format!("partial-{}", uuid::Uuid::new_v4()));
+    let written = std::fs::write(&partial, json)
+        .and_then(|_| std::fs::rename(&partial, &dest))
+        .map_err(|e| AppError::Internal(format!("Failed to write export file: {}", e)));
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e);
+    }
+
+    Ok(DataTransferInfo {
+        path,
+        schema_version: dump.schema_version,
+        tables: dump
+            .tables
+            .iter()
+            .map(|(table, rows)| (table.clone(), rows.len()))
+            .collect(),
+        skipped_tables: Vec::new(),
+        signer: Some(signer),
+        signed_by_this_install: true,
+    })
+}
+
+// Check a dump from export_all_data without importing it: its signature, who
+// signed it, and what it holds. Fails if the file was changed or cut short.
+#[tauri::command]
+pub async fn verify_data_export(
+    db: State<'_, Database>,
+    path: String,
+) -> Result<DataTransferInfo, AppError> {
+    let (dump, signer) = read_dump(&path)?;
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let tables = data_tables(&mut conn).await?;
+
+    Ok(DataTransferInfo {
+        path,
+        schema_version: dump.schema_version,
+        tables: dump
+            .tables
+            .iter()
+            .map(|(table, rows)| (table.clone(), rows.len()))
+            .collect(),
+        skipped_tables: dump
+            .tables
+            .keys()
+            .filter(|table| !tables.contains(table))
+            .cloned()
+            .collect(),
+        signed_by_this_install: signer.is_some() && signer == own_public_key(&db.pool).await?,
+        signer,
+    })
+}
+
+// Replace all data with the contents of a dump from export_all_data. Runs in
+// one transaction; credentials and settings not in the dump are kept. The
+// signature is checked before anything is touched; unsigned dumps from older
+// versions are refused unless `allow_unsigned` is set.
+#[tauri::command]
+pub async fn import_all_data(
+    db: State<'_, Database>,
+    path: String,
+    allow_unsigned: Option<bool>,
+) -> Result<DataTransferInfo, AppError> {
+    let (dump, signer) = read_dump(&path)?;
+    if signer.is_none() && !allow_unsigned.unwrap_or(false) {
+        return Err(AppError::Validation(
+            "This file isn't signed, so it can't be checked for changes or damage".to_string(),
+        ));
+    }
+    let signed_by_this_install = signer.is_some() && signer == own_public_key(&db.pool).await?;
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    // References are checked once everything is in place
+    sqlx::query("PRAGMA defer_foreign_keys = ON")
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to prepare import: {}", e))?;
+
+    let tables = data_tables(&mut tx).await?;
+    for table in tables.iter().rev().filter(|t| dump.tables.contains_key(*t)) {
+        sqlx::query(&format!("DELETE FROM {}", quote_identifier(table)))
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to clear {}: {}", table, e))?;
+    }
+
+    let mut counts = BTreeMap::new();
+    for table in &tables {
+        let Some(rows) = dump.tables.get(table) else {
+            continue;
+        };
+        // Insert triggers on earlier tables (customer directory, price
+        // history) may have added rows here; the dump has the real ones
+        sqlx::query(&format!("DELETE FROM {}", quote_identifier(table)))
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to clear {}: {}", table, e))?;
+        let mut rows = rows.clone();
+        for row in rows.iter_mut() {
+            encryption::conceal_json(table, row)?;
+        }
+        insert_rows(&mut tx, table, &rows).await?;
+        counts.insert(table.clone(), rows.len());
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save imported data: {}", e))?;
+
+    Ok(DataTransferInfo {
+        path,
+        schema_version: dump.schema_version,
+        tables: counts,
+        skipped_tables: dump
+            .tables
+            .keys()
+            .filter(|table| !tables.contains(table))
+            .cloned()
+            .collect(),
+        signer,
+        signed_by_this_install,
+    })
+}
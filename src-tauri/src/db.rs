@@ -0,0 +1,66 @@
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::error::AppError;
+
+// Same file tauri-plugin-sql opens for "sqlite:potracker.db" (inside the
+// active profile's directory)
+pub const DATABASE_FILE: &str = "potracker.db";
+
+// Backend connection pool, registered as Tauri managed state
+pub struct Database {
+    pub pool: SqlitePool,
+}
+
+// Path of the active profile's SQLite database, shared with the frontend
+pub fn database_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+    Ok(crate::profiles::active_config_dir(app)?.join(DATABASE_FILE))
+}
+
+// Open the database and bring its schema up to date
+pub async fn connect(app: &AppHandle) -> Result<Database, AppError> {
+    let path = database_path(app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create app config dir: {}", e))?;
+    }
+
+    let options = SqliteConnectOptions::new()
+        .filename(&path)
+        .create_if_missing(true)
+        .foreign_keys(true)
+        .busy_timeout(Duration::from_secs(10));
+
+    let pool = SqlitePoolOptions::new()
+        .max_connections(4)
+        .connect_with(options)
+        .await
+        .map_err(|e| format!("Failed to open database: {}", e))?;
+
+    crate::migrations::run_migrations(&pool).await?;
+
+    Ok(Database { pool })
+}
+
+// Helper: Fresh in-memory database with every migration applied. A single
+// connection that never expires, since each one would get its own empty
+// in-memory database.
+#[cfg(test)]
+pub async fn test_pool() -> SqlitePool {
+    let options = SqliteConnectOptions::new()
+        .filename(":memory:")
+        .foreign_keys(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect_with(options)
+        .await
+        .expect("in-memory database");
+    crate::migrations::run_migrations(&pool)
+        .await
+        .expect("migrations apply");
+    pool
+}
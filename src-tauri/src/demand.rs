@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::db::Database;
+use crate::error::AppError;
+
+// Per-product totals over the orders in scope. Cancelled and deleted orders
+// are left out; confirmed orders have already been taken out of stock, so only
+// the unconfirmed quantity still has to come from stock on hand.
+pub const DEMAND_SELECT: &str =
+    "SELECT oi.product_id, COALESCE(p.name, 'Deleted product') AS product_name, \
+     p.supplier_id, p.stock_quantity, \
+     COUNT(DISTINCT po.id) AS order_count, \
+     SUM(oi.quantity) AS requested_quantity, \
+     COALESCE(SUM(CASE WHEN COALESCE(po.status, 'pending') IN ('pending', 'sent', 'draft') \
+       THEN oi.quantity END), 0) AS unconfirmed_quantity, \
+     COALESCE((SELECT SUM(si.quantity) FROM supplier_order_items si \
+       JOIN supplier_orders so ON so.id = si.supplier_order_id \
+       WHERE si.product_id = oi.product_id AND so.status = 'ordered'), 0) AS on_order_quantity \
+     FROM order_items oi \
+     JOIN preorders po ON po.id = oi.preorder_id \
+     LEFT JOIN products p ON p.id = oi.product_id \
+     WHERE po.deleted_at IS NULL AND COALESCE(po.status, 'pending') != 'cancelled'";
+
+// Limits orders to a form's imported responses and/or an event; each value is bound twice
+const ORDER_SCOPE: &str = "AND (? IS NULL OR po.id IN \
+     (SELECT preorder_id FROM synced_responses WHERE form_id = ?)) \
+     AND (? IS NULL OR po.event_id = ?)";
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct DemandLine {
+    pub product_id: i64,
+    pub product_name: String,
+    pub supplier_id: Option<i64>,
+    // None when stock isn't tracked for the product
+    pub stock_quantity: Option<i64>,
+    pub order_count: i64,
+    // Everything asked for, including orders already confirmed or fulfilled
+    pub requested_quantity: i64,
+    // Asked for on orders not yet confirmed, so not yet taken from stock
+    pub unconfirmed_quantity: i64,
+    // On supplier orders placed but not received (all customers, not just this scope)
+    pub on_order_quantity: i64,
+    // Unconfirmed quantity that neither stock nor incoming supplier orders
+    // cover; None for untracked products
+    #[sqlx(skip)]
+    pub shortfall: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DemandReport {
+    pub form_id: Option<String>,
+    pub event_id: Option<i64>,
+    pub order_count: i64,
+    pub products: Vec<DemandLine>,
+    // Sum of the per-product shortfalls
+    pub total_shortfall: i64,
+}
+
+// Helper: Work out each line's shortfall and return their sum
+pub fn fill_shortfalls(lines: &mut [DemandLine]) -> i64 {
+    let mut total = 0;
+    for line in lines {
+        line.shortfall = line.stock_quantity.map(|stock| {
+            (line.unconfirmed_quantity - stock.max(0) - line.on_order_quantity).max(0)
+        });
+        total += line.shortfall.unwrap_or(0);
+    }
+    total
+}
+
+// Sum what customers asked for per product, across the orders imported from
+// a Google Form or the orders of a campaign (event), and compare it against
+// stock to see what still has to be bought.
+#[tauri::command]
+pub async fn aggregate_demand(
+    db: State<'_, Database>,
+    form_id: Option<String>,
+    event_id: Option<i64>,
+) -> Result<DemandReport, AppError> {
+    let form_id = form_id
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty());
+    if form_id.is_some() == event_id.is_some() {
+        return Err(AppError::Validation(
+            "Give either a form or a campaign to aggregate demand for".to_string(),
+        ));
+    }
+
+    let mut products = sqlx::query_as::<_, DemandLine>(&format!(
+        "{} {} GROUP BY oi.product_id ORDER BY product_name, oi.product_id",
+        DEMAND_SELECT, ORDER_SCOPE
+    ))
+    .bind(&form_id)
+    .bind(&form_id)
+    .bind(event_id)
+    .bind(event_id)
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| format!("Failed to aggregate demand: {}", e))?;
+
+    let total_shortfall = fill_shortfalls(&mut products);
+
+    let order_count = sqlx::query_scalar::<_, i64>(&format!(
+        "SELECT COUNT(*) FROM preorders po \
+         WHERE po.deleted_at IS NULL AND COALESCE(po.status, 'pending') != 'cancelled' {}",
+        ORDER_SCOPE
+    ))
+    .bind(&form_id)
+    .bind(&form_id)
+    .bind(event_id)
+    .bind(event_id)
+    .fetch_one(&db.pool)
+    .await
+    .map_err(|e| format!("Failed to count orders: {}", e))?;
+
+    Ok(DemandReport {
+        form_id,
+        event_id,
+        order_count,
+        products,
+        total_shortfall,
+    })
+}
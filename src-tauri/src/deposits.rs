@@ -0,0 +1,329 @@
+use std::path::PathBuf;
+use tauri::State;
+
+use crate::audit;
+use crate::currency::currency_decimals;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::invoice_numbers::next_invoice_number;
+use crate::invoice_template::{load_print_template, resolved_text, InvoiceStyle};
+use crate::invoices::{
+    date_part, invoice_for_order, item_table_header, letterhead, load_invoice, InvoiceData,
+    AMOUNT_RIGHT, PRICE_RIGHT, QTY_RIGHT,
+};
+use crate::models::PurchaseOrder;
+use crate::order_status::OrderStatus;
+use crate::orders::{fetch_order, load_order};
+use crate::pdf::{
+    safe_file_name, save_pdf, wrap_text, PdfWriter, MARGIN, MUTED_COLOR, PAGE_WIDTH, RULE_COLOR,
+    TEXT_COLOR,
+};
+use crate::totals::{load_tax_settings, round_amount};
+
+// Require (or stop requiring) an upfront deposit of `deposit_percent` of the
+// order total. Only before the deposit invoice goes out; after that the
+// amount is fixed.
+#[tauri::command]
+pub async fn set_order_deposit(
+    db: State<'_, Database>,
+    po_id: i64,
+    deposit_percent: Option<f64>,
+) -> Result<PurchaseOrder, AppError> {
+    if let Some(percent) = deposit_percent {
+        if !percent.is_finite() || percent <= 0.0 || percent >= 100.0 {
+            return Err(AppError::Validation(format!(
+                "Deposit must be between 0 and 100 percent, got {}",
+                percent
+            )));
+        }
+    }
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = fetch_order(&mut tx, po_id).await?;
+    if before.deleted_at.is_some() {
+        return Err(AppError::NotFound(format!("Order {} not found", po_id)));
+    }
+    let status = OrderStatus::parse(&before.status)?;
+    if !matches!(status, OrderStatus::Draft | OrderStatus::Confirmed) {
+        return Err(AppError::Conflict(format!(
+            "Order {} is already {}; deposits are set before invoicing",
+            po_id,
+            status.as_str()
+        )));
+    }
+    if let Some(number) = &before.deposit_invoice_number {
+        return Err(AppError::Conflict(format!(
+            "Deposit invoice {} has already been issued for this order",
+            number
+        )));
+    }
+
+    sqlx::query("UPDATE preorders SET deposit_percent = ?, version = version + 1 WHERE id = ?")
+        .bind(deposit_percent)
+        .bind(po_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to save deposit: {}", e))?;
+
+    let after = fetch_order(&mut tx, po_id).await?;
+    audit::record(
+        &mut *tx,
+        "order",
+        po_id,
+        "deposit",
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save deposit: {}", e))?;
+
+    Ok(after)
+}
+
+// Bill the deposit of a confirmed order: fix the amount from the order total
+// as it stands and give the deposit invoice the next invoice number. Paying
+// it in full moves the order to deposit_paid; the final invoice then bills
+// the remainder. Orders already billed keep their number.
+#[tauri::command]
+pub async fn issue_deposit_invoice(
+    db: State<'_, Database>,
+    po_id: i64,
+) -> Result<PurchaseOrder, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = fetch_order(&mut tx, po_id).await?;
+    if before.deleted_at.is_some() {
+        return Err(AppError::NotFound(format!("Order {} not found", po_id)));
+    }
+    if before.deposit_invoice_number.is_some() {
+        drop(tx);
+        return load_order(&db.pool, po_id).await;
+    }
+    let percent = before
+        .deposit_percent
+        .ok_or("This order doesn't take a deposit")?;
+    if OrderStatus::parse(&before.status)? != OrderStatus::Confirmed {
+        return Err(AppError::Validation(
+            "Deposit invoices are issued for confirmed orders".to_string(),
+        ));
+    }
+
+    // Same total and rounding the final invoice will show
+    let invoice = invoice_for_order(&mut tx, before.clone()).await?;
+    let settings = load_tax_settings(&mut tx).await?;
+    let amount = round_amount(
+        invoice.totals.total * percent / 100.0,
+        currency_decimals(&invoice.currency_code),
+        settings.rounding_mode,
+    );
+    if amount <= 0.0 {
+        return Err(AppError::Validation(
+            "Order total is zero; there's no deposit to bill".to_string(),
+        ));
+    }
+
+    let number = next_invoice_number(&mut tx).await?;
+    sqlx::query(
+        "UPDATE preorders SET deposit_amount = ?, deposit_invoice_number = ?, \
+         deposit_invoiced_at = CURRENT_TIMESTAMP WHERE id = ?",
+    )
+    .bind(amount)
+    .bind(&number)
+    .bind(po_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save deposit invoice: {}", e))?;
+
+    let after = fetch_order(&mut tx, po_id).await?;
+    audit::record(
+        &mut *tx,
+        "order",
+        po_id,
+        "deposit_invoice",
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save deposit invoice: {}", e))?;
+
+    Ok(after)
+}
+
+// Draw a deposit invoice: one line for the deposit, against the order total
+fn render_deposit_invoice(pdf: &mut PdfWriter, style: &InvoiceStyle, invoice: &InvoiceData) {
+    let order = &invoice.order;
+    let locale = invoice.locale;
+    let label = |key: &str| locale.text(key);
+    let money = |amount: f64| invoice.money.format(amount);
+    let (_, business_details, footer_terms) = resolved_text(&style.template, invoice);
+    let deposit = order.deposit_amount.unwrap_or_default();
+
+    let left_bottom = letterhead(
+        pdf,
+        style,
+        business_details.as_deref(),
+        &label("deposit.title"),
+        order.deposit_invoice_number.as_deref().unwrap_or_default(),
+    );
+    pdf.text_right(
+        AMOUNT_RIGHT,
+        9.0,
+        false,
+        MUTED_COLOR,
+        &format!(
+            "{} {}",
+            label("invoice.issued"),
+            order
+                .deposit_invoiced_at
+                .as_deref()
+                .map(date_part)
+                .unwrap_or_default()
+        ),
+    );
+    if order.status == "cancelled" {
+        pdf.advance(5.0);
+        pdf.text_right(
+            AMOUNT_RIGHT,
+            10.0,
+            true,
+            (0.8, 0.15, 0.15),
+            &label("invoice.cancelled"),
+        );
+    }
+    pdf.y = pdf.y.min(left_bottom);
+    pdf.advance(10.0);
+
+    pdf.text(MARGIN, 9.0, true, MUTED_COLOR, &label("invoice.bill_to"));
+    pdf.text(120.0, 9.0, true, MUTED_COLOR, &label("invoice.order"));
+    pdf.advance(5.5);
+    pdf.text(MARGIN, 11.0, true, TEXT_COLOR, &order.customer_name);
+    pdf.text(
+        120.0,
+        10.0,
+        false,
+        TEXT_COLOR,
+        &format!(
+            "{} {}",
+            label("invoice.confirmation_code"),
+            order.confirmation_code
+        ),
+    );
+    pdf.advance(5.0);
+    pdf.text(MARGIN, 10.0, false, TEXT_COLOR, &order.customer_email);
+    if let Some(event_name) = &invoice.event_name {
+        pdf.text(120.0, 10.0, false, TEXT_COLOR, event_name);
+    }
+    pdf.advance(14.0);
+
+    item_table_header(pdf, locale);
+    let description = locale.format(
+        "deposit.line",
+        &[
+            (
+                "percent",
+                &order.deposit_percent.unwrap_or_default().to_string(),
+            ),
+            ("code", &order.confirmation_code),
+        ],
+    );
+    let name_lines = wrap_text(&description, QTY_RIGHT - MARGIN - 18.0, 10.0, false);
+    pdf.text_right(QTY_RIGHT, 10.0, false, TEXT_COLOR, "1");
+    pdf.text_right(PRICE_RIGHT, 10.0, false, TEXT_COLOR, &money(deposit));
+    pdf.text_right(AMOUNT_RIGHT, 10.0, false, TEXT_COLOR, &money(deposit));
+    for (i, text) in name_lines.iter().enumerate() {
+        if i > 0 {
+            pdf.advance(4.5);
+        }
+        pdf.text(MARGIN, 10.0, false, TEXT_COLOR, text);
+    }
+    pdf.advance(2.0);
+    pdf.rule(RULE_COLOR, 0.4);
+    pdf.advance(5.5);
+
+    let paid = order.amount_paid.min(deposit);
+    let mut rows = vec![
+        (label("deposit.order_total"), invoice.totals.total, false),
+        (label("deposit.due"), deposit, true),
+    ];
+    if paid > 0.0 {
+        rows.push((label("invoice.paid"), paid, false));
+        rows.push((label("invoice.balance_due"), deposit - paid, true));
+    }
+    pdf.ensure_space(rows.len() as f32 * 6.0 + 10.0);
+    pdf.advance(2.0);
+    for (label, amount, bold) in rows {
+        pdf.text_right(PRICE_RIGHT, 10.0, bold, TEXT_COLOR, &label);
+        pdf.text_right(AMOUNT_RIGHT, 10.0, bold, TEXT_COLOR, &money(amount));
+        pdf.advance(6.0);
+    }
+    pdf.advance(2.0);
+    pdf.text(
+        MARGIN,
+        9.0,
+        false,
+        MUTED_COLOR,
+        &label("deposit.final_invoice_note"),
+    );
+
+    if let Some(terms) = footer_terms.as_deref().filter(|t| !t.trim().is_empty()) {
+        let lines = wrap_text(terms, PAGE_WIDTH - 2.0 * MARGIN, 8.5, false);
+        pdf.ensure_space(lines.len() as f32 * 4.0 + 12.0);
+        pdf.advance(10.0);
+        pdf.rule(RULE_COLOR, 0.4);
+        for line in lines {
+            pdf.advance(4.5);
+            pdf.text(MARGIN, 8.5, false, MUTED_COLOR, &line);
+        }
+    }
+}
+
+// Render an order's deposit invoice to `dest_dir` as a PDF named after its
+// number and return the file's path
+#[tauri::command]
+pub async fn export_deposit_invoice_pdf(
+    db: State<'_, Database>,
+    po_id: i64,
+    dest_dir: String,
+) -> Result<String, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let invoice = load_invoice(&mut conn, po_id).await?;
+    let number = invoice
+        .order
+        .deposit_invoice_number
+        .clone()
+        .ok_or("No deposit invoice has been issued for this order")?;
+    let style = InvoiceStyle::prepare(load_print_template(&mut conn).await?)?;
+    drop(conn);
+
+    let mut pdf = PdfWriter::new(&format!(
+        "{} {}",
+        invoice.locale.text("deposit.title"),
+        number
+    ))?;
+    render_deposit_invoice(&mut pdf, &style, &invoice);
+
+    let dir = PathBuf::from(&dest_dir);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create export folder: {}", e))?;
+    let path = dir.join(format!("{}.pdf", safe_file_name(&number)));
+    save_pdf(&path, &pdf.finish()?)?;
+    Ok(path.to_string_lossy().to_string())
+}
@@ -0,0 +1,328 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::audit;
+use crate::automation::{self, Poller};
+use crate::db::Database;
+use crate::domain_events::{DomainEvent, Subscriber};
+use crate::error::AppError;
+use crate::i18n::load_locale;
+use crate::log_warning;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum DesktopEvent {
+    FormResponse,
+    EmailFailed,
+    ReminderDue,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct DesktopNotificationSettings {
+    pub enabled: bool,
+    pub notify_form_response: bool,
+    pub notify_email_failed: bool,
+    pub notify_reminder_due: bool,
+}
+
+// Helper: Desktop notification settings, all on if the row is missing
+async fn load_settings(
+    conn: &mut SqliteConnection,
+) -> Result<DesktopNotificationSettings, AppError> {
+    Ok(sqlx::query_as::<_, DesktopNotificationSettings>(
+        "SELECT enabled, notify_form_response, notify_email_failed, notify_reminder_due \
+         FROM desktop_notification_settings WHERE id = 1",
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| format!("Failed to load desktop notification settings: {}", e))?
+    .unwrap_or(DesktopNotificationSettings {
+        enabled: true,
+        notify_form_response: true,
+        notify_email_failed: true,
+        notify_reminder_due: true,
+    }))
+}
+
+impl DesktopNotificationSettings {
+    // Whether notifications of this kind should be shown
+    fn wants(&self, event: DesktopEvent) -> bool {
+        self.enabled
+            && match event {
+                DesktopEvent::FormResponse => self.notify_form_response,
+                DesktopEvent::EmailFailed => self.notify_email_failed,
+                DesktopEvent::ReminderDue => self.notify_reminder_due,
+            }
+    }
+}
+
+// Helper: Queue a notification for the notifier loop, unless its kind is
+// switched off
+async fn queue_notification(
+    conn: &mut SqliteConnection,
+    event: DesktopEvent,
+    title: &str,
+    body: &str,
+) -> Result<(), AppError> {
+    if !load_settings(&mut *conn).await?.wants(event) {
+        return Ok(());
+    }
+    sqlx::query("INSERT INTO desktop_notifications (event, title, body) VALUES (?, ?, ?)")
+        .bind(event)
+        .bind(title)
+        .bind(body)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to queue desktop notification: {}", e))?;
+    Ok(())
+}
+
+// Helper: Tell the user an email couldn't be sent
+async fn notify_email_failed(
+    conn: &mut SqliteConnection,
+    to_email: &str,
+    subject: &str,
+    error: &str,
+) -> Result<(), AppError> {
+    let locale = load_locale(&mut *conn).await;
+    let body = locale.format(
+        "notify.email_failed",
+        &[("to", to_email), ("subject", subject), ("error", error)],
+    );
+    queue_notification(
+        conn,
+        DesktopEvent::EmailFailed,
+        &locale.text("desktop.email_failed.title"),
+        &body,
+    )
+    .await
+}
+
+// Helper: Tell the user payment reminders came due
+async fn notify_reminders_due(conn: &mut SqliteConnection, count: u64) -> Result<(), AppError> {
+    let locale = load_locale(&mut *conn).await;
+    let body = locale.format(
+        "desktop.reminder_due.body",
+        &[("count", &count.to_string())],
+    );
+    queue_notification(
+        conn,
+        DesktopEvent::ReminderDue,
+        &locale.text("desktop.reminder_due.title"),
+        &body,
+    )
+    .await
+}
+
+// Queues notifications for failed emails and reminders coming due. New form
+// responses are found by the notifier loop itself, since the frontend imports
+// them.
+pub struct DesktopNotifications;
+
+impl Subscriber for DesktopNotifications {
+    async fn handle(
+        &self,
+        conn: &mut SqliteConnection,
+        event: &DomainEvent<'_>,
+    ) -> Result<(), AppError> {
+        match *event {
+            DomainEvent::EmailFailed {
+                to_email,
+                subject,
+                error,
+            } => notify_email_failed(conn, to_email, subject, error).await,
+            DomainEvent::PaymentRemindersDue { count } => notify_reminders_due(conn, count).await,
+            _ => Ok(()),
+        }
+    }
+}
+
+// Helper: Newest synced form response, by rowid
+async fn latest_response(pool: &SqlitePool) -> Result<i64, AppError> {
+    sqlx::query_scalar::<_, i64>("SELECT COALESCE(MAX(rowid), 0) FROM synced_responses")
+        .fetch_one(pool)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to load form responses: {}", e)))
+}
+
+// Helper: Queue one notification per form for responses the frontend synced
+// after `seen` (a synced_responses rowid). Returns the newest rowid.
+// Responses from legacy or sheet imports have no Google Form and are left out.
+async fn queue_new_responses(pool: &SqlitePool, seen: i64) -> Result<i64, AppError> {
+    let latest = latest_response(pool).await?;
+    if latest <= seen {
+        return Ok(seen);
+    }
+
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let per_form = sqlx::query_as::<_, (String, i64)>(
+        "SELECT f.title, COUNT(*) FROM synced_responses s \
+         JOIN google_forms f ON f.form_id = s.form_id \
+         WHERE s.rowid > ? AND s.rowid <= ? \
+         GROUP BY f.form_id, f.title ORDER BY MIN(s.rowid)",
+    )
+    .bind(seen)
+    .bind(latest)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load form responses: {}", e))?;
+
+    let locale = load_locale(&mut *conn).await;
+    for (form, count) in per_form {
+        let body = locale.format(
+            "desktop.form_response.body",
+            &[("form", &form), ("count", &count.to_string())],
+        );
+        queue_notification(
+            &mut conn,
+            DesktopEvent::FormResponse,
+            &locale.text("desktop.form_response.title"),
+            &body,
+        )
+        .await?;
+    }
+    Ok(latest)
+}
+
+// Helper: Show queued notifications. Ones whose kind was switched off after
+// they were queued are skipped; in quiet hours they wait.
+async fn show_pending(app: &AppHandle, pool: &SqlitePool) -> Result<(), AppError> {
+    if automation::in_quiet_hours(pool).await? {
+        return Ok(());
+    }
+    let pending = sqlx::query_as::<_, (i64, DesktopEvent, String, String)>(
+        "SELECT id, event, title, body FROM desktop_notifications \
+         WHERE status = 'pending' ORDER BY id LIMIT 20",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load desktop notifications: {}", e))?;
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let settings = load_settings(&mut conn).await?;
+    drop(conn);
+
+    for (id, event, title, body) in pending {
+        let status = if !settings.wants(event) {
+            "skipped"
+        } else if let Err(e) = app.notification().builder().title(title).body(body).show() {
+            // Not retried: a notification that can't be shown now is stale later
+            log_warning!("Failed to show desktop notification: {}", e);
+            "skipped"
+        } else {
+            "shown"
+        };
+        sqlx::query(
+            "UPDATE desktop_notifications SET status = ?, \
+             shown_at = CASE WHEN ? = 'shown' THEN CURRENT_TIMESTAMP END WHERE id = ?",
+        )
+        .bind(status)
+        .bind(status)
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to update desktop notification: {}", e))?;
+    }
+    Ok(())
+}
+
+// Start the background loop that watches for new form responses and shows
+// queued notifications, so they reach the user with the window in the background
+pub fn start_desktop_notifier(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<Database>().pool.clone();
+        // Responses synced before this launch were already seen
+        let mut seen = latest_response(&pool).await.unwrap_or(0);
+        loop {
+            match queue_new_responses(&pool, seen).await {
+                Ok(latest) => seen = latest,
+                Err(e) => log_warning!("Form response watcher failed: {}", e),
+            }
+            if let Err(e) = show_pending(&app, &pool).await {
+                log_warning!("Desktop notifier failed: {}", e);
+            }
+            automation::wait_for_next_poll(&pool, Poller::Notifier).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_desktop_notification_settings(
+    db: State<'_, Database>,
+) -> Result<DesktopNotificationSettings, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    load_settings(&mut conn).await
+}
+
+#[tauri::command]
+pub async fn set_desktop_notification_settings(
+    db: State<'_, Database>,
+    settings: DesktopNotificationSettings,
+) -> Result<DesktopNotificationSettings, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_settings(&mut tx).await?;
+    sqlx::query(
+        "INSERT OR REPLACE INTO desktop_notification_settings \
+         (id, enabled, notify_form_response, notify_email_failed, notify_reminder_due, updated_at) \
+         VALUES (1, ?, ?, ?, ?, CURRENT_TIMESTAMP)",
+    )
+    .bind(settings.enabled)
+    .bind(settings.notify_form_response)
+    .bind(settings.notify_email_failed)
+    .bind(settings.notify_reminder_due)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save desktop notification settings: {}", e))?;
+    let after = load_settings(&mut tx).await?;
+
+    audit::record(
+        &mut *tx,
+        "desktop_notification_settings",
+        1,
+        "update",
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save desktop notification settings: {}", e))?;
+    Ok(after)
+}
+
+// Show a notification right away, to check the OS lets the app post them
+#[tauri::command]
+pub async fn send_test_desktop_notification(
+    app: AppHandle,
+    db: State<'_, Database>,
+) -> Result<(), AppError> {
+    let locale = load_locale(&db.pool).await;
+    app.notification()
+        .builder()
+        .title("POTracker")
+        .body(locale.text("notify.test"))
+        .show()
+        .map_err(|e| AppError::Internal(format!("Failed to show notification: {}", e)))
+}
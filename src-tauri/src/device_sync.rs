@@ -0,0 +1,1413 @@
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::sqlite::SqliteArguments;
+use sqlx::{Row, Sqlite, SqliteConnection, SqlitePool};
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::audit;
+use crate::crypto::{self, MIN_PASSPHRASE_LEN};
+use crate::db::Database;
+use crate::drive::{self, DriveQuery};
+use crate::encryption::{self, conceal, reveal_opt};
+use crate::error::AppError;
+use crate::http::{self, SendWithRetry};
+use crate::log_warning;
+
+// How often changes are pushed and pulled while sync is on
+const SYNC_INTERVAL_SECS: u64 = 5 * 60;
+
+// Changes per changelog file, and per transaction when applying
+const BATCH_SIZE: i64 = 500;
+const APPLY_CHUNK: usize = 200;
+
+const CHANGELOG_FORMAT_VERSION: u32 = 1;
+
+// Changelog files live in the Drive appDataFolder, one per pushed batch:
+// potracker-sync-<device>-<first seq>-<last seq>.potsync
+const CHANGELOG_PREFIX: &str = "potracker-sync-";
+const CHANGELOG_EXTENSION: &str = ".potsync";
+
+// Tables shared between devices, parents before the tables referring to
+// them. Settings, outboxes and logs stay per device.
+const SYNCED_TABLES: &[&str] = &[
+    "events",
+    "suppliers",
+    "customers",
+    "products",
+    "product_prices",
+    "preorders",
+    "order_items",
+    "order_notes",
+    "order_status_history",
+    "payments",
+    "stock_movements",
+];
+
+// Columns that only mean something on this device
+const LOCAL_COLUMNS: &[(&str, &str)] = &[("preorders", "version")];
+
+// Columns only sent when a row is created. Stock levels change through stock
+// movements, which are replayed on every device, so two devices selling the
+// same product both count.
+const COUNTER_COLUMNS: &[(&str, &str)] = &[("products", "stock_quantity")];
+
+// A row arriving from another device with the same value here is the same
+// row, e.g. the customer both devices added for one email address
+const NATURAL_KEYS: &[(&str, &str)] = &[("customers", "email")];
+
+// One sync at a time, whether from the scheduler or the user
+static SYNC_LOCK: Mutex<()> = Mutex::const_new(());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum SyncOpKind {
+    Insert,
+    Update,
+    Delete,
+    // A row shared when sync was turned on. Merged like an insert, but
+    // doesn't replay side effects such as stock changes.
+    Snapshot,
+}
+
+// One change in the changelog. data is a JSON object, see sync_ops.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SyncOp {
+    pub seq: i64,
+    pub clock: i64,
+    pub table_name: String,
+    pub row_gid: String,
+    pub kind: SyncOpKind,
+    pub data: String,
+}
+
+// Encrypted contents of one changelog file
+#[derive(Debug, Serialize, Deserialize)]
+struct SyncBatch {
+    format_version: u32,
+    device_id: String,
+    ops: Vec<SyncOp>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct InboxOp {
+    device_id: String,
+    #[sqlx(flatten)]
+    op: SyncOp,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct SyncState {
+    enabled: bool,
+    device_id: Option<String>,
+    passphrase: Option<String>,
+    last_pushed_seq: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SyncPeer {
+    pub device_id: String,
+    pub last_seq: i64,
+    pub last_seen_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyncStatus {
+    pub enabled: bool,
+    pub device_id: Option<String>,
+    pub last_sync_at: Option<String>,
+    pub last_error: Option<String>,
+    // Changes made here and not pushed yet
+    pub outgoing: i64,
+    // Other devices' changes waiting for a row they refer to
+    pub waiting: i64,
+    pub open_conflicts: i64,
+    pub peers: Vec<SyncPeer>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyncReport {
+    pub pushed: u64,
+    pub received: u64,
+    pub applied: u64,
+    pub conflicts: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SyncConflict {
+    pub id: i64,
+    pub device_id: String,
+    pub table_name: String,
+    pub row_gid: String,
+    pub kind: SyncOpKind,
+    pub data: String,
+    pub error: String,
+    pub created_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChangelogList {
+    files: Vec<ChangelogEntry>,
+    #[serde(rename = "nextPageToken")]
+    next_page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChangelogEntry {
+    id: String,
+    name: String,
+}
+
+struct ChangelogFile {
+    id: String,
+    device_id: String,
+    first_seq: i64,
+    last_seq: i64,
+}
+
+// How applying one change went
+enum Outcome {
+    Applied,
+    // Refers to a row that hasn't arrived yet; kept for the next sync
+    Waiting,
+    Conflict(String),
+}
+
+// A synced table as it is in this database
+struct SyncedTable {
+    name: &'static str,
+    // Synced columns, without the local id
+    columns: Vec<String>,
+    // Columns referring to another synced table, sent as global IDs
+    references: HashMap<String, &'static str>,
+}
+
+impl SyncedTable {
+    fn is_counter(&self, column: &str) -> bool {
+        COUNTER_COLUMNS.contains(&(self.name, column))
+    }
+
+    // SQL for the value of `column` in `row` (NEW, OLD or an alias) as it
+    // goes into the changelog
+    fn value_sql(&self, row: &str, column: &str) -> String {
+        let local = format!("{}.\"{}\"", row, column);
+        match self.references.get(column) {
+            Some(parent) => gid_sql(parent, &local),
+            None => local,
+        }
+    }
+
+    // SQL for the whole of `row` as a JSON object
+    fn row_json_sql(&self, row: &str) -> String {
+        let pairs: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| format!("'{}', {}", column, self.value_sql(row, column)))
+            .collect();
+        format!("json_object({})", pairs.join(", "))
+    }
+}
+
+// Helper: SQL for the global ID of the live `table` row with local ID `local_id`
+fn gid_sql(table: &str, local_id: &str) -> String {
+    format!(
+        "(SELECT gid FROM sync_rows WHERE table_name = '{}' AND local_id = {} \
+         AND deleted = 0 ORDER BY rowid LIMIT 1)",
+        table, local_id
+    )
+}
+
+// Helper: Read the synced tables' columns and references from the schema, so
+// columns added by later migrations sync without changes here
+async fn load_tables(conn: &mut SqliteConnection) -> Result<Vec<SyncedTable>, AppError> {
+    let mut tables = Vec::new();
+    for &name in SYNCED_TABLES {
+        let foreign_keys = sqlx::query_as::<_, (String, String)>(
+            "SELECT \"from\", \"table\" FROM pragma_foreign_key_list(?)",
+        )
+        .bind(name)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to read schema of {}: {}", name, e))?;
+
+        // References to tables that aren't synced can't be resolved elsewhere
+        let mut references = HashMap::new();
+        let mut unsynced = Vec::new();
+        for (column, parent) in foreign_keys {
+            match SYNCED_TABLES.iter().find(|table| **table == parent) {
+                Some(parent) => {
+                    references.insert(column, *parent);
+                }
+                None => unsynced.push(column),
+            }
+        }
+
+        let columns = sqlx::query_scalar::<_, String>(
+            "SELECT name FROM pragma_table_info(?) WHERE pk = 0 ORDER BY cid",
+        )
+        .bind(name)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to read schema of {}: {}", name, e))?
+        .into_iter()
+        .filter(|column| {
+            !LOCAL_COLUMNS.contains(&(name, column.as_str())) && !unsynced.contains(column)
+        })
+        .collect();
+
+        tables.push(SyncedTable {
+            name,
+            columns,
+            references,
+        });
+    }
+    Ok(tables)
+}
+
+// Helper: (Re)create the triggers that give new rows a global ID and record
+// local changes in sync_ops. Rebuilt from the current schema each launch.
+async fn install_triggers(
+    conn: &mut SqliteConnection,
+    tables: &[SyncedTable],
+) -> Result<(), AppError> {
+    drop_triggers(&mut *conn).await?;
+
+    for table in tables {
+        let t = table.name;
+        let row_gid = gid_sql(t, "NEW.id");
+
+        let insert = format!(
+            "CREATE TRIGGER trg_sync_{t}_insert AFTER INSERT ON {t} \
+             WHEN (SELECT enabled FROM sync_state WHERE id = 1) = 1 \
+             BEGIN \
+             INSERT INTO sync_rows (gid, table_name, local_id) \
+             VALUES (lower(hex(randomblob(16))), '{t}', NEW.id); \
+             UPDATE sync_state SET clock = clock + 1 WHERE id = 1 AND applying = 0; \
+             INSERT INTO sync_ops (clock, table_name, row_gid, kind, data) \
+             SELECT clock, '{t}', {row_gid}, 'insert', {data} \
+             FROM sync_state WHERE id = 1 AND applying = 0; \
+             END",
+            data = table.row_json_sql("NEW"),
+        );
+
+        // One op with just the changed fields, and their field clocks
+        let changed: Vec<String> = table
+            .columns
+            .iter()
+            .filter(|column| !table.is_counter(column))
+            .map(|column| {
+                format!(
+                    "SELECT '{column}' AS name, {value} AS value \
+                     WHERE NEW.\"{column}\" IS NOT OLD.\"{column}\"",
+                    value = table.value_sql("NEW", column),
+                )
+            })
+            .collect();
+        let update = format!(
+            "CREATE TRIGGER trg_sync_{t}_update AFTER UPDATE ON {t} \
+             WHEN (SELECT enabled AND NOT applying FROM sync_state WHERE id = 1) \
+             BEGIN \
+             UPDATE sync_state SET clock = clock + 1 WHERE id = 1; \
+             INSERT INTO sync_ops (clock, table_name, row_gid, kind, data) \
+             SELECT s.clock, '{t}', {row_gid}, 'update', c.data \
+             FROM sync_state s, (SELECT json_group_object(name, value) AS data FROM ({changed})) c \
+             WHERE s.id = 1 AND c.data <> '{{}}' AND {row_gid} IS NOT NULL; \
+             INSERT OR REPLACE INTO sync_field_clocks \
+             (table_name, local_id, column_name, clock, device_id) \
+             SELECT '{t}', NEW.id, j.key, o.clock, s.device_id \
+             FROM sync_state s JOIN sync_ops o ON o.clock = s.clock, json_each(o.data) j \
+             WHERE s.id = 1 AND o.seq = (SELECT MAX(seq) FROM sync_ops) AND o.kind = 'update'; \
+             END",
+            changed = changed.join(" UNION ALL "),
+        );
+
+        let delete = format!(
+            "CREATE TRIGGER trg_sync_{t}_delete AFTER DELETE ON {t} \
+             WHEN (SELECT enabled FROM sync_state WHERE id = 1) = 1 \
+             BEGIN \
+             UPDATE sync_state SET clock = clock + 1 WHERE id = 1 AND applying = 0; \
+             INSERT INTO sync_ops (clock, table_name, row_gid, kind) \
+             SELECT clock, '{t}', {old_gid}, 'delete' FROM sync_state \
+             WHERE id = 1 AND applying = 0 AND {old_gid} IS NOT NULL; \
+             UPDATE sync_rows SET deleted = 1 WHERE table_name = '{t}' AND local_id = OLD.id; \
+             DELETE FROM sync_field_clocks WHERE table_name = '{t}' AND local_id = OLD.id; \
+             END",
+            old_gid = gid_sql(t, "OLD.id"),
+        );
+
+        for sql in [insert, update, delete] {
+            sqlx::query(&sql)
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| format!("Failed to install sync triggers on {}: {}", t, e))?;
+        }
+    }
+    Ok(())
+}
+
+// Helper: Remove the capture triggers
+async fn drop_triggers(conn: &mut SqliteConnection) -> Result<(), AppError> {
+    for table in SYNCED_TABLES {
+        for action in ["insert", "update", "delete"] {
+            sqlx::query(&format!(
+                "DROP TRIGGER IF EXISTS trg_sync_{}_{}",
+                table, action
+            ))
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to remove sync triggers: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+// Helper: Queue every synced row as a snapshot, and deletes for rows removed
+// while sync was off, so other devices catch up with this one
+async fn snapshot_rows(
+    conn: &mut SqliteConnection,
+    tables: &[SyncedTable],
+) -> Result<(), AppError> {
+    sqlx::query("UPDATE sync_state SET clock = clock + 1 WHERE id = 1")
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to share existing data: {}", e))?;
+
+    for table in tables {
+        let t = table.name;
+        let statements = [
+            format!(
+                "INSERT INTO sync_rows (gid, table_name, local_id) \
+                 SELECT lower(hex(randomblob(16))), '{t}', r.id FROM {t} r \
+                 WHERE {gid} IS NULL",
+                gid = gid_sql(t, "r.id"),
+            ),
+            format!(
+                "INSERT INTO sync_ops (clock, table_name, row_gid, kind) \
+                 SELECT s.clock, '{t}', g.gid, 'delete' FROM sync_rows g, sync_state s \
+                 WHERE s.id = 1 AND g.table_name = '{t}' AND g.deleted = 0 \
+                 AND g.local_id NOT IN (SELECT id FROM {t})"
+            ),
+            format!(
+                "UPDATE sync_rows SET deleted = 1 WHERE table_name = '{t}' AND deleted = 0 \
+                 AND local_id NOT IN (SELECT id FROM {t})"
+            ),
+            format!(
+                "INSERT INTO sync_ops (clock, table_name, row_gid, kind, data) \
+                 SELECT s.clock, '{t}', {gid}, 'snapshot', {data} \
+                 FROM {t} r, sync_state s WHERE s.id = 1 ORDER BY r.id",
+                gid = gid_sql(t, "r.id"),
+                data = table.row_json_sql("r"),
+            ),
+        ];
+        for sql in statements {
+            sqlx::query(&sql)
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| format!("Failed to share existing {}: {}", t, e))?;
+        }
+    }
+    Ok(())
+}
+
+// Helper: Bind a JSON value from the changelog as the matching SQLite type
+fn bind_value<'q>(
+    query: sqlx::query::Query<'q, Sqlite, SqliteArguments<'q>>,
+    value: &'q Value,
+) -> sqlx::query::Query<'q, Sqlite, SqliteArguments<'q>> {
+    match value {
+        Value::Null => query.bind(None::<String>),
+        Value::Bool(flag) => query.bind(*flag),
+        Value::Number(number) => match number.as_i64() {
+            Some(integer) => query.bind(integer),
+            None => query.bind(number.as_f64()),
+        },
+        Value::String(text) => query.bind(text.as_str()),
+        other => query.bind(other.to_string()),
+    }
+}
+
+// Helper: Local ID and tombstone flag of a global ID
+async fn find_row(
+    conn: &mut SqliteConnection,
+    table: &str,
+    gid: &str,
+) -> Result<Option<(Option<i64>, bool)>, AppError> {
+    sqlx::query_as::<_, (Option<i64>, bool)>(
+        "SELECT local_id, deleted FROM sync_rows WHERE gid = ? AND table_name = ?",
+    )
+    .bind(gid)
+    .bind(table)
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to look up synced row: {}", e)))
+}
+
+// Helper: Apply the fields another device wrote later than this one did.
+// Ties on the clock go to the higher device ID, the same on every device.
+async fn merge_fields(
+    conn: &mut SqliteConnection,
+    table: &SyncedTable,
+    local_id: i64,
+    values: Vec<(String, Value)>,
+    clock: i64,
+    origin: &str,
+) -> Result<Outcome, AppError> {
+    let mut winners = Vec::new();
+    for (column, value) in values {
+        let current = sqlx::query_as::<_, (i64, String)>(
+            "SELECT clock, device_id FROM sync_field_clocks \
+             WHERE table_name = ? AND local_id = ? AND column_name = ?",
+        )
+        .bind(table.name)
+        .bind(local_id)
+        .bind(&column)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to load field clock: {}", e))?;
+        if current
+            .as_ref()
+            .is_none_or(|(theirs, device)| (clock, origin) > (*theirs, device.as_str()))
+        {
+            winners.push((column, value));
+        }
+    }
+    if winners.is_empty() {
+        return Ok(Outcome::Applied);
+    }
+
+    let assignments: Vec<String> = winners
+        .iter()
+        .map(|(column, _)| format!("\"{}\" = ?", column))
+        .collect();
+    let sql = format!(
+        "UPDATE {} SET {} WHERE id = ?",
+        table.name,
+        assignments.join(", ")
+    );
+    let mut query = sqlx::query(&sql);
+    for (_, value) in &winners {
+        query = bind_value(query, value);
+    }
+    if let Err(e) = query.bind(local_id).execute(&mut *conn).await {
+        return Ok(Outcome::Conflict(e.to_string()));
+    }
+
+    for (column, _) in &winners {
+        sqlx::query(
+            "INSERT OR REPLACE INTO sync_field_clocks \
+             (table_name, local_id, column_name, clock, device_id) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(table.name)
+        .bind(local_id)
+        .bind(column)
+        .bind(clock)
+        .bind(origin)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to save field clock: {}", e))?;
+    }
+    Ok(Outcome::Applied)
+}
+
+// Helper: Local row matching an incoming one on its table's natural key
+async fn natural_match(
+    conn: &mut SqliteConnection,
+    table: &SyncedTable,
+    values: &[(String, Value)],
+) -> Result<Option<i64>, AppError> {
+    let Some(&(_, key)) = NATURAL_KEYS.iter().find(|(name, _)| *name == table.name) else {
+        return Ok(None);
+    };
+    let Some((_, value)) = values.iter().find(|(column, _)| column == key) else {
+        return Ok(None);
+    };
+
+    let sql = format!("SELECT id FROM {} WHERE \"{}\" = ?", table.name, key);
+    let row = bind_value(sqlx::query(&sql), value)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to match {}: {}", table.name, e))?;
+    Ok(row.map(|row| row.get::<i64, _>(0)))
+}
+
+// Helper: Point a global ID at a local row
+async fn map_row(
+    conn: &mut SqliteConnection,
+    table: &str,
+    gid: &str,
+    local_id: i64,
+) -> Result<(), AppError> {
+    sqlx::query("INSERT OR REPLACE INTO sync_rows (gid, table_name, local_id) VALUES (?, ?, ?)")
+        .bind(gid)
+        .bind(table)
+        .bind(local_id)
+        .execute(conn)
+        .await
+        .map_err(|e| format!("Failed to map synced row: {}", e))?;
+    Ok(())
+}
+
+// Helper: Create a row another device added
+async fn insert_row(
+    conn: &mut SqliteConnection,
+    table: &SyncedTable,
+    op: &SyncOp,
+    values: &[(String, Value)],
+) -> Result<Outcome, AppError> {
+    if values.is_empty() {
+        return Ok(Outcome::Conflict("Change has no fields".to_string()));
+    }
+
+    let columns: Vec<String> = values
+        .iter()
+        .map(|(column, _)| format!("\"{}\"", column))
+        .collect();
+    let sql = format!(
+        "INSERT INTO {} ({}) VALUES ({})",
+        table.name,
+        columns.join(", "),
+        vec!["?"; values.len()].join(", ")
+    );
+    let mut query = sqlx::query(&sql);
+    for (_, value) in values {
+        query = bind_value(query, value);
+    }
+    let local_id = match query.execute(&mut *conn).await {
+        Ok(result) => result.last_insert_rowid(),
+        Err(e) => return Ok(Outcome::Conflict(e.to_string())),
+    };
+
+    // Swap the ID the insert trigger made up for the sender's
+    sqlx::query("DELETE FROM sync_rows WHERE table_name = ? AND local_id = ?")
+        .bind(table.name)
+        .bind(local_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to map synced row: {}", e))?;
+    map_row(&mut *conn, table.name, &op.row_gid, local_id).await?;
+
+    if op.kind == SyncOpKind::Insert && table.name == "stock_movements" {
+        let field = |name: &str| {
+            values
+                .iter()
+                .find(|(column, _)| column == name)
+                .and_then(|(_, value)| value.as_i64())
+        };
+        if let (Some(product_id), Some(change)) = (field("product_id"), field("change")) {
+            sqlx::query(
+                "UPDATE products SET stock_quantity = COALESCE(stock_quantity, 0) + ? WHERE id = ?",
+            )
+            .bind(change)
+            .bind(product_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to update stock: {}", e))?;
+        }
+    }
+    Ok(Outcome::Applied)
+}
+
+// Helper: Apply one change from device `origin`
+async fn apply_op(
+    conn: &mut SqliteConnection,
+    tables: &[SyncedTable],
+    origin: &str,
+    op: &SyncOp,
+) -> Result<Outcome, AppError> {
+    let Some(table) = tables.iter().find(|table| table.name == op.table_name) else {
+        return Ok(Outcome::Conflict(format!(
+            "Unknown table {}",
+            op.table_name
+        )));
+    };
+    let local = find_row(&mut *conn, table.name, &op.row_gid).await?;
+
+    if op.kind == SyncOpKind::Delete {
+        match local {
+            Some((Some(local_id), false)) => {
+                let sql = format!("DELETE FROM {} WHERE id = ?", table.name);
+                if let Err(e) = sqlx::query(&sql).bind(local_id).execute(&mut *conn).await {
+                    return Ok(Outcome::Conflict(e.to_string()));
+                }
+            }
+            Some(_) => {}
+            // Deleted before it got here; the tombstone stops it arriving later
+            None => {
+                sqlx::query(
+                    "INSERT INTO sync_rows (gid, table_name, local_id, deleted) VALUES (?, ?, NULL, 1)",
+                )
+                .bind(&op.row_gid)
+                .bind(table.name)
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| format!("Failed to record deleted row: {}", e))?;
+            }
+        }
+        return Ok(Outcome::Applied);
+    }
+
+    // Changes to a row deleted here are dropped
+    if matches!(local, Some((_, true))) {
+        return Ok(Outcome::Applied);
+    }
+
+    let mut data: serde_json::Map<String, Value> = match serde_json::from_str(&op.data) {
+        Ok(data) => data,
+        Err(e) => return Ok(Outcome::Conflict(format!("Unreadable change: {}", e))),
+    };
+    encryption::conceal_json(table.name, &mut data)?;
+
+    // Only columns this schema has; a newer device's extra columns are ignored
+    let mut values = Vec::new();
+    for column in &table.columns {
+        let Some(value) = data.get(column) else {
+            continue;
+        };
+        let Some(parent) = table.references.get(column).filter(|_| !value.is_null()) else {
+            values.push((column.clone(), value.clone()));
+            continue;
+        };
+        let Some(gid) = value.as_str() else {
+            return Ok(Outcome::Conflict(format!("Bad reference in {}", column)));
+        };
+        match find_row(&mut *conn, parent, gid).await? {
+            None => return Ok(Outcome::Waiting),
+            Some((Some(parent_id), false)) => values.push((column.clone(), Value::from(parent_id))),
+            Some(_) => {
+                return Ok(Outcome::Conflict(format!(
+                    "Refers to a deleted row in {}",
+                    parent
+                )))
+            }
+        }
+    }
+
+    let local_id = match local {
+        Some((Some(local_id), _)) => Some(local_id),
+        _ => None,
+    };
+    if op.kind != SyncOpKind::Update && local_id.is_none() {
+        if let Some(matched) = natural_match(&mut *conn, table, &values).await? {
+            map_row(&mut *conn, table.name, &op.row_gid, matched).await?;
+        } else {
+            return insert_row(conn, table, op, &values).await;
+        }
+    }
+
+    let Some(local_id) = find_row(&mut *conn, table.name, &op.row_gid)
+        .await?
+        .and_then(|(local_id, _)| local_id)
+    else {
+        // An update to a row whose insert hasn't arrived
+        return Ok(Outcome::Waiting);
+    };
+    if op.kind != SyncOpKind::Update {
+        values.retain(|(column, _)| !table.is_counter(column));
+    }
+    merge_fields(conn, table, local_id, values, op.clock, origin).await
+}
+
+// Helper: Apply waiting changes from other devices, oldest clock first, which
+// puts every change after the ones it depends on
+async fn apply_inbox(pool: &SqlitePool, tables: &[SyncedTable]) -> Result<(u64, u64), AppError> {
+    let inbox = sqlx::query_as::<_, InboxOp>(
+        "SELECT device_id, seq, clock, table_name, row_gid, kind, data FROM sync_inbox \
+         ORDER BY clock, device_id, seq",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load incoming changes: {}", e))?;
+
+    let (mut applied, mut conflicts) = (0, 0);
+    // Short transactions, so the app isn't locked out of the database for long
+    for chunk in inbox.chunks(APPLY_CHUNK) {
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+        sqlx::query("UPDATE sync_state SET applying = 1 WHERE id = 1")
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to apply changes: {}", e))?;
+
+        for entry in chunk {
+            // Lamport clock: edits made here from now on order after this one
+            sqlx::query("UPDATE sync_state SET clock = MAX(clock, ?) WHERE id = 1")
+                .bind(entry.op.clock)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| format!("Failed to apply changes: {}", e))?;
+
+            match apply_op(&mut tx, tables, &entry.device_id, &entry.op).await? {
+                Outcome::Waiting => continue,
+                Outcome::Applied => applied += 1,
+                Outcome::Conflict(error) => {
+                    sqlx::query(
+                        "INSERT INTO sync_conflicts \
+                         (device_id, seq, clock, table_name, row_gid, kind, data, error) \
+                         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+                    )
+                    .bind(&entry.device_id)
+                    .bind(entry.op.seq)
+                    .bind(entry.op.clock)
+                    .bind(&entry.op.table_name)
+                    .bind(&entry.op.row_gid)
+                    .bind(entry.op.kind)
+                    .bind(&entry.op.data)
+                    .bind(&error)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| format!("Failed to record sync conflict: {}", e))?;
+                    conflicts += 1;
+                }
+            }
+            sqlx::query("DELETE FROM sync_inbox WHERE device_id = ? AND seq = ?")
+                .bind(&entry.device_id)
+                .bind(entry.op.seq)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| format!("Failed to apply changes: {}", e))?;
+        }
+
+        sqlx::query("UPDATE sync_state SET applying = 0 WHERE id = 1")
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to apply changes: {}", e))?;
+        tx.commit()
+            .await
+            .map_err(|e| format!("Failed to apply changes: {}", e))?;
+    }
+    Ok((applied, conflicts))
+}
+
+// Helper: Device and sequence range from a changelog file name
+fn parse_changelog_name(name: &str) -> Option<(String, i64, i64)> {
+    let stem = name
+        .strip_prefix(CHANGELOG_PREFIX)?
+        .strip_suffix(CHANGELOG_EXTENSION)?;
+    let mut parts = stem.rsplitn(3, '-');
+    let last_seq = parts.next()?.parse().ok()?;
+    let first_seq = parts.next()?.parse().ok()?;
+    let device_id = parts.next()?.to_string();
+    Some((device_id, first_seq, last_seq))
+}
+
+// Helper: Every changelog file in the Drive appDataFolder
+async fn list_changelog(
+    client: &Client,
+    access_token: &str,
+) -> Result<Vec<ChangelogFile>, AppError> {
+    let query = DriveQuery::new()
+        .name_contains(CHANGELOG_PREFIX)
+        .trashed(false)
+        .build();
+    let mut files = Vec::new();
+    let mut page_token: Option<String> = None;
+
+    loop {
+        let mut params = vec![
+            ("spaces", "appDataFolder".to_string()),
+            ("q", query.clone()),
+            ("fields", "nextPageToken,files(id,name)".to_string()),
+            ("pageSize", "1000".to_string()),
+        ];
+        if let Some(token) = &page_token {
+            params.push(("pageToken", token.clone()));
+        }
+
+        let response = client
+            .get("https://www.googleapis.com/drive/v3/files")
+            .query(&params)
+            .bearer_auth(access_token)
+            .send_with_retry()
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to list changelog: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(http::google_error(response, "Drive API error").await);
+        }
+
+        let list: ChangelogList = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse changelog list: {}", e))?;
+
+        files.extend(list.files.into_iter().filter_map(|entry| {
+            let (device_id, first_seq, last_seq) = parse_changelog_name(&entry.name)?;
+            Some(ChangelogFile {
+                id: entry.id,
+                device_id,
+                first_seq,
+                last_seq,
+            })
+        }));
+
+        match list.next_page_token {
+            Some(token) => page_token = Some(token),
+            None => break,
+        }
+    }
+    Ok(files)
+}
+
+// Helper: A change with its encrypted fields decrypted, since every device
+// has its own key. The changelog itself is encrypted with the sync passphrase.
+fn reveal_op(mut op: SyncOp) -> Result<SyncOp, AppError> {
+    let Ok(mut data) = serde_json::from_str::<serde_json::Map<String, Value>>(&op.data) else {
+        return Ok(op);
+    };
+    encryption::reveal_json(&op.table_name, &mut data)?;
+    op.data =
+        serde_json::to_string(&data).map_err(|e| format!("Failed to serialize change: {}", e))?;
+    Ok(op)
+}
+
+// Helper: Upload this device's unpushed changes in batches. Pushed changes are
+// removed locally; the changelog keeps them.
+async fn push_changes(
+    app: &AppHandle,
+    client: &Client,
+    access_token: &str,
+    pool: &SqlitePool,
+    device_id: &str,
+    passphrase: &str,
+    mut last_pushed: i64,
+) -> Result<u64, AppError> {
+    let mut pushed = 0;
+    loop {
+        let ops = sqlx::query_as::<_, SyncOp>(
+            "SELECT seq, clock, table_name, row_gid, kind, data FROM sync_ops \
+             WHERE seq > ? ORDER BY seq LIMIT ?",
+        )
+        .bind(last_pushed)
+        .bind(BATCH_SIZE)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to load local changes: {}", e))?
+        .into_iter()
+        .map(reveal_op)
+        .collect::<Result<Vec<_>, _>>()?;
+        let (Some(first), Some(last)) = (ops.first(), ops.last()) else {
+            return Ok(pushed);
+        };
+        let (first_seq, last_seq) = (first.seq, last.seq);
+        let count = ops.len() as u64;
+
+        let batch = SyncBatch {
+            format_version: CHANGELOG_FORMAT_VERSION,
+            device_id: device_id.to_string(),
+            ops,
+        };
+        let json = serde_json::to_vec(&batch)
+            .map_err(|e| format!("Failed to serialize changes: {}", e))?;
+        let data = crypto::encrypt_with_passphrase(passphrase, &json)?;
+
+        let name = format!(
+            "{}{}-{:012}-{:012}{}",
+            CHANGELOG_PREFIX, device_id, first_seq, last_seq, CHANGELOG_EXTENSION
+        );
+        drive::resumable_upload(
+            app,
+            client,
+            access_token,
+            &name,
+            drive::UploadSource::Bytes(data),
+            "application/octet-stream",
+            Some("appDataFolder"),
+        )
+        .await?;
+
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+        sqlx::query("UPDATE sync_state SET last_pushed_seq = ? WHERE id = 1")
+            .bind(last_seq)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to record pushed changes: {}", e))?;
+        sqlx::query("DELETE FROM sync_ops WHERE seq <= ?")
+            .bind(last_seq)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to record pushed changes: {}", e))?;
+        tx.commit()
+            .await
+            .map_err(|e| format!("Failed to record pushed changes: {}", e))?;
+
+        last_pushed = last_seq;
+        pushed += count;
+    }
+}
+
+// Helper: Download other devices' changelog files not read yet into the
+// inbox. sync_peers is the vector of how far each device has been read.
+async fn pull_changes(
+    client: &Client,
+    access_token: &str,
+    pool: &SqlitePool,
+    device_id: &str,
+    passphrase: &str,
+    files: &[ChangelogFile],
+) -> Result<u64, AppError> {
+    let mut seen: HashMap<String, i64> =
+        sqlx::query_as::<_, (String, i64)>("SELECT device_id, last_seq FROM sync_peers")
+            .fetch_all(pool)
+            .await
+            .map_err(|e| format!("Failed to load sync peers: {}", e))?
+            .into_iter()
+            .collect();
+
+    let mut unread: Vec<&ChangelogFile> = files
+        .iter()
+        .filter(|file| file.device_id != device_id)
+        .filter(|file| file.last_seq > seen.get(&file.device_id).copied().unwrap_or(0))
+        .collect();
+    unread.sort_by(|a, b| (&a.device_id, a.first_seq).cmp(&(&b.device_id, b.first_seq)));
+
+    let mut received = 0;
+    for file in unread {
+        let read_to = seen.get(&file.device_id).copied().unwrap_or(0);
+        let response = client
+            .get(format!(
+                "https://www.googleapis.com/drive/v3/files/{}?alt=media",
+                file.id
+            ))
+            .bearer_auth(access_token)
+            .send_with_retry()
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to download changes: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(http::google_error(response, "Drive API read error").await);
+        }
+
+        let data = response
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to read changes: {}", e))?;
+        let json = crypto::decrypt_with_passphrase(passphrase, &data)?;
+        let batch: SyncBatch =
+            serde_json::from_slice(&json).map_err(|e| format!("Failed to parse changes: {}", e))?;
+        if batch.format_version > CHANGELOG_FORMAT_VERSION {
+            return Err(AppError::Validation(format!(
+                "Changes from device {} need a newer version of the app",
+                file.device_id
+            )));
+        }
+        if batch.device_id != file.device_id {
+            return Err(AppError::Validation(format!(
+                "Changelog file from device {} names another device",
+                file.device_id
+            )));
+        }
+
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+        for op in batch.ops.iter().filter(|op| op.seq > read_to) {
+            sqlx::query(
+                "INSERT OR IGNORE INTO sync_inbox \
+                 (device_id, seq, clock, table_name, row_gid, kind, data) \
+                 VALUES (?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(&batch.device_id)
+            .bind(op.seq)
+            .bind(op.clock)
+            .bind(&op.table_name)
+            .bind(&op.row_gid)
+            .bind(op.kind)
+            .bind(&op.data)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to store incoming changes: {}", e))?;
+            received += 1;
+        }
+        sqlx::query(
+            "INSERT INTO sync_peers (device_id, last_seq, last_seen_at) \
+             VALUES (?, ?, CURRENT_TIMESTAMP) \
+             ON CONFLICT(device_id) DO UPDATE SET \
+             last_seq = MAX(last_seq, excluded.last_seq), last_seen_at = excluded.last_seen_at",
+        )
+        .bind(&file.device_id)
+        .bind(file.last_seq)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to record sync peer: {}", e))?;
+        tx.commit()
+            .await
+            .map_err(|e| format!("Failed to store incoming changes: {}", e))?;
+
+        seen.insert(file.device_id.clone(), file.last_seq.max(read_to));
+    }
+    Ok(received)
+}
+
+// Helper: Sync state, off if the row is missing
+async fn load_state(conn: &mut SqliteConnection) -> Result<SyncState, AppError> {
+    let state = sqlx::query_as::<_, SyncState>(
+        "SELECT enabled, device_id, passphrase, last_pushed_seq FROM sync_state WHERE id = 1",
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| format!("Failed to load sync state: {}", e))?
+    .unwrap_or(SyncState {
+        enabled: false,
+        device_id: None,
+        passphrase: None,
+        last_pushed_seq: 0,
+    });
+    Ok(SyncState {
+        passphrase: reveal_opt(state.passphrase)?,
+        ..state
+    })
+}
+
+// Helper: Push local changes, pull other devices' and merge them
+async fn run_sync(app: &AppHandle, pool: &SqlitePool) -> Result<SyncReport, AppError> {
+    let _guard = SYNC_LOCK.lock().await;
+
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let state = load_state(&mut conn).await?;
+    let tables = load_tables(&mut conn).await?;
+    drop(conn);
+
+    let (true, Some(mut device_id), Some(passphrase)) =
+        (state.enabled, state.device_id, state.passphrase)
+    else {
+        return Err(AppError::Validation("Sync is turned off".to_string()));
+    };
+
+    let result: Result<SyncReport, AppError> = async {
+        let access_token = drive::stored_access_token(pool).await?;
+        let client = http::client();
+        let files = list_changelog(&client, &access_token).await?;
+
+        // A database restored from an older backup would reuse sequence
+        // numbers this device already published, which other devices skip.
+        // Carry on as a new device; the old one's later changes are then
+        // pulled back in like any other device's.
+        let published = files
+            .iter()
+            .filter(|file| file.device_id == device_id)
+            .map(|file| file.last_seq)
+            .max()
+            .unwrap_or(0);
+        if published > state.last_pushed_seq {
+            device_id = Uuid::new_v4().to_string();
+            sqlx::query("UPDATE sync_state SET device_id = ? WHERE id = 1")
+                .bind(&device_id)
+                .execute(pool)
+                .await
+                .map_err(|e| format!("Failed to save sync state: {}", e))?;
+            log_warning!("Sync changelog is ahead of this database; continuing as a new device");
+        }
+
+        let pushed = push_changes(
+            app,
+            &client,
+            &access_token,
+            pool,
+            &device_id,
+            &passphrase,
+            state.last_pushed_seq,
+        )
+        .await?;
+        let received = pull_changes(
+            &client,
+            &access_token,
+            pool,
+            &device_id,
+            &passphrase,
+            &files,
+        )
+        .await?;
+        let (applied, conflicts) = apply_inbox(pool, &tables).await?;
+        Ok(SyncReport {
+            pushed,
+            received,
+            applied,
+            conflicts,
+        })
+    }
+    .await;
+
+    let error = result.as_ref().err().map(|e| e.to_string());
+    let recorded = sqlx::query(
+        "UPDATE sync_state SET \
+         last_sync_at = CASE WHEN ? THEN CURRENT_TIMESTAMP ELSE last_sync_at END, \
+         last_error = ? WHERE id = 1",
+    )
+    .bind(error.is_none())
+    .bind(error)
+    .execute(pool)
+    .await;
+    if let Err(e) = recorded {
+        log_warning!("Failed to record sync outcome: {}", e);
+    }
+    result
+}
+
+// Start the background loop that syncs with other devices while sync is on
+pub fn start_device_sync(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<Database>().pool.clone();
+
+        // Migrations may have changed the synced tables since the triggers
+        // were made
+        let reinstalled = async {
+            let mut tx = pool
+                .begin()
+                .await
+                .map_err(|e| format!("Failed to start transaction: {}", e))?;
+            if load_state(&mut tx).await?.enabled {
+                let tables = load_tables(&mut tx).await?;
+                install_triggers(&mut tx, &tables).await?;
+            }
+            tx.commit()
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to install sync triggers: {}", e)))
+        }
+        .await;
+        if let Err(e) = reinstalled {
+            log_warning!("{}", e);
+        }
+
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(SYNC_INTERVAL_SECS));
+        loop {
+            ticker.tick().await;
+            let enabled = match pool.acquire().await {
+                Ok(mut conn) => load_state(&mut conn)
+                    .await
+                    .map(|state| state.enabled)
+                    .unwrap_or(false),
+                Err(_) => false,
+            };
+            if !enabled {
+                continue;
+            }
+            if let Err(e) = run_sync(&app, &pool).await {
+                log_warning!("Device sync failed: {}", e);
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_sync_status(db: State<'_, Database>) -> Result<SyncStatus, AppError> {
+    let (enabled, device_id, last_sync_at, last_error, outgoing, waiting, open_conflicts) =
+        sqlx::query_as::<
+            _,
+            (
+                bool,
+                Option<String>,
+                Option<String>,
+                Option<String>,
+                i64,
+                i64,
+                i64,
+            ),
+        >(
+            "SELECT enabled, device_id, last_sync_at, last_error, \
+             (SELECT COUNT(*) FROM sync_ops WHERE seq > last_pushed_seq), \
+             (SELECT COUNT(*) FROM sync_inbox), \
+             (SELECT COUNT(*) FROM sync_conflicts WHERE resolved_at IS NULL) \
+             FROM sync_state WHERE id = 1",
+        )
+        .fetch_one(&db.pool)
+        .await
+        .map_err(|e| format!("Failed to load sync state: {}", e))?;
+
+    let peers = sqlx::query_as::<_, SyncPeer>(
+        "SELECT device_id, last_seq, last_seen_at FROM sync_peers ORDER BY last_seen_at DESC",
+    )
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| format!("Failed to load sync peers: {}", e))?;
+
+    Ok(SyncStatus {
+        enabled,
+        device_id,
+        last_sync_at,
+        last_error,
+        outgoing,
+        waiting,
+        open_conflicts,
+        peers,
+    })
+}
+
+// Turn on sync. Every device in the set uses the same passphrase, which
+// encrypts the changelog. Turning it on shares everything already here.
+#[tauri::command]
+pub async fn enable_sync(
+    db: State<'_, Database>,
+    passphrase: String,
+) -> Result<SyncStatus, AppError> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(AppError::Validation(format!(
+            "Passphrase must be at least {} characters",
+            MIN_PASSPHRASE_LEN
+        )));
+    }
+
+    let guard = SYNC_LOCK.lock().await;
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_state(&mut tx).await?;
+    sqlx::query(
+        "UPDATE sync_state SET enabled = 1, device_id = COALESCE(device_id, ?), \
+         passphrase = ?, updated_at = CURRENT_TIMESTAMP WHERE id = 1",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(conceal(&passphrase)?)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to turn on sync: {}", e))?;
+
+    // Already on: only the passphrase changed
+    if !before.enabled {
+        let tables = load_tables(&mut tx).await?;
+        install_triggers(&mut tx, &tables).await?;
+        snapshot_rows(&mut tx, &tables).await?;
+    }
+    let after = load_state(&mut tx).await?;
+
+    // The passphrase stays out of the audit log
+    let view = |s: &SyncState| {
+        serde_json::json!({
+            "enabled": s.enabled,
+            "device_id": s.device_id,
+        })
+    };
+    audit::record(
+        &mut *tx,
+        "sync_state",
+        1,
+        "update",
+        Some(&view(&before)),
+        Some(&view(&after)),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to turn on sync: {}", e))?;
+    drop(guard);
+    get_sync_status(db).await
+}
+
+// Turn off sync. Changes made while it's off are shared when it's turned on
+// again.
+#[tauri::command]
+pub async fn disable_sync(db: State<'_, Database>) -> Result<SyncStatus, AppError> {
+    let guard = SYNC_LOCK.lock().await;
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_state(&mut tx).await?;
+    sqlx::query("UPDATE sync_state SET enabled = 0, updated_at = CURRENT_TIMESTAMP WHERE id = 1")
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to turn off sync: {}", e))?;
+    drop_triggers(&mut tx).await?;
+
+    audit::record(
+        &mut *tx,
+        "sync_state",
+        1,
+        "update",
+        Some(&serde_json::json!({ "enabled": before.enabled })),
+        Some(&serde_json::json!({ "enabled": false })),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to turn off sync: {}", e))?;
+    drop(guard);
+    get_sync_status(db).await
+}
+
+#[tauri::command]
+pub async fn sync_now(app: AppHandle, db: State<'_, Database>) -> Result<SyncReport, AppError> {
+    run_sync(&app, &db.pool).await
+}
+
+#[tauri::command]
+pub async fn list_sync_conflicts(db: State<'_, Database>) -> Result<Vec<SyncConflict>, AppError> {
+    sqlx::query_as::<_, SyncConflict>(
+        "SELECT id, device_id, table_name, row_gid, kind, data, error, created_at \
+         FROM sync_conflicts WHERE resolved_at IS NULL ORDER BY id",
+    )
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to load sync conflicts: {}", e)))
+}
+
+// Settle a conflict: keep this device's data, or fix the clash locally (e.g.
+// renumber an invoice) and retry the other device's change
+#[tauri::command]
+pub async fn resolve_sync_conflict(
+    db: State<'_, Database>,
+    id: i64,
+    retry: bool,
+) -> Result<(), AppError> {
+    let _guard = SYNC_LOCK.lock().await;
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    if retry {
+        sqlx::query(
+            "INSERT OR REPLACE INTO sync_inbox \
+             (device_id, seq, clock, table_name, row_gid, kind, data) \
+             SELECT device_id, seq, clock, table_name, row_gid, kind, data \
+             FROM sync_conflicts WHERE id = ? AND resolved_at IS NULL",
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to retry change: {}", e))?;
+    }
+    let resolved = sqlx::query(
+        "UPDATE sync_conflicts SET resolved_at = CURRENT_TIMESTAMP \
+         WHERE id = ? AND resolved_at IS NULL",
+    )
+    .bind(id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to resolve conflict: {}", e))?;
+    if resolved.rows_affected() == 0 {
+        return Err(AppError::NotFound(format!(
+            "Sync conflict {} not found",
+            id
+        )));
+    }
+    let tables = load_tables(&mut tx).await?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to resolve conflict: {}", e))?;
+
+    if retry {
+        apply_inbox(&db.pool, &tables).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,154 @@
+use reqwest::{Request, RequestBuilder, Response};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::error::AppError;
+use crate::log_warning;
+use crate::redact::redact;
+use crate::throttle;
+
+// Calls kept in the buffer; the oldest are dropped first
+const MAX_CALLS: usize = 500;
+
+// Off by default and on for this run only, since it's meant for chasing down
+// one problem rather than left running
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static CALLS: Mutex<VecDeque<ApiCall>> = Mutex::new(VecDeque::new());
+
+// One Google API call. Only metadata is kept: no headers, query string or
+// bodies, so tokens and customer data never reach the buffer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiCall {
+    pub at: String,
+    pub method: String,
+    // Host and path, e.g. gmail.googleapis.com/gmail/v1/users/me/messages/send
+    pub endpoint: String,
+    // None when no response came back
+    pub status: Option<u16>,
+    pub latency_ms: u64,
+    pub retries: u32,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiDiagnostics {
+    pub enabled: bool,
+    // Oldest first
+    pub calls: Vec<ApiCall>,
+}
+
+// Helper: Endpoint of a URL without the query string or fragment
+fn endpoint(url: &reqwest::Url) -> String {
+    redact(&format!("{}{}", url.host_str().unwrap_or_default(), url.path()))
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+// Add a call to the buffer while diagnostics are on
+pub fn record(call: ApiCall) {
+    if !is_enabled() {
+        return;
+    }
+    match CALLS.lock() {
+        Ok(mut calls) => {
+            if calls.len() >= MAX_CALLS {
+                calls.pop_front();
+            }
+            calls.push_back(call);
+        }
+        Err(e) => log_warning!("Failed to record API call: {}", e),
+    }
+}
+
+// What to record about a request, noted just before it's sent
+pub struct PendingCall {
+    at: String,
+    method: String,
+    endpoint: String,
+    started: Instant,
+}
+
+impl PendingCall {
+    pub fn start(request: &Request) -> Self {
+        PendingCall {
+            at: chrono::Utc::now().to_rfc3339(),
+            method: request.method().to_string(),
+            endpoint: endpoint(request.url()),
+            started: Instant::now(),
+        }
+    }
+
+    // Record the outcome, after `retries` failed attempts
+    pub fn finish(self, retries: u32, result: &reqwest::Result<Response>) {
+        record(ApiCall {
+            at: self.at,
+            method: self.method,
+            endpoint: self.endpoint,
+            status: match result {
+                Ok(response) => Some(response.status().as_u16()),
+                Err(e) => e.status().map(|status| status.as_u16()),
+            },
+            latency_ms: self.started.elapsed().as_millis() as u64,
+            retries,
+            error: result.as_ref().err().map(|e| redact(&e.to_string())),
+        });
+    }
+}
+
+// Send a request once, recording it while diagnostics are on. Used where the
+// caller handles failures itself, like resumable upload chunks; other Google
+// API calls go through http::SendWithRetry.
+pub trait SendRecorded {
+    fn send_recorded(self) -> impl Future<Output = reqwest::Result<Response>> + Send;
+}
+
+impl SendRecorded for RequestBuilder {
+    async fn send_recorded(self) -> reqwest::Result<Response> {
+        let (client, request) = self.build_split();
+        let request = request?;
+        throttle::acquire(request.url()).await;
+        if !is_enabled() {
+            return client.execute(request).await;
+        }
+        let call = PendingCall::start(&request);
+        let result = client.execute(request).await;
+        call.finish(0, &result);
+        result
+    }
+}
+
+#[tauri::command]
+pub fn get_api_diagnostics() -> Result<ApiDiagnostics, AppError> {
+    let calls = CALLS
+        .lock()
+        .map_err(|e| format!("Failed to read API diagnostics: {}", e))?;
+    Ok(ApiDiagnostics {
+        enabled: is_enabled(),
+        calls: calls.iter().cloned().collect(),
+    })
+}
+
+// Turn recording on or off; turning it off clears the buffer
+#[tauri::command]
+pub fn set_api_diagnostics(enabled: bool) -> Result<ApiDiagnostics, AppError> {
+    ENABLED.store(enabled, Ordering::SeqCst);
+    if !enabled {
+        clear_api_diagnostics()?;
+    }
+    get_api_diagnostics()
+}
+
+#[tauri::command]
+pub fn clear_api_diagnostics() -> Result<(), AppError> {
+    CALLS
+        .lock()
+        .map_err(|e| format!("Failed to clear API diagnostics: {}", e))?
+        .clear();
+    Ok(())
+}
@@ -0,0 +1,348 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use printpdf::image_crate::{self as image, imageops::FilterType, DynamicImage, ImageOutputFormat};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::SqliteConnection;
+use std::io::Cursor;
+use std::path::PathBuf;
+use tauri::{AppHandle, State};
+use uuid::Uuid;
+
+use crate::audit;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::log_warning;
+use crate::pdf::save_pdf;
+
+const DOCUMENTS_DIR: &str = "documents";
+pub const DOCUMENT_COLUMNS: &str = "id, preorder_id, kind, file_name, mime_type, width, height, \
+     size_bytes, sha256, notes, created_at";
+
+// Phone cameras produce 5-15 MB photos; anything far beyond that isn't one
+const MAX_UPLOAD_BYTES: usize = 40 * 1024 * 1024;
+// Long edge after downscaling; plenty to read a receipt or a signature
+const MAX_EDGE: u32 = 2000;
+const JPEG_QUALITY: u8 = 85;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum DocumentKind {
+    // Transfer receipt or screenshot
+    PaymentProof,
+    // Delivery note signed by the customer
+    DeliveryNote,
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct OrderDocument {
+    pub id: i64,
+    pub preorder_id: i64,
+    pub kind: DocumentKind,
+    // Relative to the profile's documents folder
+    pub file_name: String,
+    pub mime_type: String,
+    pub width: i64,
+    pub height: i64,
+    pub size_bytes: i64,
+    pub sha256: String,
+    pub notes: Option<String>,
+    pub created_at: Option<String>,
+}
+
+// Helper: The active profile's documents folder
+fn documents_dir(app: &AppHandle) -> Result<PathBuf, AppError> {
+    Ok(crate::profiles::active_data_dir(app)?.join(DOCUMENTS_DIR))
+}
+
+// Helper: Where a document's file is on disk
+pub fn document_path(app: &AppHandle, document: &OrderDocument) -> Result<PathBuf, AppError> {
+    Ok(documents_dir(app)?.join(document.file_name.split('/').collect::<PathBuf>()))
+}
+
+async fn load_document(conn: &mut SqliteConnection, id: i64) -> Result<OrderDocument, AppError> {
+    sqlx::query_as::<_, OrderDocument>(&format!(
+        "SELECT {} FROM order_documents WHERE id = ?",
+        DOCUMENT_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| format!("Failed to load document: {}", e))?
+    .ok_or_else(|| AppError::NotFound(format!("Document {} not found", id)))
+}
+
+// Helper: EXIF orientation tag (1-8) of a JPEG, if it has one. Phones store
+// photos as the sensor saw them and record the rotation here.
+fn exif_orientation(bytes: &[u8]) -> Option<u16> {
+    if !bytes.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let mut pos = 2;
+    while bytes.get(pos) == Some(&0xFF) {
+        let marker = *bytes.get(pos + 1)?;
+        // Start of the image data; no more metadata after this
+        if marker == 0xDA {
+            return None;
+        }
+        let length = u16::from_be_bytes([*bytes.get(pos + 2)?, *bytes.get(pos + 3)?]) as usize;
+        let segment = bytes.get(pos + 4..pos + 2 + length)?;
+        if marker == 0xE1 && segment.starts_with(b"Exif\0\0") {
+            return tiff_orientation(&segment[6..]);
+        }
+        pos += 2 + length;
+    }
+    None
+}
+
+// Helper: Orientation entry (tag 0x0112) of the first IFD in an EXIF block
+fn tiff_orientation(tiff: &[u8]) -> Option<u16> {
+    let big_endian = match tiff.get(..2)? {
+        b"MM" => true,
+        b"II" => false,
+        _ => return None,
+    };
+    let u16_at = |at: usize| {
+        let b = tiff.get(at..at + 2)?;
+        Some(if big_endian {
+            u16::from_be_bytes([b[0], b[1]])
+        } else {
+            u16::from_le_bytes([b[0], b[1]])
+        })
+    };
+    let u32_at = |at: usize| {
+        let b = tiff.get(at..at + 4)?;
+        Some(if big_endian {
+            u32::from_be_bytes([b[0], b[1], b[2], b[3]])
+        } else {
+            u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+        })
+    };
+    let ifd = u32_at(4)? as usize;
+    let entries = u16_at(ifd)? as usize;
+    (0..entries).find_map(|i| {
+        let entry = ifd + 2 + i * 12;
+        (u16_at(entry)? == 0x0112)
+            .then(|| u16_at(entry + 8))
+            .flatten()
+    })
+}
+
+// Helper: Turn the pixels the way the EXIF orientation says to display them
+fn apply_orientation(image: DynamicImage, orientation: u16) -> DynamicImage {
+    match orientation {
+        2 => image.fliph(),
+        3 => image.rotate180(),
+        4 => image.flipv(),
+        5 => image.rotate90().fliph(),
+        6 => image.rotate90(),
+        7 => image.rotate270().fliph(),
+        8 => image.rotate270(),
+        _ => image,
+    }
+}
+
+// Helper: Camera capture or screenshot as an upright JPEG no larger than
+// MAX_EDGE. Re-encoding also drops the metadata, including any GPS position.
+fn normalize_image(bytes: &[u8]) -> Result<DynamicImage, AppError> {
+    let decoded =
+        image::load_from_memory(bytes).map_err(|e| format!("Failed to read image: {}", e))?;
+    let mut upright = apply_orientation(decoded, exif_orientation(bytes).unwrap_or(1));
+    if upright.width() > MAX_EDGE || upright.height() > MAX_EDGE {
+        upright = upright.resize(MAX_EDGE, MAX_EDGE, FilterType::Lanczos3);
+    }
+    // JPEG has no alpha; screenshots with transparency go onto white
+    let rgba = upright.to_rgba8();
+    let flattened = image::RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let blend = |c: u8| ((c as u32 * a as u32 + 255 * (255 - a as u32)) / 255) as u8;
+        image::Rgb([blend(r), blend(g), blend(b)])
+    });
+    Ok(DynamicImage::ImageRgb8(flattened))
+}
+
+// Attach a photo captured on mobile (payment proof, signed delivery note) to
+// an order. The image is straightened, downscaled and stored as a JPEG under
+// the app data dir. Attaching the same capture twice returns the first copy.
+#[tauri::command]
+pub async fn attach_document(
+    app: AppHandle,
+    db: State<'_, Database>,
+    po_id: i64,
+    bytes: Vec<u8>,
+    kind: DocumentKind,
+    notes: Option<String>,
+) -> Result<OrderDocument, AppError> {
+    if bytes.is_empty() {
+        return Err(AppError::Validation("Document is empty".to_string()));
+    }
+    if bytes.len() > MAX_UPLOAD_BYTES {
+        return Err(AppError::Validation(format!(
+            "Document is too large ({} MB); the limit is {} MB",
+            bytes.len() / (1024 * 1024),
+            MAX_UPLOAD_BYTES / (1024 * 1024)
+        )));
+    }
+
+    let image = normalize_image(&bytes)?;
+    let mut jpeg = Vec::new();
+    image
+        .write_to(
+            &mut Cursor::new(&mut jpeg),
+            ImageOutputFormat::Jpeg(JPEG_QUALITY),
+        )
+        .map_err(|e| format!("Failed to encode image: {}", e))?;
+    let sha256: String = Sha256::digest(&jpeg)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    let notes = notes
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty());
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    sqlx::query_scalar::<_, i64>("SELECT id FROM preorders WHERE id = ? AND deleted_at IS NULL")
+        .bind(po_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to load order: {}", e))?
+        .ok_or_else(|| AppError::NotFound(format!("Order {} not found", po_id)))?;
+
+    let existing = sqlx::query_scalar::<_, i64>(
+        "SELECT id FROM order_documents WHERE preorder_id = ? AND sha256 = ?",
+    )
+    .bind(po_id)
+    .bind(&sha256)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to check existing documents: {}", e))?;
+    if let Some(id) = existing {
+        return load_document(&mut tx, id).await;
+    }
+
+    let stored = format!("{}.jpg", Uuid::new_v4());
+    let dir = documents_dir(&app)?.join(po_id.to_string());
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create documents dir: {}", e))?;
+    let path = dir.join(&stored);
+    let file_name = format!("{}/{}", po_id, stored);
+    save_pdf(&path, &jpeg)?;
+
+    let saved = async {
+        let id = sqlx::query(
+            "INSERT INTO order_documents \
+             (preorder_id, kind, file_name, mime_type, width, height, size_bytes, sha256, notes) \
+             VALUES (?, ?, ?, 'image/jpeg', ?, ?, ?, ?, ?)",
+        )
+        .bind(po_id)
+        .bind(kind)
+        .bind(&file_name)
+        .bind(image.width() as i64)
+        .bind(image.height() as i64)
+        .bind(jpeg.len() as i64)
+        .bind(&sha256)
+        .bind(&notes)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to save document: {}", e))?
+        .last_insert_rowid();
+
+        let document = load_document(&mut tx, id).await?;
+        audit::record(
+            &mut *tx,
+            "order_document",
+            id,
+            "create",
+            None,
+            Some(&document),
+        )
+        .await?;
+        tx.commit()
+            .await
+            .map_err(|e| format!("Failed to save document: {}", e))?;
+        Ok(document)
+    }
+    .await;
+    if saved.is_err() {
+        // Don't leave a file nothing points at
+        let _ = std::fs::remove_file(&path);
+    }
+    saved
+}
+
+#[tauri::command]
+pub async fn list_order_documents(
+    db: State<'_, Database>,
+    po_id: i64,
+) -> Result<Vec<OrderDocument>, AppError> {
+    sqlx::query_as::<_, OrderDocument>(&format!(
+        "SELECT {} FROM order_documents WHERE preorder_id = ? ORDER BY created_at, id",
+        DOCUMENT_COLUMNS
+    ))
+    .bind(po_id)
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to load documents: {}", e)))
+}
+
+// The stored image, base64-encoded for an <img> data URL
+#[tauri::command]
+pub async fn read_order_document(
+    app: AppHandle,
+    db: State<'_, Database>,
+    id: i64,
+) -> Result<String, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let document = load_document(&mut conn, id).await?;
+    let bytes = std::fs::read(document_path(&app, &document)?)
+        .map_err(|e| format!("Failed to read document: {}", e))?;
+    Ok(STANDARD.encode(bytes))
+}
+
+#[tauri::command]
+pub async fn delete_order_document(
+    app: AppHandle,
+    db: State<'_, Database>,
+    id: i64,
+) -> Result<(), AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let document = load_document(&mut tx, id).await?;
+    sqlx::query("DELETE FROM order_documents WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to delete document: {}", e))?;
+    audit::record(
+        &mut *tx,
+        "order_document",
+        id,
+        "delete",
+        Some(&document),
+        None,
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to delete document: {}", e))?;
+
+    // The row is gone either way; a leftover file is only wasted space
+    if let Err(e) = std::fs::remove_file(document_path(&app, &document)?) {
+        log_warning!("Failed to remove document file: {}", e);
+    }
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use sqlx::{SqliteConnection, SqlitePool};
+
+use crate::audit::AuditLog;
+use crate::chat_alerts::ChatAlerts;
+use crate::desktop_notifications::DesktopNotifications;
+use crate::error::AppError;
+use crate::inventory::StockLevel;
+use crate::log_warning;
+use crate::order_status::OrderStatusChanged;
+use crate::outgoing_webhooks::OutgoingWebhooks;
+use crate::payments::Payment;
+use crate::sms::Sms;
+use crate::telegram::Telegram;
+use crate::timeline::{EmailChannel, Timeline};
+use crate::whatsapp::WhatsApp;
+
+// Something that happened to the business, published once by the code that
+// made it happen. Everything that reacts to it (audit log, timeline,
+// webhooks, notification channels) subscribes instead of being called
+// directly.
+#[derive(Debug, Clone, Copy)]
+pub enum DomainEvent<'a> {
+    OrderCreated {
+        order_id: i64,
+    },
+    OrderStatusChanged(&'a OrderStatusChanged),
+    PaymentRecorded(&'a Payment),
+    // order_id is set for emails about an order
+    EmailSent {
+        order_id: Option<i64>,
+        to_email: &'a str,
+        subject: &'a str,
+        channel: EmailChannel,
+    },
+    EmailFailed {
+        to_email: &'a str,
+        subject: &'a str,
+        error: &'a str,
+    },
+    // A stock movement took a product down to its low-stock threshold
+    StockLow(&'a StockLevel),
+    // The reminder engine queued this many payment reminders
+    PaymentRemindersDue {
+        count: u64,
+    },
+}
+
+// Reacts to domain events. Handlers run inside the publisher's transaction,
+// so an error rolls back the change that raised the event; anything slow or
+// external (sending messages, calling APIs) is queued here and done later by
+// the subscriber's own background sender.
+pub trait Subscriber {
+    async fn handle(
+        &self,
+        conn: &mut SqliteConnection,
+        event: &DomainEvent<'_>,
+    ) -> Result<(), AppError>;
+}
+
+// Hand an event to every subscriber, in order. A new integration implements
+// Subscriber and is added here.
+pub async fn publish(conn: &mut SqliteConnection, event: &DomainEvent<'_>) -> Result<(), AppError> {
+    AuditLog.handle(&mut *conn, event).await?;
+    Timeline.handle(&mut *conn, event).await?;
+    OutgoingWebhooks.handle(&mut *conn, event).await?;
+    Telegram.handle(&mut *conn, event).await?;
+    ChatAlerts.handle(&mut *conn, event).await?;
+    DesktopNotifications.handle(&mut *conn, event).await?;
+    WhatsApp.handle(&mut *conn, event).await?;
+    Sms.handle(&mut *conn, event).await?;
+    Ok(())
+}
+
+// Helper: Publish an event that isn't part of a database change, e.g. an
+// email going out
+pub async fn publish_now(pool: &SqlitePool, event: &DomainEvent<'_>) -> Result<(), AppError> {
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    publish(&mut conn, event).await
+}
+
+// Helper: Pass an email send result through, publishing EmailFailed when it
+// failed. Problems publishing are only logged; the send error is what counts.
+pub async fn check_email_sent<T>(
+    pool: &SqlitePool,
+    to_email: &str,
+    subject: &str,
+    result: Result<T, AppError>,
+) -> Result<T, AppError> {
+    if let Err(error) = &result {
+        let failed = DomainEvent::EmailFailed {
+            to_email,
+            subject,
+            error: error.message(),
+        };
+        if let Err(e) = publish_now(pool, &failed).await {
+            log_warning!("Failed to publish email failure: {}", e);
+        }
+    }
+    result
+}
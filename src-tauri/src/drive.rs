@@ -0,0 +1,1337 @@
+use reqwest::header::{CONTENT_LENGTH, CONTENT_RANGE, LOCATION, RANGE};
+use reqwest::Client;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter, State};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use uuid::Uuid;
+
+use crate::diagnostics::SendRecorded;
+use crate::error::AppError;
+use crate::google_cache::{CacheKind, GoogleCache};
+use crate::http::{self, SendWithRetry};
+use crate::log_warning;
+
+// Drive permission structs
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DrivePermission {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub permission_type: String,
+    pub role: String,
+    #[serde(rename = "emailAddress")]
+    pub email_address: Option<String>,
+    #[serde(rename = "displayName")]
+    pub display_name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DrivePermissionList {
+    pub permissions: Vec<DrivePermission>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DriveSearchResult {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "mimeType")]
+    pub mime_type: String,
+    #[serde(rename = "modifiedTime")]
+    pub modified_time: Option<String>,
+    #[serde(rename = "webViewLink")]
+    pub web_view_link: Option<String>,
+    #[serde(rename = "iconLink")]
+    pub icon_link: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DriveSearchList {
+    pub files: Vec<DriveSearchResult>,
+}
+
+// Drive Changes API structs
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DriveChangeFile {
+    pub id: String,
+    pub name: Option<String>,
+    pub trashed: Option<bool>,
+    #[serde(rename = "modifiedTime")]
+    pub modified_time: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DriveChange {
+    #[serde(rename = "fileId")]
+    pub file_id: Option<String>,
+    pub removed: Option<bool>,
+    pub file: Option<DriveChangeFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DriveChangeList {
+    #[serde(rename = "nextPageToken")]
+    pub next_page_token: Option<String>,
+    #[serde(rename = "newStartPageToken")]
+    pub new_start_page_token: Option<String>,
+    pub changes: Vec<DriveChange>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StartPageToken {
+    #[serde(rename = "startPageToken")]
+    start_page_token: String,
+}
+
+// Emitted as the "drive-file-changed" event for every change to a watched file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchedFileChange {
+    pub file_id: String,
+    // "modified", "trashed" or "removed"
+    pub kind: String,
+    pub name: Option<String>,
+    pub modified_time: Option<String>,
+}
+
+// Escape a value for use inside a single-quoted Drive query literal
+fn escape_query_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+// Validate a file or folder name before it is created or searched for
+pub fn validate_drive_name(name: &str) -> Result<(), AppError> {
+    if name.trim().is_empty() {
+        return Err(AppError::Validation("Name must not be empty".to_string()));
+    }
+    if name.chars().count() > 255 {
+        return Err(AppError::Validation(
+            "Name must be at most 255 characters".to_string(),
+        ));
+    }
+    if name.chars().any(|c| c.is_control()) {
+        return Err(AppError::Validation(
+            "Name must not contain control characters".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// Builder for Drive `q` search strings. Every literal goes through
+// escape_query_value so user-supplied names can't alter the query.
+#[derive(Debug, Default)]
+pub struct DriveQuery {
+    clauses: Vec<String>,
+}
+
+impl DriveQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn name_equals(mut self, name: &str) -> Self {
+        self.clauses
+            .push(format!("name='{}'", escape_query_value(name)));
+        self
+    }
+
+    pub fn name_contains(mut self, text: &str) -> Self {
+        self.clauses
+            .push(format!("name contains '{}'", escape_query_value(text)));
+        self
+    }
+
+    pub fn text_contains(mut self, text: &str) -> Self {
+        let escaped = escape_query_value(text);
+        self.clauses.push(format!(
+            "(name contains '{}' or fullText contains '{}')",
+            escaped, escaped
+        ));
+        self
+    }
+
+    pub fn mime_type(mut self, mime_type: &str) -> Self {
+        self.clauses
+            .push(format!("mimeType='{}'", escape_query_value(mime_type)));
+        self
+    }
+
+    pub fn mime_type_in(mut self, mime_types: &[String]) -> Self {
+        if mime_types.is_empty() {
+            return self;
+        }
+        let alternatives: Vec<String> = mime_types
+            .iter()
+            .map(|t| format!("mimeType='{}'", escape_query_value(t)))
+            .collect();
+        self.clauses
+            .push(format!("({})", alternatives.join(" or ")));
+        self
+    }
+
+    pub fn folder(self) -> Self {
+        self.mime_type("application/vnd.google-apps.folder")
+    }
+
+    pub fn in_parent(mut self, parent_id: &str) -> Self {
+        self.clauses
+            .push(format!("'{}' in parents", escape_query_value(parent_id)));
+        self
+    }
+
+    pub fn in_any_parent(mut self, parent_ids: &[String]) -> Self {
+        if parent_ids.is_empty() {
+            return self;
+        }
+        let alternatives: Vec<String> = parent_ids
+            .iter()
+            .map(|id| format!("'{}' in parents", escape_query_value(id)))
+            .collect();
+        self.clauses
+            .push(format!("({})", alternatives.join(" or ")));
+        self
+    }
+
+    pub fn modified_after(mut self, timestamp: &chrono::DateTime<chrono::Utc>) -> Self {
+        self.clauses.push(format!(
+            "modifiedTime > '{}'",
+            timestamp.format("%Y-%m-%dT%H:%M:%S")
+        ));
+        self
+    }
+
+    pub fn trashed(mut self, trashed: bool) -> Self {
+        self.clauses.push(format!("trashed={}", trashed));
+        self
+    }
+
+    pub fn build(&self) -> String {
+        self.clauses.join(" and ")
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AppDriveFile {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "mimeType")]
+    pub mime_type: String,
+    pub parents: Option<Vec<String>>,
+    #[serde(rename = "trashedTime")]
+    pub trashed_time: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AppDriveFileList {
+    pub files: Vec<AppDriveFile>,
+    #[serde(rename = "nextPageToken")]
+    pub next_page_token: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DriveParents {
+    parents: Option<Vec<String>>,
+}
+
+// Storage quota from the About endpoint. Drive sends numbers as strings and
+// omits the limit for unlimited accounts.
+#[derive(Debug, Serialize, Deserialize)]
+struct DriveAbout {
+    #[serde(rename = "storageQuota")]
+    storage_quota: DriveStorageQuota,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DriveStorageQuota {
+    limit: Option<String>,
+    usage: Option<String>,
+    #[serde(rename = "usageInDrive")]
+    usage_in_drive: Option<String>,
+    #[serde(rename = "usageInDriveTrash")]
+    usage_in_drive_trash: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DriveQuota {
+    // None means the account has unlimited storage
+    pub limit: Option<u64>,
+    pub usage: u64,
+    pub usage_in_drive: u64,
+    pub usage_in_trash: u64,
+    pub available: Option<u64>,
+}
+
+// Emitted as the "upload-progress" event while a resumable upload runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadProgress {
+    pub upload_id: String,
+    pub file_name: String,
+    pub bytes_sent: u64,
+    pub total_bytes: u64,
+}
+
+// Data to upload: either already in memory or read from disk chunk by chunk
+pub enum UploadSource {
+    Bytes(Vec<u8>),
+    File(PathBuf),
+}
+
+enum UploadStatus {
+    Incomplete(u64),
+    Complete(String),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct UploadedFile {
+    id: String,
+}
+
+// Chunks must be a multiple of 256 KiB
+const RESUMABLE_CHUNK_SIZE: usize = 8 * 256 * 1024;
+const MAX_CHUNK_RETRIES: u32 = 5;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DriveThumbnailInfo {
+    #[serde(rename = "mimeType")]
+    mime_type: String,
+    #[serde(rename = "thumbnailLink")]
+    thumbnail_link: Option<String>,
+    size: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DriveThumbnail {
+    pub file_id: String,
+    pub mime_type: String,
+    // Ready to use as an <img> src
+    pub data_url: String,
+}
+
+// Images without a Drive-generated thumbnail are returned as-is up to this size
+const MAX_INLINE_IMAGE_BYTES: u64 = 5 * 1024 * 1024;
+const DEFAULT_THUMBNAIL_SIZE: u32 = 320;
+
+// Root folder that holds every artifact the app creates
+const APP_ROOT_FOLDER: &str = "po-tracker";
+// Deepest nesting the app creates: po-tracker/<project>/images/<file>
+const APP_FOLDER_DEPTH: usize = 3;
+
+// Roles that can be granted to collaborators. Ownership transfer is deliberately
+// not supported from inside the app.
+const SHAREABLE_ROLES: [&str; 3] = ["reader", "commenter", "writer"];
+
+// Share a Drive file (e.g. the order form) with a collaborator by email
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(file_id = %file_id, role = %role), err)]
+pub async fn share_drive_file(
+    access_token: String,
+    file_id: String,
+    email: String,
+    role: String,
+) -> Result<DrivePermission, AppError> {
+    if !SHAREABLE_ROLES.contains(&role.as_str()) {
+        return Err(AppError::Validation(format!(
+            "Invalid role '{}': expected one of {}",
+            role,
+            SHAREABLE_ROLES.join(", ")
+        )));
+    }
+
+    let client = http::client();
+
+    let body = serde_json::json!({
+        "type": "user",
+        "role": role,
+        "emailAddress": email
+    });
+
+    let response = client
+        .post(format!(
+            "https://www.googleapis.com/drive/v3/files/{}/permissions",
+            file_id
+        ))
+        .query(&[
+            ("sendNotificationEmail", "true"),
+            ("fields", "id,type,role,emailAddress,displayName"),
+        ])
+        .bearer_auth(&access_token)
+        .json(&body)
+        .send_with_retry()
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to share file: {}", e)))?;
+
+    if !response.status().is_success() {
+        return Err(http::google_error(response, "Drive API share error").await);
+    }
+
+    response
+        .json::<DrivePermission>()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to parse permission: {}", e)))
+}
+
+// List everyone who has access to a Drive file
+#[tauri::command]
+pub async fn list_permissions(
+    access_token: String,
+    file_id: String,
+    operation_id: Option<String>,
+) -> Result<Vec<DrivePermission>, AppError> {
+    http::cancellable(operation_id, async move {
+        let client = http::client();
+
+        let response = client
+            .get(format!(
+                "https://www.googleapis.com/drive/v3/files/{}/permissions",
+                file_id
+            ))
+            .query(&[(
+                "fields",
+                "permissions(id,type,role,emailAddress,displayName)",
+            )])
+            .bearer_auth(&access_token)
+            .send_with_retry()
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to list permissions: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(http::google_error(response, "Drive API permission list error").await);
+        }
+
+        let list: DrivePermissionList = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse permission list: {}", e))?;
+
+        Ok(list.permissions)
+    })
+    .await
+}
+
+// Revoke a collaborator's access to a Drive file
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(file_id = %file_id), err)]
+pub async fn remove_permission(
+    access_token: String,
+    file_id: String,
+    permission_id: String,
+) -> Result<String, AppError> {
+    let client = http::client();
+
+    let response = client
+        .delete(format!(
+            "https://www.googleapis.com/drive/v3/files/{}/permissions/{}",
+            file_id, permission_id
+        ))
+        .bearer_auth(&access_token)
+        .send_with_retry()
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to remove permission: {}", e)))?;
+
+    if !response.status().is_success() {
+        return Err(http::google_error(response, "Drive API permission delete error").await);
+    }
+
+    Ok("Permission removed".to_string())
+}
+
+// Search Drive by free text, MIME type and modification date (used by the file picker)
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn search_drive(
+    access_token: String,
+    query: String,
+    mime_types: Option<Vec<String>>,
+    modified_after: Option<String>,
+    operation_id: Option<String>,
+) -> Result<Vec<DriveSearchResult>, AppError> {
+    http::cancellable(operation_id, async move {
+        let mut builder = DriveQuery::new().trashed(false);
+
+        let text = query.trim();
+        if !text.is_empty() {
+            builder = builder.text_contains(text);
+        }
+
+        if let Some(types) = mime_types {
+            builder = builder.mime_type_in(&types);
+        }
+
+        if let Some(after) = modified_after {
+            let timestamp = chrono::DateTime::parse_from_rfc3339(&after)
+                .map_err(|e| format!("Invalid modified_after date '{}': {}", after, e))?;
+            builder = builder.modified_after(&timestamp.with_timezone(&chrono::Utc));
+        }
+
+        let q = builder.build();
+        let client = http::client();
+
+        let response = client
+            .get("https://www.googleapis.com/drive/v3/files")
+            .query(&[
+                ("q", q.as_str()),
+                (
+                    "fields",
+                    "files(id,name,mimeType,modifiedTime,webViewLink,iconLink)",
+                ),
+                ("orderBy", "modifiedTime desc"),
+                ("pageSize", "100"),
+            ])
+            .bearer_auth(&access_token)
+            .send_with_retry()
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to search Drive: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(http::google_error(response, "Drive API search error").await);
+        }
+
+        let list: DriveSearchList = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse search results: {}", e))?;
+
+        Ok(list.files)
+    })
+    .await
+}
+
+// Helper: Location of the persisted Drive changes page token (per profile,
+// since each profile can use a different Google account)
+fn changes_token_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+    let dir = crate::profiles::active_data_dir(app)?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app data dir: {}", e))?;
+    Ok(dir.join("drive_changes_token"))
+}
+
+// Helper: Fetch a fresh start page token for the changes feed
+async fn fetch_start_page_token(client: &Client, access_token: &str) -> Result<String, AppError> {
+    let response = client
+        .get("https://www.googleapis.com/drive/v3/changes/startPageToken")
+        .bearer_auth(access_token)
+        .send_with_retry()
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to get start page token: {}", e)))?;
+
+    if !response.status().is_success() {
+        return Err(http::google_error(response, "Drive API changes error").await);
+    }
+
+    let token: StartPageToken = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse start page token: {}", e))?;
+
+    Ok(token.start_page_token)
+}
+
+// Helper: Current state of every watched file, reported as changes. Used when
+// the changes feed can't say what happened since the last poll.
+async fn resync_watched_files(
+    client: &Client,
+    access_token: &str,
+    watched_file_ids: &[String],
+) -> Result<Vec<WatchedFileChange>, AppError> {
+    let mut changes = Vec::new();
+    for file_id in watched_file_ids {
+        let response = client
+            .get(format!(
+                "https://www.googleapis.com/drive/v3/files/{}",
+                file_id
+            ))
+            .query(&[("fields", "id,name,trashed,modifiedTime")])
+            .bearer_auth(access_token)
+            .send_with_retry()
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to get file: {}", e)))?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            changes.push(WatchedFileChange {
+                file_id: file_id.clone(),
+                kind: "removed".to_string(),
+                name: None,
+                modified_time: None,
+            });
+            continue;
+        }
+        if !response.status().is_success() {
+            return Err(http::google_error(response, "Drive API error").await);
+        }
+
+        let file: DriveChangeFile = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse file: {}", e))?;
+        let kind = if file.trashed.unwrap_or(false) {
+            "trashed"
+        } else {
+            "modified"
+        };
+        changes.push(WatchedFileChange {
+            file_id: file_id.clone(),
+            kind: kind.to_string(),
+            name: file.name,
+            modified_time: file.modified_time,
+        });
+    }
+    Ok(changes)
+}
+
+// Poll the Drive changes feed and report changes to watched files.
+// The first call only records a starting point; later calls return everything
+// that happened since the previous poll and emit a "drive-file-changed" event per change.
+// If Drive rejects the stored token (expired or invalid), a fresh one is stored
+// and every watched file is reported so callers resync in full.
+#[tauri::command]
+pub async fn poll_drive_changes(
+    app: AppHandle,
+    access_token: String,
+    watched_file_ids: Vec<String>,
+) -> Result<Vec<WatchedFileChange>, AppError> {
+    let client = http::client();
+    let token_path = changes_token_path(&app)?;
+
+    let stored_token = std::fs::read_to_string(&token_path)
+        .ok()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty());
+
+    let mut page_token = match stored_token {
+        Some(token) => token,
+        None => {
+            let token = fetch_start_page_token(&client, &access_token).await?;
+            std::fs::write(&token_path, &token)
+                .map_err(|e| format!("Failed to store page token: {}", e))?;
+            return Ok(Vec::new());
+        }
+    };
+
+    let mut detected = Vec::new();
+
+    loop {
+        let response = client
+            .get("https://www.googleapis.com/drive/v3/changes")
+            .query(&[
+                ("pageToken", page_token.as_str()),
+                ("includeRemoved", "true"),
+                ("pageSize", "1000"),
+                (
+                    "fields",
+                    "nextPageToken,newStartPageToken,changes(fileId,removed,file(id,name,trashed,modifiedTime))",
+                ),
+            ])
+            .bearer_auth(&access_token)
+            .send_with_retry()
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to list changes: {}", e)))?;
+
+        if matches!(
+            response.status(),
+            StatusCode::BAD_REQUEST | StatusCode::NOT_FOUND
+        ) {
+            log_warning!(
+                "Drive rejected the changes page token ({}); resyncing watched files",
+                response.status()
+            );
+            let token = fetch_start_page_token(&client, &access_token).await?;
+            std::fs::write(&token_path, &token)
+                .map_err(|e| format!("Failed to store page token: {}", e))?;
+            detected = resync_watched_files(&client, &access_token, &watched_file_ids).await?;
+            break;
+        }
+        if !response.status().is_success() {
+            return Err(http::google_error(response, "Drive API changes error").await);
+        }
+
+        let list: DriveChangeList = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse changes: {}", e))?;
+
+        for change in list.changes {
+            let file_id = match change.file_id {
+                Some(id) if watched_file_ids.contains(&id) => id,
+                _ => continue,
+            };
+
+            let kind = if change.removed.unwrap_or(false) {
+                "removed"
+            } else if change
+                .file
+                .as_ref()
+                .and_then(|f| f.trashed)
+                .unwrap_or(false)
+            {
+                "trashed"
+            } else {
+                "modified"
+            };
+
+            detected.push(WatchedFileChange {
+                file_id,
+                kind: kind.to_string(),
+                name: change.file.as_ref().and_then(|f| f.name.clone()),
+                modified_time: change.file.and_then(|f| f.modified_time),
+            });
+        }
+
+        if let Some(next) = list.next_page_token {
+            page_token = next;
+            continue;
+        }
+
+        if let Some(new_start) = list.new_start_page_token {
+            std::fs::write(&token_path, &new_start)
+                .map_err(|e| format!("Failed to store page token: {}", e))?;
+        }
+        break;
+    }
+
+    for change in &detected {
+        if let Err(e) = app.emit("drive-file-changed", change) {
+            log_warning!("Failed to emit drive-file-changed event: {}", e);
+        }
+    }
+
+    Ok(detected)
+}
+
+// Helper: Run a files.list query across all pages
+async fn list_app_files(
+    client: &Client,
+    access_token: &str,
+    query: &str,
+) -> Result<Vec<AppDriveFile>, AppError> {
+    let mut files = Vec::new();
+    let mut page_token: Option<String> = None;
+
+    loop {
+        let mut params = vec![
+            ("q", query.to_string()),
+            (
+                "fields",
+                "nextPageToken,files(id,name,mimeType,parents,trashedTime)".to_string(),
+            ),
+            ("pageSize", "1000".to_string()),
+        ];
+        if let Some(token) = &page_token {
+            params.push(("pageToken", token.clone()));
+        }
+
+        let response = client
+            .get("https://www.googleapis.com/drive/v3/files")
+            .query(&params)
+            .bearer_auth(access_token)
+            .send_with_retry()
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to list files: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(http::google_error(response, "Drive API error").await);
+        }
+
+        let list: AppDriveFileList = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse file list: {}", e))?;
+
+        files.extend(list.files);
+
+        match list.next_page_token {
+            Some(token) => page_token = Some(token),
+            None => return Ok(files),
+        }
+    }
+}
+
+// Helper: Check that a file lives somewhere under the po-tracker folder
+async fn ensure_within_app_folder(
+    client: &Client,
+    access_token: &str,
+    file_id: &str,
+) -> Result<(), AppError> {
+    let root_id = crate::find_folder(client, access_token, APP_ROOT_FOLDER)
+        .await?
+        .ok_or_else(|| AppError::Validation("The po-tracker folder does not exist".to_string()))?;
+
+    let mut current = vec![file_id.to_string()];
+    for _ in 0..APP_FOLDER_DEPTH {
+        let mut next = Vec::new();
+        for id in &current {
+            let response = client
+                .get(format!("https://www.googleapis.com/drive/v3/files/{}", id))
+                .query(&[("fields", "parents")])
+                .bearer_auth(access_token)
+                .send_with_retry()
+                .await
+                .map_err(|e| AppError::Network(format!("Failed to get file parents: {}", e)))?;
+
+            if !response.status().is_success() {
+                return Err(http::google_error(response, "Drive API error").await);
+            }
+
+            let file: DriveParents = response
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse file parents: {}", e))?;
+            next.extend(file.parents.unwrap_or_default());
+        }
+
+        if next.contains(&root_id) {
+            return Ok(());
+        }
+        current = next;
+    }
+
+    Err(AppError::Validation(
+        "File is not part of the po-tracker folder".to_string(),
+    ))
+}
+
+// Helper: Set the trashed flag on a file inside the po-tracker folder
+async fn set_trashed(access_token: &str, file_id: &str, trashed: bool) -> Result<(), AppError> {
+    let client = http::client();
+    ensure_within_app_folder(&client, access_token, file_id).await?;
+
+    let response = client
+        .patch(format!(
+            "https://www.googleapis.com/drive/v3/files/{}",
+            file_id
+        ))
+        .bearer_auth(access_token)
+        .json(&serde_json::json!({ "trashed": trashed }))
+        .send_with_retry()
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to update file: {}", e)))?;
+
+    if !response.status().is_success() {
+        return Err(http::google_error(response, "Drive API update error").await);
+    }
+
+    Ok(())
+}
+
+// Move a generated form, folder or file to the Drive trash
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(file_id = %file_id), err)]
+pub async fn trash_file(
+    cache: State<'_, GoogleCache>,
+    access_token: String,
+    file_id: String,
+) -> Result<String, AppError> {
+    set_trashed(&access_token, &file_id, true).await?;
+    cache.invalidate(CacheKind::DriveListing, None);
+    cache.invalidate(CacheKind::FolderId, None);
+    Ok("File moved to trash".to_string())
+}
+
+// Restore a previously trashed app file
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(file_id = %file_id), err)]
+pub async fn untrash_file(
+    cache: State<'_, GoogleCache>,
+    access_token: String,
+    file_id: String,
+) -> Result<String, AppError> {
+    set_trashed(&access_token, &file_id, false).await?;
+    cache.invalidate(CacheKind::DriveListing, None);
+    cache.invalidate(CacheKind::FolderId, None);
+    Ok("File restored from trash".to_string())
+}
+
+// List trashed files that belong to the po-tracker folder tree
+#[tauri::command]
+pub async fn list_trashed_app_files(access_token: String) -> Result<Vec<AppDriveFile>, AppError> {
+    let client = http::client();
+
+    let root_id = match crate::find_folder(&client, &access_token, APP_ROOT_FOLDER).await? {
+        Some(id) => id,
+        None => return Ok(Vec::new()),
+    };
+
+    // Collect every folder in the tree, trashed or not, so trashed children
+    // of live folders are found as well as trashed folders themselves
+    let mut folder_ids = vec![root_id];
+    let mut level = folder_ids.clone();
+    for _ in 1..APP_FOLDER_DEPTH {
+        let mut next = Vec::new();
+        for chunk in level.chunks(40) {
+            let query = DriveQuery::new().in_any_parent(chunk).folder().build();
+            next.extend(
+                list_app_files(&client, &access_token, &query)
+                    .await?
+                    .into_iter()
+                    .map(|f| f.id),
+            );
+        }
+        if next.is_empty() {
+            break;
+        }
+        folder_ids.extend(next.iter().cloned());
+        level = next;
+    }
+
+    let mut trashed = Vec::new();
+    for chunk in folder_ids.chunks(40) {
+        let query = DriveQuery::new().in_any_parent(chunk).trashed(true).build();
+        trashed.extend(list_app_files(&client, &access_token, &query).await?);
+    }
+
+    Ok(trashed)
+}
+
+// Helper: The stored Google access token, if it hasn't expired. The frontend
+// refreshes it while the app is open.
+pub async fn stored_access_token(pool: &SqlitePool) -> Result<String, AppError> {
+    let auth = sqlx::query_as::<_, (Option<String>, Option<String>)>(
+        "SELECT access_token, token_expiry FROM google_auth WHERE id = 1",
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load Google account: {}", e))?;
+
+    let Some((Some(access_token), token_expiry)) = auth else {
+        return Err(AppError::AuthExpired("Not signed in to Google".to_string()));
+    };
+    let expired = token_expiry
+        .and_then(|expiry| chrono::DateTime::parse_from_rfc3339(&expiry).ok())
+        .is_some_and(|expiry| expiry <= chrono::Utc::now());
+    if expired {
+        return Err(AppError::AuthExpired(
+            "Google sign-in has expired".to_string(),
+        ));
+    }
+    Ok(access_token)
+}
+
+// Helper: Fetch the account's storage quota
+pub async fn fetch_drive_quota(
+    client: &Client,
+    access_token: &str,
+) -> Result<DriveQuota, AppError> {
+    let response = client
+        .get("https://www.googleapis.com/drive/v3/about")
+        .query(&[("fields", "storageQuota")])
+        .bearer_auth(access_token)
+        .send_with_retry()
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to get storage quota: {}", e)))?;
+
+    if !response.status().is_success() {
+        return Err(http::google_error(response, "Drive API about error").await);
+    }
+
+    let about: DriveAbout = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse storage quota: {}", e))?;
+
+    let parse = |value: Option<String>| value.and_then(|v| v.parse::<u64>().ok());
+    let quota = about.storage_quota;
+    let limit = parse(quota.limit);
+    let usage = parse(quota.usage).unwrap_or(0);
+
+    Ok(DriveQuota {
+        limit,
+        usage,
+        usage_in_drive: parse(quota.usage_in_drive).unwrap_or(0),
+        usage_in_trash: parse(quota.usage_in_drive_trash).unwrap_or(0),
+        available: limit.map(|l| l.saturating_sub(usage)),
+    })
+}
+
+// Helper: Fail early with a readable message if an upload won't fit.
+// Quota lookups that fail are not treated as fatal; the upload reports its own error.
+pub async fn ensure_quota_available(
+    client: &Client,
+    access_token: &str,
+    required_bytes: u64,
+) -> Result<(), AppError> {
+    let quota = match fetch_drive_quota(client, access_token).await {
+        Ok(quota) => quota,
+        Err(e) => {
+            log_warning!("Could not check Drive quota: {}", e);
+            return Ok(());
+        }
+    };
+
+    match quota.available {
+        Some(available) if available < required_bytes => Err(AppError::Validation(format!(
+            "Not enough Google Drive storage: upload needs {} bytes but only {} bytes are free",
+            required_bytes, available
+        ))),
+        _ => Ok(()),
+    }
+}
+
+// Get Drive storage usage and limit
+#[tauri::command]
+pub async fn get_drive_quota(
+    access_token: String,
+    operation_id: Option<String>,
+) -> Result<DriveQuota, AppError> {
+    http::cancellable(operation_id, async move {
+        let client = http::client();
+        fetch_drive_quota(&client, &access_token).await
+    })
+    .await
+}
+
+impl UploadSource {
+    async fn len(&self) -> Result<u64, AppError> {
+        match self {
+            UploadSource::Bytes(data) => Ok(data.len() as u64),
+            UploadSource::File(path) => tokio::fs::metadata(path)
+                .await
+                .map(|m| m.len())
+                .map_err(|e| AppError::Internal(format!("Failed to read file size: {}", e))),
+        }
+    }
+
+    async fn read_chunk(&self, offset: u64, len: usize) -> Result<Vec<u8>, AppError> {
+        match self {
+            UploadSource::Bytes(data) => {
+                let start = (offset as usize).min(data.len());
+                let end = (start + len).min(data.len());
+                Ok(data[start..end].to_vec())
+            }
+            UploadSource::File(path) => {
+                let mut file = tokio::fs::File::open(path)
+                    .await
+                    .map_err(|e| format!("Failed to open file: {}", e))?;
+                file.seek(std::io::SeekFrom::Start(offset))
+                    .await
+                    .map_err(|e| format!("Failed to seek file: {}", e))?;
+                let mut buf = Vec::with_capacity(len);
+                file.take(len as u64)
+                    .read_to_end(&mut buf)
+                    .await
+                    .map_err(|e| format!("Failed to read file: {}", e))?;
+                Ok(buf)
+            }
+        }
+    }
+}
+
+// Helper: Open a resumable upload session and return its session URI
+async fn start_resumable_session(
+    client: &Client,
+    access_token: &str,
+    name: &str,
+    mime_type: &str,
+    parent_id: Option<&str>,
+    total_bytes: u64,
+) -> Result<String, AppError> {
+    let mut metadata = serde_json::json!({ "name": name });
+    if let Some(parent) = parent_id {
+        metadata["parents"] = serde_json::json!([parent]);
+    }
+
+    let response = client
+        .post("https://www.googleapis.com/upload/drive/v3/files?uploadType=resumable")
+        .bearer_auth(access_token)
+        .header("X-Upload-Content-Type", mime_type)
+        .header("X-Upload-Content-Length", total_bytes.to_string())
+        .json(&metadata)
+        .send_with_retry()
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to start upload session: {}", e)))?;
+
+    if !response.status().is_success() {
+        return Err(http::google_error(response, "Drive API upload error").await);
+    }
+
+    response
+        .headers()
+        .get(LOCATION)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string())
+        .ok_or_else(|| {
+            AppError::Validation("Drive did not return an upload session URI".to_string())
+        })
+}
+
+// Helper: Interpret a chunk or status response. A retryable error means the request may be retried.
+async fn read_upload_status(response: reqwest::Response) -> Result<UploadStatus, AppError> {
+    let status = response.status();
+
+    if status == StatusCode::OK || status == StatusCode::CREATED {
+        let file: UploadedFile = response
+            .json()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to parse uploaded file: {}", e)))?;
+        return Ok(UploadStatus::Complete(file.id));
+    }
+
+    if status == StatusCode::PERMANENT_REDIRECT {
+        // "Range: bytes=0-N" lists what Drive has persisted so far
+        let next = response
+            .headers()
+            .get(RANGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.rsplit('-').next())
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(|last| last + 1)
+            .unwrap_or(0);
+        return Ok(UploadStatus::Incomplete(next));
+    }
+
+    Err(http::google_error(response, &format!("Drive API upload error ({})", status)).await)
+}
+
+// Helper: Ask Drive how much of an interrupted upload it has received
+async fn query_upload_status(
+    client: &Client,
+    session_uri: &str,
+    total_bytes: u64,
+) -> Result<UploadStatus, AppError> {
+    let response = client
+        .put(session_uri)
+        .header(CONTENT_RANGE, format!("bytes */{}", total_bytes))
+        .header(CONTENT_LENGTH, 0)
+        .send_with_retry()
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to query upload status: {}", e)))?;
+
+    read_upload_status(response).await
+}
+
+// Upload to Drive through a resumable session in fixed-size chunks, retrying
+// interrupted chunks with backoff and emitting "upload-progress" events
+pub async fn resumable_upload(
+    app: &AppHandle,
+    client: &Client,
+    access_token: &str,
+    name: &str,
+    source: UploadSource,
+    mime_type: &str,
+    parent_id: Option<&str>,
+) -> Result<String, AppError> {
+    let total_bytes = source.len().await?;
+    let session_uri = start_resumable_session(
+        client,
+        access_token,
+        name,
+        mime_type,
+        parent_id,
+        total_bytes,
+    )
+    .await?;
+
+    let upload_id = Uuid::new_v4().to_string();
+    let emit_progress = |bytes_sent: u64| {
+        let progress = UploadProgress {
+            upload_id: upload_id.clone(),
+            file_name: name.to_string(),
+            bytes_sent,
+            total_bytes,
+        };
+        if let Err(e) = app.emit("upload-progress", progress) {
+            log_warning!("Failed to emit upload-progress event: {}", e);
+        }
+    };
+
+    let mut offset = 0u64;
+    let mut retries = 0u32;
+    emit_progress(0);
+
+    loop {
+        let chunk = source.read_chunk(offset, RESUMABLE_CHUNK_SIZE).await?;
+        let chunk_len = chunk.len() as u64;
+
+        let mut request = client.put(&session_uri).header(CONTENT_LENGTH, chunk_len);
+        if total_bytes > 0 {
+            request = request.header(
+                CONTENT_RANGE,
+                format!(
+                    "bytes {}-{}/{}",
+                    offset,
+                    offset + chunk_len - 1,
+                    total_bytes
+                ),
+            );
+        }
+
+        let result = match request.body(chunk).send_recorded().await {
+            Ok(response) => read_upload_status(response).await,
+            Err(e) => Err(AppError::Network(format!("Failed to upload chunk: {}", e))),
+        };
+
+        match result {
+            Ok(UploadStatus::Complete(file_id)) => {
+                emit_progress(total_bytes);
+                return Ok(file_id);
+            }
+            Ok(UploadStatus::Incomplete(next)) => {
+                offset = next;
+                retries = 0;
+                emit_progress(offset);
+            }
+            Err(e) if e.retryable() && retries < MAX_CHUNK_RETRIES => {
+                retries += 1;
+                log_warning!(
+                    "Upload chunk failed (attempt {}/{}): {}",
+                    retries,
+                    MAX_CHUNK_RETRIES,
+                    e
+                );
+                tokio::time::sleep(std::time::Duration::from_millis(500 * 2u64.pow(retries))).await;
+
+                match query_upload_status(client, &session_uri, total_bytes).await {
+                    Ok(UploadStatus::Complete(file_id)) => {
+                        emit_progress(total_bytes);
+                        return Ok(file_id);
+                    }
+                    Ok(UploadStatus::Incomplete(next)) => offset = next,
+                    // Keep the current offset and let the next attempt decide
+                    Err(_) => {}
+                }
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+// Upload a local file (attachment, exported backup) to Drive with progress events
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(parent_id = ?parent_id), err)]
+pub async fn upload_file_to_drive(
+    app: AppHandle,
+    access_token: String,
+    file_path: String,
+    parent_id: Option<String>,
+    mime_type: Option<String>,
+) -> Result<String, AppError> {
+    let path = PathBuf::from(&file_path);
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| AppError::Validation(format!("Invalid file path: {}", file_path)))?
+        .to_string();
+    validate_drive_name(&name)?;
+
+    let client = http::client();
+    let source = UploadSource::File(path);
+    ensure_quota_available(&client, &access_token, source.len().await?).await?;
+
+    resumable_upload(
+        &app,
+        &client,
+        &access_token,
+        &name,
+        source,
+        mime_type.as_deref().unwrap_or("application/octet-stream"),
+        parent_id.as_deref(),
+    )
+    .await
+}
+
+// Helper: Download bytes from an authenticated Drive URL
+async fn download_bytes(
+    client: &Client,
+    access_token: &str,
+    url: &str,
+) -> Result<(Vec<u8>, Option<String>), AppError> {
+    let response = client
+        .get(url)
+        .bearer_auth(access_token)
+        .send_with_retry()
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to download preview: {}", e)))?;
+
+    if !response.status().is_success() {
+        return Err(http::google_error(response, "Drive API preview error").await);
+    }
+
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to read preview: {}", e))?;
+
+    Ok((bytes.to_vec(), content_type))
+}
+
+// Fetch a thumbnail (or first-page preview) of a Drive file as a base64 data URL
+#[tauri::command]
+pub async fn get_drive_thumbnail(
+    access_token: String,
+    file_id: String,
+    size: Option<u32>,
+    operation_id: Option<String>,
+) -> Result<DriveThumbnail, AppError> {
+    http::cancellable(operation_id, async move {
+        use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+        let client = http::client();
+
+        let response = client
+            .get(format!(
+                "https://www.googleapis.com/drive/v3/files/{}",
+                file_id
+            ))
+            .query(&[("fields", "mimeType,thumbnailLink,size")])
+            .bearer_auth(&access_token)
+            .send_with_retry()
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to get file info: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(http::google_error(response, "Drive API error").await);
+        }
+
+        let info: DriveThumbnailInfo = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse file info: {}", e))?;
+
+        let (bytes, content_type) = if let Some(link) = info.thumbnail_link {
+            // Thumbnail links end in "=s220"; swap in the requested size
+            let size = size.unwrap_or(DEFAULT_THUMBNAIL_SIZE);
+            let link = match link.rfind("=s") {
+                Some(pos) => format!("{}=s{}", &link[..pos], size),
+                None => link,
+            };
+            download_bytes(&client, &access_token, &link).await?
+        } else if info.mime_type.starts_with("image/") {
+            let file_size = info
+                .size
+                .as_deref()
+                .and_then(|s| s.parse::<u64>().ok())
+                .unwrap_or(0);
+            if file_size > MAX_INLINE_IMAGE_BYTES {
+                return Err(AppError::Validation(
+                    "Image is too large to preview inline".to_string(),
+                ));
+            }
+            download_bytes(
+                &client,
+                &access_token,
+                &format!(
+                    "https://www.googleapis.com/drive/v3/files/{}?alt=media",
+                    file_id
+                ),
+            )
+            .await?
+        } else {
+            return Err(AppError::Validation(
+                "No preview is available for this file".to_string(),
+            ));
+        };
+
+        let mime_type = content_type
+            .filter(|t| t.starts_with("image/"))
+            .unwrap_or_else(|| "image/png".to_string());
+
+        Ok(DriveThumbnail {
+            file_id,
+            data_url: format!("data:{};base64,{}", mime_type, STANDARD.encode(&bytes)),
+            mime_type,
+        })
+    })
+    .await
+}
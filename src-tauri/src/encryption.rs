@@ -0,0 +1,463 @@
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{SqliteConnection, SqlitePool};
+use std::sync::RwLock;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::audit;
+use crate::crypto;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::models::Customer;
+use crate::session_lock;
+use crate::{log_info, log_warning};
+
+// Marks an encrypted field; base64 of the nonce and ciphertext follows
+const FIELD_PREFIX: &str = "enc:v1:";
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+
+// Columns kept encrypted while encryption is on: customer contact details and
+// the credentials of integrations only the backend reads. Credentials the
+// frontend reads itself (SMTP, Google) stay as they are.
+const ENCRYPTED_COLUMNS: &[(&str, &[&str])] = &[
+    ("customers", &["phone", "address", "notes"]),
+    ("telegram_settings", &["bot_token"]),
+    ("whatsapp_settings", &["access_token"]),
+    ("sms_settings", &["auth_token"]),
+    ("webhook_sources", &["signing_secret"]),
+    ("webhook_endpoints", &["secret"]),
+    ("auto_backup_settings", &["drive_passphrase"]),
+    ("sync_state", &["passphrase"]),
+    ("data_signing_key", &["secret_key"]),
+];
+
+const LOCKED_ERROR: &str = "Encrypted data is locked: unlock it with your passphrase first";
+
+enum KeyState {
+    Disabled,
+    Locked,
+    Unlocked([u8; KEY_LEN]),
+}
+
+// The data key, process-wide since encrypted columns are read in helpers that
+// only get a connection. Set from the database on startup.
+static KEY: RwLock<KeyState> = RwLock::new(KeyState::Disabled);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptionStatus {
+    pub enabled: bool,
+    // False while enabled until the passphrase is entered; encrypted fields
+    // can't be read or written until then
+    pub unlocked: bool,
+}
+
+// Helper: Replace the key state
+fn set_state(state: KeyState) -> Result<(), AppError> {
+    *KEY.write()
+        .map_err(|e| format!("Failed to update encryption key: {}", e))? = state;
+    Ok(())
+}
+
+// Helper: The data key, None while encryption is off
+fn current_key() -> Result<Option<[u8; KEY_LEN]>, AppError> {
+    match *KEY
+        .read()
+        .map_err(|e| format!("Failed to read encryption key: {}", e))?
+    {
+        KeyState::Disabled => Ok(None),
+        KeyState::Locked => Err(AppError::Locked(LOCKED_ERROR.to_string())),
+        KeyState::Unlocked(key) => Ok(Some(key)),
+    }
+}
+
+pub fn status() -> EncryptionStatus {
+    match KEY.read().as_deref() {
+        Ok(KeyState::Unlocked(_)) => EncryptionStatus {
+            enabled: true,
+            unlocked: true,
+        },
+        Ok(KeyState::Locked) | Err(_) => EncryptionStatus {
+            enabled: true,
+            unlocked: false,
+        },
+        Ok(KeyState::Disabled) => EncryptionStatus {
+            enabled: false,
+            unlocked: false,
+        },
+    }
+}
+
+fn is_encrypted(value: &str) -> bool {
+    value.starts_with(FIELD_PREFIX)
+}
+
+// Helper: Encrypt one field value with the data key
+fn encrypt_field(key: &[u8; KEY_LEN], plaintext: &str) -> Result<String, AppError> {
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    let cipher = Aes256Gcm::new_from_slice(key)
+        .map_err(|e| format!("Failed to initialise cipher: {}", e))?;
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_bytes())
+        .map_err(|_| "Failed to encrypt field".to_string())?;
+
+    let mut data = nonce.to_vec();
+    data.extend_from_slice(&ciphertext);
+    Ok(format!("{}{}", FIELD_PREFIX, STANDARD.encode(data)))
+}
+
+// Helper: Decrypt a value produced by encrypt_field
+fn decrypt_field(key: &[u8; KEY_LEN], value: &str) -> Result<String, AppError> {
+    let data = STANDARD
+        .decode(&value[FIELD_PREFIX.len()..])
+        .map_err(|e| format!("Failed to decode encrypted field: {}", e))?;
+    if data.len() < NONCE_LEN {
+        return Err(AppError::Validation(
+            "Encrypted field is truncated".to_string(),
+        ));
+    }
+    let cipher = Aes256Gcm::new_from_slice(key)
+        .map_err(|e| format!("Failed to initialise cipher: {}", e))?;
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(&data[..NONCE_LEN]), &data[NONCE_LEN..])
+        .map_err(|_| "Failed to decrypt field: wrong key or corrupted data".to_string())?;
+    String::from_utf8(plaintext)
+        .map_err(|e| AppError::Validation(format!("Encrypted field is not text: {}", e)))
+}
+
+// A value as it should be stored: encrypted while encryption is on, as it is
+// otherwise. Fails while locked rather than store plaintext.
+pub fn conceal(value: &str) -> Result<String, AppError> {
+    session_lock::ensure_unlocked()?;
+    match current_key()? {
+        Some(key) if !is_encrypted(value) => encrypt_field(&key, value),
+        _ => Ok(value.to_string()),
+    }
+}
+
+pub fn conceal_opt(value: Option<&str>) -> Result<Option<String>, AppError> {
+    value.map(conceal).transpose()
+}
+
+// A stored value as plaintext. Values written before encryption was turned
+// on come back as they are.
+pub fn reveal(value: String) -> Result<String, AppError> {
+    session_lock::ensure_unlocked()?;
+    if !is_encrypted(&value) {
+        return Ok(value);
+    }
+    match current_key()? {
+        Some(key) => decrypt_field(&key, &value),
+        None => Err(AppError::Validation(
+            "Found encrypted data but encryption is off".to_string(),
+        )),
+    }
+}
+
+pub fn reveal_opt(value: Option<String>) -> Result<Option<String>, AppError> {
+    value.map(reveal).transpose()
+}
+
+// A customer as loaded from the database, with contact details decrypted
+pub fn reveal_customer(customer: Customer) -> Result<Customer, AppError> {
+    Ok(Customer {
+        phone: reveal_opt(customer.phone)?,
+        address: reveal_opt(customer.address)?,
+        notes: reveal_opt(customer.notes)?,
+        ..customer
+    })
+}
+
+// Helper: The encrypted columns of a table
+fn columns_of(table: &str) -> &'static [&'static str] {
+    ENCRYPTED_COLUMNS
+        .iter()
+        .find(|(name, _)| *name == table)
+        .map_or(&[], |(_, columns)| columns)
+}
+
+// Helper: Apply `change` to the encrypted text fields of a row given as JSON
+fn map_json_fields(
+    table: &str,
+    row: &mut serde_json::Map<String, Value>,
+    change: impl Fn(String) -> Result<String, AppError>,
+) -> Result<(), AppError> {
+    for column in columns_of(table) {
+        if let Some(Value::String(value)) = row.get_mut(*column) {
+            *value = change(std::mem::take(value))?;
+        }
+    }
+    Ok(())
+}
+
+// Decrypt the encrypted fields of a row captured as JSON, e.g. a sync change
+// about to leave this device
+pub fn reveal_json(table: &str, row: &mut serde_json::Map<String, Value>) -> Result<(), AppError> {
+    map_json_fields(table, row, reveal)
+}
+
+// Encrypt the fields of a row given as JSON before it's written here
+pub fn conceal_json(table: &str, row: &mut serde_json::Map<String, Value>) -> Result<(), AppError> {
+    map_json_fields(table, row, |value| conceal(&value))
+}
+
+// Helper: The wrapped data key, None while encryption is off
+async fn load_wrapped_key(conn: &mut SqliteConnection) -> Result<Option<Vec<u8>>, AppError> {
+    sqlx::query_scalar::<_, Vec<u8>>("SELECT wrapped_key FROM encryption_settings WHERE id = 1")
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to load encryption settings: {}", e)))
+}
+
+// Helper: Unwrap the data key with the passphrase
+fn unwrap_key(passphrase: &str, wrapped: &[u8]) -> Result<[u8; KEY_LEN], AppError> {
+    crypto::decrypt_with_passphrase(passphrase, wrapped)
+        .map_err(|_| "Wrong passphrase".to_string())?
+        .try_into()
+        .map_err(|_| AppError::Internal("Stored encryption key is corrupted".to_string()))
+}
+
+// Helper: Rewrite every encrypted column's values with `change`. Values
+// already in the target form are left alone.
+async fn rewrite_columns(
+    conn: &mut SqliteConnection,
+    change: impl Fn(&str) -> Option<Result<String, AppError>>,
+) -> Result<u64, AppError> {
+    let mut rewritten = 0;
+    for (table, columns) in ENCRYPTED_COLUMNS {
+        for column in *columns {
+            let rows = sqlx::query_as::<_, (i64, String)>(&format!(
+                "SELECT rowid, {0} FROM {1} WHERE {0} IS NOT NULL",
+                column, table
+            ))
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to read {}.{}: {}", table, column, e))?;
+
+            for (rowid, value) in rows {
+                let Some(new_value) = change(&value).transpose()? else {
+                    continue;
+                };
+                sqlx::query(&format!(
+                    "UPDATE {} SET {} = ? WHERE rowid = ?",
+                    table, column
+                ))
+                .bind(new_value)
+                .bind(rowid)
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| format!("Failed to rewrite {}.{}: {}", table, column, e))?;
+                rewritten += 1;
+            }
+        }
+    }
+    Ok(rewritten)
+}
+
+// Helper: Record an encryption change without any key material
+async fn record_change(
+    conn: &mut SqliteConnection,
+    action: &str,
+    before: &EncryptionStatus,
+    after: &EncryptionStatus,
+) -> Result<(), AppError> {
+    audit::record(
+        conn,
+        "encryption_settings",
+        1,
+        action,
+        Some(before),
+        Some(after),
+    )
+    .await
+}
+
+// Helper: Tell the UI encryption was turned on, off, or unlocked
+fn emit_status(app: &AppHandle) -> EncryptionStatus {
+    let status = status();
+    if let Err(e) = app.emit("encryption-changed", &status) {
+        log_warning!("Failed to emit encryption-changed event: {}", e);
+    }
+    status
+}
+
+// Load whether encryption is on. Called on startup before any background job
+// runs, so nothing is written in plaintext while the key is locked.
+pub async fn init(pool: &SqlitePool) -> Result<(), AppError> {
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let state = match load_wrapped_key(&mut conn).await? {
+        Some(_) => KeyState::Locked,
+        None => KeyState::Disabled,
+    };
+    set_state(state)
+}
+
+#[tauri::command]
+pub fn get_encryption_status() -> Result<EncryptionStatus, AppError> {
+    Ok(status())
+}
+
+// Turn on encryption: a new data key, wrapped with the passphrase, encrypts
+// the sensitive columns, including everything already stored in plaintext.
+#[tauri::command]
+pub async fn enable_encryption(
+    app: AppHandle,
+    db: State<'_, Database>,
+    passphrase: String,
+) -> Result<EncryptionStatus, AppError> {
+    if status().enabled {
+        return Err(AppError::Conflict("Encryption is already on".to_string()));
+    }
+    let mut key = [0u8; KEY_LEN];
+    OsRng.fill_bytes(&mut key);
+    let wrapped = crypto::encrypt_with_passphrase(&passphrase, &key)?;
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    sqlx::query("INSERT INTO encryption_settings (id, wrapped_key) VALUES (1, ?)")
+        .bind(&wrapped)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to save encryption key: {}", e))?;
+    let encrypted = rewrite_columns(&mut tx, |value| {
+        (!is_encrypted(value)).then(|| encrypt_field(&key, value))
+    })
+    .await?;
+    let after = EncryptionStatus {
+        enabled: true,
+        unlocked: true,
+    };
+    record_change(&mut tx, "enable", &status(), &after).await?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to turn on encryption: {}", e))?;
+
+    set_state(KeyState::Unlocked(key))?;
+    log_info!("Encrypted {} stored values", encrypted);
+    Ok(emit_status(&app))
+}
+
+// Drop the data key from memory until the passphrase is entered again.
+// Nothing changes while encryption is off.
+pub fn forget_key(app: &AppHandle) -> Result<(), AppError> {
+    if status().unlocked {
+        set_state(KeyState::Locked)?;
+        emit_status(app);
+    }
+    Ok(())
+}
+
+// Unlock encrypted data with the passphrase. Also ends a session lock, since
+// the passphrase is what it asks for.
+pub async fn unlock(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    passphrase: &str,
+) -> Result<EncryptionStatus, AppError> {
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let wrapped = load_wrapped_key(&mut conn)
+        .await?
+        .ok_or_else(|| AppError::Validation("Encryption is off".to_string()))?;
+    let key = unwrap_key(passphrase, &wrapped)?;
+    set_state(KeyState::Unlocked(key))?;
+    session_lock::end_lock(app);
+    Ok(emit_status(app))
+}
+
+// Unlock encrypted data for this session
+#[tauri::command]
+pub async fn unlock_encryption(
+    app: AppHandle,
+    db: State<'_, Database>,
+    passphrase: String,
+) -> Result<EncryptionStatus, AppError> {
+    unlock(&app, &db.pool, &passphrase).await
+}
+
+// Re-wrap the data key with a new passphrase. The stored data is untouched.
+#[tauri::command]
+pub async fn change_encryption_passphrase(
+    db: State<'_, Database>,
+    current_passphrase: String,
+    new_passphrase: String,
+) -> Result<EncryptionStatus, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let wrapped = load_wrapped_key(&mut tx)
+        .await?
+        .ok_or_else(|| AppError::Validation("Encryption is off".to_string()))?;
+    let key = unwrap_key(&current_passphrase, &wrapped)?;
+    let rewrapped = crypto::encrypt_with_passphrase(&new_passphrase, &key)?;
+
+    sqlx::query(
+        "UPDATE encryption_settings SET wrapped_key = ?, updated_at = CURRENT_TIMESTAMP \
+         WHERE id = 1",
+    )
+    .bind(&rewrapped)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save encryption key: {}", e))?;
+    let current = status();
+    record_change(&mut tx, "change_passphrase", &current, &current).await?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to change passphrase: {}", e))?;
+    Ok(current)
+}
+
+// Turn off encryption, decrypting everything back to plaintext. Needs the
+// passphrase even while unlocked.
+#[tauri::command]
+pub async fn disable_encryption(
+    app: AppHandle,
+    db: State<'_, Database>,
+    passphrase: String,
+) -> Result<EncryptionStatus, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let wrapped = load_wrapped_key(&mut tx)
+        .await?
+        .ok_or_else(|| AppError::Validation("Encryption is off".to_string()))?;
+    let key = unwrap_key(&passphrase, &wrapped)?;
+
+    let decrypted = rewrite_columns(&mut tx, |value| {
+        is_encrypted(value).then(|| decrypt_field(&key, value))
+    })
+    .await?;
+    sqlx::query("DELETE FROM encryption_settings WHERE id = 1")
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to remove encryption key: {}", e))?;
+    let after = EncryptionStatus {
+        enabled: false,
+        unlocked: false,
+    };
+    record_change(&mut tx, "disable", &status(), &after).await?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to turn off encryption: {}", e))?;
+
+    set_state(KeyState::Disabled)?;
+    log_info!("Decrypted {} stored values", decrypted);
+    Ok(emit_status(&app))
+}
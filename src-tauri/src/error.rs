@@ -0,0 +1,192 @@
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use std::fmt;
+
+use crate::integrity::Reference;
+
+// Error returned by every command. Reaches the frontend as
+// { kind, message, retryable, status } so it can branch on the kind (an
+// expired sign-in, a network failure, bad input) rather than on the wording.
+// VersionConflict and InUse add the fields the UI needs to resolve them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppError {
+    // Bad input, or a setting the user has to fill in first
+    Validation(String),
+    NotFound(String),
+    // Already exists, or changed in the meantime
+    Conflict(String),
+    // Saved elsewhere after the caller loaded it (kind "conflict"). `current`
+    // is the record as it is now, so the UI can let the user redo or discard
+    // their change.
+    VersionConflict {
+        message: String,
+        expected_version: i64,
+        current: Value,
+    },
+    // Still used by open records; `references` lists them so the UI can link
+    // to them
+    InUse {
+        message: String,
+        entity: String,
+        id: i64,
+        references: Vec<Reference>,
+    },
+    // A sign-in or API credential expired or was revoked; signing in again fixes it
+    AuthExpired(String),
+    // Signed in, but without the permission needed for this file or resource
+    PermissionDenied(String),
+    // The sign-in lacks an OAuth scope the app needs; signing in again and
+    // granting it fixes it
+    ScopeMissing(String),
+    // Too many requests; worth retrying later
+    RateLimited {
+        message: String,
+        status: Option<u16>,
+    },
+    // The service couldn't be reached
+    Network(String),
+    // The service answered with an error
+    Provider {
+        message: String,
+        status: Option<u16>,
+    },
+    // Encrypted data or the session is locked until the passphrase or PIN is entered
+    Locked(String),
+    Cancelled(String),
+    // Anything else: database, files, unexpected data
+    Internal(String),
+}
+
+impl AppError {
+    // Error for an unsuccessful HTTP response from an outside service
+    pub fn from_status(status: u16, message: String) -> Self {
+        match status {
+            401 => AppError::AuthExpired(message),
+            403 => AppError::PermissionDenied(message),
+            404 => AppError::NotFound(message),
+            409 | 412 => AppError::Conflict(message),
+            429 => AppError::RateLimited {
+                message,
+                status: Some(status),
+            },
+            _ => AppError::Provider {
+                message,
+                status: Some(status),
+            },
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AppError::Validation(_) => "validation",
+            AppError::NotFound(_) => "not_found",
+            AppError::Conflict(_) | AppError::VersionConflict { .. } => "conflict",
+            AppError::InUse { .. } => "in_use",
+            AppError::AuthExpired(_) => "auth_expired",
+            AppError::PermissionDenied(_) => "permission_denied",
+            AppError::ScopeMissing(_) => "scope_missing",
+            AppError::RateLimited { .. } => "rate_limited",
+            AppError::Network(_) => "network",
+            AppError::Provider { .. } => "provider",
+            AppError::Locked(_) => "locked",
+            AppError::Cancelled(_) => "cancelled",
+            AppError::Internal(_) => "internal",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::Validation(message)
+            | AppError::NotFound(message)
+            | AppError::Conflict(message)
+            | AppError::VersionConflict { message, .. }
+            | AppError::InUse { message, .. }
+            | AppError::AuthExpired(message)
+            | AppError::PermissionDenied(message)
+            | AppError::ScopeMissing(message)
+            | AppError::RateLimited { message, .. }
+            | AppError::Network(message)
+            | AppError::Provider { message, .. }
+            | AppError::Locked(message)
+            | AppError::Cancelled(message)
+            | AppError::Internal(message) => message,
+        }
+    }
+
+    // HTTP status from the service, if the error came from one
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            AppError::RateLimited { status, .. } | AppError::Provider { status, .. } => *status,
+            _ => None,
+        }
+    }
+
+    // Whether trying the same thing again later may work
+    pub fn retryable(&self) -> bool {
+        match self {
+            AppError::RateLimited { .. } | AppError::Network(_) => true,
+            AppError::Provider { status, .. } => status.is_none_or(|s| s >= 500),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let extra_fields = match self {
+            AppError::VersionConflict { .. } => 2,
+            AppError::InUse { .. } => 3,
+            _ => 0,
+        };
+        let mut error = serializer.serialize_struct("AppError", 4 + extra_fields)?;
+        error.serialize_field("kind", self.kind())?;
+        error.serialize_field("message", self.message())?;
+        error.serialize_field("retryable", &self.retryable())?;
+        error.serialize_field("status", &self.status())?;
+        match self {
+            AppError::VersionConflict {
+                expected_version,
+                current,
+                ..
+            } => {
+                error.serialize_field("expected_version", expected_version)?;
+                error.serialize_field("current", current)?;
+            }
+            AppError::InUse {
+                entity,
+                id,
+                references,
+                ..
+            } => {
+                error.serialize_field("entity", entity)?;
+                error.serialize_field("id", id)?;
+                error.serialize_field("references", references)?;
+            }
+            _ => {}
+        }
+        error.end()
+    }
+}
+
+// Errors built from text (database, file and parsing failures wrapped with
+// format!) are internal unless they say otherwise
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Internal(message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::Internal(message.to_string())
+    }
+}
@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::archive::archive_order;
+use crate::audit;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::integrity::ensure_event_removable;
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Event {
+    pub id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub is_active: bool,
+    pub created_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventDeleted {
+    pub event_id: i64,
+    // Orders of the campaign, moved to the archive (restorable without the event)
+    pub archived_order_ids: Vec<i64>,
+}
+
+// Delete a campaign. Its finished and deleted orders are archived rather than
+// orphaned, and products, recurring templates and the current-event setting
+// are detached. A campaign with open orders can't be deleted.
+#[tauri::command]
+pub async fn delete_event(db: State<'_, Database>, id: i64) -> Result<EventDeleted, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let event = sqlx::query_as::<_, Event>(
+        "SELECT id, name, description, start_date, end_date, COALESCE(is_active, 1) AS is_active, \
+         created_at FROM events WHERE id = ?",
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to load event: {}", e))?
+    .ok_or_else(|| AppError::NotFound(format!("Event {} not found", id)))?;
+    ensure_event_removable(&mut tx, id).await?;
+
+    let order_ids =
+        sqlx::query_scalar::<_, i64>("SELECT id FROM preorders WHERE event_id = ? ORDER BY id")
+            .bind(id)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to load event orders: {}", e))?;
+
+    for order_id in &order_ids {
+        archive_order(&mut tx, *order_id).await?;
+    }
+
+    for sql in [
+        "UPDATE products SET event_id = NULL WHERE event_id = ?",
+        "UPDATE recurring_orders SET event_id = NULL WHERE event_id = ?",
+        "UPDATE app_settings SET current_event_id = NULL WHERE current_event_id = ?",
+    ] {
+        sqlx::query(sql)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to detach event: {}", e))?;
+    }
+
+    sqlx::query("DELETE FROM events WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to delete event: {}", e))?;
+
+    audit::record(&mut *tx, "event", id, "delete", Some(&event), None).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to delete event: {}", e))?;
+
+    Ok(EventDeleted {
+        event_id: id,
+        archived_order_ids: order_ids,
+    })
+}
@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteConnection;
+use tauri::State;
+
+use crate::audit;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::reports::validate_bound;
+
+const EXPENSE_COLUMNS: &str =
+    "x.id, x.event_id, e.name AS event_name, x.form_id, x.category, x.description, x.amount, \
+     x.incurred_at, x.created_at";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum ExpenseCategory {
+    // Payment gateway, marketplace and bank fees
+    Fee,
+    Shipping,
+    Marketing,
+    // Packaging and other consumables
+    Supplies,
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Expense {
+    pub id: i64,
+    pub event_id: Option<i64>,
+    pub event_name: Option<String>,
+    pub form_id: Option<String>,
+    pub category: ExpenseCategory,
+    pub description: String,
+    // In the default currency, like the profit reports
+    pub amount: f64,
+    pub incurred_at: String,
+    pub created_at: Option<String>,
+}
+
+// A cost to record against a campaign (event) or the Google Form its orders
+// came from; with neither it counts as general overhead
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpenseInput {
+    pub event_id: Option<i64>,
+    pub form_id: Option<String>,
+    pub category: ExpenseCategory,
+    pub description: String,
+    pub amount: f64,
+    // Date or UTC datetime; now if not given
+    pub incurred_at: Option<String>,
+}
+
+// Helper: Load one expense
+async fn load_expense(conn: &mut SqliteConnection, id: i64) -> Result<Expense, AppError> {
+    sqlx::query_as::<_, Expense>(&format!(
+        "SELECT {} FROM expenses x LEFT JOIN events e ON e.id = x.event_id WHERE x.id = ?",
+        EXPENSE_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| format!("Failed to load expense: {}", e))?
+    .ok_or_else(|| AppError::NotFound(format!("Expense {} not found", id)))
+}
+
+// Record a cost that feeds the campaign and form profit reports
+#[tauri::command]
+pub async fn record_expense(
+    db: State<'_, Database>,
+    expense: ExpenseInput,
+) -> Result<Expense, AppError> {
+    let form_id = expense
+        .form_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|id| !id.is_empty());
+    if expense.event_id.is_some() && form_id.is_some() {
+        return Err(AppError::Validation(
+            "Record an expense against a campaign or a form, not both".to_string(),
+        ));
+    }
+    if expense.description.trim().is_empty() {
+        return Err(AppError::Validation(
+            "Expense description must not be empty".to_string(),
+        ));
+    }
+    if !expense.amount.is_finite() || expense.amount <= 0.0 {
+        return Err(AppError::Validation(
+            "Expense amount must be greater than zero".to_string(),
+        ));
+    }
+    validate_bound("incurred_at", &expense.incurred_at)?;
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    if let Some(event_id) = expense.event_id {
+        sqlx::query_scalar::<_, i64>("SELECT id FROM events WHERE id = ?")
+            .bind(event_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to load campaign: {}", e))?
+            .ok_or_else(|| AppError::NotFound(format!("Campaign {} not found", event_id)))?;
+    }
+
+    let id = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO expenses (event_id, form_id, category, description, amount, incurred_at) \
+         VALUES (?, ?, ?, ?, ?, COALESCE(datetime(?), CURRENT_TIMESTAMP)) RETURNING id",
+    )
+    .bind(expense.event_id)
+    .bind(form_id)
+    .bind(expense.category)
+    .bind(expense.description.trim())
+    .bind(expense.amount)
+    .bind(expense.incurred_at.as_deref().map(str::trim))
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to record expense: {}", e))?;
+    let created = load_expense(&mut tx, id).await?;
+
+    audit::record(&mut *tx, "expense", id, "create", None, Some(&created)).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save expense: {}", e))?;
+
+    Ok(created)
+}
+
+// Expenses, newest first, optionally only those of one campaign or form
+#[tauri::command]
+pub async fn list_expenses(
+    db: State<'_, Database>,
+    event_id: Option<i64>,
+    form_id: Option<String>,
+) -> Result<Vec<Expense>, AppError> {
+    sqlx::query_as::<_, Expense>(&format!(
+        "SELECT {} FROM expenses x LEFT JOIN events e ON e.id = x.event_id \
+         WHERE (? IS NULL OR x.event_id = ?) AND (? IS NULL OR x.form_id = ?) \
+         ORDER BY x.incurred_at DESC, x.id DESC",
+        EXPENSE_COLUMNS
+    ))
+    .bind(event_id)
+    .bind(event_id)
+    .bind(&form_id)
+    .bind(&form_id)
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to list expenses: {}", e)))
+}
+
+#[tauri::command]
+pub async fn delete_expense(db: State<'_, Database>, id: i64) -> Result<(), AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_expense(&mut tx, id).await?;
+    sqlx::query("DELETE FROM expenses WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to delete expense: {}", e))?;
+
+    audit::record(&mut *tx, "expense", id, "delete", Some(&before), None).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to delete expense: {}", e)))
+}
@@ -0,0 +1,310 @@
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tauri::ipc::Channel;
+use tauri::{AppHandle, State};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::db::Database;
+use crate::error::AppError;
+use crate::http;
+use crate::tasks::{spawn_task, TaskHandle, TaskInfo};
+use crate::{FormResponse, FormResponsesData};
+
+// Largest page the Forms API hands out
+const PAGE_SIZE: u32 = 5000;
+
+// Page size when streaming, small so the first batch shows up quickly
+const STREAM_PAGE_SIZE: u32 = 500;
+
+// Forms fetched at the same time by sync_all_watched_forms. Calls beyond the
+// Forms quota are queued by the throttle either way.
+const MAX_CONCURRENT_FORMS: usize = 4;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResponseSyncResult {
+    pub form_id: String,
+    // Every response the form has
+    pub fetched: usize,
+    // The ones not imported yet, for the frontend to turn into orders
+    pub responses: Vec<FormResponse>,
+}
+
+// A form sync_all_watched_forms couldn't fetch
+#[derive(Debug, Serialize)]
+pub struct FormSyncFailure {
+    pub form_id: String,
+    pub error: AppError,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct WatchedFormsSyncResult {
+    // One per form fetched, in the order the forms were added
+    pub forms: Vec<ResponseSyncResult>,
+    pub failed: Vec<FormSyncFailure>,
+    // Totals across all forms
+    pub fetched: usize,
+    pub new_responses: usize,
+}
+
+// One page of responses sent by stream_form_responses
+#[derive(Debug, Serialize)]
+pub struct ResponseBatch {
+    // Starts at 0
+    pub index: usize,
+    pub responses: Vec<FormResponse>,
+    // Responses sent so far, this batch included
+    pub sent: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResponseStreamSummary {
+    pub form_id: String,
+    pub batches: usize,
+    pub total: usize,
+}
+
+// Helper: IDs of a form's responses that were already imported
+async fn synced_response_ids(
+    pool: &SqlitePool,
+    form_id: &str,
+) -> Result<HashSet<String>, AppError> {
+    let ids = sqlx::query_scalar::<_, String>(
+        "SELECT response_id FROM synced_responses WHERE form_id = ?",
+    )
+    .bind(form_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load synced responses: {}", e))?;
+    Ok(ids.into_iter().collect())
+}
+
+// Helper: One page of a form's responses
+async fn fetch_page(
+    client: &Client,
+    access_token: &str,
+    form_id: &str,
+    page_size: u32,
+    page_token: Option<&str>,
+) -> Result<FormResponsesData, AppError> {
+    let mut request = client
+        .get(format!(
+            "https://forms.googleapis.com/v1/forms/{}/responses",
+            form_id
+        ))
+        .query(&[("pageSize", page_size.to_string())])
+        .bearer_auth(access_token);
+    if let Some(token) = page_token {
+        request = request.query(&[("pageToken", token)]);
+    }
+    http::get_json_conditional(request, "Failed to get responses").await
+}
+
+// Helper: Page through all of a form's responses, keeping the new ones.
+// `on_page` is called with the result so far after each page.
+async fn fetch_new_responses(
+    pool: &SqlitePool,
+    access_token: &str,
+    form_id: String,
+    task: &TaskHandle,
+    on_page: impl Fn(&ResponseSyncResult),
+) -> Result<ResponseSyncResult, AppError> {
+    let synced = synced_response_ids(pool, &form_id).await?;
+    let client = http::client();
+    let mut result = ResponseSyncResult {
+        form_id,
+        fetched: 0,
+        responses: Vec::new(),
+    };
+    let mut page_token = None;
+    loop {
+        task.check_cancelled()?;
+        let page = fetch_page(
+            &client,
+            access_token,
+            &result.form_id,
+            PAGE_SIZE,
+            page_token.as_deref(),
+        )
+        .await?;
+        let responses = page.responses.unwrap_or_default();
+        result.fetched += responses.len();
+        result.responses.extend(
+            responses
+                .into_iter()
+                .filter(|response| !synced.contains(&response.response_id)),
+        );
+        on_page(&result);
+
+        page_token = page.next_page_token;
+        if page_token.is_none() {
+            break;
+        }
+    }
+    Ok(result)
+}
+
+// Helper: Sync one form, reporting pages as task progress
+async fn sync_responses(
+    pool: SqlitePool,
+    access_token: String,
+    form_id: String,
+    task: TaskHandle,
+) -> Result<ResponseSyncResult, AppError> {
+    fetch_new_responses(&pool, &access_token, form_id, &task, |result| {
+        task.progress(
+            result.fetched as u64,
+            None,
+            format!("{} new responses", result.responses.len()),
+        );
+    })
+    .await
+}
+
+// Helper: Sync every watched form, MAX_CONCURRENT_FORMS at a time, reporting
+// finished forms as task progress. A form that fails doesn't stop the rest.
+async fn sync_watched_forms(
+    pool: SqlitePool,
+    access_token: String,
+    task: TaskHandle,
+) -> Result<WatchedFormsSyncResult, AppError> {
+    let form_ids = sqlx::query_scalar::<_, String>("SELECT form_id FROM google_forms ORDER BY id")
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| format!("Failed to load forms: {}", e))?;
+    let total = form_ids.len();
+
+    let slots = Arc::new(Semaphore::new(MAX_CONCURRENT_FORMS));
+    let mut running = JoinSet::new();
+    for (position, form_id) in form_ids.into_iter().enumerate() {
+        let pool = pool.clone();
+        let access_token = access_token.clone();
+        let task = task.clone();
+        let slots = slots.clone();
+        running.spawn(async move {
+            let _slot = slots.acquire_owned().await;
+            let result =
+                fetch_new_responses(&pool, &access_token, form_id.clone(), &task, |_| {}).await;
+            (position, form_id, result)
+        });
+    }
+
+    let mut finished = Vec::with_capacity(total);
+    let mut synced = WatchedFormsSyncResult::default();
+    while let Some(joined) = running.join_next().await {
+        let (position, form_id, result) =
+            joined.map_err(|e| format!("Failed to sync form: {}", e))?;
+        match result {
+            Ok(form) => {
+                synced.fetched += form.fetched;
+                synced.new_responses += form.responses.len();
+                finished.push((position, form));
+            }
+            // Dropping the set stops the other forms too
+            Err(AppError::Cancelled(message)) => return Err(AppError::Cancelled(message)),
+            Err(error) => synced.failed.push(FormSyncFailure { form_id, error }),
+        }
+        task.progress(
+            (finished.len() + synced.failed.len()) as u64,
+            Some(total as u64),
+            format!("{} new responses", synced.new_responses),
+        );
+    }
+
+    finished.sort_by_key(|(position, _)| *position);
+    synced.forms = finished.into_iter().map(|(_, form)| form).collect();
+    Ok(synced)
+}
+
+// Fetch every response of a form, across all pages, as a background task
+// whose result is a ResponseSyncResult holding the ones not yet imported
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(form_id = %form_id), err)]
+pub async fn sync_form_responses(
+    app: AppHandle,
+    db: State<'_, Database>,
+    access_token: String,
+    form_id: String,
+) -> Result<TaskInfo, AppError> {
+    let title = sqlx::query_scalar::<_, String>("SELECT title FROM google_forms WHERE form_id = ?")
+        .bind(&form_id)
+        .fetch_optional(&db.pool)
+        .await
+        .map_err(|e| format!("Failed to load form: {}", e))?;
+    let pool = db.pool.clone();
+    let label = format!("Sync responses of {}", title.as_deref().unwrap_or(&form_id));
+    Ok(spawn_task(&app, "response_sync", label, move |task| {
+        sync_responses(pool, access_token, form_id, task)
+    }))
+}
+
+// Fetch the new responses of every form in google_forms concurrently, as one
+// background task whose result is a WatchedFormsSyncResult
+#[tauri::command]
+#[tracing::instrument(skip_all, err)]
+pub async fn sync_all_watched_forms(
+    app: AppHandle,
+    db: State<'_, Database>,
+    access_token: String,
+) -> Result<TaskInfo, AppError> {
+    let pool = db.pool.clone();
+    Ok(spawn_task(
+        &app,
+        "response_sync",
+        "Sync responses of all forms".to_string(),
+        move |task| sync_watched_forms(pool, access_token, task),
+    ))
+}
+
+// Send a form's responses to `on_batch` page by page as they arrive, instead
+// of one payload holding them all, so the UI can show the first ones early
+// and neither side keeps the full set in memory. Returns once the last batch
+// was sent.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(form_id = %form_id), err)]
+pub async fn stream_form_responses(
+    access_token: String,
+    form_id: String,
+    on_batch: Channel<ResponseBatch>,
+    operation_id: Option<String>,
+) -> Result<ResponseStreamSummary, AppError> {
+    http::cancellable(operation_id, async move {
+        let client = http::client();
+        let mut summary = ResponseStreamSummary {
+            form_id,
+            batches: 0,
+            total: 0,
+        };
+        let mut page_token = None;
+        loop {
+            let page = fetch_page(
+                &client,
+                &access_token,
+                &summary.form_id,
+                STREAM_PAGE_SIZE,
+                page_token.as_deref(),
+            )
+            .await?;
+            let responses = page.responses.unwrap_or_default();
+            summary.total += responses.len();
+            on_batch
+                .send(ResponseBatch {
+                    index: summary.batches,
+                    responses,
+                    sent: summary.total,
+                })
+                .map_err(|e| format!("Failed to send responses: {}", e))?;
+            summary.batches += 1;
+
+            page_token = page.next_page_token;
+            if page_token.is_none() {
+                break;
+            }
+        }
+        Ok(summary)
+    })
+    .await
+}
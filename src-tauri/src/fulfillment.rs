@@ -0,0 +1,557 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, QueryBuilder, Sqlite, SqliteConnection, SqlitePool};
+use std::collections::BTreeMap;
+use tauri::{AppHandle, State};
+
+use crate::audit;
+use crate::confirmation_codes::{checked_code, find_order_by_code, record_failure};
+use crate::db::Database;
+use crate::error::AppError;
+use crate::order_status::{apply_transition, emit_transition, OrderEvent, Transition};
+use crate::orders::fetch_order;
+
+const FULFILLMENT_COLUMNS: &str = "preorder_id, method, courier, tracking_number, notes, \
+     packed_at, shipped_at, delivered_at, updated_at";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum FulfillmentMethod {
+    Shipping,
+    Pickup,
+}
+
+// Steps an order goes through once it's ready to leave
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FulfillmentStage {
+    Packed,
+    // Handed to the courier, or collected for pickups
+    Shipped,
+    Delivered,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Fulfillment {
+    pub preorder_id: i64,
+    pub method: FulfillmentMethod,
+    pub courier: Option<String>,
+    pub tracking_number: Option<String>,
+    pub notes: Option<String>,
+    pub packed_at: Option<String>,
+    pub shipped_at: Option<String>,
+    pub delivered_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FulfillmentInput {
+    pub method: FulfillmentMethod,
+    pub courier: Option<String>,
+    pub tracking_number: Option<String>,
+    pub notes: Option<String>,
+}
+
+// One order's share of a product on the packing list
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PackingListLine {
+    pub order_id: i64,
+    pub customer_name: String,
+    pub confirmation_code: String,
+    pub quantity: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackingListProduct {
+    pub product_id: i64,
+    pub product_name: String,
+    pub total_quantity: i64,
+    pub orders: Vec<PackingListLine>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedOrder {
+    pub order_id: i64,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchFulfillResult {
+    pub fulfilled: Vec<i64>,
+    // Orders in the campaign that couldn't be fulfilled (e.g. not paid yet)
+    pub skipped: Vec<SkippedOrder>,
+}
+
+// What a pickup scan found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PickupOutcome {
+    // Marked collected just now; hand the items over
+    Collected,
+    // Collected earlier (see collected_at); don't hand anything over again
+    AlreadyCollected,
+    // Not paid yet; balance_due says how much to take first
+    NotPaid,
+    Cancelled,
+    // Has a courier or tracking number, so it isn't waiting at the counter
+    ShippingOrder,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PickupItem {
+    pub product_name: String,
+    pub quantity: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PickupVerification {
+    pub outcome: PickupOutcome,
+    pub order_id: i64,
+    pub customer_name: String,
+    pub confirmation_code: String,
+    pub status: String,
+    pub balance_due: f64,
+    pub collected_at: Option<String>,
+    pub items: Vec<PickupItem>,
+}
+
+impl FulfillmentStage {
+    fn column(&self) -> &'static str {
+        match self {
+            FulfillmentStage::Packed => "packed_at",
+            FulfillmentStage::Shipped => "shipped_at",
+            FulfillmentStage::Delivered => "delivered_at",
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            FulfillmentStage::Packed => "packed",
+            FulfillmentStage::Shipped => "shipped",
+            FulfillmentStage::Delivered => "delivered",
+        }
+    }
+}
+
+// Helper: Fulfillment record for an order, if one was started
+pub async fn load_fulfillment<'e, E>(
+    executor: E,
+    po_id: i64,
+) -> Result<Option<Fulfillment>, AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    sqlx::query_as::<_, Fulfillment>(&format!(
+        "SELECT {} FROM fulfillments WHERE preorder_id = ?",
+        FULFILLMENT_COLUMNS
+    ))
+    .bind(po_id)
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to load fulfillment: {}", e)))
+}
+
+// Helper: Make sure a live order has a fulfillment row
+async fn ensure_fulfillment<'e, E>(executor: E, po_id: i64) -> Result<(), AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    sqlx::query(
+        "INSERT OR IGNORE INTO fulfillments (preorder_id) \
+         SELECT id FROM preorders WHERE id = ? AND deleted_at IS NULL",
+    )
+    .bind(po_id)
+    .execute(executor)
+    .await
+    .map_err(|e| format!("Failed to start fulfillment: {}", e))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_fulfillment(
+    db: State<'_, Database>,
+    po_id: i64,
+) -> Result<Option<Fulfillment>, AppError> {
+    load_fulfillment(&db.pool, po_id).await
+}
+
+// Set how an order is delivered and its courier details
+#[tauri::command]
+pub async fn update_fulfillment(
+    db: State<'_, Database>,
+    po_id: i64,
+    fulfillment: FulfillmentInput,
+) -> Result<Fulfillment, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_fulfillment(&mut *tx, po_id).await?;
+    ensure_fulfillment(&mut *tx, po_id).await?;
+
+    let trimmed = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    };
+
+    sqlx::query(
+        "UPDATE fulfillments SET method = ?, courier = ?, tracking_number = ?, notes = ?, \
+         updated_at = CURRENT_TIMESTAMP WHERE preorder_id = ?",
+    )
+    .bind(fulfillment.method)
+    .bind(trimmed(&fulfillment.courier))
+    .bind(trimmed(&fulfillment.tracking_number))
+    .bind(&fulfillment.notes)
+    .bind(po_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to update fulfillment: {}", e))?;
+
+    let after = load_fulfillment(&mut *tx, po_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Order {} not found", po_id)))?;
+    audit::record(
+        &mut *tx,
+        "fulfillment",
+        po_id,
+        "update",
+        before.as_ref(),
+        Some(&after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save fulfillment: {}", e))?;
+
+    Ok(after)
+}
+
+// Record when an order was packed, shipped/picked up or delivered. `at`
+// defaults to now; `clear` removes a stage recorded by mistake.
+#[tauri::command]
+pub async fn set_fulfillment_stage(
+    db: State<'_, Database>,
+    po_id: i64,
+    stage: FulfillmentStage,
+    at: Option<String>,
+    clear: Option<bool>,
+) -> Result<Fulfillment, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_fulfillment(&mut *tx, po_id).await?;
+    ensure_fulfillment(&mut *tx, po_id).await?;
+
+    let value = if clear.unwrap_or(false) {
+        None
+    } else {
+        let normalized = sqlx::query_scalar::<_, Option<String>>(
+            "SELECT datetime(COALESCE(?, CURRENT_TIMESTAMP))",
+        )
+        .bind(at.as_deref().map(str::trim))
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to parse date: {}", e))?;
+        Some(normalized.ok_or_else(|| {
+            AppError::Validation(format!("Invalid date: {}", at.unwrap_or_default()))
+        })?)
+    };
+
+    sqlx::query(&format!(
+        "UPDATE fulfillments SET {} = ?, updated_at = CURRENT_TIMESTAMP WHERE preorder_id = ?",
+        stage.column()
+    ))
+    .bind(&value)
+    .bind(po_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to update fulfillment: {}", e))?;
+
+    let after = load_fulfillment(&mut *tx, po_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Order {} not found", po_id)))?;
+    audit::record(
+        &mut *tx,
+        "fulfillment",
+        po_id,
+        stage.as_str(),
+        before.as_ref(),
+        Some(&after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save fulfillment: {}", e))?;
+
+    Ok(after)
+}
+
+// Mark every paid order in a campaign (event) as fulfilled in one go. Orders
+// that aren't paid yet are reported back rather than failing the batch.
+#[tauri::command]
+pub async fn fulfill_event_orders(
+    app: AppHandle,
+    db: State<'_, Database>,
+    event_id: i64,
+) -> Result<BatchFulfillResult, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let orders = sqlx::query_as::<_, (i64, String)>(
+        "SELECT id, COALESCE(status, 'pending') FROM preorders \
+         WHERE event_id = ? AND deleted_at IS NULL \
+         AND COALESCE(status, 'pending') NOT IN ('fulfilled', 'cancelled') ORDER BY id",
+    )
+    .bind(event_id)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to load campaign orders: {}", e))?;
+
+    let mut result = BatchFulfillResult {
+        fulfilled: Vec::new(),
+        skipped: Vec::new(),
+    };
+    let mut transitions = Vec::new();
+
+    for (order_id, status) in orders {
+        if status != "paid" {
+            result.skipped.push(SkippedOrder {
+                order_id,
+                reason: format!("Order is {}, not paid", status),
+            });
+            continue;
+        }
+
+        transitions.push(apply_transition(&mut tx, order_id, OrderEvent::Fulfill).await?);
+
+        ensure_fulfillment(&mut *tx, order_id).await?;
+        sqlx::query(
+            "UPDATE fulfillments SET shipped_at = COALESCE(shipped_at, CURRENT_TIMESTAMP), \
+             updated_at = CURRENT_TIMESTAMP WHERE preorder_id = ?",
+        )
+        .bind(order_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to update fulfillment: {}", e))?;
+
+        result.fulfilled.push(order_id);
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save fulfilled orders: {}", e))?;
+
+    for transition in &transitions {
+        emit_transition(&app, transition);
+    }
+    Ok(result)
+}
+
+// Check a scanned confirmation code at the pickup counter. A paid order is
+// marked fulfilled and collected in one transaction, so scanning the same code
+// twice (or on two devices) hands the items over only once. Anything else
+// comes back with the reason it can't be collected.
+#[tauri::command]
+pub async fn verify_pickup(
+    app: AppHandle,
+    db: State<'_, Database>,
+    scanned_code: String,
+) -> Result<PickupVerification, AppError> {
+    let code = checked_code(&scanned_code)?;
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let Some((verification, transition)) = collect_pickup(&mut tx, &code, None).await? else {
+        record_failure();
+        return Err(AppError::Validation(format!(
+            "No order found for code {}",
+            code
+        )));
+    };
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save pickup: {}", e))?;
+
+    if let Some(transition) = &transition {
+        emit_transition(&app, transition);
+    }
+    Ok(verification)
+}
+
+// Helper: Pickup scan inside the caller's transaction (see verify_pickup), or
+// None when no order has the code. `scanned_at` backdates the collection for
+// scans replayed from the offline queue.
+pub async fn collect_pickup(
+    conn: &mut SqliteConnection,
+    code: &str,
+    scanned_at: Option<&str>,
+) -> Result<Option<(PickupVerification, Option<Transition>)>, AppError> {
+    let Some(po_id) = find_order_by_code(&mut *conn, code, false).await? else {
+        return Ok(None);
+    };
+
+    let order = fetch_order(conn, po_id).await?;
+    let before = load_fulfillment(&mut *conn, po_id).await?;
+    let shipping = before.as_ref().is_some_and(|f| {
+        f.method == FulfillmentMethod::Shipping
+            && (f.courier.is_some() || f.tracking_number.is_some())
+    });
+
+    let outcome = match order.status.as_str() {
+        "fulfilled" => PickupOutcome::AlreadyCollected,
+        "cancelled" => PickupOutcome::Cancelled,
+        "paid" if shipping => PickupOutcome::ShippingOrder,
+        "paid" => PickupOutcome::Collected,
+        _ => PickupOutcome::NotPaid,
+    };
+
+    let mut transition = None;
+    if outcome == PickupOutcome::Collected {
+        transition = Some(apply_transition(conn, po_id, OrderEvent::Fulfill).await?);
+
+        ensure_fulfillment(&mut *conn, po_id).await?;
+        sqlx::query(
+            "UPDATE fulfillments SET method = 'pickup', \
+             shipped_at = COALESCE(shipped_at, ?, CURRENT_TIMESTAMP), \
+             updated_at = CURRENT_TIMESTAMP WHERE preorder_id = ?",
+        )
+        .bind(scanned_at)
+        .bind(po_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to update fulfillment: {}", e))?;
+
+        let after = load_fulfillment(&mut *conn, po_id).await?;
+        audit::record(
+            &mut *conn,
+            "fulfillment",
+            po_id,
+            "pickup",
+            before.as_ref(),
+            after.as_ref(),
+        )
+        .await?;
+    }
+
+    let collected_at = match outcome {
+        PickupOutcome::Collected | PickupOutcome::AlreadyCollected => {
+            load_fulfillment(&mut *conn, po_id)
+                .await?
+                .and_then(|f| f.shipped_at)
+                .or(order.fulfilled_at.clone())
+        }
+        _ => None,
+    };
+
+    let items = sqlx::query_as::<_, PickupItem>(
+        "SELECT COALESCE(p.name, 'Product #' || oi.product_id) AS product_name, \
+         SUM(oi.quantity) AS quantity FROM order_items oi \
+         LEFT JOIN products p ON p.id = oi.product_id \
+         WHERE oi.preorder_id = ? GROUP BY oi.product_id ORDER BY MIN(oi.id)",
+    )
+    .bind(po_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load order items: {}", e))?;
+
+    let verification = PickupVerification {
+        outcome,
+        order_id: po_id,
+        customer_name: order.customer_name,
+        confirmation_code: order.confirmation_code,
+        status: transition
+            .as_ref()
+            .map(|t| t.change.to.as_str().to_string())
+            .unwrap_or(order.status),
+        balance_due: (order.total_amount - order.amount_credited - order.amount_paid).max(0.0),
+        collected_at,
+        items,
+    };
+    Ok(Some((verification, transition)))
+}
+
+// Helper: What to pack, grouped by product (see get_packing_list)
+pub async fn load_packing_list(
+    pool: &SqlitePool,
+    event_id: Option<i64>,
+    include_shipped: bool,
+) -> Result<Vec<PackingListProduct>, AppError> {
+    let mut query = QueryBuilder::<Sqlite>::new(
+        "SELECT oi.product_id, p.name, po.id, po.customer_name, po.confirmation_code, \
+         SUM(oi.quantity) FROM order_items oi \
+         JOIN preorders po ON po.id = oi.preorder_id \
+         JOIN products p ON p.id = oi.product_id \
+         LEFT JOIN fulfillments f ON f.preorder_id = po.id \
+         WHERE po.deleted_at IS NULL AND po.status IN ('confirmed', 'deposit_paid', 'invoiced', 'paid')",
+    );
+    if !include_shipped {
+        query.push(" AND f.shipped_at IS NULL");
+    }
+    if let Some(event_id) = event_id {
+        query.push(" AND po.event_id = ").push_bind(event_id);
+    }
+    query.push(
+        " GROUP BY oi.product_id, po.id \
+         ORDER BY p.name COLLATE NOCASE, oi.product_id, po.customer_name COLLATE NOCASE, po.id",
+    );
+
+    let rows = query
+        .build_query_as::<(i64, String, i64, String, String, i64)>()
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to build packing list: {}", e))?;
+
+    // Keyed by position so the product order from the query is kept
+    let mut index: BTreeMap<i64, usize> = BTreeMap::new();
+    let mut products: Vec<PackingListProduct> = Vec::new();
+    for (product_id, product_name, order_id, customer_name, confirmation_code, quantity) in rows {
+        let position = *index.entry(product_id).or_insert_with(|| {
+            products.push(PackingListProduct {
+                product_id,
+                product_name,
+                total_quantity: 0,
+                orders: Vec::new(),
+            });
+            products.len() - 1
+        });
+        let product = &mut products[position];
+        product.total_quantity += quantity;
+        product.orders.push(PackingListLine {
+            order_id,
+            customer_name,
+            confirmation_code,
+            quantity,
+        });
+    }
+
+    Ok(products)
+}
+
+// What to pack, grouped by product: confirmed, invoiced and paid orders that
+// haven't shipped yet, optionally for one campaign (event)
+#[tauri::command]
+pub async fn get_packing_list(
+    db: State<'_, Database>,
+    event_id: Option<i64>,
+    include_shipped: Option<bool>,
+) -> Result<Vec<PackingListProduct>, AppError> {
+    load_packing_list(&db.pool, event_id, include_shipped.unwrap_or(false)).await
+}
@@ -0,0 +1,120 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::State;
+
+use crate::error::AppError;
+use crate::log_warning;
+
+// What's cached and for how long. Form schemas and folder IDs rarely change;
+// listings are kept briefly so switching views doesn't list Drive again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheKind {
+    FormDetails,
+    FolderId,
+    DriveListing,
+}
+
+impl CacheKind {
+    fn ttl(self) -> Duration {
+        match self {
+            CacheKind::FormDetails => Duration::from_secs(10 * 60),
+            CacheKind::FolderId => Duration::from_secs(60 * 60),
+            CacheKind::DriveListing => Duration::from_secs(2 * 60),
+        }
+    }
+}
+
+struct CacheEntry {
+    value: serde_json::Value,
+    stored_at: Instant,
+}
+
+// Google metadata fetched recently, as Tauri managed state. Entries are kept
+// per Google account, told apart by a hash of the access token, so they lapse
+// when the token is refreshed at the latest.
+#[derive(Default)]
+pub struct GoogleCache(Mutex<HashMap<(CacheKind, String), CacheEntry>>);
+
+// Helper: Key of `key` for the account behind `access_token`
+fn cache_key(access_token: &str, key: &str) -> String {
+    let digest = Sha256::digest(access_token.as_bytes());
+    let account: String = digest[..8].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}:{}", account, key)
+}
+
+impl GoogleCache {
+    // A cached value, unless missing or older than its kind's TTL
+    pub fn get<T: DeserializeOwned>(
+        &self,
+        kind: CacheKind,
+        access_token: &str,
+        key: &str,
+    ) -> Option<T> {
+        let mut entries = self.0.lock().ok()?;
+        let entry_key = (kind, cache_key(access_token, key));
+        if entries.get(&entry_key)?.stored_at.elapsed() > kind.ttl() {
+            entries.remove(&entry_key);
+            return None;
+        }
+        serde_json::from_value(entries.get(&entry_key)?.value.clone()).ok()
+    }
+
+    pub fn put<T: Serialize>(&self, kind: CacheKind, access_token: &str, key: &str, value: &T) {
+        let value = match serde_json::to_value(value) {
+            Ok(value) => value,
+            Err(e) => {
+                log_warning!("Failed to cache Google metadata: {}", e);
+                return;
+            }
+        };
+        if let Ok(mut entries) = self.0.lock() {
+            entries.retain(|(kind, _), entry| entry.stored_at.elapsed() <= kind.ttl());
+            entries.insert(
+                (kind, cache_key(access_token, key)),
+                CacheEntry {
+                    value,
+                    stored_at: Instant::now(),
+                },
+            );
+        }
+    }
+
+    // Drop `key` of `kind` for every account, or everything of `kind` without
+    // a key. Called after a change the cached value wouldn't show.
+    pub fn invalidate(&self, kind: CacheKind, key: Option<&str>) {
+        if let Ok(mut entries) = self.0.lock() {
+            entries.retain(|(entry_kind, entry_key), _| {
+                if *entry_kind != kind {
+                    return true;
+                }
+                match key {
+                    Some(key) => entry_key.split_once(':').map(|(_, k)| k) != Some(key),
+                    None => false,
+                }
+            });
+        }
+    }
+}
+
+// Forget cached Google metadata of one kind, or all of it, so the next call
+// fetches it again
+#[tauri::command]
+pub fn clear_google_cache(
+    cache: State<'_, GoogleCache>,
+    kind: Option<CacheKind>,
+) -> Result<(), AppError> {
+    match kind {
+        Some(kind) => cache.invalidate(kind, None),
+        None => cache
+            .0
+            .lock()
+            .map_err(|e| format!("Failed to clear Google cache: {}", e))?
+            .clear(),
+    }
+    Ok(())
+}
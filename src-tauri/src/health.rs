@@ -0,0 +1,508 @@
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::SmtpTransport;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::db::{database_path, Database};
+use crate::error::AppError;
+use crate::log_warning;
+use crate::migrations;
+use crate::redact::redact;
+
+// Free space below these on the data volume is a warning, then an error
+const LOW_DISK_BYTES: u64 = 1024 * 1024 * 1024;
+const CRITICAL_DISK_BYTES: u64 = 100 * 1024 * 1024;
+
+// A write-ahead log bigger than this is checkpointed back into the database
+const MAX_WAL_BYTES: u64 = 64 * 1024 * 1024;
+
+// Queued messages still pending after this long suggest a stuck sender
+const STUCK_PENDING_MINUTES: i64 = 60;
+
+// Desktop notifications older than this are no longer worth showing
+const STALE_NOTIFICATION_HOURS: i64 = 24;
+
+// Temp and partial files younger than this may belong to a running job
+const LEFTOVER_MIN_AGE: Duration = Duration::from_secs(60 * 60);
+
+const SMTP_TIMEOUT_SECS: u64 = 10;
+
+// Outboxes whose failed and long-pending rows are counted
+const QUEUES: &[&str] = &[
+    "webhook_deliveries",
+    "telegram_messages",
+    "whatsapp_messages",
+    "sms_messages",
+    "chat_alerts",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Ok,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthCheck {
+    // Stable key the UI can attach help or actions to, e.g. "disk_space"
+    pub id: String,
+    pub status: HealthStatus,
+    pub message: String,
+    // What auto-repair fixed, if anything
+    pub repaired: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub checked_at: String,
+    // Worst status of any check
+    pub status: HealthStatus,
+    pub checks: Vec<HealthCheck>,
+}
+
+// Last report, from startup or the last run_health_check, as Tauri managed state
+#[derive(Default)]
+pub struct LastHealthReport(Mutex<Option<HealthReport>>);
+
+impl HealthCheck {
+    fn new(id: &str, status: HealthStatus, message: impl Into<String>) -> Self {
+        HealthCheck {
+            id: id.to_string(),
+            status,
+            message: message.into(),
+            repaired: None,
+        }
+    }
+
+    fn repaired(mut self, what: impl Into<String>) -> Self {
+        self.repaired = Some(what.into());
+        self
+    }
+}
+
+// Helper: A check that couldn't run at all
+fn failed(id: &str, error: AppError) -> HealthCheck {
+    HealthCheck::new(id, HealthStatus::Error, error.to_string())
+}
+
+// Helper: PRAGMA quick_check problems, empty when the database is intact
+async fn quick_check(pool: &SqlitePool) -> Result<Vec<String>, AppError> {
+    let problems = sqlx::query_scalar::<_, String>("PRAGMA quick_check")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to check database integrity: {}", e))?;
+    Ok(problems.into_iter().filter(|p| p != "ok").collect())
+}
+
+// Helper: Structural integrity and broken references. Damaged indexes are
+// rebuilt; anything else needs a backup restored.
+async fn check_integrity(pool: &SqlitePool, repair: bool) -> Result<HealthCheck, AppError> {
+    let id = "database_integrity";
+    let mut problems = quick_check(pool).await?;
+    let mut repaired = None;
+    if !problems.is_empty() && repair {
+        sqlx::query("REINDEX")
+            .execute(pool)
+            .await
+            .map_err(|e| format!("Failed to rebuild indexes: {}", e))?;
+        problems = quick_check(pool).await?;
+        repaired = problems.is_empty().then_some("Rebuilt damaged indexes");
+    }
+    if !problems.is_empty() {
+        return Ok(HealthCheck::new(
+            id,
+            HealthStatus::Error,
+            format!(
+                "Database is damaged ({}); restore a backup",
+                problems.join("; ")
+            ),
+        ));
+    }
+
+    let orphans = sqlx::query("PRAGMA foreign_key_check")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to check references: {}", e))?
+        .len();
+    let check = if orphans > 0 {
+        HealthCheck::new(
+            id,
+            HealthStatus::Warning,
+            format!("{} rows refer to records that no longer exist", orphans),
+        )
+    } else {
+        HealthCheck::new(id, HealthStatus::Ok, "Database is intact")
+    };
+    Ok(match repaired {
+        Some(what) => check.repaired(what),
+        None => check,
+    })
+}
+
+// Helper: Every migration this build knows has been applied
+async fn check_schema(pool: &SqlitePool) -> Result<HealthCheck, AppError> {
+    let id = "schema_version";
+    let current =
+        sqlx::query_scalar::<_, Option<i64>>("SELECT MAX(version) FROM schema_migrations")
+            .fetch_one(pool)
+            .await
+            .map_err(|e| format!("Failed to read schema version: {}", e))?
+            .unwrap_or(0);
+    let latest = migrations::latest_version();
+    Ok(if current < latest {
+        HealthCheck::new(
+            id,
+            HealthStatus::Error,
+            format!(
+                "Database schema is at version {} of {}; restart the app to finish updating",
+                current, latest
+            ),
+        )
+    } else {
+        HealthCheck::new(id, HealthStatus::Ok, format!("Schema version {}", current))
+    })
+}
+
+// Helper: A write-ahead log that keeps growing slows every read. Checkpoint
+// it back into the database file.
+async fn check_wal(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    repair: bool,
+) -> Result<HealthCheck, AppError> {
+    let id = "database_wal";
+    let db_path = database_path(app)?;
+    let wal = db_path.with_file_name(format!(
+        "{}-wal",
+        db_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    ));
+    let size = std::fs::metadata(&wal).map(|meta| meta.len()).unwrap_or(0);
+    if size <= MAX_WAL_BYTES {
+        return Ok(HealthCheck::new(
+            id,
+            HealthStatus::Ok,
+            "Write-ahead log is small",
+        ));
+    }
+
+    let message = format!("Write-ahead log is {} MB", size / (1024 * 1024));
+    if !repair {
+        return Ok(HealthCheck::new(id, HealthStatus::Warning, message));
+    }
+    sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to checkpoint database: {}", e))?;
+    Ok(HealthCheck::new(id, HealthStatus::Ok, message).repaired("Checkpointed the log"))
+}
+
+// Helper: Messages that gave up, or have sat in a queue too long
+async fn check_queues(pool: &SqlitePool, repair: bool) -> Result<HealthCheck, AppError> {
+    let id = "queues";
+    let mut failed = 0;
+    let mut stuck = 0;
+    for table in QUEUES {
+        let (table_failed, table_stuck) = sqlx::query_as::<_, (i64, i64)>(&format!(
+            "SELECT COALESCE(SUM(status = 'failed'), 0), \
+             COALESCE(SUM(status = 'pending' AND created_at <= datetime('now', ?)), 0) \
+             FROM {}",
+            table
+        ))
+        .bind(format!("-{} minutes", STUCK_PENDING_MINUTES))
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("Failed to count {}: {}", table, e))?;
+        failed += table_failed;
+        stuck += table_stuck;
+    }
+
+    let mut repaired = None;
+    if repair {
+        let skipped = sqlx::query(
+            "UPDATE desktop_notifications SET status = 'skipped' \
+             WHERE status = 'pending' AND created_at <= datetime('now', ?)",
+        )
+        .bind(format!("-{} hours", STALE_NOTIFICATION_HOURS))
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to clear old notifications: {}", e))?
+        .rows_affected();
+        if skipped > 0 {
+            repaired = Some(format!("Dropped {} stale desktop notifications", skipped));
+        }
+    }
+
+    let check = match (failed, stuck) {
+        (0, 0) => HealthCheck::new(id, HealthStatus::Ok, "Queues are moving"),
+        _ => HealthCheck::new(
+            id,
+            HealthStatus::Warning,
+            format!(
+                "{} messages failed and {} have been pending over {} minutes",
+                failed, stuck, STUCK_PENDING_MINUTES
+            ),
+        ),
+    };
+    Ok(match repaired {
+        Some(what) => check.repaired(what),
+        None => check,
+    })
+}
+
+// Helper: Whether background jobs can reach Google. The frontend refreshes
+// the access token while it's open, so only a missing refresh token is a
+// problem.
+async fn check_google_token(pool: &SqlitePool) -> Result<HealthCheck, AppError> {
+    let id = "google_token";
+    let auth = sqlx::query_as::<_, (Option<String>, Option<String>, Option<String>)>(
+        "SELECT auth_mode, refresh_token, token_expiry FROM google_auth WHERE id = 1",
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load Google account: {}", e))?;
+
+    let Some((auth_mode, refresh_token, token_expiry)) = auth else {
+        return Ok(HealthCheck::new(
+            id,
+            HealthStatus::Ok,
+            "Not signed in to Google",
+        ));
+    };
+    if auth_mode.as_deref() == Some("api_key") {
+        return Ok(HealthCheck::new(id, HealthStatus::Ok, "Using an API key"));
+    }
+    if refresh_token.is_none_or(|token| token.is_empty()) {
+        return Ok(HealthCheck::new(
+            id,
+            HealthStatus::Warning,
+            "Google sign-in can't be renewed; sign in again",
+        ));
+    }
+
+    let expiry = token_expiry.and_then(|expiry| chrono::DateTime::parse_from_rfc3339(&expiry).ok());
+    Ok(match expiry {
+        Some(expiry) if expiry <= chrono::Utc::now() => HealthCheck::new(
+            id,
+            HealthStatus::Ok,
+            "Access token expired; it's renewed while the app is open",
+        ),
+        Some(expiry) => HealthCheck::new(
+            id,
+            HealthStatus::Ok,
+            format!("Access token valid until {}", expiry.to_rfc3339()),
+        ),
+        None => HealthCheck::new(
+            id,
+            HealthStatus::Warning,
+            "Access token has no expiry; sign in again if Google calls fail",
+        ),
+    })
+}
+
+// Helper: Bytes free to this user on the volume holding `path`
+#[cfg(unix)]
+// Field widths differ between platforms
+#[allow(clippy::unnecessary_cast)]
+fn available_space(path: &Path) -> Option<u64> {
+    let stats = nix::sys::statvfs::statvfs(path).ok()?;
+    Some(stats.blocks_available() as u64 * stats.fragment_size() as u64)
+}
+
+#[cfg(windows)]
+fn available_space(path: &Path) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut available = 0u64;
+    // SAFETY: `wide` is NUL-terminated and outlives the call; the totals we
+    // don't need may be null
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(
+            wide.as_ptr(),
+            &mut available,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    (ok != 0).then_some(available)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn available_space(_path: &Path) -> Option<u64> {
+    None
+}
+
+// Helper: Room left for the database, backups and attachments
+fn check_disk_space(app: &AppHandle) -> Result<HealthCheck, AppError> {
+    let id = "disk_space";
+    let db_path = database_path(app)?;
+    let dir = db_path.parent().unwrap_or(&db_path);
+    let Some(available) = available_space(dir) else {
+        return Ok(HealthCheck::new(
+            id,
+            HealthStatus::Ok,
+            "Free space can't be read on this system",
+        ));
+    };
+
+    let message = format!("{} MB free", available / (1024 * 1024));
+    let status = if available < CRITICAL_DISK_BYTES {
+        HealthStatus::Error
+    } else if available < LOW_DISK_BYTES {
+        HealthStatus::Warning
+    } else {
+        HealthStatus::Ok
+    };
+    Ok(HealthCheck::new(id, status, message))
+}
+
+// Helper: Temp snapshots and half-written backups left by a crash
+async fn check_leftovers(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    repair: bool,
+) -> Result<HealthCheck, AppError> {
+    let id = "leftover_files";
+    if !repair {
+        return Ok(HealthCheck::new(
+            id,
+            HealthStatus::Ok,
+            "Cleaned up during repair",
+        ));
+    }
+    let removed = crate::backup::remove_stale_snapshots(LEFTOVER_MIN_AGE)
+        + crate::auto_backup::remove_partial_backups(app, pool, LEFTOVER_MIN_AGE).await?;
+    let check = HealthCheck::new(id, HealthStatus::Ok, "No leftover files");
+    Ok(if removed > 0 {
+        check.repaired(format!("Removed {} leftover temporary files", removed))
+    } else {
+        check
+    })
+}
+
+// Helper: Whether the SMTP server answers and accepts the saved login
+async fn check_smtp(pool: &SqlitePool) -> Result<HealthCheck, AppError> {
+    let id = "smtp";
+    let Some(settings) = crate::settings::load_smtp_settings(pool).await? else {
+        return Ok(HealthCheck::new(id, HealthStatus::Ok, "SMTP is not set up"));
+    };
+
+    let mailer = SmtpTransport::relay(&settings.smtp_server)
+        .map_err(|e| format!("Failed to create SMTP transport: {}", e))?
+        .port(settings.smtp_port as u16)
+        .credentials(Credentials::new(settings.username, settings.password))
+        .timeout(Some(Duration::from_secs(SMTP_TIMEOUT_SECS)))
+        .build();
+    let server = settings.smtp_server;
+    let reachable = tauri::async_runtime::spawn_blocking(move || mailer.test_connection())
+        .await
+        .map_err(|e| format!("Failed to test SMTP connection: {}", e))?;
+
+    Ok(match reachable {
+        Ok(true) => HealthCheck::new(id, HealthStatus::Ok, format!("{} is reachable", server)),
+        Ok(false) => HealthCheck::new(
+            id,
+            HealthStatus::Error,
+            format!("{} didn't respond", server),
+        ),
+        Err(e) => HealthCheck::new(
+            id,
+            HealthStatus::Error,
+            redact(&format!("Can't reach {}: {}", server, e)),
+        ),
+    })
+}
+
+// Helper: Run every check, repairing what can be repaired when asked
+async fn run_checks(app: &AppHandle, pool: &SqlitePool, repair: bool) -> HealthReport {
+    let checks = vec![
+        check_integrity(pool, repair)
+            .await
+            .unwrap_or_else(|e| failed("database_integrity", e)),
+        check_schema(pool)
+            .await
+            .unwrap_or_else(|e| failed("schema_version", e)),
+        check_wal(app, pool, repair)
+            .await
+            .unwrap_or_else(|e| failed("database_wal", e)),
+        check_queues(pool, repair)
+            .await
+            .unwrap_or_else(|e| failed("queues", e)),
+        check_google_token(pool)
+            .await
+            .unwrap_or_else(|e| failed("google_token", e)),
+        check_disk_space(app).unwrap_or_else(|e| failed("disk_space", e)),
+        check_leftovers(app, pool, repair)
+            .await
+            .unwrap_or_else(|e| failed("leftover_files", e)),
+        check_smtp(pool).await.unwrap_or_else(|e| failed("smtp", e)),
+    ];
+
+    HealthReport {
+        checked_at: chrono::Utc::now().to_rfc3339(),
+        status: checks
+            .iter()
+            .map(|check| check.status)
+            .max()
+            .unwrap_or(HealthStatus::Ok),
+        checks,
+    }
+}
+
+// Helper: Keep the report for get_last_health_report and tell the UI
+fn publish_report(app: &AppHandle, report: &HealthReport) {
+    if let Ok(mut last) = app.state::<LastHealthReport>().0.lock() {
+        *last = Some(report.clone());
+    }
+    if let Err(e) = app.emit("health-check", report) {
+        log_warning!("Failed to emit health check: {}", e);
+    }
+}
+
+// Run the health check with repairs once the app has started. The report is
+// emitted as "health-check" and kept for get_last_health_report.
+pub fn start_health_check(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<Database>().pool.clone();
+        let report = run_checks(&app, &pool, true).await;
+        for check in report
+            .checks
+            .iter()
+            .filter(|c| c.status != HealthStatus::Ok)
+        {
+            log_warning!("Health check {}: {}", check.id, check.message);
+        }
+        publish_report(&app, &report);
+    });
+}
+
+#[tauri::command]
+pub async fn run_health_check(
+    app: AppHandle,
+    db: State<'_, Database>,
+    repair: bool,
+) -> Result<HealthReport, AppError> {
+    let report = run_checks(&app, &db.pool, repair).await;
+    publish_report(&app, &report);
+    Ok(report)
+}
+
+#[tauri::command]
+pub fn get_last_health_report(
+    last: State<'_, LastHealthReport>,
+) -> Result<Option<HealthReport>, AppError> {
+    last.0
+        .lock()
+        .map(|report| report.clone())
+        .map_err(|e| AppError::Internal(format!("Failed to read health report: {}", e)))
+}
@@ -0,0 +1,508 @@
+use reqwest::header::{HeaderValue, ETAG, IF_NONE_MATCH, RETRY_AFTER};
+use reqwest::{Certificate, Client, ClientBuilder, Proxy, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use sqlx::SqlitePool;
+use std::future::Future;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+use crate::diagnostics::PendingCall;
+use crate::error::AppError;
+use crate::log_warning;
+use crate::redact::redact;
+use crate::settings::{self, NetworkConfig};
+use crate::throttle;
+
+// Defaults, unless the network settings say otherwise
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+// Whole request, including the body; generous so backup downloads can finish
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(300);
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+// Retries after the first attempt, waiting 500ms, 1s, 2s in between unless
+// the service asks for longer with Retry-After
+const MAX_RETRIES: u32 = 3;
+const BASE_DELAY: Duration = Duration::from_millis(500);
+const MAX_DELAY: Duration = Duration::from_secs(30);
+
+const PEM_BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+const PEM_END: &str = "-----END CERTIFICATE-----";
+
+// Reasons Google gives, in error.errors[].reason or error.details[].reason,
+// for a quota or rate limit rather than a refusal (both come back as 403)
+const RATE_LIMIT_REASONS: [&str; 6] = [
+    "rateLimitExceeded",
+    "userRateLimitExceeded",
+    "quotaExceeded",
+    "dailyLimitExceeded",
+    "sharingRateLimitExceeded",
+    "RATE_LIMIT_EXCEEDED",
+];
+// The token works but wasn't granted a scope the call needs
+const SCOPE_REASONS: [&str; 2] = ["insufficientPermissions", "ACCESS_TOKEN_SCOPE_INSUFFICIENT"];
+
+// Shared by every Google API call so connections are reused; reqwest clients
+// are cheap to clone and share their pool. Rebuilt when the network settings
+// change.
+static CLIENT: RwLock<Option<Client>> = RwLock::new(None);
+// Network settings in use, for clients built elsewhere via client_builder
+static CONFIG: RwLock<Option<NetworkConfig>> = RwLock::new(None);
+// Extra root certificates from the CA bundle, PEM, for SMTP to trust as well
+static CA_CERTS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+// Answers kept for conditional GETs, least recently used dropped first
+const MAX_ETAG_ENTRIES: usize = 100;
+// Larger bodies aren't kept; fetching them in full now and then is cheaper
+// than holding on to them
+const MAX_ETAG_BODY: usize = 8 * 1024 * 1024;
+
+// Google API operations in flight that the UI can abandon, by the ID it gave
+static OPERATIONS: Mutex<Vec<(String, Arc<Notify>)>> = Mutex::new(Vec::new());
+
+// Last answer to each GET sent with get_json_conditional that had an ETag, by URL
+static ETAGS: Mutex<Vec<(String, EtagEntry)>> = Mutex::new(Vec::new());
+
+struct EtagEntry {
+    etag: HeaderValue,
+    body: Vec<u8>,
+    used_at: Instant,
+}
+
+// Client and certificates built from the network settings, ready to install
+pub struct NetworkSetup {
+    config: NetworkConfig,
+    client: Client,
+    ca_certs: Vec<String>,
+}
+
+// Helper: The certificates in a PEM file, each with its BEGIN/END lines
+fn load_ca_certs(path: &str) -> Result<Vec<String>, AppError> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| AppError::Validation(format!("Failed to read CA bundle {}: {}", path, e)))?;
+    let certs: Vec<String> = text
+        .split_inclusive(PEM_END)
+        .filter_map(|block| block.find(PEM_BEGIN).map(|start| block[start..].to_string()))
+        .filter(|block| block.ends_with(PEM_END))
+        .collect();
+    if certs.is_empty() {
+        return Err(AppError::Validation(format!(
+            "No PEM certificates found in CA bundle {}",
+            path
+        )));
+    }
+    Ok(certs)
+}
+
+// Helper: Builder with the timeouts and proxy of `config`, trusting
+// `ca_certs`; fails on an unusable proxy URL or certificate
+fn configured_builder(config: &NetworkConfig, ca_certs: &[String]) -> Result<ClientBuilder, AppError> {
+    let connect_timeout = config
+        .connect_timeout_secs
+        .map_or(CONNECT_TIMEOUT, |secs| Duration::from_secs(secs as u64));
+    let request_timeout = config
+        .request_timeout_secs
+        .map_or(REQUEST_TIMEOUT, |secs| Duration::from_secs(secs as u64));
+    let mut builder = Client::builder()
+        .connect_timeout(connect_timeout)
+        .timeout(request_timeout)
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT);
+
+    if let Some(url) = &config.proxy_url {
+        let mut proxy = Proxy::all(url.as_str())
+            .map_err(|e| AppError::Validation(format!("Invalid proxy {}: {}", url, e)))?;
+        if let Some(username) = &config.proxy_username {
+            proxy = proxy.basic_auth(username, config.proxy_password.as_deref().unwrap_or_default());
+        }
+        builder = builder.proxy(proxy);
+    }
+
+    for pem in ca_certs {
+        let cert = Certificate::from_pem(pem.as_bytes())
+            .map_err(|e| AppError::Validation(format!("Invalid certificate in CA bundle: {}", e)))?;
+        builder = builder.add_root_certificate(cert);
+    }
+    Ok(builder)
+}
+
+// Build a client with the configured timeouts, going through the proxy and
+// trusting the CA bundle
+pub fn build(config: &NetworkConfig) -> Result<NetworkSetup, AppError> {
+    let ca_certs = match &config.ca_bundle_path {
+        Some(path) => load_ca_certs(path)?,
+        None => Vec::new(),
+    };
+    let client = configured_builder(config, &ca_certs)?
+        .build()
+        .map_err(|e| AppError::Validation(format!("Failed to configure HTTP client: {}", e)))?;
+    Ok(NetworkSetup {
+        config: config.clone(),
+        client,
+        ca_certs,
+    })
+}
+
+// Use a built client and CA bundle from the next request on
+pub fn install(setup: NetworkSetup) {
+    match CLIENT.write() {
+        Ok(mut client) => *client = Some(setup.client),
+        Err(e) => log_warning!("Failed to install HTTP client: {}", e),
+    }
+    match CONFIG.write() {
+        Ok(mut config) => *config = Some(setup.config),
+        Err(e) => log_warning!("Failed to install network settings: {}", e),
+    }
+    match CA_CERTS.write() {
+        Ok(mut certs) => *certs = setup.ca_certs,
+        Err(e) => log_warning!("Failed to install CA bundle: {}", e),
+    }
+}
+
+// Apply the saved network settings. Called on startup once the database is
+// open; until then, or if they can't be applied, the client connects directly.
+pub async fn apply_saved(pool: &SqlitePool) -> Result<(), AppError> {
+    let config = settings::load_network_config(pool).await?;
+    install(build(&config)?);
+    Ok(())
+}
+
+// Builder for a client of its own (another user agent, a shorter timeout)
+// that still uses the configured timeouts, proxy and CA bundle
+pub fn client_builder() -> ClientBuilder {
+    let config = CONFIG
+        .read()
+        .ok()
+        .and_then(|config| config.clone())
+        .unwrap_or_default();
+    configured_builder(&config, &ca_certs()).unwrap_or_else(|e| {
+        log_warning!("Failed to apply network settings: {}", e);
+        Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(REQUEST_TIMEOUT)
+    })
+}
+
+pub fn client() -> Client {
+    if let Some(client) = CLIENT.read().ok().and_then(|client| client.clone()) {
+        return client;
+    }
+    let client = client_builder().build().unwrap_or_else(|e| {
+        log_warning!("Failed to configure HTTP client, using defaults: {}", e);
+        Client::new()
+    });
+    match CLIENT.write() {
+        Ok(mut shared) => shared.get_or_insert(client).clone(),
+        Err(_) => client,
+    }
+}
+
+// Extra root certificates SMTP should trust, PEM
+pub fn ca_certs() -> Vec<String> {
+    CA_CERTS.read().map(|certs| certs.clone()).unwrap_or_default()
+}
+
+// Helper: Whether an answer is worth retrying: rate limited or a server error
+fn retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+// Helper: Wait asked for by a Retry-After header given in seconds
+fn retry_after(response: &Response) -> Option<Duration> {
+    let secs = response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(Duration::from_secs(secs).min(MAX_DELAY))
+}
+
+// Helper: Exponential backoff before retry number `retries` + 1
+fn backoff(retries: u32) -> Duration {
+    (BASE_DELAY * 2u32.pow(retries)).min(MAX_DELAY)
+}
+
+// Send a request, retrying 429 and 5xx answers, timeouts and failed
+// connections with exponential backoff. Requests with a streamed body can't be
+// repeated and are sent once. Google API calls wait their turn under the
+// per-minute throttle first. Each call is recorded for diagnostics with its
+// retry count.
+pub trait SendWithRetry {
+    fn send_with_retry(self) -> impl Future<Output = reqwest::Result<Response>> + Send;
+}
+
+impl SendWithRetry for RequestBuilder {
+    async fn send_with_retry(self) -> reqwest::Result<Response> {
+        let (client, request) = self.build_split();
+        let mut request = request?;
+        let call = PendingCall::start(&request);
+        let mut retries = 0;
+
+        let result = loop {
+            let next = if retries < MAX_RETRIES {
+                request.try_clone()
+            } else {
+                None
+            };
+            let label = format!("{} {}", request.method(), request.url().path());
+            throttle::acquire(request.url()).await;
+            let result = client.execute(request).await;
+            let Some(next) = next else {
+                break result;
+            };
+            let delay = match &result {
+                Ok(response) if retryable_status(response.status()) => {
+                    retry_after(response).unwrap_or_else(|| backoff(retries))
+                }
+                Err(e) if e.is_connect() || e.is_timeout() => backoff(retries),
+                _ => break result,
+            };
+
+            retries += 1;
+            log_warning!(
+                "{} failed (attempt {}/{}), retrying in {}ms",
+                label,
+                retries,
+                MAX_RETRIES + 1,
+                delay.as_millis()
+            );
+            tokio::time::sleep(delay).await;
+            request = next;
+        };
+
+        call.finish(retries, &result);
+        result
+    }
+}
+
+// Google API error body:
+// { "error": { "code": 403, "message": "...", "status": "PERMISSION_DENIED",
+//   "errors": [{ "reason": "insufficientPermissions" }],
+//   "details": [{ "reason": "ACCESS_TOKEN_SCOPE_INSUFFICIENT" }] } }
+// The OAuth token endpoint answers { "error": "invalid_grant",
+// "error_description": "..." } instead.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum GoogleErrorBody {
+    Api { error: GoogleApiError },
+    OAuth {
+        error: String,
+        error_description: Option<String>,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleApiError {
+    message: Option<String>,
+    status: Option<String>,
+    #[serde(default)]
+    errors: Vec<GoogleErrorReason>,
+    #[serde(default)]
+    details: Vec<GoogleErrorReason>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleErrorReason {
+    reason: Option<String>,
+}
+
+// Helper: Error for a Google answer with `status` and `body`, prefixed with
+// `context` (e.g. "Failed to get responses")
+fn parse_google_error(status: u16, body: &str, context: &str) -> AppError {
+    let parsed = match serde_json::from_str::<GoogleErrorBody>(body) {
+        Ok(parsed) => parsed,
+        Err(_) => return AppError::from_status(status, format!("{}: {}", context, redact(body))),
+    };
+
+    let error = match parsed {
+        GoogleErrorBody::OAuth {
+            error,
+            error_description,
+        } => {
+            let message = format!(
+                "{}: {}",
+                context,
+                redact(error_description.as_deref().unwrap_or(&error))
+            );
+            // invalid_grant: the code or refresh token was used, revoked or expired
+            if error == "invalid_grant" || status == 401 {
+                return AppError::AuthExpired(message);
+            }
+            return AppError::from_status(status, message);
+        }
+        GoogleErrorBody::Api { error } => error,
+    };
+
+    let message = format!(
+        "{}: {}",
+        context,
+        redact(error.message.as_deref().unwrap_or(body))
+    );
+    let has_reason = |reasons: &[&str]| {
+        error
+            .errors
+            .iter()
+            .chain(&error.details)
+            .filter_map(|e| e.reason.as_deref())
+            .any(|reason| reasons.contains(&reason))
+    };
+
+    if has_reason(&RATE_LIMIT_REASONS) || error.status.as_deref() == Some("RESOURCE_EXHAUSTED") {
+        return AppError::RateLimited {
+            message,
+            status: Some(status),
+        };
+    }
+    if has_reason(&SCOPE_REASONS) {
+        return AppError::ScopeMissing(message);
+    }
+    match error.status.as_deref() {
+        Some("UNAUTHENTICATED") => AppError::AuthExpired(message),
+        Some("PERMISSION_DENIED") => AppError::PermissionDenied(message),
+        Some("NOT_FOUND") => AppError::NotFound(message),
+        _ => AppError::from_status(status, message),
+    }
+}
+
+// Error for an unsuccessful Google API answer, typed from the error JSON
+// (rate limited, missing scope, no permission, not found) and carrying
+// Google's message rather than the raw body
+pub async fn google_error(response: Response, context: &str) -> AppError {
+    let status = response.status().as_u16();
+    let body = response.text().await.unwrap_or_default();
+    parse_google_error(status, &body, context)
+}
+
+// Helper: Keep an answer for get_json_conditional, making room if needed
+fn remember_etag(url: String, etag: HeaderValue, body: &[u8]) {
+    if body.len() > MAX_ETAG_BODY {
+        return;
+    }
+    let Ok(mut entries) = ETAGS.lock() else {
+        return;
+    };
+    entries.retain(|(key, _)| *key != url);
+    if entries.len() >= MAX_ETAG_ENTRIES {
+        let oldest = entries
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, (_, entry))| entry.used_at)
+            .map(|(index, _)| index);
+        if let Some(index) = oldest {
+            entries.swap_remove(index);
+        }
+    }
+    entries.push((
+        url,
+        EtagEntry {
+            etag,
+            body: body.to_vec(),
+            used_at: Instant::now(),
+        },
+    ));
+}
+
+// Send a GET and parse its JSON body, asking with If-None-Match whether the
+// last answer to the same URL is still current. On 304 Not Modified that
+// answer is used again, so polling data that hasn't changed transfers next to
+// nothing. The answer only comes from Google, which checks the token as
+// usual, so it's shared between accounts. Errors carry `context`.
+pub async fn get_json_conditional<T: DeserializeOwned>(
+    request: RequestBuilder,
+    context: &str,
+) -> Result<T, AppError> {
+    let (client, request) = request.build_split();
+    let mut request = request.map_err(|e| format!("{}: {}", context, e))?;
+    let url = request.url().to_string();
+    let cached = ETAGS.lock().ok().and_then(|mut entries| {
+        let (_, entry) = entries.iter_mut().find(|(key, _)| *key == url)?;
+        entry.used_at = Instant::now();
+        Some((entry.etag.clone(), entry.body.clone()))
+    });
+    if let Some((etag, _)) = &cached {
+        request.headers_mut().insert(IF_NONE_MATCH, etag.clone());
+    }
+
+    let response = RequestBuilder::from_parts(client, request)
+        .send_with_retry()
+        .await
+        .map_err(|e| AppError::Network(format!("{}: {}", context, e)))?;
+    let body = match (response.status(), cached) {
+        (StatusCode::NOT_MODIFIED, Some((_, body))) => body,
+        (status, _) if status.is_success() => {
+            let etag = response.headers().get(ETAG).cloned();
+            let body = response
+                .bytes()
+                .await
+                .map_err(|e| AppError::Network(format!("{}: {}", context, e)))?
+                .to_vec();
+            if let Some(etag) = etag {
+                remember_etag(url, etag, &body);
+            }
+            body
+        }
+        _ => return Err(google_error(response, context).await),
+    };
+    serde_json::from_slice(&body)
+        .map_err(|e| AppError::Internal(format!("{}: {}", context, e)))
+}
+
+// Helper: Drops an operation from OPERATIONS once it ends, however it ends
+struct Registered(String);
+
+impl Drop for Registered {
+    fn drop(&mut self) {
+        if let Ok(mut operations) = OPERATIONS.lock() {
+            operations.retain(|(id, _)| id != &self.0);
+        }
+    }
+}
+
+// Run a Google API operation that the UI can abandon by passing the same
+// `operation_id` to cancel_operation. Cancelling drops the work, aborting the
+// request in flight or the wait before a retry. Without an ID it just runs.
+pub async fn cancellable<T>(
+    operation_id: Option<String>,
+    work: impl Future<Output = Result<T, AppError>>,
+) -> Result<T, AppError> {
+    let Some(id) = operation_id else {
+        return work.await;
+    };
+    let cancel = Arc::new(Notify::new());
+    {
+        let mut operations = OPERATIONS
+            .lock()
+            .map_err(|e| format!("Failed to register operation: {}", e))?;
+        if operations.iter().any(|(running, _)| running == &id) {
+            return Err(AppError::Conflict(format!(
+                "Operation {} is already running",
+                id
+            )));
+        }
+        operations.push((id.clone(), cancel.clone()));
+    }
+    let _registered = Registered(id);
+
+    tokio::select! {
+        result = work => result,
+        _ = cancel.notified() => Err(AppError::Cancelled("Cancelled".to_string())),
+    }
+}
+
+// Abandon an operation started with this operation_id
+#[tauri::command]
+pub fn cancel_operation(operation_id: String) -> Result<(), AppError> {
+    let operations = OPERATIONS
+        .lock()
+        .map_err(|e| format!("Failed to read operations: {}", e))?;
+    let (_, cancel) = operations
+        .iter()
+        .find(|(id, _)| id == &operation_id)
+        .ok_or_else(|| AppError::NotFound(format!("Operation {} is not running", operation_id)))?;
+    // notify_one keeps the wake-up if the operation isn't waiting yet
+    cancel.notify_one();
+    Ok(())
+}
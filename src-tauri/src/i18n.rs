@@ -0,0 +1,392 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, Sqlite};
+use tauri::State;
+
+use crate::audit;
+use crate::db::Database;
+use crate::error::AppError;
+
+// Languages backend-generated documents, emails and pages can be written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    Id,
+}
+
+// English is the reference catalog; every key used in the code must be here.
+// Other catalogs may lag behind and fall back to it.
+const EN: &[(&str, &str)] = &[
+    // Invoices
+    ("invoice.title", "Invoice"),
+    ("invoice.titles", "Invoices"),
+    ("invoice.preview", "Invoice preview"),
+    ("invoice.draft", "Draft"),
+    ("invoice.issued", "Issued"),
+    ("invoice.cancelled", "CANCELLED"),
+    ("invoice.bill_to", "Bill to"),
+    ("invoice.order", "Order"),
+    ("invoice.confirmation_code", "Confirmation code"),
+    ("invoice.item", "Item"),
+    ("invoice.qty", "Qty"),
+    ("invoice.unit_price", "Unit price"),
+    ("invoice.amount", "Amount"),
+    ("invoice.product", "Product #{id}"),
+    ("invoice.subtotal", "Subtotal"),
+    ("invoice.tax_included", "included"),
+    ("invoice.total", "Total"),
+    ("invoice.paid", "Paid"),
+    ("invoice.credited", "Credited"),
+    ("invoice.deposit_paid", "Deposit paid ({number})"),
+    ("invoice.balance_due", "Balance due"),
+    ("invoice.notes", "Notes"),
+    ("invoice.scan_to_pay", "Scan to pay with QRIS"),
+    ("invoice.qris_enter_amount", "Enter {amount} when paying"),
+    // Credit notes
+    ("credit_note.title", "Credit note"),
+    ("credit_note.for_invoice", "Credits invoice {number}"),
+    ("credit_note.reason", "Reason"),
+    ("credit_note.reason.returned", "Items returned"),
+    ("credit_note.reason.damaged", "Items damaged"),
+    ("credit_note.reason.not_delivered", "Items not delivered"),
+    ("credit_note.reason.price_adjustment", "Price adjustment"),
+    ("credit_note.reason.goodwill", "Goodwill credit"),
+    ("credit_note.reason.other", "Other"),
+    ("credit_note.total", "Total credited"),
+    ("deposit.title", "Deposit invoice"),
+    ("deposit.line", "Deposit of {percent}% on order {code}"),
+    ("deposit.order_total", "Order total"),
+    ("deposit.due", "Deposit due"),
+    (
+        "deposit.final_invoice_note",
+        "The remainder is billed on the final invoice.",
+    ),
+    // Packing slips and pick lists
+    ("packing.title", "PACKING SLIP"),
+    ("packing.document", "Packing slips - {campaign}"),
+    ("packing.ship_to", "Ship to"),
+    ("packing.delivery", "Delivery"),
+    ("packing.pickup", "Pickup"),
+    ("packing.shipping", "Shipping"),
+    ("packing.tracking", "Tracking {number}"),
+    ("packing.invoice", "Invoice {number}"),
+    ("packing.units", "{count} item(s)"),
+    ("pick_list.title", "PICK LIST"),
+    ("pick_list.document", "Pick list - {campaign}"),
+    ("pick_list.generated", "{campaign} - generated {date}"),
+    ("pick_list.summary", "{orders} orders, {units} units"),
+    // Recurring order confirmation email
+    (
+        "email.confirm.subject",
+        "Please confirm your order - {code}",
+    ),
+    ("email.greeting", "Hi {name},"),
+    (
+        "email.confirm.intro",
+        "Your recurring order is ready. Please confirm it using the code below.",
+    ),
+    ("email.confirm.code", "Confirmation code:"),
+    ("email.item", "Item"),
+    ("email.qty", "Qty"),
+    ("email.price", "Price"),
+    ("email.total", "Total:"),
+    // Payment reminder email
+    ("email.reminder.subject", "Payment reminder - {reference}"),
+    (
+        "email.reminder.intro",
+        "This is a friendly reminder that {reference} still has {amount} to pay.",
+    ),
+    (
+        "email.reminder.pickup",
+        "Pickup at {event} is on {date}; please pay before then.",
+    ),
+    (
+        "email.reminder.paid",
+        "If you've already paid, thank you, and please ignore this email.",
+    ),
+    ("email.reminder.invoice", "invoice {number}"),
+    ("email.reminder.order", "your order {code}"),
+    // Owner and team notifications (Telegram, Slack, Discord)
+    (
+        "notify.new_order",
+        "New order {code} from {customer}: {total}",
+    ),
+    (
+        "notify.payment",
+        "Payment of {amount} received for order {code} ({customer}) via {method}",
+    ),
+    (
+        "notify.low_stock",
+        "Low stock: {product} is down to {quantity} (alert level {threshold})",
+    ),
+    (
+        "notify.email_failed",
+        "Email \"{subject}\" to {to} could not be sent: {error}",
+    ),
+    ("notify.test", "POTracker notifications are working."),
+    // Desktop notifications
+    ("desktop.form_response.title", "New form responses"),
+    ("desktop.form_response.body", "{form}: {count} new"),
+    ("desktop.email_failed.title", "Email not sent"),
+    ("desktop.reminder_due.title", "Payment reminders due"),
+    (
+        "desktop.reminder_due.body",
+        "{count} payment reminder(s) ready to send",
+    ),
+    // Text messages
+    (
+        "sms.confirmation",
+        "Hi {name}, your order is confirmed. Your confirmation code is {code}.",
+    ),
+    (
+        "sms.reminder",
+        "Hi {name}, {reference} still has {amount} to pay. Please ignore this if you've already paid.",
+    ),
+    ("sms.test", "POTracker text messages are working."),
+    // Browser page shown after Google sign-in
+    ("oauth.success.title", "Authentication Successful"),
+    ("oauth.success.heading", "Authentication Successful!"),
+    (
+        "oauth.success.body",
+        "You can close this window and return to POTracker.",
+    ),
+];
+
+const ID: &[(&str, &str)] = &[
+    ("invoice.title", "Faktur"),
+    ("invoice.titles", "Faktur"),
+    ("invoice.preview", "Pratinjau faktur"),
+    ("invoice.draft", "Draf"),
+    ("invoice.issued", "Diterbitkan"),
+    ("invoice.cancelled", "DIBATALKAN"),
+    ("invoice.bill_to", "Tagihan kepada"),
+    ("invoice.order", "Pesanan"),
+    ("invoice.confirmation_code", "Kode konfirmasi"),
+    ("invoice.item", "Barang"),
+    ("invoice.qty", "Jml"),
+    ("invoice.unit_price", "Harga satuan"),
+    ("invoice.amount", "Jumlah"),
+    ("invoice.product", "Produk #{id}"),
+    ("invoice.subtotal", "Subtotal"),
+    ("invoice.tax_included", "termasuk"),
+    ("invoice.total", "Total"),
+    ("invoice.paid", "Dibayar"),
+    ("invoice.credited", "Dikreditkan"),
+    ("invoice.deposit_paid", "Uang muka dibayar ({number})"),
+    ("invoice.balance_due", "Sisa tagihan"),
+    ("invoice.notes", "Catatan"),
+    ("invoice.scan_to_pay", "Pindai untuk membayar dengan QRIS"),
+    (
+        "invoice.qris_enter_amount",
+        "Masukkan {amount} saat membayar",
+    ),
+    ("credit_note.title", "Nota kredit"),
+    ("credit_note.for_invoice", "Atas faktur {number}"),
+    ("credit_note.reason", "Alasan"),
+    ("credit_note.reason.returned", "Barang dikembalikan"),
+    ("credit_note.reason.damaged", "Barang rusak"),
+    ("credit_note.reason.not_delivered", "Barang tidak terkirim"),
+    ("credit_note.reason.price_adjustment", "Penyesuaian harga"),
+    ("credit_note.reason.goodwill", "Kredit kebijakan"),
+    ("credit_note.reason.other", "Lainnya"),
+    ("credit_note.total", "Total dikreditkan"),
+    ("deposit.title", "Faktur uang muka"),
+    ("deposit.line", "Uang muka {percent}% untuk pesanan {code}"),
+    ("deposit.order_total", "Total pesanan"),
+    ("deposit.due", "Uang muka"),
+    (
+        "deposit.final_invoice_note",
+        "Sisa pembayaran ditagihkan pada faktur akhir.",
+    ),
+    ("packing.title", "SLIP PENGEPAKAN"),
+    ("packing.document", "Slip pengepakan - {campaign}"),
+    ("packing.ship_to", "Kirim ke"),
+    ("packing.delivery", "Pengiriman"),
+    ("packing.pickup", "Ambil sendiri"),
+    ("packing.shipping", "Dikirim"),
+    ("packing.tracking", "Resi {number}"),
+    ("packing.invoice", "Faktur {number}"),
+    ("packing.units", "{count} barang"),
+    ("pick_list.title", "DAFTAR AMBIL"),
+    ("pick_list.document", "Daftar ambil - {campaign}"),
+    ("pick_list.generated", "{campaign} - dibuat {date}"),
+    ("pick_list.summary", "{orders} pesanan, {units} unit"),
+    (
+        "email.confirm.subject",
+        "Mohon konfirmasi pesanan Anda - {code}",
+    ),
+    ("email.greeting", "Halo {name},"),
+    (
+        "email.confirm.intro",
+        "Pesanan berulang Anda sudah siap. Mohon konfirmasi dengan kode di bawah ini.",
+    ),
+    ("email.confirm.code", "Kode konfirmasi:"),
+    ("email.item", "Barang"),
+    ("email.qty", "Jml"),
+    ("email.price", "Harga"),
+    ("email.total", "Total:"),
+    (
+        "email.reminder.subject",
+        "Pengingat pembayaran - {reference}",
+    ),
+    (
+        "email.reminder.intro",
+        "Kami ingin mengingatkan bahwa {reference} masih perlu dibayar sebesar {amount}.",
+    ),
+    (
+        "email.reminder.pickup",
+        "Pengambilan di {event} pada {date}; mohon lunasi sebelum itu.",
+    ),
+    (
+        "email.reminder.paid",
+        "Jika Anda sudah membayar, terima kasih, dan abaikan email ini.",
+    ),
+    ("email.reminder.invoice", "faktur {number}"),
+    ("email.reminder.order", "pesanan Anda {code}"),
+    (
+        "notify.new_order",
+        "Pesanan baru {code} dari {customer}: {total}",
+    ),
+    (
+        "notify.payment",
+        "Pembayaran {amount} diterima untuk pesanan {code} ({customer}) melalui {method}",
+    ),
+    (
+        "notify.low_stock",
+        "Stok menipis: {product} tinggal {quantity} (batas peringatan {threshold})",
+    ),
+    (
+        "notify.email_failed",
+        "Email \"{subject}\" ke {to} gagal dikirim: {error}",
+    ),
+    ("notify.test", "Notifikasi POTracker berfungsi."),
+    ("desktop.form_response.title", "Respons formulir baru"),
+    ("desktop.form_response.body", "{form}: {count} baru"),
+    ("desktop.email_failed.title", "Email gagal dikirim"),
+    ("desktop.reminder_due.title", "Pengingat pembayaran jatuh tempo"),
+    (
+        "desktop.reminder_due.body",
+        "{count} pengingat pembayaran siap dikirim",
+    ),
+    (
+        "sms.confirmation",
+        "Halo {name}, pesanan Anda telah dikonfirmasi. Kode konfirmasi Anda: {code}.",
+    ),
+    (
+        "sms.reminder",
+        "Halo {name}, {reference} masih perlu dibayar sebesar {amount}. Abaikan pesan ini jika sudah membayar.",
+    ),
+    ("sms.test", "SMS POTracker berfungsi."),
+    ("oauth.success.title", "Autentikasi Berhasil"),
+    ("oauth.success.heading", "Autentikasi Berhasil!"),
+    (
+        "oauth.success.body",
+        "Anda dapat menutup jendela ini dan kembali ke POTracker.",
+    ),
+];
+
+// Helper: Look a key up in one catalog
+fn lookup(catalog: &[(&'static str, &'static str)], key: &str) -> Option<&'static str> {
+    catalog.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+}
+
+// Whether `key` names a message (used to validate {{t.key}} placeholders)
+pub fn has_message(key: &str) -> bool {
+    lookup(EN, key).is_some()
+}
+
+impl Locale {
+    // Accepts "id", "id-ID", "en_US" and so on
+    pub fn parse(code: &str) -> Option<Self> {
+        let language = code.trim().split(['-', '_']).next()?.to_lowercase();
+        match language.as_str() {
+            "en" => Some(Locale::En),
+            "id" | "in" => Some(Locale::Id),
+            _ => None,
+        }
+    }
+
+    fn catalog(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Locale::En => EN,
+            Locale::Id => ID,
+        }
+    }
+
+    // Message for `key`, falling back to English and then to the key itself
+    // so a missing translation never blanks out a document
+    pub fn text(self, key: &str) -> String {
+        lookup(self.catalog(), key)
+            .or_else(|| lookup(EN, key))
+            .unwrap_or(key)
+            .to_string()
+    }
+
+    // Message with its {name} arguments filled in
+    pub fn format(self, key: &str, args: &[(&str, &str)]) -> String {
+        args.iter().fold(self.text(key), |message, (name, value)| {
+            message.replace(&format!("{{{}}}", name), value)
+        })
+    }
+}
+
+// Helper: The app's locale, or English if none was saved
+pub async fn load_locale<'e, E>(executor: E) -> Locale
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    sqlx::query_scalar::<_, Locale>("SELECT locale FROM locale_settings WHERE id = 1")
+        .fetch_optional(executor)
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub async fn get_locale(db: State<'_, Database>) -> Result<Locale, AppError> {
+    Ok(load_locale(&db.pool).await)
+}
+
+// Set the language used for invoices, packing documents, emails and the
+// sign-in page. Takes a language tag like "id" or "en-US".
+#[tauri::command]
+pub async fn set_locale(db: State<'_, Database>, locale: String) -> Result<Locale, AppError> {
+    let locale = Locale::parse(&locale)
+        .ok_or_else(|| AppError::Validation(format!("Unsupported locale: {}", locale)))?;
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_locale(&mut *tx).await;
+
+    sqlx::query(
+        "INSERT OR REPLACE INTO locale_settings (id, locale, updated_at) \
+         VALUES (1, ?, CURRENT_TIMESTAMP)",
+    )
+    .bind(locale)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save locale: {}", e))?;
+
+    audit::record(
+        &mut *tx,
+        "locale_settings",
+        1,
+        "update",
+        Some(&before),
+        Some(&locale),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save locale: {}", e))?;
+
+    Ok(locale)
+}
@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteConnection;
+
+use crate::error::AppError;
+
+// Rows that still point at something the caller wants to remove
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reference {
+    pub entity: String,
+    pub ids: Vec<i64>,
+}
+
+// Helper: IDs returned by a single-bind query, wrapped as a reference if any
+async fn collect_reference(
+    conn: &mut SqliteConnection,
+    entity: &str,
+    sql: &str,
+    id: i64,
+) -> Result<Option<Reference>, AppError> {
+    let ids = sqlx::query_scalar::<_, i64>(sql)
+        .bind(id)
+        .fetch_all(conn)
+        .await
+        .map_err(|e| format!("Failed to check {} references: {}", entity, e))?;
+    Ok((!ids.is_empty()).then(|| Reference {
+        entity: entity.to_string(),
+        ids,
+    }))
+}
+
+// Open orders, active recurring templates and unreceived supplier orders that
+// still need a product
+pub async fn product_references(
+    conn: &mut SqliteConnection,
+    product_id: i64,
+) -> Result<Vec<Reference>, AppError> {
+    let checks = [
+        (
+            "order",
+            "SELECT DISTINCT po.id FROM order_items oi JOIN preorders po ON po.id = oi.preorder_id \
+             WHERE oi.product_id = ? AND po.deleted_at IS NULL \
+             AND COALESCE(po.status, 'pending') NOT IN ('fulfilled', 'cancelled') ORDER BY po.id",
+        ),
+        (
+            "recurring_order",
+            "SELECT DISTINCT r.id FROM recurring_order_items ri \
+             JOIN recurring_orders r ON r.id = ri.recurring_order_id \
+             WHERE ri.product_id = ? AND r.status != 'ended' ORDER BY r.id",
+        ),
+        (
+            "supplier_order",
+            "SELECT DISTINCT so.id FROM supplier_order_items si \
+             JOIN supplier_orders so ON so.id = si.supplier_order_id \
+             WHERE si.product_id = ? AND so.status IN ('draft', 'ordered') ORDER BY so.id",
+        ),
+    ];
+
+    let mut references = Vec::new();
+    for (entity, sql) in checks {
+        if let Some(reference) = collect_reference(&mut *conn, entity, sql, product_id).await? {
+            references.push(reference);
+        }
+    }
+    Ok(references)
+}
+
+// Helper: InUse error for `entity` `id` listing what's blocking it
+fn in_use(entity: &str, label: &str, id: i64, references: Vec<Reference>) -> AppError {
+    let summary = references
+        .iter()
+        .map(|r| format!("{} {}", r.ids.len(), r.entity.replace('_', " ")))
+        .collect::<Vec<_>>()
+        .join(", ");
+    AppError::InUse {
+        message: format!(
+            "{} {} is still used by open records ({}); finish or cancel them first",
+            label, id, summary
+        ),
+        entity: entity.to_string(),
+        id,
+        references,
+    }
+}
+
+// Fail with InUse if anything open still needs the product
+pub async fn ensure_product_removable(
+    conn: &mut SqliteConnection,
+    product_id: i64,
+) -> Result<(), AppError> {
+    let references = product_references(conn, product_id).await?;
+    if references.is_empty() {
+        return Ok(());
+    }
+    Err(in_use("product", "Product", product_id, references))
+}
+
+// Fail with InUse if the campaign still has open orders. Archiving them would
+// leave their stock reserved, so they have to be fulfilled or cancelled first.
+pub async fn ensure_event_removable(
+    conn: &mut SqliteConnection,
+    event_id: i64,
+) -> Result<(), AppError> {
+    let open_orders = collect_reference(
+        conn,
+        "order",
+        "SELECT id FROM preorders WHERE event_id = ? AND deleted_at IS NULL \
+         AND COALESCE(status, 'pending') NOT IN ('fulfilled', 'cancelled') ORDER BY id",
+        event_id,
+    )
+    .await?;
+    match open_orders {
+        Some(reference) => Err(in_use("event", "Campaign", event_id, vec![reference])),
+        None => Ok(()),
+    }
+}
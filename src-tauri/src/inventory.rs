@@ -0,0 +1,369 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection, SqlitePool};
+use std::collections::BTreeMap;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::audit;
+use crate::db::Database;
+use crate::domain_events::{publish, DomainEvent};
+use crate::error::AppError;
+use crate::log_warning;
+
+const STOCK_LEVEL_SELECT: &str = "SELECT id AS product_id, name AS product_name, stock_quantity, \
+     low_stock_threshold, \
+     (stock_quantity IS NOT NULL AND low_stock_threshold IS NOT NULL \
+      AND stock_quantity <= low_stock_threshold) AS is_low \
+     FROM products";
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StockLevel {
+    pub product_id: i64,
+    pub product_name: String,
+    // None when stock isn't tracked for the product
+    pub stock_quantity: Option<i64>,
+    pub low_stock_threshold: Option<i64>,
+    pub is_low: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StockMovement {
+    pub id: i64,
+    pub product_id: i64,
+    pub change: i64,
+    pub quantity_after: i64,
+    pub reason: String,
+    pub preorder_id: Option<i64>,
+    pub note: Option<String>,
+    pub created_at: Option<String>,
+}
+
+// Helper: Current stock level for a product
+pub async fn load_stock_level(
+    conn: &mut SqliteConnection,
+    product_id: i64,
+) -> Result<StockLevel, AppError> {
+    sqlx::query_as::<_, StockLevel>(&format!("{} WHERE id = ?", STOCK_LEVEL_SELECT))
+        .bind(product_id)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| format!("Failed to load stock level: {}", e))?
+        .ok_or_else(|| AppError::NotFound(format!("Product {} not found", product_id)))
+}
+
+// Helper: Apply a stock change and log it. Stock can't go below zero; a
+// product that wasn't tracked yet starts from zero.
+pub async fn record_movement(
+    conn: &mut SqliteConnection,
+    product_id: i64,
+    change: i64,
+    reason: &str,
+    order_id: Option<i64>,
+    note: Option<&str>,
+) -> Result<StockLevel, AppError> {
+    let before = load_stock_level(&mut *conn, product_id).await?;
+    let quantity_after = before.stock_quantity.unwrap_or(0) + change;
+    if quantity_after < 0 {
+        return Err(AppError::Validation(format!(
+            "Not enough stock for {}: {} available, {} requested",
+            before.product_name,
+            before.stock_quantity.unwrap_or(0),
+            -change
+        )));
+    }
+
+    sqlx::query("UPDATE products SET stock_quantity = ? WHERE id = ?")
+        .bind(quantity_after)
+        .bind(product_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to update stock: {}", e))?;
+
+    sqlx::query(
+        "INSERT INTO stock_movements (product_id, change, quantity_after, reason, preorder_id, note) \
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(product_id)
+    .bind(change)
+    .bind(quantity_after)
+    .bind(reason)
+    .bind(order_id)
+    .bind(note)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to record stock movement: {}", e))?;
+
+    let after = load_stock_level(&mut *conn, product_id).await?;
+    audit::record(
+        &mut *conn,
+        "product",
+        product_id,
+        reason,
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
+
+    // Only the movement that takes a product down to its threshold raises an
+    // alert, not every one after it
+    if after.is_low && !before.is_low {
+        publish(&mut *conn, &DomainEvent::StockLow(&after)).await?;
+    }
+    Ok(after)
+}
+
+// Helper: Tracked products on an order with the quantity ordered
+pub async fn tracked_order_quantities(
+    conn: &mut SqliteConnection,
+    order_id: i64,
+) -> Result<Vec<(i64, i64)>, AppError> {
+    sqlx::query_as::<_, (i64, i64)>(
+        "SELECT oi.product_id, SUM(oi.quantity) FROM order_items oi \
+         JOIN products p ON p.id = oi.product_id \
+         WHERE oi.preorder_id = ? AND p.stock_quantity IS NOT NULL \
+         GROUP BY oi.product_id",
+    )
+    .bind(order_id)
+    .fetch_all(conn)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to load order items: {}", e)))
+}
+
+// Take an order's items out of stock when it's confirmed (or restored while
+// holding stock). Fails without changing anything if a tracked product
+// doesn't have enough left. Returns products that are now at or below their
+// low-stock threshold.
+pub async fn decrement_for_order(
+    conn: &mut SqliteConnection,
+    order_id: i64,
+    reason: &str,
+) -> Result<Vec<StockLevel>, AppError> {
+    let mut low = Vec::new();
+    for (product_id, quantity) in tracked_order_quantities(&mut *conn, order_id).await? {
+        let level = record_movement(
+            &mut *conn,
+            product_id,
+            -quantity,
+            reason,
+            Some(order_id),
+            None,
+        )
+        .await?;
+        if level.is_low {
+            low.push(level);
+        }
+    }
+    Ok(low)
+}
+
+// Put an order's items back into stock when it's cancelled or deleted
+pub async fn restock_for_order(
+    conn: &mut SqliteConnection,
+    order_id: i64,
+    reason: &str,
+) -> Result<(), AppError> {
+    for (product_id, quantity) in tracked_order_quantities(&mut *conn, order_id).await? {
+        record_movement(
+            &mut *conn,
+            product_id,
+            quantity,
+            reason,
+            Some(order_id),
+            None,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+// Move stock by the difference after the items of an order that holds stock
+// were replaced. `held` is tracked_order_quantities from before the change,
+// so cancelling later restocks exactly what the order has taken.
+pub async fn adjust_for_order_edit(
+    conn: &mut SqliteConnection,
+    order_id: i64,
+    held: &[(i64, i64)],
+) -> Result<(), AppError> {
+    let mut changes: BTreeMap<i64, i64> = held.iter().copied().collect();
+    for (product_id, quantity) in tracked_order_quantities(&mut *conn, order_id).await? {
+        *changes.entry(product_id).or_default() -= quantity;
+    }
+    for (product_id, change) in changes {
+        if change != 0 {
+            record_movement(
+                &mut *conn,
+                product_id,
+                change,
+                "order_edited",
+                Some(order_id),
+                None,
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+// Reject order items that ask for more than a tracked product has in stock
+pub async fn ensure_in_stock(
+    conn: &mut SqliteConnection,
+    product_id: i64,
+    quantity: i64,
+) -> Result<(), AppError> {
+    let level = load_stock_level(conn, product_id).await?;
+    match level.stock_quantity {
+        Some(available) if available < quantity => Err(AppError::Validation(format!(
+            "Not enough stock for {}: {} available, {} ordered",
+            level.product_name, available, quantity
+        ))),
+        _ => Ok(()),
+    }
+}
+
+// Notify the frontend about products that ran low
+pub fn emit_low_stock(app: &AppHandle, levels: &[StockLevel]) {
+    for level in levels {
+        if let Err(e) = app.emit("low-stock", level) {
+            log_warning!("Failed to emit low-stock event: {}", e);
+        }
+    }
+}
+
+// Helper: Stock levels for active products, optionally only the low ones
+async fn list_stock_levels(pool: &SqlitePool, low_only: bool) -> Result<Vec<StockLevel>, AppError> {
+    let mut query = QueryBuilder::<Sqlite>::new(STOCK_LEVEL_SELECT);
+    query.push(" WHERE COALESCE(is_active, 1) = 1");
+    if low_only {
+        query.push(
+            " AND stock_quantity IS NOT NULL AND low_stock_threshold IS NOT NULL \
+             AND stock_quantity <= low_stock_threshold",
+        );
+    }
+    query.push(" ORDER BY name COLLATE NOCASE");
+
+    query
+        .build_query_as::<StockLevel>()
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to list stock levels: {}", e)))
+}
+
+// Add or remove stock by hand (deliveries, damage, stocktake corrections)
+#[tauri::command]
+pub async fn adjust_stock(
+    app: AppHandle,
+    db: State<'_, Database>,
+    product_id: i64,
+    change: i64,
+    reason: Option<String>,
+    note: Option<String>,
+) -> Result<StockLevel, AppError> {
+    if change == 0 {
+        return Err(AppError::Validation(
+            "Stock change must not be zero".to_string(),
+        ));
+    }
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let level = record_movement(
+        &mut tx,
+        product_id,
+        change,
+        reason.as_deref().unwrap_or("adjustment"),
+        None,
+        note.as_deref(),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save stock change: {}", e))?;
+
+    if level.is_low {
+        emit_low_stock(&app, std::slice::from_ref(&level));
+    }
+    Ok(level)
+}
+
+// Set or clear (None) the level at which a product counts as low on stock
+#[tauri::command]
+pub async fn set_low_stock_threshold(
+    db: State<'_, Database>,
+    product_id: i64,
+    threshold: Option<i64>,
+) -> Result<StockLevel, AppError> {
+    if threshold.is_some_and(|t| t < 0) {
+        return Err(AppError::Validation(
+            "Low-stock threshold must not be negative".to_string(),
+        ));
+    }
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_stock_level(&mut tx, product_id).await?;
+
+    sqlx::query("UPDATE products SET low_stock_threshold = ? WHERE id = ?")
+        .bind(threshold)
+        .bind(product_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to update low-stock threshold: {}", e))?;
+
+    let after = load_stock_level(&mut tx, product_id).await?;
+    audit::record(
+        &mut *tx,
+        "product",
+        product_id,
+        "set_low_stock_threshold",
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save low-stock threshold: {}", e))?;
+
+    Ok(after)
+}
+
+#[tauri::command]
+pub async fn get_stock_levels(
+    db: State<'_, Database>,
+    low_only: Option<bool>,
+) -> Result<Vec<StockLevel>, AppError> {
+    list_stock_levels(&db.pool, low_only.unwrap_or(false)).await
+}
+
+// Stock history, newest first, optionally for a single product
+#[tauri::command]
+pub async fn get_stock_movements(
+    db: State<'_, Database>,
+    product_id: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Vec<StockMovement>, AppError> {
+    let mut query = QueryBuilder::<Sqlite>::new(
+        "SELECT id, product_id, change, quantity_after, reason, preorder_id, note, created_at \
+         FROM stock_movements WHERE 1 = 1",
+    );
+    if let Some(product_id) = product_id {
+        query.push(" AND product_id = ").push_bind(product_id);
+    }
+    query
+        .push(" ORDER BY id DESC LIMIT ")
+        .push_bind(limit.unwrap_or(200).clamp(1, 1000));
+
+    query
+        .build_query_as::<StockMovement>()
+        .fetch_all(&db.pool)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to list stock movements: {}", e)))
+}
@@ -0,0 +1,216 @@
+use chrono::{Datelike, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteConnection;
+use tauri::State;
+
+use crate::audit;
+use crate::db::Database;
+use crate::error::AppError;
+
+pub const DEFAULT_PATTERN: &str = "INV-{YYYY}{MM}-{seq:4}";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvoiceNumbering {
+    pub pattern: String,
+    // What the next invoice issued today would be numbered
+    pub next_number: String,
+}
+
+// Expand a numbering pattern. Supported tokens: {YYYY}, {YY}, {MM}, {DD},
+// {seq} and {seq:N} (zero-padded to N digits). The counter restarts every
+// year, so a pattern needs a year token as well as a counter.
+pub fn format_invoice_number(pattern: &str, date: NaiveDate, seq: i64) -> Result<String, AppError> {
+    let mut output = String::new();
+    let mut has_seq = false;
+    let mut has_year = false;
+    let mut rest = pattern;
+
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .map(|offset| start + offset)
+            .ok_or_else(|| {
+                AppError::Validation(format!("Unclosed token in invoice pattern: {}", pattern))
+            })?;
+
+        match &rest[start + 1..end] {
+            "YYYY" => {
+                has_year = true;
+                output.push_str(&format!("{:04}", date.year()));
+            }
+            "YY" => {
+                has_year = true;
+                output.push_str(&format!("{:02}", date.year() % 100));
+            }
+            "MM" => output.push_str(&format!("{:02}", date.month())),
+            "DD" => output.push_str(&format!("{:02}", date.day())),
+            "seq" => {
+                has_seq = true;
+                output.push_str(&seq.to_string());
+            }
+            token => {
+                let width = token
+                    .strip_prefix("seq:")
+                    .and_then(|w| w.parse::<usize>().ok())
+                    .filter(|w| (1..=12).contains(w))
+                    .ok_or_else(|| {
+                        AppError::Validation(format!(
+                            "Unknown invoice pattern token: {{{}}}",
+                            token
+                        ))
+                    })?;
+                has_seq = true;
+                output.push_str(&format!("{:0width$}", seq, width = width));
+            }
+        }
+        rest = &rest[end + 1..];
+    }
+    output.push_str(rest);
+
+    // Without the counter every invoice in a year would get the same number
+    if !has_seq {
+        return Err(AppError::Validation(
+            "Invoice pattern must contain a {seq} token".to_string(),
+        ));
+    }
+    // ... and without the year, numbers would repeat once it restarts
+    if !has_year {
+        return Err(AppError::Validation(
+            "Invoice pattern must contain a {YYYY} or {YY} token".to_string(),
+        ));
+    }
+    Ok(output)
+}
+
+// Helper: Read the configured numbering pattern
+async fn load_pattern(conn: &mut SqliteConnection) -> Result<String, AppError> {
+    let pattern =
+        sqlx::query_scalar::<_, String>("SELECT number_pattern FROM invoice_settings WHERE id = 1")
+            .fetch_optional(conn)
+            .await
+            .map_err(|e| format!("Failed to load invoice settings: {}", e))?;
+    Ok(pattern.unwrap_or_else(|| DEFAULT_PATTERN.to_string()))
+}
+
+// Take the next number of the current year's sequence. Same transaction rule
+// as assign_invoice_number; deposit invoices number from here too.
+pub async fn next_invoice_number(conn: &mut SqliteConnection) -> Result<String, AppError> {
+    let pattern = load_pattern(&mut *conn).await?;
+    let today = Local::now().date_naive();
+
+    let seq = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO invoice_sequences (year, last_value) VALUES (?, 1) \
+         ON CONFLICT(year) DO UPDATE SET last_value = last_value + 1 \
+         RETURNING last_value",
+    )
+    .bind(today.year())
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to advance invoice sequence: {}", e))?;
+
+    format_invoice_number(&pattern, today, seq)
+}
+
+// Give an order the next invoice number for the current year. Must run inside
+// the caller's transaction: the counter bump and the order update commit or
+// roll back together, so a failed save never leaves a gap in the sequence.
+// Orders that already have a number keep it.
+pub async fn assign_invoice_number(
+    conn: &mut SqliteConnection,
+    order_id: i64,
+) -> Result<String, AppError> {
+    let existing = sqlx::query_scalar::<_, Option<String>>(
+        "SELECT invoice_number FROM preorders WHERE id = ?",
+    )
+    .bind(order_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load order: {}", e))?
+    .ok_or_else(|| AppError::NotFound(format!("Order {} not found", order_id)))?;
+
+    if let Some(number) = existing {
+        return Ok(number);
+    }
+
+    let number = next_invoice_number(&mut *conn).await?;
+
+    sqlx::query("UPDATE preorders SET invoice_number = ? WHERE id = ?")
+        .bind(&number)
+        .bind(order_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to save invoice number: {}", e))?;
+
+    Ok(number)
+}
+
+// Helper: Describe the current pattern and the number it would produce next
+async fn numbering_state(conn: &mut SqliteConnection) -> Result<InvoiceNumbering, AppError> {
+    let pattern = load_pattern(&mut *conn).await?;
+    let today = Local::now().date_naive();
+
+    let last =
+        sqlx::query_scalar::<_, i64>("SELECT last_value FROM invoice_sequences WHERE year = ?")
+            .bind(today.year())
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to load invoice sequence: {}", e))?
+            .unwrap_or(0);
+
+    let next_number = format_invoice_number(&pattern, today, last + 1)?;
+    Ok(InvoiceNumbering {
+        pattern,
+        next_number,
+    })
+}
+
+#[tauri::command]
+pub async fn get_invoice_numbering(db: State<'_, Database>) -> Result<InvoiceNumbering, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    numbering_state(&mut conn).await
+}
+
+// Change the numbering pattern; numbers already issued are left as they are
+#[tauri::command]
+pub async fn set_invoice_number_pattern(
+    db: State<'_, Database>,
+    pattern: String,
+) -> Result<InvoiceNumbering, AppError> {
+    let pattern = pattern.trim();
+    format_invoice_number(pattern, Local::now().date_naive(), 1)?;
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let previous = load_pattern(&mut tx).await?;
+
+    sqlx::query("INSERT OR REPLACE INTO invoice_settings (id, number_pattern) VALUES (1, ?)")
+        .bind(pattern)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to save invoice settings: {}", e))?;
+
+    audit::record(
+        &mut *tx,
+        "invoice_settings",
+        1,
+        "update",
+        Some(&previous.as_str()),
+        Some(&pattern),
+    )
+    .await?;
+
+    let numbering = numbering_state(&mut tx).await?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save invoice settings: {}", e))?;
+    Ok(numbering)
+}
@@ -0,0 +1,665 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use printpdf::image_crate::{self as image, DynamicImage};
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteConnection;
+use tauri::State;
+
+use crate::audit;
+use crate::currency::default_currency;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::i18n;
+use crate::invoices::{
+    invoice_for_order, load_invoice, product_name, render_invoice, settlement_rows, InvoiceData,
+};
+use crate::log_warning;
+use crate::models::{LineItem, PurchaseOrder};
+use crate::pdf::{parse_hex_color, PdfWriter};
+use crate::qris;
+use crate::recurring_orders::escape_html;
+use crate::settings::load_business_profile;
+
+const LAYOUT_COLUMNS: &str = "title, business_name, business_details, logo, accent_color, \
+     footer_terms, template_html, qris_payload, qris_dynamic";
+
+pub const DEFAULT_ACCENT_COLOR: &str = "#6366f1";
+
+// Logos are stored inline in the settings row, so keep them small
+const MAX_LOGO_BYTES: usize = 512 * 1024;
+
+// Built-in HTML layout, used when no custom template has been saved
+pub const DEFAULT_TEMPLATE_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+  <meta charset="utf-8">
+  <title>{{title}} {{invoice_number}}</title>
+  <style>
+    body { font-family: Arial, sans-serif; color: #222; line-height: 1.5; margin: 0; }
+    .container { max-width: 680px; margin: 0 auto; padding: 24px; border-top: 6px solid {{accent_color}}; }
+    .header td { vertical-align: top; }
+    .meta { text-align: right; }
+    .meta h1 { margin: 0; color: {{accent_color}}; letter-spacing: 2px; }
+    .muted { color: #6b7280; font-size: 13px; }
+    .items { width: 100%; border-collapse: collapse; margin: 24px 0 8px; }
+    .items th { text-align: left; font-size: 12px; color: #6b7280; border-bottom: 2px solid #e5e7eb; padding: 8px; }
+    .items td { border-bottom: 1px solid #eee; padding: 8px; }
+    .num { text-align: right; white-space: nowrap; }
+    .totals { margin-left: auto; border-collapse: collapse; }
+    .totals td { padding: 4px 8px; }
+    .totals .strong td { font-weight: bold; border-top: 1px solid #e5e7eb; }
+    .footer { margin-top: 32px; font-size: 12px; color: #6b7280; border-top: 1px solid #e5e7eb; padding-top: 12px; }
+  </style>
+</head>
+<body>
+  <div class="container">
+    <table class="header" width="100%">
+      <tr>
+        <td>
+          {{logo}}
+          <div><strong>{{business_name}}</strong></div>
+          <div class="muted">{{business_details}}</div>
+        </td>
+        <td class="meta">
+          <h1>{{title}}</h1>
+          <div><strong>{{invoice_number}}</strong></div>
+          <div class="muted">{{t.invoice.issued}} {{issued_on}}</div>
+        </td>
+      </tr>
+    </table>
+    <p>
+      <span class="muted">{{t.invoice.bill_to}}</span><br>
+      <strong>{{customer_name}}</strong><br>
+      {{customer_email}}
+    </p>
+    <p class="muted">{{t.invoice.confirmation_code}} {{confirmation_code}} {{event_name}}</p>
+    {{items_table}}
+    {{totals_table}}
+    {{payment_qr}}
+    <p>{{notes}}</p>
+    <div class="footer">{{footer_terms}}</div>
+  </div>
+</body>
+</html>
+"#;
+
+// Placeholders filled with plain text (HTML-escaped in HTML output). Labels
+// in the app's language are available as {{t.<message key>}}, e.g.
+// {{t.invoice.bill_to}}.
+const TEXT_PLACEHOLDERS: &[&str] = &[
+    "title",
+    "business_name",
+    "business_details",
+    "invoice_number",
+    "issued_on",
+    "status",
+    "customer_name",
+    "customer_email",
+    "confirmation_code",
+    "event_name",
+    "currency",
+    "subtotal",
+    "tax_name",
+    "tax",
+    "total",
+    "amount_paid",
+    "amount_credited",
+    "deposit_invoice_number",
+    "deposit_amount",
+    "balance_due",
+    "notes",
+    "accent_color",
+];
+
+// Placeholders that expand to generated markup; HTML template only
+const HTML_PLACEHOLDERS: &[&str] = &[
+    "logo",
+    "items_table",
+    "totals_table",
+    "payment_qr",
+    "footer_terms",
+];
+
+// How backend-rendered invoices look. Title, business details and footer
+// terms may use the same {{placeholders}} as the HTML template.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct InvoiceTemplate {
+    pub title: String,
+    pub business_name: Option<String>,
+    // Address, tax ID, bank details; one item per line
+    pub business_details: Option<String>,
+    // data:image/png;base64,... (PNG or JPEG)
+    pub logo: Option<String>,
+    // "#rrggbb"
+    pub accent_color: String,
+    pub footer_terms: Option<String>,
+    // Full HTML document with {{placeholders}}; None uses the built-in layout
+    pub template_html: Option<String>,
+    // Merchant's static QRIS payload; rupiah invoices with a balance print it
+    pub qris_payload: Option<String>,
+    // Put the balance due into the printed code
+    pub qris_dynamic: bool,
+}
+
+impl Default for InvoiceTemplate {
+    fn default() -> Self {
+        InvoiceTemplate {
+            title: "Invoice".to_string(),
+            business_name: None,
+            business_details: None,
+            logo: None,
+            accent_color: DEFAULT_ACCENT_COLOR.to_string(),
+            footer_terms: None,
+            template_html: None,
+            qris_payload: None,
+            qris_dynamic: true,
+        }
+    }
+}
+
+// A template ready to draw with: logo decoded and accent color parsed once
+// per export rather than once per invoice
+pub struct InvoiceStyle {
+    pub template: InvoiceTemplate,
+    pub logo: Option<DynamicImage>,
+    pub accent: (f32, f32, f32),
+}
+
+impl InvoiceStyle {
+    pub fn prepare(template: InvoiceTemplate) -> Result<Self, AppError> {
+        let accent = parse_hex_color(&template.accent_color).ok_or_else(|| {
+            AppError::Validation(format!("Invalid accent color: {}", template.accent_color))
+        })?;
+        let logo = match &template.logo {
+            Some(data_url) => Some(decode_logo(data_url)?),
+            None => None,
+        };
+        Ok(InvoiceStyle {
+            template,
+            logo,
+            accent,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvoicePreview {
+    pub html: String,
+    pub pdf_base64: String,
+    // {{names}} in the template that aren't known placeholders
+    pub unknown_placeholders: Vec<String>,
+}
+
+// Helper: Decode a data URL logo, checking it's an image printpdf can embed
+fn decode_logo(data_url: &str) -> Result<DynamicImage, AppError> {
+    let (header, data) = data_url
+        .split_once(',')
+        .filter(|(header, _)| header.starts_with("data:image/") && header.ends_with(";base64"))
+        .ok_or("Logo must be a base64 image data URL")?;
+    let bytes = STANDARD
+        .decode(data.trim())
+        .map_err(|e| format!("Invalid logo data: {}", e))?;
+    if bytes.len() > MAX_LOGO_BYTES {
+        return Err(AppError::Validation(format!(
+            "Logo is too large ({} KB); the limit is {} KB",
+            bytes.len() / 1024,
+            MAX_LOGO_BYTES / 1024
+        )));
+    }
+    image::load_from_memory(&bytes).map_err(|e| {
+        AppError::Validation(format!(
+            "Failed to read logo ({}): {}",
+            header.trim_start_matches("data:"),
+            e
+        ))
+    })
+}
+
+// Helper: Saved template, or the defaults if the row is missing
+pub async fn load_invoice_template(
+    conn: &mut SqliteConnection,
+) -> Result<InvoiceTemplate, AppError> {
+    let template = sqlx::query_as::<_, InvoiceTemplate>(&format!(
+        "SELECT {} FROM invoice_layout WHERE id = 1",
+        LAYOUT_COLUMNS
+    ))
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| format!("Failed to load invoice template: {}", e))?;
+    Ok(template.unwrap_or_default())
+}
+
+// Helper: Fill a template's missing business name and details from the
+// business profile
+pub async fn with_business_profile(
+    conn: &mut SqliteConnection,
+    mut template: InvoiceTemplate,
+) -> Result<InvoiceTemplate, AppError> {
+    if template.business_name.is_some() && template.business_details.is_some() {
+        return Ok(template);
+    }
+    let profile = load_business_profile(&mut *conn).await?;
+    if template.business_name.is_none() && !profile.name.is_empty() {
+        template.business_name = Some(profile.name.clone());
+    }
+    if template.business_details.is_none() {
+        template.business_details = profile.details();
+    }
+    Ok(template)
+}
+
+// Helper: The saved template as invoices print it
+pub async fn load_print_template(conn: &mut SqliteConnection) -> Result<InvoiceTemplate, AppError> {
+    let template = load_invoice_template(&mut *conn).await?;
+    with_business_profile(conn, template).await
+}
+
+// Helper: Escape multi-line text for HTML, keeping its line breaks
+fn escape_lines(text: &str) -> String {
+    escape_html(text).replace('\n', "<br>")
+}
+
+// Replace {{name}} placeholders using `lookup`. Unknown names are left in
+// place and returned so the editor can flag them.
+pub fn fill_placeholders(
+    text: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> (String, Vec<String>) {
+    let mut output = String::with_capacity(text.len());
+    let mut unknown = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let Some(end) = rest[start + 2..].find("}}").map(|i| start + 2 + i) else {
+            break;
+        };
+        let name = rest[start + 2..end].trim();
+        match lookup(name) {
+            Some(value) => output.push_str(&value),
+            None => {
+                output.push_str(&rest[start..end + 2]);
+                if !unknown.iter().any(|u| u == name) {
+                    unknown.push(name.to_string());
+                }
+            }
+        }
+        rest = &rest[end + 2..];
+    }
+    output.push_str(rest);
+    (output, unknown)
+}
+
+// Plain-text value of a placeholder for one invoice
+pub fn text_value(template: &InvoiceTemplate, invoice: &InvoiceData, name: &str) -> Option<String> {
+    let order = &invoice.order;
+    let money = |amount: f64| invoice.money.format(amount);
+    let value = match name {
+        "title" => template.title.clone(),
+        "business_name" => template.business_name.clone().unwrap_or_default(),
+        "business_details" => template.business_details.clone().unwrap_or_default(),
+        "invoice_number" => order.invoice_number.clone().unwrap_or_default(),
+        "issued_on" => invoice.issued_on.clone(),
+        "status" => order.status.clone(),
+        "customer_name" => order.customer_name.clone(),
+        "customer_email" => order.customer_email.clone(),
+        "confirmation_code" => order.confirmation_code.clone(),
+        "event_name" => invoice.event_name.clone().unwrap_or_default(),
+        "currency" => invoice.currency_code.clone(),
+        "subtotal" => money(invoice.totals.subtotal),
+        "tax_name" => invoice.totals.tax_name.clone(),
+        "tax" => money(invoice.totals.tax),
+        "total" => money(invoice.totals.total),
+        "amount_paid" => money(order.amount_paid),
+        "amount_credited" => money(order.amount_credited),
+        "deposit_invoice_number" => order.deposit_invoice_number.clone().unwrap_or_default(),
+        "deposit_amount" => order.deposit_amount.map(money).unwrap_or_default(),
+        "balance_due" => money(invoice.balance_due),
+        "notes" => order.notes.clone().unwrap_or_default(),
+        "accent_color" => template.accent_color.clone(),
+        _ => {
+            let key = name
+                .strip_prefix("t.")
+                .filter(|key| i18n::has_message(key))?;
+            invoice.locale.text(key)
+        }
+    };
+    Some(value)
+}
+
+// Helper: A template text field (title, footer terms) with its placeholders filled
+fn fill_text_field(template: &InvoiceTemplate, invoice: &InvoiceData, text: &str) -> String {
+    fill_placeholders(text, |name| text_value(template, invoice, name)).0
+}
+
+// Title, business details and footer terms as printed on a given invoice
+pub fn resolved_text(
+    template: &InvoiceTemplate,
+    invoice: &InvoiceData,
+) -> (String, Option<String>, Option<String>) {
+    (
+        fill_text_field(template, invoice, &template.title),
+        template
+            .business_details
+            .as_deref()
+            .map(|text| fill_text_field(template, invoice, text)),
+        template
+            .footer_terms
+            .as_deref()
+            .map(|text| fill_text_field(template, invoice, text)),
+    )
+}
+
+// Helper: Item rows as an HTML table
+fn items_table_html(invoice: &InvoiceData) -> String {
+    let label = |key: &str| escape_html(&invoice.locale.text(key));
+    let money = |amount: f64| escape_html(&invoice.money.format(amount));
+    let rows: String = invoice
+        .order
+        .items
+        .iter()
+        .zip(&invoice.totals.lines)
+        .map(|(item, line)| {
+            let name = product_name(invoice.locale, item);
+            format!(
+                "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+                escape_html(&name),
+                item.quantity,
+                money(item.unit_price),
+                money(line.gross)
+            )
+        })
+        .collect();
+    format!(
+        "<table class=\"items\"><thead><tr><th>{}</th><th class=\"num\">{}</th>\
+         <th class=\"num\">{}</th><th class=\"num\">{}</th></tr></thead>\
+         <tbody>{}</tbody></table>",
+        label("invoice.item"),
+        label("invoice.qty"),
+        label("invoice.unit_price"),
+        label("invoice.amount"),
+        rows
+    )
+}
+
+// Helper: Subtotal/tax/total (and payments) as an HTML table
+fn totals_table_html(invoice: &InvoiceData) -> String {
+    let money = |amount: f64| escape_html(&invoice.money.format(amount));
+    let row = |label: &str, amount: f64, strong: bool| {
+        format!(
+            "<tr{}><td>{}</td><td class=\"num\">{}</td></tr>",
+            if strong { " class=\"strong\"" } else { "" },
+            escape_html(label),
+            money(amount)
+        )
+    };
+    let label = |key: &str| invoice.locale.text(key);
+    let totals = &invoice.totals;
+    let mut rows = row(&label("invoice.subtotal"), totals.subtotal, false);
+    if totals.tax > 0.0 {
+        rows.push_str(&row(&totals.tax_name, totals.tax, false));
+    }
+    rows.push_str(&row(&label("invoice.total"), totals.total, true));
+    for (label, amount, strong) in settlement_rows(invoice) {
+        rows.push_str(&row(&label, amount, strong));
+    }
+    format!("<table class=\"totals\">{}</table>", rows)
+}
+
+// Helper: QRIS code to pay the balance by, as an inline image (empty when the
+// invoice doesn't carry one)
+fn payment_qr_html(template: &InvoiceTemplate, invoice: &InvoiceData) -> String {
+    let Some(payload) = qris::invoice_payload(template, invoice) else {
+        return String::new();
+    };
+    let image = match qris::qr_data_url(&payload) {
+        Ok(image) => image,
+        Err(e) => {
+            log_warning!("Failed to draw QRIS code: {}", e);
+            return String::new();
+        }
+    };
+    let caption = match qris::payload_amount(&payload) {
+        Some(amount) => invoice.money.format(amount),
+        None => invoice.locale.format(
+            "invoice.qris_enter_amount",
+            &[("amount", &invoice.money.format(invoice.balance_due))],
+        ),
+    };
+    format!(
+        "<div style=\"margin-top: 16px;\"><div class=\"muted\">{}</div>\
+         <img src=\"{}\" alt=\"QRIS\" width=\"180\" height=\"180\"><div>{}</div></div>",
+        escape_html(&invoice.locale.text("invoice.scan_to_pay")),
+        image,
+        escape_html(&caption)
+    )
+}
+
+// Render the HTML invoice from the template. Returns the document and any
+// unknown placeholders it contains.
+pub fn render_invoice_html(
+    template: &InvoiceTemplate,
+    invoice: &InvoiceData,
+) -> (String, Vec<String>) {
+    let (title, business_details, footer_terms) = resolved_text(template, invoice);
+    let source = template
+        .template_html
+        .as_deref()
+        .unwrap_or(DEFAULT_TEMPLATE_HTML);
+    fill_placeholders(source, |name| match name {
+        "logo" => Some(
+            template
+                .logo
+                .as_deref()
+                .map(|src| {
+                    format!(
+                        "<img src=\"{}\" alt=\"\" style=\"max-height: 64px; max-width: 220px;\">",
+                        escape_html(src)
+                    )
+                })
+                .unwrap_or_default(),
+        ),
+        "items_table" => Some(items_table_html(invoice)),
+        "totals_table" => Some(totals_table_html(invoice)),
+        "payment_qr" => Some(payment_qr_html(template, invoice)),
+        "footer_terms" => Some(escape_lines(footer_terms.as_deref().unwrap_or_default())),
+        "title" => Some(escape_html(&title)),
+        "business_details" => Some(escape_lines(
+            business_details.as_deref().unwrap_or_default(),
+        )),
+        _ => text_value(template, invoice, name).map(|value| escape_lines(&value)),
+    })
+}
+
+// Helper: Placeholders in the template's fields that nothing fills
+fn unknown_placeholders(template: &InvoiceTemplate) -> Vec<String> {
+    let known = |name: &str| {
+        TEXT_PLACEHOLDERS.contains(&name)
+            || HTML_PLACEHOLDERS.contains(&name)
+            || name.strip_prefix("t.").is_some_and(i18n::has_message)
+    };
+    let mut unknown: Vec<String> = Vec::new();
+    let fields = [
+        Some(template.title.as_str()),
+        template.business_details.as_deref(),
+        template.footer_terms.as_deref(),
+        template.template_html.as_deref(),
+    ];
+    for text in fields.into_iter().flatten() {
+        let (_, names) = fill_placeholders(text, |name| known(name).then(String::new));
+        for name in names {
+            if !unknown.contains(&name) {
+                unknown.push(name);
+            }
+        }
+    }
+    unknown
+}
+
+// Helper: A made-up invoice for previewing a template before any orders exist
+async fn sample_invoice(conn: &mut SqliteConnection) -> Result<InvoiceData, AppError> {
+    let currency = default_currency(&mut *conn).await;
+    let item = |id: i64, name: &str, quantity: i64, unit_price: f64| LineItem {
+        id,
+        preorder_id: 0,
+        product_id: id,
+        product_name: Some(name.to_string()),
+        quantity,
+        unit_price,
+    };
+    let today = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let order = PurchaseOrder {
+        id: 0,
+        customer_name: "Jane Customer".to_string(),
+        customer_email: "jane@example.com".to_string(),
+        confirmation_code: "ABC123".to_string(),
+        invoice_number: Some("INV-0001".to_string()),
+        currency_code: Some(currency),
+        status: "invoiced".to_string(),
+        total_amount: 0.0,
+        amount_paid: 0.0,
+        amount_credited: 0.0,
+        deposit_percent: None,
+        deposit_amount: None,
+        deposit_invoice_number: None,
+        notes: Some("Sample order for previewing the invoice layout".to_string()),
+        event_id: None,
+        recurring_order_id: None,
+        created_at: Some(today.clone()),
+        confirmed_at: Some(today.clone()),
+        deposit_invoiced_at: None,
+        deposit_paid_at: None,
+        invoiced_at: Some(today),
+        paid_at: None,
+        fulfilled_at: None,
+        cancelled_at: None,
+        deleted_at: None,
+        version: 1,
+        items: vec![
+            item(1, "Sample product", 2, 25.0),
+            item(2, "Another product with a longer name", 1, 40.0),
+        ],
+    };
+    invoice_for_order(conn, order).await
+}
+
+#[tauri::command]
+pub async fn get_invoice_template(db: State<'_, Database>) -> Result<InvoiceTemplate, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    load_invoice_template(&mut conn).await
+}
+
+// Save the invoice layout. The logo and color must be usable and every
+// placeholder known, so exports never fail on a bad template.
+#[tauri::command]
+pub async fn save_invoice_template(
+    db: State<'_, Database>,
+    template: InvoiceTemplate,
+) -> Result<InvoiceTemplate, AppError> {
+    let mut template = template;
+    template.qris_payload = match template.qris_payload.as_deref().map(str::trim) {
+        Some(payload) if !payload.is_empty() => Some(qris::validate_payload(payload)?),
+        _ => None,
+    };
+    InvoiceStyle::prepare(template.clone())?;
+    let unknown = unknown_placeholders(&template);
+    if !unknown.is_empty() {
+        return Err(AppError::Validation(format!(
+            "Unknown placeholders: {}",
+            unknown
+                .iter()
+                .map(|name| format!("{{{{{}}}}}", name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )));
+    }
+    let blank_to_none = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    };
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_invoice_template(&mut tx).await?;
+
+    sqlx::query(
+        "INSERT OR REPLACE INTO invoice_layout \
+         (id, title, business_name, business_details, logo, accent_color, footer_terms, \
+         template_html, qris_payload, qris_dynamic, updated_at) \
+         VALUES (1, ?, ?, ?, ?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP)",
+    )
+    .bind(template.title.trim())
+    .bind(blank_to_none(&template.business_name))
+    .bind(blank_to_none(&template.business_details))
+    .bind(blank_to_none(&template.logo))
+    .bind(template.accent_color.trim())
+    .bind(blank_to_none(&template.footer_terms))
+    .bind(blank_to_none(&template.template_html))
+    .bind(&template.qris_payload)
+    .bind(template.qris_dynamic)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save invoice template: {}", e))?;
+
+    let after = load_invoice_template(&mut tx).await?;
+    audit::record(
+        &mut *tx,
+        "invoice_layout",
+        1,
+        "update",
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save invoice template: {}", e))?;
+
+    Ok(after)
+}
+
+// Render a template (the saved one unless an edited copy is passed) as HTML
+// and PDF, for a real order or a sample one
+#[tauri::command]
+pub async fn preview_invoice_template(
+    db: State<'_, Database>,
+    template: Option<InvoiceTemplate>,
+    order_id: Option<i64>,
+) -> Result<InvoicePreview, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let template = match template {
+        Some(template) => with_business_profile(&mut conn, template).await?,
+        None => load_print_template(&mut conn).await?,
+    };
+    let invoice = match order_id {
+        Some(id) => load_invoice(&mut conn, id).await?,
+        None => sample_invoice(&mut conn).await?,
+    };
+    drop(conn);
+
+    let unknown = unknown_placeholders(&template);
+    let (html, _) = render_invoice_html(&template, &invoice);
+    let style = InvoiceStyle::prepare(template)?;
+    let mut pdf = PdfWriter::new(&invoice.locale.text("invoice.preview"))?;
+    render_invoice(&mut pdf, &style, &invoice);
+
+    Ok(InvoicePreview {
+        html,
+        pdf_base64: STANDARD.encode(pdf.finish()?),
+        unknown_placeholders: unknown,
+    })
+}
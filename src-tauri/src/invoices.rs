@@ -0,0 +1,593 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, State};
+
+use crate::bulk_orders::{matching_order_ids, OrderFilter};
+use crate::currency::{currency_decimals, default_currency};
+use crate::db::Database;
+use crate::error::AppError;
+use crate::i18n::{load_locale, Locale};
+use crate::invoice_template::{load_print_template, resolved_text, InvoiceStyle};
+use crate::log_warning;
+use crate::models::{LineItem, PurchaseOrder};
+use crate::money::{number_locale, MoneyFormat};
+use crate::orders::fetch_order;
+use crate::payments::balance_epsilon;
+use crate::pdf::{
+    safe_file_name, save_pdf, wrap_text, PdfWriter, MARGIN, MUTED_COLOR, PAGE_WIDTH, RULE_COLOR,
+    TEXT_COLOR,
+};
+use crate::qris;
+use crate::tasks::{spawn_task, TaskHandle, TaskInfo};
+use crate::totals::{
+    calculate_totals, load_tax_settings, OrderTotals, TaxMode, TotalsInput, TotalsLine,
+};
+
+// Right edges of the item table's number columns, in mm
+pub const QTY_RIGHT: f32 = 125.0;
+pub const PRICE_RIGHT: f32 = 158.0;
+pub const AMOUNT_RIGHT: f32 = PAGE_WIDTH - MARGIN;
+// Printed QRIS codes; phones read them comfortably from paper or a screen
+const QR_SIZE: f32 = 34.0;
+
+// Everything printed on one invoice
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvoiceData {
+    pub order: PurchaseOrder,
+    pub event_name: Option<String>,
+    pub currency_code: String,
+    // Language the invoice is printed in
+    pub locale: Locale,
+    // How amounts are written: currency symbol and the app's number locale
+    pub money: MoneyFormat,
+    // Date the invoice was issued (YYYY-MM-DD)
+    pub issued_on: String,
+    pub totals: OrderTotals,
+    pub balance_due: f64,
+    // QRIS payload of a pending gateway charge for the balance, printed in
+    // place of the merchant's own code
+    pub gateway_qris: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvoiceExportInfo {
+    pub dest_dir: String,
+    // One PDF per invoice, named after the invoice number
+    pub files: Vec<String>,
+    pub merged_path: Option<String>,
+    // Matching orders that haven't been invoiced yet, so have no invoice number
+    pub skipped_order_ids: Vec<i64>,
+}
+
+// Helper: Date part of a SQLite timestamp
+pub fn date_part(timestamp: &str) -> String {
+    timestamp
+        .split([' ', 'T'])
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+// Helper: An order with its totals worked out the same way order_total does
+pub async fn load_invoice(
+    conn: &mut SqliteConnection,
+    order_id: i64,
+) -> Result<InvoiceData, AppError> {
+    let order = fetch_order(&mut *conn, order_id).await?;
+    invoice_for_order(conn, order).await
+}
+
+// Helper: Invoice data for an order that's already loaded (or made up, for previews)
+pub async fn invoice_for_order(
+    conn: &mut SqliteConnection,
+    order: PurchaseOrder,
+) -> Result<InvoiceData, AppError> {
+    let event_name = match order.event_id {
+        Some(event_id) => sqlx::query_scalar::<_, String>("SELECT name FROM events WHERE id = ?")
+            .bind(event_id)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to load event: {}", e))?,
+        None => None,
+    };
+    let currency_code = match &order.currency_code {
+        Some(code) => code.clone(),
+        None => default_currency(&mut *conn).await,
+    };
+    let locale = load_locale(&mut *conn).await;
+    let money = MoneyFormat::new(&currency_code, &number_locale(&mut *conn).await);
+
+    let mut settings = load_tax_settings(&mut *conn).await?;
+    settings.decimal_places = currency_decimals(&currency_code);
+    let totals = calculate_totals(
+        &TotalsInput {
+            items: order
+                .items
+                .iter()
+                .map(|item| TotalsLine {
+                    description: item.product_name.clone(),
+                    quantity: item.quantity as f64,
+                    unit_price: item.unit_price,
+                    discount: None,
+                    tax_rate: None,
+                })
+                .collect(),
+            order_discount: None,
+            settings: None,
+        },
+        &settings,
+    )?;
+
+    let issued_on = order
+        .invoiced_at
+        .as_deref()
+        .or(order.created_at.as_deref())
+        .map(date_part)
+        .unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d").to_string());
+    let balance_due = (totals.total - order.amount_credited - order.amount_paid).max(0.0);
+    let gateway_qris = sqlx::query_scalar::<_, String>(
+        "SELECT payment_code FROM payment_links WHERE preorder_id = ? AND status = 'open' \
+         AND method = 'qris' AND payment_code IS NOT NULL AND ABS(amount - ?) < ? \
+         AND (expires_at IS NULL OR expires_at > CURRENT_TIMESTAMP) \
+         ORDER BY created_at DESC, id DESC LIMIT 1",
+    )
+    .bind(order.id)
+    .bind(balance_due)
+    .bind(balance_epsilon(&currency_code))
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load payment links: {}", e))?;
+
+    Ok(InvoiceData {
+        order,
+        event_name,
+        currency_code,
+        locale,
+        money,
+        issued_on,
+        totals,
+        balance_due,
+        gateway_qris,
+    })
+}
+
+// Helper: Line item name, for items whose product has since been deleted too
+pub fn product_name(locale: Locale, item: &LineItem) -> String {
+    item.product_name.clone().unwrap_or_else(|| {
+        locale.format("invoice.product", &[("id", &item.product_id.to_string())])
+    })
+}
+
+// Helper: Column headings of the item table, repeated on continuation pages
+pub fn item_table_header(pdf: &mut PdfWriter, locale: Locale) {
+    let label = |key: &str| locale.text(key);
+    pdf.text(MARGIN, 9.0, true, MUTED_COLOR, &label("invoice.item"));
+    pdf.text_right(QTY_RIGHT, 9.0, true, MUTED_COLOR, &label("invoice.qty"));
+    pdf.text_right(
+        PRICE_RIGHT,
+        9.0,
+        true,
+        MUTED_COLOR,
+        &label("invoice.unit_price"),
+    );
+    pdf.text_right(
+        AMOUNT_RIGHT,
+        9.0,
+        true,
+        MUTED_COLOR,
+        &label("invoice.amount"),
+    );
+    pdf.advance(2.5);
+    pdf.rule(RULE_COLOR, 0.8);
+    pdf.advance(5.5);
+}
+
+// Helper: Rows after the invoice total: credits, the deposit and other
+// payments received, and what's left to pay. Empty while nothing's been
+// paid or credited.
+pub fn settlement_rows(invoice: &InvoiceData) -> Vec<(String, f64, bool)> {
+    let order = &invoice.order;
+    let locale = invoice.locale;
+    let mut rows = Vec::new();
+    if order.amount_credited > 0.0 {
+        rows.push((
+            locale.text("invoice.credited"),
+            -order.amount_credited,
+            false,
+        ));
+    }
+    let deposit = match (&order.deposit_invoice_number, order.deposit_amount) {
+        (Some(number), Some(amount)) if order.amount_paid > 0.0 => {
+            Some((number, order.amount_paid.min(amount)))
+        }
+        _ => None,
+    };
+    let paid = order.amount_paid - deposit.map_or(0.0, |(_, amount)| amount);
+    if let Some((number, amount)) = deposit {
+        rows.push((
+            locale.format("invoice.deposit_paid", &[("number", number)]),
+            amount,
+            false,
+        ));
+    }
+    if paid > 0.0 || (deposit.is_none() && order.amount_credited > 0.0) {
+        rows.push((locale.text("invoice.paid"), paid, false));
+    }
+    if !rows.is_empty() {
+        rows.push((
+            locale.text("invoice.balance_due"),
+            invoice.balance_due,
+            true,
+        ));
+    }
+    rows
+}
+
+// Helper: Start a document on a fresh page with the template's letterhead.
+// Left: logo, business name and details. Right: title and number, leaving the
+// cursor below the number for the caller's dates. Returns where the left
+// column ends.
+pub fn letterhead(
+    pdf: &mut PdfWriter,
+    style: &InvoiceStyle,
+    business_details: Option<&str>,
+    title: &str,
+    number: &str,
+) -> f32 {
+    pdf.start_section();
+    pdf.band(MARGIN, PAGE_WIDTH - 2.0 * MARGIN, 1.5, style.accent);
+    pdf.advance(6.0);
+
+    let top = pdf.y;
+    if let Some(logo) = &style.logo {
+        let height = pdf.image(logo, MARGIN, 60.0, 18.0);
+        pdf.advance(height + 5.0);
+    } else {
+        pdf.advance(4.0);
+    }
+    if let Some(name) = style.template.business_name.as_deref() {
+        pdf.text(MARGIN, 11.0, true, TEXT_COLOR, name);
+        pdf.advance(4.5);
+    }
+    for line in business_details
+        .iter()
+        .flat_map(|details| wrap_text(details, 95.0, 9.0, false))
+    {
+        pdf.text(MARGIN, 9.0, false, MUTED_COLOR, &line);
+        pdf.advance(4.2);
+    }
+    let left_bottom = pdf.y;
+
+    pdf.y = top - 8.0;
+    pdf.text_right(
+        AMOUNT_RIGHT,
+        22.0,
+        true,
+        style.accent,
+        &title.to_uppercase(),
+    );
+    pdf.advance(7.0);
+    pdf.text_right(AMOUNT_RIGHT, 11.0, true, TEXT_COLOR, number);
+    pdf.advance(5.0);
+    left_bottom
+}
+
+// Helper: QRIS code to pay the balance by, with the merchant and the amount
+// to enter when the code doesn't carry one
+fn payment_qr(pdf: &mut PdfWriter, invoice: &InvoiceData, payload: &str) {
+    let (width, modules) = match qris::qr_modules(payload) {
+        Ok(encoded) => encoded,
+        Err(e) => {
+            log_warning!("Failed to draw QRIS code: {}", e);
+            return;
+        }
+    };
+    let locale = invoice.locale;
+    pdf.ensure_space(QR_SIZE + 16.0);
+    pdf.advance(4.0);
+    pdf.text(
+        MARGIN,
+        9.0,
+        true,
+        MUTED_COLOR,
+        &locale.text("invoice.scan_to_pay"),
+    );
+    // Room for the quiet zone scanners need around the code
+    pdf.advance(6.0);
+
+    let top = pdf.y;
+    let module = QR_SIZE / width as f32;
+    for (row, cells) in modules.chunks(width).enumerate() {
+        pdf.y = top - (row + 1) as f32 * module;
+        let mut column = 0;
+        while column < width {
+            if !cells[column] {
+                column += 1;
+                continue;
+            }
+            let start = column;
+            while column < width && cells[column] {
+                column += 1;
+            }
+            pdf.band(
+                MARGIN + start as f32 * module,
+                (column - start) as f32 * module,
+                module,
+                TEXT_COLOR,
+            );
+        }
+    }
+
+    let caption_x = MARGIN + QR_SIZE + 6.0;
+    pdf.y = top - 5.0;
+    if let Some(name) = qris::merchant_name(payload) {
+        pdf.text(caption_x, 10.0, true, TEXT_COLOR, &name);
+        pdf.advance(5.0);
+    }
+    let caption = match qris::payload_amount(payload) {
+        Some(amount) => invoice.money.format(amount),
+        None => locale.format(
+            "invoice.qris_enter_amount",
+            &[("amount", &invoice.money.format(invoice.balance_due))],
+        ),
+    };
+    pdf.text(caption_x, 10.0, false, TEXT_COLOR, &caption);
+    pdf.y = top - QR_SIZE;
+    pdf.advance(6.0);
+}
+
+// Draw one invoice starting on a fresh page, laid out by the invoice template
+pub fn render_invoice(pdf: &mut PdfWriter, style: &InvoiceStyle, invoice: &InvoiceData) {
+    let order = &invoice.order;
+    let locale = invoice.locale;
+    let label = |key: &str| locale.text(key);
+    let money = |amount: f64| invoice.money.format(amount);
+    let (title, business_details, footer_terms) = resolved_text(&style.template, invoice);
+
+    let number = order
+        .invoice_number
+        .clone()
+        .unwrap_or_else(|| label("invoice.draft"));
+    let left_bottom = letterhead(pdf, style, business_details.as_deref(), &title, &number);
+    pdf.text_right(
+        AMOUNT_RIGHT,
+        9.0,
+        false,
+        MUTED_COLOR,
+        &format!("{} {}", label("invoice.issued"), invoice.issued_on),
+    );
+    if order.status == "cancelled" {
+        pdf.advance(5.0);
+        pdf.text_right(
+            AMOUNT_RIGHT,
+            10.0,
+            true,
+            (0.8, 0.15, 0.15),
+            &label("invoice.cancelled"),
+        );
+    }
+    pdf.y = pdf.y.min(left_bottom);
+    pdf.advance(10.0);
+
+    pdf.text(MARGIN, 9.0, true, MUTED_COLOR, &label("invoice.bill_to"));
+    pdf.text(120.0, 9.0, true, MUTED_COLOR, &label("invoice.order"));
+    pdf.advance(5.5);
+    pdf.text(MARGIN, 11.0, true, TEXT_COLOR, &order.customer_name);
+    pdf.text(
+        120.0,
+        10.0,
+        false,
+        TEXT_COLOR,
+        &format!(
+            "{} {}",
+            label("invoice.confirmation_code"),
+            order.confirmation_code
+        ),
+    );
+    pdf.advance(5.0);
+    pdf.text(MARGIN, 10.0, false, TEXT_COLOR, &order.customer_email);
+    if let Some(event_name) = &invoice.event_name {
+        pdf.text(120.0, 10.0, false, TEXT_COLOR, event_name);
+    }
+    pdf.advance(14.0);
+
+    item_table_header(pdf, locale);
+    for (item, line) in order.items.iter().zip(&invoice.totals.lines) {
+        let name = product_name(locale, item);
+        let name_lines = wrap_text(&name, QTY_RIGHT - MARGIN - 18.0, 10.0, false);
+        let height = name_lines.len() as f32 * 4.5 + 3.0;
+        if pdf.y - height < MARGIN {
+            pdf.new_page();
+            item_table_header(pdf, locale);
+        }
+        pdf.text_right(
+            QTY_RIGHT,
+            10.0,
+            false,
+            TEXT_COLOR,
+            &item.quantity.to_string(),
+        );
+        pdf.text_right(
+            PRICE_RIGHT,
+            10.0,
+            false,
+            TEXT_COLOR,
+            &money(item.unit_price),
+        );
+        pdf.text_right(AMOUNT_RIGHT, 10.0, false, TEXT_COLOR, &money(line.gross));
+        for (i, text) in name_lines.iter().enumerate() {
+            if i > 0 {
+                pdf.advance(4.5);
+            }
+            pdf.text(MARGIN, 10.0, false, TEXT_COLOR, text);
+        }
+        pdf.advance(2.0);
+        pdf.rule(RULE_COLOR, 0.4);
+        pdf.advance(5.5);
+    }
+
+    let totals = &invoice.totals;
+    let mut rows: Vec<(String, f64, bool)> =
+        vec![(label("invoice.subtotal"), totals.subtotal, false)];
+    if totals.tax > 0.0 {
+        let label = match totals.tax_mode {
+            TaxMode::Exclusive => totals.tax_name.clone(),
+            TaxMode::Inclusive => {
+                format!("{} ({})", totals.tax_name, label("invoice.tax_included"))
+            }
+        };
+        rows.push((label, totals.tax, false));
+    }
+    rows.push((label("invoice.total"), totals.total, true));
+    rows.extend(settlement_rows(invoice));
+    pdf.ensure_space(rows.len() as f32 * 6.0 + 4.0);
+    pdf.advance(2.0);
+    for (label, amount, bold) in rows {
+        pdf.text_right(PRICE_RIGHT, 10.0, bold, TEXT_COLOR, &label);
+        pdf.text_right(AMOUNT_RIGHT, 10.0, bold, TEXT_COLOR, &money(amount));
+        pdf.advance(6.0);
+    }
+
+    if let Some(payload) = qris::invoice_payload(&style.template, invoice) {
+        payment_qr(pdf, invoice, &payload);
+    }
+
+    if let Some(notes) = order.notes.as_deref().filter(|n| !n.trim().is_empty()) {
+        let lines = wrap_text(notes, PAGE_WIDTH - 2.0 * MARGIN, 9.0, false);
+        pdf.ensure_space(lines.len() as f32 * 4.0 + 10.0);
+        pdf.advance(6.0);
+        pdf.text(MARGIN, 9.0, true, MUTED_COLOR, &label("invoice.notes"));
+        for line in lines {
+            pdf.advance(4.5);
+            pdf.text(MARGIN, 9.0, false, TEXT_COLOR, &line);
+        }
+    }
+
+    if let Some(terms) = footer_terms.as_deref().filter(|t| !t.trim().is_empty()) {
+        let lines = wrap_text(terms, PAGE_WIDTH - 2.0 * MARGIN, 8.5, false);
+        pdf.ensure_space(lines.len() as f32 * 4.0 + 12.0);
+        pdf.advance(10.0);
+        pdf.rule(RULE_COLOR, 0.4);
+        for line in lines {
+            pdf.advance(4.5);
+            pdf.text(MARGIN, 8.5, false, MUTED_COLOR, &line);
+        }
+    }
+}
+
+// Helper: One invoice as a standalone PDF
+fn invoice_pdf(style: &InvoiceStyle, invoice: &InvoiceData) -> Result<Vec<u8>, AppError> {
+    let title = format!(
+        "{} {}",
+        style.template.title,
+        invoice.order.invoice_number.as_deref().unwrap_or_default()
+    );
+    let mut pdf = PdfWriter::new(&title)?;
+    render_invoice(&mut pdf, style, invoice);
+    pdf.finish()
+}
+
+// Helper: Path for an invoice's file, numbered if two invoice numbers clean up
+// to the same name
+fn unique_path(dir: &Path, stem: &str) -> PathBuf {
+    let mut path = dir.join(format!("{}.pdf", stem));
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("{} ({}).pdf", stem, n));
+        n += 1;
+    }
+    path
+}
+
+// Helper: Render each invoice to its own PDF, and optionally one merged PDF,
+// reporting progress on the task after each file
+async fn export_invoices(
+    pool: SqlitePool,
+    filter: OrderFilter,
+    dest_dir: String,
+    merged: bool,
+    task: TaskHandle,
+) -> Result<InvoiceExportInfo, AppError> {
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let ids = matching_order_ids(&mut conn, &filter).await?;
+
+    let mut invoices = Vec::new();
+    let mut skipped_order_ids = Vec::new();
+    for id in ids {
+        let invoice = load_invoice(&mut conn, id).await?;
+        if invoice.order.invoice_number.is_some() {
+            invoices.push(invoice);
+        } else {
+            skipped_order_ids.push(id);
+        }
+    }
+    let style = InvoiceStyle::prepare(load_print_template(&mut conn).await?)?;
+    drop(conn);
+    invoices.sort_by(|a, b| a.order.invoice_number.cmp(&b.order.invoice_number));
+
+    let dir = PathBuf::from(&dest_dir);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create export folder: {}", e))?;
+
+    let total = invoices.len() as u64;
+    task.progress(0, Some(total), "Rendering invoices");
+    let mut files = Vec::with_capacity(invoices.len());
+    for (i, invoice) in invoices.iter().enumerate() {
+        task.check_cancelled()?;
+        let number = invoice.order.invoice_number.clone().unwrap_or_default();
+        let path = unique_path(&dir, &safe_file_name(&number));
+        save_pdf(&path, &invoice_pdf(&style, invoice)?)?;
+        files.push(path.to_string_lossy().to_string());
+        task.progress(i as u64 + 1, Some(total), number);
+    }
+
+    let merged_path = if merged && !invoices.is_empty() {
+        task.check_cancelled()?;
+        let title = invoices[0].locale.text("invoice.titles");
+        let mut pdf = PdfWriter::new(&title)?;
+        for invoice in &invoices {
+            render_invoice(&mut pdf, &style, invoice);
+        }
+        let stem = format!("invoices-{}", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+        let path = unique_path(&dir, &stem);
+        save_pdf(&path, &pdf.finish()?)?;
+        Some(path.to_string_lossy().to_string())
+    } else {
+        None
+    };
+
+    Ok(InvoiceExportInfo {
+        dest_dir,
+        files,
+        merged_path,
+        skipped_order_ids,
+    })
+}
+
+// Render every invoiced order matching the filter to its own PDF in
+// `dest_dir`, plus optionally one merged PDF of all of them. Runs as a
+// background task whose result is an InvoiceExportInfo; files already written
+// stay when it's cancelled.
+#[tauri::command]
+pub async fn export_invoices_pdf(
+    app: AppHandle,
+    db: State<'_, Database>,
+    filter: Option<OrderFilter>,
+    dest_dir: String,
+    merged: Option<bool>,
+) -> Result<TaskInfo, AppError> {
+    let pool = db.pool.clone();
+    let label = format!("Export invoices to {}", dest_dir);
+    Ok(spawn_task(&app, "invoice_export", label, move |task| {
+        export_invoices(
+            pool,
+            filter.unwrap_or_default(),
+            dest_dir,
+            merged.unwrap_or(false),
+            task,
+        )
+    }))
+}
@@ -0,0 +1,359 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use printpdf::image_crate::{DynamicImage, GrayImage, ImageOutputFormat, Luma};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::path::Path;
+use tauri::State;
+
+use crate::barcodes::{barcode_format, encode, BarcodeFormat};
+use crate::db::Database;
+use crate::error::AppError;
+use crate::i18n::load_locale;
+use crate::money::money_format;
+use crate::orders::fetch_order;
+use crate::pdf::{save_pdf, text_width, wrap_text, PdfWriter, MUTED_COLOR, TEXT_COLOR};
+use crate::products::load_product;
+
+// Thermal label printers print at 203 or 300 dpi; 300 scales down cleanly
+const LABEL_DPI: f32 = 300.0;
+// White border kept clear of text and bars, in mm
+const LABEL_MARGIN: f32 = 2.0;
+// Wider bars don't scan any better and just waste the label
+const MAX_MODULE_WIDTH: f32 = 0.5;
+const MIN_BAR_HEIGHT: f32 = 5.0;
+const BAR_COLOR: (f32, f32, f32) = (0.0, 0.0, 0.0);
+const PT_TO_MM: f32 = 0.352_778;
+
+// 5x7 bitmap glyphs for the human-readable line on PNG labels (no font
+// rasterizer in the build). Rows top to bottom, bit 4 is the leftmost pixel.
+// Lowercase is drawn as uppercase; anything else as a blank.
+const GLYPHS: &[(char, [u8; 7])] = &[
+    ('0', [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E]),
+    ('1', [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E]),
+    ('2', [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F]),
+    ('3', [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E]),
+    ('4', [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02]),
+    ('5', [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E]),
+    ('6', [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E]),
+    ('7', [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08]),
+    ('8', [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E]),
+    ('9', [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C]),
+    ('A', [0x0E, 0x11, 0x11, 0x11, 0x1F, 0x11, 0x11]),
+    ('B', [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E]),
+    ('C', [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E]),
+    ('D', [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C]),
+    ('E', [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F]),
+    ('F', [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10]),
+    ('G', [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F]),
+    ('H', [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11]),
+    ('I', [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E]),
+    ('J', [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C]),
+    ('K', [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11]),
+    ('L', [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F]),
+    ('M', [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11]),
+    ('N', [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11]),
+    ('O', [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E]),
+    ('P', [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10]),
+    ('Q', [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D]),
+    ('R', [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11]),
+    ('S', [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E]),
+    ('T', [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04]),
+    ('U', [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E]),
+    ('V', [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04]),
+    ('W', [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A]),
+    ('X', [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11]),
+    ('Y', [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04]),
+    ('Z', [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F]),
+    ('-', [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00]),
+    ('#', [0x0A, 0x0A, 0x1F, 0x0A, 0x1F, 0x0A, 0x0A]),
+    ('.', [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C]),
+    ('/', [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00]),
+];
+
+// Common label stock
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LabelSize {
+    // Small thermal labels for products and bags
+    #[default]
+    #[serde(rename = "50x25")]
+    Mm50x25,
+    // Brother DK-11209 and similar address rolls
+    #[serde(rename = "62x29")]
+    Mm62x29,
+    #[serde(rename = "100x50")]
+    Mm100x50,
+    // Shipping labels
+    #[serde(rename = "4x6")]
+    In4x6,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelFormat {
+    Png,
+    Pdf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BarcodeLabel {
+    // Encoded text: the product barcode or the order's confirmation code
+    pub barcode: String,
+    pub symbology: BarcodeFormat,
+    pub mime_type: String,
+    pub data_base64: String,
+    // Where the label was saved, when a destination was given
+    pub path: Option<String>,
+}
+
+// What goes on a label
+struct LabelContent {
+    barcode: String,
+    symbology: BarcodeFormat,
+    // Product or customer name
+    title: String,
+    // Price, or order number
+    subtitle: Option<String>,
+}
+
+impl LabelSize {
+    // Width and height in mm, landscape
+    fn dimensions(self) -> (f32, f32) {
+        match self {
+            LabelSize::Mm50x25 => (50.0, 25.0),
+            LabelSize::Mm62x29 => (62.0, 29.0),
+            LabelSize::Mm100x50 => (100.0, 50.0),
+            LabelSize::In4x6 => (152.4, 101.6),
+        }
+    }
+}
+
+// Helper: Blank modules scanners need before and after the bars
+fn quiet_zone(format: BarcodeFormat) -> (usize, usize) {
+    match format {
+        BarcodeFormat::Ean13 => (11, 7),
+        BarcodeFormat::Code128 => (10, 10),
+    }
+}
+
+// Helper: (first module, length) of each bar
+fn bar_runs(modules: &[bool]) -> Vec<(usize, usize)> {
+    let mut runs = Vec::new();
+    let mut start = None;
+    for (i, &bar) in modules.iter().chain([&false]).enumerate() {
+        match (bar, start) {
+            (true, None) => start = Some(i),
+            (false, Some(first)) => {
+                runs.push((first, i - first));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    runs
+}
+
+fn render_pdf(
+    content: &LabelContent,
+    modules: &[bool],
+    (width, height): (f32, f32),
+) -> Result<Vec<u8>, AppError> {
+    let mut pdf = PdfWriter::with_page_size(&content.title, width, height)?;
+    let inner = width - 2.0 * LABEL_MARGIN;
+    let title_size = (height / 25.0 * 8.0).clamp(7.0, 16.0);
+    let small_size = title_size * 0.85;
+    let line = |size: f32| size * PT_TO_MM * 1.2;
+    let centered = |pdf: &mut PdfWriter, size: f32, bold: bool, rgb, text: &str| {
+        let text = wrap_text(text, inner, size, bold).swap_remove(0);
+        let x = (width - text_width(&text, size, bold)) / 2.0;
+        pdf.text(x, size, bold, rgb, &text);
+    };
+
+    pdf.y = height - LABEL_MARGIN - title_size * PT_TO_MM;
+    centered(&mut pdf, title_size, true, TEXT_COLOR, &content.title);
+    if let Some(subtitle) = &content.subtitle {
+        pdf.advance(line(small_size));
+        centered(&mut pdf, small_size, false, MUTED_COLOR, subtitle);
+    }
+    let bars_top = pdf.y - line(small_size) * 0.5;
+    let code_baseline = LABEL_MARGIN + 0.5;
+    let bars_bottom = code_baseline + small_size * PT_TO_MM + 0.8;
+    let bar_height = bars_top - bars_bottom;
+    if bar_height < MIN_BAR_HEIGHT {
+        return Err(AppError::Validation(
+            "The label is too small for a barcode".to_string(),
+        ));
+    }
+
+    let (before, after) = quiet_zone(content.symbology);
+    let total = (before + modules.len() + after) as f32;
+    let module = (inner / total).min(MAX_MODULE_WIDTH);
+    let left = (width - total * module) / 2.0 + before as f32 * module;
+    pdf.y = bars_bottom;
+    for (start, length) in bar_runs(modules) {
+        pdf.band(
+            left + start as f32 * module,
+            length as f32 * module,
+            bar_height,
+            BAR_COLOR,
+        );
+    }
+
+    pdf.y = code_baseline;
+    centered(&mut pdf, small_size, false, TEXT_COLOR, &content.barcode);
+    pdf.finish()
+}
+
+// Helper: Draw text in the bitmap font, centered on `center_x`, top at `top`
+fn draw_text(image: &mut GrayImage, text: &str, center_x: u32, top: u32, scale: u32) {
+    let advance = 6 * scale;
+    let width = (text.chars().count() as u32 * advance).saturating_sub(scale);
+    let mut x = center_x.saturating_sub(width / 2);
+    for c in text.chars() {
+        let glyph = GLYPHS
+            .iter()
+            .find(|(g, _)| *g == c.to_ascii_uppercase())
+            .map(|(_, rows)| rows);
+        if let Some(rows) = glyph {
+            for (row, bits) in rows.iter().enumerate() {
+                for column in 0..5u32 {
+                    if bits & (0x10 >> column) == 0 {
+                        continue;
+                    }
+                    for dy in 0..scale {
+                        for dx in 0..scale {
+                            let (px, py) = (x + column * scale + dx, top + row as u32 * scale + dy);
+                            if px < image.width() && py < image.height() {
+                                image.put_pixel(px, py, Luma([0]));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        x += advance;
+    }
+}
+
+// PNG labels carry the bars and the encoded text; names and prices need a
+// real font, so they're only on PDF labels
+fn render_png(
+    content: &LabelContent,
+    modules: &[bool],
+    (width, height): (f32, f32),
+) -> Result<Vec<u8>, AppError> {
+    let px = |mm: f32| (mm / 25.4 * LABEL_DPI).round() as u32;
+    let (width, height) = (px(width), px(height));
+    let margin = px(LABEL_MARGIN);
+    let mut image = GrayImage::from_pixel(width, height, Luma([255]));
+
+    let (before, after) = quiet_zone(content.symbology);
+    let total = (before + modules.len() + after) as u32;
+    let module = ((width - 2 * margin) / total).min(px(MAX_MODULE_WIDTH).max(1));
+    if module == 0 {
+        return Err(AppError::Validation(format!(
+            "Barcode {} is too long for this label size",
+            content.barcode
+        )));
+    }
+    let scale = (height / 70).max(1);
+    let text_top = height - margin - 7 * scale;
+    let bars_bottom = text_top - 2 * scale;
+    let left = (width - total * module) / 2 + before as u32 * module;
+    for (start, length) in bar_runs(modules) {
+        let x0 = left + start as u32 * module;
+        for x in x0..x0 + length as u32 * module {
+            for y in margin..bars_bottom {
+                image.put_pixel(x, y, Luma([0]));
+            }
+        }
+    }
+    draw_text(&mut image, &content.barcode, width / 2, text_top, scale);
+
+    let mut bytes = Vec::new();
+    DynamicImage::ImageLuma8(image)
+        .write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png)
+        .map_err(|e| format!("Failed to render PNG: {}", e))?;
+    Ok(bytes)
+}
+
+// Render a printable label for a product (its barcode, name and price) or an
+// order (its confirmation code, for scanning at pickup). Pass exactly one of
+// `product_id` and `order_id`. The image is returned and, when `dest` is
+// given, also saved there.
+#[tauri::command]
+pub async fn render_barcode_label(
+    db: State<'_, Database>,
+    product_id: Option<i64>,
+    order_id: Option<i64>,
+    format: LabelFormat,
+    size: Option<LabelSize>,
+    dest: Option<String>,
+) -> Result<BarcodeLabel, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+
+    let content = match (product_id, order_id) {
+        (Some(product_id), None) => {
+            let product = load_product(&mut *conn, product_id).await?;
+            let barcode = product.barcode.clone().ok_or_else(|| {
+                format!("Product {} has no barcode; generate one first", product_id)
+            })?;
+            let money = money_format(&mut conn, product.currency_code.as_deref()).await?;
+            LabelContent {
+                symbology: barcode_format(&barcode),
+                barcode,
+                subtitle: Some(money.format(product.price)),
+                title: product.name,
+            }
+        }
+        (None, Some(order_id)) => {
+            let order = fetch_order(&mut conn, order_id).await?;
+            let locale = load_locale(&mut *conn).await;
+            let number = match &order.invoice_number {
+                Some(invoice) => format!("#{} - {}", order.id, invoice),
+                None => format!("#{}", order.id),
+            };
+            LabelContent {
+                barcode: order.confirmation_code,
+                symbology: BarcodeFormat::Code128,
+                title: order.customer_name,
+                subtitle: Some(format!("{} {}", locale.text("invoice.order"), number)),
+            }
+        }
+        _ => {
+            return Err(AppError::Validation(
+                "Pass either a product or an order".to_string(),
+            ))
+        }
+    };
+    drop(conn);
+
+    let modules = encode(&content.barcode, content.symbology)?;
+    let dimensions = size.unwrap_or_default().dimensions();
+    let (bytes, mime_type) = match format {
+        LabelFormat::Pdf => (
+            render_pdf(&content, &modules, dimensions)?,
+            "application/pdf",
+        ),
+        LabelFormat::Png => (render_png(&content, &modules, dimensions)?, "image/png"),
+    };
+
+    let path = match dest {
+        Some(dest) => {
+            save_pdf(Path::new(&dest), &bytes)?;
+            Some(dest)
+        }
+        None => None,
+    };
+
+    Ok(BarcodeLabel {
+        barcode: content.barcode,
+        symbology: content.symbology,
+        mime_type: mime_type.to_string(),
+        data_base64: STANDARD.encode(bytes),
+        path,
+    })
+}
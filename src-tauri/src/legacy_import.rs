@@ -0,0 +1,967 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteConnection;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use tauri::State;
+
+use crate::audit;
+use crate::confirmation_codes::unique_code;
+use crate::contacts::{save_contacts, ContactRecord};
+use crate::currency::{default_currency, normalize_currency};
+use crate::db::Database;
+use crate::error::AppError;
+use crate::models::validate_contact;
+use crate::orders::{fetch_order, order_total};
+use crate::payments::balance_epsilon;
+use crate::product_import::{cell, find_column, normalize_header, read_spreadsheet_rows};
+use crate::products::load_product;
+use crate::sheets::{cell_text, fetch_sheet_values};
+
+// Payment method recorded for amounts the old spreadsheet marked as paid
+const LEGACY_PAYMENT_METHOD: &str = "legacy";
+
+// Header names tried for each field when no mapping is given, English and
+// Indonesian (compared like product_import does: lowercase letters and digits)
+const EMAIL_HEADERS: &[&str] = &["email", "emailaddress", "customeremail", "mail", "surel"];
+const NAME_HEADERS: &[&str] = &[
+    "name",
+    "customer",
+    "customername",
+    "buyer",
+    "buyername",
+    "fullname",
+    "nama",
+    "namapembeli",
+    "namapelanggan",
+    "pembeli",
+];
+const PRODUCT_HEADERS: &[&str] = &[
+    "product",
+    "productname",
+    "item",
+    "itemname",
+    "produk",
+    "namaproduk",
+    "barang",
+    "namabarang",
+];
+const QUANTITY_HEADERS: &[&str] = &["qty", "quantity", "jumlah", "jml", "pcs", "banyaknya"];
+const UNIT_PRICE_HEADERS: &[&str] = &["price", "unitprice", "priceeach", "harga", "hargasatuan"];
+const LINE_TOTAL_HEADERS: &[&str] = &[
+    "total",
+    "linetotal",
+    "subtotal",
+    "amount",
+    "totalharga",
+    "jumlahharga",
+];
+const PAID_HEADERS: &[&str] = &[
+    "paid",
+    "amountpaid",
+    "payment",
+    "paymentstatus",
+    "dibayar",
+    "bayar",
+    "pembayaran",
+    "lunas",
+    "statusbayar",
+    "sudahbayar",
+];
+const ORDER_REF_HEADERS: &[&str] = &[
+    "orderid",
+    "orderno",
+    "ordernumber",
+    "order",
+    "invoice",
+    "invoiceno",
+    "invoicenumber",
+    "nopesanan",
+    "nomorpesanan",
+    "idpesanan",
+    "noinvoice",
+];
+const DATE_HEADERS: &[&str] = &[
+    "date",
+    "orderdate",
+    "ordered",
+    "timestamp",
+    "createdat",
+    "created",
+    "tanggal",
+    "tanggalpesan",
+    "waktu",
+];
+const PHONE_HEADERS: &[&str] = &[
+    "phone",
+    "phonenumber",
+    "mobile",
+    "whatsapp",
+    "wa",
+    "telepon",
+    "notelp",
+    "nohp",
+    "hp",
+];
+const ADDRESS_HEADERS: &[&str] = &["address", "shippingaddress", "alamat", "alamatpengiriman"];
+const NOTES_HEADERS: &[&str] = &[
+    "notes",
+    "note",
+    "remarks",
+    "comments",
+    "catatan",
+    "keterangan",
+];
+
+// Shorter aliases ("wa", "hp") only match whole headers, never part of one
+const MIN_PARTIAL_ALIAS: usize = 4;
+
+// "Paid" cells that mean the whole line was (or wasn't) paid
+const PAID_WORDS: &[&str] = &[
+    "yes",
+    "y",
+    "true",
+    "paid",
+    "lunas",
+    "sudah",
+    "sudahbayar",
+    "done",
+    "ok",
+    "v",
+    "x",
+    "✓",
+    "✔",
+];
+const UNPAID_WORDS: &[&str] = &[
+    "no",
+    "n",
+    "false",
+    "unpaid",
+    "belum",
+    "belumbayar",
+    "belumlunas",
+    "pending",
+    "-",
+];
+
+const DATETIME_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%d/%m/%Y %H:%M:%S",
+    "%d/%m/%Y %H:%M",
+    "%m/%d/%Y %H:%M:%S",
+    "%m/%d/%Y %H:%M",
+    "%d-%m-%Y %H:%M:%S",
+];
+// Day-first before month-first: "03/04/2024" is read as 3 April
+const DATE_FORMATS: &[&str] = &[
+    "%Y-%m-%d",
+    "%Y/%m/%d",
+    "%d/%m/%Y",
+    "%m/%d/%Y",
+    "%d-%m-%Y",
+    "%d.%m.%Y",
+    "%d %B %Y",
+    "%d %b %Y",
+    "%B %d, %Y",
+    "%b %d, %Y",
+];
+
+// Where the old orders live
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LegacySource {
+    // CSV or Excel/ODS workbook (first worksheet)
+    File {
+        path: String,
+    },
+    GoogleSheet {
+        access_token: String,
+        spreadsheet_id: String,
+        // e.g. "Orders!A1:Z"
+        range: String,
+    },
+}
+
+// Which column (by header text) holds each field. Leave a field out to have
+// it guessed from the headers; email and product must end up mapped. The
+// report returns the mapping that was actually used.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LegacyColumnMapping {
+    pub customer_email: Option<String>,
+    pub customer_name: Option<String>,
+    pub product: Option<String>,
+    // Defaults to 1 per row
+    pub quantity: Option<String>,
+    pub unit_price: Option<String>,
+    // Used for the unit price (total / quantity) when there's no price column
+    pub line_total: Option<String>,
+    // An amount, or a yes/no ("lunas", TRUE, ...) meaning the line was paid in full
+    pub paid: Option<String>,
+    // Rows sharing an order number become one order; without it, rows for the
+    // same customer on the same date do
+    pub order_ref: Option<String>,
+    pub date: Option<String>,
+    pub phone: Option<String>,
+    pub address: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LegacyIssue {
+    // Row number as shown in the spreadsheet (the header is row 1)
+    pub row: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LegacyOrderSummary {
+    // None on dry runs
+    pub order_id: Option<i64>,
+    pub rows: Vec<usize>,
+    pub customer_name: String,
+    pub customer_email: String,
+    pub ordered_at: Option<String>,
+    pub items: usize,
+    pub total: f64,
+    pub paid: f64,
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LegacyMigrationReport {
+    pub dry_run: bool,
+    // Recorded as synced_responses.form_id, so running again skips these orders
+    pub source_id: String,
+    pub columns: LegacyColumnMapping,
+    // Non-blank data rows read
+    pub rows: usize,
+    pub orders: Vec<LegacyOrderSummary>,
+    pub customers_created: usize,
+    pub customers_updated: usize,
+    pub products_created: Vec<String>,
+    pub payments_created: usize,
+    // Orders a previous run already migrated
+    pub already_imported: usize,
+    pub issues: Vec<LegacyIssue>,
+}
+
+// Column positions resolved against the header row
+struct LegacyColumns {
+    customer_email: usize,
+    customer_name: Option<usize>,
+    product: usize,
+    quantity: Option<usize>,
+    unit_price: Option<usize>,
+    line_total: Option<usize>,
+    paid: Option<usize>,
+    order_ref: Option<usize>,
+    date: Option<usize>,
+    phone: Option<usize>,
+    address: Option<usize>,
+    notes: Option<usize>,
+}
+
+// What a row's "paid" cell says
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PaidCell {
+    Amount(f64),
+    InFull,
+    Unpaid,
+}
+
+// One spreadsheet row: an order line plus whatever order details it carries
+struct LegacyLine {
+    row: usize,
+    name: Option<String>,
+    email: String,
+    phone: Option<String>,
+    address: Option<String>,
+    product: String,
+    quantity: i64,
+    unit_price: Option<f64>,
+    paid: PaidCell,
+    ordered_at: Option<NaiveDateTime>,
+    notes: Option<String>,
+}
+
+// Rows grouped into one order
+struct LegacyOrder {
+    key: String,
+    lines: Vec<LegacyLine>,
+}
+
+// Helper: Resolve every field to a column: explicit mappings first, then
+// headers equal to an alias, then headers containing one ("Customer Name
+// (as on KTP)"). A column is only used for one field.
+fn resolve_columns(
+    headers: &[String],
+    mapping: &LegacyColumnMapping,
+) -> Result<(LegacyColumns, LegacyColumnMapping), AppError> {
+    let fields: [(&Option<String>, &[&str]); 12] = [
+        (&mapping.customer_email, EMAIL_HEADERS),
+        (&mapping.customer_name, NAME_HEADERS),
+        (&mapping.product, PRODUCT_HEADERS),
+        (&mapping.quantity, QUANTITY_HEADERS),
+        (&mapping.unit_price, UNIT_PRICE_HEADERS),
+        (&mapping.line_total, LINE_TOTAL_HEADERS),
+        (&mapping.paid, PAID_HEADERS),
+        (&mapping.order_ref, ORDER_REF_HEADERS),
+        (&mapping.date, DATE_HEADERS),
+        (&mapping.phone, PHONE_HEADERS),
+        (&mapping.address, ADDRESS_HEADERS),
+        (&mapping.notes, NOTES_HEADERS),
+    ];
+
+    let mut found: [Option<usize>; 12] = [None; 12];
+    for (slot, (mapped, _)) in found.iter_mut().zip(&fields) {
+        if let Some(header) = mapped {
+            *slot = find_column(headers, Some(header), &[])?;
+        }
+    }
+    let normalized: Vec<String> = headers.iter().map(|h| normalize_header(h)).collect();
+    for exact in [true, false] {
+        for (i, (mapped, aliases)) in fields.iter().enumerate() {
+            if mapped.is_some() || found[i].is_some() {
+                continue;
+            }
+            found[i] = aliases.iter().find_map(|alias| {
+                normalized.iter().enumerate().position(|(column, header)| {
+                    let matches = if exact {
+                        header == alias
+                    } else {
+                        alias.len() >= MIN_PARTIAL_ALIAS && header.contains(alias)
+                    };
+                    matches && !found.contains(&Some(column))
+                })
+            });
+        }
+    }
+
+    let [customer_email, customer_name, product, quantity, unit_price, line_total, paid, order_ref, date, phone, address, notes] =
+        found;
+    let header = |column: Option<usize>| column.map(|i| headers[i].clone());
+    let used = LegacyColumnMapping {
+        customer_email: header(customer_email),
+        customer_name: header(customer_name),
+        product: header(product),
+        quantity: header(quantity),
+        unit_price: header(unit_price),
+        line_total: header(line_total),
+        paid: header(paid),
+        order_ref: header(order_ref),
+        date: header(date),
+        phone: header(phone),
+        address: header(address),
+        notes: header(notes),
+    };
+    let columns = LegacyColumns {
+        customer_email: customer_email
+            .ok_or("No customer email column found; map one explicitly")?,
+        customer_name,
+        product: product.ok_or("No product column found; map one explicitly")?,
+        quantity,
+        unit_price,
+        line_total,
+        paid,
+        order_ref,
+        date,
+        phone,
+        address,
+        notes,
+    };
+    Ok((columns, used))
+}
+
+// Helper: Amount from text like "Rp 1.500.000", "$1,234.50" or "1.234,50".
+// The last separator is the decimal point unless it's the only one of its
+// kind and followed by exactly three digits, as in "1.500" for IDR.
+pub fn parse_amount(text: &str) -> Option<f64> {
+    let negative = text.trim_start().starts_with('-') || text.contains('(') && text.contains(')');
+    let cleaned: String = text
+        .chars()
+        .filter(|c| c.is_ascii_digit() || matches!(c, '.' | ','))
+        .collect();
+    if !cleaned.chars().any(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let digits = |part: &str| -> String { part.chars().filter(char::is_ascii_digit).collect() };
+    let number = match cleaned.rfind(['.', ',']) {
+        None => cleaned,
+        Some(pos) => {
+            let separator = if cleaned[pos..].starts_with('.') {
+                '.'
+            } else {
+                ','
+            };
+            let other = if separator == '.' { ',' } else { '.' };
+            let whole = &cleaned[..pos];
+            let fraction = &cleaned[pos + 1..];
+            let is_decimal = cleaned.contains(other)
+                || (cleaned.matches(separator).count() == 1
+                    && (fraction.len() != 3 || digits(whole) == "0"));
+            if is_decimal {
+                format!("{}.{}", digits(whole), fraction)
+            } else {
+                digits(&cleaned)
+            }
+        }
+    };
+    let value = number.parse::<f64>().ok()?;
+    Some(if negative { -value } else { value })
+}
+
+// Helper: What a "paid" cell says; None when it can't be read
+fn parse_paid(text: &str) -> Option<PaidCell> {
+    let word = text.trim().to_lowercase();
+    let compact: String = word.chars().filter(|c| !c.is_whitespace()).collect();
+    if word.is_empty() || UNPAID_WORDS.contains(&compact.as_str()) {
+        return Some(PaidCell::Unpaid);
+    }
+    if PAID_WORDS.contains(&compact.as_str()) {
+        return Some(PaidCell::InFull);
+    }
+    match parse_amount(&word)? {
+        amount if amount <= 0.0 => Some(PaidCell::Unpaid),
+        amount => Some(PaidCell::Amount(amount)),
+    }
+}
+
+// Helper: Date or timestamp in any of the usual spreadsheet shapes, including
+// the serial day numbers Excel and Google Sheets store dates as
+pub fn parse_date(text: &str) -> Option<NaiveDateTime> {
+    let text = text.trim();
+    if let Ok(serial) = text.parse::<f64>() {
+        // Days since 1899-12-30; anything outside roughly 1954-2119 isn't a date
+        if !(20000.0..80000.0).contains(&serial) {
+            return None;
+        }
+        let epoch = NaiveDate::from_ymd_opt(1899, 12, 30)?.and_hms_opt(0, 0, 0)?;
+        return epoch.checked_add_signed(Duration::seconds((serial * 86400.0).round() as i64));
+    }
+    if let Ok(timestamp) = chrono::DateTime::parse_from_rfc3339(text) {
+        return Some(timestamp.naive_utc());
+    }
+    DATETIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .or_else(|| {
+            DATE_FORMATS.iter().find_map(|format| {
+                NaiveDate::parse_from_str(text, format)
+                    .ok()
+                    .and_then(|date| date.and_hms_opt(0, 0, 0))
+            })
+        })
+}
+
+// Helper: One row as an order line, or why it can't be used
+fn parse_line(
+    row: &[String],
+    row_number: usize,
+    columns: &LegacyColumns,
+    issues: &mut Vec<LegacyIssue>,
+) -> Result<LegacyLine, AppError> {
+    let email = cell(row, Some(columns.customer_email))
+        .ok_or("Missing customer email")?
+        .to_lowercase();
+    let product = cell(row, Some(columns.product)).ok_or("Missing product")?;
+    let quantity = match cell(row, columns.quantity) {
+        None => 1,
+        Some(text) => match parse_amount(&text) {
+            Some(n) if n > 0.0 && n.fract() == 0.0 => n as i64,
+            _ => return Err(AppError::Validation(format!("Invalid quantity: {}", text))),
+        },
+    };
+    let unit_price = match cell(row, columns.unit_price) {
+        Some(text) => Some(
+            parse_amount(&text).ok_or(AppError::Validation(format!("Invalid price: {}", text)))?,
+        ),
+        None => match cell(row, columns.line_total) {
+            Some(text) => Some(
+                parse_amount(&text)
+                    .ok_or(AppError::Validation(format!("Invalid total: {}", text)))?
+                    / quantity as f64,
+            ),
+            None => None,
+        },
+    };
+    if unit_price.is_some_and(|price| price < 0.0) {
+        return Err(AppError::Validation(
+            "Price must not be negative".to_string(),
+        ));
+    }
+    let paid = match cell(row, columns.paid) {
+        None => PaidCell::Unpaid,
+        Some(text) => parse_paid(&text).ok_or(AppError::Validation(format!(
+            "Unreadable paid value: {}",
+            text
+        )))?,
+    };
+    let ordered_at = match cell(row, columns.date) {
+        None => None,
+        Some(text) => {
+            let parsed = parse_date(&text);
+            if parsed.is_none() {
+                issues.push(LegacyIssue {
+                    row: row_number,
+                    message: format!("Unreadable date {}; using the import date", text),
+                });
+            }
+            parsed
+        }
+    };
+
+    Ok(LegacyLine {
+        row: row_number,
+        name: cell(row, columns.customer_name),
+        email,
+        phone: cell(row, columns.phone),
+        address: cell(row, columns.address),
+        product,
+        quantity,
+        unit_price,
+        paid,
+        ordered_at,
+        notes: cell(row, columns.notes),
+    })
+}
+
+// Helper: Rows grouped into orders, keeping the sheet's order
+fn group_orders(
+    rows: &[Vec<String>],
+    columns: &LegacyColumns,
+    issues: &mut Vec<LegacyIssue>,
+) -> (Vec<LegacyOrder>, usize) {
+    let mut orders: Vec<LegacyOrder> = Vec::new();
+    let mut by_key: HashMap<String, usize> = HashMap::new();
+    let mut read = 0;
+    for (index, row) in rows.iter().enumerate() {
+        let row_number = index + 2;
+        if row.iter().all(|value| value.trim().is_empty()) {
+            continue;
+        }
+        read += 1;
+        let line = match parse_line(row, row_number, columns, issues) {
+            Ok(line) => line,
+            Err(message) => {
+                issues.push(LegacyIssue {
+                    row: row_number,
+                    message: message.to_string(),
+                });
+                continue;
+            }
+        };
+        let key = match cell(row, columns.order_ref) {
+            Some(reference) => format!("order:{}", reference),
+            None => format!(
+                "{}:{}",
+                line.email,
+                line.ordered_at
+                    .map(|at| at.format("%Y-%m-%d").to_string())
+                    .unwrap_or_default()
+            ),
+        };
+        match by_key.get(&key) {
+            Some(&i) => orders[i].lines.push(line),
+            None => {
+                by_key.insert(key.clone(), orders.len());
+                orders.push(LegacyOrder {
+                    key,
+                    lines: vec![line],
+                });
+            }
+        }
+    }
+    (orders, read)
+}
+
+// Helper: Amount paid on an order. An order-level amount repeated on each of
+// its rows counts once.
+fn amount_paid(order: &LegacyOrder, prices: &[f64], total: f64, epsilon: f64) -> f64 {
+    let amounts: Vec<f64> = order
+        .lines
+        .iter()
+        .filter_map(|line| match line.paid {
+            PaidCell::Amount(amount) => Some(amount),
+            _ => None,
+        })
+        .collect();
+    if order.lines.iter().all(|line| line.paid == PaidCell::InFull) {
+        return total;
+    }
+    if amounts.len() == order.lines.len()
+        && amounts.len() > 1
+        && amounts.iter().all(|a| (a - amounts[0]).abs() < epsilon)
+        && amounts.iter().sum::<f64>() > total + epsilon
+    {
+        return amounts[0];
+    }
+    order
+        .lines
+        .iter()
+        .zip(prices)
+        .map(|(line, price)| match line.paid {
+            PaidCell::Amount(amount) => amount,
+            PaidCell::InFull => price * line.quantity as f64,
+            PaidCell::Unpaid => 0.0,
+        })
+        .sum()
+}
+
+// Helper: Product for a legacy line, created (inactive, since old items are
+// rarely still on sale) when nothing in the catalog has that name
+async fn product_for_line(
+    conn: &mut SqliteConnection,
+    products: &mut HashMap<String, (i64, f64)>,
+    line: &LegacyLine,
+    currency: &str,
+    event_id: Option<i64>,
+    create_products: bool,
+    created: &mut Vec<String>,
+) -> Result<Option<(i64, f64)>, AppError> {
+    let key = line.product.trim().to_lowercase();
+    if let Some(product) = products.get(&key) {
+        return Ok(Some(*product));
+    }
+    if !create_products {
+        return Ok(None);
+    }
+    let price = line.unit_price.unwrap_or(0.0);
+    let id = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO products (name, price, currency_code, unique_id, is_active, event_id) \
+         VALUES (?, ?, ?, ?, 0, ?) RETURNING id",
+    )
+    .bind(line.product.trim())
+    .bind(price)
+    .bind(currency)
+    .bind(format!(
+        "PRD-{}",
+        uuid::Uuid::new_v4().simple().to_string()[..8].to_uppercase()
+    ))
+    .bind(event_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to create product: {}", e))?;
+    let product = load_product(&mut *conn, id).await?;
+    audit::record(&mut *conn, "product", id, "import", None, Some(&product)).await?;
+
+    products.insert(key, (id, price));
+    created.push(line.product.trim().to_string());
+    Ok(Some((id, price)))
+}
+
+// Helper: Header row and data rows from the source
+async fn read_source(source: &LegacySource) -> Result<(String, Vec<Vec<String>>), AppError> {
+    match source {
+        LegacySource::File { path } => {
+            let path = Path::new(path);
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            Ok((
+                format!("legacy:file:{}", name),
+                read_spreadsheet_rows(path)?,
+            ))
+        }
+        LegacySource::GoogleSheet {
+            access_token,
+            spreadsheet_id,
+            range,
+        } => {
+            let spreadsheet_id = spreadsheet_id.trim();
+            if spreadsheet_id.is_empty() {
+                return Err(AppError::Validation(
+                    "Spreadsheet ID must not be empty".to_string(),
+                ));
+            }
+            let rows = fetch_sheet_values(access_token, spreadsheet_id, range)
+                .await?
+                .iter()
+                .map(|row| (0..row.len()).map(|i| cell_text(row, i)).collect())
+                .collect();
+            Ok((format!("legacy:sheet:{}", spreadsheet_id), rows))
+        }
+    }
+}
+
+// Migrate orders from an old orders spreadsheet: customers go into the
+// directory, each order is created with its lines at the sheet's prices, and
+// paid amounts become payments. Orders are written directly as confirmed or
+// paid, so stock levels aren't touched for sales that happened long ago.
+// With `dry_run` everything is written and rolled back, so the report shows
+// exactly what a real run would do. Everything happens in one transaction.
+#[tauri::command]
+pub async fn migrate_legacy_orders(
+    db: State<'_, Database>,
+    source: LegacySource,
+    mapping: Option<LegacyColumnMapping>,
+    currency_code: Option<String>,
+    event_id: Option<i64>,
+    create_products: Option<bool>,
+    dry_run: Option<bool>,
+) -> Result<LegacyMigrationReport, AppError> {
+    let dry_run = dry_run.unwrap_or(true);
+    let create_products = create_products.unwrap_or(true);
+    let currency_code = currency_code
+        .as_deref()
+        .map(normalize_currency)
+        .transpose()?;
+
+    let (source_id, rows) = read_source(&source).await?;
+    let mut rows = rows.into_iter();
+    let headers = rows.next().ok_or("The spreadsheet is empty")?;
+    let rows: Vec<Vec<String>> = rows.collect();
+    let (columns, used) = resolve_columns(&headers, &mapping.unwrap_or_default())?;
+
+    let mut issues = Vec::new();
+    let (orders, read) = group_orders(&rows, &columns, &mut issues);
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let product_currency = match &currency_code {
+        Some(code) => code.clone(),
+        None => default_currency(&mut tx).await,
+    };
+    let epsilon = balance_epsilon(&product_currency);
+    let mut products: HashMap<String, (i64, f64)> = HashMap::new();
+    for (id, name, price) in sqlx::query_as::<_, (i64, String, f64)>(
+        "SELECT id, name, price FROM products ORDER BY COALESCE(is_active, 1) DESC, id DESC",
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to load products: {}", e))?
+    {
+        // Active products win over inactive ones with the same name
+        products
+            .entry(name.trim().to_lowercase())
+            .or_insert((id, price));
+    }
+    let imported: HashSet<String> = sqlx::query_scalar::<_, String>(
+        "SELECT response_id FROM synced_responses WHERE form_id = ?",
+    )
+    .bind(&source_id)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to load migrated orders: {}", e))?
+    .into_iter()
+    .collect();
+
+    let mut report = LegacyMigrationReport {
+        dry_run,
+        source_id: source_id.clone(),
+        columns: used,
+        rows: read,
+        orders: Vec::new(),
+        customers_created: 0,
+        customers_updated: 0,
+        products_created: Vec::new(),
+        payments_created: 0,
+        already_imported: 0,
+        issues,
+    };
+
+    // Every customer first, so the directory gets their phone and address
+    let mut pending = Vec::new();
+    let mut contacts: Vec<ContactRecord> = Vec::new();
+    for order in orders {
+        let first_row = order.lines[0].row;
+        let response_id = format!("{}:{}", source_id, order.key);
+        if imported.contains(&response_id) {
+            report.already_imported += 1;
+            continue;
+        }
+        let email = order.lines[0].email.clone();
+        let name = order
+            .lines
+            .iter()
+            .find_map(|line| line.name.clone())
+            .unwrap_or_else(|| email.split('@').next().unwrap_or_default().to_string());
+        if let Err(message) = validate_contact(&name, &email) {
+            report.issues.push(LegacyIssue {
+                row: first_row,
+                message: message.to_string(),
+            });
+            continue;
+        }
+        contacts.push(ContactRecord {
+            name: name.clone(),
+            email: email.clone(),
+            phone: order.lines.iter().find_map(|line| line.phone.clone()),
+            address: order.lines.iter().find_map(|line| line.address.clone()),
+        });
+        pending.push((order, response_id, name, email));
+    }
+    let saved = save_contacts(&mut tx, contacts).await?;
+    report.customers_created = saved.created;
+    report.customers_updated = saved.updated;
+
+    for (order, response_id, name, email) in pending {
+        let first_row = order.lines[0].row;
+        let mut lines = Vec::new();
+        for line in &order.lines {
+            match product_for_line(
+                &mut tx,
+                &mut products,
+                line,
+                &product_currency,
+                event_id,
+                create_products,
+                &mut report.products_created,
+            )
+            .await?
+            {
+                Some((product_id, catalog_price)) => {
+                    lines.push((line, product_id, line.unit_price.unwrap_or(catalog_price)))
+                }
+                None => report.issues.push(LegacyIssue {
+                    row: line.row,
+                    message: format!("Product \"{}\" not found", line.product),
+                }),
+            }
+        }
+        if lines.is_empty() {
+            continue;
+        }
+
+        let ordered_at = order
+            .lines
+            .iter()
+            .find_map(|line| line.ordered_at)
+            .map(|at| at.format("%Y-%m-%d %H:%M:%S").to_string());
+        let notes: Vec<String> = order
+            .lines
+            .iter()
+            .filter_map(|line| line.notes.clone())
+            .collect();
+        let notes = (!notes.is_empty()).then(|| notes.join("\n"));
+
+        let code = unique_code(&mut tx).await?;
+        let order_id = sqlx::query_scalar::<_, i64>(
+            "INSERT INTO preorders (customer_name, customer_email, confirmation_code, currency_code, \
+             status, total_amount, notes, event_id, created_at, confirmed_at) \
+             VALUES (?, ?, ?, ?, 'confirmed', 0, ?, ?, COALESCE(?, CURRENT_TIMESTAMP), \
+             COALESCE(?, CURRENT_TIMESTAMP)) RETURNING id",
+        )
+        .bind(&name)
+        .bind(&email)
+        .bind(code)
+        .bind(&currency_code)
+        .bind(&notes)
+        .bind(event_id)
+        .bind(&ordered_at)
+        .bind(&ordered_at)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to create order: {}", e))?;
+
+        for (line, product_id, unit_price) in &lines {
+            sqlx::query(
+                "INSERT INTO order_items (preorder_id, product_id, quantity, unit_price) VALUES (?, ?, ?, ?)",
+            )
+            .bind(order_id)
+            .bind(product_id)
+            .bind(line.quantity)
+            .bind(unit_price)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to save order item: {}", e))?;
+        }
+        let total = order_total(&mut tx, order_id).await?;
+
+        let prices: Vec<f64> = order
+            .lines
+            .iter()
+            .map(|line| {
+                lines
+                    .iter()
+                    .find(|(l, _, _)| std::ptr::eq(*l, line))
+                    .map(|(_, _, price)| *price)
+                    .unwrap_or(0.0)
+            })
+            .collect();
+        let mut paid = amount_paid(&order, &prices, total, epsilon);
+        if paid > total + epsilon {
+            report.issues.push(LegacyIssue {
+                row: first_row,
+                message: format!(
+                    "Paid {:.2} is more than the order total {:.2}; recorded as paid in full",
+                    paid, total
+                ),
+            });
+            paid = total;
+        }
+        let settled = paid > 0.0 && total - paid <= epsilon;
+        sqlx::query(
+            "UPDATE preorders SET total_amount = ?, status = ?, \
+             paid_at = CASE WHEN ? THEN COALESCE(?, CURRENT_TIMESTAMP) END WHERE id = ?",
+        )
+        .bind(total)
+        .bind(if settled { "paid" } else { "confirmed" })
+        .bind(settled)
+        .bind(&ordered_at)
+        .bind(order_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to update order: {}", e))?;
+
+        if paid > 0.0 {
+            sqlx::query(
+                "INSERT INTO payments (preorder_id, amount, method, paid_at, notes) \
+                 VALUES (?, ?, ?, COALESCE(?, CURRENT_TIMESTAMP), ?)",
+            )
+            .bind(order_id)
+            .bind(paid)
+            .bind(LEGACY_PAYMENT_METHOD)
+            .bind(&ordered_at)
+            .bind("Migrated from legacy spreadsheet")
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to record payment: {}", e))?;
+            report.payments_created += 1;
+        }
+
+        sqlx::query(
+            "INSERT INTO synced_responses (response_id, form_id, preorder_id) VALUES (?, ?, ?)",
+        )
+        .bind(&response_id)
+        .bind(&source_id)
+        .bind(order_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to record migrated order: {}", e))?;
+
+        let created = fetch_order(&mut tx, order_id).await?;
+        audit::record(&mut *tx, "order", order_id, "import", None, Some(&created)).await?;
+
+        report.orders.push(LegacyOrderSummary {
+            order_id: (!dry_run).then_some(order_id),
+            rows: order.lines.iter().map(|line| line.row).collect(),
+            customer_name: created.customer_name,
+            customer_email: created.customer_email,
+            ordered_at: created.created_at,
+            items: lines.len(),
+            total,
+            paid,
+            status: created.status,
+        });
+    }
+
+    if dry_run {
+        tx.rollback()
+            .await
+            .map_err(|e| format!("Failed to discard preview: {}", e))?;
+    } else {
+        tx.commit()
+            .await
+            .map_err(|e| format!("Failed to save migrated orders: {}", e))?;
+    }
+
+    Ok(report)
+}
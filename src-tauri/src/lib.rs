@@ -2,13 +2,110 @@ use lettre::message::header::ContentType;
 use lettre::transport::smtp::authentication::Credentials;
 use lettre::{Message, SmtpTransport, Transport};
 use serde::{Deserialize, Serialize};
-use uuid::Uuid;
 use reqwest::Client;
 use std::collections::HashMap;
 use tiny_http::{Server, Response};
+use tauri::Manager;
+use redact::redact;
+use error::AppError;
+use http::SendWithRetry;
+use google_cache::{CacheKind, GoogleCache};
+
+mod archive;
+mod audit;
+mod auto_backup;
+mod automation;
+mod backup;
+mod bank_statement;
+mod barcode_lookup;
+mod barcodes;
+mod bulk_email;
+mod bulk_orders;
+mod chat_alerts;
+mod confirmation_codes;
+mod contacts;
+mod credit_notes;
+mod crypto;
+mod csv_export;
+mod currency;
+mod customer_analytics;
+mod customer_data;
+mod customers;
+mod dashboard;
+mod data_export;
+mod demand;
+mod db;
+mod device_sync;
+mod deposits;
+mod desktop_notifications;
+mod diagnostics;
+mod documents;
+mod domain_events;
+mod drive;
+mod encryption;
+mod error;
+mod events;
+mod expenses;
+mod form_responses;
+mod fulfillment;
+mod google_cache;
+mod health;
+mod http;
+mod i18n;
+mod integrity;
+mod inventory;
+mod invoice_numbers;
+mod invoice_template;
+mod invoices;
+mod labels;
+mod legacy_import;
+mod logging;
+mod midtrans;
+mod migrations;
+mod models;
+mod money;
+mod order_status;
+mod orders;
+mod outgoing_webhooks;
+mod packing;
+mod payment_links;
+mod payment_ocr;
+mod payment_reminders;
+mod payments;
+mod paypal;
+mod pdf;
+mod pickup_queue;
+mod pricing;
+mod product_import;
+mod products;
+mod profiles;
+mod qris;
+mod quotes;
+mod recurring_orders;
+mod redact;
+mod reports;
+mod search;
+mod session_lock;
+mod settings;
+mod sheets;
+mod sms;
+mod stripe;
+mod supplier_orders;
+mod tasks;
+mod telegram;
+mod throttle;
+mod timeline;
+mod totals;
+mod twilio;
+mod vonage;
+mod webhook_receiver;
+mod whatsapp;
+mod xlsx_export;
+
+use drive::{validate_drive_name, DriveQuery};
 
 // Data structures for SMTP settings
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SmtpSettings {
     pub smtp_server: String,
     pub smtp_port: i32,
@@ -71,6 +168,8 @@ pub struct GoogleFormInfo {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FormResponsesData {
     pub responses: Option<Vec<FormResponse>>,
+    #[serde(rename = "nextPageToken", default, skip_serializing_if = "Option::is_none")]
+    pub next_page_token: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -142,12 +241,65 @@ pub struct DriveFileList {
     pub files: Vec<DriveFile>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DriveOwner {
+    #[serde(rename = "emailAddress")]
+    pub email_address: Option<String>,
+    #[serde(rename = "displayName")]
+    pub display_name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DriveFormMetadata {
+    pub id: String,
+    pub name: String,
+    pub parents: Option<Vec<String>>,
+    #[serde(rename = "modifiedTime")]
+    pub modified_time: Option<String>,
+    #[serde(rename = "webViewLink")]
+    pub web_view_link: Option<String>,
+    pub owners: Option<Vec<DriveOwner>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DriveFormMetadataList {
+    pub files: Vec<DriveFormMetadata>,
+    #[serde(rename = "nextPageToken")]
+    pub next_page_token: Option<String>,
+}
+
+// Subset of the Forms API resource used to check whether a form is open
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FormPublishInfo {
+    #[serde(rename = "publishSettings")]
+    pub publish_settings: Option<FormPublishSettings>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FormPublishSettings {
+    #[serde(rename = "publishState")]
+    pub publish_state: Option<FormPublishState>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FormPublishState {
+    #[serde(rename = "isPublished")]
+    pub is_published: Option<bool>,
+    #[serde(rename = "isAcceptingResponses")]
+    pub is_accepting_responses: Option<bool>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ScannedForm {
     pub form_id: String,
     pub name: String,
     pub url: String,
     pub responder_url: String,
+    pub modified_time: Option<String>,
+    pub web_view_link: Option<String>,
+    pub owners: Vec<String>,
+    // None when the Forms API doesn't report publish state (older forms)
+    pub accepting_responses: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -158,26 +310,53 @@ pub struct ScannedProject {
     pub products_json: Option<String>,
 }
 
-// Generate unique confirmation code
+// Send email with invoice, logging it on the order's timeline when po_id is given.
+// Without smtp_settings the account saved in the settings store is used.
 #[tauri::command]
-fn generate_confirmation_code() -> String {
-    Uuid::new_v4()
-        .to_string()
-        .chars()
-        .take(8)
-        .collect::<String>()
-        .to_uppercase()
+#[tracing::instrument(skip_all, fields(po_id = ?po_id), err)]
+async fn send_invoice_email(
+    db: tauri::State<'_, db::Database>,
+    smtp_settings: Option<SmtpSettings>,
+    to_email: String,
+    to_name: String,
+    subject: String,
+    html_body: String,
+    po_id: Option<i64>,
+) -> Result<String, AppError> {
+    let smtp_settings = match smtp_settings {
+        Some(smtp_settings) => smtp_settings,
+        None => settings::load_smtp_settings(&db.pool)
+            .await?
+            .ok_or_else(|| AppError::Validation("SMTP is not configured".to_string()))?,
+    };
+    let (to, log_subject) = (to_email.clone(), subject.clone());
+    let sent = tauri::async_runtime::spawn_blocking(move || {
+        send_smtp_email(smtp_settings, to_email, to_name, subject, html_body)
+    })
+    .await
+    .map_err(|e| format!("Failed to send email: {}", e))?;
+    let result = domain_events::check_email_sent(&db.pool, &to, &log_subject, sent).await?;
+
+    let email_sent = domain_events::DomainEvent::EmailSent {
+        order_id: po_id,
+        to_email: &to,
+        subject: &log_subject,
+        channel: timeline::EmailChannel::Smtp,
+    };
+    domain_events::publish_now(&db.pool, &email_sent).await?;
+    Ok(result)
 }
 
-// Send email with invoice
-#[tauri::command]
-fn send_invoice_email(
+// Blocking SMTP send shared by send_invoice_email and background jobs
+#[tracing::instrument(skip_all, fields(server = %smtp_settings.smtp_server), err)]
+fn send_smtp_email(
     smtp_settings: SmtpSettings,
     to_email: String,
     to_name: String,
     subject: String,
     html_body: String,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
+    session_lock::ensure_unlocked()?;
     let from_name = smtp_settings
         .from_name
         .unwrap_or_else(|| "POTracker".to_string());
@@ -198,22 +377,43 @@ fn send_invoice_email(
 
     let creds = Credentials::new(smtp_settings.username.clone(), smtp_settings.password.clone());
 
-    let mailer = SmtpTransport::relay(&smtp_settings.smtp_server)
+    let mut transport = SmtpTransport::relay(&smtp_settings.smtp_server)
         .map_err(|e| format!("Failed to create SMTP transport: {}", e))?
         .port(smtp_settings.smtp_port as u16)
-        .credentials(creds)
-        .build();
+        .credentials(creds);
+
+    // Trust the CA bundle from the network settings as well. SMTP doesn't go
+    // through the proxy: lettre connects directly.
+    let ca_certs = http::ca_certs();
+    if !ca_certs.is_empty() {
+        use lettre::transport::smtp::client::{Certificate, Tls, TlsParameters};
+        let mut tls = TlsParameters::builder(smtp_settings.smtp_server.clone());
+        for pem in &ca_certs {
+            let cert = Certificate::from_pem(pem.as_bytes())
+                .map_err(|e| format!("Invalid certificate in CA bundle: {}", e))?;
+            tls = tls.add_root_certificate(cert);
+        }
+        let tls = tls
+            .build()
+            .map_err(|e| format!("Failed to configure SMTP TLS: {}", e))?;
+        transport = transport.tls(Tls::Wrapper(tls));
+    }
+    let mailer = transport.build();
 
     mailer
         .send(&email)
-        .map_err(|e| format!("Failed to send email: {}", e))?;
+        .map_err(|e| redact(&format!("Failed to send email: {}", e)))?;
 
+    tracing::info!("Email sent via SMTP");
     Ok("Email sent successfully".to_string())
 }
 
-// Send email via Gmail API
+// Send email via Gmail API, logging it on the order's timeline when po_id is given
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(skip_all, fields(po_id = ?po_id), err)]
 async fn send_gmail_email(
+    db: tauri::State<'_, db::Database>,
     access_token: String,
     to_email: String,
     to_name: String,
@@ -221,9 +421,12 @@ async fn send_gmail_email(
     from_name: String,
     subject: String,
     html_body: String,
-) -> Result<String, String> {
+    po_id: Option<i64>,
+) -> Result<String, AppError> {
     use base64::{Engine as _, engine::general_purpose::URL_SAFE};
     
+    session_lock::ensure_unlocked()?;
+
     // Create RFC 2822 email
     let email_content = format!(
         "From: {} <{}>\r\nTo: {} <{}>\r\nSubject: {}\r\nMIME-Version: 1.0\r\nContent-Type: text/html; charset=utf-8\r\n\r\n{}",
@@ -233,31 +436,45 @@ async fn send_gmail_email(
     // Base64 URL-safe encode the email
     let encoded_email = URL_SAFE.encode(email_content.as_bytes());
     
-    let client = Client::new();
+    let client = http::client();
     
     let body = serde_json::json!({
         "raw": encoded_email
     });
     
-    let response = client
-        .post("https://gmail.googleapis.com/gmail/v1/users/me/messages/send")
-        .bearer_auth(&access_token)
-        .json(&body)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to send email via Gmail: {}", e))?;
-    
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("Gmail API error: {}", error_text));
+    let sent = async {
+        let response = client
+            .post("https://gmail.googleapis.com/gmail/v1/users/me/messages/send")
+            .bearer_auth(&access_token)
+            .json(&body)
+            .send_with_retry()
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to send email via Gmail: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(http::google_error(response, "Gmail API error").await);
+        }
+        Ok(())
     }
+    .await;
+    domain_events::check_email_sent(&db.pool, &to_email, &subject, sent).await?;
+
+    let email_sent = domain_events::DomainEvent::EmailSent {
+        order_id: po_id,
+        to_email: &to_email,
+        subject: &subject,
+        channel: timeline::EmailChannel::Gmail,
+    };
+    domain_events::publish_now(&db.pool, &email_sent).await?;
     
+    tracing::info!("Email sent via Gmail");
     Ok("Email sent successfully via Gmail".to_string())
 }
 
 // Start OAuth callback server and get authorization URL
 #[tauri::command]
-async fn start_oauth_flow(client_id: String) -> Result<serde_json::Value, String> {
+#[tracing::instrument(skip_all, err)]
+async fn start_oauth_flow(client_id: String) -> Result<serde_json::Value, AppError> {
     // Try to find an available port starting from 8080
     let mut port = 8080;
     let max_port = 8090;
@@ -271,7 +488,7 @@ async fn start_oauth_flow(client_id: String) -> Result<serde_json::Value, String
     }
     
     if port > max_port {
-        return Err("Could not find an available port for OAuth callback".to_string());
+        return Err(AppError::Internal("Could not find an available port for OAuth callback".to_string()));
     }
     
     let redirect_uri = format!("http://localhost:{}/callback", port);
@@ -283,6 +500,10 @@ async fn start_oauth_flow(client_id: String) -> Result<serde_json::Value, String
         "https://www.googleapis.com/auth/forms.responses.readonly",
         "https://www.googleapis.com/auth/gmail.send",
         "https://www.googleapis.com/auth/drive",
+        "https://www.googleapis.com/auth/spreadsheets",
+        "https://www.googleapis.com/auth/contacts.readonly",
+        "https://www.googleapis.com/auth/drive.appdata",
+        "https://www.googleapis.com/auth/cloud-vision",
     ].join(" ");
     
     let auth_url = format!(
@@ -300,7 +521,13 @@ async fn start_oauth_flow(client_id: String) -> Result<serde_json::Value, String
 
 // Wait for OAuth callback and return the authorization code
 #[tauri::command]
-async fn wait_for_oauth_callback(port: u16) -> Result<String, String> {
+#[tracing::instrument(skip_all, fields(port = port), err)]
+async fn wait_for_oauth_callback(
+    db: tauri::State<'_, db::Database>,
+    port: u16,
+) -> Result<String, AppError> {
+    let locale = i18n::load_locale(&db.pool).await;
+    
     let server = Server::http(format!("127.0.0.1:{}", port))
         .map_err(|e| format!("Failed to start callback server: {}", e))?;
     
@@ -313,7 +540,7 @@ async fn wait_for_oauth_callback(port: u16) -> Result<String, String> {
             <!DOCTYPE html>
             <html>
             <head>
-                <title>Authentication Successful</title>
+                <title>{title}</title>
                 <style>
                     body {
                         font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, sans-serif;
@@ -378,12 +605,15 @@ async fn wait_for_oauth_callback(port: u16) -> Result<String, String> {
                         <circle class="checkmark__circle" cx="26" cy="26" r="25" fill="none"/>
                         <path class="checkmark__check" fill="none" d="M14.1 27.2l7.1 7.2 16.7-16.8"/>
                     </svg>
-                    <h1>✅ Authentication Successful!</h1>
-                    <p>You can close this window and return to POTracker.</p>
+                    <h1>✅ {heading}</h1>
+                    <p>{body}</p>
                 </div>
             </body>
             </html>
-        "#;
+        "#
+        .replace("{title}", &locale.text("oauth.success.title"))
+        .replace("{heading}", &locale.text("oauth.success.heading"))
+        .replace("{body}", &locale.text("oauth.success.body"));
         
         let _ = request.respond(Response::from_string(response_html)
             .with_header(tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"text/html; charset=utf-8"[..]).unwrap()));
@@ -400,10 +630,10 @@ async fn wait_for_oauth_callback(port: u16) -> Result<String, String> {
             return Ok(code.to_string());
         }
         
-        return Err("No authorization code found in callback".to_string());
+        return Err(AppError::Validation("No authorization code found in callback".to_string()));
     }
     
-    Err("Server stopped without receiving callback".to_string())
+    Err(AppError::Validation("Server stopped without receiving callback".to_string()))
 }
 
 // Generate Google OAuth URL (deprecated - use start_oauth_flow instead)
@@ -416,6 +646,10 @@ fn get_google_auth_url(client_id: String, redirect_uri: String) -> String {
         "https://www.googleapis.com/auth/forms.responses.readonly",
         "https://www.googleapis.com/auth/gmail.send",
         "https://www.googleapis.com/auth/drive",
+        "https://www.googleapis.com/auth/spreadsheets",
+        "https://www.googleapis.com/auth/contacts.readonly",
+        "https://www.googleapis.com/auth/drive.appdata",
+        "https://www.googleapis.com/auth/cloud-vision",
     ].join(" ");
     
     format!(
@@ -428,13 +662,14 @@ fn get_google_auth_url(client_id: String, redirect_uri: String) -> String {
 
 // Exchange authorization code for tokens
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 async fn exchange_google_code(
     code: String,
     client_id: String,
     client_secret: String,
     redirect_uri: String,
-) -> Result<GoogleTokenResponse, String> {
-    let client = Client::new();
+) -> Result<GoogleTokenResponse, AppError> {
+    let client = http::client();
     
     let params = [
         ("code", code.as_str()),
@@ -447,29 +682,29 @@ async fn exchange_google_code(
     let response = client
         .post("https://oauth2.googleapis.com/token")
         .form(&params)
-        .send()
+        .send_with_retry()
         .await
-        .map_err(|e| format!("Failed to exchange code: {}", e))?;
+        .map_err(|e| AppError::Network(format!("Failed to exchange code: {}", e)))?;
     
     if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("Token exchange failed: {}", error_text));
+        return Err(http::google_error(response, "Token exchange failed").await);
     }
     
     response
         .json::<GoogleTokenResponse>()
         .await
-        .map_err(|e| format!("Failed to parse token response: {}", e))
+        .map_err(|e| AppError::Internal(format!("Failed to parse token response: {}", e)))
 }
 
 // Refresh access token
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 async fn refresh_google_token(
     refresh_token: String,
     client_id: String,
     client_secret: String,
-) -> Result<GoogleTokenResponse, String> {
-    let client = Client::new();
+) -> Result<GoogleTokenResponse, AppError> {
+    let client = http::client();
     
     let params = [
         ("refresh_token", refresh_token.as_str()),
@@ -481,63 +716,79 @@ async fn refresh_google_token(
     let response = client
         .post("https://oauth2.googleapis.com/token")
         .form(&params)
-        .send()
+        .send_with_retry()
         .await
-        .map_err(|e| format!("Failed to refresh token: {}", e))?;
+        .map_err(|e| AppError::Network(format!("Failed to refresh token: {}", e)))?;
     
     if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("Token refresh failed: {}", error_text));
+        // Google answers 400 invalid_grant once the refresh token is revoked or expired
+        return Err(http::google_error(response, "Token refresh failed").await);
     }
     
     response
         .json::<GoogleTokenResponse>()
         .await
-        .map_err(|e| format!("Failed to parse token response: {}", e))
+        .map_err(|e| AppError::Internal(format!("Failed to parse token response: {}", e)))
 }
 
 // Get user info from Google
 #[tauri::command]
-async fn get_google_user_info(access_token: String) -> Result<GoogleUserInfo, String> {
-    let client = Client::new();
-    
-    let response = client
-        .get("https://www.googleapis.com/oauth2/v2/userinfo")
-        .bearer_auth(&access_token)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to get user info: {}", e))?;
-    
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("Failed to get user info: {}", error_text));
-    }
-    
-    response
-        .json::<GoogleUserInfo>()
-        .await
-        .map_err(|e| format!("Failed to parse user info: {}", e))
+#[tracing::instrument(skip_all, err)]
+async fn get_google_user_info(
+    access_token: String,
+    operation_id: Option<String>,
+) -> Result<GoogleUserInfo, AppError> {
+    http::cancellable(operation_id, async move {
+        let client = http::client();
+
+        let response = client
+            .get("https://www.googleapis.com/oauth2/v2/userinfo")
+            .bearer_auth(&access_token)
+            .send_with_retry()
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to get user info: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(http::google_error(response, "Failed to get user info").await);
+        }
+
+        response
+            .json::<GoogleUserInfo>()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to parse user info: {}", e)))
+    })
+    .await
 }
 
 
 
-// Helper: Find folder by name
-async fn find_folder(client: &Client, access_token: &str, name: &str) -> Result<Option<String>, String> {
-    let query = format!(
-        "mimeType='application/vnd.google-apps.folder' and name='{}' and trashed=false",
-        name
-    );
+// Helper: Find folder by name, remembering the ID once found
+async fn find_folder(
+    client: &Client,
+    cache: &GoogleCache,
+    access_token: &str,
+    name: &str,
+) -> Result<Option<String>, AppError> {
+    validate_drive_name(name)?;
+    if let Some(id) = cache.get(CacheKind::FolderId, access_token, name) {
+        return Ok(Some(id));
+    }
+    let query = DriveQuery::new()
+        .folder()
+        .name_equals(name)
+        .trashed(false)
+        .build();
     
     let response = client
         .get("https://www.googleapis.com/drive/v3/files")
         .query(&[("q", query.as_str())])
         .bearer_auth(access_token)
-        .send()
+        .send_with_retry()
         .await
-        .map_err(|e| format!("Failed to search folder: {}", e))?;
+        .map_err(|e| AppError::Network(format!("Failed to search folder: {}", e)))?;
         
     if !response.status().is_success() {
-        return Err(format!("Drive API error: {}", response.status()));
+        return Err(http::google_error(response, "Drive API error").await);
     }
     
     let list: DriveFileList = response
@@ -545,11 +796,16 @@ async fn find_folder(client: &Client, access_token: &str, name: &str) -> Result<
         .await
         .map_err(|e| format!("Failed to parse file list: {}", e))?;
         
-    Ok(list.files.first().map(|f| f.id.clone()))
+    let id = list.files.first().map(|f| f.id.clone());
+    if let Some(id) = &id {
+        cache.put(CacheKind::FolderId, access_token, name, id);
+    }
+    Ok(id)
 }
 
 // Helper: Create folder
-async fn create_folder(client: &Client, access_token: &str, name: &str) -> Result<String, String> {
+async fn create_folder(client: &Client, access_token: &str, name: &str) -> Result<String, AppError> {
+    validate_drive_name(name)?;
     let body = serde_json::json!({
         "name": name,
         "mimeType": "application/vnd.google-apps.folder"
@@ -559,12 +815,12 @@ async fn create_folder(client: &Client, access_token: &str, name: &str) -> Resul
         .post("https://www.googleapis.com/drive/v3/files")
         .bearer_auth(access_token)
         .json(&body)
-        .send()
+        .send_with_retry()
         .await
-        .map_err(|e| format!("Failed to create folder: {}", e))?;
+        .map_err(|e| AppError::Network(format!("Failed to create folder: {}", e)))?;
         
     if !response.status().is_success() {
-        return Err(format!("Drive API create error: {}", response.status()));
+        return Err(http::google_error(response, "Drive API create error").await);
     }
     
     let file: DriveFile = response
@@ -581,15 +837,15 @@ async fn move_file_to_folder(
     access_token: &str, 
     file_id: &str, 
     folder_id: &str
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     // First get current parents to remove them
     let response = client
         .get(format!("https://www.googleapis.com/drive/v3/files/{}", file_id))
         .query(&[("fields", "parents")])
         .bearer_auth(access_token)
-        .send()
+        .send_with_retry()
         .await
-        .map_err(|e| format!("Failed to get file parents: {}", e))?;
+        .map_err(|e| AppError::Network(format!("Failed to get file parents: {}", e)))?;
         
     let current_parents = if response.status().is_success() {
         let file: DriveFile = response.json().await.unwrap_or(DriveFile { 
@@ -611,12 +867,12 @@ async fn move_file_to_folder(
             ("removeParents", &current_parents)
         ])
         .bearer_auth(access_token)
-        .send()
+        .send_with_retry()
         .await
-        .map_err(|e| format!("Failed to move file: {}", e))?;
+        .map_err(|e| AppError::Network(format!("Failed to move file: {}", e)))?;
         
     if !response.status().is_success() {
-        return Err(format!("Failed to move file to folder: {}", response.status()));
+        return Err(http::google_error(response, "Failed to move file to folder").await);
     }
     
     Ok(())
@@ -624,8 +880,13 @@ async fn move_file_to_folder(
 
 // Helper: Trash/Delete file
 #[tauri::command]
-async fn delete_drive_file(access_token: String, file_id: String) -> Result<String, String> {
-    let client = Client::new();
+#[tracing::instrument(skip_all, fields(file_id = %file_id), err)]
+async fn delete_drive_file(
+    cache: tauri::State<'_, GoogleCache>,
+    access_token: String,
+    file_id: String,
+) -> Result<String, AppError> {
+    let client = http::client();
     
     let body = serde_json::json!({
         "trashed": true
@@ -635,14 +896,16 @@ async fn delete_drive_file(access_token: String, file_id: String) -> Result<Stri
         .patch(format!("https://www.googleapis.com/drive/v3/files/{}", file_id))
         .bearer_auth(access_token)
         .json(&body)
-        .send()
+        .send_with_retry()
         .await
-        .map_err(|e| format!("Failed to delete file: {}", e))?;
+        .map_err(|e| AppError::Network(format!("Failed to delete file: {}", e)))?;
         
     if !response.status().is_success() {
-         let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("Drive API delete error: {}", error_text));
+        return Err(http::google_error(response, "Drive API delete error").await);
     }
+    // The file may have been a project folder or a form listed by the scan
+    cache.invalidate(CacheKind::DriveListing, None);
+    cache.invalidate(CacheKind::FolderId, None);
     
     Ok("File moved to trash".to_string())
 }
@@ -655,7 +918,7 @@ async fn create_drive_file(
     content: &str,
     mime_type: &str, // Content MimeType (e.g., application/json)
     parent_id: Option<&str>,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     use reqwest::multipart;
 
     // Metadata part
@@ -680,13 +943,12 @@ async fn create_drive_file(
         .post("https://www.googleapis.com/upload/drive/v3/files?uploadType=multipart")
         .bearer_auth(access_token)
         .multipart(form)
-        .send()
+        .send_with_retry()
         .await
-        .map_err(|e| format!("Failed to upload file: {}", e))?;
+        .map_err(|e| AppError::Network(format!("Failed to upload file: {}", e)))?;
         
     if !response.status().is_success() {
-         let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("Drive API upload error: {}", error_text));
+        return Err(http::google_error(response, "Drive API upload error").await);
     }
     
     let file: DriveFile = response
@@ -699,23 +961,30 @@ async fn create_drive_file(
 
 // Helper: Read file content
 #[tauri::command]
-async fn read_drive_file(access_token: String, file_id: String) -> Result<String, String> {
-    let client = Client::new();
-    
-    let response = client
-        .get(format!("https://www.googleapis.com/drive/v3/files/{}?alt=media", file_id))
-        .bearer_auth(access_token)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to read file: {}", e))?;
-        
-    if !response.status().is_success() {
-         let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("Drive API read error: {}", error_text));
-    }
-    
-    let content = response.text().await.map_err(|e| format!("Failed to get content: {}", e))?;
-    Ok(content)
+#[tracing::instrument(skip_all, fields(file_id = %file_id), err)]
+async fn read_drive_file(
+    access_token: String,
+    file_id: String,
+    operation_id: Option<String>,
+) -> Result<String, AppError> {
+    http::cancellable(operation_id, async move {
+        let client = http::client();
+
+        let response = client
+            .get(format!("https://www.googleapis.com/drive/v3/files/{}?alt=media", file_id))
+            .bearer_auth(access_token)
+            .send_with_retry()
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to read file: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(http::google_error(response, "Drive API read error").await);
+        }
+
+        let content = response.text().await.map_err(|e| format!("Failed to get content: {}", e))?;
+        Ok(content)
+    })
+    .await
 }
 
 // Helper: Upload binary file to Drive
@@ -726,7 +995,7 @@ async fn upload_binary_to_drive(
     data: &[u8],
     mime_type: &str,
     parent_id: Option<&str>,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     use reqwest::multipart;
 
     let mut parents = Vec::new();
@@ -749,13 +1018,12 @@ async fn upload_binary_to_drive(
         .post("https://www.googleapis.com/upload/drive/v3/files?uploadType=multipart")
         .bearer_auth(access_token)
         .multipart(form)
-        .send()
+        .send_with_retry()
         .await
-        .map_err(|e| format!("Failed to upload binary file: {}", e))?;
+        .map_err(|e| AppError::Network(format!("Failed to upload binary file: {}", e)))?;
         
     if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("Drive API upload error: {}", error_text));
+        return Err(http::google_error(response, "Drive API upload error").await);
     }
     
     let file: DriveFile = response
@@ -771,7 +1039,7 @@ async fn make_file_public(
     client: &Client,
     access_token: &str,
     file_id: &str,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let body = serde_json::json!({
         "role": "reader",
         "type": "anyone"
@@ -781,13 +1049,12 @@ async fn make_file_public(
         .post(format!("https://www.googleapis.com/drive/v3/files/{}/permissions", file_id))
         .bearer_auth(access_token)
         .json(&body)
-        .send()
+        .send_with_retry()
         .await
-        .map_err(|e| format!("Failed to set permissions: {}", e))?;
+        .map_err(|e| AppError::Network(format!("Failed to set permissions: {}", e)))?;
         
     if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("Drive API permission error: {}", error_text));
+        return Err(http::google_error(response, "Drive API permission error").await);
     }
     
     Ok(())
@@ -795,26 +1062,34 @@ async fn make_file_public(
 
 // Helper: Find folder inside parent by name
 async fn find_folder_in_parent(
-    client: &Client, 
-    access_token: &str, 
-    name: &str, 
+    client: &Client,
+    cache: &GoogleCache,
+    access_token: &str,
+    name: &str,
     parent_id: &str
-) -> Result<Option<String>, String> {
-    let query = format!(
-        "mimeType='application/vnd.google-apps.folder' and name='{}' and '{}' in parents and trashed=false",
-        name, parent_id
-    );
+) -> Result<Option<String>, AppError> {
+    validate_drive_name(name)?;
+    let cache_key = format!("{}/{}", parent_id, name);
+    if let Some(id) = cache.get(CacheKind::FolderId, access_token, &cache_key) {
+        return Ok(Some(id));
+    }
+    let query = DriveQuery::new()
+        .folder()
+        .name_equals(name)
+        .in_parent(parent_id)
+        .trashed(false)
+        .build();
     
     let response = client
         .get("https://www.googleapis.com/drive/v3/files")
         .query(&[("q", query.as_str())])
         .bearer_auth(access_token)
-        .send()
+        .send_with_retry()
         .await
-        .map_err(|e| format!("Failed to search folder: {}", e))?;
+        .map_err(|e| AppError::Network(format!("Failed to search folder: {}", e)))?;
         
     if !response.status().is_success() {
-        return Err(format!("Drive API error: {}", response.status()));
+        return Err(http::google_error(response, "Drive API error").await);
     }
     
     let list: DriveFileList = response
@@ -822,7 +1097,11 @@ async fn find_folder_in_parent(
         .await
         .map_err(|e| format!("Failed to parse file list: {}", e))?;
         
-    Ok(list.files.first().map(|f| f.id.clone()))
+    let id = list.files.first().map(|f| f.id.clone());
+    if let Some(id) = &id {
+        cache.put(CacheKind::FolderId, access_token, &cache_key, id);
+    }
+    Ok(id)
 }
 
 // Helper: Create folder inside parent
@@ -831,7 +1110,8 @@ async fn create_folder_in_parent(
     access_token: &str, 
     name: &str, 
     parent_id: &str
-) -> Result<String, String> {
+) -> Result<String, AppError> {
+    validate_drive_name(name)?;
     let body = serde_json::json!({
         "name": name,
         "mimeType": "application/vnd.google-apps.folder",
@@ -842,12 +1122,12 @@ async fn create_folder_in_parent(
         .post("https://www.googleapis.com/drive/v3/files")
         .bearer_auth(access_token)
         .json(&body)
-        .send()
+        .send_with_retry()
         .await
-        .map_err(|e| format!("Failed to create folder: {}", e))?;
+        .map_err(|e| AppError::Network(format!("Failed to create folder: {}", e)))?;
         
     if !response.status().is_success() {
-        return Err(format!("Drive API create error: {}", response.status()));
+        return Err(http::google_error(response, "Drive API create error").await);
     }
     
     let file: DriveFile = response
@@ -860,14 +1140,16 @@ async fn create_folder_in_parent(
 
 // Upload a product image to Google Drive and return public URL
 #[tauri::command]
+#[tracing::instrument(skip_all, fields(folder_id = %project_folder_id), err)]
 async fn upload_product_image(
+    cache: tauri::State<'_, GoogleCache>,
     access_token: String,
     project_folder_id: String,
     image_name: String,
     image_data_base64: String,
     mime_type: String,
-) -> Result<String, String> {
-    let client = Client::new();
+) -> Result<String, AppError> {
+    let client = http::client();
     
     // Decode base64 image data
     let image_bytes = base64::Engine::decode(
@@ -875,8 +1157,10 @@ async fn upload_product_image(
         &image_data_base64
     ).map_err(|e| format!("Failed to decode image: {}", e))?;
     
+    drive::ensure_quota_available(&client, &access_token, image_bytes.len() as u64).await?;
+    
     // Find or create images folder inside project folder
-    let images_folder_id = match find_folder_in_parent(&client, &access_token, "images", &project_folder_id).await? {
+    let images_folder_id = match find_folder_in_parent(&client, &cache, &access_token, "images", &project_folder_id).await? {
         Some(id) => id,
         None => create_folder_in_parent(&client, &access_token, "images", &project_folder_id).await?
     };
@@ -901,18 +1185,23 @@ async fn upload_product_image(
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err)]
 async fn create_google_form(
+    cache: tauri::State<'_, GoogleCache>,
     access_token: String,
     title: String,
     products_json: Option<String>,
-) -> Result<CreateFormResult, String> {
-    let client = Client::new();
+) -> Result<CreateFormResult, AppError> {
+    validate_drive_name(&title)?;
+    let client = http::client();
     
     // 1. Ensure "po-tracker" root folder exists
-    let root_folder_id = match find_folder(&client, &access_token, "po-tracker").await? {
+    let root_folder_id = match find_folder(&client, &cache, &access_token, "po-tracker").await? {
         Some(id) => id,
         None => create_folder(&client, &access_token, "po-tracker").await?
     };
+    // The new project shows up in the next scan
+    cache.invalidate(CacheKind::DriveListing, None);
     
     // 2. Create project subfolder
     let project_folder_id = create_folder(&client, &access_token, &title).await?;
@@ -920,7 +1209,7 @@ async fn create_google_form(
     // Move project folder into root (create_folder creates in root by default unless specified, but our helper doesn't support parent yet)
     // To keep it simple, we reuse move_file_to_folder
     if let Err(e) = move_file_to_folder(&client, &access_token, &project_folder_id, &root_folder_id).await {
-         println!("Warning: Failed to move project folder into root: {}", e);
+         log_warning!("Failed to move project folder into root: {}", e);
     }
     
     // 3. Create the form
@@ -934,13 +1223,12 @@ async fn create_google_form(
         .post("https://forms.googleapis.com/v1/forms")
         .bearer_auth(&access_token)
         .json(&body)
-        .send()
+        .send_with_retry()
         .await
-        .map_err(|e| format!("Failed to create form: {}", e))?;
+        .map_err(|e| AppError::Network(format!("Failed to create form: {}", e)))?;
     
     if !response.status().is_success() {
-         let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("Failed to create form: {}", error_text));
+        return Err(http::google_error(response, "Failed to create form").await);
     }
     
     let form: GoogleFormResponse = response
@@ -950,13 +1238,13 @@ async fn create_google_form(
         
     // 4. Move form to project folder
     if let Err(e) = move_file_to_folder(&client, &access_token, &form.form_id, &project_folder_id).await {
-        println!("Warning: Failed to organize form into folder: {}", e);
+        log_warning!("Failed to organize form into folder: {}", e);
     }
     
     // 5. Upload products.json if provided
     if let Some(json_content) = products_json {
         if let Err(e) = create_drive_file(&client, &access_token, "products.json", &json_content, "application/json", Some(&project_folder_id)).await {
-            println!("Warning: Failed to upload products.json: {}", e);
+            log_warning!("Failed to upload products.json: {}", e);
         }
     }
     
@@ -969,101 +1257,264 @@ async fn create_google_form(
 }
 
 #[tauri::command]
-async fn scan_project_folders(access_token: String) -> Result<Vec<ScannedProject>, String> {
-    let client = Client::new();
-    
-    // 1. Find root folder
-    let root_folder_id = match find_folder(&client, &access_token, "po-tracker").await? {
-        Some(id) => id,
-        None => return Ok(Vec::new()), // No root folder = no projects
-    };
-    
-    // 2. List subfolders
-    // Query: parent = root and mimeType = folder
-    let query = format!(
-        "'{}' in parents and mimeType='application/vnd.google-apps.folder' and trashed=false",
-        root_folder_id
-    );
-    
-    let response = client
-        .get("https://www.googleapis.com/drive/v3/files")
-        .query(&[("q", query.as_str())])
-        .bearer_auth(&access_token)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to list folders: {}", e))?;
-        
-    let list: DriveFileList = response.json().await.map_err(|e| format!("Failed to parse folder list: {}", e))?;
-    
-    let mut projects = Vec::new();
-    
-    // 3. For each folder, find the form and products.json
-    for folder in list.files {
-        // Find form
-        let form_query = format!(
-            "'{}' in parents and mimeType='application/vnd.google-apps.form' and trashed=false",
-            folder.id
-        );
-        let form_resp = client.get("https://www.googleapis.com/drive/v3/files").query(&[("q", form_query.as_str())]).bearer_auth(&access_token).send().await;
-        
-        let mut scanned_form = None;
-        if let Ok(resp) = form_resp {
-            if let Ok(form_list) = resp.json::<DriveFileList>().await {
-                if let Some(file) = form_list.files.first() {
-                    scanned_form = Some(ScannedForm {
-                        form_id: file.id.clone(),
-                        name: file.name.clone(),
-                        url: format!("https://docs.google.com/forms/d/{}/edit", file.id),
-                        responder_url: format!("https://docs.google.com/forms/d/{}/viewform", file.id),
-                    });
+#[tracing::instrument(skip_all, err)]
+async fn scan_project_folders(
+    cache: tauri::State<'_, GoogleCache>,
+    access_token: String,
+    operation_id: Option<String>,
+) -> Result<Vec<ScannedProject>, AppError> {
+    http::cancellable(operation_id, async move {
+        if let Some(projects) = cache.get(CacheKind::DriveListing, &access_token, "projects") {
+            return Ok(projects);
+        }
+        let client = http::client();
+
+        // 1. Find root folder
+        let root_folder_id = match find_folder(&client, &cache, &access_token, "po-tracker").await? {
+            Some(id) => id,
+            None => return Ok(Vec::new()), // No root folder = no projects
+        };
+
+        // 2. List subfolders
+        // Query: parent = root and mimeType = folder
+        let query = DriveQuery::new()
+            .in_parent(&root_folder_id)
+            .folder()
+            .trashed(false)
+            .build();
+
+        let request = client
+            .get("https://www.googleapis.com/drive/v3/files")
+            .query(&[("q", query.as_str())])
+            .bearer_auth(&access_token);
+        let list: DriveFileList = http::get_json_conditional(request, "Failed to list folders").await?;
+
+        // 3. Fetch metadata for the forms in every project folder in one batch
+        let folder_ids: Vec<String> = list.files.iter().map(|f| f.id.clone()).collect();
+        let mut forms_by_folder = fetch_form_metadata(&client, &access_token, &folder_ids).await?;
+
+        let mut projects = Vec::new();
+
+        // 4. For each folder, pair the form with its products.json
+        for folder in list.files {
+            let scanned_form = forms_by_folder.remove(&folder.id);
+            if scanned_form.is_none() {
+                continue;
+            }
+
+            // Find products.json
+            let json_query = DriveQuery::new()
+                .in_parent(&folder.id)
+                .name_equals("products.json")
+                .trashed(false)
+                .build();
+            let json_resp = client.get("https://www.googleapis.com/drive/v3/files").query(&[("q", json_query.as_str())]).bearer_auth(&access_token).send_with_retry().await;
+
+            let mut products_json_content = None;
+            if let Ok(resp) = json_resp {
+                if let Ok(json_list) = resp.json::<DriveFileList>().await {
+                     if let Some(file) = json_list.files.first() {
+                         // We found the file, maybe read it? 
+                         // Reading every file might be slow. For now let's just return the ID or maybe load on demand.
+                         // The requirement is "product information ... could be imported".
+                         // Let's store the file ID effectively? Or just read it if it's small.
+                         // Let's read it.
+                         if let Ok(content) = read_drive_file(access_token.clone(), file.id.clone(), None).await {
+                             products_json_content = Some(content);
+                         }
+                     }
                 }
             }
+
+            projects.push(ScannedProject {
+                folder_id: folder.id,
+                name: folder.name,
+                form: scanned_form,
+                products_json: products_json_content
+            });
         }
+
+        cache.put(CacheKind::DriveListing, &access_token, "projects", &projects);
+        Ok(projects)
+    })
+    .await
+}
+
+// Forms API publish-state lookups in flight at once while scanning folders
+const MAX_CONCURRENT_LOOKUPS: usize = 4;
+// Extra attempts for a lookup that failed with a retryable error
+const LOOKUP_RETRIES: u32 = 2;
+
+// Helper: Batch-fetch Drive metadata for the forms inside the given project folders,
+// keyed by folder ID. Parents are chunked to keep the `q` string within Drive's limits.
+async fn fetch_form_metadata(
+    client: &Client,
+    access_token: &str,
+    folder_ids: &[String],
+) -> Result<HashMap<String, ScannedForm>, AppError> {
+    let mut files = Vec::new();
+    
+    for chunk in folder_ids.chunks(40) {
+        let query = DriveQuery::new()
+            .in_any_parent(chunk)
+            .mime_type("application/vnd.google-apps.form")
+            .trashed(false)
+            .build();
         
-        // Find products.json
-        let json_query = format!(
-            "'{}' in parents and name='products.json' and trashed=false",
-            folder.id
-        );
-        let json_resp = client.get("https://www.googleapis.com/drive/v3/files").query(&[("q", json_query.as_str())]).bearer_auth(&access_token).send().await;
-
-        let mut products_json_content = None;
-        if let Ok(resp) = json_resp {
-            if let Ok(json_list) = resp.json::<DriveFileList>().await {
-                 if let Some(file) = json_list.files.first() {
-                     // We found the file, maybe read it? 
-                     // Reading every file might be slow. For now let's just return the ID or maybe load on demand.
-                     // The requirement is "product information ... could be imported".
-                     // Let's store the file ID effectively? Or just read it if it's small.
-                     // Let's read it.
-                     if let Ok(content) = read_drive_file(access_token.clone(), file.id.clone()).await {
-                         products_json_content = Some(content);
-                     }
-                 }
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut params = vec![
+                ("q", query.clone()),
+                ("fields", "nextPageToken,files(id,name,parents,modifiedTime,webViewLink,owners(emailAddress,displayName))".to_string()),
+                ("pageSize", "1000".to_string()),
+            ];
+            if let Some(token) = &page_token {
+                params.push(("pageToken", token.clone()));
+            }
+            
+            let response = client
+                .get("https://www.googleapis.com/drive/v3/files")
+                .query(&params)
+                .bearer_auth(access_token)
+                .send_with_retry()
+                .await
+                .map_err(|e| AppError::Network(format!("Failed to list forms: {}", e)))?;
+            
+            if !response.status().is_success() {
+                return Err(http::google_error(response, "Drive API error").await);
+            }
+            
+            let list: DriveFormMetadataList = response
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse form list: {}", e))?;
+            
+            files.extend(list.files);
+            
+            match list.next_page_token {
+                Some(token) => page_token = Some(token),
+                None => break,
             }
         }
-        
-        if scanned_form.is_some() {
-             projects.push(ScannedProject {
-                 folder_id: folder.id,
-                 name: folder.name,
-                 form: scanned_form,
-                 products_json: products_json_content
-             });
+    }
+    
+    // Publish state lives in the Forms API, so look it up per form,
+    // MAX_CONCURRENT_LOOKUPS at a time
+    let slots = std::sync::Arc::new(tokio::sync::Semaphore::new(MAX_CONCURRENT_LOOKUPS));
+    let mut lookups = tokio::task::JoinSet::new();
+    for file in &files {
+        let client = client.clone();
+        let access_token = access_token.to_string();
+        let form_id = file.id.clone();
+        let slots = slots.clone();
+        lookups.spawn(async move {
+            let _slot = slots.acquire_owned().await;
+            let mut attempt = 0;
+            let accepting = loop {
+                match fetch_accepting_responses(&client, &access_token, &form_id).await {
+                    Ok(accepting) => break accepting,
+                    Err(e) if e.retryable() && attempt < LOOKUP_RETRIES => {
+                        attempt += 1;
+                        tokio::time::sleep(std::time::Duration::from_secs(attempt as u64)).await;
+                    }
+                    // Publish state is optional; the form is listed without it
+                    Err(e) => {
+                        log_warning!("Failed to get publish state of form {}: {}", form_id, e);
+                        break None;
+                    }
+                }
+            };
+            (form_id, accepting)
+        });
+    }
+    
+    let mut accepting_by_form = HashMap::new();
+    while let Some(result) = lookups.join_next().await {
+        if let Ok((form_id, accepting)) = result {
+            accepting_by_form.insert(form_id, accepting);
         }
     }
     
-    Ok(projects)
+    let mut forms_by_folder = HashMap::new();
+    for file in files {
+        let folder_id = match file.parents.as_ref().and_then(|p| p.first()) {
+            Some(id) => id.clone(),
+            None => continue,
+        };
+        if forms_by_folder.contains_key(&folder_id) {
+            continue;
+        }
+        
+        let owners = file
+            .owners
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|o| o.email_address.or(o.display_name))
+            .collect();
+        
+        forms_by_folder.insert(folder_id, ScannedForm {
+            url: format!("https://docs.google.com/forms/d/{}/edit", file.id),
+            responder_url: format!("https://docs.google.com/forms/d/{}/viewform", file.id),
+            accepting_responses: accepting_by_form.get(&file.id).copied().flatten(),
+            form_id: file.id,
+            name: file.name,
+            modified_time: file.modified_time,
+            web_view_link: file.web_view_link,
+            owners,
+        });
+    }
+    
+    Ok(forms_by_folder)
+}
+
+// Helper: Whether a form is currently accepting responses (None if the form
+// doesn't report it)
+async fn fetch_accepting_responses(client: &Client, access_token: &str, form_id: &str) -> Result<Option<bool>, AppError> {
+    let response = client
+        .get(format!("https://forms.googleapis.com/v1/forms/{}", form_id))
+        .query(&[("fields", "publishSettings")])
+        .bearer_auth(access_token)
+        .send_with_retry()
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to get form: {}", e)))?;
+    
+    if !response.status().is_success() {
+        return Err(http::google_error(response, "Forms API error").await);
+    }
+    
+    let info: FormPublishInfo = response
+        .json()
+        .await
+        .map_err(|e| {
+            let message = format!("Failed to read form: {}", e);
+            if e.is_decode() {
+                AppError::Internal(message)
+            } else {
+                AppError::Network(message)
+            }
+        })?;
+    Ok(info
+        .publish_settings
+        .and_then(|s| s.publish_state)
+        .and_then(|s| s.is_accepting_responses))
 }
 
 // Add questions to a Google Form
 #[tauri::command]
+#[tracing::instrument(skip_all, fields(form_id = %form_id, questions = questions.len()), err)]
 async fn add_form_questions(
+    db: tauri::State<'_, db::Database>,
+    cache: tauri::State<'_, GoogleCache>,
     access_token: String,
     form_id: String,
     questions: Vec<serde_json::Value>,
-) -> Result<String, String> {
-    let client = Client::new();
+) -> Result<String, AppError> {
+    let money = {
+        let mut conn = db.pool.acquire().await
+            .map_err(|e| format!("Failed to open database connection: {}", e))?;
+        money::money_format(&mut conn, None).await?
+    };
+    let client = http::client();
     
     // Build batch update request
     let mut requests: Vec<serde_json::Value> = vec![
@@ -1110,7 +1561,7 @@ async fn add_form_questions(
         let base_description = if let Some(desc) = question["description_override"].as_str() {
             desc.to_string()
         } else {
-            format!("Price: ${:.2}", question["price"].as_f64().unwrap_or(0.0))
+            format!("Price: {}", money.format(question["price"].as_f64().unwrap_or(0.0)))
         };
         // Append numbers-only hint to the description
         let description = format!("{}\n⚠️ Enter numbers only (e.g. 0, 1, 2, 3). Enter 0 if you don't want this item.", base_description);
@@ -1164,42 +1615,33 @@ async fn add_form_questions(
         .post(format!("https://forms.googleapis.com/v1/forms/{}:batchUpdate", form_id))
         .bearer_auth(&access_token)
         .json(&body)
-        .send()
+        .send_with_retry()
         .await
-        .map_err(|e| format!("Failed to add questions: {}", e))?;
+        .map_err(|e| AppError::Network(format!("Failed to add questions: {}", e)))?;
     
     if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("Failed to add questions: {}", error_text));
+        return Err(http::google_error(response, "Failed to add questions").await);
     }
+    cache.invalidate(CacheKind::FormDetails, Some(&form_id));
     
     Ok("Questions added successfully".to_string())
 }
 
 // Get form responses
 #[tauri::command]
+#[tracing::instrument(skip_all, fields(form_id = %form_id), err)]
 async fn get_form_responses(
     access_token: String,
     form_id: String,
-) -> Result<FormResponsesData, String> {
-    let client = Client::new();
-    
-    let response = client
-        .get(format!("https://forms.googleapis.com/v1/forms/{}/responses", form_id))
-        .bearer_auth(&access_token)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to get responses: {}", e))?;
-    
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("Failed to get responses: {}", error_text));
-    }
-    
-    response
-        .json::<FormResponsesData>()
-        .await
-        .map_err(|e| format!("Failed to parse responses: {}", e))
+    operation_id: Option<String>,
+) -> Result<FormResponsesData, AppError> {
+    http::cancellable(operation_id, async move {
+        let request = http::client()
+            .get(format!("https://forms.googleapis.com/v1/forms/{}/responses", form_id))
+            .bearer_auth(&access_token);
+        http::get_json_conditional(request, "Failed to get responses").await
+    })
+    .await
 }
 
 mod urlencoding {
@@ -1216,28 +1658,26 @@ mod urlencoding {
 
 // Get form details (schema)
 #[tauri::command]
+#[tracing::instrument(skip_all, fields(form_id = %form_id), err)]
 async fn get_form_details(
+    cache: tauri::State<'_, GoogleCache>,
     access_token: String,
     form_id: String,
-) -> Result<GoogleFormDetails, String> {
-    let client = Client::new();
-
-    let response = client
-        .get(format!("https://forms.googleapis.com/v1/forms/{}", form_id))
-        .bearer_auth(&access_token)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to get form details: {}", e))?;
-
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("Failed to get form details: {}", error_text));
-    }
-
-    response
-        .json::<GoogleFormDetails>()
-        .await
-        .map_err(|e| format!("Failed to parse form details: {}", e))
+    operation_id: Option<String>,
+) -> Result<GoogleFormDetails, AppError> {
+    http::cancellable(operation_id, async move {
+        if let Some(details) = cache.get(CacheKind::FormDetails, &access_token, &form_id) {
+            return Ok(details);
+        }
+        let request = http::client()
+            .get(format!("https://forms.googleapis.com/v1/forms/{}", form_id))
+            .bearer_auth(&access_token);
+        let details: GoogleFormDetails =
+            http::get_json_conditional(request, "Failed to get form details").await?;
+        cache.put(CacheKind::FormDetails, &access_token, &form_id, &details);
+        Ok(details)
+    })
+    .await
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -1247,7 +1687,8 @@ pub fn run() {
         .plugin(tauri_plugin_sql::Builder::default().build())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
-        .plugin(tauri_plugin_fs::init());
+        .plugin(tauri_plugin_fs::init())
+        .plugin(tauri_plugin_notification::init());
 
     #[cfg(mobile)]
     {
@@ -1255,8 +1696,52 @@ pub fn run() {
     }
 
     builder
+        .setup(|app| {
+            if let Err(e) = logging::init(app.handle()) {
+                eprintln!("Failed to start logging: {}", e);
+            }
+            match backup::apply_pending_restore(app.handle()) {
+                Ok(true) => log_info!("Restored database from backup"),
+                Ok(false) => {}
+                Err(e) => log_warning!("Failed to apply pending restore: {}", e),
+            }
+            
+            let database = tauri::async_runtime::block_on(db::connect(app.handle()))?;
+            if let Err(e) = tauri::async_runtime::block_on(logging::apply_saved_level(&database.pool)) {
+                log_warning!("Failed to apply log level: {}", e);
+            }
+            if let Err(e) = tauri::async_runtime::block_on(http::apply_saved(&database.pool)) {
+                log_warning!("Failed to apply network settings: {}", e);
+            }
+            tauri::async_runtime::block_on(encryption::init(&database.pool))?;
+            tauri::async_runtime::block_on(session_lock::init(&database.pool))?;
+            app.manage(database);
+            app.manage(tasks::TaskRegistry::default());
+            app.manage(GoogleCache::default());
+            throttle::init(app.handle());
+            recurring_orders::start_scheduler(app.handle().clone());
+            payment_reminders::start_reminder_engine(app.handle().clone());
+            archive::start_auto_archive(app.handle().clone());
+            outgoing_webhooks::start_dispatcher(app.handle().clone());
+            telegram::start_telegram_sender(app.handle().clone());
+            whatsapp::start_whatsapp_sender(app.handle().clone());
+            sms::start_sms_sender(app.handle().clone());
+            chat_alerts::start_chat_alert_sender(app.handle().clone());
+            desktop_notifications::start_desktop_notifier(app.handle().clone());
+            auto_backup::start_auto_backups(app.handle().clone());
+            device_sync::start_device_sync(app.handle().clone());
+            app.manage(health::LastHealthReport::default());
+            health::start_health_check(app.handle().clone());
+            app.manage(webhook_receiver::WebhookServer::default());
+            webhook_receiver::start_receiver(app.handle().clone());
+            session_lock::start_idle_lock(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
-            generate_confirmation_code,
+            confirmation_codes::generate_confirmation_code,
+            confirmation_codes::lookup_order_by_confirmation_code,
+            confirmation_codes::regenerate_confirmation_code,
+            confirmation_codes::set_confirmation_checksum,
             send_invoice_email,
             send_gmail_email,
             start_oauth_flow,
@@ -1272,7 +1757,263 @@ pub fn run() {
             upload_product_image,
             add_form_questions,
             get_form_responses,
-            get_form_details
+            get_form_details,
+            drive::share_drive_file,
+            drive::list_permissions,
+            drive::remove_permission,
+            drive::search_drive,
+            drive::poll_drive_changes,
+            drive::trash_file,
+            drive::untrash_file,
+            drive::list_trashed_app_files,
+            drive::get_drive_quota,
+            drive::upload_file_to_drive,
+            drive::get_drive_thumbnail,
+            backup::backup_to_drive,
+            backup::list_drive_backups,
+            backup::restore_from_drive,
+            products::list_products,
+            products::get_product,
+            products::create_product,
+            products::update_product,
+            products::delete_product,
+            customers::list_customers,
+            customers::get_customer,
+            customers::create_customer,
+            customers::update_customer,
+            customers::delete_customer,
+            customers::restore_customer,
+            customers::search_customers,
+            customers::get_customer_orders,
+            customers::merge_customers,
+            orders::list_orders,
+            orders::get_order,
+            orders::create_order,
+            orders::update_order,
+            orders::delete_order,
+            orders::restore_order,
+            order_status::transition_order,
+            invoice_numbers::get_invoice_numbering,
+            invoice_numbers::set_invoice_number_pattern,
+            inventory::adjust_stock,
+            inventory::set_low_stock_threshold,
+            inventory::get_stock_levels,
+            inventory::get_stock_movements,
+            payments::record_payment,
+            payments::list_payments,
+            payments::get_outstanding_balances,
+            search::search_all,
+            audit::get_audit_log,
+            pricing::get_price_history,
+            pricing::schedule_price_change,
+            totals::calculate_order_totals,
+            totals::get_tax_settings,
+            totals::set_tax_settings,
+            currency::fetch_exchange_rates,
+            currency::convert_amount,
+            recurring_orders::create_recurring_order,
+            recurring_orders::list_recurring_orders,
+            recurring_orders::pause_recurring_order,
+            recurring_orders::resume_recurring_order,
+            timeline::add_order_note,
+            timeline::delete_order_note,
+            timeline::record_order_email,
+            timeline::get_order_timeline,
+            backup::backup_database,
+            backup::restore_database,
+            auto_backup::get_auto_backup_settings,
+            auto_backup::set_auto_backup_settings,
+            auto_backup::run_backup_now,
+            auto_backup::list_backups,
+            auto_backup::restore_backup,
+            device_sync::get_sync_status,
+            device_sync::enable_sync,
+            device_sync::disable_sync,
+            device_sync::sync_now,
+            device_sync::list_sync_conflicts,
+            device_sync::resolve_sync_conflict,
+            health::run_health_check,
+            health::get_last_health_report,
+            tasks::list_active_tasks,
+            tasks::get_task,
+            tasks::cancel_task,
+            automation::get_automation_settings,
+            automation::set_automation_settings,
+            automation::get_automation_status,
+            settings::get_smtp_config,
+            settings::save_smtp_config,
+            settings::get_oauth_client,
+            settings::save_oauth_client,
+            settings::get_business_profile,
+            settings::save_business_profile,
+            settings::get_email_template,
+            settings::save_email_template,
+            settings::get_network_config,
+            settings::save_network_config,
+            encryption::get_encryption_status,
+            encryption::enable_encryption,
+            encryption::unlock_encryption,
+            encryption::change_encryption_passphrase,
+            encryption::disable_encryption,
+            customer_data::export_customer_data,
+            customer_data::erase_customer,
+            session_lock::get_session_status,
+            session_lock::touch_session,
+            session_lock::lock_session,
+            session_lock::unlock_session,
+            session_lock::set_session_lock_settings,
+            session_lock::set_session_pin,
+            logging::get_log_settings,
+            logging::set_log_level,
+            logging::get_recent_logs,
+            logging::export_logs_zip,
+            diagnostics::get_api_diagnostics,
+            diagnostics::set_api_diagnostics,
+            diagnostics::clear_api_diagnostics,
+            http::cancel_operation,
+            google_cache::clear_google_cache,
+            throttle::get_quota_pressure,
+            bulk_email::send_bulk_email,
+            form_responses::sync_form_responses,
+            form_responses::sync_all_watched_forms,
+            form_responses::stream_form_responses,
+            archive::archive_orders_before,
+            archive::restore_archived_order,
+            archive::list_archived_orders,
+            archive::get_archive_settings,
+            archive::set_archive_settings,
+            supplier_orders::create_supplier,
+            supplier_orders::list_suppliers,
+            supplier_orders::set_product_supplier,
+            supplier_orders::get_open_demand,
+            supplier_orders::generate_supplier_order,
+            supplier_orders::list_supplier_orders,
+            supplier_orders::get_supplier_order,
+            supplier_orders::set_supplier_item_cost,
+            supplier_orders::set_supplier_order_status,
+            fulfillment::get_fulfillment,
+            fulfillment::update_fulfillment,
+            fulfillment::set_fulfillment_stage,
+            fulfillment::fulfill_event_orders,
+            fulfillment::get_packing_list,
+            fulfillment::verify_pickup,
+            barcodes::lookup_product_by_barcode,
+            barcodes::generate_product_barcodes,
+            supplier_orders::add_supplier_item_by_barcode,
+            labels::render_barcode_label,
+            barcode_lookup::lookup_barcode_online,
+            pickup_queue::queue_pickup_scan,
+            pickup_queue::list_pickup_queue,
+            pickup_queue::sync_pickup_queue,
+            payment_ocr::scan_payment_proof,
+            documents::attach_document,
+            documents::list_order_documents,
+            documents::read_order_document,
+            documents::delete_order_document,
+            payment_links::list_payment_links,
+            stripe::create_payment_link,
+            stripe::sync_stripe_payments,
+            paypal::create_paypal_invoice,
+            paypal::get_paypal_invoice_status,
+            midtrans::create_midtrans_payment,
+            midtrans::sync_midtrans_payments,
+            qris::get_invoice_qris,
+            bank_statement::reconcile_bank_statement,
+            payment_reminders::list_reminder_rules,
+            payment_reminders::create_reminder_rule,
+            payment_reminders::update_reminder_rule,
+            payment_reminders::delete_reminder_rule,
+            payment_reminders::list_pending_reminders,
+            payment_reminders::send_payment_reminder,
+            payment_reminders::mark_payment_reminder_sent,
+            payment_reminders::skip_payment_reminder,
+            payment_reminders::get_order_reminders,
+            payment_reminders::set_order_reminders,
+            payments::record_refund,
+            payments::list_refunds,
+            credit_notes::create_credit_note,
+            credit_notes::list_credit_notes,
+            credit_notes::export_credit_note_pdf,
+            deposits::set_order_deposit,
+            deposits::issue_deposit_invoice,
+            deposits::export_deposit_invoice_pdf,
+            quotes::create_quote,
+            quotes::list_quotes,
+            quotes::get_quote,
+            quotes::convert_quote_to_order,
+            quotes::withdraw_quote,
+            webhook_receiver::get_webhook_receiver_settings,
+            webhook_receiver::set_webhook_receiver_settings,
+            webhook_receiver::list_webhook_sources,
+            webhook_receiver::create_webhook_source,
+            webhook_receiver::update_webhook_source,
+            webhook_receiver::delete_webhook_source,
+            webhook_receiver::list_webhook_events,
+            webhook_receiver::mark_webhook_event_handled,
+            outgoing_webhooks::list_webhook_endpoints,
+            outgoing_webhooks::create_webhook_endpoint,
+            outgoing_webhooks::update_webhook_endpoint,
+            outgoing_webhooks::delete_webhook_endpoint,
+            outgoing_webhooks::list_webhook_deliveries,
+            outgoing_webhooks::list_webhook_delivery_attempts,
+            outgoing_webhooks::retry_webhook_delivery,
+            telegram::get_telegram_settings,
+            telegram::set_telegram_settings,
+            telegram::send_telegram_notification,
+            whatsapp::get_whatsapp_settings,
+            whatsapp::set_whatsapp_settings,
+            whatsapp::list_whatsapp_messages,
+            whatsapp::retry_whatsapp_message,
+            sms::get_sms_settings,
+            sms::set_sms_settings,
+            sms::send_test_sms,
+            sms::list_sms_messages,
+            sms::retry_sms_message,
+            chat_alerts::get_chat_alert_settings,
+            chat_alerts::set_chat_alert_settings,
+            chat_alerts::send_test_chat_alert,
+            desktop_notifications::get_desktop_notification_settings,
+            desktop_notifications::set_desktop_notification_settings,
+            desktop_notifications::send_test_desktop_notification,
+            profiles::list_profiles,
+            profiles::get_active_profile,
+            profiles::create_profile,
+            profiles::rename_profile,
+            profiles::switch_profile,
+            profiles::delete_profile,
+            events::delete_event,
+            bulk_orders::bulk_update_orders,
+            dashboard::get_dashboard_stats,
+            reports::run_report,
+            reports::export_report_csv,
+            reports::export_report_pdf,
+            expenses::record_expense,
+            expenses::list_expenses,
+            expenses::delete_expense,
+            customer_analytics::get_customer_analytics,
+            demand::aggregate_demand,
+            csv_export::export_orders_csv,
+            csv_export::export_form_responses_csv,
+            product_import::import_products,
+            xlsx_export::export_orders_xlsx,
+            sheets::export_orders_to_sheet,
+            sheets::import_orders_from_sheet,
+            data_export::export_all_data,
+            data_export::import_all_data,
+            data_export::verify_data_export,
+            contacts::import_google_contacts,
+            contacts::preview_contacts_file,
+            contacts::import_contacts_file,
+            invoices::export_invoices_pdf,
+            packing::generate_packing_documents,
+            invoice_template::get_invoice_template,
+            invoice_template::save_invoice_template,
+            invoice_template::preview_invoice_template,
+            i18n::get_locale,
+            i18n::set_locale,
+            money::get_money_format,
+            legacy_import::migrate_legacy_orders,
+            migrations::get_schema_version
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
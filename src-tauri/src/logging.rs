@@ -0,0 +1,323 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, Sqlite, SqlitePool};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tauri::{AppHandle, Manager, State};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, Registry};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::audit;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::redact::redact;
+use crate::session_lock;
+
+// Log files live in <app data>/logs as potracker.YYYY-MM-DD.log
+const LOG_DIR: &str = "logs";
+const LOG_PREFIX: &str = "potracker";
+const LOG_SUFFIX: &str = "log";
+
+// Days of log files kept; older ones are deleted as the file rotates
+const MAX_LOG_FILES: usize = 7;
+
+// Target of every event logged by this app, whatever the module
+const APP_TARGET: &str = "tauri_app_lib";
+
+const LEVELS: [&str; 5] = ["error", "warn", "info", "debug", "trace"];
+const DEFAULT_LEVEL: &str = "info";
+
+// Lines returned by get_recent_logs unless asked for fewer or more
+const DEFAULT_RECENT_LINES: usize = 200;
+const MAX_RECENT_LINES: usize = 5000;
+
+// Set once by init: changes the level without restarting, and keeps the
+// background file writer alive for the life of the app
+static FILTER: OnceLock<reload::Handle<Targets, Registry>> = OnceLock::new();
+static WRITER_GUARD: OnceLock<WorkerGuard> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct LogSettings {
+    pub level: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogExport {
+    pub path: String,
+    pub files: usize,
+}
+
+// Helper: Writer that redacts every formatted event before writing it, so
+// fields recorded by tracing macros get the same treatment as log_info!
+struct Redacting<W>(W);
+
+impl<W: Write> Write for Redacting<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0
+            .write_all(redact(&String::from_utf8_lossy(buf)).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.flush()
+    }
+}
+
+struct RedactingWriter<M>(M);
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for RedactingWriter<M> {
+    type Writer = Redacting<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        Redacting(self.0.make_writer())
+    }
+}
+
+// Helper: Folder holding the log files
+fn log_dir(app: &AppHandle) -> Result<PathBuf, AppError> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?;
+    Ok(dir.join(LOG_DIR))
+}
+
+// Helper: Log files, oldest first (the date in the name sorts them)
+fn log_files(app: &AppHandle) -> Result<Vec<PathBuf>, AppError> {
+    let dir = log_dir(app)?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut files: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .map_err(|e| format!("Failed to read log folder: {}", e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    name.starts_with(LOG_PREFIX) && name.ends_with(LOG_SUFFIX)
+                })
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+// Helper: Filter logging this app at `level` and other libraries at warn
+fn targets(level: LevelFilter) -> Targets {
+    Targets::new()
+        .with_default(LevelFilter::WARN)
+        .with_target(APP_TARGET, level)
+}
+
+// Helper: Parse a level name from the settings
+fn parse_level(level: &str) -> Result<LevelFilter, AppError> {
+    if !LEVELS.contains(&level) {
+        return Err(AppError::Validation(format!(
+            "Log level must be one of: {}",
+            LEVELS.join(", ")
+        )));
+    }
+    level
+        .parse::<LevelFilter>()
+        .map_err(|e| AppError::Validation(format!("Invalid log level {}: {}", level, e)))
+}
+
+// Helper: Switch the running subscriber to `level`
+fn apply_level(level: LevelFilter) -> Result<(), AppError> {
+    let Some(filter) = FILTER.get() else {
+        return Ok(());
+    };
+    filter
+        .reload(targets(level))
+        .map_err(|e| AppError::Internal(format!("Failed to change log level: {}", e)))
+}
+
+// Start writing log_info!, log_warning! and tracing events to a daily log
+// file and stdout. Called first thing on startup, before the database is
+// open; apply_saved_level then sets the level from the settings.
+pub fn init(app: &AppHandle) -> Result<(), AppError> {
+    let dir = log_dir(app)?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create log folder: {}", e))?;
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_PREFIX)
+        .filename_suffix(LOG_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(&dir)
+        .map_err(|e| format!("Failed to open log file: {}", e))?;
+    let (file_writer, guard) = tracing_appender::non_blocking(appender);
+
+    let (filter, handle) = reload::Layer::new(targets(LevelFilter::INFO));
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(
+            fmt::layer()
+                .with_ansi(false)
+                .with_writer(RedactingWriter(file_writer)),
+        )
+        .with(fmt::layer().with_writer(RedactingWriter(std::io::stdout)))
+        .try_init()
+        .map_err(|e| format!("Failed to start logging: {}", e))?;
+
+    let _ = FILTER.set(handle);
+    let _ = WRITER_GUARD.set(guard);
+    Ok(())
+}
+
+// Helper: Saved settings, the defaults if the row is missing
+async fn load_settings<'e, E>(executor: E) -> Result<LogSettings, AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    Ok(
+        sqlx::query_as::<_, LogSettings>("SELECT level FROM log_settings WHERE id = 1")
+            .fetch_optional(executor)
+            .await
+            .map_err(|e| format!("Failed to load log settings: {}", e))?
+            .unwrap_or(LogSettings {
+                level: DEFAULT_LEVEL.to_string(),
+            }),
+    )
+}
+
+// Use the saved log level. Called on startup once the database is open.
+pub async fn apply_saved_level(pool: &SqlitePool) -> Result<(), AppError> {
+    let settings = load_settings(pool).await?;
+    apply_level(parse_level(&settings.level)?)
+}
+
+#[tauri::command]
+pub async fn get_log_settings(db: State<'_, Database>) -> Result<LogSettings, AppError> {
+    load_settings(&db.pool).await
+}
+
+// Change the log level; takes effect straight away
+#[tauri::command]
+pub async fn set_log_level(
+    db: State<'_, Database>,
+    level: String,
+) -> Result<LogSettings, AppError> {
+    let level = level.trim().to_lowercase();
+    let filter = parse_level(&level)?;
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_settings(&mut *tx).await?;
+    sqlx::query(
+        "INSERT INTO log_settings (id, level) VALUES (1, ?) \
+         ON CONFLICT(id) DO UPDATE SET level = excluded.level, updated_at = CURRENT_TIMESTAMP",
+    )
+    .bind(&level)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save log settings: {}", e))?;
+    let after = load_settings(&mut *tx).await?;
+
+    audit::record(
+        &mut *tx,
+        "log_settings",
+        1,
+        "update",
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save log settings: {}", e))?;
+
+    apply_level(filter)?;
+    tracing::info!(level = %after.level, "Log level changed");
+    Ok(after)
+}
+
+// The last `limit` lines logged (200 by default), oldest first, for showing
+// in a support screen
+#[tauri::command]
+pub fn get_recent_logs(app: AppHandle, limit: Option<usize>) -> Result<Vec<String>, AppError> {
+    session_lock::ensure_unlocked()?;
+    let limit = limit.unwrap_or(DEFAULT_RECENT_LINES).min(MAX_RECENT_LINES);
+
+    let mut lines: Vec<String> = Vec::new();
+    for path in log_files(&app)?.iter().rev() {
+        if lines.len() >= limit {
+            break;
+        }
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let wanted = limit - lines.len();
+        let mut newest: Vec<String> = text
+            .lines()
+            .rev()
+            .take(wanted)
+            .map(str::to_string)
+            .collect();
+        newest.reverse();
+        newest.append(&mut lines);
+        lines = newest;
+    }
+    Ok(lines)
+}
+
+// Helper: Zip the log files into `target`
+fn write_zip(files: &[PathBuf], target: &Path) -> Result<(), AppError> {
+    let file = std::fs::File::create(target)
+        .map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for path in files {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let bytes =
+            std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        zip.start_file(name, options)
+            .map_err(|e| format!("Failed to add log to zip: {}", e))?;
+        zip.write_all(&bytes)
+            .map_err(|e| format!("Failed to add log to zip: {}", e))?;
+    }
+    zip.finish()
+        .map_err(|e| format!("Failed to write zip: {}", e))?;
+    Ok(())
+}
+
+// Zip every log file into `dest_path` to attach to a support request
+#[tauri::command]
+pub fn export_logs_zip(app: AppHandle, dest_path: String) -> Result<LogExport, AppError> {
+    session_lock::ensure_unlocked()?;
+    let files = log_files(&app)?;
+    if files.is_empty() {
+        return Err(AppError::NotFound("No log files have been written yet".to_string()));
+    }
+
+    // Write alongside and move into place so a failed export can't clobber an older file
+    let dest = PathBuf::from(&dest_path);
+    let partial = dest.with_extension(format!("partial-{}", uuid::Uuid::new_v4()));
+    let written = write_zip(&files, &partial).and_then(|_| {
+        std::fs::rename(&partial, &dest)
+            .map_err(|e| AppError::Internal(format!("Failed to save zip: {}", e)))
+    });
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e);
+    }
+
+    Ok(LogExport {
+        path: dest_path,
+        files: files.len(),
+    })
+}
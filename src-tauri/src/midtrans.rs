@@ -0,0 +1,342 @@
+use reqwest::{Client, RequestBuilder};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tauri::{AppHandle, State};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::error::AppError;
+use crate::http;
+use crate::payment_links::{
+    insert_payment_link, order_balance, reuse_open_link, sync_payment_links, LinkState,
+    NewPaymentLink, PaymentGateway, PaymentLink, PaymentSync,
+};
+use crate::redact::redact;
+
+const MIDTRANS_API: &str = "https://api.midtrans.com/v2";
+const MIDTRANS_SANDBOX_API: &str = "https://api.sandbox.midtrans.com/v2";
+
+// Midtrans only settles in rupiah
+const MIDTRANS_CURRENCY: &str = "IDR";
+const DEFAULT_EXPIRY_HOURS: u32 = 24;
+// Banks Midtrans issues virtual accounts for
+const VA_BANKS: &[&str] = &["bca", "bni", "bri", "permata", "cimb"];
+// Midtrans reports times in Jakarta time (WIB, UTC+7)
+const WIB_OFFSET_SECONDS: i32 = 7 * 3600;
+
+// Server key from the Midtrans dashboard. Like the other gateways' keys, it's
+// passed in on every call and never stored.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MidtransCredentials {
+    pub server_key: String,
+    // Use the sandbox instead of live payments
+    #[serde(default)]
+    pub sandbox: bool,
+}
+
+// How the customer pays: a bank virtual account to transfer into, or a QRIS
+// code any Indonesian e-wallet or banking app can scan
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MidtransMethod {
+    // bca, bni, bri, permata or cimb
+    BankTransfer { bank: String },
+    Qris,
+}
+
+impl MidtransMethod {
+    // Stored as payment_links.method, e.g. 'bank_transfer:bca'
+    fn key(&self) -> String {
+        match self {
+            MidtransMethod::BankTransfer { bank } => {
+                format!("bank_transfer:{}", bank.trim().to_lowercase())
+            }
+            MidtransMethod::Qris => "qris".to_string(),
+        }
+    }
+}
+
+// Midtrans Core API access with a server key
+struct Midtrans {
+    client: Client,
+    base: &'static str,
+    server_key: String,
+}
+
+impl Midtrans {
+    fn new(credentials: &MidtransCredentials) -> Result<Midtrans, AppError> {
+        let server_key = credentials.server_key.trim();
+        if server_key.is_empty() {
+            return Err(AppError::Validation(
+                "Midtrans server key is required".to_string(),
+            ));
+        }
+        Ok(Midtrans {
+            client: http::client(),
+            base: if credentials.sandbox {
+                MIDTRANS_SANDBOX_API
+            } else {
+                MIDTRANS_API
+            },
+            server_key: server_key.to_string(),
+        })
+    }
+
+    // Send a request and return the JSON body. Midtrans answers most errors
+    // with HTTP 200 and its own status_code, so both are checked.
+    async fn send(&self, request: RequestBuilder, action: &str) -> Result<Value, AppError> {
+        let response = request
+            .basic_auth(&self.server_key, Some(""))
+            .header("Accept", "application/json")
+            .send()
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to reach Midtrans: {}", e)))?;
+        let status = response.status();
+        let text = redact(&response.text().await.unwrap_or_default());
+        let body = serde_json::from_str::<Value>(&text).unwrap_or(Value::Null);
+        let status_code = body["status_code"].as_str().unwrap_or("200");
+        if !status.is_success() || !status_code.starts_with('2') {
+            let message = body["status_message"].as_str().unwrap_or(&text);
+            // Midtrans reports some failures as HTTP 200 with the real code in the body
+            let code = if status.is_success() {
+                status_code.parse().unwrap_or(500)
+            } else {
+                status.as_u16()
+            };
+            return Err(AppError::from_status(
+                code,
+                format!("Failed to {}: {}", action, message),
+            ));
+        }
+        Ok(body)
+    }
+}
+
+impl PaymentGateway for Midtrans {
+    const PROVIDER: &'static str = "midtrans";
+
+    async fn link_state(&self, link: &PaymentLink) -> Result<LinkState, AppError> {
+        let response = self
+            .client
+            .get(format!("{}/{}/status", self.base, link.external_id))
+            .basic_auth(&self.server_key, Some(""))
+            .header("Accept", "application/json")
+            .send()
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to reach Midtrans: {}", e)))?;
+        let body = response
+            .json::<Value>()
+            .await
+            .map_err(|e| format!("Failed to parse Midtrans response: {}", e))?;
+        match body["transaction_status"].as_str().unwrap_or_default() {
+            "settlement" | "capture" => Ok(LinkState::Paid {
+                amount: body["gross_amount"]
+                    .as_str()
+                    .and_then(|amount| amount.parse::<f64>().ok())
+                    .unwrap_or(link.amount),
+                reference: body["transaction_id"]
+                    .as_str()
+                    .unwrap_or(&link.external_id)
+                    .to_string(),
+                paid_at: utc_from_wib(
+                    body["settlement_time"]
+                        .as_str()
+                        .or(body["transaction_time"].as_str()),
+                ),
+            }),
+            "expire" | "cancel" | "deny" | "failure" => Ok(LinkState::Cancelled),
+            "pending" => Ok(LinkState::Open),
+            _ => Err(AppError::Internal(format!(
+                "Failed to check Midtrans payment: {}",
+                body["status_message"].as_str().unwrap_or("unknown status")
+            ))),
+        }
+    }
+
+    async fn withdraw(&self, link: &PaymentLink) -> Result<(), AppError> {
+        self.send(
+            self.client
+                .post(format!("{}/{}/cancel", self.base, link.external_id)),
+            "cancel Midtrans payment",
+        )
+        .await
+        .map(|_| ())
+    }
+}
+
+// Helper: A Midtrans time ("2026-10-12 14:03:11", WIB) in the format payments
+// are stored in (UTC); now if it's missing
+fn utc_from_wib(time: Option<&str>) -> String {
+    let parsed = time
+        .and_then(|time| chrono::NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S").ok())
+        .map(|local| local - chrono::Duration::seconds(WIB_OFFSET_SECONDS as i64));
+    parsed
+        .unwrap_or_else(|| chrono::Utc::now().naive_utc())
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+// Helper: The virtual account number Midtrans assigned. Permata has its own
+// field; the other banks list theirs under va_numbers.
+fn va_number(charge: &Value) -> Option<String> {
+    charge["permata_va_number"]
+        .as_str()
+        .or(charge["va_numbers"][0]["va_number"].as_str())
+        .map(str::to_string)
+}
+
+// Helper: The QR image URL from a QRIS charge's actions
+fn qr_code_url(charge: &Value) -> Option<String> {
+    charge["actions"]
+        .as_array()?
+        .iter()
+        .find(|action| action["name"] == "generate-qr-code")?["url"]
+        .as_str()
+        .map(str::to_string)
+}
+
+// Create a Midtrans payment for an order's outstanding balance: a bank virtual
+// account number or a QRIS code, kept with the order's payment links and
+// reconciled like them. Asking again for the same method returns the pending
+// payment while it still matches the balance; otherwise it's cancelled and
+// replaced. Orders must be in rupiah.
+#[tauri::command]
+pub async fn create_midtrans_payment(
+    db: State<'_, Database>,
+    credentials: MidtransCredentials,
+    po_id: i64,
+    method: MidtransMethod,
+    expiry_hours: Option<u32>,
+) -> Result<PaymentLink, AppError> {
+    let midtrans = Midtrans::new(&credentials)?;
+    let expiry_hours = expiry_hours.unwrap_or(DEFAULT_EXPIRY_HOURS);
+    if expiry_hours == 0 {
+        return Err(AppError::Validation(
+            "Payment expiry must be at least an hour".to_string(),
+        ));
+    }
+    if let MidtransMethod::BankTransfer { bank } = &method {
+        if !VA_BANKS.contains(&bank.trim().to_lowercase().as_str()) {
+            return Err(AppError::Validation(format!(
+                "Midtrans doesn't issue virtual accounts for {}",
+                bank
+            )));
+        }
+    }
+
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let (order, balance, currency) = order_balance(&mut conn, po_id).await?;
+    if currency != MIDTRANS_CURRENCY {
+        return Err(AppError::Validation(format!(
+            "Midtrans only takes payments in {}; this order is in {}",
+            MIDTRANS_CURRENCY, currency
+        )));
+    }
+    let method_key = method.key();
+    if let Some(link) = reuse_open_link(
+        &mut conn,
+        &midtrans,
+        po_id,
+        balance,
+        &currency,
+        Some(&method_key),
+    )
+    .await?
+    {
+        return Ok(link);
+    }
+
+    // Rupiah amounts are whole (see currency_decimals), as Midtrans wants them
+    let gross_amount = balance.round() as i64;
+    // Order IDs are single-use at Midtrans, so each payment gets a fresh one
+    let order_id = format!("PO{}-{}", po_id, &Uuid::new_v4().simple().to_string()[..8]);
+    let mut request = json!({
+        "transaction_details": {
+            "order_id": order_id,
+            "gross_amount": gross_amount,
+        },
+        "customer_details": {
+            "first_name": order.customer_name,
+            "email": order.customer_email,
+        },
+        "item_details": [{
+            "id": po_id.to_string(),
+            "price": gross_amount,
+            "quantity": 1,
+            "name": match &order.invoice_number {
+                Some(invoice) => format!("Invoice {}", invoice),
+                None => format!("Order {}", order.confirmation_code),
+            },
+        }],
+        "custom_expiry": {
+            "expiry_duration": expiry_hours,
+            "unit": "hour",
+        },
+    });
+    match &method {
+        MidtransMethod::BankTransfer { bank } => {
+            request["payment_type"] = json!("bank_transfer");
+            request["bank_transfer"] = json!({ "bank": bank.trim().to_lowercase() });
+        }
+        MidtransMethod::Qris => {
+            request["payment_type"] = json!("qris");
+            request["qris"] = json!({ "acquirer": "gopay" });
+        }
+    }
+
+    let charge = midtrans
+        .send(
+            midtrans
+                .client
+                .post(format!("{}/charge", midtrans.base))
+                .json(&request),
+            "create Midtrans payment",
+        )
+        .await?;
+    let (url, payment_code) = match &method {
+        MidtransMethod::BankTransfer { .. } => (
+            None,
+            Some(va_number(&charge).ok_or("Midtrans didn't return a virtual account number")?),
+        ),
+        MidtransMethod::Qris => (
+            Some(qr_code_url(&charge).ok_or("Midtrans didn't return a QR code")?),
+            charge["qr_string"].as_str().map(str::to_string),
+        ),
+    };
+    let expires_at = charge["expiry_time"]
+        .as_str()
+        .map(|time| utc_from_wib(Some(time)));
+
+    insert_payment_link(
+        &mut conn,
+        po_id,
+        Midtrans::PROVIDER,
+        &NewPaymentLink {
+            external_id: order_id,
+            url,
+            method: Some(method_key),
+            payment_code,
+            expires_at,
+        },
+        balance,
+        &currency,
+    )
+    .await
+}
+
+// Ask Midtrans about every pending payment and record the ones that have
+// settled, moving fully paid invoiced orders to paid. Expired payments are
+// closed, and ones the order no longer needs are cancelled.
+#[tauri::command]
+pub async fn sync_midtrans_payments(
+    app: AppHandle,
+    db: State<'_, Database>,
+    credentials: MidtransCredentials,
+) -> Result<PaymentSync, AppError> {
+    let midtrans = Midtrans::new(&credentials)?;
+    sync_payment_links(&app, &db.pool, &midtrans).await
+}
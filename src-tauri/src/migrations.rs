@@ -0,0 +1,496 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use tauri::State;
+
+use crate::db::Database;
+use crate::error::AppError;
+use crate::{log_info, log_warning};
+
+// A versioned schema change compiled into the binary
+pub struct Migration {
+    pub version: i64,
+    pub description: &'static str,
+    pub sql: &'static str,
+}
+
+// Applied in order on startup. Versions 1-2 (001_init.sql, 002_add_auth_mode.sql)
+// predate the runner and are still created by the frontend, so the list starts at 3.
+// New migrations are appended here with the next version number.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 3,
+        description: "core_domain",
+        sql: include_str!("../migrations/003_core_domain.sql"),
+    },
+    Migration {
+        version: 4,
+        description: "order_status",
+        sql: include_str!("../migrations/004_order_status.sql"),
+    },
+    Migration {
+        version: 5,
+        description: "invoice_numbers",
+        sql: include_str!("../migrations/005_invoice_numbers.sql"),
+    },
+    Migration {
+        version: 6,
+        description: "confirmation_codes",
+        sql: include_str!("../migrations/006_confirmation_codes.sql"),
+    },
+    Migration {
+        version: 7,
+        description: "inventory",
+        sql: include_str!("../migrations/007_inventory.sql"),
+    },
+    Migration {
+        version: 8,
+        description: "customer_directory",
+        sql: include_str!("../migrations/008_customer_directory.sql"),
+    },
+    Migration {
+        version: 9,
+        description: "payments",
+        sql: include_str!("../migrations/009_payments.sql"),
+    },
+    Migration {
+        version: 10,
+        description: "search_index",
+        sql: include_str!("../migrations/010_search_index.sql"),
+    },
+    Migration {
+        version: 11,
+        description: "audit_log",
+        sql: include_str!("../migrations/011_audit_log.sql"),
+    },
+    Migration {
+        version: 12,
+        description: "price_history",
+        sql: include_str!("../migrations/012_price_history.sql"),
+    },
+    Migration {
+        version: 13,
+        description: "tax_settings",
+        sql: include_str!("../migrations/013_tax_settings.sql"),
+    },
+    Migration {
+        version: 14,
+        description: "currencies",
+        sql: include_str!("../migrations/014_currencies.sql"),
+    },
+    Migration {
+        version: 15,
+        description: "recurring_orders",
+        sql: include_str!("../migrations/015_recurring_orders.sql"),
+    },
+    Migration {
+        version: 16,
+        description: "order_timeline",
+        sql: include_str!("../migrations/016_order_timeline.sql"),
+    },
+    Migration {
+        version: 17,
+        description: "order_versions",
+        sql: include_str!("../migrations/017_order_versions.sql"),
+    },
+    Migration {
+        version: 18,
+        description: "archive",
+        sql: include_str!("../migrations/018_archive.sql"),
+    },
+    Migration {
+        version: 19,
+        description: "supplier_orders",
+        sql: include_str!("../migrations/019_supplier_orders.sql"),
+    },
+    Migration {
+        version: 20,
+        description: "fulfillment",
+        sql: include_str!("../migrations/020_fulfillment.sql"),
+    },
+    Migration {
+        version: 21,
+        description: "integrity",
+        sql: include_str!("../migrations/021_integrity.sql"),
+    },
+    Migration {
+        version: 22,
+        description: "invoice_layout",
+        sql: include_str!("../migrations/022_invoice_layout.sql"),
+    },
+    Migration {
+        version: 23,
+        description: "locale_settings",
+        sql: include_str!("../migrations/023_locale_settings.sql"),
+    },
+    Migration {
+        version: 24,
+        description: "product_barcodes",
+        sql: include_str!("../migrations/024_product_barcodes.sql"),
+    },
+    Migration {
+        version: 25,
+        description: "barcode_lookups",
+        sql: include_str!("../migrations/025_barcode_lookups.sql"),
+    },
+    Migration {
+        version: 26,
+        description: "order_documents",
+        sql: include_str!("../migrations/026_order_documents.sql"),
+    },
+    Migration {
+        version: 27,
+        description: "payment_links",
+        sql: include_str!("../migrations/027_payment_links.sql"),
+    },
+    Migration {
+        version: 28,
+        description: "payment_gateways",
+        sql: include_str!("../migrations/028_payment_gateways.sql"),
+    },
+    Migration {
+        version: 29,
+        description: "invoice_qris",
+        sql: include_str!("../migrations/029_invoice_qris.sql"),
+    },
+    Migration {
+        version: 30,
+        description: "payment_reminders",
+        sql: include_str!("../migrations/030_payment_reminders.sql"),
+    },
+    Migration {
+        version: 31,
+        description: "refunds_credit_notes",
+        sql: include_str!("../migrations/031_refunds_credit_notes.sql"),
+    },
+    Migration {
+        version: 32,
+        description: "deposits",
+        sql: include_str!("../migrations/032_deposits.sql"),
+    },
+    Migration {
+        version: 33,
+        description: "quotes",
+        sql: include_str!("../migrations/033_quotes.sql"),
+    },
+    Migration {
+        version: 34,
+        description: "webhook_receiver",
+        sql: include_str!("../migrations/034_webhook_receiver.sql"),
+    },
+    Migration {
+        version: 35,
+        description: "outgoing_webhooks",
+        sql: include_str!("../migrations/035_outgoing_webhooks.sql"),
+    },
+    Migration {
+        version: 36,
+        description: "telegram",
+        sql: include_str!("../migrations/036_telegram.sql"),
+    },
+    Migration {
+        version: 37,
+        description: "whatsapp",
+        sql: include_str!("../migrations/037_whatsapp.sql"),
+    },
+    Migration {
+        version: 38,
+        description: "sms",
+        sql: include_str!("../migrations/038_sms.sql"),
+    },
+    Migration {
+        version: 39,
+        description: "chat_alerts",
+        sql: include_str!("../migrations/039_chat_alerts.sql"),
+    },
+    Migration {
+        version: 40,
+        description: "desktop_notifications",
+        sql: include_str!("../migrations/040_desktop_notifications.sql"),
+    },
+    Migration {
+        version: 41,
+        description: "auto_backups",
+        sql: include_str!("../migrations/041_auto_backups.sql"),
+    },
+    Migration {
+        version: 42,
+        description: "device_sync",
+        sql: include_str!("../migrations/042_device_sync.sql"),
+    },
+    Migration {
+        version: 43,
+        description: "automation_settings",
+        sql: include_str!("../migrations/043_automation_settings.sql"),
+    },
+    Migration {
+        version: 44,
+        description: "settings_store",
+        sql: include_str!("../migrations/044_settings_store.sql"),
+    },
+    Migration {
+        version: 45,
+        description: "field_encryption",
+        sql: include_str!("../migrations/045_field_encryption.sql"),
+    },
+    Migration {
+        version: 46,
+        description: "data_signing_key",
+        sql: include_str!("../migrations/046_data_signing_key.sql"),
+    },
+    Migration {
+        version: 47,
+        description: "session_lock",
+        sql: include_str!("../migrations/047_session_lock.sql"),
+    },
+    Migration {
+        version: 48,
+        description: "log_settings",
+        sql: include_str!("../migrations/048_log_settings.sql"),
+    },
+    Migration {
+        version: 49,
+        description: "network_settings",
+        sql: include_str!("../migrations/049_network_settings.sql"),
+    },
+    Migration {
+        version: 50,
+        description: "network_timeouts",
+        sql: include_str!("../migrations/050_network_timeouts.sql"),
+    },
+    Migration {
+        version: 51,
+        description: "expenses",
+        sql: include_str!("../migrations/051_expenses.sql"),
+    },
+    Migration {
+        version: 52,
+        description: "invoice_pattern_year",
+        sql: include_str!("../migrations/052_invoice_pattern_year.sql"),
+    },
+    Migration {
+        version: 53,
+        description: "order_history_amounts",
+        sql: include_str!("../migrations/053_order_history_amounts.sql"),
+    },
+    Migration {
+        version: 54,
+        description: "currency_price_history",
+        sql: include_str!("../migrations/054_currency_price_history.sql"),
+    },
+];
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub description: String,
+    pub checksum: String,
+    pub applied_at: Option<String>,
+}
+
+// Applied migrations that don't match this build
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MigrationDrift {
+    // Versions whose SQL changed after they were applied
+    pub changed: Vec<i64>,
+    // Versions this build doesn't know, applied by a newer build
+    pub unknown: Vec<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SchemaVersion {
+    // Highest applied version, 0 for a database the runner has never touched
+    pub current: i64,
+    // Highest version known to this build
+    pub latest: i64,
+    pub applied: Vec<AppliedMigration>,
+    pub drift: MigrationDrift,
+}
+
+fn checksum(sql: &str) -> String {
+    Sha256::digest(sql.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// Highest schema version this build knows how to migrate to
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+// Helper: Read the migration history table
+async fn applied_migrations(pool: &SqlitePool) -> Result<Vec<AppliedMigration>, AppError> {
+    sqlx::query_as::<_, AppliedMigration>(
+        "SELECT version, description, checksum, applied_at FROM schema_migrations ORDER BY version",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to read schema migrations: {}", e)))
+}
+
+// Helper: Compare the migration history with the migrations compiled in
+fn migration_drift(applied: &[AppliedMigration]) -> MigrationDrift {
+    let mut drift = MigrationDrift::default();
+    for existing in applied {
+        match MIGRATIONS.iter().find(|m| m.version == existing.version) {
+            Some(migration) if checksum(migration.sql) != existing.checksum => {
+                drift.changed.push(existing.version)
+            }
+            Some(_) => {}
+            None => drift.unknown.push(existing.version),
+        }
+    }
+    drift
+}
+
+// Apply every pending migration, each in its own transaction. Refuses a
+// database a newer build has migrated, since this build can't know what
+// those migrations changed.
+pub async fn run_migrations(pool: &SqlitePool) -> Result<i64, AppError> {
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            checksum TEXT NOT NULL,
+            applied_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to create schema_migrations table: {}", e))?;
+
+    let applied = applied_migrations(pool).await?;
+    let drift = migration_drift(&applied);
+    if let Some(newest) = drift.unknown.iter().max() {
+        return Err(AppError::Conflict(format!(
+            "Database schema version {} is newer than this app supports ({})",
+            newest,
+            latest_version()
+        )));
+    }
+    for version in &drift.changed {
+        log_warning!("Migration {} changed after it was applied", version);
+    }
+
+    for migration in MIGRATIONS {
+        if applied.iter().any(|m| m.version == migration.version) {
+            continue;
+        }
+        let sum = checksum(migration.sql);
+
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| format!("Failed to start migration transaction: {}", e))?;
+
+        sqlx::raw_sql(migration.sql)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                format!(
+                    "Migration {} ({}) failed: {}",
+                    migration.version, migration.description, e
+                )
+            })?;
+
+        sqlx::query(
+            "INSERT INTO schema_migrations (version, description, checksum) VALUES (?, ?, ?)",
+        )
+        .bind(migration.version)
+        .bind(migration.description)
+        .bind(&sum)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to record migration: {}", e))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| format!("Failed to commit migration {}: {}", migration.version, e))?;
+
+        log_info!(
+            "Applied migration {} ({})",
+            migration.version,
+            migration.description
+        );
+    }
+
+    Ok(latest_version())
+}
+
+#[tauri::command]
+pub async fn get_schema_version(db: State<'_, Database>) -> Result<SchemaVersion, AppError> {
+    let applied = applied_migrations(&db.pool).await?;
+
+    Ok(SchemaVersion {
+        current: applied.iter().map(|m| m.version).max().unwrap_or(0),
+        latest: latest_version(),
+        drift: migration_drift(&applied),
+        applied,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    #[tokio::test]
+    async fn fresh_database_applies_every_migration() {
+        let pool = test_pool().await;
+        let applied = applied_migrations(&pool).await.unwrap();
+
+        let versions: Vec<i64> = applied.iter().map(|m| m.version).collect();
+        let expected: Vec<i64> = MIGRATIONS.iter().map(|m| m.version).collect();
+        assert_eq!(versions, expected);
+        assert_eq!(migration_drift(&applied), MigrationDrift::default());
+    }
+
+    #[tokio::test]
+    async fn rerun_is_a_no_op() {
+        let pool = test_pool().await;
+        let before = applied_migrations(&pool).await.unwrap();
+
+        assert_eq!(run_migrations(&pool).await.unwrap(), latest_version());
+        let after = applied_migrations(&pool).await.unwrap();
+        assert_eq!(after.len(), before.len());
+        for (a, b) in before.iter().zip(&after) {
+            assert_eq!(
+                (a.version, &a.checksum, &a.applied_at),
+                (b.version, &b.checksum, &b.applied_at)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn changed_migration_is_reported() {
+        let pool = test_pool().await;
+        sqlx::query("UPDATE schema_migrations SET checksum = 'edited' WHERE version = 3")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // Still starts; the change shows up in the schema version
+        run_migrations(&pool).await.unwrap();
+        let drift = migration_drift(&applied_migrations(&pool).await.unwrap());
+        assert_eq!(drift.changed, [3]);
+        assert!(drift.unknown.is_empty());
+    }
+
+    #[tokio::test]
+    async fn newer_schema_is_refused() {
+        let pool = test_pool().await;
+        sqlx::query(
+            "INSERT INTO schema_migrations (version, description, checksum) VALUES (?, 'future', '')",
+        )
+        .bind(latest_version() + 1)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        match run_migrations(&pool).await {
+            Err(AppError::Conflict(message)) => assert!(message.contains("newer")),
+            other => panic!("expected a conflict, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::error::AppError;
+
+// Core purchase-order domain model. Field names match the SQLite columns the
+// frontend already uses, so rows serialize the same way from either side.
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Product {
+    pub id: i64,
+    pub unique_id: Option<String>,
+    pub name: String,
+    pub description: Option<String>,
+    pub price: f64,
+    pub currency_code: Option<String>,
+    pub image_url: Option<String>,
+    pub event_id: Option<i64>,
+    pub is_active: bool,
+    // None when stock isn't tracked for the product
+    pub stock_quantity: Option<i64>,
+    pub low_stock_threshold: Option<i64>,
+    // Supplier the product is normally bought from
+    pub supplier_id: Option<i64>,
+    // EAN/UPC digits or Code128 text (see barcodes::normalize_barcode)
+    pub barcode: Option<String>,
+    pub created_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductInput {
+    pub unique_id: Option<String>,
+    pub barcode: Option<String>,
+    pub name: String,
+    pub description: Option<String>,
+    pub price: f64,
+    pub currency_code: Option<String>,
+    pub image_url: Option<String>,
+    pub event_id: Option<i64>,
+}
+
+// How a customer wants confirmation codes and payment reminders sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum ContactPreference {
+    #[default]
+    Email,
+    // Texts to their phone number, through the SMS provider
+    Sms,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Customer {
+    pub id: i64,
+    pub name: String,
+    pub email: String,
+    pub phone: Option<String>,
+    pub contact_preference: ContactPreference,
+    pub address: Option<String>,
+    pub notes: Option<String>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+    pub deleted_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomerInput {
+    pub name: String,
+    pub email: String,
+    pub phone: Option<String>,
+    // Left as it is on update when None
+    pub contact_preference: Option<ContactPreference>,
+    pub address: Option<String>,
+    pub notes: Option<String>,
+}
+
+// Customer with totals over the orders placed under their email
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CustomerSummary {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub customer: Customer,
+    pub order_count: i64,
+    pub total_spent: f64,
+    pub last_order_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct LineItem {
+    pub id: i64,
+    pub preorder_id: i64,
+    pub product_id: i64,
+    // Joined from products; None if the product row is gone
+    pub product_name: Option<String>,
+    pub quantity: i64,
+    pub unit_price: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineItemInput {
+    pub product_id: i64,
+    pub quantity: i64,
+    // Defaults to the product's current price
+    pub unit_price: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PurchaseOrder {
+    pub id: i64,
+    pub customer_name: String,
+    pub customer_email: String,
+    pub confirmation_code: String,
+    pub invoice_number: Option<String>,
+    // ISO 4217 code; None means the app's default currency
+    pub currency_code: Option<String>,
+    pub status: String,
+    pub total_amount: f64,
+    // Sum of recorded payments, less refunds
+    pub amount_paid: f64,
+    // Taken off the total by credit notes
+    pub amount_credited: f64,
+    // Share of the total due up front; None when the order takes no deposit
+    pub deposit_percent: Option<f64>,
+    // Fixed when the deposit invoice is issued
+    pub deposit_amount: Option<f64>,
+    pub deposit_invoice_number: Option<String>,
+    pub notes: Option<String>,
+    pub event_id: Option<i64>,
+    // Template this order was generated from, if any
+    pub recurring_order_id: Option<i64>,
+    pub created_at: Option<String>,
+    pub confirmed_at: Option<String>,
+    pub deposit_invoiced_at: Option<String>,
+    pub deposit_paid_at: Option<String>,
+    pub invoiced_at: Option<String>,
+    pub paid_at: Option<String>,
+    pub fulfilled_at: Option<String>,
+    pub cancelled_at: Option<String>,
+    pub deleted_at: Option<String>,
+    // Bumped on every change; edits must quote the version they started from
+    pub version: i64,
+    #[sqlx(skip)]
+    pub items: Vec<LineItem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurchaseOrderInput {
+    pub customer_name: String,
+    pub customer_email: String,
+    // Prices are resolved in this currency; defaults to the app's currency
+    pub currency_code: Option<String>,
+    pub notes: Option<String>,
+    pub event_id: Option<i64>,
+    pub items: Vec<LineItemInput>,
+}
+
+// Partial update; fields left as None are not changed, an empty notes
+// string clears the note and an event_id of null detaches the order from its
+// event. Status only changes through transition_order.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PurchaseOrderUpdate {
+    pub customer_name: Option<String>,
+    pub customer_email: Option<String>,
+    pub notes: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub event_id: Option<Option<i64>>,
+    pub items: Option<Vec<LineItemInput>>,
+}
+
+// Helper: Deserialize a field that was sent, null included, as Some, so a
+// missing field (None through serde's default) can be told apart from null
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+// Helper: Basic checks shared by customer and order inputs
+pub fn validate_contact(name: &str, email: &str) -> Result<(), AppError> {
+    if name.trim().is_empty() {
+        return Err(AppError::Validation(
+            "Customer name must not be empty".to_string(),
+        ));
+    }
+    let email = email.trim();
+    match email.split_once('@') {
+        Some((local, domain)) if !local.is_empty() && domain.contains('.') => Ok(()),
+        _ => Err(AppError::Validation(format!(
+            "Invalid email address: {}",
+            email
+        ))),
+    }
+}
@@ -0,0 +1,202 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteConnection;
+use tauri::State;
+
+use crate::currency::{currency_decimals, default_currency, normalize_currency};
+use crate::db::Database;
+use crate::error::AppError;
+
+const DEFAULT_NUMBER_LOCALE: &str = "en-US";
+
+// (code, symbol, symbol used in the currency's own country). Only symbols the
+// builtin PDF fonts can draw are listed; anything else (INR, THB, KRW, ...)
+// is written with its code.
+const SYMBOLS: &[(&str, &str, &str)] = &[
+    ("USD", "US$", "$"),
+    ("EUR", "€", "€"),
+    ("GBP", "£", "£"),
+    ("JPY", "¥", "¥"),
+    ("CNY", "CN¥", "¥"),
+    ("AUD", "A$", "$"),
+    ("CAD", "CA$", "$"),
+    ("NZD", "NZ$", "$"),
+    ("HKD", "HK$", "$"),
+    ("SGD", "S$", "$"),
+    ("TWD", "NT$", "$"),
+    ("MXN", "MX$", "$"),
+    ("BRL", "R$", "R$"),
+    ("IDR", "Rp", "Rp"),
+    ("MYR", "RM", "RM"),
+    ("CHF", "CHF", "CHF"),
+    ("SEK", "SEK", "kr"),
+    ("NOK", "NOK", "kr"),
+    ("DKK", "DKK", "kr."),
+];
+
+// Languages that write the symbol after the number ("1.234,50 €")
+const SYMBOL_AFTER_LANGUAGES: &[&str] = &[
+    "de", "fr", "it", "es", "sv", "nb", "nn", "no", "fi", "pl", "cs", "sk", "vi", "ru", "uk", "da",
+    "el",
+];
+
+// How amounts in one currency are written for one locale, e.g. "$1,234.50"
+// (USD, en-US), "Rp 1.500.000" (IDR, id-ID) or "1.234,50 €" (EUR, de-DE).
+// Separators come from the locale, symbol and decimal places from the currency.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MoneyFormat {
+    pub currency: String,
+    pub locale: String,
+    pub symbol: String,
+    // "1.234,50 €" rather than "€1.234,50"
+    pub symbol_after: bool,
+    // Space between symbol and number ("Rp 1.500.000", not "Rp1.500.000")
+    pub symbol_spaced: bool,
+    pub decimals: usize,
+    pub group_separator: String,
+    pub decimal_separator: String,
+}
+
+impl MoneyFormat {
+    pub fn new(currency: &str, locale: &str) -> Self {
+        let currency = currency.trim().to_uppercase();
+        let locale = if locale.trim().is_empty() {
+            DEFAULT_NUMBER_LOCALE.to_string()
+        } else {
+            locale.trim().replace('_', "-")
+        };
+        let mut parts = locale.split('-');
+        let language = parts.next().unwrap_or_default().to_lowercase();
+        let region = parts
+            .find(|part| part.len() == 2)
+            .unwrap_or_default()
+            .to_uppercase();
+
+        let symbol = SYMBOLS
+            .iter()
+            .find(|(code, _, _)| *code == currency)
+            .map(|(code, symbol, home)| {
+                // "$" is only unambiguous in the currency's own country
+                if !region.is_empty() && code.starts_with(region.as_str()) {
+                    home.to_string()
+                } else {
+                    symbol.to_string()
+                }
+            })
+            .unwrap_or_else(|| currency.clone());
+
+        let (group_separator, decimal_separator) = match (language.as_str(), region.as_str()) {
+            ("de" | "it" | "fr", "CH") => ("'", "."),
+            ("es", "MX" | "US") => (",", "."),
+            ("id" | "de" | "nl" | "it" | "es" | "pt" | "tr" | "da" | "vi" | "el", _) => (".", ","),
+            ("fr" | "sv" | "nb" | "nn" | "no" | "fi" | "pl" | "cs" | "sk" | "ru" | "uk", _) => {
+                ("\u{a0}", ",")
+            }
+            _ => (",", "."),
+        };
+        let symbol_after = SYMBOL_AFTER_LANGUAGES.contains(&language.as_str())
+            && !(region == "CH" || (language == "es" && region == "MX"));
+        let symbol_spaced = symbol_after
+            || matches!(language.as_str(), "id" | "pt" | "nl")
+            || symbol.ends_with(|c: char| c.is_alphabetic());
+
+        MoneyFormat {
+            decimals: currency_decimals(&currency).max(0) as usize,
+            currency,
+            locale,
+            symbol,
+            symbol_after,
+            symbol_spaced,
+            group_separator: group_separator.to_string(),
+            decimal_separator: decimal_separator.to_string(),
+        }
+    }
+
+    // Number with grouping and the currency's decimal places, no symbol
+    pub fn format_number(&self, amount: f64) -> String {
+        let formatted = format!("{:.*}", self.decimals, amount.abs());
+        let (whole, fraction) = formatted.split_once('.').unwrap_or((&formatted, ""));
+        let mut grouped = String::new();
+        for (i, digit) in whole.chars().enumerate() {
+            if i > 0 && (whole.len() - i) % 3 == 0 {
+                grouped.push_str(&self.group_separator);
+            }
+            grouped.push(digit);
+        }
+        if !fraction.is_empty() {
+            grouped.push_str(&self.decimal_separator);
+            grouped.push_str(fraction);
+        }
+        // No "-0.00" for amounts that round to zero
+        if amount < 0.0 && formatted.chars().any(|c| c.is_ascii_digit() && c != '0') {
+            format!("-{}", grouped)
+        } else {
+            grouped
+        }
+    }
+
+    pub fn format(&self, amount: f64) -> String {
+        let number = self.format_number(amount);
+        let (sign, number) = match number.strip_prefix('-') {
+            Some(rest) => ("-", rest),
+            None => ("", number.as_str()),
+        };
+        let space = if self.symbol_spaced { "\u{a0}" } else { "" };
+        if self.symbol_after {
+            format!("{}{}{}{}", sign, number, space, self.symbol)
+        } else {
+            format!("{}{}{}{}", sign, self.symbol, space, number)
+        }
+    }
+}
+
+// Plain machine-readable amount with the currency's decimal places
+// ("1500000" for IDR, "12.50" for USD), for CSV files spreadsheets sum
+pub fn format_amount(amount: f64, currency: &str) -> String {
+    let decimals = currency_decimals(currency).max(0) as usize;
+    let formatted = format!("{:.*}", decimals, amount);
+    if formatted.starts_with('-') && formatted.chars().all(|c| matches!(c, '-' | '0' | '.')) {
+        formatted[1..].to_string()
+    } else {
+        formatted
+    }
+}
+
+// Helper: Locale amounts are written in, from the currency picked in the app.
+// Same fallback as default_currency when the settings table isn't there yet.
+pub async fn number_locale(conn: &mut SqliteConnection) -> String {
+    sqlx::query_scalar::<_, Option<String>>("SELECT currency_locale FROM app_settings WHERE id = 1")
+        .fetch_optional(conn)
+        .await
+        .ok()
+        .flatten()
+        .flatten()
+        .filter(|locale| !locale.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_NUMBER_LOCALE.to_string())
+}
+
+// Helper: Format for `currency` (the app's default currency if None) in the
+// app's number locale
+pub async fn money_format(
+    conn: &mut SqliteConnection,
+    currency: Option<&str>,
+) -> Result<MoneyFormat, AppError> {
+    let currency = match currency {
+        Some(code) => normalize_currency(code)?,
+        None => default_currency(&mut *conn).await,
+    };
+    Ok(MoneyFormat::new(&currency, &number_locale(conn).await))
+}
+
+// How the backend writes amounts for a currency, so the UI can match it
+#[tauri::command]
+pub async fn get_money_format(
+    db: State<'_, Database>,
+    currency: Option<String>,
+) -> Result<MoneyFormat, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    money_format(&mut conn, currency.as_deref()).await
+}
@@ -0,0 +1,264 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteConnection;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::db::Database;
+use crate::domain_events::{publish, DomainEvent};
+use crate::error::AppError;
+use crate::inventory::{decrement_for_order, emit_low_stock, restock_for_order, StockLevel};
+use crate::invoice_numbers::assign_invoice_number;
+use crate::log_warning;
+use crate::models::PurchaseOrder;
+use crate::orders::load_order;
+
+// Order lifecycle:
+// Draft -> Confirmed -> Invoiced -> Paid -> Fulfilled, with Cancelled reachable
+// from any state before payment. Orders taking a deposit go
+// Confirmed -> DepositPaid -> Invoiced, and can still be cancelled after the
+// deposit (it's refunded separately).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    Draft,
+    Confirmed,
+    DepositPaid,
+    Invoiced,
+    Paid,
+    Fulfilled,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderEvent {
+    Confirm,
+    PayDeposit,
+    Invoice,
+    Pay,
+    Fulfill,
+    Cancel,
+}
+
+// Emitted as the "order-status-changed" event after every transition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderStatusChanged {
+    pub order_id: i64,
+    pub from: OrderStatus,
+    pub to: OrderStatus,
+    pub event: OrderEvent,
+}
+
+impl OrderStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderStatus::Draft => "draft",
+            OrderStatus::Confirmed => "confirmed",
+            OrderStatus::DepositPaid => "deposit_paid",
+            OrderStatus::Invoiced => "invoiced",
+            OrderStatus::Paid => "paid",
+            OrderStatus::Fulfilled => "fulfilled",
+            OrderStatus::Cancelled => "cancelled",
+        }
+    }
+
+    // Parse a stored status. "pending" and "sent" are the frontend's names for
+    // orders that haven't been confirmed yet.
+    pub fn parse(value: &str) -> Result<OrderStatus, AppError> {
+        match value {
+            "draft" | "pending" | "sent" => Ok(OrderStatus::Draft),
+            "confirmed" => Ok(OrderStatus::Confirmed),
+            "deposit_paid" => Ok(OrderStatus::DepositPaid),
+            "invoiced" => Ok(OrderStatus::Invoiced),
+            "paid" => Ok(OrderStatus::Paid),
+            "fulfilled" => Ok(OrderStatus::Fulfilled),
+            "cancelled" => Ok(OrderStatus::Cancelled),
+            other => Err(AppError::Validation(format!(
+                "Unknown order status: {}",
+                other
+            ))),
+        }
+    }
+
+    // Whether an order in this status has taken its items out of stock and
+    // not shipped them yet
+    pub fn holds_stock(&self) -> bool {
+        matches!(
+            self,
+            OrderStatus::Confirmed
+                | OrderStatus::DepositPaid
+                | OrderStatus::Invoiced
+                | OrderStatus::Paid
+        )
+    }
+
+    // Column recording when an order entered this status
+    fn timestamp_column(&self) -> Option<&'static str> {
+        match self {
+            OrderStatus::Draft => None,
+            OrderStatus::Confirmed => Some("confirmed_at"),
+            OrderStatus::DepositPaid => Some("deposit_paid_at"),
+            OrderStatus::Invoiced => Some("invoiced_at"),
+            OrderStatus::Paid => Some("paid_at"),
+            OrderStatus::Fulfilled => Some("fulfilled_at"),
+            OrderStatus::Cancelled => Some("cancelled_at"),
+        }
+    }
+
+    // The state reached by applying an event, or an error for illegal transitions
+    pub fn apply(self, event: OrderEvent) -> Result<OrderStatus, AppError> {
+        use OrderEvent::*;
+        use OrderStatus::*;
+
+        match (self, event) {
+            (Draft, Confirm) => Ok(Confirmed),
+            (Confirmed, PayDeposit) => Ok(DepositPaid),
+            (Confirmed | DepositPaid, Invoice) => Ok(Invoiced),
+            (Invoiced, Pay) => Ok(Paid),
+            (Paid, Fulfill) => Ok(Fulfilled),
+            (Draft | Confirmed | DepositPaid | Invoiced, Cancel) => Ok(Cancelled),
+            (from, event) => Err(AppError::Validation(format!(
+                "Cannot {} an order that is {}",
+                event.as_str(),
+                from.as_str()
+            ))),
+        }
+    }
+}
+
+impl OrderEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderEvent::Confirm => "confirm",
+            OrderEvent::PayDeposit => "pay_deposit",
+            OrderEvent::Invoice => "invoice",
+            OrderEvent::Pay => "pay",
+            OrderEvent::Fulfill => "fulfill",
+            OrderEvent::Cancel => "cancel",
+        }
+    }
+}
+
+// Result of a transition, emitted to the frontend once the transaction commits
+pub struct Transition {
+    pub change: OrderStatusChanged,
+    pub low_stock: Vec<StockLevel>,
+}
+
+// Apply a lifecycle event inside the caller's transaction: validate it, record
+// when it happened, and run the side effects tied to the new state
+pub async fn apply_transition(
+    conn: &mut SqliteConnection,
+    po_id: i64,
+    event: OrderEvent,
+) -> Result<Transition, AppError> {
+    let (current, deleted_at) = sqlx::query_as::<_, (String, Option<String>)>(
+        "SELECT COALESCE(status, 'pending'), deleted_at FROM preorders WHERE id = ?",
+    )
+    .bind(po_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load order: {}", e))?
+    .ok_or_else(|| AppError::NotFound(format!("Order {} not found", po_id)))?;
+
+    if deleted_at.is_some() {
+        return Err(AppError::Conflict(format!(
+            "Order {} has been deleted",
+            po_id
+        )));
+    }
+
+    let from = OrderStatus::parse(&current)?;
+    let to = from.apply(event)?;
+
+    let timestamp = to
+        .timestamp_column()
+        .map(|column| format!(", {} = CURRENT_TIMESTAMP", column))
+        .unwrap_or_default();
+
+    // Compare against the status we validated so a concurrent change can't be overwritten
+    let result = sqlx::query(&format!(
+        "UPDATE preorders SET status = ?{} WHERE id = ? AND COALESCE(status, 'pending') = ?",
+        timestamp
+    ))
+    .bind(to.as_str())
+    .bind(po_id)
+    .bind(&current)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to update order status: {}", e))?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::Conflict(format!(
+            "Order {} changed status while updating; reload and try again",
+            po_id
+        )));
+    }
+
+    let mut low_stock = Vec::new();
+    match to {
+        OrderStatus::Confirmed => {
+            low_stock = decrement_for_order(&mut *conn, po_id, "order_confirmed").await?;
+        }
+        OrderStatus::Invoiced => {
+            assign_invoice_number(&mut *conn, po_id).await?;
+        }
+        // Drafts never took anything out of stock
+        OrderStatus::Cancelled if from.holds_stock() => {
+            restock_for_order(&mut *conn, po_id, "order_cancelled").await?
+        }
+        _ => {}
+    }
+
+    sqlx::query(
+        "INSERT INTO order_status_history (preorder_id, from_status, to_status, event) VALUES (?, ?, ?, ?)",
+    )
+    .bind(po_id)
+    .bind(from.as_str())
+    .bind(to.as_str())
+    .bind(event.as_str())
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to record status history: {}", e))?;
+
+    let change = OrderStatusChanged {
+        order_id: po_id,
+        from,
+        to,
+        event,
+    };
+    publish(&mut *conn, &DomainEvent::OrderStatusChanged(&change)).await?;
+
+    Ok(Transition { change, low_stock })
+}
+
+// Notify the frontend about a committed transition
+pub fn emit_transition(app: &AppHandle, transition: &Transition) {
+    if let Err(e) = app.emit("order-status-changed", &transition.change) {
+        log_warning!("Failed to emit order-status-changed event: {}", e);
+    }
+    emit_low_stock(app, &transition.low_stock);
+}
+
+// Move an order through its lifecycle, validating the transition and recording when it happened
+#[tauri::command]
+pub async fn transition_order(
+    app: AppHandle,
+    db: State<'_, Database>,
+    po_id: i64,
+    event: OrderEvent,
+) -> Result<PurchaseOrder, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let transition = apply_transition(&mut tx, po_id, event).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save order status: {}", e))?;
+
+    emit_transition(&app, &transition);
+    load_order(&db.pool, po_id).await
+}
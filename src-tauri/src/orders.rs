@@ -0,0 +1,774 @@
+use sqlx::{Acquire, Executor, QueryBuilder, Sqlite, SqliteConnection, SqlitePool};
+use std::collections::HashMap;
+use tauri::State;
+
+use crate::audit;
+use crate::confirmation_codes::unique_code;
+use crate::currency::{currency_decimals, normalize_currency};
+use crate::customers::upsert_customer;
+use crate::db::Database;
+use crate::domain_events::{publish, DomainEvent};
+use crate::error::AppError;
+use crate::inventory::{
+    adjust_for_order_edit, decrement_for_order, ensure_in_stock, restock_for_order,
+    tracked_order_quantities,
+};
+use crate::models::{
+    validate_contact, LineItem, LineItemInput, PurchaseOrder, PurchaseOrderInput,
+    PurchaseOrderUpdate,
+};
+use crate::order_status::OrderStatus;
+use crate::pricing::{price_at, price_in_currency};
+use crate::totals::{calculate_totals, load_tax_settings, TotalsInput, TotalsLine};
+
+const ORDER_COLUMNS: &str = "id, customer_name, customer_email, confirmation_code, invoice_number, \
+     currency_code, COALESCE(status, 'pending') AS status, total_amount, \
+     (SELECT COALESCE(SUM(amount), 0.0) FROM payments WHERE preorder_id = preorders.id) - \
+     (SELECT COALESCE(SUM(amount), 0.0) FROM refunds WHERE preorder_id = preorders.id) AS amount_paid, \
+     (SELECT COALESCE(-SUM(total_amount), 0.0) FROM credit_notes WHERE preorder_id = preorders.id) \
+     AS amount_credited, deposit_percent, deposit_amount, deposit_invoice_number, \
+     notes, event_id, recurring_order_id, created_at, confirmed_at, deposit_invoiced_at, deposit_paid_at, \
+     invoiced_at, paid_at, fulfilled_at, cancelled_at, deleted_at, version";
+
+const LINE_ITEM_SELECT: &str =
+    "SELECT oi.id, oi.preorder_id, oi.product_id, p.name AS product_name, \
+     oi.quantity, oi.unit_price FROM order_items oi LEFT JOIN products p ON p.id = oi.product_id";
+
+// Helper: Conflict error carrying the order as it is now, so the UI can show
+// it and let the user redo or discard their change
+async fn conflict(pool: &SqlitePool, id: i64, expected_version: i64) -> AppError {
+    let current = match load_order(pool, id).await {
+        Ok(current) => current,
+        Err(e) => return e,
+    };
+    match serde_json::to_value(&current) {
+        Ok(value) => AppError::VersionConflict {
+            message: format!(
+                "Order {} was changed elsewhere (version {} is now {}); reload and try again",
+                id, expected_version, current.version
+            ),
+            expected_version,
+            current: value,
+        },
+        Err(e) => AppError::Internal(format!("Failed to serialize order: {}", e)),
+    }
+}
+
+// Helper: Load line items for a set of orders, grouped by order ID
+async fn load_items<'e, E>(
+    executor: E,
+    order_ids: &[i64],
+) -> Result<HashMap<i64, Vec<LineItem>>, AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let mut grouped: HashMap<i64, Vec<LineItem>> = HashMap::new();
+    if order_ids.is_empty() {
+        return Ok(grouped);
+    }
+
+    let mut query = QueryBuilder::<Sqlite>::new(LINE_ITEM_SELECT);
+    query.push(" WHERE oi.preorder_id IN (");
+    let mut separated = query.separated(", ");
+    for id in order_ids {
+        separated.push_bind(*id);
+    }
+    separated.push_unseparated(") ORDER BY oi.id");
+
+    let items = query
+        .build_query_as::<LineItem>()
+        .fetch_all(executor)
+        .await
+        .map_err(|e| format!("Failed to load order items: {}", e))?;
+
+    for item in items {
+        grouped.entry(item.preorder_id).or_default().push(item);
+    }
+    Ok(grouped)
+}
+
+// Helper: Load a single order with its line items, including soft-deleted orders.
+// Takes a pool or an open transaction.
+pub async fn load_order<'a, A>(conn: A, id: i64) -> Result<PurchaseOrder, AppError>
+where
+    A: Acquire<'a, Database = Sqlite>,
+{
+    let mut conn = conn
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    fetch_order(&mut conn, id).await
+}
+
+// Helper: load_order on a plain connection. Background tasks use this directly,
+// since futures holding the generic Acquire call can't be spawned.
+pub async fn fetch_order(conn: &mut SqliteConnection, id: i64) -> Result<PurchaseOrder, AppError> {
+    let mut order = sqlx::query_as::<_, PurchaseOrder>(&format!(
+        "SELECT {} FROM preorders WHERE id = ?",
+        ORDER_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load order: {}", e))?
+    .ok_or_else(|| AppError::NotFound(format!("Order {} not found", id)))?;
+
+    order.items = load_items(&mut *conn, &[id])
+        .await?
+        .remove(&id)
+        .unwrap_or_default();
+    Ok(order)
+}
+
+// Helper: Fill in line items for a batch of orders
+async fn attach_items(pool: &SqlitePool, orders: &mut [PurchaseOrder]) -> Result<(), AppError> {
+    let ids: Vec<i64> = orders.iter().map(|o| o.id).collect();
+    let mut items = load_items(pool, &ids).await?;
+    for order in orders.iter_mut() {
+        order.items = items.remove(&order.id).unwrap_or_default();
+    }
+    Ok(())
+}
+
+// Helper: All orders placed under an email address, newest first
+pub async fn load_orders_for_email(
+    pool: &SqlitePool,
+    email: &str,
+) -> Result<Vec<PurchaseOrder>, AppError> {
+    let mut orders = sqlx::query_as::<_, PurchaseOrder>(&format!(
+        "SELECT {} FROM preorders WHERE customer_email = ? COLLATE NOCASE AND deleted_at IS NULL \
+         ORDER BY created_at DESC",
+        ORDER_COLUMNS
+    ))
+    .bind(email.trim())
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load customer orders: {}", e))?;
+
+    attach_items(pool, &mut orders).await?;
+    Ok(orders)
+}
+
+// Helper: Replace an order's line items and return the new order total (from
+// the totals engine, so tax settings apply). Items without an explicit price
+// get the product's price as of the order date, so editing an old order
+// doesn't pick up later price changes. An order that already took its items
+// out of stock moves stock by the difference instead of checking the new
+// quantities against what's left.
+async fn write_items(
+    conn: &mut SqliteConnection,
+    order_id: i64,
+    items: &[LineItemInput],
+) -> Result<f64, AppError> {
+    if items.is_empty() {
+        return Err(AppError::Validation(
+            "An order needs at least one item".to_string(),
+        ));
+    }
+
+    let (ordered_at, currency, status) = sqlx::query_as::<
+        _,
+        (Option<String>, Option<String>, String),
+    >(
+        "SELECT created_at, currency_code, COALESCE(status, 'pending') FROM preorders WHERE id = ?",
+    )
+    .bind(order_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load order: {}", e))?;
+
+    let status = OrderStatus::parse(&status)?;
+    if status == OrderStatus::Fulfilled {
+        return Err(AppError::Validation(
+            "Items can't be changed after an order is fulfilled".to_string(),
+        ));
+    }
+    let held = if status.holds_stock() {
+        Some(tracked_order_quantities(&mut *conn, order_id).await?)
+    } else {
+        None
+    };
+
+    sqlx::query("DELETE FROM order_items WHERE preorder_id = ?")
+        .bind(order_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to clear order items: {}", e))?;
+
+    for item in items {
+        if item.quantity <= 0 {
+            return Err(AppError::Validation(format!(
+                "Quantity for product {} must be positive",
+                item.product_id
+            )));
+        }
+
+        if held.is_none() {
+            ensure_in_stock(&mut *conn, item.product_id, item.quantity).await?;
+        }
+
+        let unit_price = match item.unit_price {
+            Some(price) if price.is_finite() && price >= 0.0 => price,
+            Some(price) => {
+                return Err(AppError::Validation(format!(
+                    "Invalid unit price: {}",
+                    price
+                )))
+            }
+            None => match &currency {
+                Some(currency) => {
+                    price_in_currency(&mut *conn, item.product_id, ordered_at.as_deref(), currency)
+                        .await?
+                }
+                None => price_at(&mut *conn, item.product_id, ordered_at.as_deref()).await?,
+            },
+        };
+
+        sqlx::query(
+            "INSERT INTO order_items (preorder_id, product_id, quantity, unit_price) VALUES (?, ?, ?, ?)",
+        )
+        .bind(order_id)
+        .bind(item.product_id)
+        .bind(item.quantity)
+        .bind(unit_price)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to save order item: {}", e))?;
+    }
+
+    if let Some(held) = held {
+        adjust_for_order_edit(&mut *conn, order_id, &held).await?;
+    }
+
+    order_total(conn, order_id).await
+}
+
+// Helper: An order's total from its saved line items, through the totals
+// engine so tax settings and the currency's decimal places apply
+pub async fn order_total(conn: &mut SqliteConnection, order_id: i64) -> Result<f64, AppError> {
+    let currency =
+        sqlx::query_scalar::<_, Option<String>>("SELECT currency_code FROM preorders WHERE id = ?")
+            .bind(order_id)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to load order: {}", e))?;
+
+    let lines = sqlx::query_as::<_, (i64, f64)>(
+        "SELECT quantity, unit_price FROM order_items WHERE preorder_id = ? ORDER BY id",
+    )
+    .bind(order_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load order items: {}", e))?
+    .into_iter()
+    .map(|(quantity, unit_price)| TotalsLine {
+        description: None,
+        quantity: quantity as f64,
+        unit_price,
+        discount: None,
+        tax_rate: None,
+    })
+    .collect();
+
+    let mut settings = load_tax_settings(&mut *conn).await?;
+    if let Some(currency) = &currency {
+        settings.decimal_places = currency_decimals(currency);
+    }
+    let totals = calculate_totals(
+        &TotalsInput {
+            items: lines,
+            order_discount: None,
+            settings: None,
+        },
+        &settings,
+    )?;
+    Ok(totals.total)
+}
+
+// List orders, optionally filtered by status and event, newest first
+#[tauri::command]
+pub async fn list_orders(
+    db: State<'_, Database>,
+    status: Option<String>,
+    event_id: Option<i64>,
+    include_deleted: Option<bool>,
+) -> Result<Vec<PurchaseOrder>, AppError> {
+    let mut query = QueryBuilder::<Sqlite>::new(format!(
+        "SELECT {} FROM preorders WHERE 1 = 1",
+        ORDER_COLUMNS
+    ));
+    if !include_deleted.unwrap_or(false) {
+        query.push(" AND deleted_at IS NULL");
+    }
+    if let Some(status) = status {
+        query.push(" AND status = ").push_bind(status);
+    }
+    if let Some(event_id) = event_id {
+        query.push(" AND event_id = ").push_bind(event_id);
+    }
+    query.push(" ORDER BY created_at DESC");
+
+    let mut orders = query
+        .build_query_as::<PurchaseOrder>()
+        .fetch_all(&db.pool)
+        .await
+        .map_err(|e| format!("Failed to list orders: {}", e))?;
+
+    attach_items(&db.pool, &mut orders).await?;
+    Ok(orders)
+}
+
+#[tauri::command]
+pub async fn get_order(db: State<'_, Database>, id: i64) -> Result<PurchaseOrder, AppError> {
+    load_order(&db.pool, id).await
+}
+
+// Helper: Insert a draft order with its items and total, returning its ID.
+// The caller publishes OrderCreated once it's done linking the order.
+pub async fn insert_order(
+    conn: &mut SqliteConnection,
+    order: &PurchaseOrderInput,
+) -> Result<i64, AppError> {
+    validate_contact(&order.customer_name, &order.customer_email)?;
+
+    upsert_customer(&mut *conn, &order.customer_name, &order.customer_email).await?;
+    let confirmation_code = unique_code(&mut *conn).await?;
+    let currency_code = order
+        .currency_code
+        .as_deref()
+        .map(normalize_currency)
+        .transpose()?;
+
+    let result = sqlx::query(
+        "INSERT INTO preorders (customer_name, customer_email, confirmation_code, currency_code, status, total_amount, notes, event_id) \
+         VALUES (?, ?, ?, ?, ?, 0, ?, ?)",
+    )
+    .bind(order.customer_name.trim())
+    .bind(order.customer_email.trim())
+    .bind(confirmation_code)
+    .bind(currency_code)
+    .bind(OrderStatus::Draft.as_str())
+    .bind(&order.notes)
+    .bind(order.event_id)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to create order: {}", e))?;
+
+    let order_id = result.last_insert_rowid();
+    let total = write_items(&mut *conn, order_id, &order.items).await?;
+
+    sqlx::query("UPDATE preorders SET total_amount = ? WHERE id = ?")
+        .bind(total)
+        .bind(order_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to update order total: {}", e))?;
+
+    Ok(order_id)
+}
+
+// Create an order with its items, recording the customer in the directory
+#[tauri::command]
+pub async fn create_order(
+    db: State<'_, Database>,
+    order: PurchaseOrderInput,
+) -> Result<PurchaseOrder, AppError> {
+    place_order(&db.pool, &order).await
+}
+
+// Helper: create_order without the Tauri state, publishing OrderCreated in
+// the same transaction as the insert
+pub async fn place_order(
+    pool: &SqlitePool,
+    order: &PurchaseOrderInput,
+) -> Result<PurchaseOrder, AppError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let order_id = insert_order(&mut tx, order).await?;
+    publish(&mut tx, &DomainEvent::OrderCreated { order_id }).await?;
+    let created = load_order(&mut *tx, order_id).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save order: {}", e))?;
+
+    Ok(created)
+}
+
+// Apply a partial update to an order; replacing items recalculates the total.
+// Fails with a conflict if the order is no longer at `expected_version`.
+#[tauri::command]
+pub async fn update_order(
+    db: State<'_, Database>,
+    id: i64,
+    expected_version: i64,
+    changes: PurchaseOrderUpdate,
+) -> Result<PurchaseOrder, AppError> {
+    edit_order(&db.pool, id, expected_version, changes).await
+}
+
+// Helper: update_order without the Tauri state
+pub async fn edit_order(
+    pool: &SqlitePool,
+    id: i64,
+    expected_version: i64,
+    changes: PurchaseOrderUpdate,
+) -> Result<PurchaseOrder, AppError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_order(&mut *tx, id).await?;
+    if before.deleted_at.is_some() {
+        return Err(AppError::Conflict(format!("Order {} has been deleted", id)));
+    }
+    if before.version != expected_version {
+        drop(tx);
+        return Err(conflict(pool, id, expected_version).await);
+    }
+
+    let customer_name = changes
+        .customer_name
+        .unwrap_or(before.customer_name.clone());
+    let customer_email = changes
+        .customer_email
+        .unwrap_or(before.customer_email.clone());
+    validate_contact(&customer_name, &customer_email)?;
+
+    upsert_customer(&mut tx, &customer_name, &customer_email).await?;
+
+    let total = match &changes.items {
+        Some(items) => write_items(&mut tx, id, items).await?,
+        None => before.total_amount,
+    };
+    // An empty note clears it
+    let notes = match changes.notes {
+        Some(notes) => Some(notes.trim().to_string()).filter(|n| !n.is_empty()),
+        None => before.notes.clone(),
+    };
+
+    // Compare-and-swap on the version so a save that landed after our read isn't overwritten
+    let result = sqlx::query(
+        "UPDATE preorders SET customer_name = ?, customer_email = ?, notes = ?, event_id = ?, \
+         total_amount = ?, version = version + 1 WHERE id = ? AND version = ?",
+    )
+    .bind(customer_name.trim())
+    .bind(customer_email.trim())
+    .bind(notes)
+    .bind(changes.event_id.unwrap_or(before.event_id))
+    .bind(total)
+    .bind(id)
+    .bind(expected_version)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to update order: {}", e))?;
+
+    if result.rows_affected() == 0 {
+        drop(tx);
+        return Err(conflict(pool, id, expected_version).await);
+    }
+
+    let after = load_order(&mut *tx, id).await?;
+    audit::record(&mut *tx, "order", id, "update", Some(&before), Some(&after)).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save order: {}", e))?;
+
+    Ok(after)
+}
+
+// Helper: Set or clear an order's deleted_at, logging the change. A deleted
+// order that was holding stock gives it back, and takes it again when
+// restored. Returns None if the order is no longer at `expected_version`.
+async fn set_order_deleted(
+    conn: &mut SqliteConnection,
+    id: i64,
+    expected_version: i64,
+    deleted: bool,
+    action: &str,
+) -> Result<Option<PurchaseOrder>, AppError> {
+    let before = load_order(&mut *conn, id).await?;
+
+    let result = sqlx::query(
+        "UPDATE preorders SET deleted_at = CASE WHEN ? THEN CURRENT_TIMESTAMP ELSE NULL END, \
+         version = version + 1 WHERE id = ? AND version = ?",
+    )
+    .bind(deleted)
+    .bind(id)
+    .bind(expected_version)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to {} order: {}", action, e))?;
+
+    if result.rows_affected() == 0 {
+        return Ok(None);
+    }
+
+    // Deleting twice mustn't restock twice
+    let toggled = before.deleted_at.is_some() != deleted;
+    if toggled && OrderStatus::parse(&before.status)?.holds_stock() {
+        if deleted {
+            restock_for_order(&mut *conn, id, "order_deleted").await?;
+        } else {
+            decrement_for_order(&mut *conn, id, "order_restored").await?;
+        }
+    }
+
+    let after = load_order(&mut *conn, id).await?;
+    audit::record(&mut *conn, "order", id, action, Some(&before), Some(&after)).await?;
+    Ok(Some(after))
+}
+
+// Orders are soft-deleted so payments and history survive for disputes
+#[tauri::command]
+pub async fn delete_order(
+    db: State<'_, Database>,
+    id: i64,
+    expected_version: i64,
+) -> Result<String, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    if set_order_deleted(&mut tx, id, expected_version, true, "delete")
+        .await?
+        .is_none()
+    {
+        drop(tx);
+        return Err(conflict(&db.pool, id, expected_version).await);
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save order: {}", e))?;
+
+    Ok("Order deleted".to_string())
+}
+
+#[tauri::command]
+pub async fn restore_order(
+    db: State<'_, Database>,
+    id: i64,
+    expected_version: i64,
+) -> Result<PurchaseOrder, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let Some(restored) = set_order_deleted(&mut tx, id, expected_version, false, "restore").await?
+    else {
+        drop(tx);
+        return Err(conflict(&db.pool, id, expected_version).await);
+    };
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save order: {}", e))?;
+
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use crate::order_status::{apply_transition, OrderEvent};
+
+    // Helper: An active product priced at 10.00
+    async fn product(pool: &SqlitePool) -> i64 {
+        sqlx::query("INSERT INTO products (name, price) VALUES ('Tote bag', 10.0)")
+            .execute(pool)
+            .await
+            .unwrap()
+            .last_insert_rowid()
+    }
+
+    // Helper: A product with `stock` units tracked
+    async fn stocked_product(pool: &SqlitePool, stock: i64) -> i64 {
+        sqlx::query("INSERT INTO products (name, price, stock_quantity) VALUES ('Mug', 10.0, ?)")
+            .bind(stock)
+            .execute(pool)
+            .await
+            .unwrap()
+            .last_insert_rowid()
+    }
+
+    async fn stock(pool: &SqlitePool, product_id: i64) -> i64 {
+        sqlx::query_scalar("SELECT stock_quantity FROM products WHERE id = ?")
+            .bind(product_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn transition(pool: &SqlitePool, order_id: i64, event: OrderEvent) {
+        let mut conn = pool.acquire().await.unwrap();
+        apply_transition(&mut conn, order_id, event).await.unwrap();
+    }
+
+    fn order_input(product_id: i64, quantity: i64) -> PurchaseOrderInput {
+        PurchaseOrderInput {
+            customer_name: "Ayu".to_string(),
+            customer_email: "ayu@example.com".to_string(),
+            currency_code: None,
+            notes: None,
+            event_id: None,
+            items: vec![LineItemInput {
+                product_id,
+                quantity,
+                unit_price: Some(10.0),
+            }],
+        }
+    }
+
+    // The order form and the sheet sync both call create_order, which must
+    // reach every OrderCreated subscriber
+    #[tokio::test]
+    async fn create_order_publishes_order_created() {
+        let pool = test_pool().await;
+        let product_id = product(&pool).await;
+        sqlx::query("INSERT INTO webhook_endpoints (name, url, secret) VALUES ('Shop', 'https://example.com/hook', 's')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO webhook_endpoint_events (endpoint_id, event) VALUES (1, 'order.created')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let order = place_order(&pool, &order_input(product_id, 2))
+            .await
+            .unwrap();
+        assert_eq!(order.total_amount, 20.0);
+
+        let audited = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM audit_log WHERE entity_type = 'order' AND entity_id = ? AND action = 'create'",
+        )
+        .bind(order.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(audited, 1);
+
+        let queued = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM webhook_deliveries WHERE event = 'order.created' AND preorder_id = ?",
+        )
+        .bind(order.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(queued, 1);
+    }
+
+    // Cancelling restocks what the order holds after the edit, so stock ends
+    // where it started
+    #[tokio::test]
+    async fn editing_confirmed_order_moves_stock_by_difference() {
+        let pool = test_pool().await;
+        let product_id = stocked_product(&pool, 20).await;
+
+        let order = place_order(&pool, &order_input(product_id, 5))
+            .await
+            .unwrap();
+        transition(&pool, order.id, OrderEvent::Confirm).await;
+        assert_eq!(stock(&pool, product_id).await, 15);
+
+        let version = load_order(&pool, order.id).await.unwrap().version;
+        let changes = PurchaseOrderUpdate {
+            items: Some(order_input(product_id, 10).items),
+            ..Default::default()
+        };
+        edit_order(&pool, order.id, version, changes).await.unwrap();
+        assert_eq!(stock(&pool, product_id).await, 10);
+
+        transition(&pool, order.id, OrderEvent::Cancel).await;
+        assert_eq!(stock(&pool, product_id).await, 20);
+    }
+
+    // A confirmed order may grow into stock it already holds
+    #[tokio::test]
+    async fn editing_confirmed_order_counts_its_own_stock() {
+        let pool = test_pool().await;
+        let product_id = stocked_product(&pool, 8).await;
+
+        let order = place_order(&pool, &order_input(product_id, 5))
+            .await
+            .unwrap();
+        transition(&pool, order.id, OrderEvent::Confirm).await;
+
+        let version = load_order(&pool, order.id).await.unwrap().version;
+        let changes = PurchaseOrderUpdate {
+            items: Some(order_input(product_id, 8).items),
+            ..Default::default()
+        };
+        edit_order(&pool, order.id, version, changes).await.unwrap();
+        assert_eq!(stock(&pool, product_id).await, 0);
+    }
+
+    // Leaving event_id out keeps the event; sending null detaches it
+    #[tokio::test]
+    async fn editing_event_id_null_detaches_the_event() {
+        let pool = test_pool().await;
+        let product_id = product(&pool).await;
+        let event_id = sqlx::query("INSERT INTO events (name) VALUES ('Bazaar')")
+            .execute(&pool)
+            .await
+            .unwrap()
+            .last_insert_rowid();
+        let mut input = order_input(product_id, 1);
+        input.event_id = Some(event_id);
+        let order = place_order(&pool, &input).await.unwrap();
+
+        let changes: PurchaseOrderUpdate =
+            serde_json::from_str(r#"{"notes": "Gift wrap"}"#).unwrap();
+        let order = edit_order(&pool, order.id, order.version, changes)
+            .await
+            .unwrap();
+        assert_eq!(order.event_id, Some(event_id));
+
+        let changes: PurchaseOrderUpdate = serde_json::from_str(r#"{"event_id": null}"#).unwrap();
+        let order = edit_order(&pool, order.id, order.version, changes)
+            .await
+            .unwrap();
+        assert_eq!(order.event_id, None);
+    }
+
+    #[tokio::test]
+    async fn deleting_confirmed_order_restocks_until_restored() {
+        let pool = test_pool().await;
+        let product_id = stocked_product(&pool, 20).await;
+
+        let order = place_order(&pool, &order_input(product_id, 5))
+            .await
+            .unwrap();
+        transition(&pool, order.id, OrderEvent::Confirm).await;
+        let version = load_order(&pool, order.id).await.unwrap().version;
+
+        let mut conn = pool.acquire().await.unwrap();
+        let deleted = set_order_deleted(&mut conn, order.id, version, true, "delete")
+            .await
+            .unwrap()
+            .unwrap();
+        drop(conn);
+        assert_eq!(stock(&pool, product_id).await, 20);
+
+        let mut conn = pool.acquire().await.unwrap();
+        set_order_deleted(&mut conn, order.id, deleted.version, false, "restore")
+            .await
+            .unwrap()
+            .unwrap();
+        drop(conn);
+        assert_eq!(stock(&pool, product_id).await, 15);
+    }
+}
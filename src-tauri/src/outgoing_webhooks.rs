@@ -0,0 +1,746 @@
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sqlx::{SqliteConnection, SqlitePool};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+use uuid::Uuid;
+
+use crate::audit;
+use crate::automation::{self, Poller};
+use crate::db::Database;
+use crate::domain_events::{DomainEvent, Subscriber};
+use crate::encryption::{conceal, conceal_opt, reveal};
+use crate::error::AppError;
+use crate::http;
+use crate::log_warning;
+use crate::order_status::OrderStatus;
+use crate::orders::fetch_order;
+use crate::redact::redact;
+
+// Wait before each retry; a delivery that still fails after the last one is
+// given up on
+const RETRY_DELAYS_SECS: [i64; 6] = [60, 300, 1800, 7200, 21600, 86400];
+
+const REQUEST_TIMEOUT_SECS: u64 = 15;
+
+// Deliveries sent per dispatcher tick
+const DISPATCH_BATCH: i64 = 50;
+
+const USER_AGENT: &str = concat!("POTracker-Webhooks/", env!("CARGO_PKG_VERSION"));
+
+const ENDPOINT_COLUMNS: &str = "id, name, url, secret, enabled, created_at, updated_at";
+
+const DELIVERY_COLUMNS: &str = "d.id, d.endpoint_id, e.name AS endpoint_name, d.delivery_uid, \
+     d.event, d.preorder_id, d.payload, d.status, d.attempts, d.next_attempt_at, \
+     d.last_response_status, d.last_error, d.created_at, d.delivered_at";
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+pub enum OrderWebhookEvent {
+    #[serde(rename = "order.created")]
+    #[sqlx(rename = "order.created")]
+    Created,
+    #[serde(rename = "order.paid")]
+    #[sqlx(rename = "order.paid")]
+    Paid,
+    #[serde(rename = "order.fulfilled")]
+    #[sqlx(rename = "order.fulfilled")]
+    Fulfilled,
+}
+
+impl OrderWebhookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderWebhookEvent::Created => "order.created",
+            OrderWebhookEvent::Paid => "order.paid",
+            OrderWebhookEvent::Fulfilled => "order.fulfilled",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    // Every retry failed; only a manual retry sends it again
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WebhookEndpoint {
+    pub id: i64,
+    pub name: String,
+    pub url: String,
+    pub secret: String,
+    pub enabled: bool,
+    #[sqlx(skip)]
+    pub events: Vec<OrderWebhookEvent>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookEndpointInput {
+    pub name: String,
+    pub url: String,
+    // Generated when creating without one; kept when updating without one
+    pub secret: Option<String>,
+    pub events: Vec<OrderWebhookEvent>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub endpoint_id: i64,
+    pub endpoint_name: String,
+    pub delivery_uid: String,
+    pub event: OrderWebhookEvent,
+    pub preorder_id: Option<i64>,
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: i64,
+    pub next_attempt_at: Option<String>,
+    pub last_response_status: Option<i64>,
+    pub last_error: Option<String>,
+    pub created_at: Option<String>,
+    pub delivered_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct DeliveryAttempt {
+    pub id: i64,
+    pub delivery_id: i64,
+    pub attempted_at: Option<String>,
+    pub response_status: Option<i64>,
+    pub error: Option<String>,
+    pub duration_ms: i64,
+}
+
+// Emitted as the "webhook-delivery-failed" event when a delivery is given up on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliveryFailed {
+    pub delivery_id: i64,
+    pub endpoint_id: i64,
+    pub event: OrderWebhookEvent,
+    pub error: Option<String>,
+}
+
+// A claimed delivery with what's needed to send it
+#[derive(sqlx::FromRow)]
+struct DueDelivery {
+    id: i64,
+    endpoint_id: i64,
+    delivery_uid: String,
+    event: OrderWebhookEvent,
+    payload: String,
+    attempts: i64,
+    url: String,
+    secret: String,
+}
+
+// Helper: Queue `event` for an order to every enabled endpoint subscribed to
+// it, inside the caller's transaction so nothing is sent for changes that
+// roll back
+async fn queue_order_event(
+    conn: &mut SqliteConnection,
+    event: OrderWebhookEvent,
+    po_id: i64,
+) -> Result<(), AppError> {
+    let endpoints = sqlx::query_scalar::<_, i64>(
+        "SELECT e.id FROM webhook_endpoints e \
+         JOIN webhook_endpoint_events ev ON ev.endpoint_id = e.id \
+         WHERE e.enabled = 1 AND ev.event = ?",
+    )
+    .bind(event)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load webhook endpoints: {}", e))?;
+    if endpoints.is_empty() {
+        return Ok(());
+    }
+
+    let order = fetch_order(&mut *conn, po_id).await?;
+    let created_at = chrono::Utc::now().to_rfc3339();
+    for endpoint_id in endpoints {
+        let delivery_uid = Uuid::new_v4().to_string();
+        let payload = serde_json::json!({
+            "id": delivery_uid,
+            "event": event,
+            "created_at": created_at,
+            "data": { "order": order },
+        });
+        sqlx::query(
+            "INSERT INTO webhook_deliveries (endpoint_id, delivery_uid, event, preorder_id, payload) \
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(endpoint_id)
+        .bind(&delivery_uid)
+        .bind(event)
+        .bind(po_id)
+        .bind(payload.to_string())
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to queue webhook delivery: {}", e))?;
+    }
+    Ok(())
+}
+
+// Queues deliveries for order creation and for orders becoming paid or
+// fulfilled
+pub struct OutgoingWebhooks;
+
+impl Subscriber for OutgoingWebhooks {
+    async fn handle(
+        &self,
+        conn: &mut SqliteConnection,
+        event: &DomainEvent<'_>,
+    ) -> Result<(), AppError> {
+        let (webhook_event, po_id) = match *event {
+            DomainEvent::OrderCreated { order_id } => (OrderWebhookEvent::Created, order_id),
+            DomainEvent::OrderStatusChanged(change) => match change.to {
+                OrderStatus::Paid => (OrderWebhookEvent::Paid, change.order_id),
+                OrderStatus::Fulfilled => (OrderWebhookEvent::Fulfilled, change.order_id),
+                _ => return Ok(()),
+            },
+            _ => return Ok(()),
+        };
+        queue_order_event(conn, webhook_event, po_id).await
+    }
+}
+
+// Helper: X-POTracker-Signature value: t=<unix time>,v1=<hex HMAC-SHA256 of
+// "<t>.<body>">, the same scheme Stripe uses
+fn signature_header(secret: &str, timestamp: i64, body: &str) -> Result<String, AppError> {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .map_err(|e| format!("Invalid webhook secret: {}", e))?;
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    let hex: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    Ok(format!("t={},v1={}", timestamp, hex))
+}
+
+fn http_client() -> Result<Client, AppError> {
+    http::client_builder()
+        .user_agent(USER_AGENT)
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| AppError::Internal(format!("Failed to create HTTP client: {}", e)))
+}
+
+// Helper: Send one claimed delivery and record the attempt. Returns the
+// delivery's new status.
+async fn send_delivery(
+    pool: &SqlitePool,
+    client: &Client,
+    delivery: &DueDelivery,
+) -> Result<(DeliveryStatus, Option<String>), AppError> {
+    let timestamp = chrono::Utc::now().timestamp();
+    let signature = signature_header(&delivery.secret, timestamp, &delivery.payload)?;
+
+    let started = Instant::now();
+    let response = client
+        .post(&delivery.url)
+        .header("Content-Type", "application/json")
+        .header("X-POTracker-Event", delivery.event.as_str())
+        .header("X-POTracker-Delivery", &delivery.delivery_uid)
+        .header("X-POTracker-Signature", signature)
+        .body(delivery.payload.clone())
+        .send()
+        .await;
+    let (response_status, error) = match response {
+        Ok(response) if response.status().is_success() => (Some(response.status().as_u16()), None),
+        Ok(response) => {
+            let status = response.status();
+            let body = redact(&response.text().await.unwrap_or_default());
+            let snippet: String = body.chars().take(500).collect();
+            (
+                Some(status.as_u16()),
+                Some(format!("HTTP {}: {}", status, snippet.trim())),
+            )
+        }
+        Err(e) => (None, Some(redact(&e.to_string()))),
+    };
+    let duration_ms = started.elapsed().as_millis() as i64;
+
+    let attempts = delivery.attempts + 1;
+    let status = if error.is_none() {
+        DeliveryStatus::Delivered
+    } else if attempts > RETRY_DELAYS_SECS.len() as i64 {
+        DeliveryStatus::Failed
+    } else {
+        DeliveryStatus::Pending
+    };
+    let retry_in = RETRY_DELAYS_SECS
+        .get((attempts - 1).max(0) as usize)
+        .copied()
+        .unwrap_or_default();
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    sqlx::query(
+        "INSERT INTO webhook_delivery_attempts (delivery_id, response_status, error, duration_ms) \
+         VALUES (?, ?, ?, ?)",
+    )
+    .bind(delivery.id)
+    .bind(response_status)
+    .bind(&error)
+    .bind(duration_ms)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to record webhook attempt: {}", e))?;
+    sqlx::query(
+        "UPDATE webhook_deliveries SET status = ?, attempts = ?, last_response_status = ?, \
+         last_error = ?, next_attempt_at = CASE WHEN ? = 'pending' \
+         THEN datetime('now', '+' || ? || ' seconds') END, \
+         delivered_at = CASE WHEN ? = 'delivered' THEN CURRENT_TIMESTAMP END \
+         WHERE id = ?",
+    )
+    .bind(status)
+    .bind(attempts)
+    .bind(response_status)
+    .bind(&error)
+    .bind(status)
+    .bind(retry_in)
+    .bind(status)
+    .bind(delivery.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to update webhook delivery: {}", e))?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save webhook delivery: {}", e))?;
+
+    Ok((status, error))
+}
+
+// Helper: Claim a due delivery so a manual retry and the dispatcher can't
+// both send it. The lease runs out if the app quits mid-send.
+async fn claim_delivery(pool: &SqlitePool, id: i64) -> Result<Option<DueDelivery>, AppError> {
+    let claimed = sqlx::query(
+        "UPDATE webhook_deliveries SET next_attempt_at = datetime('now', '+10 minutes') \
+         WHERE id = ? AND status = 'pending' AND next_attempt_at <= CURRENT_TIMESTAMP",
+    )
+    .bind(id)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to claim webhook delivery: {}", e))?;
+    if claimed.rows_affected() == 0 {
+        return Ok(None);
+    }
+
+    sqlx::query_as::<_, DueDelivery>(
+        "SELECT d.id, d.endpoint_id, d.delivery_uid, d.event, d.payload, d.attempts, \
+         e.url, e.secret FROM webhook_deliveries d \
+         JOIN webhook_endpoints e ON e.id = d.endpoint_id WHERE d.id = ?",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load webhook delivery: {}", e))?
+    .map(|delivery| {
+        Ok(DueDelivery {
+            secret: reveal(delivery.secret)?,
+            ..delivery
+        })
+    })
+    .transpose()
+}
+
+// Helper: Send every pending delivery that has come due on an enabled
+// endpoint. Returns the ones given up on.
+pub async fn dispatch_due_deliveries(pool: &SqlitePool) -> Result<Vec<DeliveryFailed>, AppError> {
+    let due = sqlx::query_scalar::<_, i64>(
+        "SELECT d.id FROM webhook_deliveries d JOIN webhook_endpoints e ON e.id = d.endpoint_id \
+         WHERE d.status = 'pending' AND e.enabled = 1 AND d.next_attempt_at <= CURRENT_TIMESTAMP \
+         ORDER BY d.next_attempt_at, d.id LIMIT ?",
+    )
+    .bind(DISPATCH_BATCH)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load webhook deliveries: {}", e))?;
+    if due.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let client = http_client()?;
+    let mut failed = Vec::new();
+    for id in due {
+        let Some(delivery) = claim_delivery(pool, id).await? else {
+            continue;
+        };
+        if let (DeliveryStatus::Failed, error) = send_delivery(pool, &client, &delivery).await? {
+            failed.push(DeliveryFailed {
+                delivery_id: delivery.id,
+                endpoint_id: delivery.endpoint_id,
+                event: delivery.event,
+                error,
+            });
+        }
+    }
+    Ok(failed)
+}
+
+// Start the background loop that sends queued webhook deliveries
+pub fn start_dispatcher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<Database>().pool.clone();
+        loop {
+            match dispatch_due_deliveries(&pool).await {
+                Ok(failed) => {
+                    for failure in failed {
+                        if let Err(e) = app.emit("webhook-delivery-failed", &failure) {
+                            log_warning!("Failed to emit webhook-delivery-failed event: {}", e);
+                        }
+                    }
+                }
+                Err(e) => log_warning!("Webhook dispatcher failed: {}", e),
+            }
+            automation::wait_for_next_poll(&pool, Poller::Sender).await;
+        }
+    });
+}
+
+// Helper: Validate an endpoint's fields
+fn validate_endpoint(endpoint: &WebhookEndpointInput) -> Result<(), AppError> {
+    if endpoint.name.trim().is_empty() {
+        return Err(AppError::Validation(
+            "Webhook name must not be empty".to_string(),
+        ));
+    }
+    let url = reqwest::Url::parse(endpoint.url.trim())
+        .map_err(|e| format!("Invalid webhook URL: {}", e))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(AppError::Validation(
+            "Webhook URL must start with http:// or https://".to_string(),
+        ));
+    }
+    if endpoint.events.is_empty() {
+        return Err(AppError::Validation(
+            "Choose at least one event to send".to_string(),
+        ));
+    }
+    if matches!(&endpoint.secret, Some(secret) if secret.trim().is_empty()) {
+        return Err(AppError::Validation(
+            "Webhook secret must not be empty".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// Helper: Load endpoints with their subscribed events
+async fn load_endpoints(
+    conn: &mut SqliteConnection,
+    id: Option<i64>,
+) -> Result<Vec<WebhookEndpoint>, AppError> {
+    let mut endpoints = sqlx::query_as::<_, WebhookEndpoint>(&format!(
+        "SELECT {} FROM webhook_endpoints WHERE (? IS NULL OR id = ?) ORDER BY name",
+        ENDPOINT_COLUMNS
+    ))
+    .bind(id)
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load webhook endpoints: {}", e))?;
+
+    let rows = sqlx::query_as::<_, (i64, OrderWebhookEvent)>(
+        "SELECT endpoint_id, event FROM webhook_endpoint_events \
+         WHERE (? IS NULL OR endpoint_id = ?) ORDER BY event",
+    )
+    .bind(id)
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load webhook events: {}", e))?;
+    let mut events: HashMap<i64, Vec<OrderWebhookEvent>> = HashMap::new();
+    for (endpoint_id, event) in rows {
+        events.entry(endpoint_id).or_default().push(event);
+    }
+    for endpoint in endpoints.iter_mut() {
+        endpoint.events = events.remove(&endpoint.id).unwrap_or_default();
+        endpoint.secret = reveal(std::mem::take(&mut endpoint.secret))?;
+    }
+    Ok(endpoints)
+}
+
+async fn load_endpoint(conn: &mut SqliteConnection, id: i64) -> Result<WebhookEndpoint, AppError> {
+    load_endpoints(conn, Some(id))
+        .await?
+        .pop()
+        .ok_or_else(|| AppError::NotFound(format!("Webhook endpoint {} not found", id)))
+}
+
+// Helper: Replace an endpoint's subscribed events
+async fn write_events(
+    conn: &mut SqliteConnection,
+    endpoint_id: i64,
+    events: &[OrderWebhookEvent],
+) -> Result<(), AppError> {
+    sqlx::query("DELETE FROM webhook_endpoint_events WHERE endpoint_id = ?")
+        .bind(endpoint_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to save webhook events: {}", e))?;
+    for event in events {
+        sqlx::query(
+            "INSERT OR IGNORE INTO webhook_endpoint_events (endpoint_id, event) VALUES (?, ?)",
+        )
+        .bind(endpoint_id)
+        .bind(event)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to save webhook events: {}", e))?;
+    }
+    Ok(())
+}
+
+// Helper: What the audit log keeps of an endpoint; the secret stays out of it
+fn audit_view(endpoint: &WebhookEndpoint) -> serde_json::Value {
+    serde_json::json!({
+        "name": endpoint.name,
+        "url": endpoint.url,
+        "events": endpoint.events,
+        "enabled": endpoint.enabled,
+    })
+}
+
+#[tauri::command]
+pub async fn list_webhook_endpoints(
+    db: State<'_, Database>,
+) -> Result<Vec<WebhookEndpoint>, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    load_endpoints(&mut conn, None).await
+}
+
+#[tauri::command]
+pub async fn create_webhook_endpoint(
+    db: State<'_, Database>,
+    endpoint: WebhookEndpointInput,
+) -> Result<WebhookEndpoint, AppError> {
+    validate_endpoint(&endpoint)?;
+    let secret = endpoint
+        .secret
+        .as_deref()
+        .map(|s| s.trim().to_string())
+        .unwrap_or_else(|| format!("whsec_{}", Uuid::new_v4().simple()));
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let id = sqlx::query(
+        "INSERT INTO webhook_endpoints (name, url, secret, enabled) VALUES (?, ?, ?, ?)",
+    )
+    .bind(endpoint.name.trim())
+    .bind(endpoint.url.trim())
+    .bind(conceal(&secret)?)
+    .bind(endpoint.enabled.unwrap_or(true))
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to create webhook endpoint: {}", e))?
+    .last_insert_rowid();
+    write_events(&mut tx, id, &endpoint.events).await?;
+
+    let created = load_endpoint(&mut tx, id).await?;
+    audit::record(
+        &mut *tx,
+        "webhook_endpoint",
+        id,
+        "create",
+        None,
+        Some(&audit_view(&created)),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save webhook endpoint: {}", e))?;
+    Ok(created)
+}
+
+#[tauri::command]
+pub async fn update_webhook_endpoint(
+    db: State<'_, Database>,
+    id: i64,
+    endpoint: WebhookEndpointInput,
+) -> Result<WebhookEndpoint, AppError> {
+    validate_endpoint(&endpoint)?;
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_endpoint(&mut tx, id).await?;
+    sqlx::query(
+        "UPDATE webhook_endpoints SET name = ?, url = ?, secret = COALESCE(?, secret), \
+         enabled = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+    )
+    .bind(endpoint.name.trim())
+    .bind(endpoint.url.trim())
+    .bind(conceal_opt(endpoint.secret.as_deref().map(str::trim))?)
+    .bind(endpoint.enabled.unwrap_or(true))
+    .bind(id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to update webhook endpoint: {}", e))?;
+    write_events(&mut tx, id, &endpoint.events).await?;
+
+    let after = load_endpoint(&mut tx, id).await?;
+    audit::record(
+        &mut *tx,
+        "webhook_endpoint",
+        id,
+        "update",
+        Some(&audit_view(&before)),
+        Some(&audit_view(&after)),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to update webhook endpoint: {}", e))?;
+    Ok(after)
+}
+
+// Deleting an endpoint drops its queued deliveries and their logs too
+#[tauri::command]
+pub async fn delete_webhook_endpoint(db: State<'_, Database>, id: i64) -> Result<(), AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_endpoint(&mut tx, id).await?;
+    sqlx::query("DELETE FROM webhook_endpoints WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to delete webhook endpoint: {}", e))?;
+    audit::record(
+        &mut *tx,
+        "webhook_endpoint",
+        id,
+        "delete",
+        Some(&audit_view(&before)),
+        None,
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to delete webhook endpoint: {}", e)))
+}
+
+async fn load_delivery(pool: &SqlitePool, id: i64) -> Result<WebhookDelivery, AppError> {
+    sqlx::query_as::<_, WebhookDelivery>(&format!(
+        "SELECT {} FROM webhook_deliveries d JOIN webhook_endpoints e ON e.id = d.endpoint_id \
+         WHERE d.id = ?",
+        DELIVERY_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load webhook delivery: {}", e))?
+    .ok_or_else(|| AppError::NotFound(format!("Webhook delivery {} not found", id)))
+}
+
+// Delivery log, newest first
+#[tauri::command]
+pub async fn list_webhook_deliveries(
+    db: State<'_, Database>,
+    endpoint_id: Option<i64>,
+    status: Option<DeliveryStatus>,
+    limit: Option<i64>,
+) -> Result<Vec<WebhookDelivery>, AppError> {
+    sqlx::query_as::<_, WebhookDelivery>(&format!(
+        "SELECT {} FROM webhook_deliveries d JOIN webhook_endpoints e ON e.id = d.endpoint_id \
+         WHERE (? IS NULL OR d.endpoint_id = ?) AND (? IS NULL OR d.status = ?) \
+         ORDER BY d.created_at DESC, d.id DESC LIMIT ?",
+        DELIVERY_COLUMNS
+    ))
+    .bind(endpoint_id)
+    .bind(endpoint_id)
+    .bind(status)
+    .bind(status)
+    .bind(limit.unwrap_or(200).clamp(1, 1000))
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to list webhook deliveries: {}", e)))
+}
+
+#[tauri::command]
+pub async fn list_webhook_delivery_attempts(
+    db: State<'_, Database>,
+    delivery_id: i64,
+) -> Result<Vec<DeliveryAttempt>, AppError> {
+    sqlx::query_as::<_, DeliveryAttempt>(
+        "SELECT id, delivery_id, attempted_at, response_status, error, duration_ms \
+         FROM webhook_delivery_attempts WHERE delivery_id = ? ORDER BY id",
+    )
+    .bind(delivery_id)
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to list webhook attempts: {}", e)))
+}
+
+// Send a delivery again now, whatever its status. A failed delivery gets one
+// more attempt; a delivered one is resent with the same ID.
+#[tauri::command]
+pub async fn retry_webhook_delivery(
+    app: AppHandle,
+    db: State<'_, Database>,
+    id: i64,
+) -> Result<WebhookDelivery, AppError> {
+    let result = sqlx::query(
+        "UPDATE webhook_deliveries SET status = 'pending', next_attempt_at = CURRENT_TIMESTAMP \
+         WHERE id = ?",
+    )
+    .bind(id)
+    .execute(&db.pool)
+    .await
+    .map_err(|e| format!("Failed to update webhook delivery: {}", e))?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(format!(
+            "Webhook delivery {} not found",
+            id
+        )));
+    }
+
+    if let Some(delivery) = claim_delivery(&db.pool, id).await? {
+        let (status, error) = send_delivery(&db.pool, &http_client()?, &delivery).await?;
+        if status == DeliveryStatus::Failed {
+            let failure = DeliveryFailed {
+                delivery_id: delivery.id,
+                endpoint_id: delivery.endpoint_id,
+                event: delivery.event,
+                error,
+            };
+            if let Err(e) = app.emit("webhook-delivery-failed", &failure) {
+                log_warning!("Failed to emit webhook-delivery-failed event: {}", e);
+            }
+        }
+    }
+    load_delivery(&db.pool, id).await
+}
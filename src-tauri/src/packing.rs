@@ -0,0 +1,326 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use tauri::State;
+
+use crate::db::Database;
+use crate::encryption::reveal_opt;
+use crate::error::AppError;
+use crate::fulfillment::{
+    load_fulfillment, load_packing_list, Fulfillment, FulfillmentMethod, PackingListProduct,
+};
+use crate::i18n::{load_locale, Locale};
+use crate::invoices::product_name;
+use crate::models::PurchaseOrder;
+use crate::orders::fetch_order;
+use crate::pdf::{
+    safe_file_name, save_pdf, wrap_text, PdfWriter, MARGIN, MUTED_COLOR, PAGE_WIDTH, RULE_COLOR,
+    TEXT_COLOR,
+};
+
+const RIGHT_EDGE: f32 = PAGE_WIDTH - MARGIN;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackingDocuments {
+    // One page per order
+    pub packing_slips_path: String,
+    // Every product to pick for the campaign, with the orders it goes to
+    pub pick_list_path: String,
+    pub orders: usize,
+    pub products: usize,
+    pub units: i64,
+}
+
+// What a packing slip needs beyond the order itself
+struct SlipData {
+    order: PurchaseOrder,
+    fulfillment: Option<Fulfillment>,
+    phone: Option<String>,
+    address: Option<String>,
+}
+
+// Helper: Header shared by both documents
+fn document_header(pdf: &mut PdfWriter, title: &str, campaign: &str, reference: &str) {
+    pdf.start_section();
+    pdf.text(MARGIN, 20.0, true, TEXT_COLOR, title);
+    pdf.text_right(RIGHT_EDGE, 14.0, true, TEXT_COLOR, reference);
+    pdf.advance(7.0);
+    pdf.text(MARGIN, 10.0, false, MUTED_COLOR, campaign);
+    pdf.advance(4.0);
+    pdf.rule(RULE_COLOR, 0.8);
+    pdf.advance(9.0);
+}
+
+// Helper: Wrapped text block, moving to a new page first if it won't fit
+fn paragraph(pdf: &mut PdfWriter, size: f32, text: &str) {
+    let lines = wrap_text(text, RIGHT_EDGE - MARGIN, size, false);
+    pdf.ensure_space(lines.len() as f32 * (size * 0.45));
+    for line in lines {
+        pdf.text(MARGIN, size, false, TEXT_COLOR, &line);
+        pdf.advance(size * 0.45);
+    }
+}
+
+fn render_packing_slip(pdf: &mut PdfWriter, locale: Locale, campaign: &str, slip: &SlipData) {
+    let order = &slip.order;
+    let label = |key: &str| locale.text(key);
+    document_header(
+        pdf,
+        &label("packing.title"),
+        campaign,
+        &order.confirmation_code,
+    );
+
+    pdf.text(MARGIN, 9.0, true, MUTED_COLOR, &label("packing.ship_to"));
+    pdf.text(120.0, 9.0, true, MUTED_COLOR, &label("packing.delivery"));
+    pdf.advance(5.5);
+    let method = match slip.fulfillment.as_ref().map(|f| f.method) {
+        Some(FulfillmentMethod::Pickup) => label("packing.pickup"),
+        _ => label("packing.shipping"),
+    };
+    pdf.text(MARGIN, 11.0, true, TEXT_COLOR, &order.customer_name);
+    pdf.text(120.0, 10.0, false, TEXT_COLOR, &method);
+    pdf.advance(5.0);
+
+    // Right-hand column: courier, tracking and invoice reference
+    let mut details: Vec<String> = Vec::new();
+    if let Some(fulfillment) = &slip.fulfillment {
+        if let Some(courier) = &fulfillment.courier {
+            details.push(courier.clone());
+        }
+        if let Some(tracking) = &fulfillment.tracking_number {
+            details.push(locale.format("packing.tracking", &[("number", tracking)]));
+        }
+    }
+    if let Some(number) = &order.invoice_number {
+        details.push(locale.format("packing.invoice", &[("number", number)]));
+    }
+    let mut contact: Vec<String> = vec![order.customer_email.clone()];
+    contact.extend(slip.phone.clone());
+    if let Some(address) = &slip.address {
+        contact.extend(wrap_text(address, 95.0, 10.0, false));
+    }
+    for i in 0..contact.len().max(details.len()) {
+        if let Some(line) = contact.get(i) {
+            pdf.text(MARGIN, 10.0, false, TEXT_COLOR, line);
+        }
+        if let Some(line) = details.get(i) {
+            pdf.text(120.0, 10.0, false, TEXT_COLOR, line);
+        }
+        pdf.advance(5.0);
+    }
+    pdf.advance(8.0);
+
+    let item_header = |pdf: &mut PdfWriter| {
+        pdf.text(
+            MARGIN + 10.0,
+            9.0,
+            true,
+            MUTED_COLOR,
+            &label("invoice.item"),
+        );
+        pdf.text_right(RIGHT_EDGE, 9.0, true, MUTED_COLOR, &label("invoice.qty"));
+        pdf.advance(2.5);
+        pdf.rule(RULE_COLOR, 0.8);
+        pdf.advance(6.0);
+    };
+    item_header(pdf);
+    let mut units = 0;
+    for item in &order.items {
+        let name = product_name(locale, item);
+        let lines = wrap_text(&name, RIGHT_EDGE - MARGIN - 30.0, 10.0, false);
+        if pdf.y - (lines.len() as f32 * 4.5 + 3.0) < MARGIN {
+            pdf.new_page();
+            item_header(pdf);
+        }
+        // Box to tick once the item is in the parcel
+        pdf.text(MARGIN, 10.0, false, TEXT_COLOR, "[   ]");
+        pdf.text_right(
+            RIGHT_EDGE,
+            10.0,
+            true,
+            TEXT_COLOR,
+            &item.quantity.to_string(),
+        );
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                pdf.advance(4.5);
+            }
+            pdf.text(MARGIN + 10.0, 10.0, false, TEXT_COLOR, line);
+        }
+        pdf.advance(2.0);
+        pdf.rule(RULE_COLOR, 0.4);
+        pdf.advance(6.0);
+        units += item.quantity;
+    }
+    pdf.text_right(
+        RIGHT_EDGE,
+        10.0,
+        true,
+        TEXT_COLOR,
+        &locale.format("packing.units", &[("count", &units.to_string())]),
+    );
+    pdf.advance(10.0);
+
+    let notes = [
+        order.notes.as_deref(),
+        slip.fulfillment.as_ref().and_then(|f| f.notes.as_deref()),
+    ];
+    for note in notes.into_iter().flatten().filter(|n| !n.trim().is_empty()) {
+        pdf.ensure_space(10.0);
+        pdf.text(MARGIN, 9.0, true, MUTED_COLOR, &label("invoice.notes"));
+        pdf.advance(4.5);
+        paragraph(pdf, 9.0, note);
+        pdf.advance(3.0);
+    }
+}
+
+fn render_pick_list(
+    pdf: &mut PdfWriter,
+    locale: Locale,
+    campaign: &str,
+    products: &[PackingListProduct],
+    orders: usize,
+) {
+    let units: i64 = products.iter().map(|p| p.total_quantity).sum();
+    let generated = chrono::Local::now().format("%Y-%m-%d %H:%M").to_string();
+    document_header(
+        pdf,
+        &locale.text("pick_list.title"),
+        &locale.format(
+            "pick_list.generated",
+            &[("campaign", campaign), ("date", &generated)],
+        ),
+        &locale.format(
+            "pick_list.summary",
+            &[
+                ("orders", &orders.to_string()),
+                ("units", &units.to_string()),
+            ],
+        ),
+    );
+
+    for product in products {
+        // Keep a product's heading together with its first order line
+        pdf.ensure_space(14.0);
+        pdf.text(MARGIN, 11.0, true, TEXT_COLOR, &product.product_name);
+        pdf.text_right(
+            RIGHT_EDGE,
+            11.0,
+            true,
+            TEXT_COLOR,
+            &product.total_quantity.to_string(),
+        );
+        pdf.advance(2.0);
+        pdf.rule(RULE_COLOR, 0.4);
+        pdf.advance(5.0);
+        for line in &product.orders {
+            pdf.ensure_space(5.0);
+            pdf.text(
+                MARGIN + 4.0,
+                9.0,
+                false,
+                MUTED_COLOR,
+                &line.confirmation_code,
+            );
+            pdf.text(MARGIN + 34.0, 9.0, false, TEXT_COLOR, &line.customer_name);
+            pdf.text_right(
+                RIGHT_EDGE,
+                9.0,
+                false,
+                TEXT_COLOR,
+                &line.quantity.to_string(),
+            );
+            pdf.advance(4.8);
+        }
+        pdf.advance(4.0);
+    }
+}
+
+// Render packing slips (one page per order) and a consolidated pick list for
+// a campaign's orders that are ready to ship: confirmed, invoiced or paid and
+// not shipped yet. Both PDFs go to `dest_dir`.
+#[tauri::command]
+pub async fn generate_packing_documents(
+    db: State<'_, Database>,
+    campaign_id: i64,
+    dest_dir: String,
+) -> Result<PackingDocuments, AppError> {
+    let campaign = sqlx::query_scalar::<_, String>("SELECT name FROM events WHERE id = ?")
+        .bind(campaign_id)
+        .fetch_optional(&db.pool)
+        .await
+        .map_err(|e| format!("Failed to load event: {}", e))?
+        .ok_or_else(|| AppError::NotFound(format!("Event {} not found", campaign_id)))?;
+
+    let locale = load_locale(&db.pool).await;
+    let products = load_packing_list(&db.pool, Some(campaign_id), false).await?;
+    let order_ids: BTreeSet<i64> = products
+        .iter()
+        .flat_map(|p| p.orders.iter().map(|line| line.order_id))
+        .collect();
+    if order_ids.is_empty() {
+        return Err(AppError::Validation(format!(
+            "No orders in {} are waiting to be packed",
+            campaign
+        )));
+    }
+
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let mut slips = Vec::with_capacity(order_ids.len());
+    for id in order_ids {
+        let order = fetch_order(&mut conn, id).await?;
+        let fulfillment = load_fulfillment(&mut *conn, id).await?;
+        let contact = sqlx::query_as::<_, (Option<String>, Option<String>)>(
+            "SELECT phone, address FROM customers WHERE email = ? AND deleted_at IS NULL",
+        )
+        .bind(&order.customer_email)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to load customer: {}", e))?;
+        let (phone, address) = contact.unwrap_or_default();
+        let (phone, address) = (reveal_opt(phone)?, reveal_opt(address)?);
+        slips.push(SlipData {
+            order,
+            fulfillment,
+            phone,
+            address,
+        });
+    }
+    drop(conn);
+    slips.sort_by(|a, b| {
+        a.order
+            .customer_name
+            .to_lowercase()
+            .cmp(&b.order.customer_name.to_lowercase())
+            .then(a.order.id.cmp(&b.order.id))
+    });
+
+    let dir = PathBuf::from(&dest_dir);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create export folder: {}", e))?;
+    let stem = safe_file_name(&campaign);
+
+    let mut pdf = PdfWriter::new(&locale.format("packing.document", &[("campaign", &campaign)]))?;
+    for slip in &slips {
+        render_packing_slip(&mut pdf, locale, &campaign, slip);
+    }
+    let packing_slips_path = dir.join(format!("packing-slips-{}.pdf", stem));
+    save_pdf(&packing_slips_path, &pdf.finish()?)?;
+
+    let mut pdf = PdfWriter::new(&locale.format("pick_list.document", &[("campaign", &campaign)]))?;
+    render_pick_list(&mut pdf, locale, &campaign, &products, slips.len());
+    let pick_list_path = dir.join(format!("pick-list-{}.pdf", stem));
+    save_pdf(&pick_list_path, &pdf.finish()?)?;
+
+    Ok(PackingDocuments {
+        packing_slips_path: packing_slips_path.to_string_lossy().to_string(),
+        pick_list_path: pick_list_path.to_string_lossy().to_string(),
+        orders: slips.len(),
+        products: products.len(),
+        units: products.iter().map(|p| p.total_quantity).sum(),
+    })
+}
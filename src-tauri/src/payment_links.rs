@@ -0,0 +1,507 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, Sqlite, SqliteConnection, SqlitePool};
+use tauri::{AppHandle, State};
+
+use crate::audit;
+use crate::currency::{default_currency, normalize_currency};
+use crate::db::Database;
+use crate::error::AppError;
+use crate::models::PurchaseOrder;
+use crate::order_status::{emit_transition, Transition};
+use crate::orders::fetch_order;
+use crate::payments::{balance_epsilon, insert_payment, Payment, PaymentInput};
+
+pub const PAYMENT_LINK_COLUMNS: &str = "id, preorder_id, provider, external_id, url, method, \
+     payment_code, amount, currency_code, status, payment_reference, payment_id, created_at, \
+     expires_at, paid_at, checked_at";
+
+// A link the customer can pay an order's balance through online. Open links
+// are checked with the provider and recorded as payments once they're paid.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PaymentLink {
+    pub id: i64,
+    pub preorder_id: i64,
+    // 'stripe', 'paypal' or 'midtrans'
+    pub provider: String,
+    pub external_id: String,
+    // Page to pay on; virtual accounts have none
+    pub url: Option<String>,
+    // e.g. 'bank_transfer:bca' or 'qris'; None when the customer picks on the page
+    pub method: Option<String>,
+    // Virtual account number or QRIS payload
+    pub payment_code: Option<String>,
+    pub amount: f64,
+    pub currency_code: String,
+    // 'open', 'paid' or 'cancelled'
+    pub status: String,
+    pub payment_reference: Option<String>,
+    pub payment_id: Option<i64>,
+    pub created_at: Option<String>,
+    pub expires_at: Option<String>,
+    pub paid_at: Option<String>,
+    pub checked_at: Option<String>,
+}
+
+// What a provider handed back for a new link
+#[derive(Debug, Clone, Default)]
+pub struct NewPaymentLink {
+    pub external_id: String,
+    pub url: Option<String>,
+    pub method: Option<String>,
+    pub payment_code: Option<String>,
+    // "YYYY-MM-DD HH:MM:SS" UTC
+    pub expires_at: Option<String>,
+}
+
+// What a provider says about an open link
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkState {
+    Open,
+    Paid {
+        amount: f64,
+        // The provider's payment ID, kept as the payment reference
+        reference: String,
+        // "YYYY-MM-DD HH:MM:SS" UTC
+        paid_at: String,
+    },
+    // Withdrawn with the provider, so it can't be paid any more
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkCheckOutcome {
+    // Paid since the last check and recorded as a payment
+    Paid,
+    // Still waiting for the customer
+    Open,
+    // The order was cancelled, deleted or paid some other way; link disabled
+    Cancelled,
+    // The provider couldn't be asked, or the payment couldn't be recorded
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkCheck {
+    pub link: PaymentLink,
+    pub outcome: LinkCheckOutcome,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentSync {
+    pub checks: Vec<LinkCheck>,
+    // Orders that were marked paid by this sync
+    pub paid_orders: Vec<i64>,
+}
+
+// An online payment provider that issues links for an order's balance. Each
+// one only has to say what became of a link and how to withdraw it; checking,
+// recording payments and replacing stale links are shared.
+pub trait PaymentGateway {
+    // Stored as payment_links.provider and as the recorded payment's method
+    const PROVIDER: &'static str;
+
+    async fn link_state(&self, link: &PaymentLink) -> Result<LinkState, AppError>;
+
+    // Stop the link taking payments, so it can be replaced or dropped
+    async fn withdraw(&self, link: &PaymentLink) -> Result<(), AppError>;
+}
+
+pub async fn load_payment_link(
+    conn: &mut SqliteConnection,
+    id: i64,
+) -> Result<PaymentLink, AppError> {
+    sqlx::query_as::<_, PaymentLink>(&format!(
+        "SELECT {} FROM payment_links WHERE id = ?",
+        PAYMENT_LINK_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| format!("Failed to load payment link: {}", e))?
+    .ok_or_else(|| AppError::NotFound(format!("Payment link {} not found", id)))
+}
+
+// Helper: Links of one provider still waiting to be paid
+pub async fn open_payment_links(
+    conn: &mut SqliteConnection,
+    provider: &str,
+) -> Result<Vec<PaymentLink>, AppError> {
+    sqlx::query_as::<_, PaymentLink>(&format!(
+        "SELECT {} FROM payment_links WHERE provider = ? AND status = 'open' ORDER BY id",
+        PAYMENT_LINK_COLUMNS
+    ))
+    .bind(provider)
+    .fetch_all(conn)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to load payment links: {}", e)))
+}
+
+// Helper: An order's most recent link with one provider, whatever its status
+pub async fn latest_payment_link(
+    conn: &mut SqliteConnection,
+    preorder_id: i64,
+    provider: &str,
+) -> Result<Option<PaymentLink>, AppError> {
+    sqlx::query_as::<_, PaymentLink>(&format!(
+        "SELECT {} FROM payment_links WHERE preorder_id = ? AND provider = ? \
+         ORDER BY id DESC LIMIT 1",
+        PAYMENT_LINK_COLUMNS
+    ))
+    .bind(preorder_id)
+    .bind(provider)
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to load payment link: {}", e)))
+}
+
+// Helper: Save a link the provider just created for an order
+pub async fn insert_payment_link(
+    conn: &mut SqliteConnection,
+    preorder_id: i64,
+    provider: &str,
+    created: &NewPaymentLink,
+    amount: f64,
+    currency_code: &str,
+) -> Result<PaymentLink, AppError> {
+    let id = sqlx::query(
+        "INSERT INTO payment_links (preorder_id, provider, external_id, url, method, \
+         payment_code, amount, currency_code, expires_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(preorder_id)
+    .bind(provider)
+    .bind(&created.external_id)
+    .bind(&created.url)
+    .bind(&created.method)
+    .bind(&created.payment_code)
+    .bind(amount)
+    .bind(currency_code)
+    .bind(&created.expires_at)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to save payment link: {}", e))?
+    .last_insert_rowid();
+
+    let link = load_payment_link(conn, id).await?;
+    audit::record(&mut *conn, "payment_link", id, "create", None, Some(&link)).await?;
+    Ok(link)
+}
+
+// Helper: The order a new link is for, what it still owes and in which
+// currency. Refuses orders that can't take a payment.
+pub async fn order_balance(
+    conn: &mut SqliteConnection,
+    po_id: i64,
+) -> Result<(PurchaseOrder, f64, String), AppError> {
+    let order = fetch_order(conn, po_id).await?;
+    if order.deleted_at.is_some() {
+        return Err(AppError::NotFound(format!("Order {} not found", po_id)));
+    }
+    if order.status == "cancelled" {
+        return Err(AppError::Validation(
+            "Cannot request payment for a cancelled order".to_string(),
+        ));
+    }
+    // Before the final invoice, a deposit order only asks for its deposit
+    let balance = match order.deposit_amount {
+        Some(deposit) if order.status == "confirmed" => deposit - order.amount_paid,
+        _ => order.total_amount - order.amount_credited - order.amount_paid,
+    };
+    let currency = match &order.currency_code {
+        Some(code) => normalize_currency(code)?,
+        None => default_currency(conn).await,
+    };
+    if balance <= balance_epsilon(&currency) {
+        return Err(AppError::Conflict(
+            "Order is already paid in full".to_string(),
+        ));
+    }
+    Ok((order, balance, currency))
+}
+
+// Helper: The order's open link from this provider if it's still right for
+// the balance (and payment method), so asking twice doesn't issue two links.
+// Open links for anything else are withdrawn first, unless they've been paid
+// in the meantime, which has to be recorded before a new link makes sense.
+pub async fn reuse_open_link<G: PaymentGateway>(
+    conn: &mut SqliteConnection,
+    gateway: &G,
+    po_id: i64,
+    balance: f64,
+    currency: &str,
+    method: Option<&str>,
+) -> Result<Option<PaymentLink>, AppError> {
+    let existing: Vec<PaymentLink> = open_payment_links(&mut *conn, G::PROVIDER)
+        .await?
+        .into_iter()
+        .filter(|link| link.preorder_id == po_id)
+        .collect();
+    for link in existing {
+        let state = gateway.link_state(&link).await?;
+        if let LinkState::Paid { .. } = state {
+            return Err(AppError::Validation(format!(
+                "The order's previous {} payment has gone through; check payments first",
+                G::PROVIDER
+            )));
+        }
+        if state == LinkState::Open
+            && (link.amount - balance).abs() < balance_epsilon(currency)
+            && link.currency_code == currency
+            && link.method.as_deref() == method
+        {
+            return Ok(Some(link));
+        }
+        if state == LinkState::Open {
+            gateway.withdraw(&link).await?;
+        }
+        cancel_payment_link(&mut *conn, &link).await?;
+    }
+    Ok(None)
+}
+
+// Helper: Whether an open link should be withdrawn because the order no
+// longer needs it (cancelled, deleted, or paid some other way)
+pub async fn link_obsolete<'e, E>(executor: E, link: &PaymentLink) -> Result<bool, AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let order = sqlx::query_as::<_, (String, Option<String>, f64, f64)>(
+        "SELECT COALESCE(status, 'pending'), deleted_at, total_amount + \
+         (SELECT COALESCE(SUM(total_amount), 0.0) FROM credit_notes WHERE preorder_id = preorders.id), \
+         (SELECT COALESCE(SUM(amount), 0.0) FROM payments WHERE preorder_id = preorders.id) - \
+         (SELECT COALESCE(SUM(amount), 0.0) FROM refunds WHERE preorder_id = preorders.id) \
+         FROM preorders WHERE id = ?",
+    )
+    .bind(link.preorder_id)
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| format!("Failed to load order: {}", e))?;
+    Ok(match order {
+        None => true,
+        Some((status, deleted_at, total, paid)) => {
+            status == "cancelled"
+                || deleted_at.is_some()
+                || total - paid < link.amount - balance_epsilon(&link.currency_code)
+        }
+    })
+}
+
+// Helper: Mark an open link cancelled (after withdrawing it with the provider)
+pub async fn cancel_payment_link(
+    conn: &mut SqliteConnection,
+    link: &PaymentLink,
+) -> Result<PaymentLink, AppError> {
+    sqlx::query(
+        "UPDATE payment_links SET status = 'cancelled', checked_at = CURRENT_TIMESTAMP \
+         WHERE id = ?",
+    )
+    .bind(link.id)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to update payment link: {}", e))?;
+
+    let after = load_payment_link(conn, link.id).await?;
+    audit::record(
+        &mut *conn,
+        "payment_link",
+        link.id,
+        "cancel",
+        Some(link),
+        Some(&after),
+    )
+    .await?;
+    Ok(after)
+}
+
+// Helper: Note that a link was checked and is still open
+pub async fn touch_payment_link(
+    conn: &mut SqliteConnection,
+    link: &PaymentLink,
+) -> Result<PaymentLink, AppError> {
+    sqlx::query("UPDATE payment_links SET checked_at = CURRENT_TIMESTAMP WHERE id = ?")
+        .bind(link.id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to update payment link: {}", e))?;
+    load_payment_link(conn, link.id).await
+}
+
+// Helper: Record a link the provider reports as paid as a payment on its
+// order, inside the caller's transaction. Paying off an invoiced order moves
+// it to paid, exactly as a manually recorded payment would.
+pub async fn settle_payment_link(
+    conn: &mut SqliteConnection,
+    link: &PaymentLink,
+    amount: f64,
+    reference: &str,
+    paid_at: &str,
+) -> Result<(PaymentLink, Payment, Option<Transition>), AppError> {
+    let (payment, transition) = insert_payment(
+        conn,
+        &PaymentInput {
+            preorder_id: link.preorder_id,
+            amount,
+            method: link.provider.clone(),
+            paid_at: Some(paid_at.to_string()),
+            reference: Some(reference.to_string()),
+            proof_file_id: None,
+            proof_file_name: None,
+            notes: Some(format!("Paid online through payment link #{}", link.id)),
+        },
+    )
+    .await?;
+
+    sqlx::query(
+        "UPDATE payment_links SET status = 'paid', payment_reference = ?, payment_id = ?, \
+         paid_at = ?, checked_at = CURRENT_TIMESTAMP WHERE id = ?",
+    )
+    .bind(reference)
+    .bind(payment.id)
+    .bind(paid_at)
+    .bind(link.id)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to update payment link: {}", e))?;
+
+    let after = load_payment_link(conn, link.id).await?;
+    audit::record(
+        &mut *conn,
+        "payment_link",
+        link.id,
+        "paid",
+        Some(link),
+        Some(&after),
+    )
+    .await?;
+    Ok((after, payment, transition))
+}
+
+// Helper: Save what the provider said about an open link in its own
+// transaction: record the payment, mark it cancelled, or note the check.
+// Returns the outcome and whether the order moved to paid. A payment that
+// can't be recorded (say, it's more than what's still due) is rolled back and
+// reported, and the link stays open to be looked at again.
+pub async fn record_link_state(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    link: PaymentLink,
+    state: LinkState,
+) -> Result<(LinkCheck, bool), AppError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let recorded = match state {
+        LinkState::Paid {
+            amount,
+            reference,
+            paid_at,
+        } => settle_payment_link(&mut tx, &link, amount, &reference, &paid_at)
+            .await
+            .map(|(after, _, transition)| (after, LinkCheckOutcome::Paid, transition)),
+        LinkState::Cancelled => cancel_payment_link(&mut tx, &link)
+            .await
+            .map(|after| (after, LinkCheckOutcome::Cancelled, None)),
+        LinkState::Open => touch_payment_link(&mut tx, &link)
+            .await
+            .map(|after| (after, LinkCheckOutcome::Open, None)),
+    };
+
+    let (after, outcome, transition) = match recorded {
+        Ok(recorded) => recorded,
+        Err(e) => {
+            return Ok((
+                LinkCheck {
+                    link,
+                    outcome: LinkCheckOutcome::Error,
+                    message: Some(e.to_string()),
+                },
+                false,
+            ))
+        }
+    };
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save payment link: {}", e))?;
+    if let Some(transition) = &transition {
+        emit_transition(app, transition);
+    }
+    Ok((
+        LinkCheck {
+            link: after,
+            outcome,
+            message: None,
+        },
+        transition.is_some(),
+    ))
+}
+
+// Helper: Ask the provider about one open link and record the answer. A link
+// still open for an order that no longer needs it is withdrawn.
+pub async fn check_payment_link<G: PaymentGateway>(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    gateway: &G,
+    link: PaymentLink,
+) -> Result<(LinkCheck, bool), AppError> {
+    let mut state = gateway.link_state(&link).await;
+    if state == Ok(LinkState::Open) && link_obsolete(pool, &link).await? {
+        state = gateway.withdraw(&link).await.map(|_| LinkState::Cancelled);
+    }
+    match state {
+        Ok(state) => record_link_state(app, pool, link, state).await,
+        Err(e) => Ok((
+            LinkCheck {
+                link,
+                outcome: LinkCheckOutcome::Error,
+                message: Some(e.to_string()),
+            },
+            false,
+        )),
+    }
+}
+
+// Helper: Check every open link from one provider (see check_payment_link).
+// Meant to be polled, since a desktop app can't receive webhooks.
+pub async fn sync_payment_links<G: PaymentGateway>(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    gateway: &G,
+) -> Result<PaymentSync, AppError> {
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let links = open_payment_links(&mut conn, G::PROVIDER).await?;
+    drop(conn);
+
+    let mut checks = Vec::new();
+    let mut paid_orders = Vec::new();
+    for link in links {
+        let (check, order_paid) = check_payment_link(app, pool, gateway, link).await?;
+        if order_paid {
+            paid_orders.push(check.link.preorder_id);
+        }
+        checks.push(check);
+    }
+    Ok(PaymentSync {
+        checks,
+        paid_orders,
+    })
+}
+
+#[tauri::command]
+pub async fn list_payment_links(
+    db: State<'_, Database>,
+    preorder_id: i64,
+) -> Result<Vec<PaymentLink>, AppError> {
+    sqlx::query_as::<_, PaymentLink>(&format!(
+        "SELECT {} FROM payment_links WHERE preorder_id = ? ORDER BY id DESC",
+        PAYMENT_LINK_COLUMNS
+    ))
+    .bind(preorder_id)
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to list payment links: {}", e)))
+}
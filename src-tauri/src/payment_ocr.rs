@@ -0,0 +1,495 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::{NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+use tauri::State;
+
+use crate::currency::default_currency;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::http::{self, SendWithRetry};
+use crate::legacy_import::parse_amount;
+use crate::payments::balance_epsilon;
+
+const VISION_API: &str = "https://vision.googleapis.com/v1/images:annotate";
+// Vision rejects requests over 10 MB, and base64 adds a third
+const MAX_IMAGE_BYTES: u64 = 7 * 1024 * 1024;
+const MAX_SUGGESTIONS: usize = 5;
+
+// Lowercased words that label the transferred amount (English and Indonesian)
+const AMOUNT_LABELS: &[&str] = &["total", "amount", "nominal", "jumlah", "transfer", "paid"];
+// Lines with these are charges on top of the transfer, not the transfer itself
+const FEE_LABELS: &[&str] = &["fee", "biaya", "admin"];
+const CURRENCY_MARKERS: &[&str] = &["rp", "idr", "$", "usd", "sgd", "myr", "rm", "€", "eur"];
+const REFERENCE_LABELS: &[&str] = &["ref", "transaksi", "transaction", "trx"];
+// Month name prefixes, English and Indonesian
+const MONTHS: &[(&str, u32)] = &[
+    ("jan", 1),
+    ("feb", 2),
+    ("peb", 2),
+    ("mar", 3),
+    ("apr", 4),
+    ("may", 5),
+    ("mei", 5),
+    ("jun", 6),
+    ("jul", 7),
+    ("aug", 8),
+    ("agu", 8),
+    ("agt", 8),
+    ("sep", 9),
+    ("oct", 10),
+    ("okt", 10),
+    ("nov", 11),
+    ("nop", 11),
+    ("dec", 12),
+    ("des", 12),
+];
+const NUMERIC_DATE_FORMATS: &[&str] = &[
+    "%Y-%m-%d", "%Y/%m/%d", "%d/%m/%Y", "%d-%m-%Y", "%d.%m.%Y", "%m/%d/%Y",
+];
+
+// An open order the payment proof could be for
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentProofMatch {
+    pub order_id: i64,
+    pub customer_name: String,
+    pub confirmation_code: String,
+    pub invoice_number: Option<String>,
+    pub status: String,
+    pub balance_due: f64,
+    pub score: i64,
+    // Why it was suggested, e.g. "Amount matches the balance due"
+    pub reasons: Vec<String>,
+}
+
+// What could be read off a bank-transfer screenshot. Every field is a
+// suggestion for the payment form; nothing is recorded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentProofScan {
+    pub text: String,
+    pub amount: Option<f64>,
+    // "YYYY-MM-DD HH:MM:SS" as printed on the receipt
+    pub paid_at: Option<String>,
+    pub reference: Option<String>,
+    // A payment already recorded with the same reference, if any
+    pub duplicate_payment_id: Option<i64>,
+    // Best match first
+    pub suggestions: Vec<PaymentProofMatch>,
+}
+
+#[derive(sqlx::FromRow)]
+pub struct OpenOrder {
+    pub id: i64,
+    pub customer_name: String,
+    pub confirmation_code: String,
+    pub invoice_number: Option<String>,
+    pub status: String,
+    pub balance_due: f64,
+    pub currency_code: String,
+}
+
+// Helper: Letters and digits only, uppercased, so codes match across OCR spacing
+fn compact(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+async fn detect_text(access_token: &str, image: &[u8]) -> Result<String, AppError> {
+    let response = http::client()
+        .post(VISION_API)
+        .bearer_auth(access_token)
+        .json(&json!({
+            "requests": [{
+                "image": { "content": STANDARD.encode(image) },
+                "features": [{ "type": "DOCUMENT_TEXT_DETECTION" }],
+                "imageContext": { "languageHints": ["id", "en"] },
+            }]
+        }))
+        .send_with_retry()
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to reach Vision API: {}", e)))?;
+    if !response.status().is_success() {
+        return Err(http::google_error(response, "Vision API error").await);
+    }
+    let body = response
+        .json::<Value>()
+        .await
+        .map_err(|e| format!("Failed to parse Vision API response: {}", e))?;
+
+    let result = &body["responses"][0];
+    if let Some(message) = result["error"]["message"].as_str() {
+        return Err(AppError::Validation(format!(
+            "Vision API error: {}",
+            message
+        )));
+    }
+    Ok(result["fullTextAnnotation"]["text"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string())
+}
+
+// Helper: Every positive number in a line, e.g. "Rp150.000,00" -> 150000.0
+fn amounts_in(line: &str) -> Vec<f64> {
+    // Dates, times and account numbers written with separators aren't amounts
+    let joined = |c: char| matches!(c, ':' | '/' | '-');
+    let mut amounts = Vec::new();
+    let mut token = String::new();
+    let mut before = ' ';
+    let mut previous = ' ';
+    for c in line.chars().chain(std::iter::once(' ')) {
+        if c.is_ascii_digit() || (!token.is_empty() && matches!(c, '.' | ',')) {
+            if token.is_empty() {
+                before = previous;
+            }
+            token.push(c);
+        } else if !token.is_empty() {
+            let number = token.trim_end_matches(['.', ',']);
+            // Nor are long bare digit runs, which are account or phone numbers
+            let bare_id = number.len() >= 9 && number.chars().all(|d| d.is_ascii_digit());
+            if !joined(before) && !joined(c) && !bare_id {
+                if let Some(amount) = parse_amount(number).filter(|a| *a > 0.0) {
+                    amounts.push(amount);
+                }
+            }
+            token.clear();
+        }
+        previous = c;
+    }
+    amounts
+}
+
+// Helper: The transferred amount. Only numbers on a line with a currency
+// marker, or labelled as the amount on that line or the one above, count;
+// a label beats a bare currency marker and the first one wins.
+fn find_amount(lines: &[&str]) -> Option<f64> {
+    let mut best: Option<(u8, f64)> = None;
+    for (i, line) in lines.iter().enumerate() {
+        let lower = line.to_lowercase();
+        if FEE_LABELS.iter().any(|label| lower.contains(label)) {
+            continue;
+        }
+        let previous = i
+            .checked_sub(1)
+            .map(|p| lines[p].to_lowercase())
+            .unwrap_or_default();
+        let labelled = AMOUNT_LABELS
+            .iter()
+            .any(|label| lower.contains(label) || previous.contains(label));
+        let has_currency = lower
+            .split(|c: char| c.is_whitespace() || c.is_ascii_digit() || matches!(c, '.' | ':'))
+            .any(|word| CURRENCY_MARKERS.contains(&word));
+        let score = u8::from(labelled) * 2 + u8::from(has_currency);
+        if score == 0 {
+            continue;
+        }
+        if let Some(amount) = amounts_in(line).into_iter().next() {
+            if best.is_none_or(|(best_score, _)| score > best_score) {
+                best = Some((score, amount));
+            }
+        }
+    }
+    best.map(|(_, amount)| amount)
+}
+
+// Helper: Month number from a name like "Okt", "October" or "Agustus"
+fn month_number(word: &str) -> Option<u32> {
+    let word = word
+        .trim_matches(|c: char| !c.is_alphabetic())
+        .to_lowercase();
+    if word.len() < 3 {
+        return None;
+    }
+    MONTHS
+        .iter()
+        .find(|(prefix, _)| word.starts_with(prefix))
+        .map(|(_, month)| *month)
+}
+
+// Helper: A 4-digit year, or 2-digit shorthand for this century
+fn year_number(word: &str) -> Option<i32> {
+    let digits = word.trim_matches(|c: char| !c.is_ascii_digit());
+    match (digits.len(), digits.parse::<i32>().ok()?) {
+        (4, year) => Some(year),
+        (2, year) => Some(2000 + year),
+        _ => None,
+    }
+}
+
+// Helper: First date in a line: "2026-10-12", "12/10/2026" (day first, as
+// Indonesian banks print it), "12 Okt 2026" or "Oct 12, 2026"
+fn date_in(line: &str) -> Option<NaiveDate> {
+    let words: Vec<&str> = line
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|w| !w.is_empty())
+        .collect();
+    for (i, word) in words.iter().enumerate() {
+        let trimmed = word.trim_matches(|c: char| !c.is_ascii_digit());
+        if let Some(date) = NUMERIC_DATE_FORMATS
+            .iter()
+            .find_map(|format| NaiveDate::parse_from_str(trimmed, format).ok())
+        {
+            return Some(date);
+        }
+        let (Some(next), Some(after)) = (words.get(i + 1), words.get(i + 2)) else {
+            continue;
+        };
+        let day = |w: &str| {
+            w.trim_matches(|c: char| !c.is_ascii_digit())
+                .parse::<u32>()
+                .ok()
+        };
+        let date = match (month_number(word), month_number(next)) {
+            (None, Some(month)) => day(word)
+                .zip(year_number(after))
+                .and_then(|(d, y)| NaiveDate::from_ymd_opt(y, month, d)),
+            (Some(month), None) => day(next)
+                .zip(year_number(after))
+                .and_then(|(d, y)| NaiveDate::from_ymd_opt(y, month, d)),
+            _ => None,
+        };
+        if date.is_some() {
+            return date;
+        }
+    }
+    None
+}
+
+// Helper: First "14:05" or "14:05:33" in a line
+fn time_in(line: &str) -> Option<NaiveTime> {
+    line.split_whitespace().find_map(|word| {
+        let word = word.trim_matches(|c: char| !c.is_ascii_digit());
+        NaiveTime::parse_from_str(word, "%H:%M:%S")
+            .or_else(|_| NaiveTime::parse_from_str(word, "%H:%M"))
+            .ok()
+    })
+}
+
+// Helper: Transfer date, with the time if it's printed next to it
+fn find_paid_at(lines: &[&str]) -> Option<String> {
+    lines.iter().enumerate().find_map(|(i, line)| {
+        let date = date_in(line)?;
+        let time = time_in(line)
+            .or_else(|| lines.get(i + 1).and_then(|next| time_in(next)))
+            .unwrap_or_default();
+        Some(date.and_time(time).format("%Y-%m-%d %H:%M:%S").to_string())
+    })
+}
+
+// Helper: Reference number after a label like "No. Ref:" or "ID Transaksi",
+// on the same line or (when the label stands alone) the next one
+fn find_reference(lines: &[&str]) -> Option<String> {
+    let token = |text: &str| {
+        text.split(|c: char| c.is_whitespace() || c == ':')
+            .map(|w| w.trim_matches(|c: char| !c.is_ascii_alphanumeric()))
+            .filter(|w| {
+                w.len() >= 6
+                    && w.chars().all(|c| c.is_ascii_alphanumeric())
+                    && w.chars().any(|c| c.is_ascii_digit())
+            })
+            .max_by_key(|w| w.len())
+            .map(str::to_string)
+    };
+    for (i, line) in lines.iter().enumerate() {
+        let lower = line.to_lowercase();
+        let Some(pos) = REFERENCE_LABELS
+            .iter()
+            .filter_map(|label| lower.find(label).map(|p| p + label.len()))
+            .min()
+        else {
+            continue;
+        };
+        // Skip the rest of the label word, e.g. "Ref" in "Referensi"
+        let rest = line
+            .get(pos..)
+            .unwrap_or_default()
+            .trim_start_matches(char::is_alphabetic);
+        if let Some(reference) = token(rest) {
+            return Some(reference);
+        }
+        if !rest.chars().any(|c| c.is_alphanumeric()) {
+            if let Some(reference) = lines.get(i + 1).and_then(|next| token(next)) {
+                return Some(reference);
+            }
+        }
+    }
+    None
+}
+
+// Helper: Whether a name word is in the text, allowing for banks cutting names
+// short ("SANTOS" for "Santoso")
+fn has_name_word(text_words: &[&str], word: &str) -> bool {
+    text_words.iter().any(|w| {
+        *w == word || (w.chars().count() >= 5 && word.len() > w.len() && word.starts_with(w))
+    })
+}
+
+// Open orders with a balance due, oldest first
+pub async fn load_open_orders(pool: &SqlitePool) -> Result<Vec<OpenOrder>, AppError> {
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let currency = default_currency(&mut conn).await;
+
+    Ok(sqlx::query_as::<_, OpenOrder>(
+        "SELECT id, customer_name, confirmation_code, invoice_number, \
+         COALESCE(status, 'pending') AS status, total_amount + \
+         (SELECT COALESCE(SUM(total_amount), 0.0) FROM credit_notes WHERE preorder_id = preorders.id) - \
+         (SELECT COALESCE(SUM(amount), 0.0) FROM payments WHERE preorder_id = preorders.id) + \
+         (SELECT COALESCE(SUM(amount), 0.0) FROM refunds WHERE preorder_id = preorders.id) \
+         AS balance_due, COALESCE(currency_code, ?) AS currency_code FROM preorders \
+         WHERE deleted_at IS NULL AND COALESCE(status, 'pending') NOT IN ('cancelled', 'draft') \
+         ORDER BY id",
+    )
+    .bind(&currency)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load open orders: {}", e))?
+    .into_iter()
+    .filter(|order| order.balance_due > balance_epsilon(&order.currency_code))
+    .collect())
+}
+
+// Open orders ranked by how well they fit a payment's text (a receipt, or a
+// bank statement line) and amount. `source` names the text in the reasons,
+// e.g. "receipt".
+pub fn rank_orders(
+    orders: &[OpenOrder],
+    text: &str,
+    amount: Option<f64>,
+    source: &str,
+) -> Vec<PaymentProofMatch> {
+    let compact_text = compact(text);
+    let lower_text = text.to_lowercase();
+    let text_words: Vec<&str> = lower_text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    let mut matches: Vec<PaymentProofMatch> = orders
+        .iter()
+        .filter_map(|order| {
+            let mut score = 0;
+            let mut reasons = Vec::new();
+            let code = compact(&order.confirmation_code);
+            if !code.is_empty() && compact_text.contains(&code) {
+                score += 100;
+                reasons.push(format!("Confirmation code appears on the {}", source));
+            }
+            if let Some(invoice) = order.invoice_number.as_deref().map(compact) {
+                if !invoice.is_empty() && compact_text.contains(&invoice) {
+                    score += 80;
+                    reasons.push(format!("Invoice number appears on the {}", source));
+                }
+            }
+            if let Some(amount) = amount {
+                let epsilon = balance_epsilon(&order.currency_code);
+                if (amount - order.balance_due).abs() < epsilon {
+                    score += 50;
+                    reasons.push("Amount matches the balance due".to_string());
+                } else if amount > order.balance_due + epsilon {
+                    // record_payment won't take more than the balance
+                    score -= 40;
+                    reasons.push("Amount is more than the balance due".to_string());
+                }
+            }
+            // Transfers usually carry the sender's name
+            let name = order.customer_name.to_lowercase();
+            let name_words: Vec<&str> = name
+                .split_whitespace()
+                .filter(|w| w.chars().count() >= 3)
+                .collect();
+            let found = name_words
+                .iter()
+                .filter(|w| has_name_word(&text_words, w))
+                .count();
+            if !name_words.is_empty() && found == name_words.len() {
+                score += 30;
+                reasons.push(format!("Customer name appears on the {}", source));
+            } else if found > 0 && found * 2 >= name_words.len() {
+                score += 15;
+                reasons.push(format!(
+                    "Part of the customer name appears on the {}",
+                    source
+                ));
+            }
+
+            (score > 0).then(|| PaymentProofMatch {
+                order_id: order.id,
+                customer_name: order.customer_name.clone(),
+                confirmation_code: order.confirmation_code.clone(),
+                invoice_number: order.invoice_number.clone(),
+                status: order.status.clone(),
+                balance_due: order.balance_due,
+                score,
+                reasons,
+            })
+        })
+        .collect();
+    // Ties go to the older order, which has been waiting longer
+    matches.sort_by(|a, b| b.score.cmp(&a.score).then(a.order_id.cmp(&b.order_id)));
+    matches.truncate(MAX_SUGGESTIONS);
+    matches
+}
+
+// Read a bank-transfer screenshot with Google Cloud Vision and pull out the
+// amount, date and reference number, then suggest the open orders it most
+// likely pays for. Needs an access token with the cloud-vision scope.
+#[tauri::command]
+pub async fn scan_payment_proof(
+    db: State<'_, Database>,
+    access_token: String,
+    path: String,
+) -> Result<PaymentProofScan, AppError> {
+    let size = std::fs::metadata(&path)
+        .map_err(|e| format!("Failed to read image: {}", e))?
+        .len();
+    if size > MAX_IMAGE_BYTES {
+        return Err(AppError::Validation(format!(
+            "Image is too large to scan ({} MB); the limit is {} MB",
+            size / (1024 * 1024),
+            MAX_IMAGE_BYTES / (1024 * 1024)
+        )));
+    }
+    let image = std::fs::read(&path).map_err(|e| format!("Failed to read image: {}", e))?;
+
+    let text = detect_text(&access_token, &image).await?;
+    if text.trim().is_empty() {
+        return Err(AppError::Validation(
+            "No text found in the image".to_string(),
+        ));
+    }
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect();
+    let amount = find_amount(&lines);
+    let paid_at = find_paid_at(&lines);
+    let reference = find_reference(&lines);
+
+    let duplicate_payment_id = match &reference {
+        Some(reference) => sqlx::query_scalar::<_, i64>(
+            "SELECT id FROM payments WHERE reference = ? ORDER BY id LIMIT 1",
+        )
+        .bind(reference)
+        .fetch_optional(&db.pool)
+        .await
+        .map_err(|e| format!("Failed to check payment references: {}", e))?,
+        None => None,
+    };
+
+    let orders = load_open_orders(&db.pool).await?;
+
+    Ok(PaymentProofScan {
+        suggestions: rank_orders(&orders, &text, amount, "receipt"),
+        text,
+        amount,
+        paid_at,
+        reference,
+        duplicate_payment_id,
+    })
+}
@@ -0,0 +1,698 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::audit;
+use crate::automation::{self, Poller};
+use crate::db::Database;
+use crate::domain_events::{check_email_sent, publish, DomainEvent};
+use crate::error::AppError;
+use crate::invoices::{load_invoice, InvoiceData};
+use crate::log_warning;
+use crate::recurring_orders::escape_html;
+use crate::settings::load_smtp_settings;
+use crate::sms::{send_reminder_sms, sms_recipient};
+use crate::timeline::EmailChannel;
+
+const RULE_COLUMNS: &str = "id, name, anchor, offset_days, enabled, created_at, updated_at";
+
+// Confirmed orders that still owe money and haven't opted out, as `p`. Drafts
+// (the frontend's 'pending' and 'sent') aren't payable yet.
+const REMINDABLE_FILTER: &str = "p.deleted_at IS NULL \
+     AND p.status IN ('confirmed', 'deposit_paid', 'invoiced') \
+     AND p.total_amount + (SELECT COALESCE(SUM(total_amount), 0.0) FROM credit_notes \
+     WHERE preorder_id = p.id) - (SELECT COALESCE(SUM(amount), 0.0) FROM payments \
+     WHERE preorder_id = p.id) + (SELECT COALESCE(SUM(amount), 0.0) FROM refunds \
+     WHERE preorder_id = p.id) > 0.005 \
+     AND p.id NOT IN (SELECT preorder_id FROM reminder_opt_outs)";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum ReminderAnchor {
+    // offset_days after the order was invoiced
+    Invoiced,
+    // offset_days before the order's event starts, when it's picked up.
+    // Orders being shipped are left out.
+    Pickup,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum ReminderStatus {
+    Pending,
+    Sent,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ReminderRule {
+    pub id: i64,
+    pub name: String,
+    pub anchor: ReminderAnchor,
+    pub offset_days: i64,
+    pub enabled: bool,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReminderRuleInput {
+    pub name: String,
+    pub anchor: ReminderAnchor,
+    pub offset_days: i64,
+    // Defaults to true
+    pub enabled: Option<bool>,
+}
+
+// A queued reminder, with the email to send for it
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PendingReminder {
+    pub id: i64,
+    pub preorder_id: i64,
+    pub rule_id: i64,
+    pub rule_name: String,
+    pub anchor: ReminderAnchor,
+    pub due_at: String,
+    pub queued_at: Option<String>,
+    pub customer_name: String,
+    pub customer_email: String,
+    pub confirmation_code: String,
+    pub invoice_number: Option<String>,
+    // The order's event and its start date, for pickup reminders
+    pub event_name: Option<String>,
+    pub pickup_date: Option<String>,
+    #[sqlx(skip)]
+    pub balance_due: f64,
+    #[sqlx(skip)]
+    pub currency_code: String,
+    #[sqlx(skip)]
+    pub subject: String,
+    #[sqlx(skip)]
+    pub html_body: String,
+    // Set when the customer prefers texts and SMS is set up; the reminder is
+    // then sent as sms_body to this number instead of by email
+    #[sqlx(skip)]
+    pub sms_phone: Option<String>,
+    #[sqlx(skip)]
+    pub sms_body: String,
+}
+
+// Emitted as the "payment-reminders-queued" event when the engine queues any
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemindersQueued {
+    pub queued: u64,
+}
+
+// Helper: Check a rule before saving it
+fn validate_rule(rule: &ReminderRuleInput) -> Result<(), AppError> {
+    if rule.name.trim().is_empty() {
+        return Err(AppError::Validation(
+            "Reminder rule name is required".to_string(),
+        ));
+    }
+    if rule.offset_days < 0 {
+        return Err(AppError::Validation(format!(
+            "Invalid number of days: {}",
+            rule.offset_days
+        )));
+    }
+    Ok(())
+}
+
+async fn load_rule(conn: &mut SqliteConnection, id: i64) -> Result<ReminderRule, AppError> {
+    sqlx::query_as::<_, ReminderRule>(&format!(
+        "SELECT {} FROM reminder_rules WHERE id = ?",
+        RULE_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| format!("Failed to load reminder rule: {}", e))?
+    .ok_or_else(|| AppError::NotFound(format!("Reminder rule {} not found", id)))
+}
+
+// Helper: Drop pending reminders for orders that no longer need one, then
+// queue a reminder for every enabled rule that has come due on an order with
+// a balance. Each order gets at most one reminder per rule.
+pub async fn queue_due_reminders(pool: &SqlitePool) -> Result<u64, AppError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    sqlx::query(&format!(
+        "UPDATE payment_reminders SET status = 'skipped', closed_at = CURRENT_TIMESTAMP \
+         WHERE status = 'pending' AND preorder_id NOT IN \
+         (SELECT p.id FROM preorders p WHERE {})",
+        REMINDABLE_FILTER
+    ))
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to update payment reminders: {}", e))?;
+
+    let invoiced = sqlx::query(&format!(
+        "INSERT OR IGNORE INTO payment_reminders (preorder_id, rule_id, due_at) \
+         SELECT p.id, r.id, datetime(p.invoiced_at, '+' || r.offset_days || ' days') \
+         FROM preorders p JOIN reminder_rules r ON r.enabled = 1 AND r.anchor = 'invoiced' \
+         WHERE p.invoiced_at IS NOT NULL AND {} \
+         AND datetime(p.invoiced_at, '+' || r.offset_days || ' days') <= CURRENT_TIMESTAMP",
+        REMINDABLE_FILTER
+    ))
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to queue payment reminders: {}", e))?;
+
+    // Once pickup day has passed a pickup reminder is no use
+    let pickup = sqlx::query(&format!(
+        "INSERT OR IGNORE INTO payment_reminders (preorder_id, rule_id, due_at) \
+         SELECT p.id, r.id, datetime(e.start_date, '-' || r.offset_days || ' days') \
+         FROM preorders p JOIN events e ON e.id = p.event_id \
+         JOIN reminder_rules r ON r.enabled = 1 AND r.anchor = 'pickup' \
+         WHERE e.start_date IS NOT NULL AND {} \
+         AND NOT EXISTS (SELECT 1 FROM fulfillments f \
+         WHERE f.preorder_id = p.id AND f.method = 'shipping') \
+         AND datetime(e.start_date, '-' || r.offset_days || ' days') <= CURRENT_TIMESTAMP \
+         AND date(e.start_date) >= date('now')",
+        REMINDABLE_FILTER
+    ))
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to queue payment reminders: {}", e))?;
+
+    let queued = invoiced.rows_affected() + pickup.rows_affected();
+    if queued > 0 {
+        publish(&mut tx, &DomainEvent::PaymentRemindersDue { count: queued }).await?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save payment reminders: {}", e))?;
+
+    Ok(queued)
+}
+
+// Start the background loop that queues reminders as they come due
+pub fn start_reminder_engine(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<Database>().pool.clone();
+        loop {
+            match queue_due_reminders(&pool).await {
+                Ok(0) => {}
+                Ok(queued) => {
+                    if let Err(e) = app.emit("payment-reminders-queued", RemindersQueued { queued })
+                    {
+                        log_warning!("Failed to emit payment-reminders-queued event: {}", e);
+                    }
+                }
+                Err(e) => log_warning!("Payment reminder engine failed: {}", e),
+            }
+            automation::wait_for_next_poll(&pool, Poller::Reminders).await;
+        }
+    });
+}
+
+// Helper: What a reminder is about, as a short reference ("INV-...") and as
+// a phrase ("invoice INV-...")
+fn reminder_reference(invoice: &InvoiceData) -> (&str, String) {
+    let locale = invoice.locale;
+    let order = &invoice.order;
+    match &order.invoice_number {
+        Some(number) => (
+            number.as_str(),
+            locale.format("email.reminder.invoice", &[("number", number)]),
+        ),
+        None => (
+            order.confirmation_code.as_str(),
+            locale.format(
+                "email.reminder.order",
+                &[("code", &order.confirmation_code)],
+            ),
+        ),
+    }
+}
+
+// Helper: Text of a reminder sent by SMS, in the invoice's language
+fn reminder_sms(invoice: &InvoiceData) -> String {
+    let (_, phrase) = reminder_reference(invoice);
+    invoice.locale.format(
+        "sms.reminder",
+        &[
+            ("name", &invoice.order.customer_name),
+            ("reference", &phrase),
+            ("amount", &invoice.money.format(invoice.balance_due)),
+        ],
+    )
+}
+
+// Helper: Subject and HTML body of a reminder, in the invoice's language
+fn reminder_email(invoice: &InvoiceData, pickup: Option<(&str, &str)>) -> (String, String) {
+    let locale = invoice.locale;
+    let order = &invoice.order;
+    let (reference, phrase) = reminder_reference(invoice);
+    let subject = locale.format("email.reminder.subject", &[("reference", reference)]);
+
+    let mut paragraphs = vec![
+        locale.format("email.greeting", &[("name", &order.customer_name)]),
+        locale.format(
+            "email.reminder.intro",
+            &[
+                ("reference", &phrase),
+                ("amount", &invoice.money.format(invoice.balance_due)),
+            ],
+        ),
+    ];
+    if let Some((event, date)) = pickup {
+        paragraphs
+            .push(locale.format("email.reminder.pickup", &[("event", event), ("date", date)]));
+    }
+    paragraphs.push(locale.text("email.reminder.paid"));
+
+    let body: String = paragraphs
+        .iter()
+        .map(|paragraph| format!("<p>{}</p>", escape_html(paragraph)))
+        .collect();
+    (subject, format!("<html><body>{}</body></html>", body))
+}
+
+// Helper: Load pending reminders (one, or all when `id` is None) with their emails
+async fn load_pending(
+    conn: &mut SqliteConnection,
+    id: Option<i64>,
+) -> Result<Vec<PendingReminder>, AppError> {
+    let mut reminders = sqlx::query_as::<_, PendingReminder>(
+        "SELECT pr.id, pr.preorder_id, pr.rule_id, r.name AS rule_name, r.anchor, pr.due_at, \
+         pr.queued_at, p.customer_name, p.customer_email, p.confirmation_code, p.invoice_number, \
+         e.name AS event_name, e.start_date AS pickup_date \
+         FROM payment_reminders pr \
+         JOIN reminder_rules r ON r.id = pr.rule_id \
+         JOIN preorders p ON p.id = pr.preorder_id \
+         LEFT JOIN events e ON e.id = p.event_id \
+         WHERE pr.status = 'pending' AND (? IS NULL OR pr.id = ?) \
+         ORDER BY pr.due_at, pr.id",
+    )
+    .bind(id)
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load payment reminders: {}", e))?;
+
+    for reminder in &mut reminders {
+        let invoice = load_invoice(&mut *conn, reminder.preorder_id).await?;
+        let pickup = match (reminder.anchor, &reminder.event_name, &reminder.pickup_date) {
+            (ReminderAnchor::Pickup, Some(event), Some(date)) => {
+                Some((event.as_str(), date.as_str()))
+            }
+            _ => None,
+        };
+        let (subject, html_body) = reminder_email(&invoice, pickup);
+        reminder.sms_phone = sms_recipient(&mut *conn, reminder.preorder_id).await?;
+        if reminder.sms_phone.is_some() {
+            reminder.sms_body = reminder_sms(&invoice);
+        }
+        reminder.balance_due = invoice.balance_due;
+        reminder.currency_code = invoice.currency_code;
+        reminder.subject = subject;
+        reminder.html_body = html_body;
+    }
+    Ok(reminders)
+}
+
+// Helper: Close a pending reminder as sent or skipped
+async fn close_reminder(
+    conn: &mut SqliteConnection,
+    id: i64,
+    status: ReminderStatus,
+) -> Result<(), AppError> {
+    let result = sqlx::query(
+        "UPDATE payment_reminders SET status = ?, closed_at = CURRENT_TIMESTAMP \
+         WHERE id = ? AND status = 'pending'",
+    )
+    .bind(status)
+    .bind(id)
+    .execute(conn)
+    .await
+    .map_err(|e| format!("Failed to update payment reminder: {}", e))?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::Validation(format!(
+            "Payment reminder {} is not pending",
+            id
+        )));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_reminder_rules(db: State<'_, Database>) -> Result<Vec<ReminderRule>, AppError> {
+    sqlx::query_as::<_, ReminderRule>(&format!(
+        "SELECT {} FROM reminder_rules ORDER BY anchor, offset_days, id",
+        RULE_COLUMNS
+    ))
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to list reminder rules: {}", e)))
+}
+
+#[tauri::command]
+pub async fn create_reminder_rule(
+    db: State<'_, Database>,
+    rule: ReminderRuleInput,
+) -> Result<ReminderRule, AppError> {
+    validate_rule(&rule)?;
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let id = sqlx::query(
+        "INSERT INTO reminder_rules (name, anchor, offset_days, enabled) VALUES (?, ?, ?, ?)",
+    )
+    .bind(rule.name.trim())
+    .bind(rule.anchor)
+    .bind(rule.offset_days)
+    .bind(rule.enabled.unwrap_or(true))
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to create reminder rule: {}", e))?
+    .last_insert_rowid();
+
+    let created = load_rule(&mut tx, id).await?;
+    audit::record(
+        &mut *tx,
+        "reminder_rule",
+        id,
+        "create",
+        None,
+        Some(&created),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save reminder rule: {}", e))?;
+
+    Ok(created)
+}
+
+// Change a rule. Reminders it already queued stay queued; turning a rule off
+// only stops new ones.
+#[tauri::command]
+pub async fn update_reminder_rule(
+    db: State<'_, Database>,
+    id: i64,
+    rule: ReminderRuleInput,
+) -> Result<ReminderRule, AppError> {
+    validate_rule(&rule)?;
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_rule(&mut tx, id).await?;
+    sqlx::query(
+        "UPDATE reminder_rules SET name = ?, anchor = ?, offset_days = ?, enabled = ?, \
+         updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+    )
+    .bind(rule.name.trim())
+    .bind(rule.anchor)
+    .bind(rule.offset_days)
+    .bind(rule.enabled.unwrap_or(before.enabled))
+    .bind(id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to update reminder rule: {}", e))?;
+
+    let after = load_rule(&mut tx, id).await?;
+    audit::record(
+        &mut *tx,
+        "reminder_rule",
+        id,
+        "update",
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save reminder rule: {}", e))?;
+
+    Ok(after)
+}
+
+// Delete a rule along with the reminders it queued
+#[tauri::command]
+pub async fn delete_reminder_rule(db: State<'_, Database>, id: i64) -> Result<(), AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_rule(&mut tx, id).await?;
+    sqlx::query("DELETE FROM payment_reminders WHERE rule_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to delete payment reminders: {}", e))?;
+    sqlx::query("DELETE FROM reminder_rules WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to delete reminder rule: {}", e))?;
+
+    audit::record(&mut *tx, "reminder_rule", id, "delete", Some(&before), None).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to delete reminder rule: {}", e)))
+}
+
+// Reminder emails waiting to be sent, oldest due first. The queue is brought
+// up to date first, so reminders for orders paid since are gone.
+#[tauri::command]
+pub async fn list_pending_reminders(
+    db: State<'_, Database>,
+) -> Result<Vec<PendingReminder>, AppError> {
+    queue_due_reminders(&db.pool).await?;
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    load_pending(&mut conn, None).await
+}
+
+// Send a pending reminder: by SMS to customers who prefer texts, otherwise
+// through the saved SMTP settings
+#[tauri::command]
+pub async fn send_payment_reminder(db: State<'_, Database>, id: i64) -> Result<(), AppError> {
+    let reminder = {
+        let mut conn = db
+            .pool
+            .acquire()
+            .await
+            .map_err(|e| format!("Failed to open database connection: {}", e))?;
+        load_pending(&mut conn, Some(id))
+            .await?
+            .pop()
+            .ok_or_else(|| {
+                AppError::Validation(format!("Payment reminder {} is not pending", id))
+            })?
+    };
+
+    if let Some(phone) = &reminder.sms_phone {
+        send_reminder_sms(&db.pool, reminder.preorder_id, phone, &reminder.sms_body).await?;
+        let mut conn = db
+            .pool
+            .acquire()
+            .await
+            .map_err(|e| format!("Failed to open database connection: {}", e))?;
+        return close_reminder(&mut conn, id, ReminderStatus::Sent).await;
+    }
+
+    let settings = load_smtp_settings(&db.pool)
+        .await?
+        .ok_or_else(|| AppError::Validation("SMTP is not configured".to_string()))?;
+
+    let (to_email, subject) = (reminder.customer_email.clone(), reminder.subject.clone());
+    let sent = tauri::async_runtime::spawn_blocking(move || {
+        crate::send_smtp_email(
+            settings,
+            reminder.customer_email,
+            reminder.customer_name,
+            reminder.subject,
+            reminder.html_body,
+        )
+    })
+    .await
+    .map_err(|e| format!("Failed to send email: {}", e))?;
+    check_email_sent(&db.pool, &to_email, &subject, sent).await?;
+
+    mark_sent(
+        &db.pool,
+        id,
+        reminder.preorder_id,
+        &to_email,
+        &subject,
+        EmailChannel::Smtp,
+    )
+    .await
+}
+
+// Helper: Close a reminder as sent and log the email on the order's timeline
+async fn mark_sent(
+    pool: &SqlitePool,
+    id: i64,
+    po_id: i64,
+    to_email: &str,
+    subject: &str,
+    channel: EmailChannel,
+) -> Result<(), AppError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    close_reminder(&mut tx, id, ReminderStatus::Sent).await?;
+    let email_sent = DomainEvent::EmailSent {
+        order_id: Some(po_id),
+        to_email,
+        subject,
+        channel,
+    };
+    publish(&mut tx, &email_sent).await?;
+    tx.commit()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to save payment reminder: {}", e)))
+}
+
+// Mark a pending reminder sent after the frontend emailed it itself (Gmail
+// or the sync microservice)
+#[tauri::command]
+pub async fn mark_payment_reminder_sent(
+    db: State<'_, Database>,
+    id: i64,
+    channel: EmailChannel,
+) -> Result<(), AppError> {
+    let reminder = {
+        let mut conn = db
+            .pool
+            .acquire()
+            .await
+            .map_err(|e| format!("Failed to open database connection: {}", e))?;
+        load_pending(&mut conn, Some(id))
+            .await?
+            .pop()
+            .ok_or_else(|| {
+                AppError::Validation(format!("Payment reminder {} is not pending", id))
+            })?
+    };
+    mark_sent(
+        &db.pool,
+        id,
+        reminder.preorder_id,
+        &reminder.customer_email,
+        &reminder.subject,
+        channel,
+    )
+    .await
+}
+
+// Dismiss a pending reminder without sending it
+#[tauri::command]
+pub async fn skip_payment_reminder(db: State<'_, Database>, id: i64) -> Result<(), AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    close_reminder(&mut conn, id, ReminderStatus::Skipped).await
+}
+
+// Whether an order's customer gets payment reminders
+#[tauri::command]
+pub async fn get_order_reminders(db: State<'_, Database>, po_id: i64) -> Result<bool, AppError> {
+    let opted_out = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM reminder_opt_outs WHERE preorder_id = ?",
+    )
+    .bind(po_id)
+    .fetch_one(&db.pool)
+    .await
+    .map_err(|e| format!("Failed to load reminder setting: {}", e))?;
+    Ok(opted_out == 0)
+}
+
+// Turn payment reminders on or off for one order. Turning them off drops the
+// order's pending reminders.
+#[tauri::command]
+pub async fn set_order_reminders(
+    db: State<'_, Database>,
+    po_id: i64,
+    enabled: bool,
+) -> Result<bool, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let exists = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM preorders WHERE id = ?")
+        .bind(po_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to load order: {}", e))?;
+    if exists == 0 {
+        return Err(AppError::NotFound(format!("Order {} not found", po_id)));
+    }
+
+    let changed = if enabled {
+        sqlx::query("DELETE FROM reminder_opt_outs WHERE preorder_id = ?")
+            .bind(po_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to turn on reminders: {}", e))?
+            .rows_affected()
+    } else {
+        sqlx::query("INSERT OR IGNORE INTO reminder_opt_outs (preorder_id) VALUES (?)")
+            .bind(po_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to turn off reminders: {}", e))?
+            .rows_affected()
+    };
+    if changed > 0 {
+        if !enabled {
+            sqlx::query(
+                "UPDATE payment_reminders SET status = 'skipped', closed_at = CURRENT_TIMESTAMP \
+                 WHERE preorder_id = ? AND status = 'pending'",
+            )
+            .bind(po_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to update payment reminders: {}", e))?;
+        }
+        let setting = |enabled: bool| serde_json::json!({ "payment_reminders": enabled });
+        audit::record(
+            &mut *tx,
+            "order",
+            po_id,
+            if enabled {
+                "reminders_on"
+            } else {
+                "reminders_off"
+            },
+            Some(&setting(!enabled)),
+            Some(&setting(enabled)),
+        )
+        .await?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save reminder setting: {}", e))?;
+
+    Ok(enabled)
+}
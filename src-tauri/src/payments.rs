@@ -0,0 +1,393 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteConnection;
+use tauri::{AppHandle, State};
+
+use crate::audit;
+use crate::currency::{currency_decimals, default_currency};
+use crate::db::Database;
+use crate::domain_events::{publish, DomainEvent};
+use crate::error::AppError;
+use crate::order_status::{apply_transition, emit_transition, OrderEvent, OrderStatus, Transition};
+
+const PAYMENT_COLUMNS: &str = "id, preorder_id, amount, method, paid_at, reference, \
+     proof_file_id, proof_file_name, notes, created_at";
+
+const REFUND_COLUMNS: &str =
+    "id, preorder_id, credit_note_id, amount, method, refunded_at, reference, notes, created_at";
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Payment {
+    pub id: i64,
+    pub preorder_id: i64,
+    pub amount: f64,
+    pub method: String,
+    pub paid_at: String,
+    pub reference: Option<String>,
+    pub proof_file_id: Option<String>,
+    pub proof_file_name: Option<String>,
+    pub notes: Option<String>,
+    pub created_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentInput {
+    pub preorder_id: i64,
+    pub amount: f64,
+    // e.g. "cash", "bank_transfer", "qris"
+    pub method: String,
+    // Defaults to now
+    pub paid_at: Option<String>,
+    pub reference: Option<String>,
+    // Upload the proof with upload_file_to_drive first and pass its file ID here
+    pub proof_file_id: Option<String>,
+    pub proof_file_name: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Refund {
+    pub id: i64,
+    pub preorder_id: i64,
+    pub credit_note_id: Option<i64>,
+    pub amount: f64,
+    pub method: String,
+    pub refunded_at: String,
+    pub reference: Option<String>,
+    pub notes: Option<String>,
+    pub created_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefundInput {
+    pub preorder_id: i64,
+    // The credit note this refund pays out, if any
+    pub credit_note_id: Option<i64>,
+    pub amount: f64,
+    // e.g. "cash", "bank_transfer"
+    pub method: String,
+    // Defaults to now
+    pub refunded_at: Option<String>,
+    pub reference: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CustomerBalance {
+    pub customer_name: String,
+    pub customer_email: String,
+    pub order_count: i64,
+    pub total_ordered: f64,
+    pub total_paid: f64,
+    pub outstanding: f64,
+}
+
+// Record a (possibly partial) payment against an order. Paying off an
+// invoiced order in full moves it to paid.
+#[tauri::command]
+pub async fn record_payment(
+    app: AppHandle,
+    db: State<'_, Database>,
+    payment: PaymentInput,
+) -> Result<Payment, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let (recorded, transition) = insert_payment(&mut tx, &payment).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save payment: {}", e))?;
+
+    if let Some(transition) = transition {
+        emit_transition(&app, &transition);
+    }
+    Ok(recorded)
+}
+
+// Helper: Amounts are stored as REAL, so a difference under half the
+// currency's minor unit (half a cent, or half a rupiah for IDR) counts as
+// settled
+pub(crate) fn balance_epsilon(currency: &str) -> f64 {
+    0.5 / 10f64.powi(currency_decimals(currency) as i32)
+}
+
+// Helper: balance_epsilon for an order's currency, the default one when the
+// order doesn't set it
+pub(crate) async fn order_balance_epsilon(
+    conn: &mut SqliteConnection,
+    currency: Option<&str>,
+) -> f64 {
+    match currency {
+        Some(code) => balance_epsilon(code),
+        None => balance_epsilon(&default_currency(conn).await),
+    }
+}
+
+// Helper: Record a payment inside the caller's transaction (see record_payment)
+pub async fn insert_payment(
+    conn: &mut SqliteConnection,
+    payment: &PaymentInput,
+) -> Result<(Payment, Option<Transition>), AppError> {
+    if !payment.amount.is_finite() || payment.amount <= 0.0 {
+        return Err(AppError::Validation(format!(
+            "Invalid payment amount: {}",
+            payment.amount
+        )));
+    }
+    let method = payment.method.trim();
+    if method.is_empty() {
+        return Err(AppError::Validation(
+            "Payment method must not be empty".to_string(),
+        ));
+    }
+
+    let (status, total, paid, deposit, currency) =
+        sqlx::query_as::<_, (String, f64, f64, Option<f64>, Option<String>)>(
+        "SELECT COALESCE(status, 'pending'), total_amount + \
+         (SELECT COALESCE(SUM(total_amount), 0.0) FROM credit_notes WHERE preorder_id = preorders.id), \
+         (SELECT COALESCE(SUM(amount), 0.0) FROM payments WHERE preorder_id = preorders.id) - \
+         (SELECT COALESCE(SUM(amount), 0.0) FROM refunds WHERE preorder_id = preorders.id), \
+         deposit_amount, currency_code FROM preorders WHERE id = ? AND deleted_at IS NULL",
+    )
+    .bind(payment.preorder_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load order: {}", e))?
+    .ok_or_else(|| AppError::NotFound(format!("Order {} not found", payment.preorder_id)))?;
+
+    let status = OrderStatus::parse(&status)?;
+    if status == OrderStatus::Cancelled {
+        return Err(AppError::Validation(
+            "Cannot record a payment for a cancelled order".to_string(),
+        ));
+    }
+
+    let epsilon = order_balance_epsilon(&mut *conn, currency.as_deref()).await;
+    let outstanding = total - paid;
+    if payment.amount > outstanding + epsilon {
+        return Err(AppError::Validation(format!(
+            "Payment of {:.2} is more than the outstanding balance of {:.2}",
+            payment.amount, outstanding
+        )));
+    }
+
+    let result = sqlx::query(
+        "INSERT INTO payments (preorder_id, amount, method, paid_at, reference, proof_file_id, proof_file_name, notes) \
+         VALUES (?, ?, ?, COALESCE(?, CURRENT_TIMESTAMP), ?, ?, ?, ?)",
+    )
+    .bind(payment.preorder_id)
+    .bind(payment.amount)
+    .bind(method)
+    .bind(&payment.paid_at)
+    .bind(&payment.reference)
+    .bind(&payment.proof_file_id)
+    .bind(&payment.proof_file_name)
+    .bind(&payment.notes)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to record payment: {}", e))?;
+
+    // Deposits only exist once the deposit invoice has fixed the amount
+    let settled = outstanding - payment.amount <= epsilon;
+    let deposit_settled = deposit.is_some_and(|deposit| deposit - paid - payment.amount <= epsilon);
+    let transition = if settled && status == OrderStatus::Invoiced {
+        Some(apply_transition(conn, payment.preorder_id, OrderEvent::Pay).await?)
+    } else if deposit_settled && status == OrderStatus::Confirmed {
+        Some(apply_transition(conn, payment.preorder_id, OrderEvent::PayDeposit).await?)
+    } else {
+        None
+    };
+
+    let recorded = sqlx::query_as::<_, Payment>(&format!(
+        "SELECT {} FROM payments WHERE id = ?",
+        PAYMENT_COLUMNS
+    ))
+    .bind(result.last_insert_rowid())
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load payment: {}", e))?;
+
+    publish(&mut *conn, &DomainEvent::PaymentRecorded(&recorded)).await?;
+
+    Ok((recorded, transition))
+}
+
+#[tauri::command]
+pub async fn list_payments(
+    db: State<'_, Database>,
+    preorder_id: i64,
+) -> Result<Vec<Payment>, AppError> {
+    sqlx::query_as::<_, Payment>(&format!(
+        "SELECT {} FROM payments WHERE preorder_id = ? ORDER BY paid_at, id",
+        PAYMENT_COLUMNS
+    ))
+    .bind(preorder_id)
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to list payments: {}", e)))
+}
+
+// Unpaid totals per customer over their non-cancelled orders, largest first.
+// Credit notes come off what was ordered and refunds off what was paid.
+#[tauri::command]
+pub async fn get_outstanding_balances(
+    db: State<'_, Database>,
+) -> Result<Vec<CustomerBalance>, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    // Totals add up orders across currencies, so the default one sets the precision
+    let epsilon = balance_epsilon(&default_currency(&mut conn).await);
+
+    sqlx::query_as::<_, CustomerBalance>(
+        "SELECT MAX(o.customer_name) AS customer_name, LOWER(TRIM(o.customer_email)) AS customer_email, \
+         COUNT(*) AS order_count, SUM(o.total_amount + COALESCE(c.credited, 0.0)) AS total_ordered, \
+         SUM(COALESCE(p.paid, 0.0) - COALESCE(r.refunded, 0.0)) AS total_paid, \
+         SUM(o.total_amount + COALESCE(c.credited, 0.0)) \
+         - SUM(COALESCE(p.paid, 0.0) - COALESCE(r.refunded, 0.0)) AS outstanding \
+         FROM preorders o \
+         LEFT JOIN (SELECT preorder_id, SUM(amount) AS paid FROM payments GROUP BY preorder_id) p \
+         ON p.preorder_id = o.id \
+         LEFT JOIN (SELECT preorder_id, SUM(amount) AS refunded FROM refunds GROUP BY preorder_id) r \
+         ON r.preorder_id = o.id \
+         LEFT JOIN (SELECT preorder_id, SUM(total_amount) AS credited FROM credit_notes \
+         GROUP BY preorder_id) c ON c.preorder_id = o.id \
+         WHERE COALESCE(o.status, 'pending') <> 'cancelled' AND o.deleted_at IS NULL \
+         GROUP BY LOWER(TRIM(o.customer_email)) \
+         HAVING outstanding > ? \
+         ORDER BY outstanding DESC",
+    )
+    .bind(epsilon)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to load outstanding balances: {}", e)))
+}
+
+// Record money paid back to a customer. Refunds can't exceed what the order
+// has been paid (net of earlier refunds).
+#[tauri::command]
+pub async fn record_refund(
+    db: State<'_, Database>,
+    refund: RefundInput,
+) -> Result<Refund, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let recorded = insert_refund(&mut tx, &refund).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save refund: {}", e))?;
+
+    Ok(recorded)
+}
+
+// Helper: Record a refund inside the caller's transaction (see record_refund)
+pub async fn insert_refund(
+    conn: &mut SqliteConnection,
+    refund: &RefundInput,
+) -> Result<Refund, AppError> {
+    if !refund.amount.is_finite() || refund.amount <= 0.0 {
+        return Err(AppError::Validation(format!(
+            "Invalid refund amount: {}",
+            refund.amount
+        )));
+    }
+    let method = refund.method.trim();
+    if method.is_empty() {
+        return Err(AppError::Validation(
+            "Refund method must not be empty".to_string(),
+        ));
+    }
+
+    let (paid, currency) = sqlx::query_as::<_, (f64, Option<String>)>(
+        "SELECT (SELECT COALESCE(SUM(amount), 0.0) FROM payments WHERE preorder_id = preorders.id) - \
+         (SELECT COALESCE(SUM(amount), 0.0) FROM refunds WHERE preorder_id = preorders.id), \
+         currency_code FROM preorders WHERE id = ? AND deleted_at IS NULL",
+    )
+    .bind(refund.preorder_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load order: {}", e))?
+    .ok_or_else(|| AppError::NotFound(format!("Order {} not found", refund.preorder_id)))?;
+
+    if refund.amount > paid + order_balance_epsilon(&mut *conn, currency.as_deref()).await {
+        return Err(AppError::Validation(format!(
+            "Refund of {:.2} is more than the {:.2} paid for the order",
+            refund.amount, paid
+        )));
+    }
+    if let Some(credit_note_id) = refund.credit_note_id {
+        let order_id =
+            sqlx::query_scalar::<_, i64>("SELECT preorder_id FROM credit_notes WHERE id = ?")
+                .bind(credit_note_id)
+                .fetch_optional(&mut *conn)
+                .await
+                .map_err(|e| format!("Failed to load credit note: {}", e))?;
+        if order_id != Some(refund.preorder_id) {
+            return Err(AppError::Validation(format!(
+                "Credit note {} is not for order {}",
+                credit_note_id, refund.preorder_id
+            )));
+        }
+    }
+
+    let result = sqlx::query(
+        "INSERT INTO refunds (preorder_id, credit_note_id, amount, method, refunded_at, reference, notes) \
+         VALUES (?, ?, ?, ?, COALESCE(?, CURRENT_TIMESTAMP), ?, ?)",
+    )
+    .bind(refund.preorder_id)
+    .bind(refund.credit_note_id)
+    .bind(refund.amount)
+    .bind(method)
+    .bind(&refund.refunded_at)
+    .bind(&refund.reference)
+    .bind(&refund.notes)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to record refund: {}", e))?;
+
+    let recorded = sqlx::query_as::<_, Refund>(&format!(
+        "SELECT {} FROM refunds WHERE id = ?",
+        REFUND_COLUMNS
+    ))
+    .bind(result.last_insert_rowid())
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load refund: {}", e))?;
+
+    audit::record(
+        &mut *conn,
+        "refund",
+        recorded.id,
+        "create",
+        None,
+        Some(&recorded),
+    )
+    .await?;
+
+    Ok(recorded)
+}
+
+#[tauri::command]
+pub async fn list_refunds(
+    db: State<'_, Database>,
+    preorder_id: i64,
+) -> Result<Vec<Refund>, AppError> {
+    sqlx::query_as::<_, Refund>(&format!(
+        "SELECT {} FROM refunds WHERE preorder_id = ? ORDER BY refunded_at, id",
+        REFUND_COLUMNS
+    ))
+    .bind(preorder_id)
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to list refunds: {}", e)))
+}
@@ -0,0 +1,327 @@
+use reqwest::{Client, RequestBuilder};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tauri::{AppHandle, State};
+
+use crate::db::Database;
+use crate::error::AppError;
+use crate::http;
+use crate::payment_links::{
+    check_payment_link, insert_payment_link, latest_payment_link, order_balance, reuse_open_link,
+    LinkCheck, LinkCheckOutcome, LinkState, NewPaymentLink, PaymentGateway, PaymentLink,
+};
+use crate::redact::redact;
+
+const PAYPAL_API: &str = "https://api-m.paypal.com";
+const PAYPAL_SANDBOX_API: &str = "https://api-m.sandbox.paypal.com";
+
+// Currencies PayPal only takes in whole units
+const ZERO_DECIMAL_CURRENCIES: &[&str] = &["HUF", "JPY", "TWD"];
+
+// REST app credentials from the PayPal developer dashboard. Like the Stripe
+// key, they're passed in on every call and never stored.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PaypalCredentials {
+    pub client_id: String,
+    pub client_secret: String,
+    // Use the sandbox instead of live payments
+    #[serde(default)]
+    pub sandbox: bool,
+}
+
+// A signed-in PayPal API session
+struct Paypal {
+    client: Client,
+    base: &'static str,
+    access_token: String,
+}
+
+impl Paypal {
+    async fn connect(credentials: &PaypalCredentials) -> Result<Paypal, AppError> {
+        let base = if credentials.sandbox {
+            PAYPAL_SANDBOX_API
+        } else {
+            PAYPAL_API
+        };
+        let client = http::client();
+        let response = client
+            .post(format!("{}/v1/oauth2/token", base))
+            .basic_auth(
+                credentials.client_id.trim(),
+                Some(credentials.client_secret.trim()),
+            )
+            .form(&[("grant_type", "client_credentials")])
+            .send()
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to reach PayPal: {}", e)))?;
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let error_text = redact(&response.text().await.unwrap_or_default());
+            return Err(AppError::from_status(
+                status,
+                format!("Failed to sign in to PayPal: {}", error_text),
+            ));
+        }
+        let body = response
+            .json::<Value>()
+            .await
+            .map_err(|e| format!("Failed to parse PayPal response: {}", e))?;
+        let access_token = body["access_token"]
+            .as_str()
+            .ok_or("PayPal didn't return an access token")?
+            .to_string();
+        Ok(Paypal {
+            client,
+            base,
+            access_token,
+        })
+    }
+
+    fn invoice_url(&self, invoice_id: &str, action: &str) -> String {
+        format!(
+            "{}/v2/invoicing/invoices/{}{}",
+            self.base, invoice_id, action
+        )
+    }
+
+    // Send a request and return the JSON body (null for empty replies),
+    // surfacing PayPal's own error message when it refuses
+    async fn send(&self, request: RequestBuilder, action: &str) -> Result<Value, AppError> {
+        let response = request
+            .bearer_auth(&self.access_token)
+            .send()
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to reach PayPal: {}", e)))?;
+        let status = response.status();
+        let text = redact(&response.text().await.unwrap_or_default());
+        let body = serde_json::from_str::<Value>(&text).unwrap_or(Value::Null);
+        if !status.is_success() {
+            let message = body["details"][0]["description"]
+                .as_str()
+                .or(body["message"].as_str())
+                .unwrap_or(&text);
+            return Err(AppError::from_status(
+                status.as_u16(),
+                format!("Failed to {}: {}", action, message),
+            ));
+        }
+        Ok(body)
+    }
+
+    async fn invoice(&self, invoice_id: &str) -> Result<Value, AppError> {
+        self.send(
+            self.client.get(self.invoice_url(invoice_id, "")),
+            "load PayPal invoice",
+        )
+        .await
+    }
+}
+
+impl PaymentGateway for Paypal {
+    const PROVIDER: &'static str = "paypal";
+
+    // What an invoice's status means for its payment link
+    async fn link_state(&self, link: &PaymentLink) -> Result<LinkState, AppError> {
+        let invoice = self.invoice(&link.external_id).await?;
+        match invoice["status"].as_str().unwrap_or_default() {
+            // Paid through PayPal, or marked paid by hand in PayPal
+            "PAID" | "MARKED_AS_PAID" => {
+                let transaction = invoice["payments"]["transactions"]
+                    .as_array()
+                    .and_then(|transactions| transactions.last())
+                    .cloned()
+                    .unwrap_or(Value::Null);
+                let amount = invoice["payments"]["paid_amount"]["value"]
+                    .as_str()
+                    .and_then(|value| value.parse::<f64>().ok())
+                    .unwrap_or(link.amount);
+                Ok(LinkState::Paid {
+                    amount,
+                    reference: transaction["payment_id"]
+                        .as_str()
+                        .unwrap_or(&link.external_id)
+                        .to_string(),
+                    paid_at: paid_at(transaction["payment_date"].as_str()),
+                })
+            }
+            "CANCELLED" => Ok(LinkState::Cancelled),
+            _ => Ok(LinkState::Open),
+        }
+    }
+
+    // Cancel a sent invoice so it can't be paid any more
+    async fn withdraw(&self, link: &PaymentLink) -> Result<(), AppError> {
+        self.send(
+            self.client
+                .post(self.invoice_url(&link.external_id, "/cancel"))
+                .json(&json!({ "send_to_recipient": false, "send_to_invoicer": false })),
+            "cancel PayPal invoice",
+        )
+        .await
+        .map(|_| ())
+    }
+}
+
+// Helper: PayPal's payment date ("2026-10-12" or an RFC 3339 timestamp) in
+// the format payments are stored in; now if it's missing
+fn paid_at(date: Option<&str>) -> String {
+    let parsed = date.and_then(|date| {
+        chrono::DateTime::parse_from_rfc3339(date)
+            .map(|timestamp| timestamp.naive_utc())
+            .or_else(|_| {
+                chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                    .map(|day| day.and_hms_opt(0, 0, 0).unwrap_or_default())
+            })
+            .ok()
+    });
+    parsed
+        .unwrap_or_else(|| chrono::Utc::now().naive_utc())
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+// Helper: Amount as PayPal wants it, e.g. "12.50" or "1500" for JPY
+fn paypal_value(amount: f64, currency: &str) -> String {
+    let decimals = if ZERO_DECIMAL_CURRENCIES.contains(&currency) {
+        0
+    } else {
+        2
+    };
+    format!("{:.*}", decimals, amount)
+}
+
+// Create and send a PayPal invoice for an order's outstanding balance, as an
+// alternative to a Stripe link for customers who'd rather pay with PayPal.
+// The invoice is kept with the order's payment links and reconciled the same
+// way. PayPal emails it to the customer unless `email_customer` is false.
+#[tauri::command]
+pub async fn create_paypal_invoice(
+    db: State<'_, Database>,
+    credentials: PaypalCredentials,
+    po_id: i64,
+    email_customer: Option<bool>,
+) -> Result<PaymentLink, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let (order, balance, currency) = order_balance(&mut conn, po_id).await?;
+
+    let paypal = Paypal::connect(&credentials).await?;
+    if let Some(link) = reuse_open_link(&mut conn, &paypal, po_id, balance, &currency, None).await?
+    {
+        return Ok(link);
+    }
+
+    let reference = match &order.invoice_number {
+        Some(invoice) => format!("Invoice {}", invoice),
+        None => format!("Order {}", order.confirmation_code),
+    };
+    let draft = paypal
+        .send(
+            paypal
+                .client
+                .post(format!("{}/v2/invoicing/invoices", paypal.base))
+                .header("Prefer", "return=representation")
+                .json(&json!({
+                    "detail": {
+                        "currency_code": currency,
+                        "reference": reference,
+                        "note": format!("Confirmation code {}", order.confirmation_code),
+                    },
+                    "primary_recipients": [{
+                        "billing_info": {
+                            "name": { "full_name": order.customer_name },
+                            "email_address": order.customer_email,
+                        }
+                    }],
+                    "items": [{
+                        "name": reference,
+                        "quantity": "1",
+                        "unit_amount": {
+                            "currency_code": currency,
+                            "value": paypal_value(balance, &currency),
+                        },
+                    }],
+                    "configuration": { "allow_tip": false },
+                })),
+            "create PayPal invoice",
+        )
+        .await?;
+    let invoice_id = draft["id"]
+        .as_str()
+        .ok_or("PayPal didn't return an invoice ID")?
+        .to_string();
+
+    paypal
+        .send(
+            paypal
+                .client
+                .post(paypal.invoice_url(&invoice_id, "/send"))
+                .json(&json!({
+                    "send_to_recipient": email_customer.unwrap_or(true),
+                    "send_to_invoicer": false,
+                })),
+            "send PayPal invoice",
+        )
+        .await?;
+    // The customer's payment page only exists once the invoice is sent
+    let sent = paypal.invoice(&invoice_id).await?;
+    let url = sent["detail"]["metadata"]["recipient_view_url"]
+        .as_str()
+        .ok_or("PayPal didn't return a payment page for the invoice")?;
+
+    insert_payment_link(
+        &mut conn,
+        po_id,
+        Paypal::PROVIDER,
+        &NewPaymentLink {
+            external_id: invoice_id,
+            url: Some(url.to_string()),
+            ..Default::default()
+        },
+        balance,
+        &currency,
+    )
+    .await
+}
+
+// Check the order's latest PayPal invoice and reconcile it: a paid invoice is
+// recorded as a payment (moving a fully paid invoiced order to paid), and an
+// invoice the order no longer needs is cancelled in PayPal.
+#[tauri::command]
+pub async fn get_paypal_invoice_status(
+    app: AppHandle,
+    db: State<'_, Database>,
+    credentials: PaypalCredentials,
+    po_id: i64,
+) -> Result<LinkCheck, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let link = latest_payment_link(&mut conn, po_id, Paypal::PROVIDER)
+        .await?
+        .ok_or_else(|| AppError::Validation(format!("Order {} has no PayPal invoice", po_id)))?;
+    drop(conn);
+
+    // Paid and cancelled invoices are settled; no need to ask PayPal again
+    let outcome = match link.status.as_str() {
+        "paid" => Some(LinkCheckOutcome::Paid),
+        "cancelled" => Some(LinkCheckOutcome::Cancelled),
+        _ => None,
+    };
+    if let Some(outcome) = outcome {
+        return Ok(LinkCheck {
+            link,
+            outcome,
+            message: None,
+        });
+    }
+
+    let paypal = Paypal::connect(&credentials).await?;
+    let (check, _) = check_payment_link(&app, &db.pool, &paypal, link).await?;
+    Ok(check)
+}
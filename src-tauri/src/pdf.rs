@@ -0,0 +1,318 @@
+use printpdf::image_crate::{self as image, DynamicImage, RgbImage};
+use printpdf::{
+    BuiltinFont, Color, Image, ImageTransform, IndirectFontRef, Line, Mm, PdfDocument,
+    PdfDocumentReference, PdfLayerReference, Point, Rect, Rgb,
+};
+
+use crate::error::AppError;
+
+// A4 portrait, in mm
+pub const PAGE_WIDTH: f32 = 210.0;
+pub const PAGE_HEIGHT: f32 = 297.0;
+pub const MARGIN: f32 = 18.0;
+
+pub const TEXT_COLOR: (f32, f32, f32) = (0.13, 0.13, 0.16);
+pub const MUTED_COLOR: (f32, f32, f32) = (0.42, 0.45, 0.5);
+pub const RULE_COLOR: (f32, f32, f32) = (0.82, 0.84, 0.87);
+
+const PT_TO_MM: f32 = 0.352_778;
+
+// Resolution images are placed at before scaling; only affects the math
+const IMAGE_DPI: f32 = 300.0;
+
+// Helper: Approximate Helvetica advance width of a character, in 1/1000 em.
+// Builtin fonts carry no metrics in printpdf, and right-aligned amounts only
+// need to line up roughly.
+fn char_width(c: char, bold: bool) -> f32 {
+    let width = match c {
+        '0'..='9' | '$' => 556.0,
+        '.' | ',' | ':' | ';' | ' ' | '\u{a0}' | '!' | '|' => 278.0,
+        'i' | 'j' | 'l' | '\'' => 222.0,
+        'f' | 't' | 'I' | '/' | '(' | ')' | '[' | ']' | '-' => 300.0,
+        'm' | 'M' | 'W' => 833.0,
+        'w' => 722.0,
+        'A'..='Z' => 667.0,
+        _ => 540.0,
+    };
+    if bold {
+        width * 1.06
+    } else {
+        width
+    }
+}
+
+// Width of text at a font size, in mm
+pub fn text_width(text: &str, size: f32, bold: bool) -> f32 {
+    text.chars().map(|c| char_width(c, bold)).sum::<f32>() / 1000.0 * size * PT_TO_MM
+}
+
+// Break text into lines no wider than `width` mm, splitting on spaces (and
+// inside words that are too long on their own)
+pub fn wrap_text(text: &str, width: f32, size: f32, bold: bool) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{} {}", line, word)
+            };
+            if text_width(&candidate, size, bold) <= width {
+                line = candidate;
+                continue;
+            }
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            for c in word.chars() {
+                if !line.is_empty() && text_width(&format!("{}{}", line, c), size, bold) > width {
+                    lines.push(std::mem::take(&mut line));
+                }
+                line.push(c);
+            }
+        }
+        lines.push(line);
+    }
+    if lines.is_empty() {
+        lines.push(String::new());
+    }
+    lines
+}
+
+// Parse "#rrggbb" (or "#rgb") into PDF color components
+pub fn parse_hex_color(value: &str) -> Option<(f32, f32, f32)> {
+    let hex = value.trim().trim_start_matches('#');
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let hex = match hex.len() {
+        3 => hex.chars().flat_map(|c| [c, c]).collect::<String>(),
+        6 => hex.to_string(),
+        _ => return None,
+    };
+    let channel = |i: usize| {
+        u8::from_str_radix(&hex[i..i + 2], 16)
+            .ok()
+            .map(|v| v as f32 / 255.0)
+    };
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+fn color((r, g, b): (f32, f32, f32)) -> Color {
+    Color::Rgb(Rgb::new(r, g, b, None))
+}
+
+// Top-down page writer over printpdf. `y` is the baseline of the current line,
+// measured from the bottom of the page like PDF coordinates; callers move it
+// down with `advance` and call `ensure_space` before anything that mustn't be
+// split across pages.
+pub struct PdfWriter {
+    doc: PdfDocumentReference,
+    layer: PdfLayerReference,
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+    pub y: f32,
+    page_width: f32,
+    page_height: f32,
+    // Nothing drawn on the current page yet
+    blank: bool,
+    pages: usize,
+}
+
+impl PdfWriter {
+    pub fn new(title: &str) -> Result<Self, AppError> {
+        Self::with_page_size(title, PAGE_WIDTH, PAGE_HEIGHT)
+    }
+
+    // Pages of another size (e.g. labels), in mm
+    pub fn with_page_size(title: &str, width: f32, height: f32) -> Result<Self, AppError> {
+        let (doc, page, layer) = PdfDocument::new(title, Mm(width), Mm(height), "Layer 1");
+        let regular = doc
+            .add_builtin_font(BuiltinFont::Helvetica)
+            .map_err(|e| format!("Failed to load PDF font: {}", e))?;
+        let bold = doc
+            .add_builtin_font(BuiltinFont::HelveticaBold)
+            .map_err(|e| format!("Failed to load PDF font: {}", e))?;
+        let layer = doc.get_page(page).get_layer(layer);
+        Ok(PdfWriter {
+            doc,
+            layer,
+            regular,
+            bold,
+            y: height - MARGIN,
+            page_width: width,
+            page_height: height,
+            blank: true,
+            pages: 1,
+        })
+    }
+
+    // Continue on a fresh page, at the top margin
+    pub fn new_page(&mut self) {
+        let (page, layer) = self.doc.add_page(
+            Mm(self.page_width),
+            Mm(self.page_height),
+            format!("Layer {}", self.pages + 1),
+        );
+        self.layer = self.doc.get_page(page).get_layer(layer);
+        self.y = self.page_height - MARGIN;
+        self.blank = true;
+        self.pages += 1;
+    }
+
+    // Start a new document section (e.g. the next invoice in a merged file) on
+    // its own page, reusing the current page if nothing is on it yet
+    pub fn start_section(&mut self) {
+        if !self.blank {
+            self.new_page();
+        }
+    }
+
+    // Move to the next page if fewer than `height` mm are left above the margin
+    pub fn ensure_space(&mut self, height: f32) {
+        if self.y - height < MARGIN {
+            self.new_page();
+        }
+    }
+
+    pub fn advance(&mut self, height: f32) {
+        self.y -= height;
+    }
+
+    pub fn text(&mut self, x: f32, size: f32, bold: bool, rgb: (f32, f32, f32), text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        let font = if bold { &self.bold } else { &self.regular };
+        self.layer.set_fill_color(color(rgb));
+        self.layer.use_text(text, size, Mm(x), Mm(self.y), font);
+        self.blank = false;
+    }
+
+    // Text ending at `right` mm from the left edge
+    pub fn text_right(
+        &mut self,
+        right: f32,
+        size: f32,
+        bold: bool,
+        rgb: (f32, f32, f32),
+        text: &str,
+    ) {
+        let x = right - text_width(text, size, bold);
+        self.text(x, size, bold, rgb, text);
+    }
+
+    // Horizontal line across the text area, slightly below the current baseline
+    pub fn rule(&mut self, rgb: (f32, f32, f32), thickness: f32) {
+        let y = Mm(self.y).into();
+        let line = Line {
+            points: vec![
+                (
+                    Point {
+                        x: Mm(MARGIN).into(),
+                        y,
+                    },
+                    false,
+                ),
+                (
+                    Point {
+                        x: Mm(self.page_width - MARGIN).into(),
+                        y,
+                    },
+                    false,
+                ),
+            ],
+            is_closed: false,
+        };
+        self.layer.set_outline_color(color(rgb));
+        self.layer.set_outline_thickness(thickness);
+        self.layer.add_line(line);
+        self.blank = false;
+    }
+
+    // Filled band `height` mm tall whose bottom edge is the current baseline
+    pub fn band(&mut self, x: f32, width: f32, height: f32, rgb: (f32, f32, f32)) {
+        self.layer.set_fill_color(color(rgb));
+        self.layer.add_rect(Rect::new(
+            Mm(x),
+            Mm(self.y),
+            Mm(x + width),
+            Mm(self.y + height),
+        ));
+        self.blank = false;
+    }
+
+    // Draw an image scaled to fit within `max_width` x `max_height` mm with
+    // its top-left corner at (`x`, current baseline). Transparent pixels are
+    // flattened onto white. Returns the drawn height so the caller can advance.
+    pub fn image(&mut self, image: &DynamicImage, x: f32, max_width: f32, max_height: f32) -> f32 {
+        let rgba = image.to_rgba8();
+        let flattened = RgbImage::from_fn(rgba.width(), rgba.height(), |px, py| {
+            let [r, g, b, a] = rgba.get_pixel(px, py).0;
+            let blend = |c: u8| ((c as u32 * a as u32 + 255 * (255 - a as u32)) / 255) as u8;
+            image::Rgb([blend(r), blend(g), blend(b)])
+        });
+        // Natural size at the transform's DPI, in mm
+        let natural_width = flattened.width() as f32 / IMAGE_DPI * 25.4;
+        let natural_height = flattened.height() as f32 / IMAGE_DPI * 25.4;
+        if natural_width <= 0.0 || natural_height <= 0.0 {
+            return 0.0;
+        }
+        let scale = (max_width / natural_width).min(max_height / natural_height);
+        let height = natural_height * scale;
+
+        Image::from_dynamic_image(&DynamicImage::ImageRgb8(flattened)).add_to_layer(
+            self.layer.clone(),
+            ImageTransform {
+                translate_x: Some(Mm(x)),
+                translate_y: Some(Mm(self.y - height)),
+                scale_x: Some(scale),
+                scale_y: Some(scale),
+                dpi: Some(IMAGE_DPI),
+                ..Default::default()
+            },
+        );
+        self.blank = false;
+        height
+    }
+
+    pub fn finish(self) -> Result<Vec<u8>, AppError> {
+        self.doc
+            .save_to_bytes()
+            .map_err(|e| AppError::Internal(format!("Failed to render PDF: {}", e)))
+    }
+}
+
+// Write PDF bytes next to `dest` and rename into place, so a failed write
+// never leaves a truncated file behind
+pub fn save_pdf(dest: &std::path::Path, bytes: &[u8]) -> Result<(), AppError> {
+    let partial = dest.with_extension(format!("partial-{}", uuid::Uuid::new_v4()));
+    let written = std::fs::write(&partial, bytes)
+        .and_then(|_| std::fs::rename(&partial, dest))
+        .map_err(|e| AppError::Internal(format!("Failed to write {}: {}", dest.display(), e)));
+    if written.is_err() {
+        let _ = std::fs::remove_file(&partial);
+    }
+    written
+}
+
+// File-system safe name from an invoice number, order code, etc.
+pub fn safe_file_name(name: &str) -> String {
+    let cleaned: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if cleaned.is_empty() {
+        "document".to_string()
+    } else {
+        cleaned
+    }
+}
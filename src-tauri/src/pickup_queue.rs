@@ -0,0 +1,231 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, State};
+use uuid::Uuid;
+
+use crate::confirmation_codes::{normalize_code, verify_checksum};
+use crate::db::Database;
+use crate::error::AppError;
+use crate::fulfillment::{collect_pickup, PickupOutcome, PickupVerification};
+use crate::order_status::emit_transition;
+
+const QUEUE_FILE: &str = "pickup_queue.json";
+
+// Scans can be queued while a sync is replaying the file
+static QUEUE_LOCK: Mutex<()> = Mutex::new(());
+
+// A pickup scan taken while the device was offline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedPickupScan {
+    pub id: String,
+    pub confirmation_code: String,
+    // UTC, same format as SQLite's CURRENT_TIMESTAMP
+    pub scanned_at: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PickupSyncStatus {
+    // Marked collected as of the scan time
+    Collected,
+    // Someone else collected it first (another device, or the same code
+    // scanned twice offline); the items were handed over more than once
+    Conflict,
+    // Not collectable (unpaid, cancelled, shipping or unknown code); the
+    // counter handed items over for an order that wasn't ready
+    Rejected,
+    // Couldn't reach the database; the scan stays queued
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PickupSyncResult {
+    pub scan: QueuedPickupScan,
+    pub status: PickupSyncStatus,
+    pub verification: Option<PickupVerification>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PickupQueueSync {
+    pub results: Vec<PickupSyncResult>,
+    // Scans still waiting for the next sync
+    pub remaining: usize,
+}
+
+// Helper: The active profile's queue file
+fn queue_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+    Ok(crate::profiles::active_data_dir(app)?.join(QUEUE_FILE))
+}
+
+fn load_queue(app: &AppHandle) -> Result<Vec<QueuedPickupScan>, AppError> {
+    let path = queue_path(app)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let json = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read pickup queue: {}", e))?;
+    serde_json::from_str(&json)
+        .map_err(|e| AppError::Internal(format!("Failed to parse pickup queue: {}", e)))
+}
+
+fn save_queue(app: &AppHandle, queue: &[QueuedPickupScan]) -> Result<(), AppError> {
+    let path = queue_path(app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create app data dir: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(queue)
+        .map_err(|e| format!("Failed to serialize pickup queue: {}", e))?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json).map_err(|e| format!("Failed to save pickup queue: {}", e))?;
+    std::fs::rename(&tmp, &path)
+        .map_err(|e| AppError::Internal(format!("Failed to save pickup queue: {}", e)))
+}
+
+// Record a pickup scan without touching the database, for the mobile counter
+// when it's offline. The code's checksum is still verified so typos are
+// caught while the customer is there.
+#[tauri::command]
+pub fn queue_pickup_scan(
+    app: AppHandle,
+    scanned_code: String,
+) -> Result<QueuedPickupScan, AppError> {
+    let code = normalize_code(&scanned_code);
+    if code.is_empty() {
+        return Err(AppError::Validation("Scanned code is empty".to_string()));
+    }
+    verify_checksum(&code)?;
+
+    let _guard = QUEUE_LOCK
+        .lock()
+        .map_err(|_| "Pickup queue is unavailable")?;
+    let mut queue = load_queue(&app)?;
+    let scan = QueuedPickupScan {
+        id: Uuid::new_v4().to_string(),
+        confirmation_code: code,
+        scanned_at: chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+    };
+    queue.push(scan.clone());
+    save_queue(&app, &queue)?;
+    Ok(scan)
+}
+
+#[tauri::command]
+pub fn list_pickup_queue(app: AppHandle) -> Result<Vec<QueuedPickupScan>, AppError> {
+    let _guard = QUEUE_LOCK
+        .lock()
+        .map_err(|_| "Pickup queue is unavailable")?;
+    load_queue(&app)
+}
+
+// Replay queued scans in the order they were taken, once the database is
+// reachable again. Each scan is applied in its own transaction and dated to
+// when it was scanned; orders that were already collected come back as
+// conflicts. Replay stops at the first database error so the rest keep their
+// order for the next sync.
+#[tauri::command]
+pub async fn sync_pickup_queue(
+    app: AppHandle,
+    db: State<'_, Database>,
+) -> Result<PickupQueueSync, AppError> {
+    let queue = {
+        let _guard = QUEUE_LOCK
+            .lock()
+            .map_err(|_| "Pickup queue is unavailable")?;
+        load_queue(&app)?
+    };
+
+    let mut results = Vec::new();
+    for scan in queue {
+        let replayed = async {
+            let mut tx = db
+                .pool
+                .begin()
+                .await
+                .map_err(|e| format!("Failed to start transaction: {}", e))?;
+            let collected =
+                collect_pickup(&mut tx, &scan.confirmation_code, Some(&scan.scanned_at)).await?;
+            tx.commit()
+                .await
+                .map_err(|e| format!("Failed to save pickup: {}", e))?;
+            Ok::<_, AppError>(collected)
+        }
+        .await;
+
+        let (status, verification, message) = match replayed {
+            Err(e) => {
+                results.push(PickupSyncResult {
+                    scan,
+                    status: PickupSyncStatus::Failed,
+                    verification: None,
+                    message: Some(e.to_string()),
+                });
+                break;
+            }
+            Ok(None) => (
+                PickupSyncStatus::Rejected,
+                None,
+                Some(format!(
+                    "No order found for code {}",
+                    scan.confirmation_code
+                )),
+            ),
+            Ok(Some((verification, transition))) => {
+                if let Some(transition) = &transition {
+                    emit_transition(&app, transition);
+                }
+                let (status, message) = match verification.outcome {
+                    PickupOutcome::Collected => (PickupSyncStatus::Collected, None),
+                    PickupOutcome::AlreadyCollected => (
+                        PickupSyncStatus::Conflict,
+                        Some(match &verification.collected_at {
+                            Some(at) => format!("Already collected at {}", at),
+                            None => "Already collected".to_string(),
+                        }),
+                    ),
+                    PickupOutcome::NotPaid => (
+                        PickupSyncStatus::Rejected,
+                        Some(format!(
+                            "Order wasn't paid; {:.2} is still due",
+                            verification.balance_due
+                        )),
+                    ),
+                    PickupOutcome::Cancelled => (
+                        PickupSyncStatus::Rejected,
+                        Some("Order was cancelled".to_string()),
+                    ),
+                    PickupOutcome::ShippingOrder => (
+                        PickupSyncStatus::Rejected,
+                        Some("Order is set up for shipping".to_string()),
+                    ),
+                };
+                (status, Some(verification), message)
+            }
+        };
+        results.push(PickupSyncResult {
+            scan,
+            status,
+            verification,
+            message,
+        });
+    }
+
+    // Drop what was replayed, keeping anything queued while this ran
+    let _guard = QUEUE_LOCK
+        .lock()
+        .map_err(|_| "Pickup queue is unavailable")?;
+    let mut queue = load_queue(&app)?;
+    queue.retain(|scan| {
+        !results
+            .iter()
+            .any(|r| r.scan.id == scan.id && r.status != PickupSyncStatus::Failed)
+    });
+    save_queue(&app, &queue)?;
+
+    Ok(PickupQueueSync {
+        results,
+        remaining: queue.len(),
+    })
+}
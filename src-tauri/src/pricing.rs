@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteConnection;
+use tauri::State;
+
+use crate::audit;
+use crate::currency::convert;
+use crate::db::Database;
+use crate::error::AppError;
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PriceHistoryEntry {
+    pub id: i64,
+    pub product_id: i64,
+    pub price: f64,
+    pub currency_code: Option<String>,
+    pub effective_from: String,
+    pub created_at: Option<String>,
+}
+
+// Price of a product at a point in time (any SQLite-parsable timestamp, e.g.
+// RFC 3339), or now when `at` is None. Products without history fall back to
+// their current price.
+pub async fn price_at(
+    conn: &mut SqliteConnection,
+    product_id: i64,
+    at: Option<&str>,
+) -> Result<f64, AppError> {
+    Ok(priced_at(conn, product_id, at).await?.0)
+}
+
+// Helper: Price at a point in time along with the currency it's quoted in
+async fn priced_at(
+    conn: &mut SqliteConnection,
+    product_id: i64,
+    at: Option<&str>,
+) -> Result<(f64, Option<String>), AppError> {
+    let scheduled = sqlx::query_as::<_, (f64, Option<String>)>(
+        "SELECT price, currency_code FROM product_price_history \
+         WHERE product_id = ? AND effective_from <= COALESCE(datetime(?), CURRENT_TIMESTAMP) \
+         ORDER BY effective_from DESC, id DESC LIMIT 1",
+    )
+    .bind(product_id)
+    .bind(at)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to look up product price: {}", e))?;
+
+    if let Some(priced) = scheduled {
+        return Ok(priced);
+    }
+
+    sqlx::query_as::<_, (f64, Option<String>)>(
+        "SELECT price, currency_code FROM products WHERE id = ?",
+    )
+    .bind(product_id)
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| format!("Failed to look up product price: {}", e))?
+    .ok_or_else(|| AppError::NotFound(format!("Product {} not found", product_id)))
+}
+
+// Price of a product in `currency`. An explicit per-currency price in effect
+// at `at` wins; otherwise the base price at `at` is converted with the cached
+// exchange rates. Prices without a currency are taken to already be in
+// `currency`.
+pub async fn price_in_currency(
+    conn: &mut SqliteConnection,
+    product_id: i64,
+    at: Option<&str>,
+    currency: &str,
+) -> Result<f64, AppError> {
+    // A NULL price means the explicit price had been removed by then
+    let explicit = sqlx::query_scalar::<_, Option<f64>>(
+        "SELECT price FROM currency_price_history \
+         WHERE product_id = ? AND currency_code = ? COLLATE NOCASE \
+         AND effective_from <= COALESCE(datetime(?), CURRENT_TIMESTAMP) \
+         ORDER BY effective_from DESC, id DESC LIMIT 1",
+    )
+    .bind(product_id)
+    .bind(currency)
+    .bind(at)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to look up product price: {}", e))?
+    .flatten();
+
+    if let Some(price) = explicit {
+        return Ok(price);
+    }
+
+    let (price, quoted_in) = priced_at(&mut *conn, product_id, at).await?;
+    match quoted_in.as_deref().map(str::trim) {
+        Some(from) if !from.is_empty() => convert(conn, price, from, currency).await,
+        _ => Ok(price),
+    }
+}
+
+// Price changes for a product, newest first, including scheduled future prices
+#[tauri::command]
+pub async fn get_price_history(
+    db: State<'_, Database>,
+    product_id: i64,
+) -> Result<Vec<PriceHistoryEntry>, AppError> {
+    sqlx::query_as::<_, PriceHistoryEntry>(
+        "SELECT id, product_id, price, currency_code, effective_from, created_at \
+         FROM product_price_history WHERE product_id = ? ORDER BY effective_from DESC, id DESC",
+    )
+    .bind(product_id)
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to load price history: {}", e)))
+}
+
+// Schedule a price that applies to orders placed from `effective_from` onwards
+#[tauri::command]
+pub async fn schedule_price_change(
+    db: State<'_, Database>,
+    product_id: i64,
+    price: f64,
+    effective_from: String,
+) -> Result<PriceHistoryEntry, AppError> {
+    if !price.is_finite() || price < 0.0 {
+        return Err(AppError::Validation(format!(
+            "Invalid product price: {}",
+            price
+        )));
+    }
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let currency_code =
+        sqlx::query_scalar::<_, Option<String>>("SELECT currency_code FROM products WHERE id = ?")
+            .bind(product_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to load product: {}", e))?
+            .ok_or_else(|| AppError::NotFound(format!("Product {} not found", product_id)))?;
+
+    let entry = sqlx::query_as::<_, PriceHistoryEntry>(
+        "INSERT INTO product_price_history (product_id, price, currency_code, effective_from) \
+         SELECT ?, ?, ?, datetime(?) WHERE datetime(?) IS NOT NULL \
+         RETURNING id, product_id, price, currency_code, effective_from, created_at",
+    )
+    .bind(product_id)
+    .bind(price)
+    .bind(&currency_code)
+    .bind(&effective_from)
+    .bind(&effective_from)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to schedule price change: {}", e))?
+    .ok_or_else(|| AppError::Validation(format!("Invalid effective date: {}", effective_from)))?;
+
+    audit::record(
+        &mut *tx,
+        "product",
+        product_id,
+        "schedule_price_change",
+        None,
+        Some(&entry),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save price change: {}", e))?;
+
+    Ok(entry)
+}
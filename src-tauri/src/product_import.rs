@@ -0,0 +1,441 @@
+use calamine::{open_workbook_auto, Reader};
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteConnection;
+use std::path::Path;
+use tauri::State;
+
+use crate::audit;
+use crate::barcodes::{ensure_barcode_free, normalize_barcode};
+use crate::currency::normalize_currency;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::inventory::{load_stock_level, record_movement};
+use crate::products::load_product;
+
+// Header names tried for each field when no mapping is given (compared
+// case-insensitively, ignoring spaces, dashes and underscores)
+const NAME_HEADERS: &[&str] = &["name", "productname", "product", "title", "item"];
+const UNIQUE_ID_HEADERS: &[&str] = &["uniqueid", "sku", "code", "productcode", "productid"];
+const BARCODE_HEADERS: &[&str] = &["barcode", "ean", "upc", "gtin", "ean13"];
+const DESCRIPTION_HEADERS: &[&str] = &["description", "details"];
+const PRICE_HEADERS: &[&str] = &["price", "unitprice", "amount"];
+const CURRENCY_HEADERS: &[&str] = &["currency", "currencycode"];
+const IMAGE_URL_HEADERS: &[&str] = &["imageurl", "image", "photo"];
+const STOCK_HEADERS: &[&str] = &["stock", "stockquantity", "quantity", "qty"];
+const LOW_STOCK_HEADERS: &[&str] = &["lowstockthreshold", "reorderlevel", "lowstock"];
+
+// Which column (by header text) holds each product field. Leave a field out
+// to have it detected from common header names; name and price are required.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProductColumnMapping {
+    pub name: Option<String>,
+    pub unique_id: Option<String>,
+    pub barcode: Option<String>,
+    pub description: Option<String>,
+    pub price: Option<String>,
+    pub currency_code: Option<String>,
+    pub image_url: Option<String>,
+    pub stock_quantity: Option<String>,
+    pub low_stock_threshold: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProductImportMode {
+    // Rows whose unique ID already exists are reported as errors
+    #[default]
+    Create,
+    // Rows whose unique ID already exists update that product
+    Upsert,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductImportError {
+    // Row number as shown in the spreadsheet (the header is row 1)
+    pub row: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductImportResult {
+    pub created: usize,
+    pub updated: usize,
+    pub errors: Vec<ProductImportError>,
+}
+
+// Column positions resolved against the file's header row
+struct ResolvedColumns {
+    name: usize,
+    unique_id: Option<usize>,
+    barcode: Option<usize>,
+    description: Option<usize>,
+    price: usize,
+    currency_code: Option<usize>,
+    image_url: Option<usize>,
+    stock_quantity: Option<usize>,
+    low_stock_threshold: Option<usize>,
+}
+
+// One row after validation, ready to write
+struct ProductRow {
+    unique_id: Option<String>,
+    barcode: Option<String>,
+    name: String,
+    description: Option<String>,
+    price: f64,
+    currency_code: Option<String>,
+    image_url: Option<String>,
+    stock_quantity: Option<i64>,
+    low_stock_threshold: Option<i64>,
+}
+
+// Helper: Header text reduced to letters and digits for matching
+pub fn normalize_header(header: &str) -> String {
+    header
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+// Helper: Position of a mapped column, or of the first header matching an alias
+pub fn find_column(
+    headers: &[String],
+    mapped: Option<&String>,
+    aliases: &[&str],
+) -> Result<Option<usize>, AppError> {
+    let normalized: Vec<String> = headers.iter().map(|h| normalize_header(h)).collect();
+    match mapped {
+        Some(header) => normalized
+            .iter()
+            .position(|h| *h == normalize_header(header))
+            .map(Some)
+            .ok_or_else(|| {
+                AppError::NotFound(format!("Column \"{}\" not found in the file", header))
+            }),
+        None => Ok(aliases
+            .iter()
+            .find_map(|alias| normalized.iter().position(|h| h == alias))),
+    }
+}
+
+fn resolve_columns(
+    headers: &[String],
+    mapping: &ProductColumnMapping,
+) -> Result<ResolvedColumns, AppError> {
+    let name = find_column(headers, mapping.name.as_ref(), NAME_HEADERS)?
+        .ok_or("No product name column found; map one explicitly")?;
+    let price = find_column(headers, mapping.price.as_ref(), PRICE_HEADERS)?
+        .ok_or("No price column found; map one explicitly")?;
+    Ok(ResolvedColumns {
+        name,
+        unique_id: find_column(headers, mapping.unique_id.as_ref(), UNIQUE_ID_HEADERS)?,
+        barcode: find_column(headers, mapping.barcode.as_ref(), BARCODE_HEADERS)?,
+        description: find_column(headers, mapping.description.as_ref(), DESCRIPTION_HEADERS)?,
+        price,
+        currency_code: find_column(headers, mapping.currency_code.as_ref(), CURRENCY_HEADERS)?,
+        image_url: find_column(headers, mapping.image_url.as_ref(), IMAGE_URL_HEADERS)?,
+        stock_quantity: find_column(headers, mapping.stock_quantity.as_ref(), STOCK_HEADERS)?,
+        low_stock_threshold: find_column(
+            headers,
+            mapping.low_stock_threshold.as_ref(),
+            LOW_STOCK_HEADERS,
+        )?,
+    })
+}
+
+// Helper: Read every row of a CSV file as text
+pub fn read_csv_rows(path: &Path) -> Result<Vec<Vec<String>>, AppError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_path(path)
+        .map_err(|e| format!("Failed to open CSV file: {}", e))?;
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| format!("Failed to read CSV file: {}", e))?;
+        rows.push(record.iter().map(|field| field.to_string()).collect());
+    }
+    // Excel writes a byte order mark before the first header
+    if let Some(first) = rows.first_mut().and_then(|row| row.first_mut()) {
+        *first = first.trim_start_matches('\u{feff}').to_string();
+    }
+    Ok(rows)
+}
+
+// Helper: Read every row of the first worksheet of an Excel/ODS workbook as text
+pub fn read_workbook_rows(path: &Path) -> Result<Vec<Vec<String>>, AppError> {
+    let mut workbook =
+        open_workbook_auto(path).map_err(|e| format!("Failed to open workbook: {}", e))?;
+    let range = workbook
+        .worksheet_range_at(0)
+        .ok_or("Workbook has no worksheets")?
+        .map_err(|e| format!("Failed to read worksheet: {}", e))?;
+    Ok(range
+        .rows()
+        .map(|row| row.iter().map(|cell| cell.to_string()).collect())
+        .collect())
+}
+
+// Helper: Every row of a CSV file or workbook, picked by file extension
+pub fn read_spreadsheet_rows(path: &Path) -> Result<Vec<Vec<String>>, AppError> {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "csv" | "txt" => read_csv_rows(path),
+        "xlsx" | "xlsm" | "xls" | "ods" => read_workbook_rows(path),
+        _ => Err(AppError::Validation(format!(
+            "Unsupported file type: .{}",
+            extension
+        ))),
+    }
+}
+
+// Helper: Trimmed cell text, None when the column is unmapped or the cell blank
+pub fn cell(row: &[String], column: Option<usize>) -> Option<String> {
+    column
+        .and_then(|i| row.get(i))
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+// Helper: Whole, non-negative number from a cell ("12" or "12.0" from Excel)
+fn parse_count(value: Option<String>, field: &str) -> Result<Option<i64>, AppError> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    match value.parse::<f64>() {
+        Ok(n) if n.is_finite() && n >= 0.0 && n.fract() == 0.0 => Ok(Some(n as i64)),
+        _ => Err(AppError::Validation(format!(
+            "Invalid {}: {}",
+            field, value
+        ))),
+    }
+}
+
+fn parse_row(row: &[String], columns: &ResolvedColumns) -> Result<ProductRow, AppError> {
+    let name = cell(row, Some(columns.name)).ok_or("Product name must not be empty")?;
+    let price_text = cell(row, Some(columns.price)).ok_or("Price is missing")?;
+    let price = price_text
+        .replace([' ', '\u{a0}'], "")
+        .parse::<f64>()
+        .ok()
+        .filter(|price| price.is_finite() && *price >= 0.0)
+        .ok_or_else(|| AppError::Validation(format!("Invalid product price: {}", price_text)))?;
+    let currency_code = cell(row, columns.currency_code)
+        .as_deref()
+        .map(normalize_currency)
+        .transpose()?;
+
+    let barcode = cell(row, columns.barcode)
+        .as_deref()
+        .map(normalize_barcode)
+        .transpose()?;
+
+    Ok(ProductRow {
+        unique_id: cell(row, columns.unique_id),
+        barcode,
+        name,
+        description: cell(row, columns.description),
+        price,
+        currency_code,
+        image_url: cell(row, columns.image_url),
+        stock_quantity: parse_count(cell(row, columns.stock_quantity), "stock quantity")?,
+        low_stock_threshold: parse_count(
+            cell(row, columns.low_stock_threshold),
+            "low stock threshold",
+        )?,
+    })
+}
+
+// Helper: Bring a product's tracked stock to the imported level, logging the change
+async fn set_stock_level(
+    conn: &mut SqliteConnection,
+    product_id: i64,
+    quantity: i64,
+) -> Result<(), AppError> {
+    let level = load_stock_level(&mut *conn, product_id).await?;
+    let change = quantity - level.stock_quantity.unwrap_or(0);
+    if change != 0 || level.stock_quantity.is_none() {
+        record_movement(
+            &mut *conn,
+            product_id,
+            change,
+            "import",
+            None,
+            Some("Product import"),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+// Helper: Product a row's unique ID refers to, if any
+async fn find_existing(
+    conn: &mut SqliteConnection,
+    unique_id: Option<&str>,
+) -> Result<Option<i64>, AppError> {
+    let Some(unique_id) = unique_id else {
+        return Ok(None);
+    };
+    sqlx::query_scalar::<_, i64>("SELECT id FROM products WHERE unique_id = ? ORDER BY id LIMIT 1")
+        .bind(unique_id)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to look up product: {}", e)))
+}
+
+// Helper: Create a product from a validated row, or update `existing` with it.
+// Returns true when an existing product was updated.
+async fn write_row(
+    conn: &mut SqliteConnection,
+    row: &ProductRow,
+    existing: Option<i64>,
+) -> Result<bool, AppError> {
+    let (id, updated) = match existing {
+        Some(id) => {
+            let before = load_product(&mut *conn, id).await?;
+            sqlx::query(
+                "UPDATE products SET name = ?, description = COALESCE(?, description), price = ?, \
+                 currency_code = COALESCE(?, currency_code), image_url = COALESCE(?, image_url), \
+                 low_stock_threshold = COALESCE(?, low_stock_threshold), \
+                 barcode = COALESCE(?, barcode), is_active = 1 WHERE id = ?",
+            )
+            .bind(&row.name)
+            .bind(&row.description)
+            .bind(row.price)
+            .bind(&row.currency_code)
+            .bind(&row.image_url)
+            .bind(row.low_stock_threshold)
+            .bind(&row.barcode)
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to update product: {}", e))?;
+            let after = load_product(&mut *conn, id).await?;
+            audit::record(
+                &mut *conn,
+                "product",
+                id,
+                "import",
+                Some(&before),
+                Some(&after),
+            )
+            .await?;
+            (id, true)
+        }
+        None => {
+            let unique_id = row.unique_id.clone().unwrap_or_else(|| {
+                format!(
+                    "PRD-{}",
+                    uuid::Uuid::new_v4().simple().to_string()[..8].to_uppercase()
+                )
+            });
+            let id = sqlx::query_scalar::<_, i64>(
+                "INSERT INTO products (name, description, price, currency_code, image_url, \
+                 unique_id, low_stock_threshold, barcode) VALUES (?, ?, ?, ?, ?, ?, ?, ?) RETURNING id",
+            )
+            .bind(&row.name)
+            .bind(&row.description)
+            .bind(row.price)
+            .bind(row.currency_code.as_deref().unwrap_or("USD"))
+            .bind(&row.image_url)
+            .bind(unique_id)
+            .bind(row.low_stock_threshold)
+            .bind(&row.barcode)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to create product: {}", e))?;
+            let created = load_product(&mut *conn, id).await?;
+            audit::record(&mut *conn, "product", id, "import", None, Some(&created)).await?;
+            (id, false)
+        }
+    };
+
+    if let Some(quantity) = row.stock_quantity {
+        set_stock_level(&mut *conn, id, quantity).await?;
+    }
+    Ok(updated)
+}
+
+// Import a product catalog from a CSV or Excel file (first worksheet). The
+// first row holds the headers. Rows that fail validation are reported and
+// skipped; the rest are written in one transaction.
+#[tauri::command]
+pub async fn import_products(
+    db: State<'_, Database>,
+    path: String,
+    mapping: Option<ProductColumnMapping>,
+    mode: Option<ProductImportMode>,
+) -> Result<ProductImportResult, AppError> {
+    let mode = mode.unwrap_or_default();
+    let rows = read_spreadsheet_rows(Path::new(&path))?;
+
+    let mut rows = rows.into_iter();
+    let headers = rows.next().ok_or("The file is empty")?;
+    let columns = resolve_columns(&headers, &mapping.unwrap_or_default())?;
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let mut result = ProductImportResult {
+        created: 0,
+        updated: 0,
+        errors: Vec::new(),
+    };
+    for (index, row) in rows.enumerate() {
+        let row_number = index + 2;
+        if row.iter().all(|value| value.trim().is_empty()) {
+            continue;
+        }
+        let parsed = match parse_row(&row, &columns) {
+            Ok(parsed) => parsed,
+            Err(message) => {
+                result.errors.push(ProductImportError {
+                    row: row_number,
+                    message: message.to_string(),
+                });
+                continue;
+            }
+        };
+
+        let existing = find_existing(&mut tx, parsed.unique_id.as_deref()).await?;
+        if existing.is_some() && mode == ProductImportMode::Create {
+            result.errors.push(ProductImportError {
+                row: row_number,
+                message: format!(
+                    "A product with unique ID {} already exists",
+                    parsed.unique_id.as_deref().unwrap_or_default()
+                ),
+            });
+            continue;
+        }
+
+        if let Some(barcode) = &parsed.barcode {
+            if let Err(message) = ensure_barcode_free(&mut *tx, barcode, existing).await {
+                result.errors.push(ProductImportError {
+                    row: row_number,
+                    message: message.to_string(),
+                });
+                continue;
+            }
+        }
+
+        if write_row(&mut tx, &parsed, existing).await? {
+            result.updated += 1;
+        } else {
+            result.created += 1;
+        }
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save imported products: {}", e))?;
+
+    Ok(result)
+}
@@ -0,0 +1,233 @@
+use sqlx::{Executor, Sqlite};
+use tauri::State;
+
+use crate::audit;
+use crate::barcodes::{ensure_barcode_free, normalize_barcode};
+use crate::db::Database;
+use crate::error::AppError;
+use crate::integrity::ensure_product_removable;
+use crate::models::{Product, ProductInput};
+
+// is_active was added to existing databases with ALTER TABLE, so guard against NULLs
+const PRODUCT_COLUMNS: &str = "id, unique_id, name, description, price, currency_code, image_url, \
+     event_id, COALESCE(is_active, 1) AS is_active, stock_quantity, low_stock_threshold, \
+     supplier_id, barcode, created_at";
+
+// Helper: Validate product fields before writing them
+fn validate_product(input: &ProductInput) -> Result<(), AppError> {
+    if input.name.trim().is_empty() {
+        return Err(AppError::Validation(
+            "Product name must not be empty".to_string(),
+        ));
+    }
+    if !input.price.is_finite() || input.price < 0.0 {
+        return Err(AppError::Validation(format!(
+            "Invalid product price: {}",
+            input.price
+        )));
+    }
+    Ok(())
+}
+
+// Helper: Normalized barcode from the input, None when left blank
+fn input_barcode(input: &ProductInput) -> Result<Option<String>, AppError> {
+    input
+        .barcode
+        .as_deref()
+        .filter(|code| !code.trim().is_empty())
+        .map(normalize_barcode)
+        .transpose()
+}
+
+// Helper: Load a single product by ID
+pub async fn load_product<'e, E>(executor: E, id: i64) -> Result<Product, AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    sqlx::query_as::<_, Product>(&format!(
+        "SELECT {} FROM products WHERE id = ?",
+        PRODUCT_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| format!("Failed to load product: {}", e))?
+    .ok_or_else(|| AppError::NotFound(format!("Product {} not found", id)))
+}
+
+// List products, newest first
+#[tauri::command]
+pub async fn list_products(
+    db: State<'_, Database>,
+    include_inactive: Option<bool>,
+) -> Result<Vec<Product>, AppError> {
+    let filter = if include_inactive.unwrap_or(false) {
+        ""
+    } else {
+        "WHERE COALESCE(is_active, 1) = 1"
+    };
+
+    sqlx::query_as::<_, Product>(&format!(
+        "SELECT {} FROM products {} ORDER BY created_at DESC",
+        PRODUCT_COLUMNS, filter
+    ))
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to list products: {}", e)))
+}
+
+#[tauri::command]
+pub async fn get_product(db: State<'_, Database>, id: i64) -> Result<Product, AppError> {
+    load_product(&db.pool, id).await
+}
+
+#[tauri::command]
+pub async fn create_product(
+    db: State<'_, Database>,
+    product: ProductInput,
+) -> Result<Product, AppError> {
+    validate_product(&product)?;
+    let barcode = input_barcode(&product)?;
+
+    let unique_id = product.unique_id.clone().unwrap_or_else(|| {
+        format!(
+            "PRD-{}",
+            uuid::Uuid::new_v4().simple().to_string()[..8].to_uppercase()
+        )
+    });
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    if let Some(barcode) = &barcode {
+        ensure_barcode_free(&mut *tx, barcode, None).await?;
+    }
+
+    let result = sqlx::query(
+        "INSERT INTO products (name, description, price, currency_code, image_url, event_id, unique_id, \
+         barcode) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(product.name.trim())
+    .bind(&product.description)
+    .bind(product.price)
+    .bind(product.currency_code.as_deref().unwrap_or("USD"))
+    .bind(&product.image_url)
+    .bind(product.event_id)
+    .bind(unique_id)
+    .bind(&barcode)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to create product: {}", e))?;
+
+    let created = load_product(&mut *tx, result.last_insert_rowid()).await?;
+    audit::record(
+        &mut *tx,
+        "product",
+        created.id,
+        "create",
+        None,
+        Some(&created),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save product: {}", e))?;
+
+    Ok(created)
+}
+
+#[tauri::command]
+pub async fn update_product(
+    db: State<'_, Database>,
+    id: i64,
+    product: ProductInput,
+) -> Result<Product, AppError> {
+    validate_product(&product)?;
+    let barcode = input_barcode(&product)?;
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_product(&mut *tx, id).await?;
+    if let Some(barcode) = &barcode {
+        ensure_barcode_free(&mut *tx, barcode, Some(id)).await?;
+    }
+
+    sqlx::query(
+        "UPDATE products SET name = ?, description = ?, price = ?, currency_code = ?, image_url = ?, \
+         event_id = ?, unique_id = COALESCE(?, unique_id), barcode = COALESCE(?, barcode) WHERE id = ?",
+    )
+    .bind(product.name.trim())
+    .bind(&product.description)
+    .bind(product.price)
+    .bind(product.currency_code.as_deref().unwrap_or("USD"))
+    .bind(&product.image_url)
+    .bind(product.event_id)
+    .bind(&product.unique_id)
+    .bind(&barcode)
+    .bind(id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to update product: {}", e))?;
+
+    let after = load_product(&mut *tx, id).await?;
+    audit::record(
+        &mut *tx,
+        "product",
+        id,
+        "update",
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save product: {}", e))?;
+
+    Ok(after)
+}
+
+// Products are soft-deleted so existing order items keep their reference.
+// A product that open orders, templates or supplier orders still need can't be deleted.
+#[tauri::command]
+pub async fn delete_product(db: State<'_, Database>, id: i64) -> Result<String, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_product(&mut *tx, id).await?;
+    ensure_product_removable(&mut tx, id).await?;
+
+    sqlx::query("UPDATE products SET is_active = 0 WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to delete product: {}", e))?;
+
+    let after = load_product(&mut *tx, id).await?;
+    audit::record(
+        &mut *tx,
+        "product",
+        id,
+        "delete",
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save product: {}", e))?;
+
+    Ok("Product deleted".to_string())
+}
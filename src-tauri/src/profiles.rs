@@ -0,0 +1,269 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+use crate::db::DATABASE_FILE;
+use crate::error::AppError;
+
+// Registry of business profiles, kept next to (not inside) the databases
+const PROFILES_FILE: &str = "profiles.json";
+
+// Directory holding every non-default profile's files
+const PROFILES_DIR: &str = "profiles";
+
+// The profile that existed before profiles did; its files stay where they were
+pub const DEFAULT_PROFILE_ID: &str = "default";
+
+// A separate business: its own database, and with it its own sender identity,
+// invoice numbering and Google account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub id: String,
+    pub name: String,
+    pub created_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileList {
+    pub active: String,
+    pub profiles: Vec<Profile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveProfile {
+    pub profile: Profile,
+    // What the frontend passes to Database.load for this profile
+    pub database_url: String,
+}
+
+impl Default for ProfileList {
+    fn default() -> Self {
+        ProfileList {
+            active: DEFAULT_PROFILE_ID.to_string(),
+            profiles: vec![Profile {
+                id: DEFAULT_PROFILE_ID.to_string(),
+                name: "Default".to_string(),
+                created_at: None,
+            }],
+        }
+    }
+}
+
+impl ProfileList {
+    fn find(&self, id: &str) -> Result<&Profile, AppError> {
+        self.profiles
+            .iter()
+            .find(|p| p.id == id)
+            .ok_or_else(|| AppError::NotFound(format!("Profile {} not found", id)))
+    }
+
+    fn active_profile(&self) -> Profile {
+        self.find(&self.active)
+            .cloned()
+            .unwrap_or_else(|_| ProfileList::default().profiles.remove(0))
+    }
+}
+
+// Helper: Location of the profile registry
+fn registry_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+    let dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("Failed to resolve app config dir: {}", e))?;
+    Ok(dir.join(PROFILES_FILE))
+}
+
+// Helper: Read the registry; a missing file means only the default profile exists
+fn load_profiles(app: &AppHandle) -> Result<ProfileList, AppError> {
+    let path = registry_path(app)?;
+    if !path.exists() {
+        return Ok(ProfileList::default());
+    }
+    let raw =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read profiles: {}", e))?;
+    serde_json::from_str(&raw)
+        .map_err(|e| AppError::Internal(format!("Failed to parse profiles: {}", e)))
+}
+
+// Helper: Write the registry through a temp file so a crash can't truncate it
+fn save_profiles(app: &AppHandle, list: &ProfileList) -> Result<(), AppError> {
+    let path = registry_path(app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create app config dir: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(list)
+        .map_err(|e| format!("Failed to serialize profiles: {}", e))?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json).map_err(|e| format!("Failed to save profiles: {}", e))?;
+    std::fs::rename(&tmp, &path)
+        .map_err(|e| AppError::Internal(format!("Failed to save profiles: {}", e)))
+}
+
+// Helper: Path of a profile's files relative to an app directory
+fn profile_subdir(id: &str) -> PathBuf {
+    if id == DEFAULT_PROFILE_ID {
+        PathBuf::new()
+    } else {
+        PathBuf::from(PROFILES_DIR).join(id)
+    }
+}
+
+// Config directory of the active profile (holds its database)
+pub fn active_config_dir(app: &AppHandle) -> Result<PathBuf, AppError> {
+    let dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("Failed to resolve app config dir: {}", e))?;
+    Ok(dir.join(profile_subdir(&load_profiles(app)?.active_profile().id)))
+}
+
+// Data directory of the active profile (Drive sync state and other caches)
+pub fn active_data_dir(app: &AppHandle) -> Result<PathBuf, AppError> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?;
+    Ok(dir.join(profile_subdir(&load_profiles(app)?.active_profile().id)))
+}
+
+// Helper: tauri-plugin-sql URL for a profile's database (relative to the config dir)
+fn database_url(id: &str) -> String {
+    let path = profile_subdir(id).join(DATABASE_FILE);
+    format!("sqlite:{}", path.to_string_lossy().replace('\\', "/"))
+}
+
+// Helper: Short ID from the profile name, e.g. "Cake Shop" -> "cake-shop-1a2b3c"
+fn new_profile_id(name: &str) -> String {
+    let slug: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    let suffix = &uuid::Uuid::new_v4().simple().to_string()[..6];
+    if slug.is_empty() {
+        format!("profile-{}", suffix)
+    } else {
+        format!("{}-{}", slug.chars().take(24).collect::<String>(), suffix)
+    }
+}
+
+// Helper: Reject blank or duplicate profile names
+fn validate_name(list: &ProfileList, name: &str, except: Option<&str>) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::Validation(
+            "Profile name must not be empty".to_string(),
+        ));
+    }
+    let taken = list
+        .profiles
+        .iter()
+        .any(|p| Some(p.id.as_str()) != except && p.name.eq_ignore_ascii_case(name));
+    if taken {
+        return Err(AppError::Conflict(format!(
+            "A profile named {} already exists",
+            name
+        )));
+    }
+    Ok(name.to_string())
+}
+
+#[tauri::command]
+pub fn list_profiles(app: AppHandle) -> Result<ProfileList, AppError> {
+    load_profiles(&app)
+}
+
+#[tauri::command]
+pub fn get_active_profile(app: AppHandle) -> Result<ActiveProfile, AppError> {
+    let profile = load_profiles(&app)?.active_profile();
+    Ok(ActiveProfile {
+        database_url: database_url(&profile.id),
+        profile,
+    })
+}
+
+// Add a profile with an empty database; it's created on first switch
+#[tauri::command]
+pub fn create_profile(app: AppHandle, name: String) -> Result<Profile, AppError> {
+    let mut list = load_profiles(&app)?;
+    let name = validate_name(&list, &name, None)?;
+
+    let profile = Profile {
+        id: new_profile_id(&name),
+        name,
+        created_at: Some(chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string()),
+    };
+    list.profiles.push(profile.clone());
+    save_profiles(&app, &list)?;
+
+    Ok(profile)
+}
+
+#[tauri::command]
+pub fn rename_profile(app: AppHandle, id: String, name: String) -> Result<Profile, AppError> {
+    let mut list = load_profiles(&app)?;
+    list.find(&id)?;
+    let name = validate_name(&list, &name, Some(&id))?;
+
+    let profile = list
+        .profiles
+        .iter_mut()
+        .find(|p| p.id == id)
+        .ok_or_else(|| AppError::NotFound(format!("Profile {} not found", id)))?;
+    profile.name = name;
+    let renamed = profile.clone();
+    save_profiles(&app, &list)?;
+
+    Ok(renamed)
+}
+
+// Make another profile active. The database pool, schedulers and frontend
+// connections all belong to one profile, so the app restarts into it.
+#[tauri::command]
+pub fn switch_profile(app: AppHandle, id: String) -> Result<(), AppError> {
+    let mut list = load_profiles(&app)?;
+    list.find(&id)?;
+    if list.active == id {
+        return Ok(());
+    }
+
+    list.active = id;
+    save_profiles(&app, &list)?;
+    app.restart()
+}
+
+// Remove a profile and all of its data. The default and active profiles can't be deleted.
+#[tauri::command]
+pub fn delete_profile(app: AppHandle, id: String) -> Result<(), AppError> {
+    let mut list = load_profiles(&app)?;
+    list.find(&id)?;
+    if id == DEFAULT_PROFILE_ID {
+        return Err(AppError::Validation(
+            "The default profile can't be deleted".to_string(),
+        ));
+    }
+    if list.active == id {
+        return Err(AppError::Validation(
+            "Switch to another profile before deleting this one".to_string(),
+        ));
+    }
+
+    list.profiles.retain(|p| p.id != id);
+    save_profiles(&app, &list)?;
+
+    for dir in [app.path().app_config_dir(), app.path().app_data_dir()] {
+        let dir = dir
+            .map_err(|e| format!("Failed to resolve app dir: {}", e))?
+            .join(profile_subdir(&id));
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)
+                .map_err(|e| format!("Failed to delete profile data: {}", e))?;
+        }
+    }
+    Ok(())
+}
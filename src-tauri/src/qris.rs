@@ -0,0 +1,277 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use printpdf::image_crate::{DynamicImage, GrayImage, ImageOutputFormat, Luma};
+use qrcode::{Color, EcLevel, QrCode};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use tauri::State;
+
+use crate::db::Database;
+use crate::error::AppError;
+use crate::invoice_template::{load_invoice_template, InvoiceTemplate};
+use crate::invoices::{load_invoice, InvoiceData};
+use crate::log_warning;
+
+// QRIS is rupiah only
+const QRIS_CURRENCY: &str = "IDR";
+
+// EMVCo merchant-presented QR tags used here
+const TAG_FORMAT: &str = "00";
+const TAG_INITIATION: &str = "01";
+const TAG_CURRENCY: &str = "53";
+const TAG_AMOUNT: &str = "54";
+const TAG_COUNTRY: &str = "58";
+const TAG_MERCHANT_NAME: &str = "59";
+const TAG_CRC: &str = "63";
+// Point of initiation: 12 is for one payment of the amount in the code (static
+// codes, 11, can be paid any number of times with any amount)
+const DYNAMIC_QR: &str = "12";
+// ISO 4217 numeric code for IDR
+const RUPIAH_NUMERIC: &str = "360";
+const MAX_AMOUNT_LENGTH: usize = 13;
+
+// Blank modules scanners need around the code
+const QUIET_ZONE: usize = 4;
+// Pixels per module in PNG codes
+const PNG_MODULE_PX: u32 = 6;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvoiceQris {
+    pub payload: String,
+    // Amount written into the code; None when the customer types it in
+    pub amount: Option<f64>,
+    pub merchant_name: Option<String>,
+    // PNG as a data URL, for emails and the app
+    pub image: String,
+}
+
+// Helper: CRC-16/CCITT-FALSE as QRIS uses it, in uppercase hex
+fn crc16(data: &str) -> String {
+    let mut crc: u16 = 0xFFFF;
+    for byte in data.bytes() {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    format!("{:04X}", crc)
+}
+
+// Helper: Split a payload into its top-level (tag, value) fields
+fn parse_fields(payload: &str) -> Result<Vec<(String, String)>, AppError> {
+    if !payload.is_ascii() {
+        return Err(AppError::Validation(
+            "QRIS payload must be plain ASCII text".to_string(),
+        ));
+    }
+    let mut fields = Vec::new();
+    let mut rest = payload;
+    while !rest.is_empty() {
+        let length = rest
+            .get(2..4)
+            .and_then(|length| length.parse::<usize>().ok())
+            .ok_or("QRIS payload is malformed")?;
+        let value = rest.get(4..4 + length).ok_or("QRIS payload is cut short")?;
+        fields.push((rest[..2].to_string(), value.to_string()));
+        rest = &rest[4 + length..];
+    }
+    Ok(fields)
+}
+
+// Helper: Fields back into a payload, with a fresh CRC at the end
+fn build_payload(fields: &[(String, String)]) -> String {
+    let mut payload: String = fields
+        .iter()
+        .filter(|(tag, _)| tag != TAG_CRC)
+        .map(|(tag, value)| format!("{}{:02}{}", tag, value.len(), value))
+        .collect();
+    payload.push_str(TAG_CRC);
+    payload.push_str("04");
+    let crc = crc16(&payload);
+    payload.push_str(&crc);
+    payload
+}
+
+fn field<'a>(fields: &'a [(String, String)], tag: &str) -> Option<&'a str> {
+    fields
+        .iter()
+        .find(|(t, _)| t == tag)
+        .map(|(_, value)| value.as_str())
+}
+
+// Check a merchant QRIS payload (what the merchant's QRIS sticker encodes)
+// and return it trimmed. The CRC must match, so a typo is caught here rather
+// than by a customer whose app refuses the code.
+pub fn validate_payload(payload: &str) -> Result<String, AppError> {
+    let payload = payload.trim();
+    let fields = parse_fields(payload)?;
+    if fields
+        .first()
+        .map(|(tag, value)| (tag.as_str(), value.as_str()))
+        != Some((TAG_FORMAT, "01"))
+    {
+        return Err(AppError::Validation(
+            "Not a QRIS payload (it should start with 000201)".to_string(),
+        ));
+    }
+    match fields.last() {
+        Some((tag, crc)) if tag == TAG_CRC => {
+            if crc16(&payload[..payload.len() - 4]) != crc.to_uppercase() {
+                return Err(AppError::Validation(
+                    "QRIS payload checksum doesn't match; check it was copied whole".to_string(),
+                ));
+            }
+        }
+        _ => {
+            return Err(AppError::Validation(
+                "QRIS payload has no checksum".to_string(),
+            ))
+        }
+    }
+    if field(&fields, TAG_COUNTRY) != Some("ID")
+        || field(&fields, TAG_CURRENCY) != Some(RUPIAH_NUMERIC)
+    {
+        return Err(AppError::Validation(
+            "Not an Indonesian rupiah QRIS payload".to_string(),
+        ));
+    }
+    Ok(payload.to_string())
+}
+
+// The merchant name printed under the code
+pub fn merchant_name(payload: &str) -> Option<String> {
+    let fields = parse_fields(payload).ok()?;
+    field(&fields, TAG_MERCHANT_NAME).map(str::to_string)
+}
+
+// A one-off code for a fixed amount, made from the merchant's static payload:
+// marked dynamic, with the amount inserted in tag order and the CRC redone
+pub fn dynamic_payload(payload: &str, amount: f64) -> Result<String, AppError> {
+    let amount = format!("{}", amount.round() as i64);
+    if amount.len() > MAX_AMOUNT_LENGTH || amount.starts_with('-') {
+        return Err(AppError::Validation(format!(
+            "Amount {} can't be put in a QRIS code",
+            amount
+        )));
+    }
+    let mut fields: Vec<(String, String)> = parse_fields(payload)?
+        .into_iter()
+        .filter(|(tag, _)| tag != TAG_AMOUNT && tag != TAG_CRC)
+        .map(|(tag, value)| {
+            if tag == TAG_INITIATION {
+                (tag, DYNAMIC_QR.to_string())
+            } else {
+                (tag, value)
+            }
+        })
+        .collect();
+    if field(&fields, TAG_INITIATION).is_none() {
+        fields.insert(1, (TAG_INITIATION.to_string(), DYNAMIC_QR.to_string()));
+    }
+    let at = fields
+        .iter()
+        .position(|(tag, _)| tag.as_str() > TAG_AMOUNT)
+        .unwrap_or(fields.len());
+    fields.insert(at, (TAG_AMOUNT.to_string(), amount));
+    Ok(build_payload(&fields))
+}
+
+// The amount a payload asks for; None for static codes, where the customer
+// types it in
+pub fn payload_amount(payload: &str) -> Option<f64> {
+    let fields = parse_fields(payload).ok()?;
+    field(&fields, TAG_AMOUNT)?.parse().ok()
+}
+
+// The code to print on an invoice, if it can be paid by QRIS: unpaid rupiah
+// invoices only. A pending gateway QRIS charge for the balance comes first;
+// otherwise the merchant's own payload, with the balance in it if the layout
+// asks for dynamic codes.
+pub fn invoice_payload(template: &InvoiceTemplate, invoice: &InvoiceData) -> Option<String> {
+    if invoice.currency_code != QRIS_CURRENCY
+        || invoice.order.status == "cancelled"
+        || invoice.balance_due < 1.0
+    {
+        return None;
+    }
+    if let Some(payload) = &invoice.gateway_qris {
+        return Some(payload.clone());
+    }
+    let payload = template.qris_payload.as_deref()?;
+    if !template.qris_dynamic {
+        return Some(payload.to_string());
+    }
+    match dynamic_payload(payload, invoice.balance_due) {
+        Ok(dynamic) => Some(dynamic),
+        Err(e) => {
+            log_warning!("Failed to make a QRIS code for the invoice: {}", e);
+            Some(payload.to_string())
+        }
+    }
+}
+
+// Encode a payload as QR modules, row by row (true = dark). Returns the
+// width in modules, without the quiet zone.
+pub fn qr_modules(payload: &str) -> Result<(usize, Vec<bool>), AppError> {
+    let code = QrCode::with_error_correction_level(payload, EcLevel::M)
+        .map_err(|e| format!("Failed to encode QR code: {}", e))?;
+    let width = code.width();
+    let modules = code
+        .into_colors()
+        .into_iter()
+        .map(|color| color == Color::Dark)
+        .collect();
+    Ok((width, modules))
+}
+
+// A payload as a PNG data URL, quiet zone included
+pub fn qr_data_url(payload: &str) -> Result<String, AppError> {
+    let (width, modules) = qr_modules(payload)?;
+    let size = (width + 2 * QUIET_ZONE) as u32 * PNG_MODULE_PX;
+    let image = GrayImage::from_fn(size, size, |x, y| {
+        let column = (x / PNG_MODULE_PX) as usize;
+        let row = (y / PNG_MODULE_PX) as usize;
+        let inside = (QUIET_ZONE..QUIET_ZONE + width).contains(&column)
+            && (QUIET_ZONE..QUIET_ZONE + width).contains(&row);
+        if inside && modules[(row - QUIET_ZONE) * width + column - QUIET_ZONE] {
+            Luma([0])
+        } else {
+            Luma([255])
+        }
+    });
+    let mut bytes = Vec::new();
+    DynamicImage::ImageLuma8(image)
+        .write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png)
+        .map_err(|e| format!("Failed to render QR code: {}", e))?;
+    Ok(format!("data:image/png;base64,{}", STANDARD.encode(bytes)))
+}
+
+// The QRIS code for an order's invoice, for emails and on screen; None when
+// the invoice wouldn't carry one (see invoice_payload)
+#[tauri::command]
+pub async fn get_invoice_qris(
+    db: State<'_, Database>,
+    po_id: i64,
+) -> Result<Option<InvoiceQris>, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let invoice = load_invoice(&mut conn, po_id).await?;
+    let template = load_invoice_template(&mut conn).await?;
+    drop(conn);
+
+    let Some(payload) = invoice_payload(&template, &invoice) else {
+        return Ok(None);
+    };
+    Ok(Some(InvoiceQris {
+        amount: payload_amount(&payload),
+        merchant_name: merchant_name(&payload),
+        image: qr_data_url(&payload)?,
+        payload,
+    }))
+}
@@ -0,0 +1,424 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, Sqlite, SqliteConnection};
+use std::collections::HashMap;
+use tauri::State;
+
+use crate::audit;
+use crate::currency::{currency_decimals, default_currency, exchange_rate, normalize_currency};
+use crate::db::Database;
+use crate::domain_events::{publish, DomainEvent};
+use crate::error::AppError;
+use crate::models::{validate_contact, LineItemInput, PurchaseOrder, PurchaseOrderInput};
+use crate::orders::{fetch_order, insert_order};
+use crate::pricing::price_in_currency;
+use crate::totals::{calculate_totals, load_tax_settings, round_amount, TotalsInput, TotalsLine};
+
+// How long a quote holds its prices unless told otherwise
+const DEFAULT_VALID_DAYS: i64 = 14;
+const MAX_VALID_DAYS: i64 = 365;
+
+// Open quotes past valid_until read as expired
+const QUOTE_COLUMNS: &str = "id, customer_name, customer_email, currency_code, base_currency, \
+     exchange_rate, rate_date, notes, event_id, total_amount, valid_until, \
+     CASE WHEN status = 'open' AND valid_until <= CURRENT_TIMESTAMP THEN 'expired' \
+     ELSE status END AS status, preorder_id, accepted_at, created_at, updated_at";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum QuoteStatus {
+    Open,
+    // Converted into an order
+    Accepted,
+    Withdrawn,
+    // Open but past valid_until; never stored
+    Expired,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct QuoteItem {
+    pub id: i64,
+    pub quote_id: i64,
+    pub product_id: i64,
+    pub product_name: Option<String>,
+    pub quantity: i64,
+    // In the quote's currency, locked until the quote expires
+    pub unit_price: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Quote {
+    pub id: i64,
+    pub customer_name: String,
+    pub customer_email: String,
+    pub currency_code: String,
+    // The app's currency when quoted; exchange_rate is units of
+    // currency_code per unit of it
+    pub base_currency: String,
+    pub exchange_rate: f64,
+    pub rate_date: Option<String>,
+    pub notes: Option<String>,
+    pub event_id: Option<i64>,
+    pub total_amount: f64,
+    pub valid_until: String,
+    pub status: QuoteStatus,
+    pub preorder_id: Option<i64>,
+    pub accepted_at: Option<String>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+    #[sqlx(skip)]
+    pub items: Vec<QuoteItem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuoteInput {
+    pub customer_name: String,
+    pub customer_email: String,
+    // Defaults to the app's currency
+    pub currency_code: Option<String>,
+    pub notes: Option<String>,
+    pub event_id: Option<i64>,
+    // Days the prices hold; defaults to DEFAULT_VALID_DAYS
+    pub valid_days: Option<i64>,
+    // Items without a unit_price are priced in the quote's currency now
+    pub items: Vec<LineItemInput>,
+}
+
+// Helper: Load a quote with its items
+async fn load_quote(conn: &mut SqliteConnection, id: i64) -> Result<Quote, AppError> {
+    let mut quote = sqlx::query_as::<_, Quote>(&format!(
+        "SELECT {} FROM quotes WHERE id = ?",
+        QUOTE_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load quote: {}", e))?
+    .ok_or_else(|| AppError::NotFound(format!("Quote {} not found", id)))?;
+
+    quote.items = load_items(&mut *conn, &[id])
+        .await?
+        .remove(&id)
+        .unwrap_or_default();
+    Ok(quote)
+}
+
+// Helper: Quote items for a set of quotes, grouped by quote ID
+async fn load_items<'e, E>(
+    executor: E,
+    ids: &[i64],
+) -> Result<HashMap<i64, Vec<QuoteItem>>, AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let mut grouped: HashMap<i64, Vec<QuoteItem>> = HashMap::new();
+    if ids.is_empty() {
+        return Ok(grouped);
+    }
+
+    let placeholders = vec!["?"; ids.len()].join(", ");
+    let sql = format!(
+        "SELECT qi.id, qi.quote_id, qi.product_id, p.name AS product_name, \
+         qi.quantity, qi.unit_price FROM quote_items qi \
+         LEFT JOIN products p ON p.id = qi.product_id \
+         WHERE qi.quote_id IN ({}) ORDER BY qi.id",
+        placeholders
+    );
+    let mut query = sqlx::query_as::<_, QuoteItem>(&sql);
+    for id in ids {
+        query = query.bind(id);
+    }
+
+    for item in query
+        .fetch_all(executor)
+        .await
+        .map_err(|e| format!("Failed to load quote items: {}", e))?
+    {
+        grouped.entry(item.quote_id).or_default().push(item);
+    }
+    Ok(grouped)
+}
+
+// Quote a customer prices in their currency, held for `valid_days`. Prices
+// are fixed now, from per-currency prices or today's cached exchange rate,
+// and don't move with the rate afterwards. Stock isn't reserved until the
+// quote becomes an order.
+#[tauri::command]
+pub async fn create_quote(db: State<'_, Database>, quote: QuoteInput) -> Result<Quote, AppError> {
+    validate_contact(&quote.customer_name, &quote.customer_email)?;
+    let valid_days = quote.valid_days.unwrap_or(DEFAULT_VALID_DAYS);
+    if !(1..=MAX_VALID_DAYS).contains(&valid_days) {
+        return Err(AppError::Validation(format!(
+            "A quote can be valid for 1 to {} days",
+            MAX_VALID_DAYS
+        )));
+    }
+    if quote.items.is_empty() {
+        return Err(AppError::Validation(
+            "A quote needs at least one item".to_string(),
+        ));
+    }
+    for item in &quote.items {
+        if item.quantity <= 0 {
+            return Err(AppError::Validation(format!(
+                "Quantity for product {} must be positive",
+                item.product_id
+            )));
+        }
+        if let Some(price) = item.unit_price {
+            if !price.is_finite() || price < 0.0 {
+                return Err(AppError::Validation(format!(
+                    "Invalid unit price: {}",
+                    price
+                )));
+            }
+        }
+    }
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let base_currency = default_currency(&mut tx).await;
+    let currency_code = match &quote.currency_code {
+        Some(code) => normalize_currency(code)?,
+        None => base_currency.clone(),
+    };
+    let (rate, rate_date) = exchange_rate(&mut tx, &base_currency, &currency_code).await?;
+
+    let mut settings = load_tax_settings(&mut tx).await?;
+    settings.decimal_places = currency_decimals(&currency_code);
+
+    // Converted prices are rounded so the locked price is the one shown
+    let mut prices = Vec::with_capacity(quote.items.len());
+    for item in &quote.items {
+        let price = match item.unit_price {
+            Some(price) => price,
+            None => round_amount(
+                price_in_currency(&mut tx, item.product_id, None, &currency_code).await?,
+                settings.decimal_places,
+                settings.rounding_mode,
+            ),
+        };
+        prices.push(price);
+    }
+    let totals = calculate_totals(
+        &TotalsInput {
+            items: quote
+                .items
+                .iter()
+                .zip(&prices)
+                .map(|(item, price)| TotalsLine {
+                    description: None,
+                    quantity: item.quantity as f64,
+                    unit_price: *price,
+                    discount: None,
+                    tax_rate: None,
+                })
+                .collect(),
+            order_discount: None,
+            settings: None,
+        },
+        &settings,
+    )?;
+
+    let result = sqlx::query(
+        "INSERT INTO quotes (customer_name, customer_email, currency_code, base_currency, \
+         exchange_rate, rate_date, notes, event_id, total_amount, valid_until) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, datetime('now', ?))",
+    )
+    .bind(quote.customer_name.trim())
+    .bind(quote.customer_email.trim())
+    .bind(&currency_code)
+    .bind(&base_currency)
+    .bind(rate)
+    .bind(&rate_date)
+    .bind(&quote.notes)
+    .bind(quote.event_id)
+    .bind(totals.total)
+    .bind(format!("+{} days", valid_days))
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to create quote: {}", e))?;
+
+    let id = result.last_insert_rowid();
+    for (item, price) in quote.items.iter().zip(&prices) {
+        sqlx::query(
+            "INSERT INTO quote_items (quote_id, product_id, quantity, unit_price) VALUES (?, ?, ?, ?)",
+        )
+        .bind(id)
+        .bind(item.product_id)
+        .bind(item.quantity)
+        .bind(price)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to save quote item: {}", e))?;
+    }
+
+    let created = load_quote(&mut tx, id).await?;
+    audit::record(&mut *tx, "quote", id, "create", None, Some(&created)).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save quote: {}", e))?;
+
+    Ok(created)
+}
+
+// Quotes, newest first; expired is a status filter like the others
+#[tauri::command]
+pub async fn list_quotes(
+    db: State<'_, Database>,
+    status: Option<QuoteStatus>,
+) -> Result<Vec<Quote>, AppError> {
+    let mut quotes = sqlx::query_as::<_, Quote>(&format!(
+        "SELECT * FROM (SELECT {} FROM quotes) WHERE ? IS NULL OR status = ? \
+         ORDER BY created_at DESC, id DESC",
+        QUOTE_COLUMNS
+    ))
+    .bind(status)
+    .bind(status)
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| format!("Failed to list quotes: {}", e))?;
+
+    let ids: Vec<i64> = quotes.iter().map(|q| q.id).collect();
+    let mut items = load_items(&db.pool, &ids).await?;
+    for quote in &mut quotes {
+        quote.items = items.remove(&quote.id).unwrap_or_default();
+    }
+    Ok(quotes)
+}
+
+#[tauri::command]
+pub async fn get_quote(db: State<'_, Database>, id: i64) -> Result<Quote, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    load_quote(&mut conn, id).await
+}
+
+// Helper: Refuse anything but an open quote, saying why
+fn ensure_open(quote: &Quote) -> Result<(), AppError> {
+    match quote.status {
+        QuoteStatus::Open => Ok(()),
+        QuoteStatus::Expired => Err(AppError::Validation(format!(
+            "Quote {} expired on {}",
+            quote.id, quote.valid_until
+        ))),
+        QuoteStatus::Accepted => Err(AppError::Conflict(format!(
+            "Quote {} has already been converted into order {}",
+            quote.id,
+            quote.preorder_id.unwrap_or_default()
+        ))),
+        QuoteStatus::Withdrawn => Err(AppError::Validation(format!(
+            "Quote {} has been withdrawn",
+            quote.id
+        ))),
+    }
+}
+
+// Turn an open quote into a draft order at the quoted prices and currency,
+// whatever the rates have done since
+#[tauri::command]
+pub async fn convert_quote_to_order(
+    db: State<'_, Database>,
+    id: i64,
+) -> Result<PurchaseOrder, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_quote(&mut tx, id).await?;
+    ensure_open(&before)?;
+
+    let order = PurchaseOrderInput {
+        customer_name: before.customer_name.clone(),
+        customer_email: before.customer_email.clone(),
+        currency_code: Some(before.currency_code.clone()),
+        notes: before.notes.clone(),
+        event_id: before.event_id,
+        items: before
+            .items
+            .iter()
+            .map(|item| LineItemInput {
+                product_id: item.product_id,
+                quantity: item.quantity,
+                unit_price: Some(item.unit_price),
+            })
+            .collect(),
+    };
+    let order_id = insert_order(&mut tx, &order).await?;
+
+    // Status guard so a concurrent convert can't produce a second order
+    let result = sqlx::query(
+        "UPDATE quotes SET status = 'accepted', preorder_id = ?, accepted_at = CURRENT_TIMESTAMP, \
+         updated_at = CURRENT_TIMESTAMP WHERE id = ? AND status = 'open'",
+    )
+    .bind(order_id)
+    .bind(id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to update quote: {}", e))?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::Validation(format!(
+            "Quote {} changed while converting; reload and try again",
+            id
+        )));
+    }
+
+    publish(&mut tx, &DomainEvent::OrderCreated { order_id }).await?;
+    let created = fetch_order(&mut tx, order_id).await?;
+    let after = load_quote(&mut tx, id).await?;
+    audit::record(&mut *tx, "quote", id, "accept", Some(&before), Some(&after)).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save order: {}", e))?;
+
+    Ok(created)
+}
+
+// Take back an open quote before the customer accepts it
+#[tauri::command]
+pub async fn withdraw_quote(db: State<'_, Database>, id: i64) -> Result<Quote, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_quote(&mut tx, id).await?;
+    ensure_open(&before)?;
+
+    sqlx::query(
+        "UPDATE quotes SET status = 'withdrawn', updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+    )
+    .bind(id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to update quote: {}", e))?;
+
+    let after = load_quote(&mut tx, id).await?;
+    audit::record(
+        &mut *tx,
+        "quote",
+        id,
+        "withdraw",
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save quote: {}", e))?;
+
+    Ok(after)
+}
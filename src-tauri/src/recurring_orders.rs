@@ -0,0 +1,677 @@
+use chrono::{Duration, Months, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, Sqlite, SqliteConnection, SqlitePool};
+use std::collections::HashMap;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::audit;
+use crate::automation::{self, Poller};
+use crate::currency::normalize_currency;
+use crate::db::Database;
+use crate::domain_events::{check_email_sent, publish, publish_now, DomainEvent};
+use crate::error::AppError;
+use crate::i18n::{load_locale, Locale};
+use crate::invoices::product_name;
+use crate::log_warning;
+use crate::models::{validate_contact, LineItemInput, PurchaseOrder, PurchaseOrderInput};
+use crate::money::{money_format, MoneyFormat};
+use crate::orders::{insert_order, load_order};
+use crate::settings::load_smtp_settings;
+use crate::timeline::EmailChannel;
+
+// SQLite's datetime() / CURRENT_TIMESTAMP format (UTC)
+const SQLITE_DATETIME: &str = "%Y-%m-%d %H:%M:%S";
+
+const RECURRING_COLUMNS: &str =
+    "id, customer_name, customer_email, currency_code, notes, event_id, \
+     frequency, interval_count, next_run_at, ends_at, send_confirmation, status, last_run_at, \
+     created_at, updated_at";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum Frequency {
+    Weekly,
+    Monthly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum RecurringStatus {
+    Active,
+    Paused,
+    Ended,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RecurringOrderItem {
+    pub id: i64,
+    pub recurring_order_id: i64,
+    pub product_id: i64,
+    pub product_name: Option<String>,
+    pub quantity: i64,
+    // None uses the product's price at the time each order is generated
+    pub unit_price: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RecurringOrder {
+    pub id: i64,
+    pub customer_name: String,
+    pub customer_email: String,
+    pub currency_code: Option<String>,
+    pub notes: Option<String>,
+    pub event_id: Option<i64>,
+    pub frequency: Frequency,
+    pub interval_count: i64,
+    pub next_run_at: String,
+    pub ends_at: Option<String>,
+    // Email the customer the new order's confirmation code each cycle
+    pub send_confirmation: bool,
+    pub status: RecurringStatus,
+    pub last_run_at: Option<String>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+    #[sqlx(skip)]
+    pub items: Vec<RecurringOrderItem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecurringOrderInput {
+    pub customer_name: String,
+    pub customer_email: String,
+    pub currency_code: Option<String>,
+    pub notes: Option<String>,
+    pub event_id: Option<i64>,
+    pub frequency: Frequency,
+    // Every N weeks/months; defaults to 1
+    pub interval_count: Option<i64>,
+    // First order date; defaults to now
+    pub starts_at: Option<String>,
+    pub ends_at: Option<String>,
+    pub send_confirmation: Option<bool>,
+    pub items: Vec<LineItemInput>,
+}
+
+// Emitted as the "recurring-order-created" event for each generated order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecurringOrderCreated {
+    pub recurring_order_id: i64,
+    pub order_id: i64,
+    pub next_run_at: String,
+    pub status: RecurringStatus,
+}
+
+// Helper: Load a template with its items
+async fn load_recurring_order(
+    conn: &mut SqliteConnection,
+    id: i64,
+) -> Result<RecurringOrder, AppError> {
+    let mut recurring = sqlx::query_as::<_, RecurringOrder>(&format!(
+        "SELECT {} FROM recurring_orders WHERE id = ?",
+        RECURRING_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load recurring order: {}", e))?
+    .ok_or_else(|| AppError::NotFound(format!("Recurring order {} not found", id)))?;
+
+    recurring.items = load_items(&mut *conn, &[id])
+        .await?
+        .remove(&id)
+        .unwrap_or_default();
+    Ok(recurring)
+}
+
+// Helper: Template items for a set of templates, grouped by template ID
+async fn load_items<'e, E>(
+    executor: E,
+    ids: &[i64],
+) -> Result<HashMap<i64, Vec<RecurringOrderItem>>, AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let mut grouped: HashMap<i64, Vec<RecurringOrderItem>> = HashMap::new();
+    if ids.is_empty() {
+        return Ok(grouped);
+    }
+
+    let placeholders = vec!["?"; ids.len()].join(", ");
+    let sql = format!(
+        "SELECT ri.id, ri.recurring_order_id, ri.product_id, p.name AS product_name, \
+         ri.quantity, ri.unit_price FROM recurring_order_items ri \
+         LEFT JOIN products p ON p.id = ri.product_id \
+         WHERE ri.recurring_order_id IN ({}) ORDER BY ri.id",
+        placeholders
+    );
+    let mut query = sqlx::query_as::<_, RecurringOrderItem>(&sql);
+    for id in ids {
+        query = query.bind(id);
+    }
+
+    for item in query
+        .fetch_all(executor)
+        .await
+        .map_err(|e| format!("Failed to load recurring order items: {}", e))?
+    {
+        grouped
+            .entry(item.recurring_order_id)
+            .or_default()
+            .push(item);
+    }
+    Ok(grouped)
+}
+
+// Helper: Normalize a user-supplied timestamp to SQLite's format
+async fn normalize_timestamp(conn: &mut SqliteConnection, value: &str) -> Result<String, AppError> {
+    sqlx::query_scalar::<_, Option<String>>("SELECT datetime(?)")
+        .bind(value.trim())
+        .fetch_one(conn)
+        .await
+        .map_err(|e| format!("Failed to parse date: {}", e))?
+        .ok_or_else(|| AppError::Validation(format!("Invalid date: {}", value)))
+}
+
+// Helper: The run after `from`
+fn advance(
+    from: NaiveDateTime,
+    frequency: Frequency,
+    interval: i64,
+) -> Result<NaiveDateTime, AppError> {
+    let interval = interval.max(1);
+    let next = match frequency {
+        Frequency::Weekly => from.checked_add_signed(Duration::weeks(interval)),
+        Frequency::Monthly => u32::try_from(interval)
+            .ok()
+            .and_then(|months| from.checked_add_months(Months::new(months))),
+    };
+    next.ok_or_else(|| AppError::Validation("Recurring order schedule is out of range".to_string()))
+}
+
+// Helper: First run after now. Cycles missed while the app was closed are
+// skipped rather than generating a backlog of orders.
+fn next_run_after_now(
+    last: &str,
+    frequency: Frequency,
+    interval: i64,
+) -> Result<NaiveDateTime, AppError> {
+    let now = Utc::now().naive_utc();
+    let mut next = NaiveDateTime::parse_from_str(last, SQLITE_DATETIME)
+        .map_err(|e| format!("Invalid schedule date {}: {}", last, e))?;
+    while next <= now {
+        next = advance(next, frequency, interval)?;
+    }
+    Ok(next)
+}
+
+// Helper: Status after scheduling `next`; templates past their end date stop
+fn status_for_next(next: &NaiveDateTime, ends_at: Option<&str>) -> RecurringStatus {
+    match ends_at.and_then(|end| NaiveDateTime::parse_from_str(end, SQLITE_DATETIME).ok()) {
+        Some(end) if *next > end => RecurringStatus::Ended,
+        _ => RecurringStatus::Active,
+    }
+}
+
+// Helper: Generate the order for a due template and schedule its next run
+async fn materialize(
+    conn: &mut SqliteConnection,
+    id: i64,
+) -> Result<RecurringOrderCreated, AppError> {
+    let template = load_recurring_order(&mut *conn, id).await?;
+    if template.status != RecurringStatus::Active {
+        return Err(AppError::Validation(format!(
+            "Recurring order {} is not active",
+            id
+        )));
+    }
+
+    let order = PurchaseOrderInput {
+        customer_name: template.customer_name.clone(),
+        customer_email: template.customer_email.clone(),
+        currency_code: template.currency_code.clone(),
+        notes: template.notes.clone(),
+        event_id: template.event_id,
+        items: template
+            .items
+            .iter()
+            .map(|item| LineItemInput {
+                product_id: item.product_id,
+                quantity: item.quantity,
+                unit_price: item.unit_price,
+            })
+            .collect(),
+    };
+    let order_id = insert_order(&mut *conn, &order).await?;
+
+    sqlx::query("UPDATE preorders SET recurring_order_id = ? WHERE id = ?")
+        .bind(id)
+        .bind(order_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to link recurring order: {}", e))?;
+
+    publish(&mut *conn, &DomainEvent::OrderCreated { order_id }).await?;
+
+    let next = next_run_after_now(
+        &template.next_run_at,
+        template.frequency,
+        template.interval_count,
+    )?;
+    let status = status_for_next(&next, template.ends_at.as_deref());
+    let next_run_at = next.format(SQLITE_DATETIME).to_string();
+
+    sqlx::query(
+        "UPDATE recurring_orders SET next_run_at = ?, status = ?, last_run_at = CURRENT_TIMESTAMP, \
+         updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+    )
+    .bind(&next_run_at)
+    .bind(status)
+    .bind(id)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to schedule recurring order: {}", e))?;
+
+    Ok(RecurringOrderCreated {
+        recurring_order_id: id,
+        order_id,
+        next_run_at,
+        status,
+    })
+}
+
+// Helper: HTML escaping for values placed in email bodies and printed
+// templates, attribute values included
+pub fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+// Helper: Email body asking the customer to confirm this cycle's order
+fn confirmation_email_html(locale: Locale, money: &MoneyFormat, order: &PurchaseOrder) -> String {
+    let rows: String = order
+        .items
+        .iter()
+        .map(|item| {
+            format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape_html(&product_name(locale, item)),
+                item.quantity,
+                escape_html(&money.format(item.unit_price))
+            )
+        })
+        .collect();
+
+    let label = |key: &str| escape_html(&locale.text(key));
+    format!(
+        "<html><body>\
+         <p>{}</p>\
+         <p>{}</p>\
+         <p><strong>{}</strong> {}</p>\
+         <table><tr><th>{}</th><th>{}</th><th>{}</th></tr>{}</table>\
+         <p><strong>{}</strong> {}</p>\
+         </body></html>",
+        escape_html(&locale.format("email.greeting", &[("name", &order.customer_name)])),
+        label("email.confirm.intro"),
+        label("email.confirm.code"),
+        escape_html(&order.confirmation_code),
+        label("email.item"),
+        label("email.qty"),
+        label("email.price"),
+        rows,
+        label("email.total"),
+        escape_html(&money.format(order.total_amount))
+    )
+}
+
+// Helper: Email the customer their new order's confirmation code via SMTP
+async fn send_confirmation(pool: &SqlitePool, order_id: i64) -> Result<(), AppError> {
+    let settings = load_smtp_settings(pool)
+        .await?
+        .ok_or_else(|| AppError::Validation("SMTP is not configured".to_string()))?;
+    let order = load_order(pool, order_id).await?;
+
+    let locale = load_locale(pool).await;
+    let money = {
+        let mut conn = pool
+            .acquire()
+            .await
+            .map_err(|e| format!("Failed to open database connection: {}", e))?;
+        money_format(&mut conn, order.currency_code.as_deref()).await?
+    };
+    let subject = locale.format(
+        "email.confirm.subject",
+        &[("code", &order.confirmation_code)],
+    );
+    let html_body = confirmation_email_html(locale, &money, &order);
+    let (to_email, log_subject) = (order.customer_email.clone(), subject.clone());
+    let sent = tauri::async_runtime::spawn_blocking(move || {
+        crate::send_smtp_email(
+            settings,
+            order.customer_email,
+            order.customer_name,
+            subject,
+            html_body,
+        )
+    })
+    .await
+    .map_err(|e| format!("Failed to send email: {}", e))?;
+    check_email_sent(pool, &to_email, &log_subject, sent).await?;
+
+    let email_sent = DomainEvent::EmailSent {
+        order_id: Some(order_id),
+        to_email: &to_email,
+        subject: &log_subject,
+        channel: EmailChannel::Smtp,
+    };
+    publish_now(pool, &email_sent).await
+}
+
+// Generate orders for every template that's due. Each template runs in its
+// own transaction so one bad template doesn't hold up the others. Templates
+// that email a confirmation stay due through quiet hours and once the daily
+// send limit is reached, so the order and its email go out together.
+pub async fn run_due_recurring_orders(
+    app: AppHandle,
+) -> Result<Vec<RecurringOrderCreated>, AppError> {
+    let pool = app.state::<Database>().pool.clone();
+
+    let due = sqlx::query_as::<_, (i64, bool)>(
+        "SELECT id, send_confirmation FROM recurring_orders \
+         WHERE status = 'active' AND next_run_at <= CURRENT_TIMESTAMP ORDER BY next_run_at",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("Failed to load due recurring orders: {}", e))?;
+
+    let mut allowance = automation::send_allowance(&pool).await?;
+    let mut created = Vec::new();
+    for (id, email) in due {
+        if email && allowance == Some(0) {
+            continue;
+        }
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        let generated = match materialize(&mut tx, id).await {
+            Ok(generated) => generated,
+            Err(e) => {
+                log_warning!("Failed to generate recurring order {}: {}", id, e);
+                continue;
+            }
+        };
+
+        tx.commit()
+            .await
+            .map_err(|e| format!("Failed to save recurring order: {}", e))?;
+
+        if let Err(e) = app.emit("recurring-order-created", &generated) {
+            log_warning!("Failed to emit recurring-order-created event: {}", e);
+        }
+        if email {
+            allowance = allowance.map(|left| left - 1);
+            automation::record_sends(&pool, 1).await?;
+            if let Err(e) = send_confirmation(&pool, generated.order_id).await {
+                log_warning!(
+                    "Failed to email confirmation for order {}: {}",
+                    generated.order_id,
+                    e
+                );
+            }
+        }
+        created.push(generated);
+    }
+    Ok(created)
+}
+
+// Start the background loop that materializes due recurring orders
+pub fn start_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<Database>().pool.clone();
+        loop {
+            if let Err(e) = run_due_recurring_orders(app.clone()).await {
+                log_warning!("Recurring order scheduler failed: {}", e);
+            }
+            automation::wait_for_next_poll(&pool, Poller::Scheduler).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn create_recurring_order(
+    db: State<'_, Database>,
+    recurring: RecurringOrderInput,
+) -> Result<RecurringOrder, AppError> {
+    validate_contact(&recurring.customer_name, &recurring.customer_email)?;
+    let interval_count = recurring.interval_count.unwrap_or(1);
+    if interval_count < 1 {
+        return Err(AppError::Validation(format!(
+            "Invalid interval: {}",
+            interval_count
+        )));
+    }
+    if recurring.items.is_empty() {
+        return Err(AppError::Validation(
+            "A recurring order needs at least one item".to_string(),
+        ));
+    }
+    for item in &recurring.items {
+        if item.quantity <= 0 {
+            return Err(AppError::Validation(format!(
+                "Quantity for product {} must be positive",
+                item.product_id
+            )));
+        }
+        if let Some(price) = item.unit_price {
+            if !price.is_finite() || price < 0.0 {
+                return Err(AppError::Validation(format!(
+                    "Invalid unit price: {}",
+                    price
+                )));
+            }
+        }
+    }
+    let currency_code = recurring
+        .currency_code
+        .as_deref()
+        .map(normalize_currency)
+        .transpose()?;
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let next_run_at = match &recurring.starts_at {
+        Some(starts_at) => normalize_timestamp(&mut tx, starts_at).await?,
+        None => Utc::now().naive_utc().format(SQLITE_DATETIME).to_string(),
+    };
+    let ends_at = match &recurring.ends_at {
+        Some(ends_at) => Some(normalize_timestamp(&mut tx, ends_at).await?),
+        None => None,
+    };
+    if matches!(&ends_at, Some(end) if *end < next_run_at) {
+        return Err(AppError::Validation(
+            "A recurring order can't end before it starts".to_string(),
+        ));
+    }
+
+    let result = sqlx::query(
+        "INSERT INTO recurring_orders (customer_name, customer_email, currency_code, notes, event_id, \
+         frequency, interval_count, next_run_at, ends_at, send_confirmation) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(recurring.customer_name.trim())
+    .bind(recurring.customer_email.trim())
+    .bind(currency_code)
+    .bind(&recurring.notes)
+    .bind(recurring.event_id)
+    .bind(recurring.frequency)
+    .bind(interval_count)
+    .bind(&next_run_at)
+    .bind(&ends_at)
+    .bind(recurring.send_confirmation.unwrap_or(false))
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to create recurring order: {}", e))?;
+
+    let id = result.last_insert_rowid();
+    for item in &recurring.items {
+        sqlx::query(
+            "INSERT INTO recurring_order_items (recurring_order_id, product_id, quantity, unit_price) \
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(id)
+        .bind(item.product_id)
+        .bind(item.quantity)
+        .bind(item.unit_price)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to save recurring order item: {}", e))?;
+    }
+
+    let created = load_recurring_order(&mut tx, id).await?;
+    audit::record(
+        &mut *tx,
+        "recurring_order",
+        id,
+        "create",
+        None,
+        Some(&created),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save recurring order: {}", e))?;
+
+    Ok(created)
+}
+
+// Recurring order templates, soonest next run first
+#[tauri::command]
+pub async fn list_recurring_orders(
+    db: State<'_, Database>,
+    status: Option<RecurringStatus>,
+) -> Result<Vec<RecurringOrder>, AppError> {
+    let mut recurring = sqlx::query_as::<_, RecurringOrder>(&format!(
+        "SELECT {} FROM recurring_orders WHERE ? IS NULL OR status = ? ORDER BY next_run_at",
+        RECURRING_COLUMNS
+    ))
+    .bind(status)
+    .bind(status)
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| format!("Failed to list recurring orders: {}", e))?;
+
+    let ids: Vec<i64> = recurring.iter().map(|r| r.id).collect();
+    let mut items = load_items(&db.pool, &ids).await?;
+    for template in &mut recurring {
+        template.items = items.remove(&template.id).unwrap_or_default();
+    }
+    Ok(recurring)
+}
+
+// Helper: Move a template between active and paused, logging the change
+async fn set_recurring_status(
+    db: &Database,
+    id: i64,
+    from: RecurringStatus,
+    to: RecurringStatus,
+    action: &str,
+) -> Result<RecurringOrder, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_recurring_order(&mut tx, id).await?;
+    if before.status != from {
+        return Err(AppError::Validation(format!(
+            "Cannot {} a recurring order that is {}",
+            action,
+            format!("{:?}", before.status).to_lowercase()
+        )));
+    }
+
+    // A resumed template picks up at its next future date instead of firing
+    // immediately for the cycles it missed while paused
+    let (to, next_run_at) = match to {
+        RecurringStatus::Active => {
+            let next =
+                next_run_after_now(&before.next_run_at, before.frequency, before.interval_count)?;
+            (
+                status_for_next(&next, before.ends_at.as_deref()),
+                next.format(SQLITE_DATETIME).to_string(),
+            )
+        }
+        _ => (to, before.next_run_at.clone()),
+    };
+
+    sqlx::query(
+        "UPDATE recurring_orders SET status = ?, next_run_at = ?, updated_at = CURRENT_TIMESTAMP \
+         WHERE id = ?",
+    )
+    .bind(to)
+    .bind(&next_run_at)
+    .bind(id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to {} recurring order: {}", action, e))?;
+
+    let after = load_recurring_order(&mut tx, id).await?;
+    audit::record(
+        &mut *tx,
+        "recurring_order",
+        id,
+        action,
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save recurring order: {}", e))?;
+
+    Ok(after)
+}
+
+#[tauri::command]
+pub async fn pause_recurring_order(
+    db: State<'_, Database>,
+    id: i64,
+) -> Result<RecurringOrder, AppError> {
+    set_recurring_status(
+        &db,
+        id,
+        RecurringStatus::Active,
+        RecurringStatus::Paused,
+        "pause",
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn resume_recurring_order(
+    db: State<'_, Database>,
+    id: i64,
+) -> Result<RecurringOrder, AppError> {
+    set_recurring_status(
+        &db,
+        id,
+        RecurringStatus::Paused,
+        RecurringStatus::Active,
+        "resume",
+    )
+    .await
+}
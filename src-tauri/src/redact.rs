@@ -0,0 +1,147 @@
+// Shown in place of anything that looks like a credential
+const REDACTED: &str = "[REDACTED]";
+
+// Authorization header schemes whose token follows a space
+const AUTH_SCHEMES: &[&str] = &["bearer ", "basic "];
+
+// Field names whose value is a credential, in JSON ("key": "value"), query
+// strings and form bodies (key=value) or plain text (key: value). Matched as
+// whole words, case-insensitively.
+const SECRET_KEYS: &[&str] = &[
+    "access_token",
+    "refresh_token",
+    "id_token",
+    "token",
+    "auth_token",
+    "bot_token",
+    "client_secret",
+    "secret",
+    "signing_secret",
+    "password",
+    "passphrase",
+    "api_key",
+    "apikey",
+    "key",
+    "code_verifier",
+];
+
+fn is_word_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'-'
+}
+
+fn is_token_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"-._~+/=".contains(&b)
+}
+
+// Helper: End of the run of bytes from `from` that satisfy `keep`
+fn scan(bytes: &[u8], from: usize, keep: impl Fn(u8) -> bool) -> usize {
+    bytes[from.min(bytes.len())..]
+        .iter()
+        .position(|b| !keep(*b))
+        .map_or(bytes.len(), |n| from + n)
+}
+
+// Helper: Byte ranges of `text` holding credentials. Every range starts and
+// ends next to an ASCII byte, so slicing on them is safe.
+fn secret_ranges(text: &str) -> Vec<(usize, usize)> {
+    let lower = text.to_ascii_lowercase();
+    let bytes = lower.as_bytes();
+    let mut ranges = Vec::new();
+
+    // Authorization: Bearer ya29...
+    for scheme in AUTH_SCHEMES {
+        for (start, _) in lower.match_indices(scheme) {
+            let from = start + scheme.len();
+            ranges.push((from, scan(bytes, from, is_token_byte)));
+        }
+    }
+
+    // "refresh_token": "1//0g...", password=hunter2, client_secret: abc
+    for key in SECRET_KEYS {
+        for (start, _) in lower.match_indices(key) {
+            let mut i = start + key.len();
+            if (start > 0 && is_word_byte(bytes[start - 1]))
+                || bytes.get(i).is_some_and(|b| is_word_byte(*b))
+            {
+                continue;
+            }
+            if matches!(bytes.get(i), Some(b'"' | b'\'')) {
+                i += 1;
+            }
+            i = scan(bytes, i, |b| b == b' ');
+            if !matches!(bytes.get(i), Some(b'=' | b':')) {
+                continue;
+            }
+            i = scan(bytes, i + 1, |b| b == b' ');
+            let end = match bytes.get(i) {
+                Some(&quote @ (b'"' | b'\'')) => {
+                    i += 1;
+                    scan(bytes, i, |b| b != quote)
+                }
+                _ => scan(bytes, i, |b| {
+                    !b.is_ascii_whitespace() && !b"&,;)}\"'".contains(&b)
+                }),
+            };
+            ranges.push((i, end));
+        }
+    }
+
+    // Telegram puts the bot token in the path: /bot123456:ABC.../sendMessage
+    for (start, _) in lower.match_indices("/bot") {
+        let from = start + "/bot".len();
+        let end = scan(bytes, from, |b| b != b'/' && !b.is_ascii_whitespace());
+        if bytes.get(from).is_some_and(u8::is_ascii_digit) && lower[from..end].contains(':') {
+            ranges.push((from, end));
+        }
+    }
+
+    // user:password@ in a URL
+    for (start, _) in lower.match_indices("://") {
+        let from = start + "://".len();
+        let end = scan(bytes, from, |b| {
+            b != b'/' && b != b'@' && !b.is_ascii_whitespace()
+        });
+        if bytes.get(end) == Some(&b'@') && lower[from..end].contains(':') {
+            ranges.push((from, end));
+        }
+    }
+
+    ranges.retain(|(from, end)| end > from);
+    ranges.sort_unstable();
+    ranges
+}
+
+// Text with bearer tokens, passwords, refresh tokens and other credentials
+// replaced by [REDACTED]. Applied to API and SMTP errors before they reach
+// the frontend, and to every log line.
+pub fn redact(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut copied = 0;
+    for (from, end) in secret_ranges(text) {
+        if from < copied {
+            copied = copied.max(end);
+            continue;
+        }
+        out.push_str(&text[copied..from]);
+        out.push_str(REDACTED);
+        copied = end;
+    }
+    out.push_str(&text[copied..]);
+    out
+}
+
+// Log a line (to the log file and stdout, see logging.rs) with credentials redacted
+#[macro_export]
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        ::tracing::info!("{}", $crate::redact::redact(&format!($($arg)*)))
+    };
+}
+
+// Log a warning with credentials redacted
+#[macro_export]
+macro_rules! log_warning {
+    ($($arg:tt)*) => {
+        ::tracing::warn!("{}", $crate::redact::redact(&format!($($arg)*)))
+    };
+}
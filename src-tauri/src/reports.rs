@@ -0,0 +1,732 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteConnection;
+use std::path::PathBuf;
+use tauri::State;
+
+use crate::csv_export::{self, CsvExportInfo};
+use crate::currency::{default_currency, order_currency_factors};
+use crate::dashboard::SOLD_STATUSES;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::money::format_amount;
+use crate::pdf::{
+    safe_file_name, save_pdf, text_width, PdfWriter, MARGIN, MUTED_COLOR, PAGE_WIDTH, RULE_COLOR,
+    TEXT_COLOR,
+};
+
+// Aging buckets of unpaid_aging, by days since the order was invoiced (or
+// created, if it wasn't)
+const AGING_BUCKETS: [(i64, &str); 3] = [(30, "0-30 days"), (60, "31-60 days"), (90, "61-90 days")];
+const AGING_OVERDUE: &str = "Over 90 days";
+
+const ROW_HEIGHT: f32 = 5.5;
+
+// The predefined reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportId {
+    RevenueByPeriod,
+    RevenueByProduct,
+    RevenueByCustomer,
+    // Balances still owed, by how long they've been outstanding
+    UnpaidAging,
+    // Revenue less supplier costs and expenses, per campaign (event)
+    CampaignProfit,
+    // ... per Google Form the orders were imported from
+    FormProfit,
+}
+
+impl ReportId {
+    fn title(self) -> &'static str {
+        match self {
+            ReportId::RevenueByPeriod => "Revenue by period",
+            ReportId::RevenueByProduct => "Revenue by product",
+            ReportId::RevenueByCustomer => "Revenue by customer",
+            ReportId::UnpaidAging => "Unpaid balance aging",
+            ReportId::CampaignProfit => "Profit by campaign",
+            ReportId::FormProfit => "Profit by form",
+        }
+    }
+
+    // Used in exported file names
+    fn slug(self) -> &'static str {
+        match self {
+            ReportId::RevenueByPeriod => "revenue-by-period",
+            ReportId::RevenueByProduct => "revenue-by-product",
+            ReportId::RevenueByCustomer => "revenue-by-customer",
+            ReportId::UnpaidAging => "unpaid-aging",
+            ReportId::CampaignProfit => "campaign-profit",
+            ReportId::FormProfit => "form-profit",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportGranularity {
+    Day,
+    // ISO-style "2024-W07", weeks starting on Monday
+    Week,
+    #[default]
+    Month,
+    Year,
+}
+
+impl ReportGranularity {
+    // SQLite strftime() format of a period's label
+    fn format(self) -> &'static str {
+        match self {
+            ReportGranularity::Day => "%Y-%m-%d",
+            ReportGranularity::Week => "%Y-W%W",
+            ReportGranularity::Month => "%Y-%m",
+            ReportGranularity::Year => "%Y",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReportParams {
+    // Orders created on or after / before, as dates or UTC datetimes. The
+    // profit reports also take expenses incurred in the range.
+    pub created_from: Option<String>,
+    pub created_to: Option<String>,
+    // revenue_by_period only, monthly by default
+    pub granularity: Option<ReportGranularity>,
+    // Most rows of the product and customer reports, highest revenue first
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnKind {
+    Text,
+    Integer,
+    Money,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportColumn {
+    pub key: String,
+    pub label: String,
+    pub kind: ColumnKind,
+}
+
+// One cell, of its column's kind; null where there's nothing to show
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ReportValue {
+    Integer(i64),
+    Money(f64),
+    Text(String),
+    Empty,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportDataset {
+    pub report: ReportId,
+    pub title: String,
+    pub params: ReportParams,
+    // Default currency; order amounts in other currencies are converted at
+    // the cached exchange rates
+    pub currency: String,
+    pub columns: Vec<ReportColumn>,
+    pub rows: Vec<Vec<ReportValue>>,
+    // Sum of each Integer and Money column, Empty for the rest
+    pub totals: Vec<ReportValue>,
+    pub generated_at: String,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct PeriodRow {
+    period: Option<String>,
+    orders: i64,
+    revenue: f64,
+    paid: f64,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct ProductRow {
+    product_name: String,
+    quantity: i64,
+    orders: i64,
+    revenue: f64,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct CustomerRow {
+    customer_name: String,
+    customer_email: String,
+    orders: i64,
+    revenue: f64,
+    paid: f64,
+    balance: f64,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct ProfitRow {
+    campaign: String,
+    orders: i64,
+    revenue: f64,
+    supplier_cost: f64,
+    fees: f64,
+    other_expenses: f64,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct AgingRow {
+    bucket: String,
+    orders: i64,
+    balance: f64,
+}
+
+// Helper: Column of a dataset
+fn column(key: &str, label: &str, kind: ColumnKind) -> ReportColumn {
+    ReportColumn {
+        key: key.to_string(),
+        label: label.to_string(),
+        kind,
+    }
+}
+
+// Check a date parses the way SQLite's datetime() will read it
+pub fn validate_bound(field: &str, value: &Option<String>) -> Result<(), AppError> {
+    let Some(value) = value else {
+        return Ok(());
+    };
+    let value = value.trim();
+    let valid = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok()
+        || chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").is_ok()
+        || chrono::DateTime::parse_from_rfc3339(value).is_ok();
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::Validation(format!(
+            "{} must be a date (YYYY-MM-DD) or datetime",
+            field
+        )))
+    }
+}
+
+fn validate_params(params: &ReportParams) -> Result<(), AppError> {
+    validate_bound("created_from", &params.created_from)?;
+    validate_bound("created_to", &params.created_to)?;
+    if params.limit.is_some_and(|limit| limit < 1) {
+        return Err(AppError::Validation("Limit must be at least 1".to_string()));
+    }
+    Ok(())
+}
+
+// CTEs `fx` (from order_currency_factors) and `sold`: live and archived
+// confirmed-or-later orders created within a range, with revenue net of
+// credit notes and payments net of refunds, converted into the default
+// currency. `factor` converts the order's other amounts the same way. Takes
+// four binds: created_from twice, then created_to twice (NULL for no bound).
+pub fn sold_orders_cte(fx: &str) -> String {
+    format!(
+        "WITH {fx}, sold AS ( \
+             SELECT h.id, h.created_at, h.invoiced_at, h.customer_name, h.event_id, h.form_id, \
+             LOWER(TRIM(h.customer_email)) AS customer_email, h.supplier_cost, \
+             COALESCE(fx.factor, 1.0) AS factor, \
+             (h.total_amount - h.amount_credited) * COALESCE(fx.factor, 1.0) AS revenue, \
+             h.amount_paid * COALESCE(fx.factor, 1.0) AS paid \
+             FROM order_history h LEFT JOIN fx ON fx.currency = UPPER(h.currency_code) \
+             WHERE h.status IN {sold} \
+             AND (? IS NULL OR h.created_at >= datetime(?)) \
+             AND (? IS NULL OR h.created_at < datetime(?))) ",
+        fx = fx,
+        sold = SOLD_STATUSES
+    )
+}
+
+// Helper: Totals row for `rows`
+fn totals_of(columns: &[ReportColumn], rows: &[Vec<ReportValue>]) -> Vec<ReportValue> {
+    columns
+        .iter()
+        .enumerate()
+        .map(|(index, column)| {
+            let cells = rows.iter().filter_map(|row| row.get(index));
+            match column.kind {
+                ColumnKind::Text => ReportValue::Empty,
+                ColumnKind::Integer => ReportValue::Integer(
+                    cells
+                        .filter_map(|cell| match cell {
+                            ReportValue::Integer(value) => Some(*value),
+                            _ => None,
+                        })
+                        .sum(),
+                ),
+                ColumnKind::Money => ReportValue::Money(
+                    cells
+                        .filter_map(|cell| match cell {
+                            ReportValue::Money(value) => Some(*value),
+                            _ => None,
+                        })
+                        .sum(),
+                ),
+            }
+        })
+        .collect()
+}
+
+// Compute a report in SQL
+pub async fn build_report(
+    conn: &mut SqliteConnection,
+    report: ReportId,
+    params: ReportParams,
+) -> Result<ReportDataset, AppError> {
+    validate_params(&params)?;
+    let from = params.created_from.as_deref().map(str::trim);
+    let to = params.created_to.as_deref().map(str::trim);
+    // SQLite reads a negative LIMIT as none
+    let limit = params.limit.unwrap_or(-1);
+    let currency = default_currency(&mut *conn).await;
+    let sold = sold_orders_cte(&order_currency_factors(&mut *conn, &currency).await?);
+
+    let (columns, rows) = match report {
+        ReportId::RevenueByPeriod => {
+            let granularity = params.granularity.unwrap_or_default();
+            let rows = sqlx::query_as::<_, PeriodRow>(&format!(
+                "{} SELECT strftime(?, created_at) AS period, COUNT(*) AS orders, \
+                 COALESCE(SUM(revenue), 0.0) AS revenue, COALESCE(SUM(paid), 0.0) AS paid \
+                 FROM sold GROUP BY period ORDER BY period",
+                sold
+            ))
+            .bind(from)
+            .bind(from)
+            .bind(to)
+            .bind(to)
+            .bind(granularity.format())
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to compute revenue by period: {}", e))?;
+            (
+                vec![
+                    column("period", "Period", ColumnKind::Text),
+                    column("orders", "Orders", ColumnKind::Integer),
+                    column("revenue", "Revenue", ColumnKind::Money),
+                    column("paid", "Paid", ColumnKind::Money),
+                ],
+                rows.into_iter()
+                    .map(|row| {
+                        vec![
+                            row.period.map_or(ReportValue::Empty, ReportValue::Text),
+                            ReportValue::Integer(row.orders),
+                            ReportValue::Money(row.revenue),
+                            ReportValue::Money(row.paid),
+                        ]
+                    })
+                    .collect(),
+            )
+        }
+        ReportId::RevenueByProduct => {
+            let rows = sqlx::query_as::<_, ProductRow>(&format!(
+                "{} SELECT COALESCE(p.name, 'Deleted product') AS product_name, \
+                 SUM(i.quantity) AS quantity, COUNT(DISTINCT i.preorder_id) AS orders, \
+                 SUM((i.quantity * i.unit_price - i.amount_credited) * sold.factor) AS revenue \
+                 FROM order_item_history i JOIN sold ON sold.id = i.preorder_id \
+                 LEFT JOIN products p ON p.id = i.product_id \
+                 GROUP BY i.product_id ORDER BY revenue DESC, quantity DESC LIMIT ?",
+                sold
+            ))
+            .bind(from)
+            .bind(from)
+            .bind(to)
+            .bind(to)
+            .bind(limit)
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to compute revenue by product: {}", e))?;
+            (
+                vec![
+                    column("product_name", "Product", ColumnKind::Text),
+                    column("quantity", "Quantity", ColumnKind::Integer),
+                    column("orders", "Orders", ColumnKind::Integer),
+                    column("revenue", "Revenue", ColumnKind::Money),
+                ],
+                rows.into_iter()
+                    .map(|row| {
+                        vec![
+                            ReportValue::Text(row.product_name),
+                            ReportValue::Integer(row.quantity),
+                            ReportValue::Integer(row.orders),
+                            ReportValue::Money(row.revenue),
+                        ]
+                    })
+                    .collect(),
+            )
+        }
+        ReportId::RevenueByCustomer => {
+            let rows = sqlx::query_as::<_, CustomerRow>(&format!(
+                "{} SELECT MAX(customer_name) AS customer_name, customer_email, \
+                 COUNT(*) AS orders, SUM(revenue) AS revenue, SUM(paid) AS paid, \
+                 SUM(MAX(revenue - paid, 0.0)) AS balance \
+                 FROM sold GROUP BY customer_email ORDER BY revenue DESC LIMIT ?",
+                sold
+            ))
+            .bind(from)
+            .bind(from)
+            .bind(to)
+            .bind(to)
+            .bind(limit)
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to compute revenue by customer: {}", e))?;
+            (
+                vec![
+                    column("customer_name", "Customer", ColumnKind::Text),
+                    column("customer_email", "Email", ColumnKind::Text),
+                    column("orders", "Orders", ColumnKind::Integer),
+                    column("revenue", "Revenue", ColumnKind::Money),
+                    column("paid", "Paid", ColumnKind::Money),
+                    column("balance", "Balance", ColumnKind::Money),
+                ],
+                rows.into_iter()
+                    .map(|row| {
+                        vec![
+                            ReportValue::Text(row.customer_name),
+                            ReportValue::Text(row.customer_email),
+                            ReportValue::Integer(row.orders),
+                            ReportValue::Money(row.revenue),
+                            ReportValue::Money(row.paid),
+                            ReportValue::Money(row.balance),
+                        ]
+                    })
+                    .collect(),
+            )
+        }
+        ReportId::CampaignProfit | ReportId::FormProfit => {
+            // Column orders and expenses are grouped by, and the row label.
+            // Orders and expenses outside any group share one row, so both
+            // reports add up to the same totals.
+            let (key, heading, label) = if report == ReportId::CampaignProfit {
+                (
+                    "event_id",
+                    "Campaign",
+                    "COALESCE((SELECT name FROM events WHERE id = k.campaign), \
+                     CASE WHEN k.campaign IS NULL THEN 'No campaign' ELSE 'Deleted campaign' END)",
+                )
+            } else {
+                (
+                    "form_id",
+                    "Form",
+                    "COALESCE((SELECT title FROM google_forms WHERE form_id = k.campaign), \
+                     CASE WHEN k.campaign IS NULL THEN 'Not from a form' ELSE k.campaign END)",
+                )
+            };
+            let mut rows = sqlx::query_as::<_, ProfitRow>(&format!(
+                "{sold}, \
+                 campaign_orders AS ( \
+                     SELECT {key} AS campaign, COUNT(*) AS orders, \
+                     SUM(revenue) AS revenue, SUM(supplier_cost) AS supplier_cost \
+                     FROM sold GROUP BY campaign), \
+                 campaign_expenses AS ( \
+                     SELECT {key} AS campaign, \
+                     COALESCE(SUM(CASE WHEN category = 'fee' THEN amount END), 0.0) AS fees, \
+                     COALESCE(SUM(CASE WHEN category != 'fee' THEN amount END), 0.0) AS other_expenses \
+                     FROM expenses WHERE (? IS NULL OR incurred_at >= datetime(?)) \
+                     AND (? IS NULL OR incurred_at < datetime(?)) GROUP BY campaign), \
+                 campaigns AS ( \
+                     SELECT campaign FROM campaign_orders UNION SELECT campaign FROM campaign_expenses) \
+                 SELECT {label} AS campaign, COALESCE(o.orders, 0) AS orders, \
+                 COALESCE(o.revenue, 0.0) AS revenue, COALESCE(o.supplier_cost, 0.0) AS supplier_cost, \
+                 COALESCE(x.fees, 0.0) AS fees, COALESCE(x.other_expenses, 0.0) AS other_expenses \
+                 FROM campaigns k \
+                 LEFT JOIN campaign_orders o ON o.campaign IS k.campaign \
+                 LEFT JOIN campaign_expenses x ON x.campaign IS k.campaign",
+                sold = sold,
+                key = key,
+                label = label
+            ))
+            .bind(from)
+            .bind(from)
+            .bind(to)
+            .bind(to)
+            .bind(from)
+            .bind(from)
+            .bind(to)
+            .bind(to)
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to compute profit: {}", e))?;
+
+            let profit =
+                |row: &ProfitRow| row.revenue - row.supplier_cost - row.fees - row.other_expenses;
+            rows.sort_by(|a, b| profit(b).total_cmp(&profit(a)));
+            (
+                vec![
+                    column("campaign", heading, ColumnKind::Text),
+                    column("orders", "Orders", ColumnKind::Integer),
+                    column("revenue", "Revenue", ColumnKind::Money),
+                    column("supplier_cost", "Supplier costs", ColumnKind::Money),
+                    column("fees", "Fees", ColumnKind::Money),
+                    column("other_expenses", "Other expenses", ColumnKind::Money),
+                    column("profit", "Profit", ColumnKind::Money),
+                ],
+                rows.into_iter()
+                    .map(|row| {
+                        let profit = profit(&row);
+                        vec![
+                            ReportValue::Text(row.campaign),
+                            ReportValue::Integer(row.orders),
+                            ReportValue::Money(row.revenue),
+                            ReportValue::Money(row.supplier_cost),
+                            ReportValue::Money(row.fees),
+                            ReportValue::Money(row.other_expenses),
+                            ReportValue::Money(profit),
+                        ]
+                    })
+                    .collect(),
+            )
+        }
+        ReportId::UnpaidAging => {
+            let buckets: String = AGING_BUCKETS
+                .iter()
+                .map(|(days, label)| format!("WHEN age <= {} THEN '{}' ", days, label))
+                .collect();
+            let rows = sqlx::query_as::<_, AgingRow>(&format!(
+                "{} SELECT CASE {}ELSE '{}' END AS bucket, COUNT(*) AS orders, \
+                 SUM(revenue - paid) AS balance \
+                 FROM (SELECT revenue, paid, CAST(julianday('now') - \
+                 julianday(COALESCE(invoiced_at, created_at)) AS INTEGER) AS age \
+                 FROM sold WHERE revenue > paid) \
+                 GROUP BY bucket ORDER BY MIN(age)",
+                sold, buckets, AGING_OVERDUE
+            ))
+            .bind(from)
+            .bind(from)
+            .bind(to)
+            .bind(to)
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to compute unpaid aging: {}", e))?;
+            (
+                vec![
+                    column("bucket", "Outstanding for", ColumnKind::Text),
+                    column("orders", "Orders", ColumnKind::Integer),
+                    column("balance", "Balance", ColumnKind::Money),
+                ],
+                rows.into_iter()
+                    .map(|row| {
+                        vec![
+                            ReportValue::Text(row.bucket),
+                            ReportValue::Integer(row.orders),
+                            ReportValue::Money(row.balance),
+                        ]
+                    })
+                    .collect(),
+            )
+        }
+    };
+
+    let totals = totals_of(&columns, &rows);
+    Ok(ReportDataset {
+        report,
+        title: report.title().to_string(),
+        params,
+        currency,
+        columns,
+        rows,
+        totals,
+        generated_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+// Helper: A cell as text, amounts with the currency's decimal places
+fn cell_text(value: &ReportValue, currency: &str) -> String {
+    match value {
+        ReportValue::Integer(value) => value.to_string(),
+        ReportValue::Money(value) => format_amount(*value, currency),
+        ReportValue::Text(value) => value.clone(),
+        ReportValue::Empty => String::new(),
+    }
+}
+
+// Helper: Cut text to fit `width` mm, marking the cut
+fn fit_text(text: &str, width: f32, size: f32, bold: bool) -> String {
+    if text_width(text, size, bold) <= width {
+        return text.to_string();
+    }
+    let mut fitted = String::new();
+    for c in text.chars() {
+        if text_width(&format!("{}{}...", fitted, c), size, bold) > width {
+            break;
+        }
+        fitted.push(c);
+    }
+    format!("{}...", fitted)
+}
+
+// Helper: Left edge and width of each column; text columns get twice the room
+fn column_layout(columns: &[ReportColumn]) -> Vec<(f32, f32)> {
+    let weight = |column: &ReportColumn| match column.kind {
+        ColumnKind::Text => 2.0,
+        _ => 1.0,
+    };
+    let total: f32 = columns.iter().map(weight).sum();
+    let available = PAGE_WIDTH - 2.0 * MARGIN;
+    let mut x = MARGIN;
+    columns
+        .iter()
+        .map(|column| {
+            let width = available * weight(column) / total.max(1.0);
+            let left = x;
+            x += width;
+            (left, width)
+        })
+        .collect()
+}
+
+// Helper: One table row, numbers right-aligned
+fn render_row(
+    pdf: &mut PdfWriter,
+    columns: &[ReportColumn],
+    layout: &[(f32, f32)],
+    cells: &[String],
+    bold: bool,
+) {
+    for ((column, (left, width)), cell) in columns.iter().zip(layout).zip(cells) {
+        let text = fit_text(cell, width - 2.0, 9.0, bold);
+        match column.kind {
+            ColumnKind::Text => pdf.text(*left, 9.0, bold, TEXT_COLOR, &text),
+            _ => pdf.text_right(left + width - 2.0, 9.0, bold, TEXT_COLOR, &text),
+        }
+    }
+}
+
+// Helper: Lay out a dataset as a table, repeating the header on every page
+fn render_report(pdf: &mut PdfWriter, dataset: &ReportDataset) {
+    let layout = column_layout(&dataset.columns);
+    let header: Vec<String> = dataset
+        .columns
+        .iter()
+        .map(|column| column.label.clone())
+        .collect();
+
+    pdf.text(MARGIN, 16.0, true, TEXT_COLOR, &dataset.title);
+    pdf.advance(7.0);
+    let range = match (&dataset.params.created_from, &dataset.params.created_to) {
+        (Some(from), Some(to)) => format!("Orders created from {} to {}", from, to),
+        (Some(from), None) => format!("Orders created from {}", from),
+        (None, Some(to)) => format!("Orders created before {}", to),
+        (None, None) => "All orders".to_string(),
+    };
+    pdf.text(
+        MARGIN,
+        9.0,
+        false,
+        MUTED_COLOR,
+        &format!(
+            "{} - amounts in {} - generated {}",
+            range, dataset.currency, dataset.generated_at
+        ),
+    );
+    pdf.advance(10.0);
+
+    render_row(pdf, &dataset.columns, &layout, &header, true);
+    pdf.advance(1.5);
+    pdf.rule(RULE_COLOR, 0.6);
+    for row in &dataset.rows {
+        if pdf.y - ROW_HEIGHT < MARGIN {
+            pdf.new_page();
+            render_row(pdf, &dataset.columns, &layout, &header, true);
+            pdf.advance(1.5);
+            pdf.rule(RULE_COLOR, 0.6);
+        }
+        pdf.advance(ROW_HEIGHT);
+        let cells: Vec<String> = row
+            .iter()
+            .map(|value| cell_text(value, &dataset.currency))
+            .collect();
+        render_row(pdf, &dataset.columns, &layout, &cells, false);
+    }
+
+    pdf.ensure_space(ROW_HEIGHT * 2.0);
+    pdf.advance(1.5);
+    pdf.rule(RULE_COLOR, 0.6);
+    pdf.advance(ROW_HEIGHT);
+    let mut totals: Vec<String> = dataset
+        .totals
+        .iter()
+        .map(|value| cell_text(value, &dataset.currency))
+        .collect();
+    if let Some(first) = totals.first_mut().filter(|first| first.is_empty()) {
+        *first = "Total".to_string();
+    }
+    render_row(pdf, &dataset.columns, &layout, &totals, true);
+}
+
+// Helper: Build a report on a pooled connection
+async fn load_report(
+    db: &Database,
+    report_id: ReportId,
+    params: Option<ReportParams>,
+) -> Result<ReportDataset, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    build_report(&mut conn, report_id, params.unwrap_or_default()).await
+}
+
+// Compute one of the predefined reports as a dataset of typed columns and rows
+#[tauri::command]
+pub async fn run_report(
+    db: State<'_, Database>,
+    report_id: ReportId,
+    params: Option<ReportParams>,
+) -> Result<ReportDataset, AppError> {
+    load_report(&db, report_id, params).await
+}
+
+// Write a report to a CSV file, one row per dataset row; totals are left out
+// so spreadsheets can sum the columns
+#[tauri::command]
+pub async fn export_report_csv(
+    db: State<'_, Database>,
+    report_id: ReportId,
+    params: Option<ReportParams>,
+    dest_path: String,
+) -> Result<CsvExportInfo, AppError> {
+    let dataset = load_report(&db, report_id, params).await?;
+    let header: Vec<String> = dataset
+        .columns
+        .iter()
+        .map(|column| column.label.clone())
+        .collect();
+    let rows: Vec<Vec<String>> = dataset
+        .rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|value| cell_text(value, &dataset.currency))
+                .collect()
+        })
+        .collect();
+    csv_export::write_table(&dest_path, &header, &rows).await
+}
+
+// Render a report to `dest_dir` as a PDF table with a totals row and return
+// the file's path
+#[tauri::command]
+pub async fn export_report_pdf(
+    db: State<'_, Database>,
+    report_id: ReportId,
+    params: Option<ReportParams>,
+    dest_dir: String,
+) -> Result<String, AppError> {
+    let dataset = load_report(&db, report_id, params).await?;
+    let mut pdf = PdfWriter::new(&dataset.title)?;
+    render_report(&mut pdf, &dataset);
+
+    let dir = PathBuf::from(&dest_dir);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create export folder: {}", e))?;
+    let name = format!(
+        "{}-{}",
+        report_id.slug(),
+        chrono::Utc::now().format("%Y-%m-%d")
+    );
+    let path = dir.join(format!("{}.pdf", safe_file_name(&name)));
+    save_pdf(&path, &pdf.finish()?)?;
+    Ok(path.to_string_lossy().to_string())
+}
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::db::Database;
+use crate::error::AppError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum SearchEntity {
+    Order,
+    Customer,
+    Product,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SearchResult {
+    pub entity_type: SearchEntity,
+    pub entity_id: i64,
+    pub title: String,
+    // Matching fragment of the indexed text
+    pub snippet: String,
+    // bm25 score; lower is a better match
+    pub score: f64,
+}
+
+// Turn free text into an FTS5 query: every word must match, as a prefix, so
+// partial codes and names find results as the user types. Quoting each word
+// keeps FTS5 operators in user input from being interpreted.
+fn build_match_query(input: &str) -> Option<String> {
+    let terms: Vec<String> = input
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+// Ranked search across orders, customers and products
+#[tauri::command]
+pub async fn search_all(
+    db: State<'_, Database>,
+    query: String,
+    limit: Option<i64>,
+) -> Result<Vec<SearchResult>, AppError> {
+    let Some(match_query) = build_match_query(&query) else {
+        return Ok(Vec::new());
+    };
+
+    // Title matches weigh most, then codes/emails/SKUs, then free text
+    sqlx::query_as::<_, SearchResult>(
+        "SELECT entity_type, CAST(entity_id AS INTEGER) AS entity_id, title, \
+         snippet(search_index, -1, '', '', '…', 12) AS snippet, \
+         bm25(search_index, 0.0, 0.0, 10.0, 5.0, 1.0) AS score \
+         FROM search_index WHERE search_index MATCH ? \
+         ORDER BY score LIMIT ?",
+    )
+    .bind(match_query)
+    .bind(limit.unwrap_or(25).clamp(1, 200))
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to search: {}", e)))
+}
@@ -0,0 +1,368 @@
+use aes_gcm::aead::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, Sqlite, SqlitePool};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::audit;
+use crate::db::Database;
+use crate::encryption;
+use crate::error::AppError;
+use crate::log_warning;
+
+const LOCKED_ERROR: &str = "The app is locked: enter your passphrase or PIN to continue";
+
+// How often the idle timer is checked
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+// Allowed idle timeout, in minutes
+const IDLE_TIMEOUT_RANGE: (i64, i64) = (1, 24 * 60);
+
+// PINs are digits only
+const PIN_LEN_RANGE: (usize, usize) = (4, 12);
+
+// Pause after a wrong PIN, so guessing one takes a while
+const WRONG_PIN_DELAY: Duration = Duration::from_secs(1);
+
+// Process-wide like the encryption key: checked by the helpers every read and
+// write of contact details and credentials goes through
+static LOCKED: AtomicBool = AtomicBool::new(false);
+static LAST_ACTIVITY: Mutex<Option<Instant>> = Mutex::new(None);
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct StoredSettings {
+    idle_timeout_mins: Option<i64>,
+    pin_hash: Option<String>,
+}
+
+// Session lock settings as shown and audited; the PIN hash stays in the
+// database
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionLockSettings {
+    // Minutes without activity before the app locks, None for never
+    pub idle_timeout_mins: Option<i64>,
+    pub has_pin: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionStatus {
+    pub locked: bool,
+    pub idle_timeout_mins: Option<i64>,
+    pub has_pin: bool,
+    // Seconds until the idle lock, None without a timeout or while locked
+    pub locks_in_secs: Option<u64>,
+}
+
+impl From<&StoredSettings> for SessionLockSettings {
+    fn from(stored: &StoredSettings) -> Self {
+        SessionLockSettings {
+            idle_timeout_mins: stored.idle_timeout_mins,
+            has_pin: stored.pin_hash.is_some(),
+        }
+    }
+}
+
+// Fail while the session is locked. Called before contact details or
+// credentials are read or written and before any email is sent.
+pub fn ensure_unlocked() -> Result<(), AppError> {
+    if LOCKED.load(Ordering::SeqCst) {
+        return Err(AppError::Locked(LOCKED_ERROR.to_string()));
+    }
+    Ok(())
+}
+
+// Helper: Note user activity now, restarting the idle timer
+fn record_activity() {
+    match LAST_ACTIVITY.lock() {
+        Ok(mut last) => *last = Some(Instant::now()),
+        Err(e) => log_warning!("Failed to record activity: {}", e),
+    }
+}
+
+// Helper: Time since the last activity
+fn idle_for() -> Duration {
+    LAST_ACTIVITY
+        .lock()
+        .ok()
+        .and_then(|last| *last)
+        .map_or(Duration::ZERO, |last| last.elapsed())
+}
+
+// Helper: Tell the frontend the session was locked or unlocked
+fn emit_locked(app: &AppHandle, locked: bool) {
+    if let Err(e) = app.emit("session-lock-changed", locked) {
+        log_warning!("Failed to emit session-lock-changed event: {}", e);
+    }
+}
+
+// Lock the session and drop the decrypted data key from memory
+fn lock(app: &AppHandle) -> Result<(), AppError> {
+    LOCKED.store(true, Ordering::SeqCst);
+    encryption::forget_key(app)?;
+    emit_locked(app, true);
+    Ok(())
+}
+
+// End a session lock once the passphrase or PIN has been checked
+pub fn end_lock(app: &AppHandle) {
+    record_activity();
+    if LOCKED.swap(false, Ordering::SeqCst) {
+        emit_locked(app, false);
+    }
+}
+
+// Helper: Stored settings, the defaults if the row is missing
+async fn load_settings<'e, E>(executor: E) -> Result<StoredSettings, AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    Ok(sqlx::query_as::<_, StoredSettings>(
+        "SELECT idle_timeout_mins, pin_hash FROM session_lock_settings WHERE id = 1",
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| format!("Failed to load session lock settings: {}", e))?
+    .unwrap_or(StoredSettings {
+        idle_timeout_mins: None,
+        pin_hash: None,
+    }))
+}
+
+// Helper: Whether the session can be unlocked again once locked
+fn can_unlock(settings: &StoredSettings) -> bool {
+    settings.pin_hash.is_some() || encryption::status().enabled
+}
+
+// Helper: Check a PIN against its stored hash
+async fn verify_pin(pin: &str, pin_hash: &str) -> Result<(), AppError> {
+    let hash =
+        PasswordHash::new(pin_hash).map_err(|e| format!("Stored PIN is corrupted: {}", e))?;
+    if Argon2::default()
+        .verify_password(pin.as_bytes(), &hash)
+        .is_err()
+    {
+        tokio::time::sleep(WRONG_PIN_DELAY).await;
+        return Err(AppError::Validation("Wrong PIN".to_string()));
+    }
+    Ok(())
+}
+
+// Helper: Argon2id hash of a new PIN
+fn hash_pin(pin: &str) -> Result<String, AppError> {
+    let (min, max) = PIN_LEN_RANGE;
+    if !(min..=max).contains(&pin.len()) || !pin.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(AppError::Validation(format!(
+            "PIN must be {} to {} digits",
+            min, max
+        )));
+    }
+    Argon2::default()
+        .hash_password(pin.as_bytes(), &SaltString::generate(&mut OsRng))
+        .map(|hash| hash.to_string())
+        .map_err(|e| AppError::Internal(format!("Failed to hash PIN: {}", e)))
+}
+
+// Start locked if a restart would otherwise skip the idle lock. Called on
+// startup alongside encryption::init.
+pub async fn init(pool: &SqlitePool) -> Result<(), AppError> {
+    let settings = load_settings(pool).await?;
+    LOCKED.store(
+        settings.idle_timeout_mins.is_some() && can_unlock(&settings),
+        Ordering::SeqCst,
+    );
+    record_activity();
+    Ok(())
+}
+
+// Lock the session once it has been idle for the configured time
+pub fn start_idle_lock(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<Database>().pool.clone();
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            if LOCKED.load(Ordering::SeqCst) {
+                continue;
+            }
+            let timeout = match load_settings(&pool).await {
+                Ok(settings) => settings.idle_timeout_mins,
+                Err(e) => {
+                    log_warning!("Idle lock check failed: {}", e);
+                    continue;
+                }
+            };
+            let Some(mins) = timeout else {
+                continue;
+            };
+            if idle_for() >= Duration::from_secs(mins as u64 * 60) {
+                if let Err(e) = lock(&app) {
+                    log_warning!("Failed to lock session: {}", e);
+                }
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_session_status(db: State<'_, Database>) -> Result<SessionStatus, AppError> {
+    let settings = load_settings(&db.pool).await?;
+    let locked = LOCKED.load(Ordering::SeqCst);
+    Ok(SessionStatus {
+        locked,
+        idle_timeout_mins: settings.idle_timeout_mins,
+        has_pin: settings.pin_hash.is_some(),
+        locks_in_secs: settings
+            .idle_timeout_mins
+            .filter(|_| !locked)
+            .map(|mins| (mins as u64 * 60).saturating_sub(idle_for().as_secs())),
+    })
+}
+
+// Restart the idle timer. The frontend calls this on user input.
+#[tauri::command]
+pub fn touch_session() -> Result<(), AppError> {
+    if !LOCKED.load(Ordering::SeqCst) {
+        record_activity();
+    }
+    Ok(())
+}
+
+// Lock the session now
+#[tauri::command]
+pub async fn lock_session(app: AppHandle, db: State<'_, Database>) -> Result<(), AppError> {
+    if !can_unlock(&load_settings(&db.pool).await?) {
+        return Err(AppError::Validation(
+            "Set a PIN or turn on encryption before locking".to_string(),
+        ));
+    }
+    lock(&app)
+}
+
+// Unlock with the encryption passphrase while encryption is on, otherwise
+// with the PIN
+#[tauri::command]
+pub async fn unlock_session(
+    app: AppHandle,
+    db: State<'_, Database>,
+    secret: String,
+) -> Result<(), AppError> {
+    if encryption::status().enabled {
+        encryption::unlock(&app, &db.pool, &secret).await?;
+        return Ok(());
+    }
+    if let Some(pin_hash) = load_settings(&db.pool).await?.pin_hash {
+        verify_pin(&secret, &pin_hash).await?;
+    }
+    end_lock(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_session_lock_settings(
+    db: State<'_, Database>,
+    idle_timeout_mins: Option<i64>,
+) -> Result<SessionLockSettings, AppError> {
+    ensure_unlocked()?;
+    let (min, max) = IDLE_TIMEOUT_RANGE;
+    if idle_timeout_mins.is_some_and(|mins| !(min..=max).contains(&mins)) {
+        return Err(AppError::Validation(format!(
+            "Idle timeout must be between {} and {} minutes",
+            min, max
+        )));
+    }
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_settings(&mut *tx).await?;
+    if idle_timeout_mins.is_some() && !can_unlock(&before) {
+        return Err(AppError::Validation(
+            "Set a PIN or turn on encryption before turning on the idle lock".to_string(),
+        ));
+    }
+    sqlx::query(
+        "INSERT INTO session_lock_settings (id, idle_timeout_mins) VALUES (1, ?) \
+         ON CONFLICT(id) DO UPDATE SET idle_timeout_mins = excluded.idle_timeout_mins, \
+         updated_at = CURRENT_TIMESTAMP",
+    )
+    .bind(idle_timeout_mins)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save session lock settings: {}", e))?;
+    let after = load_settings(&mut *tx).await?;
+
+    audit::record(
+        &mut *tx,
+        "session_lock_settings",
+        1,
+        "update",
+        Some(&SessionLockSettings::from(&before)),
+        Some(&SessionLockSettings::from(&after)),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save session lock settings: {}", e))?;
+    record_activity();
+    Ok(SessionLockSettings::from(&after))
+}
+
+// Set, change or remove (new_pin None) the PIN. Changing or removing one
+// takes the current PIN.
+#[tauri::command]
+pub async fn set_session_pin(
+    db: State<'_, Database>,
+    current_pin: Option<String>,
+    new_pin: Option<String>,
+) -> Result<SessionLockSettings, AppError> {
+    ensure_unlocked()?;
+    let new_hash = new_pin.as_deref().map(hash_pin).transpose()?;
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_settings(&mut *tx).await?;
+    if let Some(pin_hash) = &before.pin_hash {
+        verify_pin(current_pin.as_deref().unwrap_or_default(), pin_hash).await?;
+    }
+    if new_hash.is_none() && before.idle_timeout_mins.is_some() && !encryption::status().enabled {
+        return Err(AppError::Validation(
+            "Turn off the idle lock before removing the PIN".to_string(),
+        ));
+    }
+    sqlx::query(
+        "INSERT INTO session_lock_settings (id, pin_hash) VALUES (1, ?) \
+         ON CONFLICT(id) DO UPDATE SET pin_hash = excluded.pin_hash, \
+         updated_at = CURRENT_TIMESTAMP",
+    )
+    .bind(&new_hash)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save PIN: {}", e))?;
+    let after = load_settings(&mut *tx).await?;
+
+    audit::record(
+        &mut *tx,
+        "session_lock_settings",
+        1,
+        "update_pin",
+        Some(&SessionLockSettings::from(&before)),
+        Some(&SessionLockSettings::from(&after)),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save PIN: {}", e))?;
+    Ok(SessionLockSettings::from(&after))
+}
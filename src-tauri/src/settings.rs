@@ -0,0 +1,822 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, Sqlite, SqliteConnection, SqlitePool};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::audit;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::http;
+use crate::log_warning;
+use crate::pdf::parse_hex_color;
+use crate::SmtpSettings;
+
+// Allowed HTTP timeouts, in seconds
+const CONNECT_TIMEOUT_RANGE: (i64, i64) = (1, 120);
+const REQUEST_TIMEOUT_RANGE: (i64, i64) = (5, 3600);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SettingsSection {
+    Smtp,
+    OauthClient,
+    BusinessProfile,
+    EmailTemplate,
+    Network,
+}
+
+// Emitted as "settings-changed" after a section is saved, so open windows
+// and hooks reload it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingsChanged {
+    pub section: SettingsSection,
+}
+
+// SMTP account for outgoing email. The password is write-only: it's never
+// sent back, and saving without one keeps the stored password.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmtpConfig {
+    pub smtp_server: String,
+    pub smtp_port: i64,
+    pub username: String,
+    #[serde(default)]
+    pub password: Option<String>,
+    pub from_email: String,
+    pub from_name: Option<String>,
+    // Whether a password is stored, ignored when saving
+    #[serde(default)]
+    pub has_password: bool,
+}
+
+// Google OAuth client used for sign-in. The secret is write-only like the
+// SMTP password.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthClientConfig {
+    pub client_id: String,
+    #[serde(default)]
+    pub client_secret: Option<String>,
+    #[serde(default)]
+    pub has_client_secret: bool,
+}
+
+// Proxy and extra root certificates for networks that need them. The proxy
+// is used by the HTTP client for Google and the other APIs; SMTP connects
+// directly but trusts the CA bundle too. The proxy password is write-only
+// like the SMTP password.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, sqlx::FromRow)]
+pub struct NetworkConfig {
+    // http://, https://, socks5:// or socks5h://host:port, None to connect directly
+    pub proxy_url: Option<String>,
+    pub proxy_username: Option<String>,
+    #[serde(default)]
+    pub proxy_password: Option<String>,
+    #[serde(default)]
+    #[sqlx(skip)]
+    pub has_proxy_password: bool,
+    // PEM file with one or more certificates
+    pub ca_bundle_path: Option<String>,
+    // None for the defaults (10s to connect, 300s per request)
+    pub connect_timeout_secs: Option<i64>,
+    pub request_timeout_secs: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, sqlx::FromRow)]
+pub struct BusinessProfile {
+    pub name: String,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    // One item per line
+    pub address: Option<String>,
+    pub tax_id: Option<String>,
+    pub website: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmailSectionKind {
+    Header,
+    Greeting,
+    QrCode,
+    ItemsTable,
+    Total,
+    Footer,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailSection {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: EmailSectionKind,
+    pub label: String,
+    pub enabled: bool,
+    pub order: i64,
+}
+
+// Layout of the invoice email the frontend renders after an order is placed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailTemplate {
+    pub sections: Vec<EmailSection>,
+    pub header_title: String,
+    pub header_subtitle: String,
+    pub footer_text: String,
+    // "#rrggbb"
+    pub primary_color: String,
+    pub secondary_color: String,
+    pub use_banner_image: bool,
+    pub banner_image_url: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct EmailTemplateRow {
+    sections: String,
+    header_title: Option<String>,
+    header_subtitle: Option<String>,
+    footer_text: Option<String>,
+    primary_color: Option<String>,
+    secondary_color: Option<String>,
+    use_banner_image: Option<bool>,
+    banner_image_url: Option<String>,
+}
+
+impl SmtpConfig {
+    // Copy safe to send to the frontend or the audit log
+    fn redacted(&self) -> Self {
+        SmtpConfig {
+            password: None,
+            has_password: self.password.as_deref().is_some_and(|p| !p.is_empty()),
+            ..self.clone()
+        }
+    }
+}
+
+impl OAuthClientConfig {
+    fn redacted(&self) -> Self {
+        OAuthClientConfig {
+            client_secret: None,
+            has_client_secret: self.client_secret.as_deref().is_some_and(|s| !s.is_empty()),
+            ..self.clone()
+        }
+    }
+}
+
+impl NetworkConfig {
+    fn redacted(&self) -> Self {
+        NetworkConfig {
+            proxy_password: None,
+            has_proxy_password: self.proxy_password.as_deref().is_some_and(|p| !p.is_empty()),
+            ..self.clone()
+        }
+    }
+}
+
+impl Default for EmailTemplate {
+    fn default() -> Self {
+        let section = |id: &str, kind, label: &str, order| EmailSection {
+            id: id.to_string(),
+            kind,
+            label: label.to_string(),
+            enabled: true,
+            order,
+        };
+        EmailTemplate {
+            sections: vec![
+                section("header", EmailSectionKind::Header, "Header", 0),
+                section("greeting", EmailSectionKind::Greeting, "Greeting", 1),
+                section(
+                    "qr_code",
+                    EmailSectionKind::QrCode,
+                    "QR Code & Confirmation",
+                    2,
+                ),
+                section(
+                    "items_table",
+                    EmailSectionKind::ItemsTable,
+                    "Items Table",
+                    3,
+                ),
+                section("total", EmailSectionKind::Total, "Total Amount", 4),
+                section("footer", EmailSectionKind::Footer, "Footer", 5),
+            ],
+            header_title: "Pre-Order Invoice".to_string(),
+            header_subtitle: "Thank you for your order!".to_string(),
+            footer_text: "This is an automated email from POTracker".to_string(),
+            primary_color: "#6366f1".to_string(),
+            secondary_color: "#a855f7".to_string(),
+            use_banner_image: false,
+            banner_image_url: String::new(),
+        }
+    }
+}
+
+// Helper: Trimmed text, None when blank
+fn non_blank(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+// Helper: Loose email check, the same one customer contacts get
+fn validate_email(label: &str, email: &str) -> Result<(), AppError> {
+    match email.trim().split_once('@') {
+        Some((local, domain)) if !local.is_empty() && domain.contains('.') => Ok(()),
+        _ => Err(AppError::Validation(format!(
+            "{} is not a valid email address: {}",
+            label, email
+        ))),
+    }
+}
+
+// Helper: An http(s) URL
+fn validate_url(label: &str, url: &str) -> Result<(), AppError> {
+    match reqwest::Url::parse(url.trim()) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Ok(()),
+        _ => Err(AppError::Validation(format!(
+            "{} must be an http or https URL: {}",
+            label, url
+        ))),
+    }
+}
+
+// Helper: A proxy URL with a scheme reqwest can use and a host
+fn validate_proxy_url(url: &str) -> Result<(), AppError> {
+    match reqwest::Url::parse(url) {
+        Ok(parsed)
+            if matches!(parsed.scheme(), "http" | "https" | "socks5" | "socks5h")
+                && parsed.host_str().is_some() =>
+        {
+            Ok(())
+        }
+        _ => Err(AppError::Validation(format!(
+            "Proxy must be an http, https, socks5 or socks5h URL with a host: {}",
+            url
+        ))),
+    }
+}
+
+// Helper: A timeout in seconds within `range`, if set
+fn validate_timeout(label: &str, secs: Option<i64>, range: (i64, i64)) -> Result<(), AppError> {
+    match secs {
+        Some(secs) if secs < range.0 || secs > range.1 => Err(AppError::Validation(format!(
+            "{} must be between {} and {} seconds",
+            label, range.0, range.1
+        ))),
+        _ => Ok(()),
+    }
+}
+
+// Helper: A "#rrggbb" color
+fn validate_color(label: &str, color: &str) -> Result<(), AppError> {
+    if color.trim().len() == 7 && color.starts_with('#') && parse_hex_color(color).is_some() {
+        Ok(())
+    } else {
+        Err(AppError::Validation(format!(
+            "{} must be a #rrggbb color: {}",
+            label, color
+        )))
+    }
+}
+
+// Helper: Tell the frontend a section changed
+fn emit_changed(app: &AppHandle, section: SettingsSection) {
+    if let Err(e) = app.emit("settings-changed", SettingsChanged { section }) {
+        log_warning!("Failed to emit settings-changed event: {}", e);
+    }
+}
+
+// Helper: Stored SMTP account with its password, None if not set up
+pub async fn load_smtp_config<'e, E>(executor: E) -> Result<Option<SmtpConfig>, AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let row = sqlx::query_as::<_, (String, i64, String, String, String, Option<String>)>(
+        "SELECT smtp_server, smtp_port, username, password, from_email, from_name \
+         FROM smtp_settings WHERE id = 1",
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| format!("Failed to load SMTP settings: {}", e))?;
+
+    Ok(row.map(
+        |(smtp_server, smtp_port, username, password, from_email, from_name)| SmtpConfig {
+            smtp_server,
+            smtp_port,
+            username,
+            has_password: !password.is_empty(),
+            password: Some(password),
+            from_email,
+            from_name,
+        },
+    ))
+}
+
+// Helper: SMTP settings for sending, if set up
+pub async fn load_smtp_settings(pool: &SqlitePool) -> Result<Option<SmtpSettings>, AppError> {
+    Ok(load_smtp_config(pool).await?.map(|config| SmtpSettings {
+        smtp_server: config.smtp_server,
+        smtp_port: config.smtp_port as i32,
+        username: config.username,
+        password: config.password.unwrap_or_default(),
+        from_email: config.from_email,
+        from_name: config.from_name,
+    }))
+}
+
+// Helper: Stored OAuth client with its secret, None if not set up
+pub async fn load_oauth_client<'e, E>(executor: E) -> Result<Option<OAuthClientConfig>, AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let row = sqlx::query_as::<_, (String, String)>(
+        "SELECT client_id, client_secret FROM google_config WHERE id = 1",
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| format!("Failed to load OAuth client: {}", e))?;
+
+    Ok(row.map(|(client_id, client_secret)| OAuthClientConfig {
+        client_id,
+        has_client_secret: !client_secret.is_empty(),
+        client_secret: Some(client_secret),
+    }))
+}
+
+// Helper: Network settings with the proxy password, empty if never saved
+pub async fn load_network_config<'e, E>(executor: E) -> Result<NetworkConfig, AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let config = sqlx::query_as::<_, NetworkConfig>(
+        "SELECT proxy_url, proxy_username, proxy_password, ca_bundle_path, \
+         connect_timeout_secs, request_timeout_secs FROM network_settings WHERE id = 1",
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| format!("Failed to load network settings: {}", e))?
+    .unwrap_or_default();
+    Ok(NetworkConfig {
+        has_proxy_password: config.proxy_password.as_deref().is_some_and(|p| !p.is_empty()),
+        ..config
+    })
+}
+
+// Helper: Business profile, blank if never saved
+pub async fn load_business_profile<'e, E>(executor: E) -> Result<BusinessProfile, AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    Ok(sqlx::query_as::<_, BusinessProfile>(
+        "SELECT name, email, phone, address, tax_id, website FROM business_profile WHERE id = 1",
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| format!("Failed to load business profile: {}", e))?
+    .unwrap_or_default())
+}
+
+// Helper: Invoice email template, the built-in one if never saved. Columns
+// the frontend left NULL fall back to the defaults too.
+pub async fn load_email_template(conn: &mut SqliteConnection) -> Result<EmailTemplate, AppError> {
+    let row = sqlx::query_as::<_, EmailTemplateRow>(
+        "SELECT sections, header_title, header_subtitle, footer_text, primary_color, \
+         secondary_color, use_banner_image, banner_image_url FROM invoice_templates WHERE id = 1",
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| format!("Failed to load email template: {}", e))?;
+
+    let defaults = EmailTemplate::default();
+    let Some(row) = row else {
+        return Ok(defaults);
+    };
+    Ok(EmailTemplate {
+        sections: serde_json::from_str(&row.sections).unwrap_or(defaults.sections),
+        header_title: row.header_title.unwrap_or(defaults.header_title),
+        header_subtitle: row.header_subtitle.unwrap_or(defaults.header_subtitle),
+        footer_text: row.footer_text.unwrap_or(defaults.footer_text),
+        primary_color: row.primary_color.unwrap_or(defaults.primary_color),
+        secondary_color: row.secondary_color.unwrap_or(defaults.secondary_color),
+        use_banner_image: row.use_banner_image.unwrap_or(defaults.use_banner_image),
+        banner_image_url: row.banner_image_url.unwrap_or(defaults.banner_image_url),
+    })
+}
+
+impl BusinessProfile {
+    // Contact lines printed under the business name, one item per line
+    pub fn details(&self) -> Option<String> {
+        let lines: Vec<String> = [
+            self.address.clone(),
+            self.phone.clone(),
+            self.email.clone(),
+            self.website.clone(),
+            self.tax_id.clone(),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+}
+
+#[tauri::command]
+pub async fn get_smtp_config(db: State<'_, Database>) -> Result<Option<SmtpConfig>, AppError> {
+    Ok(load_smtp_config(&db.pool)
+        .await?
+        .map(|config| config.redacted()))
+}
+
+#[tauri::command]
+pub async fn save_smtp_config(
+    app: AppHandle,
+    db: State<'_, Database>,
+    config: SmtpConfig,
+) -> Result<SmtpConfig, AppError> {
+    let smtp_server = config.smtp_server.trim().to_string();
+    if smtp_server.is_empty() || smtp_server.contains(char::is_whitespace) {
+        return Err(AppError::Validation(format!(
+            "Invalid SMTP server: {}",
+            config.smtp_server
+        )));
+    }
+    if !(1..=65535).contains(&config.smtp_port) {
+        return Err(AppError::Validation(format!(
+            "Invalid SMTP port: {}",
+            config.smtp_port
+        )));
+    }
+    let username = config.username.trim().to_string();
+    if username.is_empty() {
+        return Err(AppError::Validation(
+            "SMTP username must not be empty".to_string(),
+        ));
+    }
+    validate_email("From address", &config.from_email)?;
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_smtp_config(&mut *tx).await?;
+    let password = match config.password.filter(|p| !p.is_empty()) {
+        Some(password) => password,
+        None => before
+            .as_ref()
+            .and_then(|b| b.password.clone())
+            .filter(|p| !p.is_empty())
+            .ok_or("SMTP password is required")?,
+    };
+    sqlx::query(
+        "INSERT INTO smtp_settings \
+         (id, smtp_server, smtp_port, username, password, from_email, from_name) \
+         VALUES (1, ?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(id) DO UPDATE SET smtp_server = excluded.smtp_server, \
+         smtp_port = excluded.smtp_port, username = excluded.username, \
+         password = excluded.password, from_email = excluded.from_email, \
+         from_name = excluded.from_name",
+    )
+    .bind(&smtp_server)
+    .bind(config.smtp_port)
+    .bind(&username)
+    .bind(&password)
+    .bind(config.from_email.trim())
+    .bind(non_blank(config.from_name))
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save SMTP settings: {}", e))?;
+    let after = load_smtp_config(&mut *tx)
+        .await?
+        .ok_or("Failed to save SMTP settings")?
+        .redacted();
+
+    audit::record(
+        &mut *tx,
+        "smtp_settings",
+        1,
+        "update",
+        before.map(|b| b.redacted()).as_ref(),
+        Some(&after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save SMTP settings: {}", e))?;
+    emit_changed(&app, SettingsSection::Smtp);
+    Ok(after)
+}
+
+#[tauri::command]
+pub async fn get_oauth_client(
+    db: State<'_, Database>,
+) -> Result<Option<OAuthClientConfig>, AppError> {
+    Ok(load_oauth_client(&db.pool)
+        .await?
+        .map(|config| config.redacted()))
+}
+
+#[tauri::command]
+pub async fn save_oauth_client(
+    app: AppHandle,
+    db: State<'_, Database>,
+    config: OAuthClientConfig,
+) -> Result<OAuthClientConfig, AppError> {
+    let client_id = config.client_id.trim().to_string();
+    if !client_id.ends_with(".apps.googleusercontent.com") {
+        return Err(AppError::Validation(format!(
+            "Client ID should end in .apps.googleusercontent.com: {}",
+            client_id
+        )));
+    }
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_oauth_client(&mut *tx).await?;
+    let client_secret = match non_blank(config.client_secret) {
+        Some(secret) => secret,
+        None => before
+            .as_ref()
+            .and_then(|b| b.client_secret.clone())
+            .filter(|s| !s.is_empty())
+            .ok_or("Client secret is required")?,
+    };
+    sqlx::query(
+        "INSERT INTO google_config (id, client_id, client_secret) VALUES (1, ?, ?) \
+         ON CONFLICT(id) DO UPDATE SET client_id = excluded.client_id, \
+         client_secret = excluded.client_secret",
+    )
+    .bind(&client_id)
+    .bind(&client_secret)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save OAuth client: {}", e))?;
+    let after = load_oauth_client(&mut *tx)
+        .await?
+        .ok_or("Failed to save OAuth client")?
+        .redacted();
+
+    audit::record(
+        &mut *tx,
+        "google_config",
+        1,
+        "update",
+        before.map(|b| b.redacted()).as_ref(),
+        Some(&after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save OAuth client: {}", e))?;
+    emit_changed(&app, SettingsSection::OauthClient);
+    Ok(after)
+}
+
+#[tauri::command]
+pub async fn get_network_config(db: State<'_, Database>) -> Result<NetworkConfig, AppError> {
+    Ok(load_network_config(&db.pool).await?.redacted())
+}
+
+// Save the proxy, CA bundle and timeouts; the HTTP client and SMTP use them
+// from the next request
+#[tauri::command]
+pub async fn save_network_config(
+    app: AppHandle,
+    db: State<'_, Database>,
+    config: NetworkConfig,
+) -> Result<NetworkConfig, AppError> {
+    let proxy_url = non_blank(config.proxy_url);
+    if let Some(url) = &proxy_url {
+        validate_proxy_url(url)?;
+    }
+    let proxy_username = proxy_url.as_ref().and(non_blank(config.proxy_username));
+    validate_timeout("Connect timeout", config.connect_timeout_secs, CONNECT_TIMEOUT_RANGE)?;
+    validate_timeout("Request timeout", config.request_timeout_secs, REQUEST_TIMEOUT_RANGE)?;
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_network_config(&mut *tx).await?;
+    // Without a new password the stored one is kept, as long as there's still
+    // a username to go with it
+    let proxy_password = match proxy_username {
+        Some(_) => config
+            .proxy_password
+            .filter(|p| !p.is_empty())
+            .or(before.proxy_password.clone().filter(|p| !p.is_empty())),
+        None => None,
+    };
+    let config = NetworkConfig {
+        proxy_url,
+        proxy_username,
+        proxy_password,
+        has_proxy_password: false,
+        ca_bundle_path: non_blank(config.ca_bundle_path),
+        connect_timeout_secs: config.connect_timeout_secs,
+        request_timeout_secs: config.request_timeout_secs,
+    };
+    // Build the client now so a bad proxy or certificate file is reported
+    // before anything is saved
+    let setup = http::build(&config)?;
+
+    sqlx::query(
+        "INSERT INTO network_settings \
+         (id, proxy_url, proxy_username, proxy_password, ca_bundle_path, \
+         connect_timeout_secs, request_timeout_secs, updated_at) \
+         VALUES (1, ?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP) \
+         ON CONFLICT(id) DO UPDATE SET proxy_url = excluded.proxy_url, \
+         proxy_username = excluded.proxy_username, proxy_password = excluded.proxy_password, \
+         ca_bundle_path = excluded.ca_bundle_path, \
+         connect_timeout_secs = excluded.connect_timeout_secs, \
+         request_timeout_secs = excluded.request_timeout_secs, updated_at = excluded.updated_at",
+    )
+    .bind(&config.proxy_url)
+    .bind(&config.proxy_username)
+    .bind(&config.proxy_password)
+    .bind(&config.ca_bundle_path)
+    .bind(config.connect_timeout_secs)
+    .bind(config.request_timeout_secs)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save network settings: {}", e))?;
+    let after = load_network_config(&mut *tx).await?.redacted();
+
+    audit::record(
+        &mut *tx,
+        "network_settings",
+        1,
+        "update",
+        Some(&before.redacted()),
+        Some(&after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save network settings: {}", e))?;
+    http::install(setup);
+    emit_changed(&app, SettingsSection::Network);
+    Ok(after)
+}
+
+#[tauri::command]
+pub async fn get_business_profile(db: State<'_, Database>) -> Result<BusinessProfile, AppError> {
+    load_business_profile(&db.pool).await
+}
+
+#[tauri::command]
+pub async fn save_business_profile(
+    app: AppHandle,
+    db: State<'_, Database>,
+    profile: BusinessProfile,
+) -> Result<BusinessProfile, AppError> {
+    let profile = BusinessProfile {
+        name: profile.name.trim().to_string(),
+        email: non_blank(profile.email),
+        phone: non_blank(profile.phone),
+        address: non_blank(profile.address),
+        tax_id: non_blank(profile.tax_id),
+        website: non_blank(profile.website),
+    };
+    if profile.name.is_empty() {
+        return Err(AppError::Validation(
+            "Business name must not be empty".to_string(),
+        ));
+    }
+    if let Some(email) = &profile.email {
+        validate_email("Business email", email)?;
+    }
+    if let Some(website) = &profile.website {
+        validate_url("Website", website)?;
+    }
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_business_profile(&mut *tx).await?;
+    sqlx::query(
+        "INSERT INTO business_profile \
+         (id, name, email, phone, address, tax_id, website, updated_at) \
+         VALUES (1, ?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP) \
+         ON CONFLICT(id) DO UPDATE SET name = excluded.name, email = excluded.email, \
+         phone = excluded.phone, address = excluded.address, tax_id = excluded.tax_id, \
+         website = excluded.website, updated_at = excluded.updated_at",
+    )
+    .bind(&profile.name)
+    .bind(&profile.email)
+    .bind(&profile.phone)
+    .bind(&profile.address)
+    .bind(&profile.tax_id)
+    .bind(&profile.website)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save business profile: {}", e))?;
+    let after = load_business_profile(&mut *tx).await?;
+
+    audit::record(
+        &mut *tx,
+        "business_profile",
+        1,
+        "update",
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save business profile: {}", e))?;
+    emit_changed(&app, SettingsSection::BusinessProfile);
+    Ok(after)
+}
+
+#[tauri::command]
+pub async fn get_email_template(db: State<'_, Database>) -> Result<EmailTemplate, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    load_email_template(&mut conn).await
+}
+
+#[tauri::command]
+pub async fn save_email_template(
+    app: AppHandle,
+    db: State<'_, Database>,
+    template: EmailTemplate,
+) -> Result<EmailTemplate, AppError> {
+    if template.sections.is_empty() {
+        return Err(AppError::Validation(
+            "Email template needs at least one section".to_string(),
+        ));
+    }
+    let mut ids = std::collections::HashSet::new();
+    for section in &template.sections {
+        if section.id.trim().is_empty() || !ids.insert(section.id.as_str()) {
+            return Err(AppError::Validation(format!(
+                "Duplicate or empty section ID: {:?}",
+                section.id
+            )));
+        }
+    }
+    validate_color("Primary color", &template.primary_color)?;
+    validate_color("Secondary color", &template.secondary_color)?;
+    if template.use_banner_image {
+        validate_url("Banner image", &template.banner_image_url)?;
+    }
+    let sections = serde_json::to_string(&template.sections)
+        .map_err(|e| format!("Failed to serialize sections: {}", e))?;
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_email_template(&mut tx).await?;
+    sqlx::query(
+        "INSERT OR REPLACE INTO invoice_templates \
+         (id, sections, header_title, header_subtitle, footer_text, primary_color, \
+         secondary_color, use_banner_image, banner_image_url) \
+         VALUES (1, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&sections)
+    .bind(&template.header_title)
+    .bind(&template.header_subtitle)
+    .bind(&template.footer_text)
+    .bind(template.primary_color.trim())
+    .bind(template.secondary_color.trim())
+    .bind(template.use_banner_image)
+    .bind(template.banner_image_url.trim())
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save email template: {}", e))?;
+    let after = load_email_template(&mut tx).await?;
+
+    audit::record(
+        &mut *tx,
+        "invoice_templates",
+        1,
+        "update",
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save email template: {}", e))?;
+    emit_changed(&app, SettingsSection::EmailTemplate);
+    Ok(after)
+}
@@ -0,0 +1,489 @@
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use tauri::State;
+
+use crate::bulk_orders::{matching_order_ids, OrderFilter};
+use crate::csv_export::{load_order_rows, EXPORT_BATCH_SIZE, ORDER_HEADERS};
+use crate::db::Database;
+use crate::error::AppError;
+use crate::http::{self, SendWithRetry};
+use crate::log_warning;
+
+const SHEETS_API: &str = "https://sheets.googleapis.com/v4/spreadsheets";
+
+// Tab the orders are written to; replaced on every export
+const ORDERS_SHEET: &str = "Orders";
+
+// Drive folder new spreadsheets are filed under, next to the project folders
+const APP_ROOT_FOLDER: &str = "po-tracker";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SheetExportInfo {
+    pub spreadsheet_id: String,
+    pub spreadsheet_url: String,
+    // Data rows written, not counting the header
+    pub rows: usize,
+}
+
+// Which sheet columns (by header text) hold the customer and the ordered
+// quantities. Without `products`, a column titled with a product's name (or
+// "Quantity: <name>", as on generated forms) is read as that product's quantity.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SheetColumnMapping {
+    pub customer_name: String,
+    pub customer_email: String,
+    // Header -> product ID
+    pub products: Option<HashMap<String, i64>>,
+    // Column with a stable key per row (e.g. a timestamp); defaults to the row number
+    pub row_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParsedOrderItem {
+    pub product_id: i64,
+    pub quantity: i64,
+    pub unit_price: f64,
+}
+
+// An order read from a form response or sheet row, not yet created. The
+// response ID is what synced_responses records once it's imported.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParsedOrder {
+    pub response_id: String,
+    // Sheet row number (1-based)
+    pub row: usize,
+    pub customer_name: String,
+    pub customer_email: String,
+    pub items: Vec<ParsedOrderItem>,
+    pub total_amount: f64,
+    pub already_imported: bool,
+    // Cells that couldn't be read, e.g. a quantity that isn't a number
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SheetImport {
+    // Recorded as synced_responses.form_id for orders created from this sheet
+    pub source_id: String,
+    pub orders: Vec<ParsedOrder>,
+    // Headers that looked like quantities but match no product
+    pub unmatched_columns: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ValueRange {
+    values: Option<Vec<Vec<Value>>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreatedSpreadsheet {
+    #[serde(rename = "spreadsheetId")]
+    spreadsheet_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SpreadsheetSheets {
+    sheets: Option<Vec<SheetEntry>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SheetEntry {
+    properties: SheetProperties,
+}
+
+#[derive(Debug, Deserialize)]
+struct SheetProperties {
+    title: String,
+}
+
+// Helper: Fail with the API's own message on a non-2xx response
+async fn check_response(response: reqwest::Response, action: &str) -> Result<Value, AppError> {
+    if !response.status().is_success() {
+        return Err(http::google_error(response, &format!("Failed to {}", action)).await);
+    }
+    response
+        .json::<Value>()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to parse Sheets response: {}", e)))
+}
+
+// Helper: New spreadsheet with an Orders tab, filed under the po-tracker folder
+async fn create_spreadsheet(client: &Client, access_token: &str) -> Result<String, AppError> {
+    let title = format!(
+        "POTracker Orders {}",
+        chrono::Local::now().format("%Y-%m-%d %H:%M")
+    );
+    let response = client
+        .post(SHEETS_API)
+        .bearer_auth(access_token)
+        .json(&json!({
+            "properties": { "title": title },
+            "sheets": [{ "properties": { "title": ORDERS_SHEET } }],
+        }))
+        .send_with_retry()
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to create spreadsheet: {}", e)))?;
+    let created: CreatedSpreadsheet =
+        serde_json::from_value(check_response(response, "create spreadsheet").await?)
+            .map_err(|e| format!("Failed to parse spreadsheet: {}", e))?;
+
+    // Filing it away is a nicety; the export still works from the Drive root
+    let root_id = match crate::find_folder(client, access_token, APP_ROOT_FOLDER).await {
+        Ok(Some(id)) => Some(id),
+        Ok(None) => crate::create_folder(client, access_token, APP_ROOT_FOLDER)
+            .await
+            .ok(),
+        Err(_) => None,
+    };
+    if let Some(root_id) = root_id {
+        if let Err(e) =
+            crate::move_file_to_folder(client, access_token, &created.spreadsheet_id, &root_id)
+                .await
+        {
+            log_warning!("Failed to move spreadsheet to folder: {}", e);
+        }
+    }
+
+    Ok(created.spreadsheet_id)
+}
+
+// Helper: Add the Orders tab to an existing spreadsheet if it isn't there yet
+async fn ensure_orders_sheet(
+    client: &Client,
+    access_token: &str,
+    spreadsheet_id: &str,
+) -> Result<(), AppError> {
+    let response = client
+        .get(format!("{}/{}", SHEETS_API, spreadsheet_id))
+        .query(&[("fields", "sheets.properties.title")])
+        .bearer_auth(access_token)
+        .send_with_retry()
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to open spreadsheet: {}", e)))?;
+    let existing: SpreadsheetSheets =
+        serde_json::from_value(check_response(response, "open spreadsheet").await?)
+            .map_err(|e| format!("Failed to parse spreadsheet: {}", e))?;
+
+    let has_sheet = existing
+        .sheets
+        .unwrap_or_default()
+        .iter()
+        .any(|sheet| sheet.properties.title == ORDERS_SHEET);
+    if has_sheet {
+        return Ok(());
+    }
+
+    let response = client
+        .post(format!("{}/{}:batchUpdate", SHEETS_API, spreadsheet_id))
+        .bearer_auth(access_token)
+        .json(&json!({
+            "requests": [{ "addSheet": { "properties": { "title": ORDERS_SHEET } } }],
+        }))
+        .send_with_retry()
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to add sheet: {}", e)))?;
+    check_response(response, "add sheet").await?;
+    Ok(())
+}
+
+// Helper: Header plus one row per order, amounts as numbers
+async fn order_values(db: &Database, ids: &[i64]) -> Result<Vec<Vec<Value>>, AppError> {
+    let mut values = vec![ORDER_HEADERS.iter().map(|h| json!(h)).collect::<Vec<_>>()];
+    for batch in ids.chunks(EXPORT_BATCH_SIZE) {
+        for row in load_order_rows(db, batch).await? {
+            values.push(vec![
+                json!(row.id),
+                json!(row.created_at.unwrap_or_default()),
+                json!(row.customer_name),
+                json!(row.customer_email),
+                json!(row.confirmation_code),
+                json!(row.invoice_number.unwrap_or_default()),
+                json!(row.status),
+                json!(row.event_name.unwrap_or_default()),
+                json!(row.currency_code.unwrap_or_default()),
+                json!(row.items.unwrap_or_default()),
+                json!(row.total_amount),
+                json!(row.amount_paid),
+                json!(row.total_amount - row.amount_credited - row.amount_paid),
+                json!(row.paid_at.unwrap_or_default()),
+                json!(row.notes.unwrap_or_default()),
+            ]);
+        }
+    }
+    Ok(values)
+}
+
+// Write orders (all live orders, or those matching `filter`) to the Orders tab
+// of a Google Sheet, replacing what was there. Without `spreadsheet_id` a new
+// spreadsheet is created in the linked account's Drive.
+#[tauri::command]
+pub async fn export_orders_to_sheet(
+    db: State<'_, Database>,
+    access_token: String,
+    spreadsheet_id: Option<String>,
+    filter: Option<OrderFilter>,
+) -> Result<SheetExportInfo, AppError> {
+    let filter = filter.unwrap_or_default();
+    let ids = {
+        let mut conn = db
+            .pool
+            .acquire()
+            .await
+            .map_err(|e| format!("Failed to open database connection: {}", e))?;
+        matching_order_ids(&mut conn, &filter).await?
+    };
+    let values = order_values(&db, &ids).await?;
+
+    let client = http::client();
+    let spreadsheet_id = match spreadsheet_id
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+    {
+        Some(id) => {
+            ensure_orders_sheet(&client, &access_token, &id).await?;
+            id
+        }
+        None => create_spreadsheet(&client, &access_token).await?,
+    };
+
+    let response = client
+        .post(format!(
+            "{}/{}/values:batchClear",
+            SHEETS_API, spreadsheet_id
+        ))
+        .bearer_auth(&access_token)
+        .json(&json!({ "ranges": [ORDERS_SHEET] }))
+        .send_with_retry()
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to clear sheet: {}", e)))?;
+    check_response(response, "clear sheet").await?;
+
+    // RAW keeps customer-entered text from being parsed as formulas
+    let response = client
+        .post(format!(
+            "{}/{}/values:batchUpdate",
+            SHEETS_API, spreadsheet_id
+        ))
+        .bearer_auth(&access_token)
+        .json(&json!({
+            "valueInputOption": "RAW",
+            "data": [{ "range": format!("{}!A1", ORDERS_SHEET), "values": &values }],
+        }))
+        .send_with_retry()
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to write sheet: {}", e)))?;
+    check_response(response, "write sheet").await?;
+
+    let rows = values.len() - 1;
+    Ok(SheetExportInfo {
+        spreadsheet_url: format!(
+            "https://docs.google.com/spreadsheets/d/{}/edit",
+            spreadsheet_id
+        ),
+        spreadsheet_id,
+        rows,
+    })
+}
+
+// Helper: Cell value as trimmed text (numbers come back unformatted)
+pub fn cell_text(row: &[Value], column: usize) -> String {
+    match row.get(column) {
+        Some(Value::String(text)) => text.trim().to_string(),
+        Some(Value::Number(number)) => number.to_string(),
+        Some(Value::Bool(flag)) => flag.to_string(),
+        _ => String::new(),
+    }
+}
+
+// Helper: Position of a header, compared case-insensitively
+fn header_position(headers: &[String], name: &str) -> Result<usize, AppError> {
+    headers
+        .iter()
+        .position(|h| h.eq_ignore_ascii_case(name.trim()))
+        .ok_or_else(|| AppError::NotFound(format!("Column \"{}\" not found in the sheet", name)))
+}
+
+// Helper: Raw cell values of a sheet range, numbers and dates unformatted
+pub async fn fetch_sheet_values(
+    access_token: &str,
+    spreadsheet_id: &str,
+    range: &str,
+) -> Result<Vec<Vec<Value>>, AppError> {
+    let client = http::client();
+    let response = client
+        .get(format!(
+            "{}/{}/values/{}",
+            SHEETS_API,
+            spreadsheet_id,
+            crate::urlencoding::encode(range.trim())
+        ))
+        .query(&[("valueRenderOption", "UNFORMATTED_VALUE")])
+        .bearer_auth(access_token)
+        .send_with_retry()
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to read sheet: {}", e)))?;
+    let range: ValueRange = serde_json::from_value(check_response(response, "read sheet").await?)
+        .map_err(|e| format!("Failed to parse sheet values: {}", e))?;
+    Ok(range.values.unwrap_or_default())
+}
+
+// Read orders from a Google Sheet range (first row = headers) into the same
+// parsed shape the Forms sync builds. Nothing is created; the caller creates
+// the orders and records each response ID as synced, as for form responses.
+#[tauri::command]
+pub async fn import_orders_from_sheet(
+    db: State<'_, Database>,
+    access_token: String,
+    spreadsheet_id: String,
+    range: String,
+    column_mapping: SheetColumnMapping,
+) -> Result<SheetImport, AppError> {
+    let spreadsheet_id = spreadsheet_id.trim().to_string();
+    if spreadsheet_id.is_empty() {
+        return Err(AppError::Validation(
+            "Spreadsheet ID must not be empty".to_string(),
+        ));
+    }
+
+    let mut rows = fetch_sheet_values(&access_token, &spreadsheet_id, &range)
+        .await?
+        .into_iter();
+    let headers: Vec<String> = match rows.next() {
+        Some(header_row) => (0..header_row.len())
+            .map(|i| cell_text(&header_row, i))
+            .collect(),
+        None => return Err(AppError::Validation("The sheet range is empty".to_string())),
+    };
+
+    let name_column = header_position(&headers, &column_mapping.customer_name)?;
+    let email_column = header_position(&headers, &column_mapping.customer_email)?;
+    let key_column = column_mapping
+        .row_key
+        .as_deref()
+        .map(|key| header_position(&headers, key))
+        .transpose()?;
+
+    let products = sqlx::query_as::<_, (i64, String, f64)>(
+        "SELECT id, name, price FROM products WHERE COALESCE(is_active, 1) = 1",
+    )
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| format!("Failed to load products: {}", e))?;
+    let prices: HashMap<i64, f64> = products
+        .iter()
+        .map(|(id, _, price)| (*id, *price))
+        .collect();
+
+    // (column, product ID) for every quantity column
+    let mut product_columns = Vec::new();
+    let mut unmatched_columns = Vec::new();
+    match &column_mapping.products {
+        Some(mapped) => {
+            for (header, product_id) in mapped {
+                if !prices.contains_key(product_id) {
+                    return Err(AppError::NotFound(format!(
+                        "Product {} not found",
+                        product_id
+                    )));
+                }
+                product_columns.push((header_position(&headers, header)?, *product_id));
+            }
+        }
+        None => {
+            for (column, header) in headers.iter().enumerate() {
+                if column == name_column || column == email_column || Some(column) == key_column {
+                    continue;
+                }
+                let name = header.strip_prefix("Quantity: ").unwrap_or(header).trim();
+                match products
+                    .iter()
+                    .find(|(_, product, _)| product.trim().eq_ignore_ascii_case(name))
+                {
+                    Some((id, _, _)) => product_columns.push((column, *id)),
+                    None if header.starts_with("Quantity: ") => {
+                        unmatched_columns.push(header.clone())
+                    }
+                    None => {}
+                }
+            }
+        }
+    }
+    if product_columns.is_empty() {
+        return Err(AppError::Validation(
+            "No product quantity columns found; map them explicitly".to_string(),
+        ));
+    }
+
+    let source_id = format!("sheet:{}", spreadsheet_id);
+    let synced: HashSet<String> = sqlx::query_scalar::<_, String>(
+        "SELECT response_id FROM synced_responses WHERE form_id = ?",
+    )
+    .bind(&source_id)
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| format!("Failed to load imported rows: {}", e))?
+    .into_iter()
+    .collect();
+
+    let mut orders = Vec::new();
+    for (index, row) in rows.enumerate() {
+        let row_number = index + 2;
+        if row
+            .iter()
+            .all(|cell| matches!(cell, Value::Null) || cell == "")
+        {
+            continue;
+        }
+
+        let mut warnings = Vec::new();
+        let mut items = Vec::new();
+        for (column, product_id) in &product_columns {
+            let text = cell_text(&row, *column);
+            if text.is_empty() {
+                continue;
+            }
+            match text.parse::<f64>() {
+                Ok(quantity) if quantity.fract() == 0.0 && quantity >= 0.0 => {
+                    if quantity > 0.0 {
+                        let unit_price = prices[product_id];
+                        items.push(ParsedOrderItem {
+                            product_id: *product_id,
+                            quantity: quantity as i64,
+                            unit_price,
+                        });
+                    }
+                }
+                _ => warnings.push(format!("{}: invalid quantity {}", headers[*column], text)),
+            }
+        }
+
+        let key = key_column
+            .map(|column| cell_text(&row, column))
+            .filter(|key| !key.is_empty())
+            .unwrap_or_else(|| format!("row-{}", row_number));
+        let response_id = format!("{}:{}", source_id, key);
+        let total_amount = items
+            .iter()
+            .map(|item| item.unit_price * item.quantity as f64)
+            .sum();
+
+        orders.push(ParsedOrder {
+            already_imported: synced.contains(&response_id),
+            response_id,
+            row: row_number,
+            customer_name: cell_text(&row, name_column),
+            customer_email: cell_text(&row, email_column),
+            items,
+            total_amount,
+            warnings,
+        });
+    }
+
+    Ok(SheetImport {
+        source_id,
+        orders,
+        unmatched_columns,
+    })
+}
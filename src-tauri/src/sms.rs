@@ -0,0 +1,507 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use tauri::{AppHandle, Manager, State};
+
+use crate::audit;
+use crate::automation::{self, Poller};
+use crate::customers::normalize_phone;
+use crate::db::Database;
+use crate::domain_events::{DomainEvent, Subscriber};
+use crate::encryption::{conceal_opt, reveal, reveal_opt};
+use crate::error::AppError;
+use crate::i18n::load_locale;
+use crate::log_warning;
+use crate::models::ContactPreference;
+use crate::order_status::OrderStatus;
+use crate::twilio::Twilio;
+use crate::vonage::Vonage;
+
+// Sends per text before it's marked failed
+const MAX_ATTEMPTS: i64 = 5;
+
+const MESSAGE_COLUMNS: &str = "id, preorder_id, kind, provider, to_phone, body, status, attempts, \
+     provider_message_id, last_error, created_at, sent_at";
+
+// A service that delivers text messages. Each one only has to send a single
+// text; settings, queueing, retries and the message log are shared.
+pub trait SmsProvider {
+    // Send `body` to `to` (international digits, no +) and return the
+    // provider's message ID
+    async fn send(&self, to: &str, body: &str) -> Result<String, AppError>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum SmsProviderKind {
+    Twilio,
+    Vonage,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum SmsKind {
+    ConfirmationCode,
+    PaymentReminder,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum SmsStatus {
+    Pending,
+    Sent,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SmsSettings {
+    pub enabled: bool,
+    pub provider: SmsProviderKind,
+    // Twilio Account SID, or Vonage API key
+    pub account_id: Option<String>,
+    // Twilio auth token, or Vonage API secret
+    pub auth_token: Option<String>,
+    pub sender: Option<String>,
+    pub default_country_code: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SmsMessage {
+    pub id: i64,
+    pub preorder_id: i64,
+    pub kind: SmsKind,
+    pub provider: SmsProviderKind,
+    pub to_phone: String,
+    pub body: String,
+    pub status: SmsStatus,
+    pub attempts: i64,
+    pub provider_message_id: Option<String>,
+    pub last_error: Option<String>,
+    pub created_at: Option<String>,
+    pub sent_at: Option<String>,
+}
+
+impl SmsSettings {
+    // Account ID, auth token and sender, when all are filled in
+    pub fn credentials(&self) -> Result<(&str, &str, &str), AppError> {
+        match (
+            self.account_id.as_deref().filter(|v| !v.is_empty()),
+            self.auth_token.as_deref().filter(|v| !v.is_empty()),
+            self.sender.as_deref().filter(|v| !v.is_empty()),
+        ) {
+            (Some(account_id), Some(auth_token), Some(sender)) => {
+                Ok((account_id, auth_token, sender))
+            }
+            _ => Err(AppError::Validation(
+                "SMS needs an account ID, auth token and sender".to_string(),
+            )),
+        }
+    }
+}
+
+// Helper: SMS settings, disabled defaults if the row is missing
+async fn load_settings(conn: &mut SqliteConnection) -> Result<SmsSettings, AppError> {
+    let settings = sqlx::query_as::<_, SmsSettings>(
+        "SELECT enabled, provider, account_id, auth_token, sender, default_country_code \
+         FROM sms_settings WHERE id = 1",
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| format!("Failed to load SMS settings: {}", e))?
+    .unwrap_or(SmsSettings {
+        enabled: false,
+        provider: SmsProviderKind::Twilio,
+        account_id: None,
+        auth_token: None,
+        sender: None,
+        default_country_code: "62".to_string(),
+    });
+    Ok(SmsSettings {
+        auth_token: reveal_opt(settings.auth_token)?,
+        ..settings
+    })
+}
+
+// Helper: Send one text through the configured provider
+async fn send_text(settings: &SmsSettings, to: &str, body: &str) -> Result<String, AppError> {
+    if !settings.enabled {
+        return Err(AppError::Validation(
+            "SMS sending is switched off".to_string(),
+        ));
+    }
+    match settings.provider {
+        SmsProviderKind::Twilio => Twilio::new(settings)?.send(to, body).await,
+        SmsProviderKind::Vonage => Vonage::new(settings)?.send(to, body).await,
+    }
+}
+
+// Helper: The number to text an order's customer at, if they prefer texts,
+// have a usable phone number and SMS is set up. None means use email.
+pub async fn sms_recipient(
+    conn: &mut SqliteConnection,
+    po_id: i64,
+) -> Result<Option<String>, AppError> {
+    let settings = load_settings(&mut *conn).await?;
+    if !settings.enabled || settings.credentials().is_err() {
+        return Ok(None);
+    }
+    let customer = sqlx::query_as::<_, (ContactPreference, Option<String>)>(
+        "SELECT c.contact_preference, c.phone FROM preorders p \
+         JOIN customers c ON c.email = p.customer_email WHERE p.id = ?",
+    )
+    .bind(po_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load customer: {}", e))?;
+
+    Ok(match customer {
+        Some((ContactPreference::Sms, Some(phone))) => {
+            normalize_phone(&reveal(phone)?, &settings.default_country_code)
+        }
+        _ => None,
+    })
+}
+
+// Helper: Add a pending text to the log
+async fn insert_message(
+    conn: &mut SqliteConnection,
+    po_id: i64,
+    kind: SmsKind,
+    to_phone: &str,
+    body: &str,
+) -> Result<i64, AppError> {
+    let provider = load_settings(&mut *conn).await?.provider;
+    let result = sqlx::query(
+        "INSERT INTO sms_messages (preorder_id, kind, provider, to_phone, body) \
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(po_id)
+    .bind(kind)
+    .bind(provider)
+    .bind(to_phone)
+    .bind(body)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to queue SMS: {}", e))?;
+    Ok(result.last_insert_rowid())
+}
+
+// Helper: Queue the confirmation code text for a just-confirmed order, in the
+// transaction that confirms it, if its customer prefers texts
+async fn queue_confirmation_code(conn: &mut SqliteConnection, po_id: i64) -> Result<(), AppError> {
+    let Some(phone) = sms_recipient(&mut *conn, po_id).await? else {
+        return Ok(());
+    };
+    let (name, code) = sqlx::query_as::<_, (String, String)>(
+        "SELECT customer_name, confirmation_code FROM preorders WHERE id = ?",
+    )
+    .bind(po_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load order: {}", e))?;
+
+    let body = load_locale(&mut *conn)
+        .await
+        .format("sms.confirmation", &[("name", &name), ("code", &code)]);
+    insert_message(conn, po_id, SmsKind::ConfirmationCode, &phone, &body).await?;
+    Ok(())
+}
+
+// Queues the confirmation code text once an order is confirmed
+pub struct Sms;
+
+impl Subscriber for Sms {
+    async fn handle(
+        &self,
+        conn: &mut SqliteConnection,
+        event: &DomainEvent<'_>,
+    ) -> Result<(), AppError> {
+        match *event {
+            DomainEvent::OrderStatusChanged(change) if change.to == OrderStatus::Confirmed => {
+                queue_confirmation_code(conn, change.order_id).await
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// Helper: Send one logged text and record the outcome
+async fn deliver(
+    pool: &SqlitePool,
+    settings: &SmsSettings,
+    message: &SmsMessage,
+) -> Result<(), AppError> {
+    let result = send_text(settings, &message.to_phone, &message.body).await;
+    let attempts = message.attempts + 1;
+    let status = match &result {
+        Ok(_) => SmsStatus::Sent,
+        // Only queued confirmations are retried in the background; a reminder
+        // that fails stays pending on the reminder queue instead
+        Err(_)
+            if message.kind == SmsKind::ConfirmationCode
+                && attempts < MAX_ATTEMPTS
+                && settings.enabled =>
+        {
+            SmsStatus::Pending
+        }
+        Err(_) => SmsStatus::Failed,
+    };
+
+    sqlx::query(
+        "UPDATE sms_messages SET status = ?, attempts = ?, provider = ?, \
+         provider_message_id = COALESCE(?, provider_message_id), last_error = ?, \
+         sent_at = CASE WHEN ? = 'sent' THEN CURRENT_TIMESTAMP ELSE sent_at END WHERE id = ?",
+    )
+    .bind(status)
+    .bind(attempts)
+    .bind(settings.provider)
+    .bind(result.as_ref().ok())
+    .bind(result.as_ref().err().map(|e| e.to_string()))
+    .bind(status)
+    .bind(message.id)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to update SMS: {}", e))?;
+    result.map(|_| ())
+}
+
+async fn load_message(pool: &SqlitePool, id: i64) -> Result<SmsMessage, AppError> {
+    sqlx::query_as::<_, SmsMessage>(&format!(
+        "SELECT {} FROM sms_messages WHERE id = ?",
+        MESSAGE_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load SMS: {}", e))?
+    .ok_or_else(|| AppError::NotFound(format!("SMS {} not found", id)))
+}
+
+// Helper: Text a payment reminder right away, logging it either way. Errors
+// if the provider refused it.
+pub async fn send_reminder_sms(
+    pool: &SqlitePool,
+    po_id: i64,
+    to_phone: &str,
+    body: &str,
+) -> Result<(), AppError> {
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let settings = load_settings(&mut conn).await?;
+    let id = insert_message(&mut conn, po_id, SmsKind::PaymentReminder, to_phone, body).await?;
+    drop(conn);
+
+    let message = load_message(pool, id).await?;
+    deliver(pool, &settings, &message).await
+}
+
+// Helper: Send queued texts
+pub async fn send_pending_messages(pool: &SqlitePool) -> Result<(), AppError> {
+    let batch = automation::send_batch_size(pool).await?;
+    if batch == 0 {
+        return Ok(());
+    }
+    let pending = sqlx::query_as::<_, SmsMessage>(&format!(
+        "SELECT {} FROM sms_messages WHERE status = 'pending' ORDER BY id LIMIT ?",
+        MESSAGE_COLUMNS
+    ))
+    .bind(batch)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load SMS queue: {}", e))?;
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let settings = load_settings(&mut conn).await?;
+    drop(conn);
+
+    automation::record_sends(pool, pending.len()).await?;
+    for message in &pending {
+        if let Err(e) = deliver(pool, &settings, message).await {
+            log_warning!("Failed to send SMS {}: {}", message.id, e);
+        }
+    }
+    Ok(())
+}
+
+// Start the background loop that sends queued texts
+pub fn start_sms_sender(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<Database>().pool.clone();
+        loop {
+            if let Err(e) = send_pending_messages(&pool).await {
+                log_warning!("SMS sender failed: {}", e);
+            }
+            automation::wait_for_next_poll(&pool, Poller::Sender).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_sms_settings(db: State<'_, Database>) -> Result<SmsSettings, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    load_settings(&mut conn).await
+}
+
+#[tauri::command]
+pub async fn set_sms_settings(
+    db: State<'_, Database>,
+    settings: SmsSettings,
+) -> Result<SmsSettings, AppError> {
+    let trimmed = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    };
+    let settings = SmsSettings {
+        account_id: trimmed(&settings.account_id),
+        auth_token: trimmed(&settings.auth_token),
+        sender: trimmed(&settings.sender),
+        default_country_code: settings
+            .default_country_code
+            .trim()
+            .trim_start_matches('+')
+            .to_string(),
+        ..settings
+    };
+    if settings.enabled {
+        // Catches a missing field or a malformed ID before anything is sent
+        match settings.provider {
+            SmsProviderKind::Twilio => Twilio::new(&settings).map(|_| ())?,
+            SmsProviderKind::Vonage => Vonage::new(&settings).map(|_| ())?,
+        }
+    }
+    if settings.default_country_code.is_empty()
+        || !settings
+            .default_country_code
+            .chars()
+            .all(|c| c.is_ascii_digit())
+    {
+        return Err(AppError::Validation(format!(
+            "Invalid country code: {}",
+            settings.default_country_code
+        )));
+    }
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_settings(&mut tx).await?;
+    sqlx::query(
+        "INSERT OR REPLACE INTO sms_settings \
+         (id, enabled, provider, account_id, auth_token, sender, default_country_code, updated_at) \
+         VALUES (1, ?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP)",
+    )
+    .bind(settings.enabled)
+    .bind(settings.provider)
+    .bind(&settings.account_id)
+    .bind(conceal_opt(settings.auth_token.as_deref())?)
+    .bind(&settings.sender)
+    .bind(&settings.default_country_code)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save SMS settings: {}", e))?;
+    let after = load_settings(&mut tx).await?;
+
+    // The auth token stays out of the audit log
+    let view = |s: &SmsSettings| {
+        serde_json::json!({
+            "enabled": s.enabled,
+            "provider": s.provider,
+            "account_id": s.account_id,
+            "sender": s.sender,
+            "default_country_code": s.default_country_code,
+        })
+    };
+    audit::record(
+        &mut *tx,
+        "sms_settings",
+        1,
+        "update",
+        Some(&view(&before)),
+        Some(&view(&after)),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save SMS settings: {}", e))?;
+    Ok(after)
+}
+
+// Text a test message to a number, to check the provider settings
+#[tauri::command]
+pub async fn send_test_sms(db: State<'_, Database>, to_phone: String) -> Result<String, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let settings = load_settings(&mut conn).await?;
+    let body = load_locale(&mut *conn).await.text("sms.test");
+    drop(conn);
+
+    let to = normalize_phone(&to_phone, &settings.default_country_code)
+        .ok_or_else(|| AppError::Validation(format!("Invalid phone number: {}", to_phone)))?;
+    send_text(&settings, &to, &body).await
+}
+
+// Texts for one order, or the most recent across all orders
+#[tauri::command]
+pub async fn list_sms_messages(
+    db: State<'_, Database>,
+    preorder_id: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Vec<SmsMessage>, AppError> {
+    sqlx::query_as::<_, SmsMessage>(&format!(
+        "SELECT {} FROM sms_messages WHERE (? IS NULL OR preorder_id = ?) \
+         ORDER BY created_at DESC, id DESC LIMIT ?",
+        MESSAGE_COLUMNS
+    ))
+    .bind(preorder_id)
+    .bind(preorder_id)
+    .bind(limit.unwrap_or(200).clamp(1, 1000))
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to list SMS: {}", e)))
+}
+
+// Send a failed (or still pending) text again now
+#[tauri::command]
+pub async fn retry_sms_message(db: State<'_, Database>, id: i64) -> Result<SmsMessage, AppError> {
+    let message = load_message(&db.pool, id).await?;
+    if message.status == SmsStatus::Sent {
+        return Err(AppError::Conflict(
+            "This text has already been sent".to_string(),
+        ));
+    }
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let settings = load_settings(&mut conn).await?;
+    drop(conn);
+
+    // The outcome is on the returned message either way
+    let _ = deliver(&db.pool, &settings, &message).await;
+    load_message(&db.pool, id).await
+}
@@ -0,0 +1,253 @@
+use reqwest::{Client, RequestBuilder};
+use serde_json::Value;
+use tauri::{AppHandle, State};
+
+use crate::db::Database;
+use crate::error::AppError;
+use crate::http;
+use crate::payment_links::{
+    insert_payment_link, order_balance, reuse_open_link, sync_payment_links, LinkState,
+    NewPaymentLink, PaymentGateway, PaymentLink, PaymentSync,
+};
+
+const STRIPE_API: &str = "https://api.stripe.com/v1";
+
+// Currencies Stripe takes in whole units (its list differs from
+// currency_decimals: it wants IDR in hundredths, for one)
+const ZERO_DECIMAL_CURRENCIES: &[&str] = &[
+    "BIF", "CLP", "DJF", "GNF", "JPY", "KMF", "KRW", "MGA", "PYG", "RWF", "UGX", "VND", "VUV",
+    "XAF", "XOF", "XPF",
+];
+const THREE_DECIMAL_CURRENCIES: &[&str] = &["BHD", "JOD", "KWD", "OMR", "TND"];
+
+// Helper: Decimal places of Stripe's smallest unit for a currency
+fn stripe_decimals(currency: &str) -> i32 {
+    if ZERO_DECIMAL_CURRENCIES.contains(&currency) {
+        0
+    } else if THREE_DECIMAL_CURRENCIES.contains(&currency) {
+        3
+    } else {
+        2
+    }
+}
+
+// Helper: Amount in Stripe's smallest unit, e.g. 12.50 USD -> 1250
+fn to_minor_units(amount: f64, currency: &str) -> i64 {
+    let minor = (amount * 10f64.powi(stripe_decimals(currency))).round() as i64;
+    // Three-decimal currencies must end in 0
+    if stripe_decimals(currency) == 3 {
+        (minor + 5) / 10 * 10
+    } else {
+        minor
+    }
+}
+
+fn from_minor_units(minor: i64, currency: &str) -> f64 {
+    minor as f64 / 10f64.powi(stripe_decimals(currency))
+}
+
+// Stripe API access with a secret key, which is never stored
+struct Stripe {
+    client: Client,
+    secret_key: String,
+}
+
+impl Stripe {
+    fn new(secret_key: &str) -> Result<Stripe, AppError> {
+        let secret_key = secret_key.trim();
+        if !secret_key.starts_with("sk_") && !secret_key.starts_with("rk_") {
+            return Err(AppError::Validation(
+                "Stripe secret key must start with sk_ or rk_".to_string(),
+            ));
+        }
+        Ok(Stripe {
+            client: http::client(),
+            secret_key: secret_key.to_string(),
+        })
+    }
+
+    // Send a request and return the JSON body, surfacing Stripe's own error
+    // message when it refuses
+    async fn send(&self, request: RequestBuilder, action: &str) -> Result<Value, AppError> {
+        let response = request
+            .bearer_auth(&self.secret_key)
+            .send()
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to reach Stripe: {}", e)))?;
+        let status = response.status();
+        let body = response
+            .json::<Value>()
+            .await
+            .map_err(|e| format!("Failed to parse Stripe response: {}", e))?;
+        if !status.is_success() {
+            let message = body["error"]["message"].as_str().unwrap_or("unknown error");
+            return Err(AppError::from_status(
+                status.as_u16(),
+                format!("Failed to {}: {}", action, message),
+            ));
+        }
+        Ok(body)
+    }
+}
+
+impl PaymentGateway for Stripe {
+    const PROVIDER: &'static str = "stripe";
+
+    // Whether a link has been paid, from its completed Checkout sessions
+    async fn link_state(&self, link: &PaymentLink) -> Result<LinkState, AppError> {
+        let body = self
+            .send(
+                self.client
+                    .get(format!("{}/checkout/sessions", STRIPE_API))
+                    .query(&[
+                        ("payment_link", link.external_id.as_str()),
+                        ("status", "complete"),
+                        ("limit", "10"),
+                    ]),
+                "check Stripe payments",
+            )
+            .await?;
+
+        let paid = body["data"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|session| session["payment_status"] == "paid");
+        let Some(session) = paid else {
+            return Ok(LinkState::Open);
+        };
+        let currency = session["currency"]
+            .as_str()
+            .unwrap_or(&link.currency_code)
+            .to_uppercase();
+        let paid_at = session["created"]
+            .as_i64()
+            .and_then(|seconds| chrono::DateTime::from_timestamp(seconds, 0))
+            .unwrap_or_else(chrono::Utc::now);
+        Ok(LinkState::Paid {
+            amount: from_minor_units(session["amount_total"].as_i64().unwrap_or(0), &currency),
+            // The PaymentIntent is what shows up in the Stripe dashboard and payouts
+            reference: session["payment_intent"]
+                .as_str()
+                .or(session["id"].as_str())
+                .unwrap_or_default()
+                .to_string(),
+            paid_at: paid_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+        })
+    }
+
+    async fn withdraw(&self, link: &PaymentLink) -> Result<(), AppError> {
+        self.send(
+            self.client
+                .post(format!("{}/payment_links/{}", STRIPE_API, link.external_id))
+                .form(&[("active", "false")]),
+            "disable Stripe payment link",
+        )
+        .await
+        .map(|_| ())
+    }
+}
+
+// Create a Stripe Payment Link for an order's outstanding balance and keep it
+// with the order, ready to send to the customer. Asking again returns the same
+// link while it's still open for that amount; if the balance changed, the old
+// link is disabled and replaced. The secret key isn't stored.
+#[tauri::command]
+pub async fn create_payment_link(
+    db: State<'_, Database>,
+    secret_key: String,
+    po_id: i64,
+) -> Result<PaymentLink, AppError> {
+    let stripe = Stripe::new(&secret_key)?;
+
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let (order, balance, currency) = order_balance(&mut conn, po_id).await?;
+    if let Some(link) = reuse_open_link(&mut conn, &stripe, po_id, balance, &currency, None).await?
+    {
+        return Ok(link);
+    }
+
+    let label = match &order.invoice_number {
+        Some(invoice) => format!("Invoice {}", invoice),
+        None => format!("Order {}", order.confirmation_code),
+    };
+    let order_id = po_id.to_string();
+    let price = stripe
+        .send(
+            stripe.client.post(format!("{}/prices", STRIPE_API)).form(&[
+                ("currency", currency.to_lowercase()),
+                (
+                    "unit_amount",
+                    to_minor_units(balance, &currency).to_string(),
+                ),
+                ("product_data[name]", label),
+                ("product_data[metadata][preorder_id]", order_id.clone()),
+            ]),
+            "create Stripe price",
+        )
+        .await?;
+    let price_id = price["id"]
+        .as_str()
+        .ok_or("Stripe didn't return a price ID")?;
+
+    let created = stripe
+        .send(
+            stripe
+                .client
+                .post(format!("{}/payment_links", STRIPE_API))
+                .form(&[
+                    ("line_items[0][price]", price_id),
+                    ("line_items[0][quantity]", "1"),
+                    // One payment per link, so a shared link can't be paid twice
+                    ("restrictions[completed_sessions][limit]", "1"),
+                    ("metadata[preorder_id]", order_id.as_str()),
+                    (
+                        "metadata[confirmation_code]",
+                        order.confirmation_code.as_str(),
+                    ),
+                    (
+                        "payment_intent_data[metadata][preorder_id]",
+                        order_id.as_str(),
+                    ),
+                ]),
+            "create Stripe payment link",
+        )
+        .await?;
+    let (Some(external_id), Some(url)) = (created["id"].as_str(), created["url"].as_str()) else {
+        return Err(AppError::Validation(
+            "Stripe didn't return a payment link".to_string(),
+        ));
+    };
+
+    insert_payment_link(
+        &mut conn,
+        po_id,
+        Stripe::PROVIDER,
+        &NewPaymentLink {
+            external_id: external_id.to_string(),
+            url: Some(url.to_string()),
+            ..Default::default()
+        },
+        balance,
+        &currency,
+    )
+    .await
+}
+
+// Ask Stripe about every open payment link and record the ones that have been
+// paid, moving fully paid invoiced orders to paid. Links whose order was
+// cancelled or settled some other way are disabled. Meant to be polled, since
+// a desktop app can't receive Stripe's webhooks.
+#[tauri::command]
+pub async fn sync_stripe_payments(
+    app: AppHandle,
+    db: State<'_, Database>,
+    secret_key: String,
+) -> Result<PaymentSync, AppError> {
+    let stripe = Stripe::new(&secret_key)?;
+    sync_payment_links(&app, &db.pool, &stripe).await
+}
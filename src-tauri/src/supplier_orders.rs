@@ -0,0 +1,756 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, QueryBuilder, Sqlite, SqliteConnection};
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use tauri::State;
+
+use crate::audit;
+use crate::barcodes::find_product_by_barcode;
+use crate::currency::normalize_currency;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::inventory::record_movement;
+use crate::models::Product;
+use crate::products::load_product;
+
+const SUPPLIER_COLUMNS: &str = "id, name, email, phone, notes, created_at";
+
+const SUPPLIER_ORDER_COLUMNS: &str =
+    "so.id, so.supplier_id, s.name AS supplier_name, so.status, so.currency_code, so.notes, \
+     so.ordered_at, so.received_at, so.cancelled_at, so.created_at";
+
+// Uncovered customer demand per (order, product): quantities on open orders
+// minus what live supplier orders were already raised for
+const UNCOVERED_DEMAND_SELECT: &str = "SELECT oi.preorder_id, oi.product_id, \
+     SUM(oi.quantity) - COALESCE((SELECT SUM(d.quantity) FROM supplier_order_demand d \
+       JOIN supplier_order_items si ON si.id = d.supplier_order_item_id \
+       JOIN supplier_orders so ON so.id = si.supplier_order_id \
+       WHERE d.preorder_id = oi.preorder_id AND si.product_id = oi.product_id \
+       AND so.status != 'cancelled'), 0) AS uncovered \
+     FROM order_items oi \
+     JOIN preorders po ON po.id = oi.preorder_id \
+     JOIN products p ON p.id = oi.product_id \
+     WHERE po.deleted_at IS NULL \
+     AND COALESCE(po.status, 'pending') NOT IN ('fulfilled', 'cancelled')";
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Supplier {
+    pub id: i64,
+    pub name: String,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub notes: Option<String>,
+    pub created_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupplierInput {
+    pub name: String,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub notes: Option<String>,
+}
+
+// Supplier order lifecycle: Draft -> Ordered -> Received, with Cancelled
+// reachable before the goods arrive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum SupplierOrderStatus {
+    Draft,
+    Ordered,
+    Received,
+    Cancelled,
+}
+
+// Customer order quantity a supplier order item covers
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct DemandLink {
+    pub supplier_order_item_id: i64,
+    pub preorder_id: i64,
+    pub quantity: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SupplierOrderItem {
+    pub id: i64,
+    pub supplier_order_id: i64,
+    pub product_id: i64,
+    pub product_name: Option<String>,
+    pub quantity: i64,
+    pub unit_cost: Option<f64>,
+    #[sqlx(skip)]
+    pub demand: Vec<DemandLink>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SupplierOrder {
+    pub id: i64,
+    pub supplier_id: Option<i64>,
+    pub supplier_name: Option<String>,
+    pub status: SupplierOrderStatus,
+    pub currency_code: Option<String>,
+    pub notes: Option<String>,
+    pub ordered_at: Option<String>,
+    pub received_at: Option<String>,
+    pub cancelled_at: Option<String>,
+    pub created_at: Option<String>,
+    #[sqlx(skip)]
+    pub items: Vec<SupplierOrderItem>,
+}
+
+// Open customer demand for one product
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductDemand {
+    pub product_id: i64,
+    pub product_name: String,
+    pub supplier_id: Option<i64>,
+    // Not yet covered by a draft, ordered or received supplier order
+    pub uncovered_quantity: i64,
+    pub order_ids: Vec<i64>,
+}
+
+impl SupplierOrderStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SupplierOrderStatus::Draft => "draft",
+            SupplierOrderStatus::Ordered => "ordered",
+            SupplierOrderStatus::Received => "received",
+            SupplierOrderStatus::Cancelled => "cancelled",
+        }
+    }
+
+    fn can_become(&self, next: SupplierOrderStatus) -> bool {
+        use SupplierOrderStatus::*;
+        matches!(
+            (self, next),
+            (Draft, Ordered) | (Ordered, Received) | (Draft, Cancelled) | (Ordered, Cancelled)
+        )
+    }
+
+    // Column recording when an order entered this status
+    fn timestamp_column(&self) -> Option<&'static str> {
+        match self {
+            SupplierOrderStatus::Draft => None,
+            SupplierOrderStatus::Ordered => Some("ordered_at"),
+            SupplierOrderStatus::Received => Some("received_at"),
+            SupplierOrderStatus::Cancelled => Some("cancelled_at"),
+        }
+    }
+}
+
+// Helper: Load a supplier by ID
+async fn load_supplier<'e, E>(executor: E, id: i64) -> Result<Supplier, AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    sqlx::query_as::<_, Supplier>(&format!(
+        "SELECT {} FROM suppliers WHERE id = ?",
+        SUPPLIER_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| format!("Failed to load supplier: {}", e))?
+    .ok_or_else(|| AppError::NotFound(format!("Supplier {} not found", id)))
+}
+
+// Helper: Load a supplier order with its items and the demand they cover
+async fn load_supplier_order(
+    conn: &mut SqliteConnection,
+    id: i64,
+) -> Result<SupplierOrder, AppError> {
+    let mut order = sqlx::query_as::<_, SupplierOrder>(&format!(
+        "SELECT {} FROM supplier_orders so LEFT JOIN suppliers s ON s.id = so.supplier_id \
+         WHERE so.id = ?",
+        SUPPLIER_ORDER_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load supplier order: {}", e))?
+    .ok_or_else(|| AppError::NotFound(format!("Supplier order {} not found", id)))?;
+
+    let mut items = sqlx::query_as::<_, SupplierOrderItem>(
+        "SELECT si.id, si.supplier_order_id, si.product_id, p.name AS product_name, si.quantity, \
+         si.unit_cost FROM supplier_order_items si LEFT JOIN products p ON p.id = si.product_id \
+         WHERE si.supplier_order_id = ? ORDER BY si.id",
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load supplier order items: {}", e))?;
+
+    let links = sqlx::query_as::<_, DemandLink>(
+        "SELECT d.supplier_order_item_id, d.preorder_id, d.quantity FROM supplier_order_demand d \
+         JOIN supplier_order_items si ON si.id = d.supplier_order_item_id \
+         WHERE si.supplier_order_id = ? ORDER BY d.preorder_id",
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load supplier order demand: {}", e))?;
+
+    let mut grouped: HashMap<i64, Vec<DemandLink>> = HashMap::new();
+    for link in links {
+        grouped
+            .entry(link.supplier_order_item_id)
+            .or_default()
+            .push(link);
+    }
+    for item in &mut items {
+        item.demand = grouped.remove(&item.id).unwrap_or_default();
+    }
+
+    order.items = items;
+    Ok(order)
+}
+
+// Helper: Uncovered (order, product, quantity) rows, optionally limited to
+// one supplier's products and one event
+async fn uncovered_demand(
+    conn: &mut SqliteConnection,
+    supplier_id: Option<i64>,
+    event_id: Option<i64>,
+) -> Result<Vec<(i64, i64, i64)>, AppError> {
+    let mut query = QueryBuilder::<Sqlite>::new(UNCOVERED_DEMAND_SELECT);
+    if let Some(supplier_id) = supplier_id {
+        query.push(" AND p.supplier_id = ").push_bind(supplier_id);
+    }
+    if let Some(event_id) = event_id {
+        query.push(" AND po.event_id = ").push_bind(event_id);
+    }
+    query.push(
+        " GROUP BY oi.preorder_id, oi.product_id HAVING uncovered > 0 \
+         ORDER BY oi.product_id, oi.preorder_id",
+    );
+
+    query
+        .build_query_as::<(i64, i64, i64)>()
+        .fetch_all(conn)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to load open demand: {}", e)))
+}
+
+#[tauri::command]
+pub async fn create_supplier(
+    db: State<'_, Database>,
+    supplier: SupplierInput,
+) -> Result<Supplier, AppError> {
+    if supplier.name.trim().is_empty() {
+        return Err(AppError::Validation(
+            "Supplier name must not be empty".to_string(),
+        ));
+    }
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let created = sqlx::query_as::<_, Supplier>(&format!(
+        "INSERT INTO suppliers (name, email, phone, notes) VALUES (?, ?, ?, ?) RETURNING {}",
+        SUPPLIER_COLUMNS
+    ))
+    .bind(supplier.name.trim())
+    .bind(supplier.email.as_deref().map(str::trim))
+    .bind(&supplier.phone)
+    .bind(&supplier.notes)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to create supplier: {}", e))?;
+
+    audit::record(
+        &mut *tx,
+        "supplier",
+        created.id,
+        "create",
+        None,
+        Some(&created),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save supplier: {}", e))?;
+
+    Ok(created)
+}
+
+#[tauri::command]
+pub async fn list_suppliers(db: State<'_, Database>) -> Result<Vec<Supplier>, AppError> {
+    sqlx::query_as::<_, Supplier>(&format!(
+        "SELECT {} FROM suppliers ORDER BY name COLLATE NOCASE",
+        SUPPLIER_COLUMNS
+    ))
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to list suppliers: {}", e)))
+}
+
+// Set or clear (None) the supplier a product is bought from
+#[tauri::command]
+pub async fn set_product_supplier(
+    db: State<'_, Database>,
+    product_id: i64,
+    supplier_id: Option<i64>,
+) -> Result<Product, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    if let Some(supplier_id) = supplier_id {
+        load_supplier(&mut *tx, supplier_id).await?;
+    }
+    let before = load_product(&mut *tx, product_id).await?;
+
+    sqlx::query("UPDATE products SET supplier_id = ? WHERE id = ?")
+        .bind(supplier_id)
+        .bind(product_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to update product supplier: {}", e))?;
+
+    let after = load_product(&mut *tx, product_id).await?;
+    audit::record(
+        &mut *tx,
+        "product",
+        product_id,
+        "set_supplier",
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save product supplier: {}", e))?;
+
+    Ok(after)
+}
+
+// Customer demand not yet covered by a supplier order, per product
+#[tauri::command]
+pub async fn get_open_demand(
+    db: State<'_, Database>,
+    supplier_id: Option<i64>,
+    event_id: Option<i64>,
+) -> Result<Vec<ProductDemand>, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+
+    let rows = uncovered_demand(&mut conn, supplier_id, event_id).await?;
+
+    let mut demand: BTreeMap<i64, ProductDemand> = BTreeMap::new();
+    for (order_id, product_id, quantity) in rows {
+        let entry = match demand.entry(product_id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let product = load_product(&mut *conn, product_id).await?;
+                entry.insert(ProductDemand {
+                    product_id,
+                    product_name: product.name,
+                    supplier_id: product.supplier_id,
+                    uncovered_quantity: 0,
+                    order_ids: Vec::new(),
+                })
+            }
+        };
+        entry.uncovered_quantity += quantity;
+        entry.order_ids.push(order_id);
+    }
+
+    Ok(demand.into_values().collect())
+}
+
+// Raise one draft supplier order covering all open customer demand (for a
+// supplier's products and/or an event). Each item records which customer
+// orders it was raised for, so the same demand isn't ordered twice.
+// Helper: What the product cost on its latest supplier order, to start from
+async fn last_unit_cost(
+    conn: &mut SqliteConnection,
+    product_id: i64,
+) -> Result<Option<f64>, AppError> {
+    sqlx::query_scalar::<_, Option<f64>>(
+        "SELECT si.unit_cost FROM supplier_order_items si \
+         JOIN supplier_orders so ON so.id = si.supplier_order_id \
+         WHERE si.product_id = ? AND si.unit_cost IS NOT NULL AND so.status != 'cancelled' \
+         ORDER BY si.id DESC LIMIT 1",
+    )
+    .bind(product_id)
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to load last unit cost: {}", e)))
+    .map(Option::flatten)
+}
+
+#[tauri::command]
+pub async fn generate_supplier_order(
+    db: State<'_, Database>,
+    supplier_id: Option<i64>,
+    event_id: Option<i64>,
+    currency_code: Option<String>,
+    notes: Option<String>,
+) -> Result<SupplierOrder, AppError> {
+    let currency_code = currency_code
+        .as_deref()
+        .map(normalize_currency)
+        .transpose()?;
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    if let Some(supplier_id) = supplier_id {
+        load_supplier(&mut *tx, supplier_id).await?;
+    }
+
+    let rows = uncovered_demand(&mut tx, supplier_id, event_id).await?;
+    if rows.is_empty() {
+        return Err(AppError::Validation(
+            "No open customer demand to order".to_string(),
+        ));
+    }
+
+    let order_id = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO supplier_orders (supplier_id, currency_code, notes) VALUES (?, ?, ?) \
+         RETURNING id",
+    )
+    .bind(supplier_id)
+    .bind(&currency_code)
+    .bind(&notes)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to create supplier order: {}", e))?;
+
+    let mut by_product: BTreeMap<i64, Vec<(i64, i64)>> = BTreeMap::new();
+    for (preorder_id, product_id, quantity) in rows {
+        by_product
+            .entry(product_id)
+            .or_default()
+            .push((preorder_id, quantity));
+    }
+
+    for (product_id, demand) in by_product {
+        let last_cost = last_unit_cost(&mut tx, product_id).await?;
+
+        let item_id = sqlx::query_scalar::<_, i64>(
+            "INSERT INTO supplier_order_items (supplier_order_id, product_id, quantity, unit_cost) \
+             VALUES (?, ?, ?, ?) RETURNING id",
+        )
+        .bind(order_id)
+        .bind(product_id)
+        .bind(demand.iter().map(|(_, quantity)| quantity).sum::<i64>())
+        .bind(last_cost)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to add supplier order item: {}", e))?;
+
+        for (preorder_id, quantity) in demand {
+            sqlx::query(
+                "INSERT INTO supplier_order_demand (supplier_order_item_id, preorder_id, quantity) \
+                 VALUES (?, ?, ?)",
+            )
+            .bind(item_id)
+            .bind(preorder_id)
+            .bind(quantity)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to link customer demand: {}", e))?;
+        }
+    }
+
+    let created = load_supplier_order(&mut tx, order_id).await?;
+    audit::record(
+        &mut *tx,
+        "supplier_order",
+        order_id,
+        "create",
+        None,
+        Some(&created),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save supplier order: {}", e))?;
+
+    Ok(created)
+}
+
+// Supplier orders, newest first, optionally in one status
+#[tauri::command]
+pub async fn list_supplier_orders(
+    db: State<'_, Database>,
+    status: Option<SupplierOrderStatus>,
+) -> Result<Vec<SupplierOrder>, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+
+    let mut query = QueryBuilder::<Sqlite>::new("SELECT so.id FROM supplier_orders so WHERE 1 = 1");
+    if let Some(status) = status {
+        query.push(" AND so.status = ").push_bind(status);
+    }
+    query.push(" ORDER BY so.id DESC");
+
+    let ids = query
+        .build_query_scalar::<i64>()
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to list supplier orders: {}", e))?;
+
+    let mut orders = Vec::with_capacity(ids.len());
+    for id in ids {
+        orders.push(load_supplier_order(&mut conn, id).await?);
+    }
+    Ok(orders)
+}
+
+#[tauri::command]
+pub async fn get_supplier_order(
+    db: State<'_, Database>,
+    id: i64,
+) -> Result<SupplierOrder, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    load_supplier_order(&mut conn, id).await
+}
+
+// Add a scanned product to a draft supplier order, or bump its quantity if
+// it's already on the order
+#[tauri::command]
+pub async fn add_supplier_item_by_barcode(
+    db: State<'_, Database>,
+    supplier_order_id: i64,
+    barcode: String,
+    quantity: Option<i64>,
+) -> Result<SupplierOrder, AppError> {
+    let quantity = quantity.unwrap_or(1);
+    if quantity <= 0 {
+        return Err(AppError::Validation(
+            "Quantity must be positive".to_string(),
+        ));
+    }
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_supplier_order(&mut tx, supplier_order_id).await?;
+    if before.status != SupplierOrderStatus::Draft {
+        return Err(AppError::Conflict(format!(
+            "Supplier order {} is already {}",
+            supplier_order_id,
+            before.status.as_str()
+        )));
+    }
+    let product = find_product_by_barcode(&mut tx, &barcode)
+        .await?
+        .ok_or_else(|| {
+            AppError::Validation(format!("No product with barcode {}", barcode.trim()))
+        })?;
+
+    match before
+        .items
+        .iter()
+        .find(|item| item.product_id == product.id)
+    {
+        Some(item) => {
+            sqlx::query("UPDATE supplier_order_items SET quantity = quantity + ? WHERE id = ?")
+                .bind(quantity)
+                .bind(item.id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| format!("Failed to update supplier order item: {}", e))?;
+        }
+        None => {
+            let last_cost = last_unit_cost(&mut tx, product.id).await?;
+            sqlx::query(
+                "INSERT INTO supplier_order_items (supplier_order_id, product_id, quantity, unit_cost) \
+                 VALUES (?, ?, ?, ?)",
+            )
+            .bind(supplier_order_id)
+            .bind(product.id)
+            .bind(quantity)
+            .bind(last_cost)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to add supplier order item: {}", e))?;
+        }
+    }
+
+    let after = load_supplier_order(&mut tx, supplier_order_id).await?;
+    audit::record(
+        &mut *tx,
+        "supplier_order",
+        supplier_order_id,
+        "add_item",
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save supplier order: {}", e))?;
+
+    Ok(after)
+}
+
+// Record the supplier's price for an item of a draft or placed order
+#[tauri::command]
+pub async fn set_supplier_item_cost(
+    db: State<'_, Database>,
+    item_id: i64,
+    unit_cost: Option<f64>,
+) -> Result<SupplierOrder, AppError> {
+    if unit_cost.is_some_and(|cost| !cost.is_finite() || cost < 0.0) {
+        return Err(AppError::Validation(
+            "Unit cost must not be negative".to_string(),
+        ));
+    }
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let order_id = sqlx::query_scalar::<_, i64>(
+        "SELECT supplier_order_id FROM supplier_order_items WHERE id = ?",
+    )
+    .bind(item_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to load supplier order item: {}", e))?
+    .ok_or_else(|| AppError::NotFound(format!("Supplier order item {} not found", item_id)))?;
+
+    let before = load_supplier_order(&mut tx, order_id).await?;
+    if matches!(
+        before.status,
+        SupplierOrderStatus::Received | SupplierOrderStatus::Cancelled
+    ) {
+        return Err(AppError::Conflict(format!(
+            "Supplier order {} is already {}",
+            order_id,
+            before.status.as_str()
+        )));
+    }
+
+    sqlx::query("UPDATE supplier_order_items SET unit_cost = ? WHERE id = ?")
+        .bind(unit_cost)
+        .bind(item_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to update unit cost: {}", e))?;
+
+    let after = load_supplier_order(&mut tx, order_id).await?;
+    audit::record(
+        &mut *tx,
+        "supplier_order",
+        order_id,
+        "set_item_cost",
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save unit cost: {}", e))?;
+
+    Ok(after)
+}
+
+// Move a supplier order through its lifecycle. Receiving adds the items to
+// stock for products whose stock is tracked; cancelling frees the customer
+// demand it covered so it can be ordered again.
+#[tauri::command]
+pub async fn set_supplier_order_status(
+    db: State<'_, Database>,
+    id: i64,
+    status: SupplierOrderStatus,
+) -> Result<SupplierOrder, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_supplier_order(&mut tx, id).await?;
+    if !before.status.can_become(status) {
+        return Err(AppError::Validation(format!(
+            "Cannot change supplier order from {} to {}",
+            before.status.as_str(),
+            status.as_str()
+        )));
+    }
+
+    let timestamp = status
+        .timestamp_column()
+        .map(|column| format!(", {} = CURRENT_TIMESTAMP", column))
+        .unwrap_or_default();
+    sqlx::query(&format!(
+        "UPDATE supplier_orders SET status = ?{} WHERE id = ?",
+        timestamp
+    ))
+    .bind(status)
+    .bind(id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to update supplier order: {}", e))?;
+
+    if status == SupplierOrderStatus::Received {
+        let note = format!("Supplier order #{}", id);
+        for item in &before.items {
+            let tracked = load_product(&mut *tx, item.product_id)
+                .await?
+                .stock_quantity
+                .is_some();
+            if tracked {
+                record_movement(
+                    &mut tx,
+                    item.product_id,
+                    item.quantity,
+                    "supplier_received",
+                    None,
+                    Some(&note),
+                )
+                .await?;
+            }
+        }
+    }
+
+    let after = load_supplier_order(&mut tx, id).await?;
+    audit::record(
+        &mut *tx,
+        "supplier_order",
+        id,
+        status.as_str(),
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save supplier order: {}", e))?;
+
+    Ok(after)
+}
@@ -0,0 +1,242 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::error::AppError;
+use crate::log_warning;
+use crate::redact::redact;
+
+// Finished tasks kept for get_task, oldest dropped first
+const MAX_FINISHED_TASKS: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+// A background task as the UI sees it. Sent with every "task-progress" event
+// and once more as "task-finished".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskInfo {
+    pub id: u64,
+    // What kind of work this is, e.g. "invoice_export"
+    pub kind: String,
+    pub label: String,
+    pub status: TaskStatus,
+    pub done: u64,
+    // None until the task knows how much work there is
+    pub total: Option<u64>,
+    pub message: Option<String>,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    // What the command would have returned, once completed
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+}
+
+struct TaskEntry {
+    info: TaskInfo,
+    cancel: Arc<AtomicBool>,
+}
+
+// Running and recently finished tasks, as Tauri managed state
+#[derive(Default)]
+pub struct TaskRegistry {
+    next_id: AtomicU64,
+    tasks: Mutex<HashMap<u64, TaskEntry>>,
+}
+
+// Given to the work of a task to report progress and notice cancellation
+#[derive(Clone)]
+pub struct TaskHandle {
+    app: AppHandle,
+    id: u64,
+    cancel: Arc<AtomicBool>,
+}
+
+impl TaskRegistry {
+    // Helper: Change a task's info, returning the updated copy
+    fn update(&self, id: u64, change: impl FnOnce(&mut TaskInfo)) -> Option<TaskInfo> {
+        let mut tasks = self.tasks.lock().ok()?;
+        let entry = tasks.get_mut(&id)?;
+        change(&mut entry.info);
+        Some(entry.info.clone())
+    }
+
+    // Helper: Drop the oldest finished tasks beyond MAX_FINISHED_TASKS
+    fn prune(tasks: &mut HashMap<u64, TaskEntry>) {
+        let mut finished: Vec<u64> = tasks
+            .values()
+            .filter(|entry| entry.info.status != TaskStatus::Running)
+            .map(|entry| entry.info.id)
+            .collect();
+        if finished.len() <= MAX_FINISHED_TASKS {
+            return;
+        }
+        finished.sort_unstable();
+        for id in &finished[..finished.len() - MAX_FINISHED_TASKS] {
+            tasks.remove(id);
+        }
+    }
+}
+
+impl TaskHandle {
+    // Record how far the task has got and tell the UI
+    pub fn progress(&self, done: u64, total: Option<u64>, message: impl Into<String>) {
+        let message = message.into();
+        let updated = self.app.state::<TaskRegistry>().update(self.id, |info| {
+            info.done = done;
+            info.total = total;
+            info.message = Some(message);
+        });
+        if let Some(info) = updated {
+            if let Err(e) = self.app.emit("task-progress", info) {
+                log_warning!("Failed to emit task-progress event: {}", e);
+            }
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    // Stop the work with an error if the user cancelled it. Call between
+    // steps; a step already under way finishes first.
+    pub fn check_cancelled(&self) -> Result<(), AppError> {
+        if self.is_cancelled() {
+            Err(AppError::Cancelled("Cancelled".to_string()))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// Run `work` in the background as a tracked task and return straight away.
+// Its progress is emitted as "task-progress" and its outcome as
+// "task-finished"; either way it stays available to get_task for a while.
+pub fn spawn_task<F, Fut, T>(app: &AppHandle, kind: &str, label: String, work: F) -> TaskInfo
+where
+    F: FnOnce(TaskHandle) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<T, AppError>> + Send + 'static,
+    T: Serialize,
+{
+    let registry = app.state::<TaskRegistry>();
+    let id = registry.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+    let info = TaskInfo {
+        id,
+        kind: kind.to_string(),
+        label,
+        status: TaskStatus::Running,
+        done: 0,
+        total: None,
+        message: None,
+        started_at: chrono::Utc::now().to_rfc3339(),
+        finished_at: None,
+        result: None,
+        error: None,
+    };
+    let cancel = Arc::new(AtomicBool::new(false));
+    if let Ok(mut tasks) = registry.tasks.lock() {
+        tasks.insert(
+            id,
+            TaskEntry {
+                info: info.clone(),
+                cancel: cancel.clone(),
+            },
+        );
+    }
+
+    let handle = TaskHandle {
+        app: app.clone(),
+        id,
+        cancel,
+    };
+    tauri::async_runtime::spawn(async move {
+        let outcome = work(handle.clone()).await.and_then(|result| {
+            serde_json::to_value(result)
+                .map_err(|e| AppError::Internal(format!("Failed to serialize result: {}", e)))
+        });
+
+        let registry = handle.app.state::<TaskRegistry>();
+        let finished = registry.update(id, |info| {
+            info.finished_at = Some(chrono::Utc::now().to_rfc3339());
+            match outcome {
+                Ok(result) => {
+                    info.status = TaskStatus::Completed;
+                    info.result = Some(result);
+                }
+                Err(_) if handle.is_cancelled() => info.status = TaskStatus::Cancelled,
+                Err(e) => {
+                    info.status = TaskStatus::Failed;
+                    info.error = Some(redact(&e.to_string()));
+                }
+            }
+        });
+        if let Ok(mut tasks) = registry.tasks.lock() {
+            TaskRegistry::prune(&mut tasks);
+        }
+        if let Some(info) = finished {
+            if let Err(e) = handle.app.emit("task-finished", info) {
+                log_warning!("Failed to emit task-finished event: {}", e);
+            }
+        }
+    });
+    info
+}
+
+// Tasks still running, oldest first
+#[tauri::command]
+pub fn list_active_tasks(registry: State<'_, TaskRegistry>) -> Result<Vec<TaskInfo>, AppError> {
+    let tasks = registry
+        .tasks
+        .lock()
+        .map_err(|e| format!("Failed to read tasks: {}", e))?;
+    let mut active: Vec<TaskInfo> = tasks
+        .values()
+        .filter(|entry| entry.info.status == TaskStatus::Running)
+        .map(|entry| entry.info.clone())
+        .collect();
+    active.sort_by_key(|info| info.id);
+    Ok(active)
+}
+
+// A running or recently finished task, with its result once completed
+#[tauri::command]
+pub fn get_task(registry: State<'_, TaskRegistry>, id: u64) -> Result<TaskInfo, AppError> {
+    let tasks = registry
+        .tasks
+        .lock()
+        .map_err(|e| format!("Failed to read tasks: {}", e))?;
+    tasks
+        .get(&id)
+        .map(|entry| entry.info.clone())
+        .ok_or_else(|| AppError::NotFound(format!("Task {} not found", id)))
+}
+
+// Ask a running task to stop. It ends as cancelled once it reaches a point
+// where stopping is safe.
+#[tauri::command]
+pub fn cancel_task(registry: State<'_, TaskRegistry>, id: u64) -> Result<(), AppError> {
+    let tasks = registry
+        .tasks
+        .lock()
+        .map_err(|e| format!("Failed to read tasks: {}", e))?;
+    let entry = tasks
+        .get(&id)
+        .ok_or_else(|| AppError::NotFound(format!("Task {} not found", id)))?;
+    if entry.info.status != TaskStatus::Running {
+        return Err(AppError::Conflict(format!(
+            "Task {} has already finished",
+            id
+        )));
+    }
+    entry.cancel.store(true, Ordering::Relaxed);
+    Ok(())
+}
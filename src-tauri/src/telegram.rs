@@ -0,0 +1,382 @@
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use tauri::{AppHandle, Manager, State};
+
+use crate::audit;
+use crate::automation::{self, Poller};
+use crate::db::Database;
+use crate::domain_events::{DomainEvent, Subscriber};
+use crate::encryption::{conceal_opt, reveal_opt};
+use crate::error::AppError;
+use crate::http;
+use crate::i18n::load_locale;
+use crate::log_warning;
+use crate::money::money_format;
+use crate::payments::Payment;
+
+const API_BASE: &str = "https://api.telegram.org";
+
+// Sends per message before it's marked failed
+const MAX_ATTEMPTS: i64 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum TelegramEvent {
+    NewOrder,
+    Payment,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TelegramSettings {
+    pub enabled: bool,
+    // From @BotFather, "123456:ABC-..."
+    pub bot_token: Option<String>,
+    // Numeric chat ID, or @channelname for a channel
+    pub chat_id: Option<String>,
+    pub notify_new_order: bool,
+    pub notify_payment: bool,
+}
+
+// Helper: Telegram settings, disabled defaults if the row is missing
+async fn load_settings(conn: &mut SqliteConnection) -> Result<TelegramSettings, AppError> {
+    let settings = sqlx::query_as::<_, TelegramSettings>(
+        "SELECT enabled, bot_token, chat_id, notify_new_order, notify_payment \
+         FROM telegram_settings WHERE id = 1",
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| format!("Failed to load Telegram settings: {}", e))?
+    .unwrap_or(TelegramSettings {
+        enabled: false,
+        bot_token: None,
+        chat_id: None,
+        notify_new_order: true,
+        notify_payment: true,
+    });
+    Ok(TelegramSettings {
+        bot_token: reveal_opt(settings.bot_token)?,
+        ..settings
+    })
+}
+
+impl TelegramSettings {
+    // Bot token and chat to send to, if messages of this kind should go out
+    fn target(&self, event: Option<TelegramEvent>) -> Option<(&str, &str)> {
+        let wanted = match event {
+            Some(TelegramEvent::NewOrder) => self.notify_new_order,
+            Some(TelegramEvent::Payment) => self.notify_payment,
+            None => true,
+        };
+        let token = self.bot_token.as_deref().filter(|t| !t.trim().is_empty())?;
+        let chat_id = self.chat_id.as_deref().filter(|c| !c.trim().is_empty())?;
+        (self.enabled && wanted).then_some((token.trim(), chat_id.trim()))
+    }
+}
+
+// Helper: Post one message through the Bot API
+async fn send_message(
+    client: &Client,
+    bot_token: &str,
+    chat_id: &str,
+    text: &str,
+) -> Result<(), AppError> {
+    let response = client
+        .post(format!("{}/bot{}/sendMessage", API_BASE, bot_token))
+        .json(&serde_json::json!({
+            "chat_id": chat_id,
+            "text": text,
+            "disable_web_page_preview": true,
+        }))
+        .send()
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to reach Telegram: {}", e.without_url())))?;
+
+    let status = response.status();
+    let body: serde_json::Value = response.json().await.unwrap_or_default();
+    if status.is_success() && body["ok"].as_bool().unwrap_or(false) {
+        Ok(())
+    } else {
+        Err(AppError::from_status(
+            status.as_u16(),
+            format!(
+                "Telegram rejected the message ({}): {}",
+                status,
+                body["description"].as_str().unwrap_or("no description")
+            ),
+        ))
+    }
+}
+
+// Helper: Queue a message. Runs in the caller's transaction, so a
+// rolled-back change never gets announced.
+async fn queue_message(
+    conn: &mut SqliteConnection,
+    event: TelegramEvent,
+    po_id: i64,
+    text: &str,
+) -> Result<(), AppError> {
+    sqlx::query("INSERT INTO telegram_messages (event, preorder_id, text) VALUES (?, ?, ?)")
+        .bind(event)
+        .bind(po_id)
+        .bind(text)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to queue Telegram message: {}", e))?;
+    Ok(())
+}
+
+// Helper: Code, customer and currency of an order, for message text
+async fn order_summary(
+    conn: &mut SqliteConnection,
+    po_id: i64,
+) -> Result<(String, String, f64, Option<String>), AppError> {
+    sqlx::query_as::<_, (String, String, f64, Option<String>)>(
+        "SELECT confirmation_code, customer_name, total_amount, currency_code \
+         FROM preorders WHERE id = ?",
+    )
+    .bind(po_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load order: {}", e))?
+    .ok_or_else(|| AppError::NotFound(format!("Order {} not found", po_id)))
+}
+
+// Helper: Announce a newly created order
+async fn notify_new_order(conn: &mut SqliteConnection, po_id: i64) -> Result<(), AppError> {
+    let settings = load_settings(&mut *conn).await?;
+    if settings.target(Some(TelegramEvent::NewOrder)).is_none() {
+        return Ok(());
+    }
+
+    let (code, customer, total, currency) = order_summary(&mut *conn, po_id).await?;
+    let money = money_format(&mut *conn, currency.as_deref()).await?;
+    let text = load_locale(&mut *conn).await.format(
+        "notify.new_order",
+        &[
+            ("code", &code),
+            ("customer", &customer),
+            ("total", &money.format(total)),
+        ],
+    );
+    queue_message(conn, TelegramEvent::NewOrder, po_id, &text).await
+}
+
+// Helper: Announce a recorded payment
+async fn notify_payment(conn: &mut SqliteConnection, payment: &Payment) -> Result<(), AppError> {
+    let settings = load_settings(&mut *conn).await?;
+    if settings.target(Some(TelegramEvent::Payment)).is_none() {
+        return Ok(());
+    }
+
+    let (code, customer, _, currency) = order_summary(&mut *conn, payment.preorder_id).await?;
+    let money = money_format(&mut *conn, currency.as_deref()).await?;
+    let text = load_locale(&mut *conn).await.format(
+        "notify.payment",
+        &[
+            ("amount", &money.format(payment.amount)),
+            ("code", &code),
+            ("customer", &customer),
+            ("method", &payment.method),
+        ],
+    );
+    queue_message(conn, TelegramEvent::Payment, payment.preorder_id, &text).await
+}
+
+// Announces new orders and payments to the owner's chat
+pub struct Telegram;
+
+impl Subscriber for Telegram {
+    async fn handle(
+        &self,
+        conn: &mut SqliteConnection,
+        event: &DomainEvent<'_>,
+    ) -> Result<(), AppError> {
+        match *event {
+            DomainEvent::OrderCreated { order_id } => notify_new_order(conn, order_id).await,
+            DomainEvent::PaymentRecorded(payment) => notify_payment(conn, payment).await,
+            _ => Ok(()),
+        }
+    }
+}
+
+// Helper: Send queued messages. Ones whose kind was switched off after they
+// were queued are marked failed instead of sent.
+pub async fn send_pending_messages(pool: &SqlitePool) -> Result<(), AppError> {
+    let batch = automation::send_batch_size(pool).await?;
+    if batch == 0 {
+        return Ok(());
+    }
+    let pending = sqlx::query_as::<_, (i64, TelegramEvent, String, i64)>(
+        "SELECT id, event, text, attempts FROM telegram_messages \
+         WHERE status = 'pending' ORDER BY id LIMIT ?",
+    )
+    .bind(batch)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load Telegram messages: {}", e))?;
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let settings = load_settings(&mut conn).await?;
+    drop(conn);
+
+    automation::record_sends(pool, pending.len()).await?;
+    let client = http::client();
+    for (id, event, text, attempts) in pending {
+        let result = match settings.target(Some(event)) {
+            Some((token, chat_id)) => send_message(&client, token, chat_id, &text).await,
+            None => Err(AppError::Validation(
+                "Telegram notifications are switched off".to_string(),
+            )),
+        };
+        let attempts = attempts + 1;
+        let status = match &result {
+            Ok(()) => "sent",
+            Err(_) if attempts < MAX_ATTEMPTS && settings.target(Some(event)).is_some() => {
+                "pending"
+            }
+            Err(_) => "failed",
+        };
+        sqlx::query(
+            "UPDATE telegram_messages SET status = ?, attempts = ?, last_error = ?, \
+             sent_at = CASE WHEN ? = 'sent' THEN CURRENT_TIMESTAMP END WHERE id = ?",
+        )
+        .bind(status)
+        .bind(attempts)
+        .bind(result.err().map(|e| e.to_string()))
+        .bind(status)
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to update Telegram message: {}", e))?;
+    }
+    Ok(())
+}
+
+// Start the background loop that sends queued Telegram messages
+pub fn start_telegram_sender(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<Database>().pool.clone();
+        loop {
+            if let Err(e) = send_pending_messages(&pool).await {
+                log_warning!("Telegram sender failed: {}", e);
+            }
+            automation::wait_for_next_poll(&pool, Poller::Sender).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_telegram_settings(db: State<'_, Database>) -> Result<TelegramSettings, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    load_settings(&mut conn).await
+}
+
+#[tauri::command]
+pub async fn set_telegram_settings(
+    db: State<'_, Database>,
+    settings: TelegramSettings,
+) -> Result<TelegramSettings, AppError> {
+    let bot_token = settings
+        .bot_token
+        .as_deref()
+        .map(str::trim)
+        .filter(|t| !t.is_empty());
+    let chat_id = settings
+        .chat_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty());
+    if settings.enabled && (bot_token.is_none() || chat_id.is_none()) {
+        return Err(AppError::Validation(
+            "A bot token and chat ID are needed to turn Telegram on".to_string(),
+        ));
+    }
+    if bot_token.is_some_and(|t| !t.contains(':')) {
+        return Err(AppError::Validation(
+            "That doesn't look like a bot token from @BotFather".to_string(),
+        ));
+    }
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_settings(&mut tx).await?;
+    sqlx::query(
+        "INSERT OR REPLACE INTO telegram_settings \
+         (id, enabled, bot_token, chat_id, notify_new_order, notify_payment, updated_at) \
+         VALUES (1, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP)",
+    )
+    .bind(settings.enabled)
+    .bind(conceal_opt(bot_token)?)
+    .bind(chat_id)
+    .bind(settings.notify_new_order)
+    .bind(settings.notify_payment)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save Telegram settings: {}", e))?;
+    let after = load_settings(&mut tx).await?;
+
+    // The token stays out of the audit log
+    let view = |s: &TelegramSettings| {
+        serde_json::json!({
+            "enabled": s.enabled,
+            "chat_id": s.chat_id,
+            "notify_new_order": s.notify_new_order,
+            "notify_payment": s.notify_payment,
+        })
+    };
+    audit::record(
+        &mut *tx,
+        "telegram_settings",
+        1,
+        "update",
+        Some(&view(&before)),
+        Some(&view(&after)),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save Telegram settings: {}", e))?;
+    Ok(after)
+}
+
+// Send a message to the configured chat right away, e.g. to check the setup.
+// Without `text` a short test message is sent.
+#[tauri::command]
+pub async fn send_telegram_notification(
+    db: State<'_, Database>,
+    text: Option<String>,
+) -> Result<(), AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let settings = load_settings(&mut conn).await?;
+    let text = match text.filter(|t| !t.trim().is_empty()) {
+        Some(text) => text,
+        None => load_locale(&mut *conn).await.text("notify.test"),
+    };
+    drop(conn);
+
+    let (token, chat_id) = settings
+        .target(None)
+        .ok_or("Telegram isn't set up; add a bot token and chat ID and turn it on")?;
+    send_message(&http::client(), token, chat_id, &text).await
+}
@@ -0,0 +1,220 @@
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use crate::error::AppError;
+use crate::log_warning;
+
+const WINDOW: Duration = Duration::from_secs(60);
+// How often a raised level is checked again
+const RECHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+// Share of the budget used in the last minute at which the UI is told calls
+// are about to slow down
+const HIGH_PRESSURE: f64 = 0.8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GoogleApi {
+    Forms,
+    Drive,
+    Gmail,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PressureLevel {
+    Normal,
+    // Close to the budget
+    High,
+    // At the budget; calls wait for a free slot
+    Throttled,
+}
+
+// Sent as "quota-pressure" whenever an API's level changes, so the UI can
+// show "syncing slowly"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaPressure {
+    pub api: GoogleApi,
+    pub level: PressureLevel,
+    // Calls in the last minute, and the most allowed
+    pub used: usize,
+    pub limit: usize,
+}
+
+struct Window {
+    calls: VecDeque<Instant>,
+    level: PressureLevel,
+}
+
+impl Window {
+    // Forget calls older than a minute
+    fn prune(&mut self) {
+        while self.calls.front().is_some_and(|call| call.elapsed() >= WINDOW) {
+            self.calls.pop_front();
+        }
+    }
+}
+
+// Calls made to one API in the last minute
+struct Throttle {
+    api: GoogleApi,
+    // Calls allowed per minute, below Google's per-user limits so 429s stay rare
+    limit: usize,
+    // Held while waiting for a slot; the async mutex is fair, so callers get
+    // their turn in the order they arrived
+    queue: tokio::sync::Mutex<()>,
+    window: Mutex<Window>,
+}
+
+impl Throttle {
+    const fn new(api: GoogleApi, limit: usize) -> Self {
+        Throttle {
+            api,
+            limit,
+            queue: tokio::sync::Mutex::const_new(()),
+            window: Mutex::new(Window {
+                calls: VecDeque::new(),
+                level: PressureLevel::Normal,
+            }),
+        }
+    }
+}
+
+static THROTTLES: [Throttle; 3] = [
+    Throttle::new(GoogleApi::Forms, 240),
+    Throttle::new(GoogleApi::Drive, 600),
+    Throttle::new(GoogleApi::Gmail, 120),
+];
+
+// Set on startup for the pressure events
+static APP: OnceLock<AppHandle> = OnceLock::new();
+
+pub fn init(app: &AppHandle) {
+    let _ = APP.set(app.clone());
+}
+
+// Helper: The API a request goes to, None for ones that aren't throttled
+fn api_of(url: &Url) -> Option<GoogleApi> {
+    let host = url.host_str()?;
+    let path = url.path();
+    match host {
+        "forms.googleapis.com" => Some(GoogleApi::Forms),
+        "gmail.googleapis.com" => Some(GoogleApi::Gmail),
+        "www.googleapis.com" if path.starts_with("/drive") || path.starts_with("/upload/drive") => {
+            Some(GoogleApi::Drive)
+        }
+        _ => None,
+    }
+}
+
+// Helper: Level for `used` calls out of `limit`
+fn level_of(used: usize, limit: usize) -> PressureLevel {
+    if used >= limit {
+        PressureLevel::Throttled
+    } else if used as f64 >= limit as f64 * HIGH_PRESSURE {
+        PressureLevel::High
+    } else {
+        PressureLevel::Normal
+    }
+}
+
+// Helper: Note the level, telling the UI if it changed. Once it rises, it's
+// checked again every few seconds so the UI hears when it drops back even if
+// no more calls are made.
+fn set_level(throttle: &'static Throttle, window: &mut Window, level: PressureLevel) {
+    if window.level == level {
+        return;
+    }
+    if window.level == PressureLevel::Normal {
+        schedule_recheck(throttle);
+    }
+    window.level = level;
+    let pressure = QuotaPressure {
+        api: throttle.api,
+        level,
+        used: window.calls.len(),
+        limit: throttle.limit,
+    };
+    if let Some(app) = APP.get() {
+        if let Err(e) = app.emit("quota-pressure", pressure) {
+            log_warning!("Failed to emit quota-pressure event: {}", e);
+        }
+    }
+}
+
+// Helper: Recompute the level until it's back to normal
+fn schedule_recheck(throttle: &'static Throttle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(RECHECK_INTERVAL).await;
+            let Ok(mut window) = throttle.window.lock() else {
+                return;
+            };
+            window.prune();
+            let level = level_of(window.calls.len(), throttle.limit);
+            set_level(throttle, &mut window, level);
+            if level == PressureLevel::Normal {
+                return;
+            }
+        }
+    });
+}
+
+// Wait until a call to the API behind `url` fits in its per-minute budget,
+// then count it. Calls to other services go straight through.
+pub async fn acquire(url: &Url) {
+    let Some(api) = api_of(url) else {
+        return;
+    };
+    let Some(throttle) = THROTTLES.iter().find(|throttle| throttle.api == api) else {
+        return;
+    };
+
+    let _turn = throttle.queue.lock().await;
+    loop {
+        let wait = {
+            let Ok(mut window) = throttle.window.lock() else {
+                return;
+            };
+            window.prune();
+            if window.calls.len() < throttle.limit {
+                window.calls.push_back(Instant::now());
+                let level = level_of(window.calls.len(), throttle.limit);
+                set_level(throttle, &mut window, level);
+                return;
+            }
+            set_level(throttle, &mut window, PressureLevel::Throttled);
+            window
+                .calls
+                .front()
+                .map_or(Duration::ZERO, |oldest| WINDOW.saturating_sub(oldest.elapsed()))
+        };
+        tokio::time::sleep(wait).await;
+    }
+}
+
+// Current use of each API's budget
+#[tauri::command]
+pub fn get_quota_pressure() -> Result<Vec<QuotaPressure>, AppError> {
+    THROTTLES
+        .iter()
+        .map(|throttle| {
+            let mut window = throttle
+                .window
+                .lock()
+                .map_err(|e| format!("Failed to read quota use: {}", e))?;
+            window.prune();
+            let used = window.calls.len();
+            Ok(QuotaPressure {
+                api: throttle.api,
+                level: level_of(used, throttle.limit),
+                used,
+                limit: throttle.limit,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,376 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, Sqlite, SqliteConnection};
+use tauri::State;
+
+use crate::audit;
+use crate::db::Database;
+use crate::domain_events::{publish_now, DomainEvent, Subscriber};
+use crate::error::AppError;
+use crate::money::money_format;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum EmailChannel {
+    Smtp,
+    Gmail,
+    // Sent by the sync microservice on the frontend's behalf
+    Microservice,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct OrderNote {
+    pub id: i64,
+    pub preorder_id: i64,
+    pub body: String,
+    pub author: Option<String>,
+    pub created_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimelineKind {
+    Created,
+    StatusChange,
+    Note,
+    Email,
+    Payment,
+    Refund,
+    CreditNote,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineEntry {
+    pub at: String,
+    pub kind: TimelineKind,
+    pub summary: String,
+    // Kind-specific fields (note ID, payment amount, form title, ...)
+    pub details: serde_json::Value,
+}
+
+const NOTE_COLUMNS: &str = "id, preorder_id, body, author, created_at";
+
+// Helper: Log an email sent about an order
+async fn record_email<'e, E>(
+    executor: E,
+    po_id: i64,
+    to_email: &str,
+    subject: &str,
+    channel: EmailChannel,
+) -> Result<(), AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    sqlx::query(
+        "INSERT INTO order_emails (preorder_id, to_email, subject, channel) VALUES (?, ?, ?, ?)",
+    )
+    .bind(po_id)
+    .bind(to_email.trim())
+    .bind(subject)
+    .bind(channel)
+    .execute(executor)
+    .await
+    .map_err(|e| format!("Failed to record sent email: {}", e))?;
+    Ok(())
+}
+
+// Logs emails about an order on its timeline
+pub struct Timeline;
+
+impl Subscriber for Timeline {
+    async fn handle(
+        &self,
+        conn: &mut SqliteConnection,
+        event: &DomainEvent<'_>,
+    ) -> Result<(), AppError> {
+        match *event {
+            DomainEvent::EmailSent {
+                order_id: Some(order_id),
+                to_email,
+                subject,
+                channel,
+            } => record_email(conn, order_id, to_email, subject, channel).await,
+            _ => Ok(()),
+        }
+    }
+}
+
+// Record an email the frontend sent itself (e.g. through the sync microservice)
+#[tauri::command]
+pub async fn record_order_email(
+    db: State<'_, Database>,
+    po_id: i64,
+    to_email: String,
+    subject: String,
+    channel: EmailChannel,
+) -> Result<(), AppError> {
+    let email_sent = DomainEvent::EmailSent {
+        order_id: Some(po_id),
+        to_email: &to_email,
+        subject: &subject,
+        channel,
+    };
+    publish_now(&db.pool, &email_sent).await
+}
+
+#[tauri::command]
+pub async fn add_order_note(
+    db: State<'_, Database>,
+    po_id: i64,
+    body: String,
+) -> Result<OrderNote, AppError> {
+    let body = body.trim();
+    if body.is_empty() {
+        return Err(AppError::Validation("Note must not be empty".to_string()));
+    }
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let note = sqlx::query_as::<_, OrderNote>(&format!(
+        "INSERT INTO order_notes (preorder_id, body, author) \
+         SELECT id, ?, (SELECT user_email FROM google_auth WHERE id = 1) FROM preorders WHERE id = ? \
+         RETURNING {}",
+        NOTE_COLUMNS
+    ))
+    .bind(body)
+    .bind(po_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to add note: {}", e))?
+    .ok_or_else(|| AppError::NotFound(format!("Order {} not found", po_id)))?;
+
+    audit::record(&mut *tx, "order_note", note.id, "create", None, Some(&note)).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save note: {}", e))?;
+
+    Ok(note)
+}
+
+#[tauri::command]
+pub async fn delete_order_note(db: State<'_, Database>, note_id: i64) -> Result<String, AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let note = sqlx::query_as::<_, OrderNote>(&format!(
+        "DELETE FROM order_notes WHERE id = ? RETURNING {}",
+        NOTE_COLUMNS
+    ))
+    .bind(note_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to delete note: {}", e))?
+    .ok_or_else(|| AppError::NotFound(format!("Note {} not found", note_id)))?;
+
+    audit::record(&mut *tx, "order_note", note.id, "delete", Some(&note), None).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to delete note: {}", e))?;
+
+    Ok("Note deleted".to_string())
+}
+
+// Everything that happened to an order, oldest first: how it was created,
+// status changes, notes, emails and payments
+#[tauri::command]
+pub async fn get_order_timeline(
+    db: State<'_, Database>,
+    po_id: i64,
+) -> Result<Vec<TimelineEntry>, AppError> {
+    let (created_at, recurring_order_id, currency_code) =
+        sqlx::query_as::<_, (Option<String>, Option<i64>, Option<String>)>(
+            "SELECT created_at, recurring_order_id, currency_code FROM preorders WHERE id = ?",
+        )
+        .bind(po_id)
+        .fetch_optional(&db.pool)
+        .await
+        .map_err(|e| format!("Failed to load order: {}", e))?
+        .ok_or_else(|| AppError::NotFound(format!("Order {} not found", po_id)))?;
+
+    let form_response = sqlx::query_as::<_, (String, String, Option<String>)>(
+        "SELECT r.response_id, r.form_id, f.title FROM synced_responses r \
+         LEFT JOIN google_forms f ON f.form_id = r.form_id WHERE r.preorder_id = ? LIMIT 1",
+    )
+    .bind(po_id)
+    .fetch_optional(&db.pool)
+    .await
+    .map_err(|e| format!("Failed to load form response: {}", e))?;
+
+    let mut entries = Vec::new();
+
+    let (summary, details) = match (form_response, recurring_order_id) {
+        (Some((response_id, form_id, _)), _) if form_id.starts_with("legacy:") => (
+            "Migrated from legacy spreadsheet".to_string(),
+            serde_json::json!({ "source": "legacy", "source_id": form_id, "response_id": response_id }),
+        ),
+        (Some((response_id, form_id, title)), _) => (
+            format!(
+                "Imported from Google Form \"{}\"",
+                title.as_deref().unwrap_or(&form_id)
+            ),
+            serde_json::json!({ "source": "google_form", "form_id": form_id, "response_id": response_id }),
+        ),
+        (None, Some(recurring_id)) => (
+            format!("Generated from recurring order #{}", recurring_id),
+            serde_json::json!({ "source": "recurring", "recurring_order_id": recurring_id }),
+        ),
+        (None, None) => (
+            "Order created".to_string(),
+            serde_json::json!({ "source": "manual" }),
+        ),
+    };
+    entries.push(TimelineEntry {
+        at: created_at.unwrap_or_default(),
+        kind: TimelineKind::Created,
+        summary,
+        details,
+    });
+
+    let history = sqlx::query_as::<_, (Option<String>, String, String, String)>(
+        "SELECT created_at, from_status, to_status, event FROM order_status_history \
+         WHERE preorder_id = ? ORDER BY id",
+    )
+    .bind(po_id)
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| format!("Failed to load status history: {}", e))?;
+
+    for (at, from, to, event) in history {
+        entries.push(TimelineEntry {
+            at: at.unwrap_or_default(),
+            kind: TimelineKind::StatusChange,
+            summary: format!("Status changed from {} to {}", from, to),
+            details: serde_json::json!({ "from": from, "to": to, "event": event }),
+        });
+    }
+
+    let notes = sqlx::query_as::<_, OrderNote>(&format!(
+        "SELECT {} FROM order_notes WHERE preorder_id = ? ORDER BY id",
+        NOTE_COLUMNS
+    ))
+    .bind(po_id)
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| format!("Failed to load notes: {}", e))?;
+
+    for note in notes {
+        entries.push(TimelineEntry {
+            at: note.created_at.clone().unwrap_or_default(),
+            kind: TimelineKind::Note,
+            summary: note.body.clone(),
+            details: serde_json::json!({ "note_id": note.id, "author": note.author }),
+        });
+    }
+
+    let emails = sqlx::query_as::<_, (Option<String>, String, String, EmailChannel)>(
+        "SELECT sent_at, to_email, subject, channel FROM order_emails \
+         WHERE preorder_id = ? ORDER BY id",
+    )
+    .bind(po_id)
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| format!("Failed to load sent emails: {}", e))?;
+
+    for (at, to_email, subject, channel) in emails {
+        entries.push(TimelineEntry {
+            at: at.unwrap_or_default(),
+            kind: TimelineKind::Email,
+            summary: format!("Emailed \"{}\" to {}", subject, to_email),
+            details: serde_json::json!({ "to": to_email, "subject": subject, "channel": channel }),
+        });
+    }
+
+    let money = {
+        let mut conn = db
+            .pool
+            .acquire()
+            .await
+            .map_err(|e| format!("Failed to open database connection: {}", e))?;
+        money_format(&mut conn, currency_code.as_deref()).await?
+    };
+    let payments = sqlx::query_as::<_, (i64, String, f64, String, Option<String>)>(
+        // Payment dates are user-entered; normalize them so they sort with the rest
+        "SELECT id, COALESCE(datetime(paid_at), paid_at), amount, method, reference FROM payments \
+         WHERE preorder_id = ? ORDER BY paid_at, id",
+    )
+    .bind(po_id)
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| format!("Failed to load payments: {}", e))?;
+
+    for (id, at, amount, method, reference) in payments {
+        entries.push(TimelineEntry {
+            at,
+            kind: TimelineKind::Payment,
+            summary: format!(
+                "Payment of {} received via {}",
+                money.format(amount),
+                method
+            ),
+            details: serde_json::json!({
+                "payment_id": id,
+                "amount": amount,
+                "method": method,
+                "reference": reference,
+            }),
+        });
+    }
+
+    let refunds = sqlx::query_as::<_, (i64, String, f64, String, Option<String>)>(
+        "SELECT id, COALESCE(datetime(refunded_at), refunded_at), amount, method, reference \
+         FROM refunds WHERE preorder_id = ? ORDER BY refunded_at, id",
+    )
+    .bind(po_id)
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| format!("Failed to load refunds: {}", e))?;
+
+    for (id, at, amount, method, reference) in refunds {
+        entries.push(TimelineEntry {
+            at,
+            kind: TimelineKind::Refund,
+            summary: format!("Refunded {} via {}", money.format(amount), method),
+            details: serde_json::json!({
+                "refund_id": id,
+                "amount": amount,
+                "method": method,
+                "reference": reference,
+            }),
+        });
+    }
+
+    let credit_notes = sqlx::query_as::<_, (i64, Option<String>, String, f64)>(
+        "SELECT id, issued_at, credit_note_number, total_amount FROM credit_notes \
+         WHERE preorder_id = ? ORDER BY id",
+    )
+    .bind(po_id)
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| format!("Failed to load credit notes: {}", e))?;
+
+    for (id, at, number, total) in credit_notes {
+        entries.push(TimelineEntry {
+            at: at.unwrap_or_default(),
+            kind: TimelineKind::CreditNote,
+            summary: format!("Credit note {} issued for {}", number, money.format(-total)),
+            details: serde_json::json!({
+                "credit_note_id": id,
+                "credit_note_number": number,
+                "total_amount": total,
+            }),
+        });
+    }
+
+    // Stable sort keeps same-second entries in the order gathered above
+    entries.sort_by(|a, b| a.at.cmp(&b.at));
+    Ok(entries)
+}
@@ -0,0 +1,462 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteConnection;
+use tauri::State;
+
+use crate::audit;
+use crate::db::Database;
+use crate::error::AppError;
+
+// Invoice math lives here so every total the app shows or stores comes from
+// the same code. Amounts are f64 like the rest of the schema; rounding to the
+// currency's decimal places happens at well-defined points below.
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+pub enum Discount {
+    // Percentage of the amount, 0-100
+    Percent(f64),
+    // Fixed amount off
+    Amount(f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum TaxMode {
+    Exclusive,
+    Inclusive,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum RoundingMode {
+    HalfUp,
+    HalfEven,
+    Down,
+    Up,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TaxSettings {
+    pub tax_name: String,
+    pub tax_rate: f64,
+    pub tax_mode: TaxMode,
+    pub rounding_mode: RoundingMode,
+    pub decimal_places: i64,
+    // Round each line's tax before summing instead of rounding the order's tax once
+    pub round_per_line: bool,
+}
+
+impl Default for TaxSettings {
+    fn default() -> Self {
+        TaxSettings {
+            tax_name: "Tax".to_string(),
+            tax_rate: 0.0,
+            tax_mode: TaxMode::Exclusive,
+            rounding_mode: RoundingMode::HalfUp,
+            decimal_places: 2,
+            round_per_line: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TotalsLine {
+    pub description: Option<String>,
+    pub quantity: f64,
+    pub unit_price: f64,
+    pub discount: Option<Discount>,
+    // Overrides the default tax rate for this line (e.g. 0 for exempt items)
+    pub tax_rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TotalsInput {
+    pub items: Vec<TotalsLine>,
+    pub order_discount: Option<Discount>,
+    // Defaults to the saved tax settings
+    pub settings: Option<TaxSettings>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LineTotals {
+    pub gross: f64,
+    pub line_discount: f64,
+    // This line's share of the order-level discount
+    pub order_discount: f64,
+    pub taxable: f64,
+    pub tax: f64,
+    pub total: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderTotals {
+    pub lines: Vec<LineTotals>,
+    // Sum of lines after line discounts, before the order discount
+    pub subtotal: f64,
+    pub line_discounts: f64,
+    pub order_discount: f64,
+    pub tax: f64,
+    pub tax_name: String,
+    pub tax_mode: TaxMode,
+    pub total: f64,
+}
+
+// Round to `decimals` places. Values are first snapped to 1e-9 so binary
+// representation noise (2.675 stored as 2.67499999...) doesn't decide ties.
+pub fn round_amount(value: f64, decimals: i64, mode: RoundingMode) -> f64 {
+    let factor = 10f64.powi(decimals.clamp(0, 6) as i32);
+    let scaled = (value * factor * 1e9).round() / 1e9;
+    let magnitude = scaled.abs();
+    let floor = magnitude.floor();
+    let fraction = magnitude - floor;
+
+    let rounded = match mode {
+        RoundingMode::Down => floor,
+        RoundingMode::Up => magnitude.ceil(),
+        RoundingMode::HalfUp => {
+            if fraction >= 0.5 {
+                floor + 1.0
+            } else {
+                floor
+            }
+        }
+        RoundingMode::HalfEven => {
+            if fraction > 0.5 || (fraction == 0.5 && floor % 2.0 != 0.0) {
+                floor + 1.0
+            } else {
+                floor
+            }
+        }
+    };
+
+    rounded.copysign(scaled) / factor
+}
+
+// Helper: Amount a discount takes off `base`, never more than the base itself
+fn discount_amount(discount: Option<Discount>, base: f64) -> Result<f64, AppError> {
+    let amount = match discount {
+        None => 0.0,
+        Some(Discount::Percent(pct)) if pct.is_finite() && (0.0..=100.0).contains(&pct) => {
+            base * pct / 100.0
+        }
+        Some(Discount::Amount(value)) if value.is_finite() && value >= 0.0 => value,
+        Some(other) => {
+            return Err(AppError::Validation(format!(
+                "Invalid discount: {:?}",
+                other
+            )))
+        }
+    };
+    Ok(amount.min(base.max(0.0)))
+}
+
+// Compute line and order totals. Discounts apply before tax; the order-level
+// discount is spread over lines in proportion to their amounts so lines with
+// different tax rates are each taxed on what the customer actually pays.
+pub fn calculate_totals(
+    input: &TotalsInput,
+    settings: &TaxSettings,
+) -> Result<OrderTotals, AppError> {
+    if !settings.tax_rate.is_finite() || settings.tax_rate < 0.0 {
+        return Err(AppError::Validation(format!(
+            "Invalid tax rate: {}",
+            settings.tax_rate
+        )));
+    }
+    let round = |value: f64| round_amount(value, settings.decimal_places, settings.rounding_mode);
+
+    let mut lines = Vec::with_capacity(input.items.len());
+    for item in &input.items {
+        if !item.quantity.is_finite() || item.quantity < 0.0 {
+            return Err(AppError::Validation(format!(
+                "Invalid quantity: {}",
+                item.quantity
+            )));
+        }
+        if !item.unit_price.is_finite() || item.unit_price < 0.0 {
+            return Err(AppError::Validation(format!(
+                "Invalid unit price: {}",
+                item.unit_price
+            )));
+        }
+        let gross = round(item.quantity * item.unit_price);
+        let line_discount = round(discount_amount(item.discount, gross)?);
+        lines.push(LineTotals {
+            gross,
+            line_discount,
+            ..Default::default()
+        });
+    }
+
+    let subtotal: f64 = lines.iter().map(|l| l.gross - l.line_discount).sum();
+    let order_discount = round(discount_amount(input.order_discount, subtotal)?);
+
+    // Allocate the order discount; the last line with an amount takes the rounding remainder
+    let mut allocated = 0.0;
+    let last_with_value = lines.iter().rposition(|l| l.gross - l.line_discount > 0.0);
+    for (i, line) in lines.iter_mut().enumerate() {
+        let net = line.gross - line.line_discount;
+        line.order_discount = if Some(i) == last_with_value {
+            round(order_discount - allocated)
+        } else if subtotal > 0.0 {
+            round(order_discount * net / subtotal)
+        } else {
+            0.0
+        };
+        allocated += line.order_discount;
+        line.taxable = round(net - line.order_discount);
+    }
+
+    let mut tax_total = 0.0;
+    for (line, item) in lines.iter_mut().zip(&input.items) {
+        let rate = item.tax_rate.unwrap_or(settings.tax_rate);
+        if !rate.is_finite() || rate < 0.0 {
+            return Err(AppError::Validation(format!("Invalid tax rate: {}", rate)));
+        }
+        let tax = match settings.tax_mode {
+            TaxMode::Exclusive => line.taxable * rate / 100.0,
+            TaxMode::Inclusive => line.taxable - line.taxable / (1.0 + rate / 100.0),
+        };
+        line.tax = if settings.round_per_line {
+            round(tax)
+        } else {
+            tax
+        };
+        tax_total += line.tax;
+        line.total = match settings.tax_mode {
+            TaxMode::Exclusive => round(line.taxable + line.tax),
+            TaxMode::Inclusive => line.taxable,
+        };
+    }
+
+    let tax = round(tax_total);
+    let taxable: f64 = lines.iter().map(|l| l.taxable).sum();
+    let total = match settings.tax_mode {
+        TaxMode::Exclusive => round(taxable + tax),
+        TaxMode::Inclusive => round(taxable),
+    };
+    if !settings.round_per_line {
+        for line in &mut lines {
+            line.tax = round(line.tax);
+        }
+    }
+    let line_discounts = round(lines.iter().map(|l| l.line_discount).sum());
+
+    Ok(OrderTotals {
+        lines,
+        subtotal: round(subtotal),
+        line_discounts,
+        order_discount,
+        tax,
+        tax_name: settings.tax_name.clone(),
+        tax_mode: settings.tax_mode,
+        total,
+    })
+}
+
+// Helper: Saved tax settings, or the defaults if none were saved
+pub async fn load_tax_settings(conn: &mut SqliteConnection) -> Result<TaxSettings, AppError> {
+    let settings = sqlx::query_as::<_, TaxSettings>(
+        "SELECT tax_name, tax_rate, tax_mode, rounding_mode, decimal_places, round_per_line \
+         FROM tax_settings WHERE id = 1",
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| format!("Failed to load tax settings: {}", e))?;
+    Ok(settings.unwrap_or_default())
+}
+
+// Compute totals for an order (or a draft of one) using the shared engine
+#[tauri::command]
+pub async fn calculate_order_totals(
+    db: State<'_, Database>,
+    order: TotalsInput,
+) -> Result<OrderTotals, AppError> {
+    let settings = match &order.settings {
+        Some(settings) => settings.clone(),
+        None => {
+            let mut conn = db
+                .pool
+                .acquire()
+                .await
+                .map_err(|e| format!("Failed to open database connection: {}", e))?;
+            load_tax_settings(&mut conn).await?
+        }
+    };
+    calculate_totals(&order, &settings)
+}
+
+#[tauri::command]
+pub async fn get_tax_settings(db: State<'_, Database>) -> Result<TaxSettings, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    load_tax_settings(&mut conn).await
+}
+
+#[tauri::command]
+pub async fn set_tax_settings(
+    db: State<'_, Database>,
+    settings: TaxSettings,
+) -> Result<TaxSettings, AppError> {
+    if !settings.tax_rate.is_finite() || !(0.0..=100.0).contains(&settings.tax_rate) {
+        return Err(AppError::Validation(format!(
+            "Invalid tax rate: {}",
+            settings.tax_rate
+        )));
+    }
+    if !(0..=6).contains(&settings.decimal_places) {
+        return Err(AppError::Validation(format!(
+            "Decimal places must be between 0 and 6, got {}",
+            settings.decimal_places
+        )));
+    }
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_tax_settings(&mut tx).await?;
+
+    sqlx::query(
+        "INSERT OR REPLACE INTO tax_settings \
+         (id, tax_name, tax_rate, tax_mode, rounding_mode, decimal_places, round_per_line) \
+         VALUES (1, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(settings.tax_name.trim())
+    .bind(settings.tax_rate)
+    .bind(settings.tax_mode)
+    .bind(settings.rounding_mode)
+    .bind(settings.decimal_places)
+    .bind(settings.round_per_line)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save tax settings: {}", e))?;
+
+    let after = load_tax_settings(&mut tx).await?;
+    audit::record(
+        &mut *tx,
+        "tax_settings",
+        1,
+        "update",
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save tax settings: {}", e))?;
+
+    Ok(after)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(tax_rate: f64, tax_mode: TaxMode) -> TaxSettings {
+        TaxSettings {
+            tax_rate,
+            tax_mode,
+            ..Default::default()
+        }
+    }
+
+    fn line(quantity: f64, unit_price: f64) -> TotalsLine {
+        TotalsLine {
+            description: None,
+            quantity,
+            unit_price,
+            discount: None,
+            tax_rate: None,
+        }
+    }
+
+    fn input(items: Vec<TotalsLine>, order_discount: Option<Discount>) -> TotalsInput {
+        TotalsInput {
+            items,
+            order_discount,
+            settings: None,
+        }
+    }
+
+    #[test]
+    fn rounding_modes() {
+        assert_eq!(round_amount(2.665, 2, RoundingMode::HalfUp), 2.67);
+        assert_eq!(round_amount(2.665, 2, RoundingMode::HalfEven), 2.66);
+        assert_eq!(round_amount(2.675, 2, RoundingMode::HalfEven), 2.68);
+        assert_eq!(round_amount(2.669, 2, RoundingMode::Down), 2.66);
+        assert_eq!(round_amount(2.661, 2, RoundingMode::Up), 2.67);
+        // Ties round away from zero for negative amounts too
+        assert_eq!(round_amount(-2.665, 2, RoundingMode::HalfUp), -2.67);
+        // Currencies without minor units
+        assert_eq!(round_amount(12.5, 0, RoundingMode::HalfUp), 13.0);
+        assert_eq!(round_amount(12.5, 0, RoundingMode::HalfEven), 12.0);
+    }
+
+    #[test]
+    fn tax_exclusive_adds_tax_on_top() {
+        let totals = calculate_totals(
+            &input(vec![line(2.0, 50.0)], None),
+            &settings(11.0, TaxMode::Exclusive),
+        )
+        .unwrap();
+        assert_eq!(totals.subtotal, 100.0);
+        assert_eq!(totals.tax, 11.0);
+        assert_eq!(totals.total, 111.0);
+    }
+
+    #[test]
+    fn tax_inclusive_takes_tax_out_of_the_price() {
+        let totals = calculate_totals(
+            &input(vec![line(1.0, 111.0)], None),
+            &settings(11.0, TaxMode::Inclusive),
+        )
+        .unwrap();
+        assert_eq!(totals.tax, 11.0);
+        assert_eq!(totals.total, 111.0);
+        assert_eq!(totals.lines[0].total, 111.0);
+    }
+
+    #[test]
+    fn order_discount_is_spread_by_line_amount() {
+        let mut exempt = line(1.0, 70.0);
+        exempt.tax_rate = Some(0.0);
+        let totals = calculate_totals(
+            &input(vec![line(1.0, 30.0), exempt], Some(Discount::Amount(10.0))),
+            &settings(10.0, TaxMode::Exclusive),
+        )
+        .unwrap();
+
+        assert_eq!(totals.lines[0].order_discount, 3.0);
+        assert_eq!(totals.lines[1].order_discount, 7.0);
+        // Only the taxed line's share of the discount lowers the tax
+        assert_eq!(totals.lines[0].taxable, 27.0);
+        assert_eq!(totals.tax, 2.7);
+        assert_eq!(totals.total, 92.7);
+    }
+
+    #[test]
+    fn last_line_takes_the_rounding_remainder() {
+        let totals = calculate_totals(
+            &input(
+                vec![line(1.0, 10.0), line(1.0, 10.0), line(1.0, 10.0)],
+                Some(Discount::Amount(10.0)),
+            ),
+            &TaxSettings::default(),
+        )
+        .unwrap();
+
+        let shares: Vec<f64> = totals.lines.iter().map(|l| l.order_discount).collect();
+        assert_eq!(shares, [3.33, 3.33, 3.34]);
+        assert_eq!(totals.order_discount, 10.0);
+        assert_eq!(totals.total, 20.0);
+    }
+}
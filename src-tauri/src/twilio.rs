@@ -0,0 +1,74 @@
+use reqwest::Client;
+use serde_json::Value;
+
+use crate::error::AppError;
+use crate::http;
+use crate::sms::{SmsProvider, SmsSettings};
+
+const TWILIO_API: &str = "https://api.twilio.com/2010-04-01";
+
+// Twilio Programmable Messaging with an Account SID and auth token
+pub struct Twilio {
+    client: Client,
+    account_sid: String,
+    auth_token: String,
+    from: String,
+}
+
+impl Twilio {
+    pub fn new(settings: &SmsSettings) -> Result<Twilio, AppError> {
+        let (account_sid, auth_token, from) = settings.credentials()?;
+        if !account_sid.starts_with("AC") {
+            return Err(AppError::Validation(
+                "Twilio Account SID must start with AC".to_string(),
+            ));
+        }
+        // Numbers need the +; alphanumeric sender IDs are sent as they are
+        let from = if from.chars().all(|c| c.is_ascii_digit()) {
+            format!("+{}", from)
+        } else {
+            from.to_string()
+        };
+        Ok(Twilio {
+            client: http::client(),
+            account_sid: account_sid.to_string(),
+            auth_token: auth_token.to_string(),
+            from,
+        })
+    }
+}
+
+impl SmsProvider for Twilio {
+    async fn send(&self, to: &str, body: &str) -> Result<String, AppError> {
+        let response = self
+            .client
+            .post(format!(
+                "{}/Accounts/{}/Messages.json",
+                TWILIO_API, self.account_sid
+            ))
+            .basic_auth(&self.account_sid, Some(&self.auth_token))
+            .form(&[
+                ("To", format!("+{}", to).as_str()),
+                ("From", self.from.as_str()),
+                ("Body", body),
+            ])
+            .send()
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to reach Twilio: {}", e)))?;
+        let status = response.status();
+        let body = response
+            .json::<Value>()
+            .await
+            .map_err(|e| format!("Failed to parse Twilio response: {}", e))?;
+        match body["sid"].as_str() {
+            Some(sid) if status.is_success() => Ok(sid.to_string()),
+            _ => Err(AppError::from_status(
+                status.as_u16(),
+                format!(
+                    "Failed to send SMS: {}",
+                    body["message"].as_str().unwrap_or("unknown error")
+                ),
+            )),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use reqwest::Client;
+use serde_json::Value;
+
+use crate::error::AppError;
+use crate::http;
+use crate::sms::{SmsProvider, SmsSettings};
+
+const VONAGE_SMS_API: &str = "https://rest.nexmo.com/sms/json";
+
+// Vonage (Nexmo) SMS API with an API key and secret
+pub struct Vonage {
+    client: Client,
+    api_key: String,
+    api_secret: String,
+    from: String,
+}
+
+impl Vonage {
+    pub fn new(settings: &SmsSettings) -> Result<Vonage, AppError> {
+        let (api_key, api_secret, from) = settings.credentials()?;
+        Ok(Vonage {
+            client: http::client(),
+            api_key: api_key.to_string(),
+            api_secret: api_secret.to_string(),
+            from: from.trim_start_matches('+').to_string(),
+        })
+    }
+}
+
+impl SmsProvider for Vonage {
+    // Vonage answers 200 even when it refuses; each message part carries its
+    // own status, "0" meaning accepted
+    async fn send(&self, to: &str, body: &str) -> Result<String, AppError> {
+        let response = self
+            .client
+            .post(VONAGE_SMS_API)
+            .form(&[
+                ("api_key", self.api_key.as_str()),
+                ("api_secret", self.api_secret.as_str()),
+                ("from", self.from.as_str()),
+                ("to", to),
+                ("text", body),
+                ("type", "unicode"),
+            ])
+            .send()
+            .await
+            .map_err(|e| AppError::Network(format!("Failed to reach Vonage: {}", e)))?;
+        let body = response
+            .json::<Value>()
+            .await
+            .map_err(|e| format!("Failed to parse Vonage response: {}", e))?;
+
+        let parts = body["messages"].as_array().cloned().unwrap_or_default();
+        if let Some(refused) = parts.iter().find(|part| part["status"] != "0") {
+            return Err(AppError::Internal(format!(
+                "Failed to send SMS: {}",
+                refused["error-text"].as_str().unwrap_or("unknown error")
+            )));
+        }
+        parts
+            .first()
+            .and_then(|part| part["message-id"].as_str())
+            .map(str::to_string)
+            .ok_or_else(|| {
+                AppError::Internal("Failed to send SMS: Vonage returned no message ID".to_string())
+            })
+    }
+}
@@ -0,0 +1,748 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{SqliteConnection, SqlitePool};
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tiny_http::{Method, Request, Response, Server};
+
+use crate::audit;
+use crate::db::Database;
+use crate::encryption::{conceal, reveal};
+use crate::error::AppError;
+use crate::log_warning;
+
+// Bodies past this are refused; provider events are a few KB
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+// Stripe's default tolerance for the signed timestamp, used for generic
+// senders' timestamps too
+const SIGNATURE_TOLERANCE_SECS: i64 = 300;
+
+const SOURCE_COLUMNS: &str =
+    "id, slug, name, provider, signing_secret, enabled, created_at, updated_at";
+
+const EVENT_COLUMNS: &str = "e.id, e.source_id, s.slug AS source_slug, s.provider, \
+     e.event_type, e.external_id, e.payload, e.received_at, e.handled_at";
+
+type HmacSha256 = Hmac<Sha256>;
+
+// The running receiver, so changing its settings can stop it
+#[derive(Default)]
+pub struct WebhookServer(Mutex<Option<Arc<Server>>>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum WebhookProvider {
+    // Stripe-Signature: t=<unix time>,v1=<hex HMAC of "t.body">
+    Stripe,
+    // X-Shopify-Hmac-Sha256: <base64 HMAC of the body>
+    Shopify,
+    // X-Signature: sha256=<hex HMAC of the body> (Zapier, scripts, ...). With
+    // X-Signature-Timestamp: <unix time>, the HMAC is of "timestamp.body".
+    Generic,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WebhookReceiverSettings {
+    pub enabled: bool,
+    pub port: i64,
+    // Listen on every interface instead of just 127.0.0.1
+    pub bind_all: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WebhookSource {
+    pub id: i64,
+    // Path segment: POST /webhooks/<slug>
+    pub slug: String,
+    pub name: String,
+    pub provider: WebhookProvider,
+    pub signing_secret: String,
+    pub enabled: bool,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookSourceInput {
+    pub slug: String,
+    pub name: String,
+    pub provider: WebhookProvider,
+    pub signing_secret: String,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WebhookEvent {
+    pub id: i64,
+    pub source_id: i64,
+    pub source_slug: String,
+    pub provider: WebhookProvider,
+    // e.g. "payment_intent.succeeded", "orders/paid", "payment.received"
+    pub event_type: String,
+    // The sender's event ID, or "sha256:<hash>" of the body and signed
+    // timestamp when it gave none
+    pub external_id: Option<String>,
+    // Request body as received (JSON)
+    pub payload: String,
+    pub received_at: Option<String>,
+    pub handled_at: Option<String>,
+}
+
+// Emitted as the "webhook-received" event for each new verified event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookReceived {
+    pub id: i64,
+    pub source_slug: String,
+    pub provider: WebhookProvider,
+    pub event_type: String,
+    pub external_id: Option<String>,
+}
+
+// Helper: HMAC-SHA256 of `parts` under `secret`, ready to verify against
+fn signer(secret: &str, parts: &[&[u8]]) -> Result<HmacSha256, AppError> {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .map_err(|e| format!("Invalid signing secret: {}", e))?;
+    for part in parts {
+        mac.update(part);
+    }
+    Ok(mac)
+}
+
+// Helper: Bytes of a hex string, None if it isn't one
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+// Helper: Whether `signature` (raw bytes) is the HMAC of `parts`, compared
+// in constant time
+fn signature_matches(secret: &str, parts: &[&[u8]], signature: &[u8]) -> Result<bool, AppError> {
+    Ok(signer(secret, parts)?.verify_slice(signature).is_ok())
+}
+
+// Helper: Refuse a signed timestamp outside the replay window
+fn check_timestamp(timestamp: i64, now: i64) -> Result<(), AppError> {
+    if (now - timestamp).abs() > SIGNATURE_TOLERANCE_SECS {
+        return Err(AppError::Validation(
+            "Signature timestamp is outside the tolerance".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// Helper: Check a request's signature the way its provider signs. `now` is
+// the current Unix time, for the replay window. Returns the signed timestamp,
+// if the provider signs one.
+fn verify_signature(
+    source: &WebhookSource,
+    header: impl Fn(&str) -> Option<String>,
+    body: &[u8],
+    now: i64,
+) -> Result<Option<i64>, AppError> {
+    let secret = source.signing_secret.as_str();
+    let mut signed_at = None;
+    let valid = match source.provider {
+        WebhookProvider::Stripe => {
+            let signature = header("Stripe-Signature").ok_or("Missing Stripe-Signature header")?;
+            let mut timestamp = None;
+            let mut candidates = Vec::new();
+            for part in signature.split(',') {
+                match part.trim().split_once('=') {
+                    Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+                    Some(("v1", value)) => candidates.extend(decode_hex(value)),
+                    _ => {}
+                }
+            }
+            let timestamp = timestamp.ok_or("Stripe-Signature has no timestamp")?;
+            check_timestamp(timestamp, now)?;
+            signed_at = Some(timestamp);
+            let signed = timestamp.to_string();
+            let mut valid = false;
+            for candidate in &candidates {
+                valid |= signature_matches(secret, &[signed.as_bytes(), b".", body], candidate)?;
+            }
+            valid
+        }
+        WebhookProvider::Shopify => {
+            let signature =
+                header("X-Shopify-Hmac-Sha256").ok_or("Missing X-Shopify-Hmac-Sha256 header")?;
+            let signature = STANDARD
+                .decode(signature.trim())
+                .map_err(|_| "X-Shopify-Hmac-Sha256 is not base64".to_string())?;
+            signature_matches(secret, &[body], &signature)?
+        }
+        WebhookProvider::Generic => {
+            let signature = header("X-Signature-256")
+                .or_else(|| header("X-Signature"))
+                .ok_or("Missing X-Signature header")?;
+            let hex = signature.trim();
+            let hex = hex.strip_prefix("sha256=").unwrap_or(hex);
+            let signature = decode_hex(hex).ok_or("X-Signature is not hex")?;
+            match header("X-Signature-Timestamp") {
+                Some(timestamp) => {
+                    let timestamp = timestamp.trim();
+                    let parsed = timestamp
+                        .parse::<i64>()
+                        .map_err(|_| "X-Signature-Timestamp is not a Unix time".to_string())?;
+                    check_timestamp(parsed, now)?;
+                    signed_at = Some(parsed);
+                    signature_matches(secret, &[timestamp.as_bytes(), b".", body], &signature)?
+                }
+                None => signature_matches(secret, &[body], &signature)?,
+            }
+        }
+    };
+    if valid {
+        Ok(signed_at)
+    } else {
+        Err(AppError::Validation("Signature does not match".to_string()))
+    }
+}
+
+// Helper: Event type and the sender's event ID, from the body or headers
+fn describe_event(
+    provider: WebhookProvider,
+    header: impl Fn(&str) -> Option<String>,
+    payload: &serde_json::Value,
+) -> (String, Option<String>) {
+    let field = |name: &str| match &payload[name] {
+        serde_json::Value::String(value) => Some(value.clone()),
+        serde_json::Value::Number(value) => Some(value.to_string()),
+        _ => None,
+    };
+    let (event_type, external_id) = match provider {
+        WebhookProvider::Stripe => (field("type"), field("id")),
+        WebhookProvider::Shopify => (
+            header("X-Shopify-Topic"),
+            header("X-Shopify-Webhook-Id").or_else(|| header("X-Shopify-Event-Id")),
+        ),
+        WebhookProvider::Generic => (
+            field("event").or_else(|| field("type")),
+            header("X-Event-Id").or_else(|| field("id")),
+        ),
+    };
+    (
+        event_type.unwrap_or_else(|| "unknown".to_string()),
+        external_id,
+    )
+}
+
+// Helper: Dedupe key for an event the sender gave no ID: the body and the
+// signed timestamp, so a replayed request is stored once
+fn idempotency_key(body: &[u8], signed_at: Option<i64>) -> String {
+    let mut hasher = Sha256::new();
+    if let Some(timestamp) = signed_at {
+        hasher.update(timestamp.to_string().as_bytes());
+        hasher.update(b".");
+    }
+    hasher.update(body);
+    let digest: String = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("sha256:{}", digest)
+}
+
+// Helper: Verify and store one delivery. Returns the HTTP status to answer
+// with, and the event when it's new.
+async fn receive(
+    pool: &SqlitePool,
+    slug: &str,
+    header: impl Fn(&str) -> Option<String>,
+    body: &[u8],
+) -> (u16, Result<Option<WebhookReceived>, AppError>) {
+    let source = match sqlx::query_as::<_, WebhookSource>(&format!(
+        "SELECT {} FROM webhook_sources WHERE slug = ? AND enabled = 1",
+        SOURCE_COLUMNS
+    ))
+    .bind(slug)
+    .fetch_optional(pool)
+    .await
+    {
+        Ok(Some(source)) => match reveal_source(source) {
+            Ok(source) => source,
+            Err(e) => return (500, Err(e)),
+        },
+        Ok(None) => {
+            return (
+                404,
+                Err(AppError::Validation(format!(
+                    "Unknown webhook source: {}",
+                    slug
+                ))),
+            )
+        }
+        Err(e) => {
+            return (
+                500,
+                Err(AppError::Internal(format!(
+                    "Failed to load webhook source: {}",
+                    e
+                ))),
+            )
+        }
+    };
+
+    let now = chrono::Utc::now().timestamp();
+    let signed_at = match verify_signature(&source, &header, body, now) {
+        Ok(signed_at) => signed_at,
+        Err(e) => return (401, Err(e)),
+    };
+    let payload = match serde_json::from_slice::<serde_json::Value>(body) {
+        Ok(payload) => payload,
+        Err(e) => {
+            return (
+                400,
+                Err(AppError::Validation(format!("Body is not JSON: {}", e))),
+            )
+        }
+    };
+    let (event_type, external_id) = describe_event(source.provider, &header, &payload);
+    let external_id = external_id.or_else(|| Some(idempotency_key(body, signed_at)));
+
+    // Redeliveries of an event already stored are acknowledged and dropped
+    let inserted = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO webhook_events (source_id, event_type, external_id, payload) \
+         VALUES (?, ?, ?, ?) ON CONFLICT(source_id, external_id) DO NOTHING RETURNING id",
+    )
+    .bind(source.id)
+    .bind(&event_type)
+    .bind(&external_id)
+    .bind(String::from_utf8_lossy(body).to_string())
+    .fetch_optional(pool)
+    .await;
+
+    match inserted {
+        Ok(id) => (
+            200,
+            Ok(id.map(|id| WebhookReceived {
+                id,
+                source_slug: source.slug,
+                provider: source.provider,
+                event_type,
+                external_id,
+            })),
+        ),
+        Err(e) => (
+            500,
+            Err(AppError::Internal(format!(
+                "Failed to store webhook event: {}",
+                e
+            ))),
+        ),
+    }
+}
+
+// Helper: Answer one request to the receiver
+fn handle_request(app: &AppHandle, mut request: Request) {
+    let path = request
+        .url()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let slug = path
+        .strip_prefix("/webhooks/")
+        .map(|slug| slug.trim_end_matches('/'))
+        .filter(|slug| !slug.is_empty() && !slug.contains('/'));
+
+    let (status, message) = match (request.method(), slug) {
+        (Method::Post, Some(slug)) => {
+            let mut body = Vec::new();
+            let read = request
+                .as_reader()
+                .take(MAX_BODY_BYTES as u64 + 1)
+                .read_to_end(&mut body);
+            if let Err(e) = read {
+                log_warning!("Failed to read webhook body for {}: {}", slug, e);
+                (400, "Bad request".to_string())
+            } else if body.len() > MAX_BODY_BYTES {
+                (413, "Body too large".to_string())
+            } else {
+                let headers = request.headers().to_vec();
+                let header = |name: &str| {
+                    headers
+                        .iter()
+                        .find(|h| h.field.as_str().as_str().eq_ignore_ascii_case(name))
+                        .map(|h| h.value.as_str().to_string())
+                };
+                let pool = app.state::<Database>().pool.clone();
+                let (status, result) =
+                    tauri::async_runtime::block_on(receive(&pool, slug, header, &body));
+                match result {
+                    Ok(received) => {
+                        if let Some(received) = received {
+                            if let Err(e) = app.emit("webhook-received", &received) {
+                                log_warning!("Failed to emit webhook-received event: {}", e);
+                            }
+                        }
+                        (status, "ok".to_string())
+                    }
+                    // The detail goes to the log; the sender only learns the status
+                    Err(e) => {
+                        log_warning!("Rejected webhook for {}: {}", slug, e);
+                        let message = match status {
+                            400 => "Bad request",
+                            401 => "Unauthorized",
+                            404 => "Not found",
+                            _ => "Internal error",
+                        };
+                        (status, message.to_string())
+                    }
+                }
+            }
+        }
+        (_, Some(_)) => (405, "Use POST".to_string()),
+        _ => (404, "Not found".to_string()),
+    };
+
+    let _ = request.respond(Response::from_string(message).with_status_code(status));
+}
+
+// Helper: Stop the receiver if it's running, then start it again if enabled
+fn restart(app: &AppHandle, settings: &WebhookReceiverSettings) -> Result<(), AppError> {
+    let state = app.state::<WebhookServer>();
+    let mut running = state
+        .0
+        .lock()
+        .map_err(|_| "Webhook receiver state is poisoned".to_string())?;
+    if let Some(server) = running.take() {
+        server.unblock();
+    }
+    if !settings.enabled {
+        return Ok(());
+    }
+
+    let host = if settings.bind_all {
+        "0.0.0.0"
+    } else {
+        "127.0.0.1"
+    };
+    let server = Arc::new(
+        Server::http(format!("{}:{}", host, settings.port))
+            .map_err(|e| format!("Failed to start webhook receiver: {}", e))?,
+    );
+    *running = Some(server.clone());
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+            handle_request(&app, request);
+        }
+    });
+    Ok(())
+}
+
+// Helper: Receiver settings, defaults if the row is missing
+async fn load_settings(pool: &SqlitePool) -> Result<WebhookReceiverSettings, AppError> {
+    Ok(sqlx::query_as::<_, WebhookReceiverSettings>(
+        "SELECT enabled, port, bind_all FROM webhook_receiver_settings WHERE id = 1",
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load webhook receiver settings: {}", e))?
+    .unwrap_or(WebhookReceiverSettings {
+        enabled: false,
+        port: 8787,
+        bind_all: false,
+    }))
+}
+
+// Start the receiver at launch if it's enabled
+pub fn start_receiver(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<Database>().pool.clone();
+        let started = match load_settings(&pool).await {
+            Ok(settings) => restart(&app, &settings),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = started {
+            log_warning!("Webhook receiver not started: {}", e);
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_webhook_receiver_settings(
+    db: State<'_, Database>,
+) -> Result<WebhookReceiverSettings, AppError> {
+    load_settings(&db.pool).await
+}
+
+// Save the receiver settings and restart it with them
+#[tauri::command]
+pub async fn set_webhook_receiver_settings(
+    app: AppHandle,
+    db: State<'_, Database>,
+    settings: WebhookReceiverSettings,
+) -> Result<WebhookReceiverSettings, AppError> {
+    if !(1024..=65535).contains(&settings.port) {
+        return Err(AppError::Validation(format!(
+            "Invalid port: {}",
+            settings.port
+        )));
+    }
+    sqlx::query(
+        "INSERT INTO webhook_receiver_settings (id, enabled, port, bind_all) VALUES (1, ?, ?, ?) \
+         ON CONFLICT(id) DO UPDATE SET enabled = excluded.enabled, port = excluded.port, \
+         bind_all = excluded.bind_all",
+    )
+    .bind(settings.enabled)
+    .bind(settings.port)
+    .bind(settings.bind_all)
+    .execute(&db.pool)
+    .await
+    .map_err(|e| format!("Failed to save webhook receiver settings: {}", e))?;
+
+    restart(&app, &settings)?;
+    Ok(settings)
+}
+
+// Helper: Validate a source's fields
+fn validate_source(source: &WebhookSourceInput) -> Result<(), AppError> {
+    let slug = source.slug.trim();
+    if slug.is_empty()
+        || !slug
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(AppError::Validation(
+            "Slug may only use letters, digits, - and _".to_string(),
+        ));
+    }
+    if source.name.trim().is_empty() {
+        return Err(AppError::Validation(
+            "Webhook source name must not be empty".to_string(),
+        ));
+    }
+    if source.signing_secret.trim().is_empty() {
+        return Err(AppError::Validation(
+            "Signing secret must not be empty".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// Helper: A source as loaded, with its signing secret decrypted
+fn reveal_source(source: WebhookSource) -> Result<WebhookSource, AppError> {
+    Ok(WebhookSource {
+        signing_secret: reveal(source.signing_secret)?,
+        ..source
+    })
+}
+
+async fn load_source(conn: &mut SqliteConnection, id: i64) -> Result<WebhookSource, AppError> {
+    sqlx::query_as::<_, WebhookSource>(&format!(
+        "SELECT {} FROM webhook_sources WHERE id = ?",
+        SOURCE_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| format!("Failed to load webhook source: {}", e))?
+    .ok_or_else(|| AppError::NotFound(format!("Webhook source {} not found", id)))
+    .and_then(reveal_source)
+}
+
+#[tauri::command]
+pub async fn list_webhook_sources(db: State<'_, Database>) -> Result<Vec<WebhookSource>, AppError> {
+    sqlx::query_as::<_, WebhookSource>(&format!(
+        "SELECT {} FROM webhook_sources ORDER BY name",
+        SOURCE_COLUMNS
+    ))
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| format!("Failed to list webhook sources: {}", e))?
+    .into_iter()
+    .map(reveal_source)
+    .collect()
+}
+
+// Helper: What the audit log keeps of a source; the secret stays out of it
+fn audit_view(source: &WebhookSource) -> serde_json::Value {
+    serde_json::json!({
+        "slug": source.slug,
+        "name": source.name,
+        "provider": source.provider,
+        "enabled": source.enabled,
+    })
+}
+
+#[tauri::command]
+pub async fn create_webhook_source(
+    db: State<'_, Database>,
+    source: WebhookSourceInput,
+) -> Result<WebhookSource, AppError> {
+    validate_source(&source)?;
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let id = sqlx::query(
+        "INSERT INTO webhook_sources (slug, name, provider, signing_secret, enabled) \
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(source.slug.trim())
+    .bind(source.name.trim())
+    .bind(source.provider)
+    .bind(conceal(source.signing_secret.trim())?)
+    .bind(source.enabled.unwrap_or(true))
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to create webhook source: {}", e))?
+    .last_insert_rowid();
+
+    let created = load_source(&mut tx, id).await?;
+    audit::record(
+        &mut *tx,
+        "webhook_source",
+        id,
+        "create",
+        None,
+        Some(&audit_view(&created)),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save webhook source: {}", e))?;
+    Ok(created)
+}
+
+#[tauri::command]
+pub async fn update_webhook_source(
+    db: State<'_, Database>,
+    id: i64,
+    source: WebhookSourceInput,
+) -> Result<WebhookSource, AppError> {
+    validate_source(&source)?;
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_source(&mut tx, id).await?;
+    sqlx::query(
+        "UPDATE webhook_sources SET slug = ?, name = ?, provider = ?, signing_secret = ?, \
+         enabled = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+    )
+    .bind(source.slug.trim())
+    .bind(source.name.trim())
+    .bind(source.provider)
+    .bind(conceal(source.signing_secret.trim())?)
+    .bind(source.enabled.unwrap_or(true))
+    .bind(id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to update webhook source: {}", e))?;
+
+    let after = load_source(&mut tx, id).await?;
+    audit::record(
+        &mut *tx,
+        "webhook_source",
+        id,
+        "update",
+        Some(&audit_view(&before)),
+        Some(&audit_view(&after)),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to update webhook source: {}", e))?;
+    Ok(after)
+}
+
+// Deleting a source drops its stored events too
+#[tauri::command]
+pub async fn delete_webhook_source(db: State<'_, Database>, id: i64) -> Result<(), AppError> {
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_source(&mut tx, id).await?;
+    sqlx::query("DELETE FROM webhook_sources WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to delete webhook source: {}", e))?;
+    audit::record(
+        &mut *tx,
+        "webhook_source",
+        id,
+        "delete",
+        Some(&audit_view(&before)),
+        None,
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to delete webhook source: {}", e)))
+}
+
+// Received events, newest first
+#[tauri::command]
+pub async fn list_webhook_events(
+    db: State<'_, Database>,
+    source_id: Option<i64>,
+    unhandled_only: Option<bool>,
+    limit: Option<i64>,
+) -> Result<Vec<WebhookEvent>, AppError> {
+    sqlx::query_as::<_, WebhookEvent>(&format!(
+        "SELECT {} FROM webhook_events e JOIN webhook_sources s ON s.id = e.source_id \
+         WHERE (? IS NULL OR e.source_id = ?) AND (? = 0 OR e.handled_at IS NULL) \
+         ORDER BY e.received_at DESC, e.id DESC LIMIT ?",
+        EVENT_COLUMNS
+    ))
+    .bind(source_id)
+    .bind(source_id)
+    .bind(unhandled_only.unwrap_or(false))
+    .bind(limit.unwrap_or(200).clamp(1, 1000))
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to list webhook events: {}", e)))
+}
+
+// Mark an event dealt with, e.g. once its payment has been recorded
+#[tauri::command]
+pub async fn mark_webhook_event_handled(
+    db: State<'_, Database>,
+    id: i64,
+) -> Result<WebhookEvent, AppError> {
+    sqlx::query(
+        "UPDATE webhook_events SET handled_at = COALESCE(handled_at, CURRENT_TIMESTAMP) \
+         WHERE id = ?",
+    )
+    .bind(id)
+    .execute(&db.pool)
+    .await
+    .map_err(|e| format!("Failed to update webhook event: {}", e))?;
+
+    sqlx::query_as::<_, WebhookEvent>(&format!(
+        "SELECT {} FROM webhook_events e JOIN webhook_sources s ON s.id = e.source_id \
+         WHERE e.id = ?",
+        EVENT_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(&db.pool)
+    .await
+    .map_err(|e| format!("Failed to load webhook event: {}", e))?
+    .ok_or_else(|| AppError::NotFound(format!("Webhook event {} not found", id)))
+}
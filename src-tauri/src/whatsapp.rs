@@ -0,0 +1,572 @@
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use tauri::{AppHandle, Manager, State};
+
+use crate::audit;
+use crate::automation::{self, Poller};
+use crate::customers::normalize_phone;
+use crate::db::Database;
+use crate::domain_events::{DomainEvent, Subscriber};
+use crate::encryption::{conceal_opt, reveal, reveal_opt};
+use crate::error::AppError;
+use crate::http;
+use crate::log_warning;
+use crate::money::money_format;
+use crate::order_status::OrderStatus;
+
+const GRAPH_API: &str = "https://graph.facebook.com/v19.0";
+
+// Sends per message before it's marked failed
+const MAX_ATTEMPTS: i64 = 5;
+
+const MESSAGE_COLUMNS: &str = "id, preorder_id, kind, to_phone, template, variables, status, \
+     attempts, message_id, last_error, created_at, sent_at";
+
+// Template body variables, in order:
+//   confirmation:    {{1}} customer name, {{2}} confirmation code, {{3}} order total
+//   pickup_reminder: {{1}} customer name, {{2}} confirmation code, {{3}} event, {{4}} pickup date
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum WhatsappKind {
+    Confirmation,
+    PickupReminder,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum WhatsappStatus {
+    Pending,
+    Sent,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WhatsappSettings {
+    pub enabled: bool,
+    // Phone number ID from the WhatsApp Business app dashboard
+    pub phone_number_id: Option<String>,
+    pub access_token: Option<String>,
+    // Language code the templates were approved in, e.g. "en" or "id"
+    pub template_language: String,
+    pub confirmation_template: Option<String>,
+    pub pickup_template: Option<String>,
+    pub pickup_days_before: i64,
+    pub default_country_code: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WhatsappMessage {
+    pub id: i64,
+    pub preorder_id: i64,
+    pub kind: WhatsappKind,
+    pub to_phone: String,
+    pub template: String,
+    // JSON array of the body variables
+    pub variables: String,
+    pub status: WhatsappStatus,
+    pub attempts: i64,
+    pub message_id: Option<String>,
+    pub last_error: Option<String>,
+    pub created_at: Option<String>,
+    pub sent_at: Option<String>,
+}
+
+// Helper: WhatsApp settings, disabled defaults if the row is missing
+async fn load_settings(conn: &mut SqliteConnection) -> Result<WhatsappSettings, AppError> {
+    let settings = sqlx::query_as::<_, WhatsappSettings>(
+        "SELECT enabled, phone_number_id, access_token, template_language, \
+         confirmation_template, pickup_template, pickup_days_before, default_country_code \
+         FROM whatsapp_settings WHERE id = 1",
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| format!("Failed to load WhatsApp settings: {}", e))?
+    .unwrap_or(WhatsappSettings {
+        enabled: false,
+        phone_number_id: None,
+        access_token: None,
+        template_language: "en".to_string(),
+        confirmation_template: None,
+        pickup_template: None,
+        pickup_days_before: 1,
+        default_country_code: "62".to_string(),
+    });
+    Ok(WhatsappSettings {
+        access_token: reveal_opt(settings.access_token)?,
+        ..settings
+    })
+}
+
+impl WhatsappSettings {
+    // Phone number ID and token to send with, if sending is on
+    fn credentials(&self) -> Option<(&str, &str)> {
+        let phone_number_id = self.phone_number_id.as_deref().filter(|p| !p.is_empty())?;
+        let access_token = self.access_token.as_deref().filter(|t| !t.is_empty())?;
+        self.enabled.then_some((phone_number_id, access_token))
+    }
+
+    // Template for a kind of message, if that message is switched on
+    fn template(&self, kind: WhatsappKind) -> Option<&str> {
+        self.credentials()?;
+        match kind {
+            WhatsappKind::Confirmation => self.confirmation_template.as_deref(),
+            WhatsappKind::PickupReminder => self.pickup_template.as_deref(),
+        }
+        .filter(|t| !t.is_empty())
+    }
+}
+
+// Helper: Queue one message unless the order already has one of its kind
+async fn queue_message(
+    conn: &mut SqliteConnection,
+    po_id: i64,
+    kind: WhatsappKind,
+    to_phone: &str,
+    template: &str,
+    variables: &[String],
+) -> Result<(), AppError> {
+    let variables = serde_json::to_string(variables)
+        .map_err(|e| format!("Failed to encode WhatsApp variables: {}", e))?;
+    sqlx::query(
+        "INSERT OR IGNORE INTO whatsapp_messages (preorder_id, kind, to_phone, template, variables) \
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(po_id)
+    .bind(kind)
+    .bind(to_phone)
+    .bind(template)
+    .bind(variables)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to queue WhatsApp message: {}", e))?;
+    Ok(())
+}
+
+// Helper: Queue the order confirmation for a just-confirmed order, in the
+// transaction that confirms it. Customers without a usable phone number in
+// the directory are skipped.
+async fn queue_confirmation(conn: &mut SqliteConnection, po_id: i64) -> Result<(), AppError> {
+    let settings = load_settings(&mut *conn).await?;
+    let Some(template) = settings.template(WhatsappKind::Confirmation) else {
+        return Ok(());
+    };
+
+    let (name, code, total, currency, phone) = sqlx::query_as::<
+        _,
+        (String, String, f64, Option<String>, Option<String>),
+    >(
+        "SELECT p.customer_name, p.confirmation_code, p.total_amount, p.currency_code, c.phone \
+         FROM preorders p LEFT JOIN customers c ON c.email = p.customer_email \
+         WHERE p.id = ?",
+    )
+    .bind(po_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load order: {}", e))?
+    .ok_or_else(|| AppError::NotFound(format!("Order {} not found", po_id)))?;
+    let Some(phone) = reveal_opt(phone)?
+        .as_deref()
+        .and_then(|p| normalize_phone(p, &settings.default_country_code))
+    else {
+        return Ok(());
+    };
+
+    let total = money_format(&mut *conn, currency.as_deref())
+        .await?
+        .format(total);
+    queue_message(
+        conn,
+        po_id,
+        WhatsappKind::Confirmation,
+        &phone,
+        template,
+        &[name, code, total],
+    )
+    .await
+}
+
+// Queues the order confirmation once an order is confirmed
+pub struct WhatsApp;
+
+impl Subscriber for WhatsApp {
+    async fn handle(
+        &self,
+        conn: &mut SqliteConnection,
+        event: &DomainEvent<'_>,
+    ) -> Result<(), AppError> {
+        match *event {
+            DomainEvent::OrderStatusChanged(change) if change.to == OrderStatus::Confirmed => {
+                queue_confirmation(conn, change.order_id).await
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// Helper: Queue pickup reminders for orders whose event is within
+// pickup_days_before days. Shipped orders and customers without a phone
+// number are skipped. Returns how many were queued.
+pub async fn queue_pickup_reminders(pool: &SqlitePool) -> Result<u64, AppError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let settings = load_settings(&mut tx).await?;
+    let Some(template) = settings.template(WhatsappKind::PickupReminder) else {
+        return Ok(0);
+    };
+
+    let due = sqlx::query_as::<_, (i64, String, String, String, String, String)>(
+        "SELECT p.id, p.customer_name, p.confirmation_code, c.phone, e.name, e.start_date \
+         FROM preorders p JOIN events e ON e.id = p.event_id \
+         JOIN customers c ON c.email = p.customer_email \
+         WHERE p.deleted_at IS NULL \
+         AND p.status IN ('confirmed', 'deposit_paid', 'invoiced', 'paid') \
+         AND c.phone IS NOT NULL AND e.start_date IS NOT NULL \
+         AND date(e.start_date) >= date('now') \
+         AND date(e.start_date, '-' || ? || ' days') <= date('now') \
+         AND NOT EXISTS (SELECT 1 FROM fulfillments f \
+         WHERE f.preorder_id = p.id AND f.method = 'shipping') \
+         AND NOT EXISTS (SELECT 1 FROM whatsapp_messages m \
+         WHERE m.preorder_id = p.id AND m.kind = 'pickup_reminder')",
+    )
+    .bind(settings.pickup_days_before)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to load pickup reminders: {}", e))?;
+
+    let mut queued = 0;
+    for (po_id, name, code, phone, event, date) in due {
+        let Some(phone) = normalize_phone(&reveal(phone)?, &settings.default_country_code) else {
+            continue;
+        };
+        queue_message(
+            &mut tx,
+            po_id,
+            WhatsappKind::PickupReminder,
+            &phone,
+            template,
+            &[name, code, event, date],
+        )
+        .await?;
+        queued += 1;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save pickup reminders: {}", e))?;
+    Ok(queued)
+}
+
+// Helper: Send a template message. Returns the message's wamid.
+async fn send_template(
+    client: &Client,
+    settings: &WhatsappSettings,
+    message: &WhatsappMessage,
+) -> Result<String, AppError> {
+    let (phone_number_id, access_token) = settings
+        .credentials()
+        .ok_or("WhatsApp sending is switched off")?;
+    let variables: Vec<String> = serde_json::from_str(&message.variables)
+        .map_err(|e| format!("Invalid WhatsApp variables: {}", e))?;
+    let parameters: Vec<serde_json::Value> = variables
+        .iter()
+        .map(|text| serde_json::json!({ "type": "text", "text": text }))
+        .collect();
+
+    let response = client
+        .post(format!("{}/{}/messages", GRAPH_API, phone_number_id))
+        .bearer_auth(access_token)
+        .json(&serde_json::json!({
+            "messaging_product": "whatsapp",
+            "to": message.to_phone,
+            "type": "template",
+            "template": {
+                "name": message.template,
+                "language": { "code": settings.template_language },
+                "components": [{ "type": "body", "parameters": parameters }],
+            },
+        }))
+        .send()
+        .await
+        .map_err(|e| AppError::Network(format!("Failed to reach WhatsApp: {}", e)))?;
+
+    let status = response.status();
+    let body: serde_json::Value = response.json().await.unwrap_or_default();
+    match body["messages"][0]["id"].as_str() {
+        Some(id) if status.is_success() => Ok(id.to_string()),
+        _ => Err(AppError::from_status(
+            status.as_u16(),
+            format!(
+                "WhatsApp rejected the message ({}): {}",
+                status,
+                body["error"]["message"]
+                    .as_str()
+                    .unwrap_or("no description")
+            ),
+        )),
+    }
+}
+
+// Helper: Send one message and record the outcome
+async fn send_one(
+    pool: &SqlitePool,
+    client: &Client,
+    settings: &WhatsappSettings,
+    message: &WhatsappMessage,
+) -> Result<(), AppError> {
+    let result = send_template(client, settings, message).await;
+    let attempts = message.attempts + 1;
+    let status = match &result {
+        Ok(_) => WhatsappStatus::Sent,
+        Err(_) if attempts < MAX_ATTEMPTS && settings.credentials().is_some() => {
+            WhatsappStatus::Pending
+        }
+        Err(_) => WhatsappStatus::Failed,
+    };
+    let (message_id, error) = match result {
+        Ok(id) => (Some(id), None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+
+    sqlx::query(
+        "UPDATE whatsapp_messages SET status = ?, attempts = ?, \
+         message_id = COALESCE(?, message_id), last_error = ?, \
+         sent_at = CASE WHEN ? = 'sent' THEN CURRENT_TIMESTAMP ELSE sent_at END WHERE id = ?",
+    )
+    .bind(status)
+    .bind(attempts)
+    .bind(message_id)
+    .bind(error)
+    .bind(status)
+    .bind(message.id)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to update WhatsApp message: {}", e))?;
+    Ok(())
+}
+
+// Helper: Send queued messages. With sending switched off they're marked
+// failed rather than sent late.
+pub async fn send_pending_messages(pool: &SqlitePool) -> Result<(), AppError> {
+    let batch = automation::send_batch_size(pool).await?;
+    if batch == 0 {
+        return Ok(());
+    }
+    let pending = sqlx::query_as::<_, WhatsappMessage>(&format!(
+        "SELECT {} FROM whatsapp_messages WHERE status = 'pending' ORDER BY id LIMIT ?",
+        MESSAGE_COLUMNS
+    ))
+    .bind(batch)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load WhatsApp messages: {}", e))?;
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let settings = load_settings(&mut conn).await?;
+    drop(conn);
+
+    automation::record_sends(pool, pending.len()).await?;
+    let client = http::client();
+    for message in &pending {
+        send_one(pool, &client, &settings, message).await?;
+    }
+    Ok(())
+}
+
+// Start the background loop that queues pickup reminders and sends messages
+pub fn start_whatsapp_sender(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<Database>().pool.clone();
+        loop {
+            if let Err(e) = queue_pickup_reminders(&pool).await {
+                log_warning!("Failed to queue WhatsApp pickup reminders: {}", e);
+            }
+            if let Err(e) = send_pending_messages(&pool).await {
+                log_warning!("WhatsApp sender failed: {}", e);
+            }
+            automation::wait_for_next_poll(&pool, Poller::Sender).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_whatsapp_settings(db: State<'_, Database>) -> Result<WhatsappSettings, AppError> {
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    load_settings(&mut conn).await
+}
+
+#[tauri::command]
+pub async fn set_whatsapp_settings(
+    db: State<'_, Database>,
+    settings: WhatsappSettings,
+) -> Result<WhatsappSettings, AppError> {
+    let trimmed = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    };
+    let settings = WhatsappSettings {
+        phone_number_id: trimmed(&settings.phone_number_id),
+        access_token: trimmed(&settings.access_token),
+        template_language: settings.template_language.trim().to_string(),
+        confirmation_template: trimmed(&settings.confirmation_template),
+        pickup_template: trimmed(&settings.pickup_template),
+        default_country_code: settings
+            .default_country_code
+            .trim()
+            .trim_start_matches('+')
+            .to_string(),
+        ..settings
+    };
+    if settings.enabled && (settings.phone_number_id.is_none() || settings.access_token.is_none()) {
+        return Err(AppError::Validation(
+            "A phone number ID and access token are needed to turn WhatsApp on".to_string(),
+        ));
+    }
+    if settings.template_language.is_empty() {
+        return Err(AppError::Validation(
+            "Template language must not be empty".to_string(),
+        ));
+    }
+    if !(0..=30).contains(&settings.pickup_days_before) {
+        return Err(AppError::Validation(
+            "Pickup reminders can go out 0 to 30 days before".to_string(),
+        ));
+    }
+    if settings.default_country_code.is_empty()
+        || !settings
+            .default_country_code
+            .chars()
+            .all(|c| c.is_ascii_digit())
+    {
+        return Err(AppError::Validation(format!(
+            "Invalid country code: {}",
+            settings.default_country_code
+        )));
+    }
+
+    let mut tx = db
+        .pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let before = load_settings(&mut tx).await?;
+    sqlx::query(
+        "INSERT OR REPLACE INTO whatsapp_settings \
+         (id, enabled, phone_number_id, access_token, template_language, confirmation_template, \
+         pickup_template, pickup_days_before, default_country_code, updated_at) \
+         VALUES (1, ?, ?, ?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP)",
+    )
+    .bind(settings.enabled)
+    .bind(&settings.phone_number_id)
+    .bind(conceal_opt(settings.access_token.as_deref())?)
+    .bind(&settings.template_language)
+    .bind(&settings.confirmation_template)
+    .bind(&settings.pickup_template)
+    .bind(settings.pickup_days_before)
+    .bind(&settings.default_country_code)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to save WhatsApp settings: {}", e))?;
+    let after = load_settings(&mut tx).await?;
+
+    // The access token stays out of the audit log
+    let view = |s: &WhatsappSettings| {
+        serde_json::json!({
+            "enabled": s.enabled,
+            "phone_number_id": s.phone_number_id,
+            "template_language": s.template_language,
+            "confirmation_template": s.confirmation_template,
+            "pickup_template": s.pickup_template,
+            "pickup_days_before": s.pickup_days_before,
+            "default_country_code": s.default_country_code,
+        })
+    };
+    audit::record(
+        &mut *tx,
+        "whatsapp_settings",
+        1,
+        "update",
+        Some(&view(&before)),
+        Some(&view(&after)),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save WhatsApp settings: {}", e))?;
+    Ok(after)
+}
+
+// Messages for one order, or the most recent across all orders
+#[tauri::command]
+pub async fn list_whatsapp_messages(
+    db: State<'_, Database>,
+    preorder_id: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Vec<WhatsappMessage>, AppError> {
+    sqlx::query_as::<_, WhatsappMessage>(&format!(
+        "SELECT {} FROM whatsapp_messages WHERE (? IS NULL OR preorder_id = ?) \
+         ORDER BY created_at DESC, id DESC LIMIT ?",
+        MESSAGE_COLUMNS
+    ))
+    .bind(preorder_id)
+    .bind(preorder_id)
+    .bind(limit.unwrap_or(200).clamp(1, 1000))
+    .fetch_all(&db.pool)
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to list WhatsApp messages: {}", e)))
+}
+
+async fn load_message(pool: &SqlitePool, id: i64) -> Result<WhatsappMessage, AppError> {
+    sqlx::query_as::<_, WhatsappMessage>(&format!(
+        "SELECT {} FROM whatsapp_messages WHERE id = ?",
+        MESSAGE_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to load WhatsApp message: {}", e))?
+    .ok_or_else(|| AppError::NotFound(format!("WhatsApp message {} not found", id)))
+}
+
+// Send a failed (or still pending) message again now
+#[tauri::command]
+pub async fn retry_whatsapp_message(
+    db: State<'_, Database>,
+    id: i64,
+) -> Result<WhatsappMessage, AppError> {
+    let message = load_message(&db.pool, id).await?;
+    if message.status == WhatsappStatus::Sent {
+        return Err(AppError::Conflict(
+            "This message has already been sent".to_string(),
+        ));
+    }
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+    let settings = load_settings(&mut conn).await?;
+    drop(conn);
+
+    send_one(&db.pool, &http::client(), &settings, &message).await?;
+    load_message(&db.pool, id).await
+}
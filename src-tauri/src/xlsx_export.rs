@@ -0,0 +1,368 @@
+use rust_xlsxwriter::{Format, Workbook, Worksheet, XlsxError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tauri::State;
+
+use crate::bulk_orders::{matching_order_ids, OrderFilter};
+use crate::csv_export::{OrderExportRow, ORDER_EXPORT_SELECT};
+use crate::db::Database;
+use crate::demand::{fill_shortfalls, DemandLine, DEMAND_SELECT};
+use crate::error::AppError;
+
+// Orders in scope, bound once as a JSON array so any number of IDs fits
+const IN_EXPORTED_ORDERS: &str = "po.id IN (SELECT value FROM json_each(?))";
+
+const MONEY_FORMAT: &str = "#,##0.00";
+
+// Label for orders without their own currency
+const DEFAULT_CURRENCY_LABEL: &str = "Default";
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct PaymentExportRow {
+    id: i64,
+    preorder_id: i64,
+    customer_name: String,
+    paid_at: String,
+    method: String,
+    reference: Option<String>,
+    amount: f64,
+    currency_code: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct XlsxExportInfo {
+    pub path: String,
+    pub orders: usize,
+    pub payments: usize,
+    pub products: usize,
+}
+
+struct Formats {
+    header: Format,
+    money: Format,
+    bold: Format,
+    bold_money: Format,
+}
+
+impl Formats {
+    fn new() -> Self {
+        Formats {
+            header: Format::new().set_bold().set_background_color("#DDEBF7"),
+            money: Format::new().set_num_format(MONEY_FORMAT),
+            bold: Format::new().set_bold(),
+            bold_money: Format::new().set_bold().set_num_format(MONEY_FORMAT),
+        }
+    }
+}
+
+// Helper: Bold header row with frozen panes and a filter over the data
+fn write_headers(
+    sheet: &mut Worksheet,
+    formats: &Formats,
+    headers: &[(&str, f64)],
+    data_rows: usize,
+) -> Result<(), XlsxError> {
+    for (col, (title, width)) in headers.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, *title, &formats.header)?;
+        sheet.set_column_width(col as u16, *width)?;
+    }
+    sheet.set_freeze_panes(1, 0)?;
+    sheet.autofilter(0, 0, data_rows as u32, (headers.len() - 1) as u16)?;
+    Ok(())
+}
+
+fn orders_sheet(orders: &[OrderExportRow], formats: &Formats) -> Result<Worksheet, XlsxError> {
+    let mut sheet = Worksheet::new();
+    sheet.set_name("Orders")?;
+    write_headers(
+        &mut sheet,
+        formats,
+        &[
+            ("Order ID", 10.0),
+            ("Created", 20.0),
+            ("Customer", 24.0),
+            ("Email", 28.0),
+            ("Confirmation Code", 18.0),
+            ("Invoice Number", 16.0),
+            ("Status", 12.0),
+            ("Event", 20.0),
+            ("Currency", 10.0),
+            ("Items", 40.0),
+            ("Total", 14.0),
+            ("Paid", 14.0),
+            ("Balance", 14.0),
+            ("Paid At", 20.0),
+            ("Notes", 30.0),
+        ],
+        orders.len(),
+    )?;
+
+    for (i, order) in orders.iter().enumerate() {
+        let row = i as u32 + 1;
+        sheet.write_number(row, 0, order.id as f64)?;
+        sheet.write_string(row, 1, order.created_at.clone().unwrap_or_default())?;
+        sheet.write_string(row, 2, &order.customer_name)?;
+        sheet.write_string(row, 3, &order.customer_email)?;
+        sheet.write_string(row, 4, &order.confirmation_code)?;
+        sheet.write_string(row, 5, order.invoice_number.clone().unwrap_or_default())?;
+        sheet.write_string(row, 6, &order.status)?;
+        sheet.write_string(row, 7, order.event_name.clone().unwrap_or_default())?;
+        sheet.write_string(row, 8, order.currency_code.clone().unwrap_or_default())?;
+        sheet.write_string(row, 9, order.items.clone().unwrap_or_default())?;
+        sheet.write_number_with_format(row, 10, order.total_amount, &formats.money)?;
+        sheet.write_number_with_format(row, 11, order.amount_paid, &formats.money)?;
+        sheet.write_number_with_format(
+            row,
+            12,
+            order.total_amount - order.amount_credited - order.amount_paid,
+            &formats.money,
+        )?;
+        sheet.write_string(row, 13, order.paid_at.clone().unwrap_or_default())?;
+        sheet.write_string(row, 14, order.notes.clone().unwrap_or_default())?;
+    }
+    Ok(sheet)
+}
+
+fn payments_sheet(
+    payments: &[PaymentExportRow],
+    formats: &Formats,
+) -> Result<Worksheet, XlsxError> {
+    let mut sheet = Worksheet::new();
+    sheet.set_name("Payments")?;
+    write_headers(
+        &mut sheet,
+        formats,
+        &[
+            ("Payment ID", 12.0),
+            ("Order ID", 10.0),
+            ("Customer", 24.0),
+            ("Paid At", 20.0),
+            ("Method", 14.0),
+            ("Reference", 20.0),
+            ("Currency", 10.0),
+            ("Amount", 14.0),
+        ],
+        payments.len(),
+    )?;
+
+    for (i, payment) in payments.iter().enumerate() {
+        let row = i as u32 + 1;
+        sheet.write_number(row, 0, payment.id as f64)?;
+        sheet.write_number(row, 1, payment.preorder_id as f64)?;
+        sheet.write_string(row, 2, &payment.customer_name)?;
+        sheet.write_string(row, 3, &payment.paid_at)?;
+        sheet.write_string(row, 4, &payment.method)?;
+        sheet.write_string(row, 5, payment.reference.clone().unwrap_or_default())?;
+        sheet.write_string(row, 6, payment.currency_code.clone().unwrap_or_default())?;
+        sheet.write_number_with_format(row, 7, payment.amount, &formats.money)?;
+    }
+    Ok(sheet)
+}
+
+fn demand_sheet(demand: &[DemandLine], formats: &Formats) -> Result<Worksheet, XlsxError> {
+    let mut sheet = Worksheet::new();
+    sheet.set_name("Demand")?;
+    write_headers(
+        &mut sheet,
+        formats,
+        &[
+            ("Product ID", 12.0),
+            ("Product", 30.0),
+            ("Orders", 10.0),
+            ("Requested", 12.0),
+            ("Unconfirmed", 13.0),
+            ("In Stock", 10.0),
+            ("On Order", 10.0),
+            ("Shortfall", 10.0),
+        ],
+        demand.len(),
+    )?;
+
+    for (i, line) in demand.iter().enumerate() {
+        let row = i as u32 + 1;
+        sheet.write_number(row, 0, line.product_id as f64)?;
+        sheet.write_string(row, 1, &line.product_name)?;
+        sheet.write_number(row, 2, line.order_count as f64)?;
+        sheet.write_number(row, 3, line.requested_quantity as f64)?;
+        sheet.write_number(row, 4, line.unconfirmed_quantity as f64)?;
+        // Untracked products leave stock and shortfall blank
+        if let Some(stock) = line.stock_quantity {
+            sheet.write_number(row, 5, stock as f64)?;
+        }
+        sheet.write_number(row, 6, line.on_order_quantity as f64)?;
+        if let Some(shortfall) = line.shortfall {
+            sheet.write_number(row, 7, shortfall as f64)?;
+        }
+    }
+    Ok(sheet)
+}
+
+// Totals per currency and order counts per status. Amounts in different
+// currencies are never added together.
+fn summary_sheet(
+    orders: &[OrderExportRow],
+    payments: usize,
+    total_shortfall: i64,
+    formats: &Formats,
+) -> Result<Worksheet, XlsxError> {
+    let mut sheet = Worksheet::new();
+    sheet.set_name("Summary")?;
+    sheet.set_column_width(0, 22)?;
+    for col in 1..=4 {
+        sheet.set_column_width(col, 14)?;
+    }
+
+    sheet.write_string_with_format(0, 0, "Generated", &formats.bold)?;
+    sheet.write_string(
+        0,
+        1,
+        chrono::Utc::now().format("%Y-%m-%d %H:%M UTC").to_string(),
+    )?;
+    sheet.write_string_with_format(1, 0, "Orders", &formats.bold)?;
+    sheet.write_number(1, 1, orders.len() as f64)?;
+    sheet.write_string_with_format(2, 0, "Payments", &formats.bold)?;
+    sheet.write_number(2, 1, payments as f64)?;
+    sheet.write_string_with_format(3, 0, "Units short", &formats.bold)?;
+    sheet.write_number(3, 1, total_shortfall as f64)?;
+
+    // (orders, total less credit notes, paid) per currency
+    let mut by_currency: BTreeMap<String, (usize, f64, f64)> = BTreeMap::new();
+    let mut by_status: BTreeMap<String, usize> = BTreeMap::new();
+    for order in orders {
+        let currency = order
+            .currency_code
+            .clone()
+            .unwrap_or_else(|| DEFAULT_CURRENCY_LABEL.to_string());
+        let entry = by_currency.entry(currency).or_default();
+        entry.0 += 1;
+        // Cancelled orders are counted but owe nothing
+        if order.status != "cancelled" {
+            entry.1 += order.total_amount - order.amount_credited;
+            entry.2 += order.amount_paid;
+        }
+        *by_status.entry(order.status.clone()).or_default() += 1;
+    }
+
+    let mut row = 5;
+    for (col, title) in ["Currency", "Orders", "Total", "Paid", "Balance"]
+        .iter()
+        .enumerate()
+    {
+        sheet.write_string_with_format(row, col as u16, *title, &formats.header)?;
+    }
+    for (currency, (count, total, paid)) in &by_currency {
+        row += 1;
+        sheet.write_string(row, 0, currency)?;
+        sheet.write_number(row, 1, *count as f64)?;
+        sheet.write_number_with_format(row, 2, *total, &formats.bold_money)?;
+        sheet.write_number_with_format(row, 3, *paid, &formats.money)?;
+        sheet.write_number_with_format(row, 4, total - paid, &formats.money)?;
+    }
+
+    row += 2;
+    sheet.write_string_with_format(row, 0, "Status", &formats.header)?;
+    sheet.write_string_with_format(row, 1, "Orders", &formats.header)?;
+    for (status, count) in &by_status {
+        row += 1;
+        sheet.write_string(row, 0, status)?;
+        sheet.write_number(row, 1, *count as f64)?;
+    }
+    Ok(sheet)
+}
+
+fn build_workbook(
+    orders: &[OrderExportRow],
+    payments: &[PaymentExportRow],
+    demand: &[DemandLine],
+    total_shortfall: i64,
+) -> Result<Workbook, XlsxError> {
+    let formats = Formats::new();
+    let mut workbook = Workbook::new();
+    workbook.push_worksheet(summary_sheet(
+        orders,
+        payments.len(),
+        total_shortfall,
+        &formats,
+    )?);
+    workbook.push_worksheet(orders_sheet(orders, &formats)?);
+    workbook.push_worksheet(payments_sheet(payments, &formats)?);
+    workbook.push_worksheet(demand_sheet(demand, &formats)?);
+    Ok(workbook)
+}
+
+// Write orders (all live orders, or those matching `filter`) to an Excel
+// workbook with Summary, Orders, Payments and Demand sheets
+#[tauri::command]
+pub async fn export_orders_xlsx(
+    db: State<'_, Database>,
+    filter: Option<OrderFilter>,
+    dest_path: String,
+) -> Result<XlsxExportInfo, AppError> {
+    let filter = filter.unwrap_or_default();
+    let mut conn = db
+        .pool
+        .acquire()
+        .await
+        .map_err(|e| format!("Failed to open database connection: {}", e))?;
+
+    let ids = matching_order_ids(&mut conn, &filter).await?;
+    let ids_json =
+        serde_json::to_string(&ids).map_err(|e| format!("Failed to encode order IDs: {}", e))?;
+
+    let orders = sqlx::query_as::<_, OrderExportRow>(&format!(
+        "{} WHERE {} ORDER BY po.id",
+        ORDER_EXPORT_SELECT, IN_EXPORTED_ORDERS
+    ))
+    .bind(&ids_json)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load orders: {}", e))?;
+
+    let payments = sqlx::query_as::<_, PaymentExportRow>(&format!(
+        "SELECT pay.id, pay.preorder_id, po.customer_name, pay.paid_at, pay.method, \
+         pay.reference, pay.amount, po.currency_code \
+         FROM payments pay JOIN preorders po ON po.id = pay.preorder_id \
+         WHERE {} ORDER BY pay.paid_at, pay.id",
+        IN_EXPORTED_ORDERS
+    ))
+    .bind(&ids_json)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to load payments: {}", e))?;
+
+    let mut demand = sqlx::query_as::<_, DemandLine>(&format!(
+        "{} AND {} GROUP BY oi.product_id ORDER BY product_name, oi.product_id",
+        DEMAND_SELECT, IN_EXPORTED_ORDERS
+    ))
+    .bind(&ids_json)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to aggregate demand: {}", e))?;
+    let total_shortfall = fill_shortfalls(&mut demand);
+
+    let mut workbook = build_workbook(&orders, &payments, &demand, total_shortfall)
+        .map_err(|e| format!("Failed to build workbook: {}", e))?;
+
+    // Save alongside and move into place so a failed export can't clobber an older file
+    let dest = PathBuf::from(&dest_path);
+    let partial = dest.with_extension(format!("partial-{}", uuid::Uuid::new_v4()));
+    let saved = workbook
+        .save(&partial)
+        .map_err(|e| AppError::Internal(format!("Failed to write workbook: {}", e)))
+        .and_then(|_| {
+            std::fs::rename(&partial, &dest)
+                .map_err(|e| AppError::Internal(format!("Failed to save workbook: {}", e)))
+        });
+    if let Err(e) = saved {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e);
+    }
+
+    Ok(XlsxExportInfo {
+        path: dest_path,
+        orders: orders.len(),
+        payments: payments.len(),
+        products: demand.len(),
+    })
+}
@@ -5,6 +5,7 @@ import { invoke } from '@tauri-apps/api/core';
 import { usePreOrders, useSmtpSettings, useCurrency, useInvoiceTemplate } from '../hooks/useDatabase';
 import { useGoogleAuthContext } from '../contexts/GoogleAuthContext';
 import { PreOrder } from '../types';
+import { errorMessage } from '../utils/errors';
 
 declare global {
     interface Window {
@@ -258,18 +259,12 @@ export function ConfirmOrder() {
         setLoading(true);
 
         try {
-            const order = await confirmByCode(confirmCode);
-            if (order) {
-                // If confirmed_at is already set in the returned object, it means it was ALREADY confirmed before this call.
-                // If it is null/undefined, it means we just confirmed it (since we returned {...order, status: 'confirmed'} without updating confirmed_at in local object)
-                const isAlreadyClaimed = !!order.confirmed_at;
-
-                if (isAlreadyClaimed) {
+            const result = await confirmByCode(confirmCode);
+            if (result) {
+                const { order, alreadyClaimed } = result;
+                if (alreadyClaimed) {
+                    // A claimed code can't be used to claim the items again
                     setError(`⚠️ Order ${order.confirmation_code} was ALREADY claimed on ${new Date(order.confirmed_at!).toLocaleString()}`);
-                    // We do NOT setConfirmedOrder here if we want to block the "Success" screen.
-                    // Or we can show the screen but with a warning.
-                    // The user said: "it cannot be used to claim items".
-                    // So let's show an error and NOT show the success screen.
                     return;
                 }
 
@@ -289,7 +284,8 @@ export function ConfirmOrder() {
                             fromEmail: auth.user_email,
                             fromName: auth.user_name || 'POTracker',
                             subject,
-                            htmlBody
+                            htmlBody,
+                            poId: order.id
                         });
                         console.log('Confirmation email sent via Gmail');
                     } else if (smtpSettings) {
@@ -298,7 +294,8 @@ export function ConfirmOrder() {
                             toEmail: order.customer_email,
                             toName: order.customer_name,
                             subject,
-                            htmlBody
+                            htmlBody,
+                            poId: order.id
                         });
                         console.log('Confirmation email sent via SMTP');
                     }
@@ -312,7 +309,7 @@ export function ConfirmOrder() {
             }
         } catch (err) {
             console.error('Failed to confirm order:', err);
-            setError('Failed to confirm order. Please try again.');
+            setError(`Failed to confirm order: ${errorMessage(err)}`);
         } finally {
             setLoading(false);
         }
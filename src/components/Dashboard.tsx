@@ -3,10 +3,11 @@ import { invoke } from '@tauri-apps/api/core';
 import QRCode from 'qrcode';
 import { useStats, usePreOrders, useSmtpSettings, useCurrency } from '../hooks/useDatabase';
 import { useGoogleAuthContext } from '../contexts/GoogleAuthContext';
+import { errorMessage } from '../utils/errors';
 
 export function Dashboard() {
     const { stats } = useStats();
-    const { orders, deleteOrder, updateConfirmationCode, getOrderItems } = usePreOrders();
+    const { orders, deleteOrder, regenerateConfirmationCode, getOrderItems } = usePreOrders();
     const { settings: smtpSettings } = useSmtpSettings();
     const { auth, isAuthenticated, getAccessToken } = useGoogleAuthContext();
     const { formatCurrency } = useCurrency();
@@ -115,7 +116,7 @@ export function Dashboard() {
             setMessage({ type: 'success', text: 'Order deleted successfully' });
         } catch (error) {
             console.error('Failed to delete order:', error);
-            setMessage({ type: 'error', text: 'Failed to delete order' });
+            setMessage({ type: 'error', text: `Failed to delete order: ${errorMessage(error)}` });
         } finally {
             setProcessingId(null);
         }
@@ -128,16 +129,13 @@ export function Dashboard() {
 
         setProcessingId(orderId);
         try {
-            // 1. Generate new code
-            const newCode: string = await invoke('generate_confirmation_code');
+            // 1. Give the order a new code
+            const newCode = await regenerateConfirmationCode(orderId);
 
-            // 2. Update DB
-            await updateConfirmationCode(orderId, newCode);
-
-            // 3. Get Items for email
+            // 2. Get Items for email
             const items = await getOrderItems(orderId);
 
-            // 4. Send Email
+            // 3. Send Email
             const qrCodeUrl = await QRCode.toDataURL(newCode);
             const htmlBody = generateEmailHtml(customerName, newCode, items, totalAmount, qrCodeUrl);
             const subject = `Updated Order Invoice - ${newCode}`;
@@ -151,7 +149,8 @@ export function Dashboard() {
                     fromEmail: auth.user_email,
                     fromName: auth.user_name || 'POTracker',
                     subject,
-                    htmlBody
+                    htmlBody,
+                    poId: orderId
                 });
                 setMessage({ type: 'success', text: `New code generated (${newCode}) and email sent!` });
             } else if (smtpSettings) {
@@ -160,7 +159,8 @@ export function Dashboard() {
                     toEmail: customerEmail,
                     toName: customerName,
                     subject,
-                    htmlBody
+                    htmlBody,
+                    poId: orderId
                 });
                 setMessage({ type: 'success', text: `New code generated (${newCode}) and email sent!` });
             } else {
@@ -169,7 +169,7 @@ export function Dashboard() {
 
         } catch (error) {
             console.error('Failed to reprocessing order:', error);
-            setMessage({ type: 'error', text: `Failed to reprocess order: ${error}` });
+            setMessage({ type: 'error', text: `Failed to reprocess order: ${errorMessage(error)}` });
         } finally {
             setProcessingId(null);
         }
@@ -184,25 +184,33 @@ export function Dashboard() {
 
             <div className="stats-grid">
                 <div className="stat-card">
-                    <div className="stat-value">{stats.totalProducts}</div>
+                    <div className="stat-value">{stats?.total_products ?? 0}</div>
                     <div className="stat-label">Total Products</div>
                 </div>
                 <div className="stat-card">
-                    <div className="stat-value">{stats.totalOrders}</div>
+                    <div className="stat-value">{stats?.order_count ?? 0}</div>
                     <div className="stat-label">Total Orders</div>
                 </div>
                 <div className="stat-card">
-                    <div className="stat-value">{stats.pendingOrders}</div>
+                    <div className="stat-value">{stats?.pending_orders ?? 0}</div>
                     <div className="stat-label">Pending Orders</div>
                 </div>
                 <div className="stat-card">
-                    <div className="stat-value">{stats.confirmedOrders}</div>
+                    <div className="stat-value">{stats?.confirmed_orders ?? 0}</div>
                     <div className="stat-label">Confirmed Orders</div>
                 </div>
                 <div className="stat-card">
-                    <div className="stat-value">{formatCurrency(stats.totalRevenue)}</div>
+                    <div className="stat-value">{formatCurrency(stats?.revenue ?? 0)}</div>
                     <div className="stat-label">Total Revenue</div>
                 </div>
+                <div className="stat-card">
+                    <div className="stat-value">{formatCurrency(stats?.unpaid_balance ?? 0)}</div>
+                    <div className="stat-label">Unpaid Balance</div>
+                </div>
+                <div className="stat-card">
+                    <div className="stat-value">{Math.round((stats?.fulfillment_rate ?? 0) * 100)}%</div>
+                    <div className="stat-label">Fulfilled</div>
+                </div>
             </div>
 
             <div className="card">
@@ -2,6 +2,7 @@ import { useState } from 'react';
 import { useEvents, useAppSettings } from '../hooks/useDatabase';
 import { Event } from '../types';
 import { CustomDatePicker } from './ui/DatePicker';
+import { errorMessage } from '../utils/errors';
 
 // Locale lookup for currency codes
 const CURRENCY_LOCALE_MAP: Record<string, string> = {
@@ -82,7 +83,7 @@ export function EventManager({ onEventsChanged }: EventManagerProps) {
                 await setCurrency(formData.currency_code, locale);
             }
         } catch (error) {
-            setMessage({ type: 'error', text: `Failed: ${error}` });
+            setMessage({ type: 'error', text: `Failed: ${errorMessage(error)}` });
         }
     };
 
@@ -92,6 +93,8 @@ export function EventManager({ onEventsChanged }: EventManagerProps) {
             await deleteEvent(id);
             setMessage({ type: 'success', text: 'Event deleted' });
             onEventsChanged?.();
+        } catch (error) {
+            setMessage({ type: 'error', text: `Failed: ${errorMessage(error)}` });
         } finally {
             setDeleting(null);
         }
@@ -6,6 +6,7 @@ import { useGoogleForms, useEvents } from '../hooks/useDatabase';
 import { useSync } from '../hooks/useSync';
 import { FormEditor } from './FormEditor';
 import { Product } from '../types';
+import { errorMessage } from '../utils/errors';
 
 
 
@@ -17,6 +18,10 @@ interface ScannedProject {
         name: string;
         url: string;
         responder_url: string;
+        modified_time?: string;
+        web_view_link?: string;
+        owners: string[];
+        accepting_responses?: boolean;
     };
     products_json?: string;
 }
@@ -66,7 +71,7 @@ export function GoogleForms() {
             await startAuth();
         } catch (error) {
             console.error('Failed to start auth:', error);
-            setMessage({ type: 'error', text: `Failed to start authentication: ${error}` });
+            setMessage({ type: 'error', text: `Failed to start authentication: ${errorMessage(error)}` });
         }
     };
 
@@ -198,7 +203,7 @@ export function GoogleForms() {
             setMessage({ type: 'success', text: 'Form created successfully!' });
         } catch (error) {
             console.error('Failed to create form:', error);
-            setMessage({ type: 'error', text: `Failed to create form: ${error}` });
+            setMessage({ type: 'error', text: `Failed to create form: ${errorMessage(error)}` });
         } finally {
             setCreating(false);
         }
@@ -240,7 +245,7 @@ export function GoogleForms() {
             }
         } catch (error) {
             console.error('Failed to scan projects:', error);
-            setMessage({ type: 'error', text: `Failed to scan projects: ${error}` });
+            setMessage({ type: 'error', text: `Failed to scan projects: ${errorMessage(error)}` });
         } finally {
             setScanning(false);
         }
@@ -291,7 +296,7 @@ export function GoogleForms() {
             }
         } catch (e) {
             console.error('Failed to import products:', e);
-            setMessage({ type: 'error', text: `Failed to import products: ${e}` });
+            setMessage({ type: 'error', text: `Failed to import products: ${errorMessage(e)}` });
         }
     };
 
@@ -312,7 +317,7 @@ export function GoogleForms() {
             setProjects(prev => prev.filter(p => p.folder_id !== folderId));
         } catch (error) {
             console.error('Failed to delete project:', error);
-            setMessage({ type: 'error', text: `Failed to delete project: ${error}` });
+            setMessage({ type: 'error', text: `Failed to delete project: ${errorMessage(error)}` });
         }
     };
 
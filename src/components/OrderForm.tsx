@@ -6,6 +6,7 @@ import { usePreOrders, useSmtpSettings, useCurrency, useInvoiceTemplate } from '
 import { useProductsContext } from '../contexts/ProductsContext';
 import { useGoogleAuthContext } from '../contexts/GoogleAuthContext';
 import { Product, InvoiceSection } from '../types';
+import { errorMessage } from '../utils/errors';
 
 const SYNC_MICROSERVICE_URL = import.meta.env.VITE_SYNC_MICROSERVICE_URL || 'http://localhost:3001';
 const GOOGLE_CLIENT_ID = import.meta.env.VITE_GOOGLE_CLIENT_ID || '';
@@ -28,6 +29,7 @@ export function OrderForm({ onOrderCreated }: OrderFormProps) {
     const [notes, setNotes] = useState('');
     const [selectedItems, setSelectedItems] = useState<Map<number, number>>(new Map());
     const [createdOrder, setCreatedOrder] = useState<{
+        id?: number;
         code: string;
         total: number;
         customerEmail: string;
@@ -66,9 +68,6 @@ export function OrderForm({ onOrderCreated }: OrderFormProps) {
         }
 
         try {
-            const confirmationCode: string = await invoke('generate_confirmation_code');
-            const total = calculateTotal();
-
             const items: { productId: number; quantity: number; unitPrice: number }[] = [];
             selectedItems.forEach((quantity, productId) => {
                 const product = products.find(p => p.id === productId);
@@ -81,18 +80,17 @@ export function OrderForm({ onOrderCreated }: OrderFormProps) {
                 }
             });
 
-            await createOrder(
+            const order = await createOrder(
                 customerName,
                 customerEmail,
-                confirmationCode,
-                total,
                 notes || null,
                 items
             );
 
             setCreatedOrder({
-                code: confirmationCode,
-                total,
+                id: order.id!,
+                code: order.confirmation_code || '',
+                total: order.total_amount,
                 customerEmail,
                 customerName
             });
@@ -101,7 +99,7 @@ export function OrderForm({ onOrderCreated }: OrderFormProps) {
             onOrderCreated?.();
         } catch (error) {
             console.error('Failed to create order:', error);
-            setMessage({ type: 'error', text: 'Failed to create order' });
+            setMessage({ type: 'error', text: `Failed to create order: ${errorMessage(error)}` });
         }
     };
 
@@ -328,6 +326,15 @@ export function OrderForm({ onOrderCreated }: OrderFormProps) {
                     throw new Error(err.error || 'Failed to send email');
                 }
 
+                if (createdOrder.id) {
+                    await invoke('record_order_email', {
+                        poId: createdOrder.id,
+                        toEmail: createdOrder.customerEmail,
+                        subject,
+                        channel: 'microservice'
+                    });
+                }
+
                 const emailType = isAuthenticated ? 'Gmail' : 'SMTP';
                 setMessage({ type: 'success', text: `Invoice email sent via ${emailType}!` });
             } else {
@@ -335,7 +342,7 @@ export function OrderForm({ onOrderCreated }: OrderFormProps) {
             }
         } catch (error) {
             console.error('Failed to send email:', error);
-            setMessage({ type: 'error', text: `Failed to send email: ${error}` });
+            setMessage({ type: 'error', text: `Failed to send email: ${errorMessage(error)}` });
         } finally {
             setSending(false);
         }
@@ -1,10 +1,26 @@
 import { useState, useEffect, useCallback, useRef } from 'react';
 import { usePreOrders, useCurrency } from '../hooks/useDatabase';
-import { PreOrder, OrderItemDetail } from '../types';
+import { PreOrder, OrderItemDetail, OrderEvent } from '../types';
 import { QRCodeSVG } from 'qrcode.react';
+import { errorMessage } from '../utils/errors';
+
+// Events the state machine accepts from each status, with their menu labels
+const STATUS_ACTIONS: Record<string, { event: OrderEvent; label: string }[]> = {
+    pending: [{ event: 'confirm', label: 'Confirm' }, { event: 'cancel', label: 'Cancel' }],
+    sent: [{ event: 'confirm', label: 'Confirm' }, { event: 'cancel', label: 'Cancel' }],
+    draft: [{ event: 'confirm', label: 'Confirm' }, { event: 'cancel', label: 'Cancel' }],
+    confirmed: [
+        { event: 'pay_deposit', label: 'Record deposit' },
+        { event: 'invoice', label: 'Invoice' },
+        { event: 'cancel', label: 'Cancel' },
+    ],
+    deposit_paid: [{ event: 'invoice', label: 'Invoice' }, { event: 'cancel', label: 'Cancel' }],
+    invoiced: [{ event: 'pay', label: 'Mark paid' }, { event: 'cancel', label: 'Cancel' }],
+    paid: [{ event: 'fulfill', label: 'Fulfill' }],
+};
 
 export function OrderList() {
-    const { orders, loading, getOrderItems, updateOrderStatus, deleteOrder, reload } = usePreOrders();
+    const { orders, loading, getOrderItems, transitionOrder, deleteOrder, reload } = usePreOrders();
     const { formatCurrency } = useCurrency();
     const [searchTerm, setSearchTerm] = useState('');
     const [statusFilter, setStatusFilter] = useState<'all' | 'pending' | 'confirmed' | 'sent'>('all');
@@ -105,8 +121,14 @@ export function OrderList() {
         if (selectedOrder?.id === id) closeModal();
     };
 
-    const handleStatusChange = async (id: number, newStatus: string) => {
-        await updateOrderStatus(id, newStatus);
+    const handleTransition = async (id: number, event: OrderEvent) => {
+        try {
+            const updated = await transitionOrder(id, event);
+            if (selectedOrder?.id === id) setSelectedOrder(updated);
+        } catch (err) {
+            setNotification(`Failed to update order: ${errorMessage(err)}`);
+            setTimeout(() => setNotification(null), 5000);
+        }
     };
 
     const filteredOrders = orders.filter(order => {
@@ -283,19 +305,21 @@ export function OrderList() {
                                         </div>
                                         <div>
                                             <div style={{ fontSize: 'var(--text-xs)', color: 'var(--color-text-muted)' }}>Status</div>
-                                            <select
-                                                className={`status-badge status-${selectedOrder.status}`}
-                                                value={selectedOrder.status}
-                                                onChange={(e) => {
-                                                    handleStatusChange(selectedOrder.id!, e.target.value);
-                                                    setSelectedOrder({ ...selectedOrder, status: e.target.value as any });
-                                                }}
-                                                style={{ border: 'none', cursor: 'pointer', marginTop: '2px' }}
-                                            >
-                                                <option value="pending">Pending</option>
-                                                <option value="confirmed">Confirmed</option>
-                                                <option value="sent">Sent</option>
-                                            </select>
+                                            <span className={`status-badge status-${selectedOrder.status}`}>{selectedOrder.status}</span>
+                                            {(STATUS_ACTIONS[selectedOrder.status || 'pending'] || []).length > 0 && (
+                                                <select
+                                                    value=""
+                                                    onChange={(e) => {
+                                                        if (e.target.value) handleTransition(selectedOrder.id!, e.target.value as OrderEvent);
+                                                    }}
+                                                    style={{ cursor: 'pointer', marginLeft: 'var(--space-sm)' }}
+                                                >
+                                                    <option value="">Change status…</option>
+                                                    {STATUS_ACTIONS[selectedOrder.status || 'pending'].map(action => (
+                                                        <option key={action.event} value={action.event}>{action.label}</option>
+                                                    ))}
+                                                </select>
+                                            )}
                                         </div>
                                     </div>
                                 </div>
@@ -2,6 +2,7 @@ import { useState } from 'react';
 import { useCurrency, useEvents } from '../hooks/useDatabase';
 import { useProductsContext } from '../contexts/ProductsContext';
 import { Product, Tag } from '../types';
+import { errorMessage } from '../utils/errors';
 
 // Preset tag colors
 const TAG_COLORS = [
@@ -34,6 +35,7 @@ export function ProductList() {
         name: '', description: '', price: '', currency_code: 'USD', event_id: '', prices: [], selectedTagIds: []
     });
     const [deleting, setDeleting] = useState<number | null>(null);
+    const [deleteError, setDeleteError] = useState<string | null>(null);
 
     // Tag management modal state
     const [showTagModal, setShowTagModal] = useState(false);
@@ -104,8 +106,11 @@ export function ProductList() {
 
     const handleDelete = async (id: number) => {
         setDeleting(id);
+        setDeleteError(null);
         try {
             await deleteProduct(id);
+        } catch (error) {
+            setDeleteError(errorMessage(error));
         } finally {
             setDeleting(null);
         }
@@ -175,6 +180,12 @@ export function ProductList() {
                 <p className="page-subtitle">Manage your product catalog</p>
             </div>
 
+            {deleteError && (
+                <div className="toast error" onClick={() => setDeleteError(null)}>
+                    {deleteError}
+                </div>
+            )}
+
             {/* Tag Filter Bar */}
             {tags.length > 0 && (
                 <div style={{
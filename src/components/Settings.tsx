@@ -2,6 +2,7 @@ import { useState, useEffect } from 'react';
 import { useSmtpSettings, useAppSettings } from '../hooks/useDatabase';
 import { useGoogleAuthContext } from '../contexts/GoogleAuthContext';
 import { InvoiceEditor } from './InvoiceEditor';
+import { errorMessage } from '../utils/errors';
 
 export function Settings() {
     const { settings, loading, saveSettings } = useSmtpSettings();
@@ -94,7 +95,7 @@ export function Settings() {
             setMessage({ type: 'success', text: 'Authentication successful!' });
         } catch (error) {
             console.error('Failed to start auth:', error);
-            setMessage({ type: 'error', text: `${error}` });
+            setMessage({ type: 'error', text: errorMessage(error) });
         }
     };
 
@@ -117,7 +118,7 @@ export function Settings() {
             setMessage({ type: 'success', text: 'API key saved successfully!' });
         } catch (error) {
             console.error('Failed to save API key:', error);
-            setMessage({ type: 'error', text: `Failed to save API key: ${error}` });
+            setMessage({ type: 'error', text: `Failed to save API key: ${errorMessage(error)}` });
         }
     };
 
@@ -2,6 +2,7 @@ import { createContext, useContext, useState, useEffect, useCallback, ReactNode
 import { invoke } from '@tauri-apps/api/core';
 import { openUrl } from '@tauri-apps/plugin-opener';
 import Database from '@tauri-apps/plugin-sql';
+import { getDatabaseUrl } from '../utils/databaseUrl';
 import { GoogleAuth } from '../types';
 
 // Get credentials from environment variables (set at build time)
@@ -29,7 +30,7 @@ let db: Database | null = null;
 
 async function getDatabase(): Promise<Database> {
     if (!db) {
-        db = await Database.load('sqlite:potracker.db');
+        db = await Database.load(await getDatabaseUrl());
 
         // Create tables if not exists
         await db.execute(`
@@ -1,5 +1,7 @@
 import { createContext, useContext, useState, useEffect, useCallback, ReactNode } from 'react';
 import Database from '@tauri-apps/plugin-sql';
+import { invoke } from '@tauri-apps/api/core';
+import { getDatabaseUrl } from '../utils/databaseUrl';
 import { Product, Tag } from '../types';
 
 // Reuse the singleton database from useDatabase
@@ -7,7 +9,7 @@ let db: Database | null = null;
 
 async function getDatabase(): Promise<Database> {
     if (!db) {
-        db = await Database.load('sqlite:potracker.db');
+        db = await Database.load(await getDatabaseUrl());
     }
     return db;
 }
@@ -158,8 +160,8 @@ export function ProductsProvider({ children }: ProductsProviderProps) {
     };
 
     const deleteProduct = async (id: number) => {
-        const database = await getDatabase();
-        await database.execute('UPDATE products SET is_active = 0 WHERE id = ?', [id]);
+        // Soft delete in the backend, which refuses products still on open orders
+        await invoke('delete_product', { id });
         await loadProducts();
     };
 
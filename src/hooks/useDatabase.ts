@@ -1,13 +1,15 @@
 import { useEffect, useState, useCallback } from 'react';
 import Database from '@tauri-apps/plugin-sql';
-import { Product, PreOrder, OrderItem, SmtpSettings, Event, AppSettings, Tag } from '../types';
+import { invoke } from '@tauri-apps/api/core';
+import { getDatabaseUrl } from '../utils/databaseUrl';
+import { Product, PreOrder, OrderEvent, OrderItem, SmtpSettings, Event, AppSettings, Tag, DashboardPeriod, DashboardStats } from '../types';
 import { runProductUpdater } from '../utils/productUpdater';
 
 let db: Database | null = null;
 
 export async function getDatabase(): Promise<Database> {
     if (!db) {
-        db = await Database.load('sqlite:potracker.db');
+        db = await Database.load(await getDatabaseUrl());
 
         // Run migrations - Events table (must be first)
         await db.execute(`
@@ -236,9 +238,8 @@ export function useProducts() {
     };
 
     const deleteProduct = async (id: number) => {
-        const database = await getDatabase();
-        // Soft delete instead of hard delete
-        await database.execute('UPDATE products SET is_active = 0 WHERE id = ?', [id]);
+        // Soft delete in the backend, which refuses products still on open orders
+        await invoke('delete_product', { id });
         await loadProducts();
     };
 
@@ -253,7 +254,7 @@ export function usePreOrders(options: { autoLoad?: boolean } = { autoLoad: true
     const loadOrders = useCallback(async () => {
         try {
             const database = await getDatabase();
-            const result = await database.select<PreOrder[]>('SELECT * FROM preorders ORDER BY created_at DESC');
+            const result = await database.select<PreOrder[]>('SELECT * FROM preorders WHERE deleted_at IS NULL ORDER BY created_at DESC');
             setOrders(result);
         } catch (error) {
             console.error('Failed to load orders:', error);
@@ -268,32 +269,29 @@ export function usePreOrders(options: { autoLoad?: boolean } = { autoLoad: true
         }
     }, [loadOrders, options.autoLoad]);
 
+    // Create the order in the backend, which checks stock, files the customer,
+    // assigns a confirmation code and publishes OrderCreated
     const createOrder = async (
         customerName: string,
         customerEmail: string,
-        confirmationCode: string,
-        totalAmount: number,
         notes: string | null,
         items: { productId: number; quantity: number; unitPrice: number }[]
-    ) => {
-        const database = await getDatabase();
-
-        const result = await database.execute(
-            'INSERT INTO preorders (customer_name, customer_email, confirmation_code, total_amount, notes) VALUES (?, ?, ?, ?, ?)',
-            [customerName, customerEmail, confirmationCode, totalAmount, notes]
-        );
-
-        const orderId = result.lastInsertId;
-
-        for (const item of items) {
-            await database.execute(
-                'INSERT INTO order_items (preorder_id, product_id, quantity, unit_price) VALUES (?, ?, ?, ?)',
-                [orderId, item.productId, item.quantity, item.unitPrice]
-            );
-        }
+    ): Promise<PreOrder> => {
+        const order = await invoke<PreOrder>('create_order', {
+            order: {
+                customer_name: customerName,
+                customer_email: customerEmail,
+                notes,
+                items: items.map(item => ({
+                    product_id: item.productId,
+                    quantity: item.quantity,
+                    unit_price: item.unitPrice
+                }))
+            }
+        });
 
         await loadOrders();
-        return orderId;
+        return order;
     };
 
     const getOrderItems = async (orderId: number) => {
@@ -307,60 +305,50 @@ export function usePreOrders(options: { autoLoad?: boolean } = { autoLoad: true
         );
     };
 
-    const updateOrderStatus = async (id: number, status: string) => {
-        const database = await getDatabase();
-        if (status === 'confirmed') {
-            await database.execute(
-                'UPDATE preorders SET status = ?, confirmed_at = CURRENT_TIMESTAMP WHERE id = ?',
-                [status, id]
-            );
-        } else {
-            await database.execute('UPDATE preorders SET status = ? WHERE id = ?', [status, id]);
-        }
+    // Status changes go through the backend state machine, which rejects
+    // illegal transitions and keeps stock, history and timestamps in step.
+    const transitionOrder = async (id: number, event: OrderEvent): Promise<PreOrder> => {
+        const order = await invoke<PreOrder>('transition_order', { poId: id, event });
         await loadOrders();
+        return order;
     };
 
-    const confirmByCode = async (code: string): Promise<PreOrder | null> => {
-        const database = await getDatabase();
-        const orders = await database.select<PreOrder[]>(
-            'SELECT * FROM preorders WHERE confirmation_code = ?',
-            [code.toUpperCase()]
-        );
-
-        if (orders.length > 0) {
-            const order = orders[0];
-            // Check if already confirmed
-            if (order.status === 'confirmed') {
-                console.log("Order already confirmed:", order);
-                return order; // Return order but don't update status, component will check status
-            }
-
-            await database.execute(
-                'UPDATE preorders SET status = ?, confirmed_at = CURRENT_TIMESTAMP WHERE confirmation_code = ?',
-                ['confirmed', code.toUpperCase()]
-            );
-            await loadOrders();
-            return { ...order, status: 'confirmed' };
+    // Confirm the order a customer's code belongs to. The backend lookup is
+    // rate limited and compares codes in constant time; confirming goes through
+    // the order state machine so history, stock and events stay consistent.
+    const confirmByCode = async (code: string): Promise<{ order: PreOrder; alreadyClaimed: boolean } | null> => {
+        const order = await invoke<PreOrder | null>('lookup_order_by_confirmation_code', { code });
+        if (!order) {
+            return null;
         }
-        return null;
+        if (order.confirmed_at) {
+            return { order, alreadyClaimed: true };
+        }
+
+        const confirmed = await invoke<PreOrder>('transition_order', { poId: order.id, event: 'confirm' });
+        await loadOrders();
+        return { order: confirmed, alreadyClaimed: false };
     };
 
     const deleteOrder = async (id: number) => {
-        const database = await getDatabase();
-        await database.execute('DELETE FROM preorders WHERE id = ?', [id]);
+        // Soft delete in the backend, which audits it. Passing the version the
+        // list was loaded at makes it refuse if the order was edited since.
+        const order = orders.find(o => o.id === id);
+        if (!order) {
+            throw new Error(`Order ${id} is not loaded`);
+        }
+        await invoke('delete_order', { id, expectedVersion: order.version ?? 0 });
         await loadOrders();
     };
 
-    const updateConfirmationCode = async (id: number, newCode: string) => {
-        const database = await getDatabase();
-        await database.execute(
-            'UPDATE preorders SET confirmation_code = ? WHERE id = ?',
-            [newCode, id]
-        );
+    // Give an order a new confirmation code; returns the code
+    const regenerateConfirmationCode = async (id: number): Promise<string> => {
+        const order = await invoke<PreOrder>('regenerate_confirmation_code', { orderId: id });
         await loadOrders();
+        return order.confirmation_code!;
     };
 
-    return { orders, loading, createOrder, getOrderItems, updateOrderStatus, confirmByCode, deleteOrder, updateConfirmationCode, reload: loadOrders };
+    return { orders, loading, createOrder, getOrderItems, transitionOrder, confirmByCode, deleteOrder, regenerateConfirmationCode, reload: loadOrders };
 }
 
 // SMTP Settings hooks
@@ -384,13 +372,12 @@ export function useSmtpSettings() {
         loadSettings();
     }, [loadSettings]);
 
+    // Saved through the backend, which validates and audits the change;
+    // an empty password keeps the stored one
     const saveSettings = async (newSettings: SmtpSettings) => {
-        const database = await getDatabase();
-        await database.execute(
-            `INSERT OR REPLACE INTO smtp_settings (id, smtp_server, smtp_port, username, password, from_email, from_name) 
-       VALUES (1, ?, ?, ?, ?, ?, ?)`,
-            [newSettings.smtp_server, newSettings.smtp_port, newSettings.username, newSettings.password, newSettings.from_email, newSettings.from_name || 'POTracker']
-        );
+        await invoke('save_smtp_config', {
+            config: { ...newSettings, from_name: newSettings.from_name || 'POTracker' }
+        });
         await loadSettings();
     };
 
@@ -398,36 +385,16 @@ export function useSmtpSettings() {
 }
 
 // Stats hooks
-export function useStats() {
-    const [stats, setStats] = useState({
-        totalProducts: 0,
-        totalOrders: 0,
-        pendingOrders: 0,
-        confirmedOrders: 0,
-        totalRevenue: 0
-    });
+export function useStats(period: DashboardPeriod = 'all') {
+    const [stats, setStats] = useState<DashboardStats | null>(null);
 
     const loadStats = useCallback(async () => {
         try {
-            const database = await getDatabase();
-
-            const products = await database.select<{ count: number }[]>('SELECT COUNT(*) as count FROM products');
-            const orders = await database.select<{ count: number }[]>('SELECT COUNT(*) as count FROM preorders');
-            const pending = await database.select<{ count: number }[]>("SELECT COUNT(*) as count FROM preorders WHERE status = 'pending'");
-            const confirmed = await database.select<{ count: number }[]>("SELECT COUNT(*) as count FROM preorders WHERE status = 'confirmed'");
-            const revenue = await database.select<{ total: number }[]>("SELECT COALESCE(SUM(total_amount), 0) as total FROM preorders WHERE status = 'confirmed'");
-
-            setStats({
-                totalProducts: products[0]?.count || 0,
-                totalOrders: orders[0]?.count || 0,
-                pendingOrders: pending[0]?.count || 0,
-                confirmedOrders: confirmed[0]?.count || 0,
-                totalRevenue: revenue[0]?.total || 0
-            });
+            setStats(await invoke<DashboardStats>('get_dashboard_stats', { period }));
         } catch (error) {
             console.error('Failed to load stats:', error);
         }
-    }, []);
+    }, [period]);
 
     useEffect(() => {
         loadStats();
@@ -494,11 +461,9 @@ export function useGoogleAuth() {
     }, [loadAuth]);
 
     const saveConfig = async (clientId: string, clientSecret: string) => {
-        const database = await getDatabase();
-        await database.execute(
-            'INSERT OR REPLACE INTO google_config (id, client_id, client_secret) VALUES (1, ?, ?)',
-            [clientId, clientSecret]
-        );
+        await invoke('save_oauth_client', {
+            config: { client_id: clientId, client_secret: clientSecret }
+        });
         await loadAuth();
     };
 
@@ -628,11 +593,11 @@ export function useGoogleForms() {
         return result.length > 0;
     };
 
-    const markResponseSynced = async (responseId: string, formId: string) => {
+    const markResponseSynced = async (responseId: string, formId: string, preorderId?: number) => {
         const database = await getDatabase();
         await database.execute(
-            'INSERT OR IGNORE INTO synced_responses (response_id, form_id) VALUES (?, ?)',
-            [responseId, formId]
+            'INSERT OR IGNORE INTO synced_responses (response_id, form_id, preorder_id) VALUES (?, ?, ?)',
+            [responseId, formId, preorderId ?? null]
         );
     };
 
@@ -697,11 +662,9 @@ export function useEvents() {
     };
 
     const deleteEvent = async (id: number) => {
-        const database = await getDatabase();
-        // Clear event reference from products and orders
-        await database.execute('UPDATE products SET event_id = NULL WHERE event_id = ?', [id]);
-        await database.execute('UPDATE preorders SET event_id = NULL WHERE event_id = ?', [id]);
-        await database.execute('DELETE FROM events WHERE id = ?', [id]);
+        // The backend refuses while the event has open orders; otherwise it
+        // archives the event's orders and detaches its products
+        await invoke('delete_event', { id });
         await loadEvents();
     };
 
@@ -885,21 +848,9 @@ export function useInvoiceTemplate() {
     }, [loadTemplate]);
 
     const saveTemplate = async (newTemplate: typeof DEFAULT_INVOICE_TEMPLATE) => {
-        const database = await getDatabase();
-        await database.execute(
-            `INSERT OR REPLACE INTO invoice_templates (id, sections, header_title, header_subtitle, footer_text, primary_color, secondary_color, use_banner_image, banner_image_url)
-             VALUES (1, ?, ?, ?, ?, ?, ?, ?, ?)`,
-            [
-                JSON.stringify(newTemplate.sections),
-                newTemplate.header_title,
-                newTemplate.header_subtitle,
-                newTemplate.footer_text,
-                newTemplate.primary_color,
-                newTemplate.secondary_color,
-                newTemplate.use_banner_image ? 1 : 0,
-                newTemplate.banner_image_url || ''
-            ]
-        );
+        await invoke('save_email_template', {
+            template: { ...newTemplate, banner_image_url: newTemplate.banner_image_url || '' }
+        });
         setTemplate(newTemplate);
     };
 
@@ -254,19 +254,19 @@ export function useSync() {
                     }
                 }
 
-                if (items.length > 0) {
-                    // Generate confirmation code
-                    const confirmationCode: string = await invoke('generate_confirmation_code');
+                let orderId: number | undefined;
 
-                    // Create order
-                    await createOrder(
+                if (items.length > 0) {
+                    // Create order; the backend assigns its confirmation code and total
+                    const order = await createOrder(
                         customerName,
                         customerEmail,
-                        confirmationCode,
-                        totalAmount,
                         `Imported from Google Form on ${new Date().toLocaleString()}`,
                         items
                     );
+                    orderId = order.id;
+                    const confirmationCode = order.confirmation_code || '';
+                    totalAmount = order.total_amount;
 
                     // Send Email via Microservice
                     try {
@@ -373,6 +373,15 @@ export function useSync() {
                                 throw new Error(err.error || 'Failed to send email via microservice');
                             }
                             console.log(`Sent invoice email to ${customerEmail} via ${emailPayload.type}`);
+
+                            if (orderId) {
+                                await invoke('record_order_email', {
+                                    poId: orderId,
+                                    toEmail: customerEmail,
+                                    subject,
+                                    channel: 'microservice'
+                                });
+                            }
                         } else {
                             console.warn('No email configured (Gmail or SMTP), skipping email');
                         }
@@ -386,7 +395,7 @@ export function useSync() {
                 }
 
                 // Mark as synced
-                await markResponseSynced(formResponse.responseId, formId);
+                await markResponseSynced(formResponse.responseId, formId, orderId);
             }
 
             return imported;
@@ -35,6 +35,10 @@ export interface Product {
     tags?: Tag[];
     image_url?: string;
     event_id?: number;
+    stock_quantity?: number | null; // null when stock isn't tracked
+    low_stock_threshold?: number | null;
+    supplier_id?: number | null;
+    barcode?: string | null; // EAN/UPC digits or Code128 text
     created_at?: string;
 }
 
@@ -43,14 +47,29 @@ export interface PreOrder {
     customer_name: string;
     customer_email: string;
     confirmation_code?: string;
-    status?: 'pending' | 'sent' | 'confirmed';
+    invoice_number?: string;
+    currency_code?: string;
+    status?: 'pending' | 'sent' | 'confirmed' | 'draft' | 'deposit_paid' | 'invoiced' | 'paid' | 'fulfilled' | 'cancelled';
     total_amount: number;
+    amount_paid?: number; // net of refunds
+    amount_credited?: number; // by credit notes
+    deposit_percent?: number | null; // share of the total due up front
+    deposit_amount?: number | null; // fixed when the deposit invoice is issued
+    deposit_invoice_number?: string | null;
+    deposit_invoiced_at?: string | null;
+    deposit_paid_at?: string | null;
     notes?: string;
     event_id?: number;
+    recurring_order_id?: number;
     created_at?: string;
     confirmed_at?: string;
+    deleted_at?: string;
+    version?: number;
 }
 
+// Lifecycle events accepted by the order state machine
+export type OrderEvent = 'confirm' | 'pay_deposit' | 'invoice' | 'pay' | 'fulfill' | 'cancel';
+
 export interface OrderItem {
     id?: number;
     preorder_id: number;
@@ -155,3 +174,1363 @@ export interface InvoiceTemplate {
 
 export type View = 'dashboard' | 'products' | 'new-order' | 'confirm' | 'settings' | 'google-forms' | 'events' | 'orders';
 
+export interface OrderNote {
+    id: number;
+    preorder_id: number;
+    body: string;
+    author?: string;
+    created_at?: string;
+}
+
+export interface TimelineEntry {
+    at: string;
+    kind: 'created' | 'status_change' | 'note' | 'email' | 'payment' | 'refund' | 'credit_note';
+    summary: string;
+    details: Record<string, unknown>;
+}
+
+export interface ArchivedOrder {
+    id: number;
+    customer_name: string;
+    customer_email: string;
+    confirmation_code?: string;
+    invoice_number?: string;
+    status: NonNullable<PreOrder['status']>;
+    total_amount: number;
+    amount_paid: number;
+    currency_code?: string;
+    event_id?: number;
+    created_at?: string;
+    deleted_at?: string;
+    archived_at?: string;
+}
+
+export interface ArchiveSettings {
+    archive_after_days: number | null;
+}
+
+export interface Supplier {
+    id: number;
+    name: string;
+    email?: string;
+    phone?: string;
+    notes?: string;
+    created_at?: string;
+}
+
+export type SupplierOrderStatus = 'draft' | 'ordered' | 'received' | 'cancelled';
+
+export interface SupplierOrderItem {
+    id: number;
+    supplier_order_id: number;
+    product_id: number;
+    product_name?: string;
+    quantity: number;
+    unit_cost?: number;
+    demand: { supplier_order_item_id: number; preorder_id: number; quantity: number }[];
+}
+
+export interface SupplierOrder {
+    id: number;
+    supplier_id?: number;
+    supplier_name?: string;
+    status: SupplierOrderStatus;
+    currency_code?: string;
+    notes?: string;
+    ordered_at?: string;
+    received_at?: string;
+    cancelled_at?: string;
+    created_at?: string;
+    items: SupplierOrderItem[];
+}
+
+export interface ProductDemand {
+    product_id: number;
+    product_name: string;
+    supplier_id?: number;
+    uncovered_quantity: number;
+    order_ids: number[];
+}
+
+export interface Fulfillment {
+    preorder_id: number;
+    method: 'shipping' | 'pickup';
+    courier?: string;
+    tracking_number?: string;
+    notes?: string;
+    packed_at?: string;
+    shipped_at?: string;
+    delivered_at?: string;
+    updated_at?: string;
+}
+
+export interface PackingListProduct {
+    product_id: number;
+    product_name: string;
+    total_quantity: number;
+    orders: { order_id: number; customer_name: string; confirmation_code: string; quantity: number }[];
+}
+
+export interface BatchFulfillResult {
+    fulfilled: number[];
+    skipped: { order_id: number; reason: string }[];
+}
+
+export interface Profile {
+    id: string;
+    name: string;
+    created_at?: string;
+}
+
+export interface ActiveProfile {
+    profile: Profile;
+    database_url: string;
+}
+
+export type AppErrorKind =
+    | 'validation'
+    | 'not_found'
+    | 'conflict'
+    | 'in_use'
+    | 'auth_expired'
+    | 'permission_denied'
+    | 'scope_missing'
+    | 'rate_limited'
+    | 'network'
+    | 'provider'
+    | 'locked'
+    | 'cancelled'
+    | 'internal';
+
+// Error returned by backend commands; branch on `kind` rather than the message
+export interface AppError {
+    kind: AppErrorKind;
+    message: string;
+    retryable: boolean;
+    status: number | null;
+    // Order edits that lost to a newer save: the order as it is now
+    expected_version?: number;
+    current?: PreOrder;
+    // 'in_use': the open records still pointing at the entity
+    entity?: string;
+    id?: number;
+    references?: { entity: string; ids: number[] }[];
+}
+
+export interface OrderFilter {
+    status?: string;
+    event_id?: number;
+    form_id?: string;
+    customer_email?: string;
+    created_from?: string;
+    created_to?: string;
+    order_ids?: number[];
+}
+
+export type BulkChange =
+    | { op: 'transition'; event: Exclude<OrderEvent, 'pay_deposit'> }
+    | { op: 'discount'; percent: number }
+    | { op: 'set_event'; event_id: number | null }
+    | { op: 'append_note'; note: string };
+
+export interface BulkUpdateResult {
+    dry_run: boolean;
+    matched: number;
+    orders: {
+        order_id: number;
+        customer_name: string;
+        status_before: string;
+        status_after: string;
+        total_before: number;
+        total_after: number;
+    }[];
+    errors: { order_id: number; message: string }[];
+}
+
+export type DashboardPeriod = 'today' | 'week' | 'month' | 'year' | 'all';
+
+export interface DashboardStats {
+    period: DashboardPeriod;
+    since: string | null;
+    total_products: number;
+    order_count: number;
+    pending_orders: number;
+    confirmed_orders: number;
+    cancelled_orders: number;
+    revenue: number;
+    amount_paid: number;
+    unpaid_balance: number;
+    fulfillment_rate: number;
+    top_products: {
+        product_id: number;
+        product_name: string;
+        quantity: number;
+        revenue: number;
+    }[];
+}
+
+// Predefined reports computed by run_report, also exportable to CSV and PDF
+export type ReportId =
+    | 'revenue_by_period'
+    | 'revenue_by_product'
+    | 'revenue_by_customer'
+    | 'unpaid_aging'
+    | 'campaign_profit'
+    | 'form_profit';
+
+export type ReportGranularity = 'day' | 'week' | 'month' | 'year';
+
+export interface ReportParams {
+    // Dates (YYYY-MM-DD) or UTC datetimes; the profit reports also take
+    // expenses incurred in the range
+    created_from?: string | null;
+    created_to?: string | null;
+    granularity?: ReportGranularity | null;
+    limit?: number | null;
+}
+
+export type ReportColumnKind = 'text' | 'integer' | 'money';
+
+export interface ReportColumn {
+    key: string;
+    label: string;
+    kind: ReportColumnKind;
+}
+
+export type ReportValue = number | string | null;
+
+export interface ReportDataset {
+    report: ReportId;
+    title: string;
+    params: ReportParams;
+    // Amounts are in this currency's units
+    currency: string;
+    columns: ReportColumn[];
+    // Cells in column order
+    rows: ReportValue[][];
+    totals: ReportValue[];
+    generated_at: string;
+}
+
+export interface CustomerSpend {
+    // null for customers missing from the directory
+    customer_id: number | null;
+    name: string;
+    email: string;
+    order_count: number;
+    total_spent: number;
+    last_order_at: string | null;
+}
+
+// From get_customer_analytics, over confirmed-or-later orders
+export interface CustomerAnalytics {
+    customers: number;
+    repeat_customers: number;
+    // 0-1
+    repeat_rate: number;
+    average_order_value: number;
+    churn_months: number;
+    // No order in churn_months, biggest spenders first
+    churned: CustomerSpend[];
+    top_spenders: CustomerSpend[];
+}
+
+export type ExpenseCategory = 'fee' | 'shipping' | 'marketing' | 'supplies' | 'other';
+
+// A cost feeding the profit reports, recorded against a campaign (event) or
+// a Google Form, or neither for general overhead
+export interface Expense {
+    id: number;
+    event_id: number | null;
+    event_name: string | null;
+    form_id: string | null;
+    category: ExpenseCategory;
+    description: string;
+    amount: number;
+    incurred_at: string;
+    created_at: string | null;
+}
+
+export interface ExpenseInput {
+    event_id?: number | null;
+    form_id?: string | null;
+    category: ExpenseCategory;
+    description: string;
+    amount: number;
+    incurred_at?: string | null;
+}
+
+export interface DemandLine {
+    product_id: number;
+    product_name: string;
+    supplier_id: number | null;
+    stock_quantity: number | null;
+    order_count: number;
+    requested_quantity: number;
+    unconfirmed_quantity: number;
+    on_order_quantity: number;
+    shortfall: number | null;
+}
+
+export interface DemandReport {
+    form_id: string | null;
+    event_id: number | null;
+    order_count: number;
+    products: DemandLine[];
+    total_shortfall: number;
+}
+
+export interface CsvExportInfo {
+    path: string;
+    rows: number;
+}
+
+export interface ProductColumnMapping {
+    name?: string;
+    unique_id?: string;
+    description?: string;
+    price?: string;
+    currency_code?: string;
+    image_url?: string;
+    stock_quantity?: string;
+    low_stock_threshold?: string;
+}
+
+export type ProductImportMode = 'create' | 'upsert';
+
+export interface ProductImportResult {
+    created: number;
+    updated: number;
+    errors: { row: number; message: string }[];
+}
+
+export interface XlsxExportInfo {
+    path: string;
+    orders: number;
+    payments: number;
+    products: number;
+}
+
+export interface SheetExportInfo {
+    spreadsheet_id: string;
+    spreadsheet_url: string;
+    rows: number;
+}
+
+export interface SheetColumnMapping {
+    customer_name: string;
+    customer_email: string;
+    products?: Record<string, number>;
+    row_key?: string;
+}
+
+export interface ParsedOrder {
+    response_id: string;
+    row: number;
+    customer_name: string;
+    customer_email: string;
+    items: { product_id: number; quantity: number; unit_price: number }[];
+    total_amount: number;
+    already_imported: boolean;
+    warnings: string[];
+}
+
+export interface SheetImport {
+    source_id: string;
+    orders: ParsedOrder[];
+    unmatched_columns: string[];
+}
+
+// Result of export_all_data, verify_data_export and import_all_data. Exports
+// are signed with this install's Ed25519 key; signer is the base64 public key,
+// null for an unsigned file from an older version.
+export interface DataTransferInfo {
+    path: string;
+    schema_version: number;
+    tables: Record<string, number>;
+    skipped_tables: string[];
+    signer: string | null;
+    signed_by_this_install: boolean;
+}
+
+export interface ContactImportResult {
+    created: number;
+    updated: number;
+    unchanged: number;
+    skipped: string[];
+}
+
+export interface ContactRecord {
+    name: string;
+    email: string;
+    phone: string | null;
+    address: string | null;
+}
+
+export interface ContactColumnMapping {
+    name?: string | null;
+    first_name?: string | null;
+    last_name?: string | null;
+    email?: string | null;
+    phone?: string | null;
+    address?: string | null;
+}
+
+export interface ContactFilePreview {
+    headers: string[];
+    mapping: ContactColumnMapping;
+    contacts: ContactRecord[];
+    total_rows: number;
+}
+
+export interface InvoiceExportInfo {
+    dest_dir: string;
+    files: string[];
+    merged_path: string | null;
+    skipped_order_ids: number[];
+}
+
+export interface PackingDocuments {
+    packing_slips_path: string;
+    pick_list_path: string;
+    orders: number;
+    products: number;
+    units: number;
+}
+
+export interface InvoiceLayoutTemplate {
+    title: string;
+    business_name: string | null;
+    business_details: string | null;
+    logo: string | null;
+    accent_color: string;
+    footer_terms: string | null;
+    template_html: string | null;
+    // Merchant's static QRIS payload, printed on unpaid rupiah invoices
+    qris_payload: string | null;
+    // Put the balance due into the printed code
+    qris_dynamic: boolean;
+}
+
+export interface InvoicePreview {
+    html: string;
+    pdf_base64: string;
+    unknown_placeholders: string[];
+}
+
+export interface InvoiceQris {
+    payload: string;
+    amount: number | null;
+    merchant_name: string | null;
+    // PNG data URL
+    image: string;
+}
+
+// Language of backend-generated invoices, packing documents and emails
+export type AppLocale = 'en' | 'id';
+
+export interface MoneyFormat {
+    currency: string;
+    locale: string;
+    symbol: string;
+    symbol_after: boolean;
+    symbol_spaced: boolean;
+    decimals: number;
+    group_separator: string;
+    decimal_separator: string;
+}
+
+export type LegacySource =
+    | { type: 'file'; path: string }
+    | { type: 'google_sheet'; access_token: string; spreadsheet_id: string; range: string };
+
+// Header text per field; null/omitted fields are guessed from the headers
+export interface LegacyColumnMapping {
+    customer_email?: string | null;
+    customer_name?: string | null;
+    product?: string | null;
+    quantity?: string | null;
+    unit_price?: string | null;
+    line_total?: string | null;
+    paid?: string | null;
+    order_ref?: string | null;
+    date?: string | null;
+    phone?: string | null;
+    address?: string | null;
+    notes?: string | null;
+}
+
+export interface LegacyOrderSummary {
+    order_id: number | null;
+    rows: number[];
+    customer_name: string;
+    customer_email: string;
+    ordered_at: string | null;
+    items: number;
+    total: number;
+    paid: number;
+    status: string;
+}
+
+export interface LegacyMigrationReport {
+    dry_run: boolean;
+    source_id: string;
+    columns: LegacyColumnMapping;
+    rows: number;
+    orders: LegacyOrderSummary[];
+    customers_created: number;
+    customers_updated: number;
+    products_created: string[];
+    payments_created: number;
+    already_imported: number;
+    issues: { row: number; message: string }[];
+}
+
+export type PickupOutcome = 'collected' | 'already_collected' | 'not_paid' | 'cancelled' | 'shipping_order';
+
+export interface PickupVerification {
+    outcome: PickupOutcome;
+    order_id: number;
+    customer_name: string;
+    confirmation_code: string;
+    status: string;
+    balance_due: number;
+    collected_at: string | null;
+    items: { product_name: string; quantity: number }[];
+}
+
+export interface QueuedPickupScan {
+    id: string;
+    confirmation_code: string;
+    scanned_at: string;
+}
+
+export type PickupSyncStatus = 'collected' | 'conflict' | 'rejected' | 'failed';
+
+export interface PickupSyncResult {
+    scan: QueuedPickupScan;
+    status: PickupSyncStatus;
+    verification: PickupVerification | null;
+    message: string | null;
+}
+
+export interface PickupQueueSync {
+    results: PickupSyncResult[];
+    remaining: number;
+}
+
+export interface PaymentProofMatch {
+    order_id: number;
+    customer_name: string;
+    confirmation_code: string;
+    invoice_number: string | null;
+    status: string;
+    balance_due: number;
+    score: number;
+    reasons: string[];
+}
+
+export interface PaymentProofScan {
+    text: string;
+    amount: number | null;
+    paid_at: string | null;
+    reference: string | null;
+    duplicate_payment_id: number | null;
+    suggestions: PaymentProofMatch[];
+}
+
+// Argument of record_payment
+export interface PaymentInput {
+    preorder_id: number;
+    amount: number;
+    method: string;
+    paid_at: string | null;
+    reference: string | null;
+    proof_file_id: string | null;
+    proof_file_name: string | null;
+    notes: string | null;
+}
+
+// Argument of record_refund; amount is positive, up to what's been paid
+export interface RefundInput {
+    preorder_id: number;
+    credit_note_id: number | null;
+    amount: number;
+    method: string;
+    refunded_at: string | null;
+    reference: string | null;
+    notes: string | null;
+}
+
+export interface Refund {
+    id: number;
+    preorder_id: number;
+    credit_note_id: number | null;
+    amount: number;
+    method: string;
+    refunded_at: string;
+    reference: string | null;
+    notes: string | null;
+    created_at?: string;
+}
+
+export type CreditReason = 'returned' | 'damaged' | 'not_delivered' | 'price_adjustment' | 'goodwill' | 'other';
+
+// Amounts are negative, as printed on the credit note
+export interface CreditNoteItem {
+    id: number;
+    credit_note_id: number;
+    order_item_id: number | null;
+    product_id: number | null;
+    description: string;
+    quantity: number;
+    unit_price: number;
+}
+
+export interface CreditNote {
+    id: number;
+    preorder_id: number;
+    credit_note_number: string;
+    invoice_number: string;
+    reason_code: CreditReason;
+    reason: string | null;
+    subtotal: number;
+    tax_amount: number;
+    total_amount: number;
+    issued_at?: string;
+    items: CreditNoteItem[];
+}
+
+// Positive amounts to take off; order_item_id lines default to the whole invoice line
+export interface CreditLineInput {
+    order_item_id: number | null;
+    description: string | null;
+    quantity: number | null;
+    unit_price: number | null;
+}
+
+export interface CreditNoteInput {
+    preorder_id: number;
+    reason_code: CreditReason;
+    reason: string | null; // required for 'other'
+    lines: CreditLineInput[];
+    refund_method: string | null;
+    refund_reference: string | null;
+}
+
+export interface IssuedCreditNote {
+    credit_note: CreditNote;
+    refund: Refund | null;
+}
+
+// 'expired' is an open quote past valid_until
+export type QuoteStatus = 'open' | 'accepted' | 'withdrawn' | 'expired';
+
+export interface QuoteItem {
+    id: number;
+    quote_id: number;
+    product_id: number;
+    product_name: string | null;
+    quantity: number;
+    unit_price: number; // locked, in the quote's currency
+}
+
+export interface Quote {
+    id: number;
+    customer_name: string;
+    customer_email: string;
+    currency_code: string;
+    base_currency: string;
+    exchange_rate: number; // units of currency_code per base_currency
+    rate_date: string | null;
+    notes: string | null;
+    event_id: number | null;
+    total_amount: number;
+    valid_until: string;
+    status: QuoteStatus;
+    preorder_id: number | null;
+    accepted_at: string | null;
+    created_at?: string;
+    updated_at?: string;
+    items: QuoteItem[];
+}
+
+export interface QuoteInput {
+    customer_name: string;
+    customer_email: string;
+    currency_code?: string;
+    notes?: string;
+    event_id?: number;
+    valid_days?: number; // defaults to 14
+    items: { product_id: number; quantity: number; unit_price?: number }[];
+}
+
+export interface StatementCredit {
+    row: number;
+    paid_at: string;
+    description: string;
+    amount: number;
+    reference: string;
+    duplicate_payment_id: number | null;
+    suggestions: PaymentProofMatch[];
+    // Record with record_payment once the user confirms it
+    proposal: PaymentInput | null;
+}
+
+export interface BankStatementReconciliation {
+    credits: StatementCredit[];
+    proposed: number;
+    debits_skipped: number;
+    unreadable_rows: number[];
+}
+
+// 'invoiced': offset_days after invoicing; 'pickup': offset_days before the event starts
+export type ReminderAnchor = 'invoiced' | 'pickup';
+
+export interface ReminderRule {
+    id: number;
+    name: string;
+    anchor: ReminderAnchor;
+    offset_days: number;
+    enabled: boolean;
+    created_at?: string;
+    updated_at?: string;
+}
+
+export interface ReminderRuleInput {
+    name: string;
+    anchor: ReminderAnchor;
+    offset_days: number;
+    enabled?: boolean;
+}
+
+export interface PendingReminder {
+    id: number;
+    preorder_id: number;
+    rule_id: number;
+    rule_name: string;
+    anchor: ReminderAnchor;
+    due_at: string;
+    queued_at?: string;
+    customer_name: string;
+    customer_email: string;
+    confirmation_code: string;
+    invoice_number?: string;
+    event_name?: string;
+    pickup_date?: string;
+    balance_due: number;
+    currency_code: string;
+    subject: string;
+    html_body: string;
+    // Set when the reminder goes out by SMS instead of email
+    sms_phone: string | null;
+    sms_body: string;
+}
+
+export type DocumentKind = 'payment_proof' | 'delivery_note' | 'other';
+
+export interface OrderDocument {
+    id: number;
+    preorder_id: number;
+    kind: DocumentKind;
+    file_name: string;
+    mime_type: string;
+    width: number;
+    height: number;
+    size_bytes: number;
+    sha256: string;
+    notes: string | null;
+    created_at: string | null;
+}
+
+export interface PaymentLink {
+    id: number;
+    preorder_id: number;
+    provider: 'stripe' | 'paypal' | 'midtrans';
+    external_id: string;
+    url: string | null;
+    method: string | null;
+    payment_code: string | null;
+    amount: number;
+    currency_code: string;
+    status: 'open' | 'paid' | 'cancelled';
+    payment_reference: string | null;
+    payment_id: number | null;
+    created_at: string | null;
+    expires_at: string | null;
+    paid_at: string | null;
+    checked_at: string | null;
+}
+
+export type LinkCheckOutcome = 'paid' | 'open' | 'cancelled' | 'error';
+
+export interface LinkCheck {
+    link: PaymentLink;
+    outcome: LinkCheckOutcome;
+    message: string | null;
+}
+
+export interface PaymentSync {
+    checks: LinkCheck[];
+    paid_orders: number[];
+}
+
+export interface PaypalCredentials {
+    client_id: string;
+    client_secret: string;
+    sandbox?: boolean;
+}
+
+export interface MidtransCredentials {
+    server_key: string;
+    sandbox?: boolean;
+}
+
+export type MidtransMethod =
+    | { type: 'bank_transfer'; bank: 'bca' | 'bni' | 'bri' | 'permata' | 'cimb' }
+    | { type: 'qris' };
+
+export type BarcodeFormat = 'ean13' | 'code128';
+
+export type LabelSize = '50x25' | '62x29' | '100x50' | '4x6';
+
+export interface BarcodeLabel {
+    barcode: string;
+    symbology: BarcodeFormat;
+    mime_type: string;
+    data_base64: string;
+    path: string | null;
+}
+
+// Published product details for a barcode not in the catalog
+export interface OnlineProduct {
+    barcode: string;
+    found: boolean;
+    source: 'openfoodfacts' | 'upcitemdb' | null;
+    name: string | null;
+    brand: string | null;
+    description: string | null;
+    image_url: string | null;
+    fetched_at: string | null;
+}
+
+export type WebhookProvider = 'stripe' | 'shopify' | 'generic';
+
+export interface WebhookReceiverSettings {
+    enabled: boolean;
+    port: number;
+    bind_all: boolean;
+}
+
+// Deliveries go to POST /webhooks/<slug>
+export interface WebhookSource {
+    id: number;
+    slug: string;
+    name: string;
+    provider: WebhookProvider;
+    signing_secret: string;
+    enabled: boolean;
+    created_at: string | null;
+    updated_at: string | null;
+}
+
+export interface WebhookSourceInput {
+    slug: string;
+    name: string;
+    provider: WebhookProvider;
+    signing_secret: string;
+    enabled?: boolean;
+}
+
+export interface WebhookEvent {
+    id: number;
+    source_id: number;
+    source_slug: string;
+    provider: WebhookProvider;
+    event_type: string;
+    external_id: string | null;
+    payload: string;
+    received_at: string | null;
+    handled_at: string | null;
+}
+
+// Payload of the 'webhook-received' event
+export interface WebhookReceived {
+    id: number;
+    source_slug: string;
+    provider: WebhookProvider;
+    event_type: string;
+    external_id: string | null;
+}
+
+export type OrderWebhookEvent = 'order.created' | 'order.paid' | 'order.fulfilled';
+
+export type WebhookDeliveryStatus = 'pending' | 'delivered' | 'failed';
+
+// Outgoing: gets a POST signed with X-POTracker-Signature (t=...,v1=...)
+export interface WebhookEndpoint {
+    id: number;
+    name: string;
+    url: string;
+    secret: string;
+    enabled: boolean;
+    events: OrderWebhookEvent[];
+    created_at: string | null;
+    updated_at: string | null;
+}
+
+export interface WebhookEndpointInput {
+    name: string;
+    url: string;
+    secret?: string | null;
+    events: OrderWebhookEvent[];
+    enabled?: boolean;
+}
+
+export interface WebhookDelivery {
+    id: number;
+    endpoint_id: number;
+    endpoint_name: string;
+    delivery_uid: string;
+    event: OrderWebhookEvent;
+    preorder_id: number | null;
+    payload: string;
+    status: WebhookDeliveryStatus;
+    attempts: number;
+    next_attempt_at: string | null;
+    last_response_status: number | null;
+    last_error: string | null;
+    created_at: string | null;
+    delivered_at: string | null;
+}
+
+export interface WebhookDeliveryAttempt {
+    id: number;
+    delivery_id: number;
+    attempted_at: string | null;
+    response_status: number | null;
+    error: string | null;
+    duration_ms: number;
+}
+
+// Payload of the 'webhook-delivery-failed' event
+export interface WebhookDeliveryFailed {
+    delivery_id: number;
+    endpoint_id: number;
+    event: OrderWebhookEvent;
+    error: string | null;
+}
+
+export interface TelegramSettings {
+    enabled: boolean;
+    bot_token: string | null;
+    chat_id: string | null;
+    notify_new_order: boolean;
+    notify_payment: boolean;
+}
+
+export interface WhatsappSettings {
+    enabled: boolean;
+    phone_number_id: string | null;
+    access_token: string | null;
+    template_language: string;
+    // Body variables: {{1}} name, {{2}} confirmation code, {{3}} total
+    confirmation_template: string | null;
+    // Body variables: {{1}} name, {{2}} confirmation code, {{3}} event, {{4}} pickup date
+    pickup_template: string | null;
+    pickup_days_before: number;
+    default_country_code: string;
+}
+
+export type WhatsappKind = 'confirmation' | 'pickup_reminder';
+
+export interface WhatsappMessage {
+    id: number;
+    preorder_id: number;
+    kind: WhatsappKind;
+    to_phone: string;
+    template: string;
+    variables: string;
+    status: 'pending' | 'sent' | 'failed';
+    attempts: number;
+    message_id: string | null;
+    last_error: string | null;
+    created_at: string | null;
+    sent_at: string | null;
+}
+
+export type ContactPreference = 'email' | 'sms';
+
+export type SmsProviderKind = 'twilio' | 'vonage';
+
+export interface SmsSettings {
+    enabled: boolean;
+    provider: SmsProviderKind;
+    // Twilio Account SID, or Vonage API key
+    account_id: string | null;
+    // Twilio auth token, or Vonage API secret
+    auth_token: string | null;
+    sender: string | null;
+    default_country_code: string;
+}
+
+export type SmsKind = 'confirmation_code' | 'payment_reminder';
+
+export interface SmsMessage {
+    id: number;
+    preorder_id: number;
+    kind: SmsKind;
+    provider: SmsProviderKind;
+    to_phone: string;
+    body: string;
+    status: 'pending' | 'sent' | 'failed';
+    attempts: number;
+    provider_message_id: string | null;
+    last_error: string | null;
+    created_at: string | null;
+    sent_at: string | null;
+}
+
+export type ChatChannel = 'slack' | 'discord';
+
+export interface ChatAlertSettings {
+    enabled: boolean;
+    // Incoming webhook URLs; a channel without one gets no alerts
+    slack_webhook_url: string | null;
+    discord_webhook_url: string | null;
+    notify_new_order: boolean;
+    notify_low_stock: boolean;
+    notify_email_failed: boolean;
+}
+
+export interface DesktopNotificationSettings {
+    enabled: boolean;
+    notify_form_response: boolean;
+    notify_email_failed: boolean;
+    notify_reminder_due: boolean;
+}
+
+export interface AutoBackupSettings {
+    enabled: boolean;
+    // Absolute path; null uses the profile's backups folder
+    folder: string | null;
+    keep_count: number;
+    interval_hours: number;
+    // Also upload an encrypted copy to Drive, locked with drive_passphrase
+    drive_enabled: boolean;
+    drive_passphrase: string | null;
+    // Outcome of the last run; ignored when saving
+    last_backup_at?: string | null;
+    last_error?: string | null;
+}
+
+export interface AutoBackupFile {
+    name: string;
+    path: string;
+    size_bytes: number;
+    created_at: string | null;
+}
+
+export interface SyncPeer {
+    device_id: string;
+    // Highest changelog sequence read from this device
+    last_seq: number;
+    last_seen_at: string | null;
+}
+
+export interface SyncStatus {
+    enabled: boolean;
+    device_id: string | null;
+    last_sync_at: string | null;
+    last_error: string | null;
+    // Changes made here and not pushed yet
+    outgoing: number;
+    // Other devices' changes waiting for a row they refer to
+    waiting: number;
+    open_conflicts: number;
+    peers: SyncPeer[];
+}
+
+export interface SyncReport {
+    pushed: number;
+    received: number;
+    applied: number;
+    conflicts: number;
+}
+
+export type SyncOpKind = 'insert' | 'update' | 'delete' | 'snapshot';
+
+export interface SyncConflict {
+    id: number;
+    device_id: string;
+    table_name: string;
+    row_gid: string;
+    kind: SyncOpKind;
+    // JSON object of the incoming fields
+    data: string;
+    error: string;
+    created_at: string | null;
+}
+
+export type HealthStatus = 'ok' | 'warning' | 'error';
+
+export interface HealthCheck {
+    // database_integrity, schema_version, database_wal, queues, google_token,
+    // disk_space, leftover_files or smtp
+    id: string;
+    status: HealthStatus;
+    message: string;
+    // What auto-repair fixed, if anything
+    repaired: string | null;
+}
+
+export interface HealthReport {
+    checked_at: string;
+    // Worst status of any check
+    status: HealthStatus;
+    checks: HealthCheck[];
+}
+
+export type TaskStatus = 'running' | 'completed' | 'failed' | 'cancelled';
+
+// A background task; sent with "task-progress" and "task-finished" events
+export interface TaskInfo<T = unknown> {
+    id: number;
+    // invoice_export, bulk_email or response_sync
+    kind: string;
+    label: string;
+    status: TaskStatus;
+    done: number;
+    total: number | null;
+    message: string | null;
+    started_at: string;
+    finished_at: string | null;
+    // What the command produces, once completed
+    result: T | null;
+    error: string | null;
+}
+
+export interface BulkEmail {
+    to_email: string;
+    to_name: string;
+    subject: string;
+    html_body: string;
+    po_id?: number | null;
+}
+
+export interface BulkEmailFailure {
+    to_email: string;
+    po_id: number | null;
+    error: string;
+}
+
+export interface BulkEmailResult {
+    sent: number;
+    failed: BulkEmailFailure[];
+}
+
+// A Google Forms response as the Forms API returns it
+export interface FormResponse {
+    responseId: string;
+    createTime: string;
+    answers?: Record<string, { questionId: string; textAnswers?: { answers: { value: string }[] } }>;
+}
+
+export interface ResponseSyncResult {
+    form_id: string;
+    fetched: number;
+    responses: FormResponse[];
+}
+
+// Result of the sync_all_watched_forms task. A form that couldn't be fetched
+// is listed in failed while the others still sync.
+export interface FormSyncFailure {
+    form_id: string;
+    error: AppError;
+}
+
+export interface WatchedFormsSyncResult {
+    forms: ResponseSyncResult[];
+    failed: FormSyncFailure[];
+    fetched: number;
+    new_responses: number;
+}
+
+// Sent through the Channel given to stream_form_responses, one page at a
+// time; the command itself resolves with the summary after the last batch.
+export interface ResponseBatch {
+    index: number;
+    responses: FormResponse[];
+    sent: number;
+}
+
+export interface ResponseStreamSummary {
+    form_id: string;
+    batches: number;
+    total: number;
+}
+
+export interface AutomationSettings {
+    // Seconds between passes of the recurring order scheduler
+    scheduler_interval_secs: number;
+    // ... of the payment reminder engine
+    reminder_interval_secs: number;
+    // ... of the webhook, Telegram, WhatsApp, SMS and chat alert senders
+    sender_interval_secs: number;
+    // ... of the desktop notifier
+    notifier_interval_secs: number;
+    quiet_hours_enabled: boolean;
+    // Local time, HH:MM; may wrap past midnight
+    quiet_start: string;
+    quiet_end: string;
+    // Automated messages per day; null for no limit
+    max_daily_sends: number | null;
+}
+
+export interface AutomationStatus {
+    in_quiet_hours: boolean;
+    sent_today: number;
+    sends_left: number | null;
+}
+
+export type SettingsSection = 'smtp' | 'oauth_client' | 'business_profile' | 'email_template' | 'network';
+
+// Payload of the "settings-changed" event
+export interface SettingsChanged {
+    section: SettingsSection;
+}
+
+// SMTP account as the backend returns it; the password is write-only and
+// saving without one keeps the stored password
+export interface SmtpConfig {
+    smtp_server: string;
+    smtp_port: number;
+    username: string;
+    password?: string | null;
+    from_email: string;
+    from_name: string | null;
+    has_password: boolean;
+}
+
+export interface OAuthClientConfig {
+    client_id: string;
+    client_secret?: string | null;
+    has_client_secret: boolean;
+}
+
+// Proxy (http, https, socks5 or socks5h URL) and extra root certificates for
+// HTTP APIs; SMTP trusts the CA bundle but doesn't use the proxy. The proxy
+// password is write-only like the SMTP password.
+export interface NetworkConfig {
+    proxy_url: string | null;
+    proxy_username: string | null;
+    proxy_password?: string | null;
+    has_proxy_password: boolean;
+    ca_bundle_path: string | null;
+    // Seconds; null for the defaults (10 to connect, 300 per request)
+    connect_timeout_secs: number | null;
+    request_timeout_secs: number | null;
+}
+
+// Printed on invoices when the invoice layout has no business name or details
+export interface BusinessProfile {
+    name: string;
+    email: string | null;
+    phone: string | null;
+    address: string | null;
+    tax_id: string | null;
+    website: string | null;
+}
+
+// Optional encryption of customer contact details and integration
+// credentials. While enabled but not unlocked, those can't be read or saved;
+// unlock_encryption takes the passphrase. Sent as "encryption-changed".
+export interface EncryptionStatus {
+    enabled: boolean;
+    unlocked: boolean;
+}
+
+// Result of export_customer_data: a folder holding customer-data.json and
+// copies of the documents attached to the customer's orders
+export interface CustomerDataExport {
+    path: string;
+    tables: Record<string, number>;
+    attachments: number;
+}
+
+// Result of erase_customer. Payment proofs on Google Drive aren't removed;
+// trash drive_file_ids there.
+export interface CustomerErasure {
+    customer_id: number;
+    orders: number;
+    archived_orders: number;
+    quotes: number;
+    recurring_orders: number;
+    documents_deleted: number;
+    drive_file_ids: string[];
+}
+
+// Session lock: after idle_timeout_mins without activity (reported with
+// touch_session) contact details and credentials can't be read or written and
+// no email is sent until unlock_session gets the encryption passphrase, or the
+// PIN while encryption is off. Changes are sent as "session-lock-changed"
+// with the new locked flag.
+export interface SessionLockSettings {
+    idle_timeout_mins: number | null;
+    has_pin: boolean;
+}
+
+export interface SessionStatus {
+    locked: boolean;
+    idle_timeout_mins: number | null;
+    has_pin: boolean;
+    locks_in_secs: number | null;
+}
+
+// Logging: log lines go to a daily file in the app data dir (7 days kept).
+// level is error, warn, info, debug or trace and applies straight away.
+export interface LogSettings {
+    level: string;
+}
+
+// Result of export_logs_zip
+export interface LogExport {
+    path: string;
+    files: number;
+}
+
+// One Google API call recorded while diagnostics are on (set_api_diagnostics).
+// endpoint is host and path only; status is null when no response came back.
+export interface ApiCall {
+    at: string;
+    method: string;
+    endpoint: string;
+    status: number | null;
+    latency_ms: number;
+    retries: number;
+    error: string | null;
+}
+
+export interface ApiDiagnostics {
+    enabled: boolean;
+    calls: ApiCall[];
+}
+
+// Google metadata cached by the backend: form schemas for 10 minutes, folder
+// IDs for an hour, the project scan for 2 minutes. clear_google_cache takes
+// one kind, or none to clear everything.
+export type GoogleCacheKind = 'form_details' | 'folder_id' | 'drive_listing';
+
+// Google API call budgets, counted per minute by the backend. Payload of the
+// "quota-pressure" event, sent when an API's level changes; "throttled" means
+// calls are queued, so syncing is slower than usual.
+export type GoogleApi = 'forms' | 'drive' | 'gmail';
+
+export type PressureLevel = 'normal' | 'high' | 'throttled';
+
+export interface QuotaPressure {
+    api: GoogleApi;
+    level: PressureLevel;
+    used: number;
+    limit: number;
+}
@@ -0,0 +1,20 @@
+import { invoke } from '@tauri-apps/api/core';
+import { ActiveProfile } from '../types';
+
+let cachedUrl: Promise<string> | null = null;
+
+/**
+ * Get the database URL of the active business profile.
+ * Switching profiles restarts the app, so the result is cached for the session.
+ */
+export function getDatabaseUrl(): Promise<string> {
+    if (!cachedUrl) {
+        cachedUrl = invoke<ActiveProfile>('get_active_profile')
+            .then(active => active.database_url)
+            .catch(err => {
+                console.error('Failed to load active profile, using default database:', err);
+                return 'sqlite:potracker.db';
+            });
+    }
+    return cachedUrl;
+}
@@ -0,0 +1,15 @@
+import { AppError } from '../types';
+
+export function isAppError(error: unknown): error is AppError {
+    return typeof error === 'object' && error !== null && 'kind' in error && 'message' in error;
+}
+
+/**
+ * Text to show for an error thrown by invoke() or anything else.
+ * Backend commands reject with an AppError object rather than a string.
+ */
+export function errorMessage(error: unknown): string {
+    if (isAppError(error)) return error.message;
+    if (error instanceof Error) return error.message;
+    return String(error);
+}